@@ -0,0 +1,248 @@
+//! Compares the operations of [`Vector3`] with the same arithmetic written
+//! out by hand on bare arrays, which is what they should compile down to,
+//! and with the loops over their elements they were first written as.
+//!
+//! Run with `cargo bench --bench vector`.
+
+use std::ops::{Add, Mul, MulAssign, Sub};
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use impulse::{vec::Vector3, Real};
+
+const COUNT: usize = 1024;
+
+type Array = [Real; 3];
+
+/// A vector whose operations loop over their elements, as those of
+/// [`Vector3`] did before they were built from elementwise maps.
+#[derive(Clone, Copy)]
+struct Looped {
+	elements: Array,
+}
+
+impl Looped {
+	fn dot(&self, rhs: &Self) -> Real {
+		self.elements
+			.iter()
+			.zip(rhs.elements.iter())
+			.fold(0.0, |acc, (a, b)| (*a).mul_add(*b, acc))
+	}
+
+	fn cross(&self, rhs: &Self) -> Self {
+		let ([x, y, z], [rhs_x, rhs_y, rhs_z]) = (self.elements, rhs.elements);
+		Self {
+			elements: [
+				y.mul_add(rhs_z, -z * rhs_y),
+				z.mul_add(rhs_x, -x * rhs_z),
+				x.mul_add(rhs_y, -y * rhs_x),
+			],
+		}
+	}
+}
+
+impl Add for Looped {
+	type Output = Self;
+
+	fn add(self, rhs: Self) -> Self {
+		let mut elements = self.elements;
+		elements
+			.iter_mut()
+			.zip(rhs.elements.iter())
+			.for_each(|(a, b)| *a += *b);
+		Self { elements }
+	}
+}
+
+impl Sub for Looped {
+	type Output = Self;
+
+	fn sub(self, rhs: Self) -> Self {
+		let mut elements = self.elements;
+		elements
+			.iter_mut()
+			.zip(rhs.elements.iter())
+			.for_each(|(a, b)| *a -= *b);
+		Self { elements }
+	}
+}
+
+impl Mul for Looped {
+	type Output = Self;
+
+	fn mul(self, rhs: Self) -> Self {
+		let mut elements = self.elements;
+		elements
+			.iter_mut()
+			.zip(rhs.elements.iter())
+			.for_each(|(a, b)| *a *= *b);
+		Self { elements }
+	}
+}
+
+impl MulAssign<Real> for Looped {
+	fn mul_assign(&mut self, rhs: Real) {
+		self.elements.iter_mut().for_each(|a| *a *= rhs);
+	}
+}
+
+fn vectors() -> Vec<Vector3> {
+	(0..COUNT)
+		.map(|index| {
+			let index = index as Real;
+			Vector3::new(index.sin(), index.cos(), index * 0.01)
+		})
+		.collect()
+}
+
+fn arrays(vectors: &[Vector3]) -> Vec<Array> {
+	vectors.iter().map(|vector| [vector.x(), vector.y(), vector.z()]).collect()
+}
+
+fn looped(vectors: &[Vector3]) -> Vec<Looped> {
+	arrays(vectors).into_iter().map(|elements| Looped { elements }).collect()
+}
+
+fn add(criterion: &mut Criterion) {
+	let (first, second) = (vectors(), vectors().into_iter().rev().collect::<Vec<_>>());
+	let (first_arrays, second_arrays) = (arrays(&first), arrays(&second));
+	let mut group = criterion.benchmark_group("add");
+	group.bench_function("vector", |bencher| {
+		bencher.iter(|| {
+			first
+				.iter()
+				.zip(&second)
+				.fold(Vector3::zero(), |sum, (a, b)| sum + (*a + *b) - *b * *a)
+		});
+	});
+	let (first_looped, second_looped) = (looped(&first), looped(&second));
+	group.bench_function("looped", |bencher| {
+		bencher.iter(|| {
+			first_looped
+				.iter()
+				.zip(&second_looped)
+				.fold(Looped { elements: [0.0; 3] }, |sum, (a, b)| sum + (*a + *b) - *b * *a)
+		});
+	});
+	group.bench_function("array", |bencher| {
+		bencher.iter(|| {
+			first_arrays.iter().zip(&second_arrays).fold([0.0; 3], |sum: Array, (a, b)| {
+				[
+					sum[0] + (a[0] + b[0]) - b[0] * a[0],
+					sum[1] + (a[1] + b[1]) - b[1] * a[1],
+					sum[2] + (a[2] + b[2]) - b[2] * a[2],
+				]
+			})
+		});
+	});
+	group.finish();
+}
+
+fn dot(criterion: &mut Criterion) {
+	let (first, second) = (vectors(), vectors().into_iter().rev().collect::<Vec<_>>());
+	let (first_arrays, second_arrays) = (arrays(&first), arrays(&second));
+	let mut group = criterion.benchmark_group("dot");
+	group.bench_function("vector", |bencher| {
+		bencher.iter(|| first.iter().zip(&second).map(|(a, b)| a.dot(b)).sum::<Real>());
+	});
+	let (first_looped, second_looped) = (looped(&first), looped(&second));
+	group.bench_function("looped", |bencher| {
+		bencher.iter(|| {
+			first_looped
+				.iter()
+				.zip(&second_looped)
+				.map(|(a, b)| a.dot(b))
+				.sum::<Real>()
+		});
+	});
+	group.bench_function("array", |bencher| {
+		bencher.iter(|| {
+			first_arrays
+				.iter()
+				.zip(&second_arrays)
+				.map(|(a, b)| a[2].mul_add(b[2], a[1].mul_add(b[1], a[0] * b[0])))
+				.sum::<Real>()
+		});
+	});
+	group.finish();
+}
+
+fn cross(criterion: &mut Criterion) {
+	let (first, second) = (vectors(), vectors().into_iter().rev().collect::<Vec<_>>());
+	let (first_arrays, second_arrays) = (arrays(&first), arrays(&second));
+	let mut group = criterion.benchmark_group("cross");
+	group.bench_function("vector", |bencher| {
+		bencher.iter(|| {
+			first
+				.iter()
+				.zip(&second)
+				.fold(Vector3::zero(), |sum, (a, b)| sum + a.cross(b))
+		});
+	});
+	let (first_looped, second_looped) = (looped(&first), looped(&second));
+	group.bench_function("looped", |bencher| {
+		bencher.iter(|| {
+			first_looped
+				.iter()
+				.zip(&second_looped)
+				.fold(Looped { elements: [0.0; 3] }, |sum, (a, b)| sum + a.cross(b))
+		});
+	});
+	group.bench_function("array", |bencher| {
+		bencher.iter(|| {
+			first_arrays.iter().zip(&second_arrays).fold([0.0; 3], |sum: Array, (a, b)| {
+				[
+					sum[0] + a[1].mul_add(b[2], -a[2] * b[1]),
+					sum[1] + a[2].mul_add(b[0], -a[0] * b[2]),
+					sum[2] + a[0].mul_add(b[1], -a[1] * b[0]),
+				]
+			})
+		});
+	});
+	group.finish();
+}
+
+fn scale(criterion: &mut Criterion) {
+	// Each form is copied outside the timing, so that only the scaling is timed
+	let vectors = vectors();
+	let mut group = criterion.benchmark_group("scale");
+	group.bench_function("vector", |bencher| {
+		bencher.iter_batched_ref(
+			|| vectors.clone(),
+			|vectors| {
+				for vector in vectors.iter_mut() {
+					*vector *= black_box(0.5);
+				}
+			},
+			BatchSize::SmallInput,
+		);
+	});
+	let looped = looped(&vectors);
+	group.bench_function("looped", |bencher| {
+		bencher.iter_batched_ref(
+			|| looped.clone(),
+			|looped| {
+				for vector in looped.iter_mut() {
+					*vector *= black_box(0.5);
+				}
+			},
+			BatchSize::SmallInput,
+		);
+	});
+	let arrays = arrays(&vectors);
+	group.bench_function("array", |bencher| {
+		bencher.iter_batched_ref(
+			|| arrays.clone(),
+			|arrays| {
+				for array in arrays.iter_mut() {
+					let scale = black_box(0.5);
+					*array = [array[0] * scale, array[1] * scale, array[2] * scale];
+				}
+			},
+			BatchSize::SmallInput,
+		);
+	});
+	group.finish();
+}
+
+criterion_group!(benches, add, dot, cross, scale);
+criterion_main!(benches);
@@ -1,4 +1,4 @@
-use impulse::{Particle, Real};
+use impulse::{Particle, Real, SimulationClock};
 use macroquad::prelude::*;
 
 const PARTICLE_TIMEOUT_SECS: f32 = 5.0;
@@ -17,6 +17,7 @@ enum Shot {
 
 struct Round {
 	particle: Particle,
+	previous_position: impulse::Vector3,
 	start_time: Option<f32>,
 	alive: bool,
 	trajectory: Vec<Vec3>,
@@ -30,6 +31,7 @@ struct GameState {
 	camera: Camera3D,
 	yaw: f32,
 	pitch: f32,
+	clock: SimulationClock,
 }
 
 impl GameState {
@@ -37,6 +39,7 @@ impl GameState {
 		let rounds = (0..AMMO_COUNT)
 			.map(|_| Round {
 				particle: shot_as_particle(Shot::Pistol, impulse::Vector3::zero()),
+				previous_position: impulse::Vector3::zero(),
 				start_time: None,
 				alive: false,
 				trajectory: Vec::new(),
@@ -56,6 +59,7 @@ impl GameState {
 			},
 			yaw: 0.0,
 			pitch: 0.0,
+			clock: SimulationClock::default(),
 		}
 	}
 }
@@ -138,18 +142,23 @@ fn handle_input(game_state: &mut GameState) {
 }
 
 fn update_physics(game_state: &mut GameState) {
-	let dt = get_frame_time();
+	let steps = game_state.clock.advance(get_frame_time());
+	let dt = game_state.clock.fixed_step;
 
 	for round in &mut game_state.rounds {
 		if round.alive {
-			round.particle.integrate(dt);
-			round.trajectory.push(to_vec3(&round.particle.position));
+			for _ in 0..steps {
+				round.previous_position = round.particle.position;
+				round.particle.integrate(dt);
+				round.trajectory.push(to_vec3(&round.particle.position));
+			}
 		} else if game_state.should_fire {
 			round.start_time = Some(get_time() as f32);
 			round.alive = true;
 			round.trajectory.clear();
 			let spawn_pos = impulse::Vector3::new(0.0, 1.5, 0.0);
 			round.particle = shot_as_particle(game_state.next_shot, spawn_pos);
+			round.previous_position = spawn_pos;
 			round.trajectory.push(to_vec3(&spawn_pos));
 			round.particle.velocity =
 				impulse::Vector3::new(0.0, round.particle.velocity.y(), round.particle.velocity.z());
@@ -182,9 +191,12 @@ fn render_scene(game_state: &GameState) {
 	// Draw launch point with the color of the currently selected shot
 	draw_sphere(Vec3::new(0.0, 1.5, 0.0), 0.5, None, shot_color(game_state.next_shot));
 
+	// Rounds are drawn between their last two steps, by the time left over from them
+	let blend = game_state.clock.blend();
 	for round in &game_state.rounds {
 		if round.alive {
-			draw_sphere(to_vec3(&round.particle.position), 0.5, None, round.color);
+			let position = round.previous_position + (round.particle.position - round.previous_position) * blend;
+			draw_sphere(to_vec3(&position), 0.5, None, round.color);
 
 			if round.trajectory.len() > 1 {
 				for i in 0..(round.trajectory.len() - 1) {
@@ -210,7 +222,7 @@ fn shot_as_particle(shot: Shot, position: impulse::Vector3) -> Particle {
 			acceleration: impulse::Vector3::new(0.0, -1.0, 0.0),
 			damping: 0.99,
 			position,
-			force_accumulator: impulse::Vector3::zero(),
+			..Default::default()
 		},
 		Shot::Artillery => Particle {
 			inverse_mass: (200.0 as Real).recip(),
@@ -218,7 +230,7 @@ fn shot_as_particle(shot: Shot, position: impulse::Vector3) -> Particle {
 			acceleration: impulse::Vector3::new(0.0, -20.0, 0.0),
 			damping: 0.99,
 			position,
-			force_accumulator: impulse::Vector3::zero(),
+			..Default::default()
 		},
 		Shot::Fireball => Particle {
 			inverse_mass: (1.0 as Real).recip(),
@@ -226,7 +238,7 @@ fn shot_as_particle(shot: Shot, position: impulse::Vector3) -> Particle {
 			acceleration: impulse::Vector3::new(0.0, 0.6, 0.0),
 			damping: 0.9,
 			position,
-			force_accumulator: impulse::Vector3::zero(),
+			..Default::default()
 		},
 		Shot::Laser => Particle {
 			inverse_mass: (0.1 as Real).recip(),
@@ -234,7 +246,7 @@ fn shot_as_particle(shot: Shot, position: impulse::Vector3) -> Particle {
 			acceleration: impulse::Vector3::new(0.0, 0.0, 0.0),
 			damping: 0.99,
 			position,
-			force_accumulator: impulse::Vector3::zero(),
+			..Default::default()
 		},
 		Shot::Grenade => Particle {
 			inverse_mass: (0.9 as Real).recip(),
@@ -242,7 +254,7 @@ fn shot_as_particle(shot: Shot, position: impulse::Vector3) -> Particle {
 			acceleration: impulse::Vector3::new(0.0, -10.0, 0.0),
 			damping: 0.99,
 			position,
-			force_accumulator: impulse::Vector3::zero(),
+			..Default::default()
 		},
 	}
 }
@@ -1,4 +1,4 @@
-use impulse::{Particle, Vector3};
+use impulse::{Particle, SimulationClock, Vector3};
 use macroquad::prelude::*;
 use rand::gen_range;
 
@@ -45,7 +45,7 @@ impl Firework {
 			acceleration: Vector3::new(0.0, -9.8, 0.0),
 			damping: 0.99,
 			inverse_mass: 1.0,
-			force_accumulator: Vector3::zero(),
+			..Default::default()
 		};
 
 		let firework_type = match gen_range(0, 7) {
@@ -112,7 +112,7 @@ impl Firework {
 					acceleration: Vector3::new(0.0, -2.0, 0.0),
 					damping: 0.99,
 					inverse_mass: 1.0,
-					force_accumulator: Vector3::zero(),
+					..Default::default()
 				}
 			})
 			.collect();
@@ -164,9 +164,9 @@ impl Firework {
 		if !self.exploded {
 			draw_sphere(
 				vec3(
-					self.rocket.position.x() as f32,
-					self.rocket.position.y() as f32,
-					self.rocket.position.z() as f32,
+					self.rocket.position.x(),
+					self.rocket.position.y(),
+					self.rocket.position.z(),
 				),
 				0.5,
 				None,
@@ -185,11 +185,7 @@ impl Firework {
 				};
 
 				draw_sphere(
-					vec3(
-						particle.position.x() as f32,
-						particle.position.y() as f32,
-						particle.position.z() as f32,
-					),
+					vec3(particle.position.x(), particle.position.y(), particle.position.z()),
 					0.2,
 					None,
 					particle_color,
@@ -203,6 +199,7 @@ struct FireworksDisplay {
 	fireworks: Vec<Firework>,
 	last_launch_time: f32,
 	camera: Camera3D,
+	clock: SimulationClock,
 }
 
 impl FireworksDisplay {
@@ -216,6 +213,7 @@ impl FireworksDisplay {
 				target: vec3(0.0, 20.0, 0.0),
 				..Default::default()
 			},
+			clock: SimulationClock::default(),
 		}
 	}
 
@@ -252,7 +250,9 @@ async fn main() {
 	let mut display = FireworksDisplay::new();
 
 	loop {
-		display.update(get_frame_time());
+		for _ in 0..display.clock.advance(get_frame_time()) {
+			display.update(display.clock.fixed_step);
+		}
 		display.draw();
 		next_frame().await
 	}
@@ -0,0 +1,239 @@
+use crate::{
+	body::RigidBody, body_set::BodySet, generators::ForceGenerator, matrix::Matrix3, quaternion::Quaternion,
+	vec::Vector3, Real,
+};
+
+/// A force generator that applies an aerodynamic force.
+///
+/// The aerodynamic tensor converts the velocity of the surface through the air,
+/// in body coordinates, into the force it generates, also in body coordinates.
+/// Negative diagonal terms give drag along each axis, and off-diagonal terms
+/// turn motion along one axis into lift along another.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Aero {
+	/// Holds the aerodynamic tensor for the surface in body space.
+	pub tensor: Matrix3,
+
+	/// Holds the relative position of the aerodynamic surface in body coordinates.
+	pub position: Vector3,
+
+	/// Holds the velocity of the wind in world coordinates.
+	pub wind_speed: Vector3,
+}
+
+impl Aero {
+	#[must_use]
+	pub const fn new(tensor: Matrix3, position: Vector3, wind_speed: Vector3) -> Self {
+		Self {
+			tensor,
+			position,
+			wind_speed,
+		}
+	}
+
+	/// Returns the world-space force the given tensor generates on the body.
+	#[must_use]
+	pub fn force_from_tensor(&self, body: &RigidBody, tensor: &Matrix3) -> Vector3 {
+		// The velocity of the body through the air, in body coordinates
+		let velocity = body.get_direction_in_local(body.velocity - self.wind_speed);
+		body.get_direction_in_world(*tensor * velocity)
+	}
+
+	/// Applies the force the given tensor generates at the aerodynamic surface.
+	pub fn apply_tensor(&self, body: &mut RigidBody, tensor: &Matrix3) {
+		let force = self.force_from_tensor(body, tensor);
+		body.add_force_at_body_point(force, self.position);
+	}
+}
+
+impl ForceGenerator for Aero {
+	fn update_force(&mut self, bodies: &mut BodySet, index: usize, _duration: Real) {
+		bodies.update(index, |body| self.apply_tensor(body, &self.tensor));
+	}
+}
+
+/// An aerodynamic surface whose tensor is blended by a control setting,
+/// such as an aileron, elevator, or rudder.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AeroControl {
+	/// Holds the surface, with the tensor used when the control is centered.
+	pub aero: Aero,
+
+	/// Holds the tensor used when the control is at its minimum.
+	pub min_tensor: Matrix3,
+
+	/// Holds the tensor used when the control is at its maximum.
+	pub max_tensor: Matrix3,
+
+	/// Holds the current position of the control, from -1 at the
+	/// minimum through 0 at the base tensor to 1 at the maximum.
+	pub control_setting: Real,
+}
+
+impl AeroControl {
+	#[must_use]
+	pub const fn new(aero: Aero, min_tensor: Matrix3, max_tensor: Matrix3) -> Self {
+		Self {
+			aero,
+			min_tensor,
+			max_tensor,
+			control_setting: 0.0,
+		}
+	}
+
+	/// Sets the control position, clamped to the range -1 to 1.
+	pub const fn set_control(&mut self, value: Real) {
+		self.control_setting = value.clamp(-1.0, 1.0);
+	}
+
+	/// Returns the tensor for the current control setting.
+	#[must_use]
+	pub fn tensor(&self) -> Matrix3 {
+		let setting = self.control_setting.clamp(-1.0, 1.0);
+		if setting < 0.0 {
+			self.min_tensor.linear_interpolate(&self.aero.tensor, setting + 1.0)
+		} else {
+			self.aero.tensor.linear_interpolate(&self.max_tensor, setting)
+		}
+	}
+}
+
+impl ForceGenerator for AeroControl {
+	fn update_force(&mut self, bodies: &mut BodySet, index: usize, _duration: Real) {
+		let tensor = self.tensor();
+		bodies.update(index, |body| self.aero.apply_tensor(body, &tensor));
+	}
+}
+
+/// An aerodynamic surface that can be turned relative to the body,
+/// such as a tilting rotor or a swivelling sail.
+///
+/// The tensor of the inner [`Aero`] is given in the surface's own coordinates,
+/// and is rotated by the surface orientation before being applied to the body.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AngledAero {
+	/// Holds the surface, with its tensor in surface coordinates.
+	pub aero: Aero,
+
+	/// Holds the orientation of the surface relative to the body.
+	pub orientation: Quaternion,
+}
+
+impl AngledAero {
+	#[must_use]
+	pub const fn new(aero: Aero) -> Self {
+		Self {
+			aero,
+			orientation: Quaternion::identity(),
+		}
+	}
+
+	/// Sets the orientation of the surface relative to the body.
+	pub fn set_orientation(&mut self, orientation: Quaternion) {
+		self.orientation = orientation.normalize();
+	}
+
+	/// Returns the surface's tensor rotated into body coordinates.
+	#[must_use]
+	pub fn tensor(&self) -> Matrix3 {
+		let rotation = Matrix3::from_quaternion(&self.orientation);
+		rotation * self.aero.tensor * rotation.transpose()
+	}
+}
+
+impl ForceGenerator for AngledAero {
+	fn update_force(&mut self, bodies: &mut BodySet, index: usize, _duration: Real) {
+		let tensor = self.tensor();
+		bodies.update(index, |body| self.aero.apply_tensor(body, &tensor));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn glider(orientation: Quaternion) -> RigidBody {
+		let mut body = RigidBody {
+			velocity: Vector3::new(10.0, 0.0, 0.0),
+			orientation,
+			inverse_mass: 1.0,
+			..Default::default()
+		};
+		body.calculate_derived_data();
+		body
+	}
+
+	fn wing_tensor() -> Matrix3 {
+		// Drag against forward motion, and lift from forward motion
+		Matrix3::new([-0.1, 0.0, 0.0, 1.0, -0.5, 0.0, 0.0, 0.0, -0.1])
+	}
+
+	#[test]
+	pub fn wing_generates_lift_and_drag() {
+		let mut bodies: BodySet = std::iter::once(glider(Quaternion::identity())).collect();
+		let mut wing = Aero::new(wing_tensor(), Vector3::zero(), Vector3::zero());
+		wing.update_force(&mut bodies, 0, 1.0);
+		assert_eq!(bodies.forces()[0], Vector3::new(-1.0, 10.0, 0.0));
+		assert_eq!(bodies.torques()[0], Vector3::zero());
+	}
+
+	#[test]
+	pub fn force_follows_body_orientation() {
+		// Rolled onto its side, the wing's lift points sideways
+		let rolled = Quaternion::from_axis_angle(Vector3::x_axis(), std::f32::consts::FRAC_PI_2);
+		let body = glider(rolled);
+		let wing = Aero::new(wing_tensor(), Vector3::zero(), Vector3::zero());
+		let force = wing.force_from_tensor(&body, &wing.tensor);
+		assert!((force - Vector3::new(-1.0, 0.0, 10.0)).magnitude() < 1e-4);
+	}
+
+	#[test]
+	pub fn tail_surface_turns_body() {
+		let mut bodies: BodySet = std::iter::once(glider(Quaternion::identity())).collect();
+		let mut tail = Aero::new(wing_tensor(), Vector3::new(-2.0, 0.0, 0.0), Vector3::zero());
+		tail.update_force(&mut bodies, 0, 1.0);
+		assert_eq!(bodies.torques()[0], Vector3::new(0.0, 0.0, -20.0));
+	}
+
+	#[test]
+	pub fn headwind_adds_to_airspeed() {
+		let body = glider(Quaternion::identity());
+		let wing = Aero::new(wing_tensor(), Vector3::zero(), Vector3::new(-10.0, 0.0, 0.0));
+		assert_eq!(
+			wing.force_from_tensor(&body, &wing.tensor),
+			Vector3::new(-2.0, 20.0, 0.0)
+		);
+	}
+
+	#[test]
+	pub fn control_blends_tensors() {
+		let base = wing_tensor();
+		let min = base * 0.0;
+		let max = base * 2.0;
+		let mut aileron = AeroControl::new(Aero::new(base, Vector3::zero(), Vector3::zero()), min, max);
+		assert_eq!(aileron.tensor(), base);
+		aileron.set_control(-0.5);
+		assert_eq!(aileron.tensor(), base * 0.5);
+		aileron.set_control(5.0);
+		assert_eq!(aileron.tensor(), max);
+
+		let mut bodies: BodySet = std::iter::once(glider(Quaternion::identity())).collect();
+		aileron.update_force(&mut bodies, 0, 1.0);
+		assert_eq!(bodies.forces()[0], Vector3::new(-2.0, 20.0, 0.0));
+	}
+
+	#[test]
+	pub fn angled_surface_turns_its_force() {
+		let mut rotor = AngledAero::new(Aero::new(wing_tensor(), Vector3::zero(), Vector3::zero()));
+		let body = glider(Quaternion::identity());
+		assert_eq!(rotor.tensor(), wing_tensor());
+
+		// Tilted a quarter turn about the direction of travel, lift points sideways
+		rotor.set_orientation(Quaternion::from_axis_angle(
+			Vector3::x_axis(),
+			std::f32::consts::FRAC_PI_2,
+		));
+		let force = rotor.aero.force_from_tensor(&body, &rotor.tensor());
+		assert!((force - Vector3::new(-1.0, 0.0, 10.0)).magnitude() < 1e-4);
+	}
+}
@@ -0,0 +1,254 @@
+use std::{f32::consts::PI, ops::Range};
+
+use crate::{
+	cloth::SpringStiffness, forces::ParticleSpringLink, links::particle_distance, particle::Particle,
+	portable::sin_cos, real_from_count, vec::Vector3, Real,
+};
+
+/// Builds a soft body: particles spread evenly over a sphere, joined to their
+/// nearest neighbours by springs and inflated by an internal pressure force
+/// that resists changes to the enclosed volume.
+#[derive(Debug, Clone, Copy)]
+pub struct BlobBuilder {
+	center: Vector3,
+	radius: Real,
+	count: usize,
+	neighbours: usize,
+	total_mass: Real,
+	damping: Real,
+	stiffness: SpringStiffness,
+	pressure: Real,
+}
+
+impl BlobBuilder {
+	#[must_use]
+	pub const fn new(center: Vector3, radius: Real) -> Self {
+		Self {
+			center,
+			radius,
+			count: 32,
+			neighbours: 4,
+			total_mass: 1.0,
+			damping: 0.99,
+			stiffness: SpringStiffness::new(30.0, 0.5),
+			pressure: 10.0,
+		}
+	}
+
+	/// Sets the number of particles on the surface of the blob. A blob has at least four.
+	#[must_use]
+	pub fn particles(mut self, count: usize) -> Self {
+		self.count = count.max(4);
+		self
+	}
+
+	/// Sets how many of its nearest neighbours each particle is joined to.
+	#[must_use]
+	pub fn neighbours(mut self, neighbours: usize) -> Self {
+		self.neighbours = neighbours.max(1);
+		self
+	}
+
+	/// Sets the mass of the whole blob, shared evenly between its particles.
+	#[must_use]
+	pub const fn total_mass(mut self, total_mass: Real) -> Self {
+		self.total_mass = total_mass;
+		self
+	}
+
+	/// Sets the damping applied to every particle in the blob.
+	#[must_use]
+	pub const fn damping(mut self, damping: Real) -> Self {
+		self.damping = damping;
+		self
+	}
+
+	/// Sets the stiffness of the springs joining the surface particles.
+	#[must_use]
+	pub const fn stiffness(mut self, stiffness: SpringStiffness) -> Self {
+		self.stiffness = stiffness;
+		self
+	}
+
+	/// Sets the pressure constant. The higher it is, the harder
+	/// the blob resists being squashed or stretched.
+	#[must_use]
+	pub const fn pressure(mut self, pressure: Real) -> Self {
+		self.pressure = pressure;
+		self
+	}
+
+	/// Returns the position of the particle at the given index, spreading
+	/// the particles evenly over the sphere along a Fibonacci spiral.
+	#[must_use]
+	pub fn position(&self, index: usize) -> Vector3 {
+		let golden_angle = PI * (3.0 - Real::sqrt(5.0));
+		let y = 1.0 - 2.0 * (real_from_count(index) + 0.5) / real_from_count(self.count);
+		let ring_radius = y.mul_add(-y, 1.0).max(0.0).sqrt();
+		let (sin, cos) = sin_cos(golden_angle * real_from_count(index));
+		self.center + Vector3::new(cos * ring_radius, y, sin * ring_radius) * self.radius
+	}
+
+	/// Appends the blob's particles to the given list and returns
+	/// the blob describing the springs and pressure between them.
+	pub fn build(self, particles: &mut Vec<Particle>) -> Blob {
+		let first = particles.len();
+		let inverse_mass = if self.total_mass > 0.0 {
+			real_from_count(self.count) / self.total_mass
+		} else {
+			0.0
+		};
+		particles.extend((0..self.count).map(|index| Particle {
+			position: self.position(index),
+			damping: self.damping,
+			inverse_mass,
+			..Default::default()
+		}));
+
+		let mut springs: Vec<ParticleSpringLink> = Vec::new();
+		for index in first..first + self.count {
+			let mut others = (first..first + self.count)
+				.filter(|other| *other != index)
+				.collect::<Vec<_>>();
+			others.sort_by(|a, b| {
+				particle_distance(particles, [index, *a]).total_cmp(&particle_distance(particles, [index, *b]))
+			});
+			for other in others.into_iter().take(self.neighbours) {
+				let pair = [index.min(other), index.max(other)];
+				if springs.iter().any(|spring| spring.particles == pair) {
+					continue;
+				}
+				springs.push(ParticleSpringLink {
+					particles: pair,
+					spring_constant: self.stiffness.spring_constant,
+					damping: self.stiffness.damping,
+					rest_length: particle_distance(particles, pair),
+				});
+			}
+		}
+
+		let mut blob = Blob {
+			particles: first..first + self.count,
+			springs,
+			pressure: self.pressure,
+			rest_volume: 0.0,
+		};
+		blob.rest_volume = blob.volume(particles);
+		blob
+	}
+}
+
+/// A pressurised soft body created by a [`BlobBuilder`].
+#[derive(Debug, Default, Clone)]
+pub struct Blob {
+	/// Holds the indices of the blob's surface particles.
+	pub particles: Range<usize>,
+
+	/// Holds the springs joining neighbouring surface particles.
+	pub springs: Vec<ParticleSpringLink>,
+
+	/// Holds the pressure constant.
+	pub pressure: Real,
+
+	/// Holds the volume the blob encloses when at rest.
+	pub rest_volume: Real,
+}
+
+impl Blob {
+	/// Returns the average position of the blob's particles.
+	#[must_use]
+	pub fn centroid(&self, particles: &[Particle]) -> Vector3 {
+		let sum = particles[self.particles.clone()]
+			.iter()
+			.fold(Vector3::zero(), |acc, particle| acc + particle.position);
+		sum * real_from_count(self.particles.len()).recip()
+	}
+
+	/// Returns the mean distance of the blob's particles from its centroid.
+	#[must_use]
+	pub fn radius(&self, particles: &[Particle]) -> Real {
+		let centroid = self.centroid(particles);
+		let total = particles[self.particles.clone()]
+			.iter()
+			.fold(0.0, |acc, particle| acc + (particle.position - centroid).magnitude());
+		total / real_from_count(self.particles.len())
+	}
+
+	/// Estimates the enclosed volume by treating the blob as a sphere of its mean radius.
+	#[must_use]
+	pub fn volume(&self, particles: &[Particle]) -> Real {
+		4.0 / 3.0 * PI * self.radius(particles).powi(3)
+	}
+
+	/// Adds the spring forces and the internal pressure force to the blob's particles.
+	///
+	/// The pressure pushes each particle away from the centroid when the blob
+	/// is compressed below its rest volume and pulls it back in when expanded,
+	/// scaled by the share of the surface area each particle represents.
+	pub fn apply_forces(&self, particles: &mut [Particle]) {
+		for spring in &self.springs {
+			spring.apply_force(particles);
+		}
+
+		let volume = self.volume(particles);
+		if volume <= 0.0 {
+			return;
+		}
+		let centroid = self.centroid(particles);
+		let radius = self.radius(particles);
+		let area_per_particle = 4.0 * PI * radius * radius / real_from_count(self.particles.len());
+		let pressure = self.pressure * (self.rest_volume / volume - 1.0);
+		for particle in &mut particles[self.particles.clone()] {
+			let outward = (particle.position - centroid).normalize();
+			particle.add_force(outward * (pressure * area_per_particle));
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::assert_equal;
+
+	use super::*;
+
+	#[test]
+	pub fn particles_lie_on_sphere() {
+		let builder = BlobBuilder::new(Vector3::new(1.0, 2.0, 3.0), 2.0).particles(20);
+		for index in 0..20 {
+			let distance = (builder.position(index) - Vector3::new(1.0, 2.0, 3.0)).magnitude();
+			assert!((distance - 2.0).abs() < 1e-5);
+		}
+	}
+
+	#[test]
+	pub fn springs_are_unique() {
+		let mut particles = Vec::new();
+		let blob = BlobBuilder::new(Vector3::zero(), 1.0)
+			.particles(12)
+			.neighbours(3)
+			.build(&mut particles);
+		assert_eq!(particles.len(), 12);
+		for (index, spring) in blob.springs.iter().enumerate() {
+			assert!(blob.springs[index + 1..]
+				.iter()
+				.all(|other| other.particles != spring.particles));
+		}
+	}
+
+	#[test]
+	pub fn compressed_blob_pushes_outwards() {
+		let mut particles = Vec::new();
+		let blob = BlobBuilder::new(Vector3::zero(), 1.0)
+			.particles(8)
+			.stiffness(SpringStiffness::new(0.0, 0.0))
+			.build(&mut particles);
+		assert_equal(blob.volume(&particles), blob.rest_volume);
+		for particle in &mut particles {
+			particle.position *= 0.5;
+		}
+		blob.apply_forces(&mut particles);
+		assert!(particles
+			.iter()
+			.all(|particle| particle.force_accumulator.dot(&particle.position) > 0.0));
+	}
+}
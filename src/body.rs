@@ -0,0 +1,1114 @@
+use crate::{
+	compound::Compound,
+	material::Material,
+	matrix::{Matrix3, Matrix4},
+	portable::powf,
+	quaternion::Quaternion,
+	shape::Shape,
+	vec::Vector3,
+	Real,
+};
+
+/// The kinetic energy level below which a body is put to sleep, unless
+/// its world is given another.
+///
+/// Lower values let bodies settle further before sleeping, at the cost of
+/// more bodies staying awake.
+pub const DEFAULT_SLEEP_EPSILON: Real = 0.3;
+
+/// The motion a body is given when woken, which its next integration turns
+/// into twice the sleep epsilon it is integrated with, so that it does not
+/// immediately fall back asleep.
+pub(crate) const WAKING_MOTION: Real = Real::MAX;
+
+/// How a rigid body takes part in the simulation.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub enum BodyKind {
+	/// The body is moved by forces, torques, and collisions.
+	#[default]
+	Dynamic,
+
+	/// The body moves with whatever velocity it is given, but is never
+	/// pushed by forces or collisions, like a moving platform.
+	Kinematic,
+
+	/// The body never moves, but other bodies still collide with it.
+	Static,
+}
+
+/// How the gyroscopic torque of a spinning body is handled during integration.
+///
+/// A body spinning about anything but a principal axis of its inertia has
+/// its angular velocity change even with no torque applied. Ignoring this is
+/// cheap and fine for most objects, but long thin bodies spun fast will
+/// precess incorrectly or gain energy.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub enum GyroscopicTorque {
+	/// The gyroscopic term is ignored.
+	#[default]
+	Ignored,
+
+	/// The gyroscopic term is added to the torque. This is cheap and accurate for
+	/// small timesteps, but gains energy and becomes unstable for fast spins.
+	Explicit,
+
+	/// The gyroscopic term is solved implicitly with one Newton step. This is
+	/// stable at any spin rate, at the cost of a small loss of energy.
+	Implicit,
+}
+
+/// A rigid body is the basic simulation object in the rigid-body physics core.
+///
+/// It has the linear state of a [`Particle`](crate::Particle) plus an
+/// orientation and angular velocity, so it can spin as well as move.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct RigidBody {
+	/// Holds how the body takes part in the simulation.
+	pub kind: BodyKind,
+
+	/// Holds the linear position of the rigid body's center of mass in world space.
+	pub position: Vector3,
+
+	/// Holds the angular orientation of the rigid body in world space.
+	pub orientation: Quaternion,
+
+	/// Holds the linear velocity of the rigid body in world space.
+	pub velocity: Vector3,
+
+	/// Holds the angular velocity of the rigid body in world space,
+	/// as an axis scaled by the rate of rotation in radians per second.
+	pub angular_velocity: Vector3,
+
+	/// Holds the constant acceleration of the rigid body,
+	/// usually used to set the acceleration due to gravity.
+	pub acceleration: Vector3,
+
+	/// Holds how much of the gravity of the world the body feels, so that
+	/// a balloon can be given a negative scale to rise, and one of zero floats.
+	pub gravity_scale: Real,
+
+	/// Holds the gravity the body feels in place of the world's, if any,
+	/// such as toward the surface under a character with magnetic boots.
+	/// It is scaled by the gravity scale like the world's.
+	pub gravity_override: Option<Vector3>,
+
+	/// Holds the amount of damping applied to linear motion, as the
+	/// fraction of velocity kept after one second. Damping is required
+	/// to remove energy added through numerical instability in the integrator.
+	///
+	/// Because it is a fraction per second rather than per step, the same
+	/// value gives the same damping whatever timestep is used.
+	pub linear_damping: Real,
+
+	/// Holds the amount of damping applied to angular motion, as the
+	/// fraction of angular velocity kept after one second.
+	pub angular_damping: Real,
+
+	/// Holds the inverse of the mass of the rigid body.
+	///
+	/// As with particles, holding the inverse allows immovable
+	/// bodies to be represented with an inverse mass of zero.
+	pub inverse_mass: Real,
+
+	/// Holds the inverse of the body's inertia tensor in body space.
+	///
+	/// The inertia tensor is the rotational equivalent of mass, and
+	/// a zero inverse means the body cannot be rotated by torques.
+	pub inverse_inertia_tensor: Matrix3,
+
+	/// Holds how the gyroscopic torque is handled during integration.
+	pub gyroscopic_torque: GyroscopicTorque,
+
+	/// Holds the fastest the body may spin, in radians per second, or `None`
+	/// if its spin is not limited. Small bodies hit by large impulses can
+	/// otherwise spin fast enough to make the integration unstable.
+	pub max_angular_velocity: Option<Real>,
+
+	/// Holds whether the body is asleep. Sleeping bodies are not integrated
+	/// and are woken when a force is applied to them or they are hit.
+	pub sleeping: bool,
+
+	/// Holds whether the body is allowed to fall asleep. Bodies under the
+	/// user's control, such as the player, should not sleep.
+	pub can_sleep: bool,
+
+	/// Holds whether the body's motion is swept each step to stop it passing
+	/// through thin objects, for fast movers such as bullets. See [`crate::ccd`].
+	pub continuous_collision: bool,
+
+	/// Holds a recency-weighted average of the body's kinetic energy,
+	/// used to decide when it has settled enough to sleep. A body that was
+	/// just woken holds [`Real::MAX`] until it is next integrated.
+	pub motion: Real,
+
+	/// Holds the accumulated force to be applied at the next
+	/// integration step only. This value is zeroed at each
+	/// integration step.
+	pub force_accumulator: Vector3,
+
+	/// Holds the accumulated torque to be applied at the next
+	/// integration step only. This value is zeroed at each
+	/// integration step.
+	pub torque_accumulator: Vector3,
+
+	/// Holds a transform matrix for converting body space into world space.
+	/// This is derived data, updated by `calculate_derived_data`.
+	pub transform_matrix: Matrix4,
+
+	/// Holds the inverse inertia tensor of the body in world space.
+	/// This is derived data, updated by `calculate_derived_data`.
+	pub inverse_inertia_tensor_world: Matrix3,
+
+	/// Holds a value of the user's choosing, such as the handle of the game
+	/// entity the body belongs to. It is passed back in the world's events.
+	pub user_data: u64,
+}
+
+impl Default for RigidBody {
+	fn default() -> Self {
+		Self {
+			kind: BodyKind::default(),
+			position: Vector3::default(),
+			orientation: Quaternion::default(),
+			velocity: Vector3::default(),
+			angular_velocity: Vector3::default(),
+			acceleration: Vector3::default(),
+			gravity_scale: 1.0,
+			gravity_override: None,
+			linear_damping: 0.0,
+			angular_damping: 0.0,
+			inverse_mass: 0.0,
+			inverse_inertia_tensor: Matrix3::default(),
+			gyroscopic_torque: GyroscopicTorque::default(),
+			max_angular_velocity: None,
+			sleeping: false,
+			can_sleep: false,
+			continuous_collision: false,
+			motion: 0.0,
+			force_accumulator: Vector3::default(),
+			torque_accumulator: Vector3::default(),
+			transform_matrix: Matrix4::default(),
+			inverse_inertia_tensor_world: Matrix3::default(),
+			user_data: 0,
+		}
+	}
+}
+
+impl RigidBody {
+	#[must_use]
+	pub const fn mass(&self) -> Real {
+		self.inverse_mass.recip()
+	}
+
+	#[must_use]
+	pub fn has_finite_mass(&self) -> bool {
+		self.inverse_mass != 0.0
+	}
+
+	/// Returns the inverse mass seen by collisions and constraints,
+	/// which is zero for bodies that cannot be pushed.
+	#[must_use]
+	pub const fn effective_inverse_mass(&self) -> Real {
+		match self.kind {
+			BodyKind::Dynamic => self.inverse_mass,
+			BodyKind::Kinematic | BodyKind::Static => 0.0,
+		}
+	}
+
+	/// Returns the world-space inverse inertia tensor seen by collisions
+	/// and constraints, which is zero for bodies that cannot be pushed.
+	#[must_use]
+	pub const fn effective_inverse_inertia_tensor_world(&self) -> Matrix3 {
+		match self.kind {
+			BodyKind::Dynamic => self.inverse_inertia_tensor_world,
+			BodyKind::Kinematic | BodyKind::Static => Matrix3::zero(),
+		}
+	}
+
+	/// Returns the acceleration due to gravity the body feels in a world
+	/// with the given gravity, which is its override if it has one, scaled
+	/// by its gravity scale.
+	#[must_use]
+	pub fn gravity(&self, world_gravity: Vector3) -> Vector3 {
+		self.gravity_override.unwrap_or(world_gravity) * self.gravity_scale
+	}
+
+	/// Returns the energy of the body's linear and angular motion, which is
+	/// zero for bodies with infinite mass.
+	#[must_use]
+	pub fn kinetic_energy(&self) -> Real {
+		if !self.has_finite_mass() {
+			return 0.0;
+		}
+		let inertia_tensor_world = self.inverse_inertia_tensor_world.inverse().unwrap_or_default();
+		let angular_momentum = inertia_tensor_world * self.angular_velocity;
+		0.5 * self
+			.mass()
+			.mul_add(self.velocity.dot(&self.velocity), self.angular_velocity.dot(&angular_momentum))
+	}
+
+	/// Sets the body-space inertia tensor, storing its inverse.
+	/// A singular tensor leaves the body unable to rotate.
+	pub fn set_inertia_tensor(&mut self, inertia_tensor: &Matrix3) {
+		self.inverse_inertia_tensor = inertia_tensor.inverse().unwrap_or_default();
+	}
+
+	/// Returns the body-space inertia tensor, or a zero
+	/// tensor if the body cannot be rotated by torques.
+	#[must_use]
+	pub fn inertia_tensor(&self) -> Matrix3 {
+		self.inverse_inertia_tensor.inverse().unwrap_or_default()
+	}
+
+	/// Calculates internal data from the body's state.
+	///
+	/// This is called automatically at the end of integration, and should be
+	/// called after changing the position, orientation, or inertia tensor
+	/// directly. The orientation is normalized along the way.
+	pub fn calculate_derived_data(&mut self) {
+		self.orientation = self.orientation.normalize();
+		self.transform_matrix = Matrix4::from_transform(&self.orientation, self.position);
+		let rotation = self.transform_matrix.rotation();
+		self.inverse_inertia_tensor_world = rotation * self.inverse_inertia_tensor * rotation.transpose();
+	}
+
+	/// Converts the given point from body space into world space.
+	#[must_use]
+	pub fn get_point_in_world(&self, point: Vector3) -> Vector3 {
+		self.transform_matrix.transform(point)
+	}
+
+	/// Converts the given point from world space into body space.
+	#[must_use]
+	pub fn get_point_in_local(&self, point: Vector3) -> Vector3 {
+		self.transform_matrix.transform_inverse(point)
+	}
+
+	/// Converts the given direction from body space into world space.
+	#[must_use]
+	pub fn get_direction_in_world(&self, direction: Vector3) -> Vector3 {
+		self.transform_matrix.transform_direction(direction)
+	}
+
+	/// Converts the given direction from world space into body space.
+	#[must_use]
+	pub fn get_direction_in_local(&self, direction: Vector3) -> Vector3 {
+		self.transform_matrix.transform_inverse_direction(direction)
+	}
+
+	/// Returns the velocity of the given world-space point as if it
+	/// were attached to the body, including the effect of its spin.
+	#[must_use]
+	pub fn velocity_at_point(&self, point: Vector3) -> Vector3 {
+		self.velocity + self.angular_velocity.cross(&(point - self.position))
+	}
+
+	/// Wakes the body or puts it to sleep. Sleeping bodies have their
+	/// velocities cleared, and woken bodies are given enough motion that
+	/// they do not immediately fall back asleep.
+	pub fn set_awake(&mut self, awake: bool) {
+		if awake {
+			self.sleeping = false;
+			self.motion = WAKING_MOTION;
+		} else {
+			self.sleeping = true;
+			self.velocity = Vector3::zero();
+			self.angular_velocity = Vector3::zero();
+		}
+	}
+
+	/// Sets whether the body may sleep, waking it if it may not.
+	pub fn set_can_sleep(&mut self, can_sleep: bool) {
+		self.can_sleep = can_sleep;
+		if !can_sleep && self.sleeping {
+			self.set_awake(true);
+		}
+	}
+
+	/// Wakes the body if it is asleep and the given force or torque is not zero.
+	fn wake_for(&mut self, applied: Vector3) {
+		if self.sleeping && applied.magnitude_squared() > 0.0 {
+			self.set_awake(true);
+		}
+	}
+
+	/// Adds the given force to the center of mass of the rigid body.
+	/// The force is expressed in world coordinates.
+	pub fn add_force(&mut self, force: Vector3) {
+		self.force_accumulator += force;
+		self.wake_for(force);
+	}
+
+	/// Adds the given force to the given point on the rigid body.
+	///
+	/// Both the force and the point are in world coordinates. A force away
+	/// from the center of mass generates a torque as well as a force.
+	pub fn add_force_at_point(&mut self, force: Vector3, point: Vector3) {
+		let arm = point - self.position;
+		self.force_accumulator += force;
+		self.torque_accumulator += arm.cross(&force);
+		self.wake_for(force);
+	}
+
+	/// Adds the given force to the given point on the rigid body.
+	/// The force is in world coordinates, but the point is in body coordinates,
+	/// which is useful for thrusters and other fixed attachment points.
+	pub fn add_force_at_body_point(&mut self, force: Vector3, point: Vector3) {
+		self.add_force_at_point(force, self.get_point_in_world(point));
+	}
+
+	/// Adds the given torque to the rigid body.
+	/// The torque is expressed in world coordinates.
+	pub fn add_torque(&mut self, torque: Vector3) {
+		self.torque_accumulator += torque;
+		self.wake_for(torque);
+	}
+
+	/// Applies the given torque impulse, in world coordinates, changing
+	/// the angular velocity immediately rather than over the next step.
+	///
+	/// Impulses only affect dynamic bodies, and wake them if they are asleep.
+	pub fn apply_torque_impulse(&mut self, impulse: Vector3) {
+		let change = self.effective_inverse_inertia_tensor_world() * impulse;
+		self.wake_for(change);
+		self.angular_velocity += change;
+		self.clamp_angular_velocity();
+	}
+
+	/// Applies the given impulse at the given point, both in world
+	/// coordinates, changing the velocities immediately rather than over
+	/// the next step. An impulse away from the center of mass spins the body too.
+	///
+	/// Impulses only affect dynamic bodies, and wake them if they are asleep.
+	pub fn apply_impulse_at_point(&mut self, impulse: Vector3, point: Vector3) {
+		let change = impulse * self.effective_inverse_mass();
+		self.wake_for(change);
+		self.velocity += change;
+		self.apply_torque_impulse((point - self.position).cross(&impulse));
+	}
+
+	/// Scales the angular velocity down to the maximum, if it has one.
+	pub fn clamp_angular_velocity(&mut self) {
+		self.angular_velocity = clamp_angular_velocity(self.angular_velocity, self.max_angular_velocity);
+	}
+
+	/// Clears the forces and torques in the accumulators.
+	pub fn clear_accumulators(&mut self) {
+		self.force_accumulator = Vector3::zero();
+		self.torque_accumulator = Vector3::zero();
+	}
+
+	/// Integrates the rigid body forward in time by the given amount.
+	///
+	/// Velocities are updated from the accumulated force and torque first, and
+	/// the new velocities are then used to move and rotate the body.
+	///
+	/// Kinematic bodies move with their current velocities and ignore any
+	/// forces, while static bodies are not integrated at all.
+	pub fn integrate(&mut self, duration: Real) {
+		self.integrate_with_sleep_epsilon(duration, DEFAULT_SLEEP_EPSILON);
+	}
+
+	/// Integrates the rigid body forward in time by the given amount, putting
+	/// it to sleep once its motion has stayed below the given sleep epsilon.
+	pub fn integrate_with_sleep_epsilon(&mut self, duration: Real, sleep_epsilon: Real) {
+		if duration <= 0.0 {
+			return;
+		}
+		match self.kind {
+			BodyKind::Static => return,
+			BodyKind::Kinematic => {
+				self.integrate_motion(duration);
+				self.clear_accumulators();
+				return;
+			},
+			BodyKind::Dynamic => {},
+		}
+
+		// Infinite mass and sleeping bodies should not be integrated
+		if self.inverse_mass <= 0.0 || self.sleeping {
+			return;
+		}
+
+		// Calculate linear and angular acceleration from the accumulators
+		let acceleration = self.acceleration + self.force_accumulator * self.inverse_mass;
+		let mut torque = self.torque_accumulator;
+		if self.gyroscopic_torque == GyroscopicTorque::Explicit {
+			torque -= gyroscopic_term(&self.transform_matrix, &self.inverse_inertia_tensor, self.angular_velocity);
+		}
+		let angular_acceleration = self.inverse_inertia_tensor_world * torque;
+
+		// Update linear and angular velocity
+		self.velocity += acceleration * duration;
+		self.angular_velocity += angular_acceleration * duration;
+		if self.gyroscopic_torque == GyroscopicTorque::Implicit {
+			self.angular_velocity = implicit_gyroscopic_velocity(
+				&self.transform_matrix,
+				&self.inverse_inertia_tensor,
+				self.angular_velocity,
+				duration,
+			);
+		}
+
+		// Impose drag
+		self.velocity *= powf(self.linear_damping, duration);
+		self.angular_velocity *= powf(self.angular_damping, duration);
+		self.clamp_angular_velocity();
+
+		self.integrate_motion(duration);
+		self.clear_accumulators();
+		self.update_motion(duration, sleep_epsilon);
+	}
+
+	/// Updates the recency-weighted motion and puts the body to sleep
+	/// once it has stayed below the sleep epsilon for long enough.
+	fn update_motion(&mut self, duration: Real, epsilon: Real) {
+		if !self.can_sleep {
+			return;
+		}
+		let (motion, settled) = settle(self.motion, [self.velocity, self.angular_velocity], duration, epsilon);
+		self.motion = motion;
+		if settled {
+			self.set_awake(false);
+		}
+	}
+
+	/// Moves and rotates the body by its current velocities.
+	fn integrate_motion(&mut self, duration: Real) {
+		self.position += self.velocity * duration;
+		self.orientation.add_scaled_vector(self.angular_velocity, duration);
+		self.calculate_derived_data();
+	}
+}
+
+/// Returns the angular velocity scaled down to the maximum, if there is one.
+pub(crate) fn clamp_angular_velocity(angular_velocity: Vector3, max_angular_velocity: Option<Real>) -> Vector3 {
+	match max_angular_velocity {
+		Some(max) if angular_velocity.magnitude_squared() > max * max => angular_velocity.normalize() * max,
+		_ => angular_velocity,
+	}
+}
+
+/// Returns the gyroscopic term `ω × (Iω)` in world space, for a body with
+/// the given transform and body-space inverse inertia tensor.
+pub(crate) fn gyroscopic_term(
+	transform: &Matrix4,
+	inverse_inertia_tensor: &Matrix3,
+	angular_velocity: Vector3,
+) -> Vector3 {
+	let angular_velocity = transform.transform_inverse_direction(angular_velocity);
+	let momentum = inverse_inertia_tensor.inverse().unwrap_or_default() * angular_velocity;
+	transform.transform_direction(angular_velocity.cross(&momentum))
+}
+
+/// Returns the angular velocity updated for the gyroscopic torque over the
+/// step with one Newton iteration of the implicit Euler update, in body space.
+pub(crate) fn implicit_gyroscopic_velocity(
+	transform: &Matrix4,
+	inverse_inertia_tensor: &Matrix3,
+	angular_velocity: Vector3,
+	duration: Real,
+) -> Vector3 {
+	let inertia_tensor = inverse_inertia_tensor.inverse().unwrap_or_default();
+	let local = transform.transform_inverse_direction(angular_velocity);
+	let momentum = inertia_tensor * local;
+	let residual = local.cross(&momentum) * duration;
+	let jacobian = inertia_tensor
+		+ (Matrix3::skew_symmetric(local) * inertia_tensor - Matrix3::skew_symmetric(momentum)) * duration;
+	let Some(inverse_jacobian) = jacobian.inverse() else {
+		return angular_velocity;
+	};
+	transform.transform_direction(local - inverse_jacobian * residual)
+}
+
+/// Returns the recency-weighted motion of a body that moved with the given
+/// linear and angular velocities over the step, and whether it has stayed
+/// below the sleep epsilon for long enough to be put to sleep.
+pub(crate) fn settle(motion: Real, velocities: [Vector3; 2], duration: Real, epsilon: Real) -> (Real, bool) {
+	let motion = if motion >= WAKING_MOTION { 2.0 * epsilon } else { motion };
+	let [velocity, angular_velocity] = velocities;
+	let current_motion = velocity.dot(&velocity) + angular_velocity.dot(&angular_velocity);
+	let bias = powf(0.5, duration);
+	let motion = bias.mul_add(motion, (1.0 - bias) * current_motion);
+	if motion < epsilon {
+		(motion, true)
+	} else {
+		(motion.min(10.0 * epsilon), false)
+	}
+}
+
+/// Bodies whose transforms can be looked up by index, whether kept whole
+/// side by side or as the columns of a [`BodySet`](crate::body_set::BodySet).
+pub trait BodyTransforms {
+	/// Returns the transform of the given body from body space into world space.
+	fn transform(&self, body: usize) -> &Matrix4;
+}
+
+impl<T: AsRef<[RigidBody]> + ?Sized> BodyTransforms for T {
+	fn transform(&self, body: usize) -> &Matrix4 {
+		&self.as_ref()[body].transform_matrix
+	}
+}
+
+/// Builds a rigid body whose mass and inertia tensor are derived from a shape.
+///
+/// The mass comes from the shape's volume and a density unless it is set
+/// explicitly, and the inertia tensor is always that of the solid shape.
+#[derive(Debug, Clone, Copy)]
+pub struct RigidBodyBuilder {
+	kind: BodyKind,
+	volume: Real,
+
+	/// Holds the inertia tensor of the shape when it has unit mass.
+	unit_inertia_tensor: Matrix3,
+	density: Real,
+	mass: Option<Real>,
+	position: Vector3,
+	orientation: Quaternion,
+	velocity: Vector3,
+	angular_velocity: Vector3,
+	acceleration: Vector3,
+	gravity_scale: Real,
+	gravity_override: Option<Vector3>,
+	linear_damping: Real,
+	angular_damping: Real,
+	gyroscopic_torque: GyroscopicTorque,
+	max_angular_velocity: Option<Real>,
+	can_sleep: bool,
+	continuous_collision: bool,
+	user_data: u64,
+}
+
+impl RigidBodyBuilder {
+	#[must_use]
+	pub fn new(shape: Shape) -> Self {
+		Self::from_mass_properties(shape.volume(), shape.inertia_tensor(1.0))
+	}
+
+	/// Creates a builder for a body made of the compound's shapes. The body's
+	/// position is the compound's center of mass, where its colliders from
+	/// [`Compound::colliders`] are centered.
+	#[must_use]
+	pub fn compound(compound: &Compound) -> Self {
+		Self::from_mass_properties(compound.volume(), compound.inertia_tensor(1.0))
+	}
+
+	fn from_mass_properties(volume: Real, unit_inertia_tensor: Matrix3) -> Self {
+		Self {
+			kind: BodyKind::Dynamic,
+			volume,
+			unit_inertia_tensor,
+			density: 1.0,
+			mass: None,
+			position: Vector3::zero(),
+			orientation: Quaternion::identity(),
+			velocity: Vector3::zero(),
+			angular_velocity: Vector3::zero(),
+			acceleration: Vector3::zero(),
+			gravity_scale: 1.0,
+			gravity_override: None,
+			linear_damping: 0.99,
+			angular_damping: 0.99,
+			gyroscopic_torque: GyroscopicTorque::Ignored,
+			max_angular_velocity: None,
+			can_sleep: true,
+			continuous_collision: false,
+			user_data: 0,
+		}
+	}
+
+	#[must_use]
+	pub const fn kind(mut self, kind: BodyKind) -> Self {
+		self.kind = kind;
+		self
+	}
+
+	/// Sets the density used to derive the mass from the shape's volume.
+	#[must_use]
+	pub const fn density(mut self, density: Real) -> Self {
+		self.density = density;
+		self
+	}
+
+	/// Sets the density used to derive the mass to that of the material.
+	#[must_use]
+	pub const fn material(self, material: &Material) -> Self {
+		self.density(material.density)
+	}
+
+	/// Sets the mass directly, ignoring the density.
+	#[must_use]
+	pub const fn mass(mut self, mass: Real) -> Self {
+		self.mass = Some(mass);
+		self
+	}
+
+	#[must_use]
+	pub const fn position(mut self, position: Vector3) -> Self {
+		self.position = position;
+		self
+	}
+
+	#[must_use]
+	pub const fn orientation(mut self, orientation: Quaternion) -> Self {
+		self.orientation = orientation;
+		self
+	}
+
+	#[must_use]
+	pub const fn velocity(mut self, velocity: Vector3) -> Self {
+		self.velocity = velocity;
+		self
+	}
+
+	#[must_use]
+	pub const fn angular_velocity(mut self, angular_velocity: Vector3) -> Self {
+		self.angular_velocity = angular_velocity;
+		self
+	}
+
+	#[must_use]
+	pub const fn acceleration(mut self, acceleration: Vector3) -> Self {
+		self.acceleration = acceleration;
+		self
+	}
+
+	/// Sets how much of the gravity of the world the body feels.
+	#[must_use]
+	pub const fn gravity_scale(mut self, gravity_scale: Real) -> Self {
+		self.gravity_scale = gravity_scale;
+		self
+	}
+
+	/// Sets the gravity the body feels in place of the world's.
+	#[must_use]
+	pub const fn gravity_override(mut self, gravity: Vector3) -> Self {
+		self.gravity_override = Some(gravity);
+		self
+	}
+
+	/// Sets the linear and angular damping.
+	#[must_use]
+	pub const fn damping(mut self, linear: Real, angular: Real) -> Self {
+		self.linear_damping = linear;
+		self.angular_damping = angular;
+		self
+	}
+
+	/// Sets how the gyroscopic torque is handled during integration.
+	#[must_use]
+	pub const fn gyroscopic_torque(mut self, gyroscopic_torque: GyroscopicTorque) -> Self {
+		self.gyroscopic_torque = gyroscopic_torque;
+		self
+	}
+
+	/// Limits how fast the body may spin, in radians per second.
+	#[must_use]
+	pub const fn max_angular_velocity(mut self, max_angular_velocity: Real) -> Self {
+		self.max_angular_velocity = Some(max_angular_velocity);
+		self
+	}
+
+	/// Sets whether the body may fall asleep. Bodies may sleep by default.
+	#[must_use]
+	pub const fn can_sleep(mut self, can_sleep: bool) -> Self {
+		self.can_sleep = can_sleep;
+		self
+	}
+
+	/// Sets whether the body's motion is swept each step, for fast movers.
+	#[must_use]
+	pub const fn continuous_collision(mut self, continuous_collision: bool) -> Self {
+		self.continuous_collision = continuous_collision;
+		self
+	}
+
+	/// Sets the value of the user's choosing held by the body.
+	#[must_use]
+	pub const fn user_data(mut self, user_data: u64) -> Self {
+		self.user_data = user_data;
+		self
+	}
+
+	/// Creates the rigid body, with its derived data already calculated.
+	/// A body with no mass is given infinite mass instead.
+	#[must_use]
+	pub fn build(self) -> RigidBody {
+		let mass = self.mass.unwrap_or(self.density * self.volume);
+		let mut body = RigidBody {
+			kind: self.kind,
+			position: self.position,
+			orientation: self.orientation,
+			velocity: self.velocity,
+			angular_velocity: self.angular_velocity,
+			acceleration: self.acceleration,
+			gravity_scale: self.gravity_scale,
+			gravity_override: self.gravity_override,
+			linear_damping: self.linear_damping,
+			angular_damping: self.angular_damping,
+			gyroscopic_torque: self.gyroscopic_torque,
+			max_angular_velocity: self.max_angular_velocity,
+			can_sleep: self.can_sleep,
+			continuous_collision: self.continuous_collision,
+			user_data: self.user_data,
+			motion: WAKING_MOTION,
+			inverse_mass: if mass > 0.0 { mass.recip() } else { 0.0 },
+			..Default::default()
+		};
+		if mass > 0.0 {
+			body.set_inertia_tensor(&(self.unit_inertia_tensor * mass));
+		}
+		body.calculate_derived_data();
+		body
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::f32::consts::PI;
+
+	use crate::assert_equal;
+
+	use super::*;
+
+	fn box_body() -> RigidBody {
+		let mut body = RigidBody {
+			inverse_mass: 1.0,
+			linear_damping: 1.0,
+			angular_damping: 1.0,
+			..Default::default()
+		};
+		body.set_inertia_tensor(&Matrix3::diagonal(Vector3::new(1.0, 2.0, 4.0)));
+		body.calculate_derived_data();
+		body
+	}
+
+	fn is_close(actual: Vector3, expected: Vector3) -> bool {
+		(actual - expected).magnitude() < 1e-5
+	}
+
+	#[test]
+	pub fn inertia_tensor_is_stored_inverted() {
+		let mut body = RigidBody::default();
+		let inertia_tensor = Matrix3::diagonal(Vector3::new(2.0, 4.0, 8.0));
+		body.set_inertia_tensor(&inertia_tensor);
+		assert_eq!(
+			body.inverse_inertia_tensor,
+			Matrix3::diagonal(Vector3::new(0.5, 0.25, 0.125))
+		);
+		assert_eq!(body.inertia_tensor(), inertia_tensor);
+	}
+
+	#[test]
+	pub fn default_body_is_immovable() {
+		let body = RigidBody::default();
+		assert!(!body.has_finite_mass());
+		assert_eq!(body.orientation, Quaternion::identity());
+		assert_eq!(body.inertia_tensor(), Matrix3::default());
+	}
+
+	#[test]
+	pub fn force_accelerates_body() {
+		let mut body = RigidBody {
+			inverse_mass: 0.5,
+			..box_body()
+		};
+		body.add_force(Vector3::new(4.0, 0.0, 0.0));
+		body.integrate(0.5);
+		assert_eq!(body.velocity, Vector3::new(1.0, 0.0, 0.0));
+		assert_eq!(body.position, Vector3::new(0.5, 0.0, 0.0));
+		assert_eq!(body.force_accumulator, Vector3::zero());
+	}
+
+	#[test]
+	pub fn spinning_top_turns_at_constant_rate() {
+		// Spinning about a principal axis with no torque, a quarter turn per second
+		let mut body = RigidBody {
+			angular_velocity: Vector3::new(0.0, PI * 0.5, 0.0),
+			..box_body()
+		};
+		for _ in 0..1000 {
+			body.integrate(0.001);
+		}
+		assert_eq!(body.angular_velocity, Vector3::new(0.0, PI * 0.5, 0.0));
+		let forward = body.orientation.rotate(Vector3::x_axis());
+		assert!((forward - Vector3::new(0.0, 0.0, -1.0)).magnitude() < 1e-3);
+	}
+
+	#[test]
+	pub fn torque_tumbles_box_about_each_axis() {
+		// The same torque spins the box faster about axes with less inertia
+		for (axis, inertia) in [
+			(Vector3::x_axis(), 1.0),
+			(Vector3::y_axis(), 2.0),
+			(Vector3::z_axis(), 4.0),
+		] {
+			let mut body = box_body();
+			body.add_torque(axis * 2.0);
+			body.integrate(0.5);
+			assert_eq!(body.angular_velocity, axis * (1.0 / inertia));
+			assert_eq!(body.torque_accumulator, Vector3::zero());
+		}
+	}
+
+	#[test]
+	pub fn force_at_point_adds_torque() {
+		let mut body = RigidBody {
+			position: Vector3::new(1.0, 0.0, 0.0),
+			..box_body()
+		};
+		body.add_force_at_point(Vector3::y_axis(), Vector3::new(2.0, 0.0, 0.0));
+		assert_eq!(body.force_accumulator, Vector3::y_axis());
+		assert_eq!(body.torque_accumulator, Vector3::z_axis());
+
+		// A force through the center of mass adds no torque
+		body.clear_accumulators();
+		body.add_force_at_point(Vector3::x_axis(), Vector3::new(3.0, 0.0, 0.0));
+		assert_eq!(body.torque_accumulator, Vector3::zero());
+	}
+
+	#[test]
+	pub fn force_at_body_point_follows_orientation() {
+		// Turned a quarter about z, the body's x axis points along world y
+		let mut body = RigidBody {
+			orientation: Quaternion::from_axis_angle(Vector3::z_axis(), PI * 0.5),
+			..box_body()
+		};
+		body.calculate_derived_data();
+		body.add_force_at_body_point(Vector3::x_axis(), Vector3::x_axis());
+		assert!(is_close(body.torque_accumulator, -Vector3::z_axis()));
+	}
+
+	#[test]
+	pub fn derived_data_follows_state() {
+		let mut body = RigidBody {
+			position: Vector3::new(0.0, 1.0, 0.0),
+			orientation: Quaternion::new(2.0, 0.0, 0.0, 2.0),
+			..box_body()
+		};
+		body.calculate_derived_data();
+		assert_eq!(
+			body.orientation,
+			Quaternion::from_axis_angle(Vector3::z_axis(), PI * 0.5)
+		);
+		assert_eq!(
+			body.transform_matrix.transform(Vector3::x_axis()),
+			Vector3::new(0.0, 2.0, 0.0)
+		);
+		// Turned about z, the body's x and y inertia are swapped in world space
+		let expected = Matrix3::diagonal(Vector3::new(0.5, 1.0, 0.25));
+		for row in 0..3 {
+			assert!(is_close(body.inverse_inertia_tensor_world.row(row), expected.row(row)));
+		}
+	}
+
+	#[test]
+	pub fn damping_is_split_and_timestep_independent() {
+		// An asteroid whose drift damps out while it keeps spinning
+		let asteroid = RigidBody {
+			velocity: Vector3::x_axis(),
+			angular_velocity: Vector3::y_axis(),
+			linear_damping: 0.5,
+			..box_body()
+		};
+		let mut coarse = asteroid;
+		coarse.integrate(1.0);
+		let mut fine = asteroid;
+		for _ in 0..10 {
+			fine.integrate(0.1);
+		}
+		for body in [coarse, fine] {
+			assert!(is_close(body.velocity, Vector3::new(0.5, 0.0, 0.0)));
+			assert_eq!(body.angular_velocity, Vector3::y_axis());
+		}
+	}
+
+	#[test]
+	pub fn kinematic_body_moves_but_ignores_forces() {
+		let mut platform = RigidBody {
+			kind: BodyKind::Kinematic,
+			velocity: Vector3::x_axis(),
+			acceleration: Vector3::new(0.0, -10.0, 0.0),
+			..box_body()
+		};
+		platform.add_force(Vector3::new(0.0, 100.0, 0.0));
+		platform.integrate(0.5);
+		assert_eq!(platform.position, Vector3::new(0.5, 0.0, 0.0));
+		assert_eq!(platform.velocity, Vector3::x_axis());
+		assert_eq!(platform.force_accumulator, Vector3::zero());
+		assert!(platform.effective_inverse_mass() <= 0.0);
+	}
+
+	#[test]
+	pub fn static_body_is_not_integrated() {
+		let mut wall = RigidBody {
+			kind: BodyKind::Static,
+			velocity: Vector3::x_axis(),
+			..box_body()
+		};
+		wall.integrate(0.5);
+		assert_eq!(wall.position, Vector3::zero());
+		assert_eq!(wall.effective_inverse_inertia_tensor_world(), Matrix3::zero());
+	}
+
+	#[test]
+	pub fn point_and_direction_conversion() {
+		let mut body = RigidBody {
+			position: Vector3::new(1.0, 0.0, 0.0),
+			orientation: Quaternion::from_axis_angle(Vector3::z_axis(), PI * 0.5),
+			..box_body()
+		};
+		body.calculate_derived_data();
+		let world = body.get_point_in_world(Vector3::x_axis());
+		assert!(is_close(world, Vector3::new(1.0, 1.0, 0.0)));
+		assert!(is_close(body.get_point_in_local(world), Vector3::x_axis()));
+		let direction = body.get_direction_in_world(Vector3::x_axis());
+		assert!(is_close(direction, Vector3::y_axis()));
+		assert!(is_close(body.get_direction_in_local(direction), Vector3::x_axis()));
+	}
+
+	#[test]
+	pub fn velocity_at_point_includes_spin() {
+		let body = RigidBody {
+			velocity: Vector3::x_axis(),
+			angular_velocity: Vector3::new(0.0, 0.0, 2.0),
+			..box_body()
+		};
+		assert_eq!(body.velocity_at_point(Vector3::x_axis()), Vector3::new(1.0, 2.0, 0.0));
+		assert_eq!(body.velocity_at_point(Vector3::zero()), Vector3::x_axis());
+	}
+
+	#[test]
+	pub fn builder_derives_mass_properties() {
+		let body = RigidBodyBuilder::new(Shape::Cuboid {
+			half_size: Vector3::new(1.0, 1.0, 1.0),
+		})
+		.density(0.5)
+		.position(Vector3::y_axis())
+		.build();
+		assert_equal(body.mass(), 4.0);
+		for row in 0..3 {
+			let expected = Matrix3::identity().row(row) * (8.0 / 3.0);
+			assert!(is_close(body.inertia_tensor().row(row), expected));
+		}
+		assert_eq!(body.get_point_in_world(Vector3::zero()), Vector3::y_axis());
+
+		let heavy = RigidBodyBuilder::new(Shape::Sphere { radius: 1.0 }).mass(10.0).build();
+		assert_equal(heavy.mass(), 10.0);
+		let immovable = RigidBodyBuilder::new(Shape::Sphere { radius: 1.0 }).mass(0.0).build();
+		assert!(!immovable.has_finite_mass());
+	}
+
+	fn angular_momentum_and_energy(body: &RigidBody) -> (Vector3, Real) {
+		let inertia_tensor_world = body.inverse_inertia_tensor_world.inverse().unwrap();
+		let momentum = inertia_tensor_world * body.angular_velocity;
+		(momentum, 0.5 * body.angular_velocity.dot(&momentum))
+	}
+
+	#[test]
+	pub fn gyroscopic_torque_conserves_angular_momentum() {
+		// A fast spin near the unstable intermediate axis
+		for gyroscopic_torque in [GyroscopicTorque::Explicit, GyroscopicTorque::Implicit] {
+			let mut body = RigidBody {
+				angular_velocity: Vector3::new(0.1, 10.0, 0.1),
+				gyroscopic_torque,
+				..box_body()
+			};
+			let (momentum, energy) = angular_momentum_and_energy(&body);
+			for _ in 0..200 {
+				body.integrate(0.001);
+			}
+			let (final_momentum, final_energy) = angular_momentum_and_energy(&body);
+			assert!((final_momentum - momentum).magnitude() < 0.01 * momentum.magnitude());
+			assert!(final_energy < energy * 1.01);
+		}
+	}
+
+	#[test]
+	pub fn implicit_gyroscopic_torque_does_not_gain_energy() {
+		let mut body = RigidBody {
+			angular_velocity: Vector3::new(20.0, 1.0, 30.0),
+			gyroscopic_torque: GyroscopicTorque::Implicit,
+			..box_body()
+		};
+		let (_, energy) = angular_momentum_and_energy(&body);
+		for _ in 0..600 {
+			body.integrate(1.0 / 60.0);
+		}
+		let (_, final_energy) = angular_momentum_and_energy(&body);
+		assert!(final_energy <= energy * 1.0001);
+	}
+
+	#[test]
+	pub fn resting_body_falls_asleep_and_wakes_on_force() {
+		let mut body = RigidBodyBuilder::new(Shape::default())
+			.velocity(Vector3::new(0.1, 0.0, 0.0))
+			.build();
+		for _ in 0..120 {
+			body.integrate(1.0 / 60.0);
+		}
+		assert!(body.sleeping);
+		assert_eq!(body.velocity, Vector3::zero());
+
+		let position = body.position;
+		body.integrate(1.0 / 60.0);
+		assert_eq!(body.position, position);
+
+		body.add_force(Vector3::x_axis());
+		assert!(!body.sleeping);
+	}
+
+	#[test]
+	pub fn moving_or_restricted_bodies_stay_awake() {
+		let mut fast = RigidBodyBuilder::new(Shape::default())
+			.velocity(Vector3::new(5.0, 0.0, 0.0))
+			.damping(1.0, 1.0)
+			.build();
+		let mut player = RigidBodyBuilder::new(Shape::default()).can_sleep(false).build();
+		for _ in 0..120 {
+			fast.integrate(1.0 / 60.0);
+			player.integrate(1.0 / 60.0);
+		}
+		assert!(!fast.sleeping);
+		assert!(!player.sleeping);
+	}
+
+	#[test]
+	pub fn torque_impulse_changes_spin_immediately() {
+		let mut body = box_body();
+		body.apply_torque_impulse(Vector3::new(2.0, 2.0, 2.0));
+		assert_eq!(body.angular_velocity, Vector3::new(2.0, 1.0, 0.5));
+
+		let mut wall = RigidBody {
+			kind: BodyKind::Static,
+			..box_body()
+		};
+		wall.apply_torque_impulse(Vector3::new(2.0, 2.0, 2.0));
+		assert_eq!(wall.angular_velocity, Vector3::zero());
+	}
+
+	#[test]
+	pub fn impulses_off_center_push_and_spin() {
+		let mut body = box_body();
+		body.apply_impulse_at_point(Vector3::new(0.0, 0.0, 2.0), Vector3::new(1.0, 0.0, 0.0));
+		assert_eq!(body.velocity, Vector3::new(0.0, 0.0, 2.0));
+		assert_eq!(body.angular_velocity, Vector3::new(0.0, -1.0, 0.0));
+	}
+
+	#[test]
+	pub fn angular_velocity_is_clamped() {
+		let mut body = RigidBody {
+			max_angular_velocity: Some(5.0),
+			..box_body()
+		};
+		body.apply_torque_impulse(Vector3::new(0.0, 0.0, 100.0));
+		assert_eq!(body.angular_velocity, Vector3::new(0.0, 0.0, 5.0));
+
+		body.add_torque(Vector3::new(100.0, 0.0, 0.0));
+		body.integrate(0.5);
+		assert_equal(body.angular_velocity.magnitude(), 5.0);
+	}
+}
@@ -0,0 +1,682 @@
+use crate::{
+	body::{
+		clamp_angular_velocity, gyroscopic_term, implicit_gyroscopic_velocity, settle, BodyKind, BodyTransforms,
+		GyroscopicTorque, RigidBody, WAKING_MOTION,
+	},
+	matrix::{Matrix3, Matrix4},
+	quaternion::Quaternion,
+	portable::powf,
+	vec::Vector3,
+	Real,
+};
+
+/// The flags of a body that decide how it takes part in each step, kept
+/// in a column of their own so that they can be checked without touching
+/// the rest of the body.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BodyFlags {
+	pub kind: BodyKind,
+	pub sleeping: bool,
+	pub can_sleep: bool,
+	pub continuous_collision: bool,
+}
+
+/// The parts of a body that the step only reads while integrating it.
+#[derive(Debug, Clone, Copy)]
+struct BodySettings {
+	acceleration: Vector3,
+	gravity_scale: Real,
+	gravity_override: Option<Vector3>,
+	linear_damping: Real,
+	angular_damping: Real,
+	gyroscopic_torque: GyroscopicTorque,
+	max_angular_velocity: Option<Real>,
+	user_data: u64,
+}
+
+/// The bodies of a [`PhysicsWorld`](crate::world::PhysicsWorld), stored as
+/// a column per part of a body rather than as whole bodies side by side.
+///
+/// Each body is named by its handle, the index [`Self::push`] returns, which
+/// is also its row in every column. A body keeps its handle until it is
+/// removed, so the colliders, joints, and contacts naming it never need to
+/// be remapped. The row of a removed body is left vacant, holding a static
+/// body that takes no part in the step, and is given to the next body
+/// pushed, the last vacated first.
+///
+/// The stages of a step that touch every body, such as integration, sleep
+/// checks, and updating the colliders, stream through just the columns they
+/// need. Whole bodies are copied out with [`Self::get`] and back with
+/// [`Self::set`] where the rest of the crate works with [`RigidBody`]s.
+#[derive(Debug, Default)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(crate = "serde", from = "BodyRows", into = "BodyRows")
+)]
+pub struct BodySet {
+	positions: Vec<Vector3>,
+	orientations: Vec<Quaternion>,
+	velocities: Vec<Vector3>,
+	angular_velocities: Vec<Vector3>,
+	inverse_masses: Vec<Real>,
+
+	/// Holds the inverse inertia tensor of each body in body space.
+	inverse_inertia_tensors: Vec<Matrix3>,
+
+	/// Holds the inverse inertia tensor of each body in world space.
+	inverse_inertia_tensors_world: Vec<Matrix3>,
+
+	/// Holds the transform of each body from body space into world space.
+	transforms: Vec<Matrix4>,
+
+	forces: Vec<Vector3>,
+	torques: Vec<Vector3>,
+	motions: Vec<Real>,
+	flags: Vec<BodyFlags>,
+	settings: Vec<BodySettings>,
+
+	/// Holds whether each row was left vacant by a removed body.
+	vacant: Vec<bool>,
+
+	/// Holds the vacant rows, the last vacated last.
+	free: Vec<usize>,
+}
+
+/// The rows a set is written as, with the vacant ones among them.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(crate = "serde")]
+struct BodyRows {
+	bodies: Vec<RigidBody>,
+	free: Vec<usize>,
+}
+
+#[cfg(feature = "serde")]
+impl From<BodySet> for BodyRows {
+	fn from(set: BodySet) -> Self {
+		Self {
+			bodies: set.iter().collect(),
+			free: set.free,
+		}
+	}
+}
+
+#[cfg(feature = "serde")]
+impl From<BodyRows> for BodySet {
+	fn from(rows: BodyRows) -> Self {
+		let mut set: Self = rows.bodies.into_iter().collect();
+		for body in &rows.free {
+			set.vacant[*body] = true;
+		}
+		set.free = rows.free;
+		set
+	}
+}
+
+impl BodySet {
+	/// Creates an empty set.
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Returns the number of rows, which is one more than the highest
+	/// handle, counting those left vacant by removed bodies.
+	#[must_use]
+	pub const fn len(&self) -> usize {
+		self.flags.len()
+	}
+
+	/// Returns whether there are no rows.
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.flags.is_empty()
+	}
+
+	/// Returns whether the handle names a body, rather than a vacant row or none at all.
+	#[must_use]
+	pub fn contains(&self, body: usize) -> bool {
+		self.vacant.get(body).is_some_and(|vacant| !vacant)
+	}
+
+	/// Adds the body to the set, returning its handle, which is that of the
+	/// last body removed if its row is still vacant.
+	pub fn push(&mut self, body: &RigidBody) -> usize {
+		if let Some(handle) = self.free.pop() {
+			self.vacant[handle] = false;
+			self.set(handle, body);
+			return handle;
+		}
+		self.positions.push(body.position);
+		self.orientations.push(body.orientation);
+		self.velocities.push(body.velocity);
+		self.angular_velocities.push(body.angular_velocity);
+		self.inverse_masses.push(body.inverse_mass);
+		self.inverse_inertia_tensors.push(body.inverse_inertia_tensor);
+		self.inverse_inertia_tensors_world.push(body.inverse_inertia_tensor_world);
+		self.transforms.push(body.transform_matrix);
+		self.forces.push(body.force_accumulator);
+		self.torques.push(body.torque_accumulator);
+		self.motions.push(body.motion);
+		self.flags.push(BodyFlags {
+			kind: body.kind,
+			sleeping: body.sleeping,
+			can_sleep: body.can_sleep,
+			continuous_collision: body.continuous_collision,
+		});
+		self.settings.push(BodySettings {
+			acceleration: body.acceleration,
+			gravity_scale: body.gravity_scale,
+			gravity_override: body.gravity_override,
+			linear_damping: body.linear_damping,
+			angular_damping: body.angular_damping,
+			gyroscopic_torque: body.gyroscopic_torque,
+			max_angular_velocity: body.max_angular_velocity,
+			user_data: body.user_data,
+		});
+		self.vacant.push(false);
+		self.len() - 1
+	}
+
+	/// Takes the body out of the set, returning it, and leaves its row vacant
+	/// for the next body pushed.
+	///
+	/// # Panics
+	///
+	/// Panics if no body in the set has the handle.
+	pub fn remove(&mut self, body: usize) -> RigidBody {
+		assert!(self.contains(body), "no body has the handle {body}");
+		let removed = self.get(body);
+		self.set(
+			body,
+			&RigidBody {
+				kind: BodyKind::Static,
+				can_sleep: false,
+				..RigidBody::default()
+			},
+		);
+		self.vacant[body] = true;
+		self.free.push(body);
+		removed
+	}
+
+	/// Returns a copy of the whole body with the given handle.
+	#[must_use]
+	pub fn get(&self, body: usize) -> RigidBody {
+		let (flags, settings) = (self.flags[body], self.settings[body]);
+		RigidBody {
+			kind: flags.kind,
+			position: self.positions[body],
+			orientation: self.orientations[body],
+			velocity: self.velocities[body],
+			angular_velocity: self.angular_velocities[body],
+			acceleration: settings.acceleration,
+			gravity_scale: settings.gravity_scale,
+			gravity_override: settings.gravity_override,
+			linear_damping: settings.linear_damping,
+			angular_damping: settings.angular_damping,
+			inverse_mass: self.inverse_masses[body],
+			inverse_inertia_tensor: self.inverse_inertia_tensors[body],
+			gyroscopic_torque: settings.gyroscopic_torque,
+			max_angular_velocity: settings.max_angular_velocity,
+			sleeping: flags.sleeping,
+			can_sleep: flags.can_sleep,
+			continuous_collision: flags.continuous_collision,
+			motion: self.motions[body],
+			force_accumulator: self.forces[body],
+			torque_accumulator: self.torques[body],
+			transform_matrix: self.transforms[body],
+			inverse_inertia_tensor_world: self.inverse_inertia_tensors_world[body],
+			user_data: settings.user_data,
+		}
+	}
+
+	/// Replaces the body with the given handle with the given one.
+	pub fn set(&mut self, body: usize, whole: &RigidBody) {
+		self.positions[body] = whole.position;
+		self.orientations[body] = whole.orientation;
+		self.velocities[body] = whole.velocity;
+		self.angular_velocities[body] = whole.angular_velocity;
+		self.inverse_masses[body] = whole.inverse_mass;
+		self.inverse_inertia_tensors[body] = whole.inverse_inertia_tensor;
+		self.inverse_inertia_tensors_world[body] = whole.inverse_inertia_tensor_world;
+		self.transforms[body] = whole.transform_matrix;
+		self.forces[body] = whole.force_accumulator;
+		self.torques[body] = whole.torque_accumulator;
+		self.motions[body] = whole.motion;
+		self.flags[body] = BodyFlags {
+			kind: whole.kind,
+			sleeping: whole.sleeping,
+			can_sleep: whole.can_sleep,
+			continuous_collision: whole.continuous_collision,
+		};
+		self.settings[body] = BodySettings {
+			acceleration: whole.acceleration,
+			gravity_scale: whole.gravity_scale,
+			gravity_override: whole.gravity_override,
+			linear_damping: whole.linear_damping,
+			angular_damping: whole.angular_damping,
+			gyroscopic_torque: whole.gyroscopic_torque,
+			max_angular_velocity: whole.max_angular_velocity,
+			user_data: whole.user_data,
+		};
+	}
+
+	/// Changes the body with the given handle through a copy of it, which
+	/// is then put back, returning what the change returns.
+	pub fn update<R>(&mut self, body: usize, change: impl FnOnce(&mut RigidBody) -> R) -> R {
+		let mut whole = self.get(body);
+		let changed = change(&mut whole);
+		self.set(body, &whole);
+		changed
+	}
+
+	/// Returns a copy of each whole body, in order of their handles, with
+	/// the static bodies held by vacant rows among them.
+	#[must_use]
+	pub fn iter(&self) -> impl ExactSizeIterator<Item = RigidBody> + '_ {
+		(0..self.len()).map(|body| self.get(body))
+	}
+
+	#[must_use]
+	pub fn positions(&self) -> &[Vector3] {
+		&self.positions
+	}
+
+	#[must_use]
+	pub fn orientations(&self) -> &[Quaternion] {
+		&self.orientations
+	}
+
+	#[must_use]
+	pub fn velocities(&self) -> &[Vector3] {
+		&self.velocities
+	}
+
+	#[must_use]
+	pub fn angular_velocities(&self) -> &[Vector3] {
+		&self.angular_velocities
+	}
+
+	#[must_use]
+	pub fn inverse_masses(&self) -> &[Real] {
+		&self.inverse_masses
+	}
+
+	/// Returns the force accumulated by each body for the next integration.
+	#[must_use]
+	pub fn forces(&self) -> &[Vector3] {
+		&self.forces
+	}
+
+	/// Returns the torque accumulated by each body for the next integration.
+	#[must_use]
+	pub fn torques(&self) -> &[Vector3] {
+		&self.torques
+	}
+
+	/// Returns the transform of each body from body space into world space,
+	/// as of its last [`Self::calculate_derived_data`].
+	#[must_use]
+	pub fn transforms(&self) -> &[Matrix4] {
+		&self.transforms
+	}
+
+	/// Returns the recency-weighted average of each body's kinetic energy,
+	/// used to decide when it has settled enough to sleep.
+	#[must_use]
+	pub fn motions(&self) -> &[Real] {
+		&self.motions
+	}
+
+	#[must_use]
+	pub fn flags(&self) -> &[BodyFlags] {
+		&self.flags
+	}
+
+	/// Returns the value of the user's choosing held by the body.
+	#[must_use]
+	pub fn user_data(&self, body: usize) -> u64 {
+		self.settings[body].user_data
+	}
+
+	/// Returns the energy of the linear and angular motion of the dynamic
+	/// bodies with finite mass, as [`RigidBody::kinetic_energy`] gives it for each.
+	#[must_use]
+	pub fn kinetic_energy(&self) -> Real {
+		self.massive()
+			.map(|body| {
+				let (velocity, angular_velocity) = (self.velocities[body], self.angular_velocities[body]);
+				let inertia_tensor_world = self.inverse_inertia_tensors_world[body].inverse().unwrap_or_default();
+				let angular_momentum = inertia_tensor_world * angular_velocity;
+				0.5 * self.inverse_masses[body]
+					.recip()
+					.mul_add(velocity.dot(&velocity), angular_velocity.dot(&angular_momentum))
+			})
+			.sum()
+	}
+
+	/// Returns the energy stored by the constant accelerations of the dynamic
+	/// bodies with finite mass, and by the given gravity as each of them
+	/// feels it if there is any, measured from the origin.
+	#[must_use]
+	pub fn potential_energy(&self, gravity: Option<Vector3>) -> Real {
+		-self
+			.massive()
+			.map(|body| {
+				let settings = &self.settings[body];
+				let weight = gravity.map_or_else(Vector3::zero, |gravity| {
+					settings.gravity_override.unwrap_or(gravity) * settings.gravity_scale
+				});
+				self.inverse_masses[body].recip() * (weight + settings.acceleration).dot(&self.positions[body])
+			})
+			.sum::<Real>()
+	}
+
+	/// Returns the handles of the dynamic bodies with finite mass.
+	fn massive(&self) -> impl Iterator<Item = usize> + '_ {
+		(0..self.len()).filter(|body| self.flags[*body].kind == BodyKind::Dynamic && self.inverse_masses[*body] != 0.0)
+	}
+
+	/// Returns whether the body is dynamic and awake.
+	#[must_use]
+	pub fn is_active(&self, body: usize) -> bool {
+		let flags = self.flags[body];
+		flags.kind == BodyKind::Dynamic && !flags.sleeping
+	}
+
+	/// Wakes the body or puts it to sleep, as [`RigidBody::set_awake`] does.
+	pub fn set_awake(&mut self, body: usize, awake: bool) {
+		self.flags[body].sleeping = !awake;
+		if awake {
+			self.motions[body] = WAKING_MOTION;
+		} else {
+			self.velocities[body] = Vector3::zero();
+			self.angular_velocities[body] = Vector3::zero();
+		}
+	}
+
+	/// Adds the given force, in world coordinates, to the body's center of
+	/// mass, waking it, as [`RigidBody::add_force`] does.
+	pub fn add_force(&mut self, body: usize, force: Vector3) {
+		self.forces[body] += force;
+		self.wake_for(body, force);
+	}
+
+	/// Adds the given force to the given point on the body, both in world
+	/// coordinates, as [`RigidBody::add_force_at_point`] does.
+	pub fn add_force_at_point(&mut self, body: usize, force: Vector3, point: Vector3) {
+		let arm = point - self.positions[body];
+		self.forces[body] += force;
+		self.torques[body] += arm.cross(&force);
+		self.wake_for(body, force);
+	}
+
+	/// Adds the given torque, in world coordinates, to the body, as [`RigidBody::add_torque`] does.
+	pub fn add_torque(&mut self, body: usize, torque: Vector3) {
+		self.torques[body] += torque;
+		self.wake_for(body, torque);
+	}
+
+	/// Wakes the body if it is asleep and the given force or torque is not zero.
+	fn wake_for(&mut self, body: usize, applied: Vector3) {
+		if self.flags[body].sleeping && applied.magnitude_squared() > 0.0 {
+			self.set_awake(body, true);
+		}
+	}
+
+	/// Clears the forces and torques accumulated by every body.
+	pub fn clear_accumulators(&mut self) {
+		self.forces.fill(Vector3::zero());
+		self.torques.fill(Vector3::zero());
+	}
+
+	/// Calculates the transform and world-space inverse inertia tensor of
+	/// every body from its position and orientation, as
+	/// [`RigidBody::calculate_derived_data`] does.
+	pub fn calculate_derived_data(&mut self) {
+		for body in 0..self.len() {
+			self.derive(body);
+		}
+	}
+
+	/// Calculates the transform and world-space inverse inertia tensor of the body.
+	fn derive(&mut self, body: usize) {
+		let orientation = self.orientations[body].normalize();
+		let transform = Matrix4::from_transform(&orientation, self.positions[body]);
+		let rotation = transform.rotation();
+		self.orientations[body] = orientation;
+		self.transforms[body] = transform;
+		self.inverse_inertia_tensors_world[body] = rotation * self.inverse_inertia_tensors[body] * rotation.transpose();
+	}
+
+	/// Adds the weight each awake dynamic body with finite mass feels in a
+	/// world with the given gravity to its accumulated force.
+	pub fn apply_gravity(&mut self, gravity: Vector3) {
+		for body in 0..self.len() {
+			let flags = self.flags[body];
+			let inverse_mass = self.inverse_masses[body];
+			if flags.kind == BodyKind::Dynamic && inverse_mass != 0.0 && !flags.sleeping {
+				let settings = &self.settings[body];
+				let weight = settings.gravity_override.unwrap_or(gravity) * settings.gravity_scale;
+				self.forces[body] += weight * inverse_mass.recip();
+			}
+		}
+	}
+
+	/// Integrates every body forward in time by the given duration, putting
+	/// to sleep those whose motion has stayed below the given sleep epsilon,
+	/// as [`RigidBody::integrate_with_sleep_epsilon`] does for one body.
+	///
+	/// The velocities are updated from the forces first, then the bodies are
+	/// moved, then their motion is checked for sleep, each pass touching only
+	/// the columns it needs. Static bodies, and dynamic bodies that are asleep
+	/// or have infinite mass, are passed over from their flags and masses alone.
+	pub fn integrate(&mut self, duration: Real, sleep_epsilon: Real) {
+		if duration <= 0.0 {
+			return;
+		}
+		self.accelerate(duration);
+		self.advance(duration);
+		self.settle(duration, sleep_epsilon);
+	}
+
+	/// Returns whether the body is dynamic, awake, and of finite mass, and
+	/// so is moved by the forces on it.
+	fn is_accelerated(&self, body: usize) -> bool {
+		let flags = self.flags[body];
+		flags.kind == BodyKind::Dynamic && !flags.sleeping && self.inverse_masses[body] > 0.0
+	}
+
+	/// Updates the velocities of the bodies moved by forces from the forces
+	/// and torques accumulated, then damps and clamps them.
+	fn accelerate(&mut self, duration: Real) {
+		for body in 0..self.len() {
+			if !self.is_accelerated(body) {
+				continue;
+			}
+			let settings = self.settings[body];
+			let (transform, inverse_inertia_tensor) = (&self.transforms[body], &self.inverse_inertia_tensors[body]);
+			let acceleration = settings.acceleration + self.forces[body] * self.inverse_masses[body];
+			let mut torque = self.torques[body];
+			if settings.gyroscopic_torque == GyroscopicTorque::Explicit {
+				torque -= gyroscopic_term(transform, inverse_inertia_tensor, self.angular_velocities[body]);
+			}
+			let angular_acceleration = self.inverse_inertia_tensors_world[body] * torque;
+
+			let velocity = &mut self.velocities[body];
+			let angular_velocity = &mut self.angular_velocities[body];
+			*velocity += acceleration * duration;
+			*angular_velocity += angular_acceleration * duration;
+			if settings.gyroscopic_torque == GyroscopicTorque::Implicit {
+				*angular_velocity =
+					implicit_gyroscopic_velocity(transform, inverse_inertia_tensor, *angular_velocity, duration);
+			}
+			*velocity *= powf(settings.linear_damping, duration);
+			*angular_velocity *= powf(settings.angular_damping, duration);
+			*angular_velocity = clamp_angular_velocity(*angular_velocity, settings.max_angular_velocity);
+		}
+	}
+
+	/// Moves and rotates the kinematic bodies and those moved by forces by
+	/// their velocities, updating their transforms and clearing their forces.
+	fn advance(&mut self, duration: Real) {
+		for body in 0..self.len() {
+			if self.flags[body].kind != BodyKind::Kinematic && !self.is_accelerated(body) {
+				continue;
+			}
+			self.positions[body] += self.velocities[body] * duration;
+			self.orientations[body].add_scaled_vector(self.angular_velocities[body], duration);
+			self.derive(body);
+			self.forces[body] = Vector3::zero();
+			self.torques[body] = Vector3::zero();
+		}
+	}
+
+	/// Updates the motion of the bodies moved by forces that may sleep,
+	/// putting to sleep those that have settled.
+	fn settle(&mut self, duration: Real, sleep_epsilon: Real) {
+		for body in 0..self.len() {
+			if !self.flags[body].can_sleep || !self.is_accelerated(body) {
+				continue;
+			}
+			let velocities = [self.velocities[body], self.angular_velocities[body]];
+			let (motion, settled) = settle(self.motions[body], velocities, duration, sleep_epsilon);
+			self.motions[body] = motion;
+			if settled {
+				self.set_awake(body, false);
+			}
+		}
+	}
+}
+
+impl Clone for BodySet {
+	fn clone(&self) -> Self {
+		let mut set = Self::default();
+		set.clone_from(self);
+		set
+	}
+
+	/// Copies each column into the storage already held, so that a set
+	/// saved into again and again stops allocating once it has grown.
+	fn clone_from(&mut self, source: &Self) {
+		self.positions.clone_from(&source.positions);
+		self.orientations.clone_from(&source.orientations);
+		self.velocities.clone_from(&source.velocities);
+		self.angular_velocities.clone_from(&source.angular_velocities);
+		self.inverse_masses.clone_from(&source.inverse_masses);
+		self.inverse_inertia_tensors.clone_from(&source.inverse_inertia_tensors);
+		self.inverse_inertia_tensors_world
+			.clone_from(&source.inverse_inertia_tensors_world);
+		self.transforms.clone_from(&source.transforms);
+		self.forces.clone_from(&source.forces);
+		self.torques.clone_from(&source.torques);
+		self.motions.clone_from(&source.motions);
+		self.flags.clone_from(&source.flags);
+		self.settings.clone_from(&source.settings);
+		self.vacant.clone_from(&source.vacant);
+		self.free.clone_from(&source.free);
+	}
+}
+
+impl BodyTransforms for BodySet {
+	fn transform(&self, body: usize) -> &Matrix4 {
+		&self.transforms[body]
+	}
+}
+
+impl FromIterator<RigidBody> for BodySet {
+	fn from_iter<I: IntoIterator<Item = RigidBody>>(bodies: I) -> Self {
+		let mut set = Self::default();
+		for body in bodies {
+			set.push(&body);
+		}
+		set
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{body::RigidBodyBuilder, shape::Shape, world::DEFAULT_GRAVITY};
+
+	use super::*;
+
+	fn body(position: Vector3) -> RigidBody {
+		RigidBodyBuilder::new(Shape::default())
+			.position(position)
+			.angular_velocity(Vector3::new(0.0, 2.0, 1.0))
+			.damping(0.9, 0.8)
+			.user_data(7)
+			.build()
+	}
+
+	#[test]
+	pub fn bodies_come_back_out_whole() {
+		let mut set = BodySet::new();
+		let whole = body(Vector3::new(1.0, 2.0, 3.0));
+		let handle = set.push(&whole);
+		let copy = set.get(handle);
+		assert_eq!(copy.position, whole.position);
+		assert_eq!(copy.angular_velocity, whole.angular_velocity);
+		assert_eq!(copy.inverse_inertia_tensor, whole.inverse_inertia_tensor);
+		assert_eq!(copy.transform_matrix, whole.transform_matrix);
+		assert_eq!(copy.user_data, 7);
+		set.update(handle, |body| body.kind = BodyKind::Kinematic);
+		assert_eq!(set.flags()[handle].kind, BodyKind::Kinematic);
+	}
+
+	#[test]
+	pub fn removed_rows_are_reused() {
+		let mut set: BodySet = [Vector3::zero(), Vector3::x_axis(), Vector3::y_axis()].map(body).into_iter().collect();
+		assert_eq!(set.remove(1).position, Vector3::x_axis());
+		assert_eq!(set.remove(0).position, Vector3::zero());
+		assert!(!set.contains(0) && !set.contains(1) && set.contains(2));
+		assert_eq!(set.flags()[1].kind, BodyKind::Static);
+
+		// The last row vacated is given out first, and no row is added
+		assert_eq!(set.push(&body(Vector3::z_axis())), 0);
+		assert_eq!(set.push(&body(Vector3::z_axis())), 1);
+		assert_eq!(set.push(&body(Vector3::z_axis())), 3);
+		assert_eq!(set.get(0).position, Vector3::z_axis());
+		assert!((0..4).all(|body| set.contains(body)));
+	}
+
+	#[test]
+	pub fn columns_integrate_as_whole_bodies_do() {
+		let mut bodies = vec![body(Vector3::zero()); 6];
+		bodies[1].kind = BodyKind::Static;
+		bodies[2].kind = BodyKind::Kinematic;
+		bodies[3].gyroscopic_torque = GyroscopicTorque::Explicit;
+		bodies[3].max_angular_velocity = Some(1.5);
+		bodies[4].gyroscopic_torque = GyroscopicTorque::Implicit;
+		bodies[5].angular_velocity = Vector3::zero();
+		bodies[5].gravity_scale = 0.0;
+		let mut set: BodySet = bodies.iter().copied().collect();
+		for _ in 0..20 {
+			set.clear_accumulators();
+			set.calculate_derived_data();
+			set.apply_gravity(DEFAULT_GRAVITY);
+			set.integrate(0.1, 0.3);
+			for body in &mut bodies {
+				body.clear_accumulators();
+				body.calculate_derived_data();
+				if body.kind == BodyKind::Dynamic && !body.sleeping {
+					body.force_accumulator += body.gravity(DEFAULT_GRAVITY) * body.mass();
+				}
+				body.integrate_with_sleep_epsilon(0.1, 0.3);
+			}
+		}
+		assert!(bodies[5].sleeping);
+		for (handle, body) in bodies.iter().enumerate() {
+			let copy = set.get(handle);
+			assert_eq!(copy.position, body.position);
+			assert_eq!(copy.orientation, body.orientation);
+			assert_eq!(copy.velocity, body.velocity);
+			assert_eq!(copy.angular_velocity, body.angular_velocity);
+			assert_eq!(copy.transform_matrix, body.transform_matrix);
+			assert_eq!(copy.motion.to_bits(), body.motion.to_bits());
+			assert_eq!(copy.sleeping, body.sleeping);
+		}
+	}
+}
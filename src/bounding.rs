@@ -0,0 +1,278 @@
+use std::f32::consts::PI;
+
+use crate::{vec::Vector3, Real};
+
+/// A volume enclosing one or more objects, used to quickly rule out
+/// pairs of objects that cannot be touching.
+pub trait BoundingVolume: Copy {
+	/// Returns true if this volume overlaps the other.
+	fn overlaps(&self, other: &Self) -> bool;
+
+	/// Returns the smallest volume of this kind enclosing both volumes.
+	#[must_use]
+	fn merge(&self, other: &Self) -> Self;
+
+	/// Returns a measure of the size of the volume. Hierarchies try to
+	/// keep this small, so that fewer volumes overlap.
+	fn size(&self) -> Real;
+
+	/// Returns the distance along the ray at which it enters the volume,
+	/// or `None` if it misses or only enters beyond the given distance.
+	/// A ray starting inside the volume enters it at a distance of zero.
+	fn ray_distance(&self, ray: &Ray, max_distance: Real) -> Option<Real>;
+
+	/// Returns how much this volume would grow if the other were added to it.
+	fn growth(&self, other: &Self) -> Real {
+		self.merge(other).size() - self.size()
+	}
+}
+
+/// A ray, starting at an origin and travelling in a direction of unit length.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Ray {
+	pub origin: Vector3,
+	pub direction: Vector3,
+}
+
+impl Ray {
+	/// Creates a ray from the given origin in the given direction, which is normalized.
+	#[must_use]
+	pub fn new(origin: Vector3, direction: Vector3) -> Self {
+		Self {
+			origin,
+			direction: direction.normalize(),
+		}
+	}
+
+	/// Returns the point the given distance along the ray.
+	#[must_use]
+	pub fn point_at(&self, distance: Real) -> Vector3 {
+		self.origin + self.direction * distance
+	}
+}
+
+/// A bounding sphere, the cheapest volume to test for overlap.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+	pub center: Vector3,
+	pub radius: Real,
+}
+
+impl BoundingSphere {
+	#[must_use]
+	pub const fn new(center: Vector3, radius: Real) -> Self {
+		Self { center, radius }
+	}
+}
+
+impl BoundingVolume for BoundingSphere {
+	fn overlaps(&self, other: &Self) -> bool {
+		let distance_squared = (self.center - other.center).magnitude_squared();
+		let radii = self.radius + other.radius;
+		distance_squared < radii * radii
+	}
+
+	fn merge(&self, other: &Self) -> Self {
+		let offset = other.center - self.center;
+		let distance_squared = offset.magnitude_squared();
+		let radius_difference = other.radius - self.radius;
+
+		// If one sphere already encloses the other, it is the result
+		if radius_difference * radius_difference >= distance_squared {
+			return if self.radius > other.radius { *self } else { *other };
+		}
+
+		let distance = distance_squared.sqrt();
+		let radius = (distance + self.radius + other.radius) * 0.5;
+		Self::new(self.center + offset * ((radius - self.radius) / distance), radius)
+	}
+
+	fn ray_distance(&self, ray: &Ray, max_distance: Real) -> Option<Real> {
+		let offset = ray.origin - self.center;
+		let projection = offset.dot(&ray.direction);
+		let excess = self.radius.mul_add(-self.radius, offset.magnitude_squared());
+
+		// The ray starts outside the sphere and points away from it
+		if excess > 0.0 && projection > 0.0 {
+			return None;
+		}
+		let discriminant = projection.mul_add(projection, -excess);
+		if discriminant < 0.0 {
+			return None;
+		}
+		let distance = (-projection - discriminant.sqrt()).max(0.0);
+		(distance <= max_distance).then_some(distance)
+	}
+
+	/// Returns the volume of the sphere.
+	fn size(&self) -> Real {
+		4.0 / 3.0 * PI * self.radius.powi(3)
+	}
+}
+
+/// An axis-aligned bounding box, which fits elongated objects more
+/// tightly than a sphere.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Aabb {
+	pub min: Vector3,
+	pub max: Vector3,
+}
+
+impl Aabb {
+	#[must_use]
+	pub const fn new(min: Vector3, max: Vector3) -> Self {
+		Self { min, max }
+	}
+
+	/// Creates a box with the given center and half its size along each axis.
+	#[must_use]
+	pub fn from_center(center: Vector3, half_size: Vector3) -> Self {
+		Self::new(center - half_size, center + half_size)
+	}
+
+	#[must_use]
+	pub fn center(&self) -> Vector3 {
+		(self.min + self.max) * 0.5
+	}
+
+	/// Returns half the size of the box along each axis.
+	#[must_use]
+	pub fn half_size(&self) -> Vector3 {
+		(self.max - self.min) * 0.5
+	}
+
+	/// Returns the point in or on the box closest to the given point.
+	#[must_use]
+	pub fn closest_point(&self, point: Vector3) -> Vector3 {
+		let mut closest = point;
+		for axis in 0..3 {
+			closest[axis] = point[axis].clamp(self.min[axis], self.max[axis]);
+		}
+		closest
+	}
+
+	/// Returns true if the given point is inside the box or on its surface.
+	#[must_use]
+	pub fn contains_point(&self, point: Vector3) -> bool {
+		(0..3).all(|axis| point[axis] >= self.min[axis] && point[axis] <= self.max[axis])
+	}
+}
+
+impl BoundingVolume for Aabb {
+	fn overlaps(&self, other: &Self) -> bool {
+		(0..3).all(|axis| self.min[axis] <= other.max[axis] && other.min[axis] <= self.max[axis])
+	}
+
+	fn merge(&self, other: &Self) -> Self {
+		let mut merged = *self;
+		for axis in 0..3 {
+			merged.min[axis] = self.min[axis].min(other.min[axis]);
+			merged.max[axis] = self.max[axis].max(other.max[axis]);
+		}
+		merged
+	}
+
+	fn ray_distance(&self, ray: &Ray, max_distance: Real) -> Option<Real> {
+		let mut entry: Real = 0.0;
+		let mut exit = max_distance;
+		for axis in 0..3 {
+			let (origin, direction) = (ray.origin[axis], ray.direction[axis]);
+			if direction.abs() < Real::EPSILON {
+				// Parallel to the slab, so the ray must already be within it
+				if origin < self.min[axis] || origin > self.max[axis] {
+					return None;
+				}
+				continue;
+			}
+			let inverse = direction.recip();
+			let near = (self.min[axis] - origin) * inverse;
+			let far = (self.max[axis] - origin) * inverse;
+			entry = entry.max(near.min(far));
+			exit = exit.min(near.max(far));
+			if entry > exit {
+				return None;
+			}
+		}
+		Some(entry)
+	}
+
+	/// Returns the surface area of the box, which unlike its volume
+	/// still grows when a flat box is extended along its plane.
+	fn size(&self) -> Real {
+		let size = self.max - self.min;
+		2.0 * size
+			.z()
+			.mul_add(size.x(), size.x().mul_add(size.y(), size.y() * size.z()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::assert_equal;
+
+	use super::*;
+
+	#[test]
+	pub fn sphere_overlap_and_merge() {
+		let first = BoundingSphere::new(Vector3::zero(), 1.0);
+		let second = BoundingSphere::new(Vector3::new(3.0, 0.0, 0.0), 1.0);
+		assert!(!first.overlaps(&second));
+		assert!(first.overlaps(&BoundingSphere::new(Vector3::new(1.5, 0.0, 0.0), 1.0)));
+
+		let merged = first.merge(&second);
+		assert_eq!(merged, BoundingSphere::new(Vector3::new(1.5, 0.0, 0.0), 2.5));
+
+		let inner = BoundingSphere::new(Vector3::new(0.5, 0.0, 0.0), 0.25);
+		assert_eq!(first.merge(&inner), first);
+		assert_eq!(inner.merge(&first), first);
+	}
+
+	#[test]
+	pub fn aabb_overlap_and_merge() {
+		let first = Aabb::from_center(Vector3::zero(), Vector3::new(1.0, 1.0, 1.0));
+		let second = Aabb::from_center(Vector3::new(3.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0));
+		assert!(!first.overlaps(&second));
+		assert!(first.overlaps(&Aabb::from_center(
+			Vector3::new(2.0, 0.0, 0.0),
+			Vector3::new(1.0, 1.0, 1.0)
+		)));
+
+		let merged = first.merge(&second);
+		assert_eq!(
+			merged,
+			Aabb::new(Vector3::new(-1.0, -1.0, -1.0), Vector3::new(4.0, 1.0, 1.0))
+		);
+		assert_eq!(merged.center(), Vector3::new(1.5, 0.0, 0.0));
+		assert!(merged.contains_point(Vector3::new(4.0, 0.0, 0.0)));
+		assert_eq!(
+			first.closest_point(Vector3::new(3.0, 0.5, -2.0)),
+			Vector3::new(1.0, 0.5, -1.0)
+		);
+		assert_equal(first.size(), 24.0);
+		assert_equal(first.growth(&second), 24.0);
+	}
+
+	#[test]
+	pub fn ray_distance() {
+		let ray = Ray::new(Vector3::new(-5.0, 0.0, 0.0), Vector3::new(2.0, 0.0, 0.0));
+		assert_eq!(ray.point_at(2.0), Vector3::new(-3.0, 0.0, 0.0));
+
+		let sphere = BoundingSphere::new(Vector3::zero(), 1.0);
+		assert_eq!(sphere.ray_distance(&ray, 10.0), Some(4.0));
+		assert_eq!(sphere.ray_distance(&ray, 3.0), None);
+		let behind = Ray::new(Vector3::new(5.0, 0.0, 0.0), Vector3::x_axis());
+		assert_eq!(sphere.ray_distance(&behind, 10.0), None);
+		let inside = Ray::new(Vector3::zero(), Vector3::y_axis());
+		assert_eq!(sphere.ray_distance(&inside, 10.0), Some(0.0));
+
+		let aabb = Aabb::from_center(Vector3::new(0.0, 0.5, 0.0), Vector3::new(1.0, 1.0, 1.0));
+		assert_eq!(aabb.ray_distance(&ray, 10.0), Some(4.0));
+		assert_eq!(aabb.ray_distance(&ray, 3.0), None);
+		let above = Ray::new(Vector3::new(-5.0, 2.0, 0.0), Vector3::x_axis());
+		assert_eq!(aabb.ray_distance(&above, 10.0), None);
+		let diagonal = Ray::new(Vector3::new(-3.0, -2.5, 0.0), Vector3::new(1.0, 1.0, 0.0));
+		assert!(aabb
+			.ray_distance(&diagonal, 10.0)
+			.is_some_and(|distance| (distance - 8.0_f32.sqrt()).abs() < 1e-5));
+	}
+}
@@ -0,0 +1,558 @@
+use crate::{
+	bounding::{Aabb, Ray},
+	bvh::{Bvh, PotentialContact, RayCandidate},
+	hash_grid::HashGrid,
+	shape::Shape,
+	Real,
+};
+
+/// Puts the given pairs into a stable order and removes duplicates.
+///
+/// Each pair is written with the lower body index first, and the pairs are
+/// sorted by those indices, so the same scene always produces the same list
+/// whatever order the broad phase found the pairs in.
+pub fn sort_pairs(contacts: &mut Vec<PotentialContact>) {
+	for contact in contacts.iter_mut() {
+		contact.bodies.sort_unstable();
+	}
+	contacts.sort_unstable();
+	contacts.dedup();
+}
+
+/// A run of potential contacts between bodies of the same pair of shape types.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ContactBatch {
+	/// Holds the type indices of the shapes, lowest first.
+	pub shape_types: [usize; 2],
+
+	/// Holds the contacts, each with the body of the first shape type first.
+	pub contacts: Vec<PotentialContact>,
+}
+
+/// Returns the type indices of the shapes of the pair, lowest first, given
+/// the type index of each body's shape.
+fn shape_types(contact: &PotentialContact, shape_type: &impl Fn(usize) -> usize) -> [usize; 2] {
+	let mut types = contact.bodies.map(shape_type);
+	types.sort_unstable();
+	types
+}
+
+/// Sorts the given pairs into runs of the same pair of shape types, given
+/// the type index of each body's shape, so that the narrow phase can run
+/// each detector over a contiguous run.
+///
+/// Each pair is written with the body of the lower shape type first. The
+/// runs are ordered by shape types, and the pairs within a run by their
+/// indices, so the same pairs always end up in the same order.
+pub fn sort_by_shape(contacts: &mut [PotentialContact], shape_type: impl Fn(usize) -> usize) {
+	for contact in contacts.iter_mut() {
+		if shape_type(contact.bodies[0]) > shape_type(contact.bodies[1]) {
+			contact.bodies.reverse();
+		}
+	}
+	contacts.sort_unstable_by_key(|contact| (shape_types(contact, &shape_type), *contact));
+}
+
+/// Returns the runs of pairs sorted by [`sort_by_shape`], each with the
+/// type indices of its shapes, lowest first.
+pub fn shape_batches<'a>(
+	contacts: &'a [PotentialContact],
+	shape_type: impl Fn(usize) -> usize + Copy + 'a,
+) -> impl Iterator<Item = ([usize; 2], &'a [PotentialContact])> + 'a {
+	contacts
+		.chunk_by(move |first, second| shape_types(first, &shape_type) == shape_types(second, &shape_type))
+		.map(move |run| (shape_types(&run[0], &shape_type), run))
+}
+
+/// Groups the given pairs into batches by the types of the bodies' shapes,
+/// as [`sort_by_shape`] orders them.
+#[must_use]
+pub fn batch_by_shape(contacts: &[PotentialContact], shapes: &[Shape]) -> Vec<ContactBatch> {
+	let shape_type = |body: usize| shapes[body].type_index();
+	let mut sorted = contacts.to_vec();
+	sort_by_shape(&mut sorted, shape_type);
+	shape_batches(&sorted, shape_type)
+		.map(|(shape_types, contacts)| ContactBatch {
+			shape_types,
+			contacts: contacts.to_vec(),
+		})
+		.collect()
+}
+
+/// Which collision groups a body belongs to, and which groups it collides with.
+///
+/// Two bodies are only paired if each is a member of a group the other's
+/// filter accepts. For example, player projectiles can leave the player's
+/// group out of their filter, and debris can leave its own group out of
+/// its filter so that it only collides with everything else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct CollisionGroups {
+	/// Holds the bits of the groups the body belongs to.
+	pub memberships: u32,
+
+	/// Holds the bits of the groups the body collides with.
+	pub filter: u32,
+}
+
+impl Default for CollisionGroups {
+	/// Bodies are members of every group and collide with every group by default.
+	fn default() -> Self {
+		Self::new(u32::MAX, u32::MAX)
+	}
+}
+
+impl CollisionGroups {
+	#[must_use]
+	pub const fn new(memberships: u32, filter: u32) -> Self {
+		Self { memberships, filter }
+	}
+
+	/// Returns true if bodies with these groups and the other groups may collide.
+	#[must_use]
+	pub const fn interacts_with(&self, other: &Self) -> bool {
+		self.memberships & other.filter != 0 && other.memberships & self.filter != 0
+	}
+}
+
+/// A hook that decides whether a pair of bodies that may be in contact
+/// should be passed to the narrow phase.
+///
+/// Filters express rules that collision groups cannot, such as a ragdoll
+/// ignoring the character that spawned it for a moment. A filter is asked
+/// about each pair after the collision groups have accepted it. Closures
+/// taking the pair of body indices are filters.
+pub trait ContactFilter {
+	/// Returns true if the given bodies should be tested for contact.
+	fn accepts(&self, bodies: [usize; 2]) -> bool;
+}
+
+impl<F: Fn([usize; 2]) -> bool> ContactFilter for F {
+	fn accepts(&self, bodies: [usize; 2]) -> bool {
+		self(bodies)
+	}
+}
+
+/// A filter that rejects chosen pairs of bodies for a limited time.
+#[derive(Debug, Default, Clone)]
+pub struct PairExclusions {
+	/// Holds each excluded pair, lower index first, with the time left to exclude it.
+	exclusions: Vec<([usize; 2], Real)>,
+}
+
+impl PairExclusions {
+	/// Excludes the pair of bodies for the given duration, replacing any
+	/// time already left on it.
+	pub fn exclude(&mut self, bodies: [usize; 2], duration: Real) {
+		let pair = ordered(bodies);
+		self.exclusions.retain(|(excluded, _)| *excluded != pair);
+		self.exclusions.push((pair, duration));
+	}
+
+	/// Counts down the time left on each exclusion, dropping those that have run out.
+	pub fn advance(&mut self, duration: Real) {
+		for (_, remaining) in &mut self.exclusions {
+			*remaining -= duration;
+		}
+		self.exclusions.retain(|(_, remaining)| *remaining > 0.0);
+	}
+
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.exclusions.is_empty()
+	}
+}
+
+impl ContactFilter for PairExclusions {
+	fn accepts(&self, bodies: [usize; 2]) -> bool {
+		let pair = ordered(bodies);
+		self.exclusions.iter().all(|(excluded, _)| *excluded != pair)
+	}
+}
+
+const fn ordered(bodies: [usize; 2]) -> [usize; 2] {
+	if bodies[1] < bodies[0] {
+		[bodies[1], bodies[0]]
+	} else {
+		bodies
+	}
+}
+
+/// A broad phase, which finds the pairs of bodies that may be in contact
+/// so that only those pairs need to be passed to the narrow phase.
+///
+/// Every broad phase is given each body's bounding box, and bodies are
+/// identified by their index, so the implementations can be swapped
+/// without changing the code that drives them.
+pub trait BroadPhase {
+	/// Adds the given body with the given bounding box, replacing it if it is already present.
+	fn insert(&mut self, body: usize, bounds: Aabb);
+
+	/// Removes the given body, returning true if it was present.
+	fn remove(&mut self, body: usize) -> bool;
+
+	/// Moves the given body to its new bounding box.
+	fn update(&mut self, body: usize, bounds: Aabb);
+
+	/// Sets the collision groups of the given body.
+	fn set_groups(&mut self, body: usize, groups: CollisionGroups);
+
+	/// Returns every pair of bodies whose bounds overlap and whose groups
+	/// interact, once each and in the order given by [`sort_pairs`].
+	fn potential_contacts(&self) -> Vec<PotentialContact>;
+
+	/// Replaces the contents of the given list with the same pairs as
+	/// [`Self::potential_contacts`]. Broad phases that can write the pairs
+	/// straight into the list do, keeping any other storage the search needs,
+	/// so that searching step after step allocates nothing once warm. With
+	/// the `rayon` feature they search on several threads, joining the
+	/// pairs in a fixed order.
+	fn potential_contacts_into(&mut self, contacts: &mut Vec<PotentialContact>) {
+		contacts.clear();
+		contacts.extend(self.potential_contacts());
+	}
+
+	/// Returns the potential contacts that the given filter accepts.
+	fn filtered_potential_contacts(&self, filter: &dyn ContactFilter) -> Vec<PotentialContact> {
+		let mut contacts = self.potential_contacts();
+		contacts.retain(|contact| filter.accepts(contact.bodies));
+		contacts
+	}
+
+	/// Returns the bodies whose bounds overlap the given box and whose
+	/// groups interact with the given groups.
+	fn query(&self, bounds: &Aabb, groups: CollisionGroups) -> Vec<usize>;
+
+	/// Replaces the contents of the given list with the same bodies as
+	/// [`Self::query`]. Broad phases that can write the bodies straight into
+	/// the list do, so that its storage is reused from query to query.
+	fn query_into(&self, bounds: &Aabb, groups: CollisionGroups, bodies: &mut Vec<usize>) {
+		bodies.clear();
+		bodies.extend(self.query(bounds, groups));
+	}
+
+	/// Returns the bodies whose bounds the ray enters within the given distance
+	/// and whose groups interact with the given groups, nearest first. These
+	/// are the only bodies that need exact ray tests.
+	fn ray_cast(&self, ray: &Ray, max_distance: Real, groups: CollisionGroups) -> Vec<RayCandidate>;
+}
+
+/// A broad phase backed by a bounding volume hierarchy of boxes.
+///
+/// This suits scenes with bodies of very different sizes.
+#[derive(Debug, Default, Clone)]
+pub struct BvhBroadPhase {
+	pub bvh: Bvh<Aabb>,
+
+	/// Holds the leaf handle of each body, by body index.
+	handles: Vec<Option<usize>>,
+
+	/// Holds the collision groups of each body, by body index.
+	groups: Vec<CollisionGroups>,
+
+	/// Holds the storage the search for pairs on several threads reuses.
+	#[cfg(feature = "rayon")]
+	search: crate::bvh::ContactSearch,
+}
+
+impl BvhBroadPhase {
+	fn groups(&self, body: usize) -> CollisionGroups {
+		self.groups.get(body).copied().unwrap_or_default()
+	}
+
+	/// Drops the pairs whose groups do not interact and puts the rest in order.
+	fn finish_pairs(&self, contacts: &mut Vec<PotentialContact>) {
+		contacts.retain(|contact| {
+			let [first, second] = contact.bodies;
+			self.groups(first).interacts_with(&self.groups(second))
+		});
+		sort_pairs(contacts);
+	}
+}
+
+impl BroadPhase for BvhBroadPhase {
+	fn insert(&mut self, body: usize, bounds: Aabb) {
+		self.remove(body);
+		if self.handles.len() <= body {
+			self.handles.resize(body + 1, None);
+		}
+		self.handles[body] = Some(self.bvh.insert(body, bounds));
+	}
+
+	fn remove(&mut self, body: usize) -> bool {
+		let Some(handle) = self.handles.get_mut(body).and_then(Option::take) else {
+			return false;
+		};
+		self.bvh.remove(handle).is_some()
+	}
+
+	fn update(&mut self, body: usize, bounds: Aabb) {
+		match self.handles.get(body).copied().flatten() {
+			Some(handle) => self.bvh.refit(handle, bounds),
+			None => self.insert(body, bounds),
+		}
+	}
+
+	fn set_groups(&mut self, body: usize, groups: CollisionGroups) {
+		if self.groups.len() <= body {
+			self.groups.resize(body + 1, CollisionGroups::default());
+		}
+		self.groups[body] = groups;
+	}
+
+	fn potential_contacts(&self) -> Vec<PotentialContact> {
+		#[cfg(feature = "rayon")]
+		let mut contacts = self.bvh.par_potential_contacts();
+		#[cfg(not(feature = "rayon"))]
+		let mut contacts = self.bvh.potential_contacts();
+		self.finish_pairs(&mut contacts);
+		contacts
+	}
+
+	fn potential_contacts_into(&mut self, contacts: &mut Vec<PotentialContact>) {
+		#[cfg(feature = "rayon")]
+		self.bvh.par_potential_contacts_into(&mut self.search, contacts);
+		#[cfg(not(feature = "rayon"))]
+		self.bvh.potential_contacts_into(contacts);
+		self.finish_pairs(contacts);
+	}
+
+	fn query(&self, bounds: &Aabb, groups: CollisionGroups) -> Vec<usize> {
+		let mut bodies = Vec::new();
+		self.query_into(bounds, groups, &mut bodies);
+		bodies
+	}
+
+	fn query_into(&self, bounds: &Aabb, groups: CollisionGroups, bodies: &mut Vec<usize>) {
+		self.bvh.query_into(bounds, bodies);
+		bodies.retain(|body| self.groups(*body).interacts_with(&groups));
+	}
+
+	fn ray_cast(&self, ray: &Ray, max_distance: Real, groups: CollisionGroups) -> Vec<RayCandidate> {
+		let mut candidates = self.bvh.ray_cast(ray, max_distance);
+		candidates.retain(|candidate| self.groups(candidate.body).interacts_with(&groups));
+		candidates
+	}
+}
+
+/// The grid holds each body as the sphere enclosing its bounding box.
+///
+/// This suits scenes with many bodies of about the cell size.
+impl BroadPhase for HashGrid {
+	fn insert(&mut self, body: usize, bounds: Aabb) {
+		Self::insert(self, body, bounds.center(), bounds.half_size().magnitude());
+	}
+
+	fn remove(&mut self, body: usize) -> bool {
+		Self::remove(self, body)
+	}
+
+	fn update(&mut self, body: usize, bounds: Aabb) {
+		Self::update(self, body, bounds.center(), bounds.half_size().magnitude());
+	}
+
+	fn set_groups(&mut self, body: usize, groups: CollisionGroups) {
+		Self::set_groups(self, body, groups);
+	}
+
+	fn potential_contacts(&self) -> Vec<PotentialContact> {
+		#[cfg(feature = "rayon")]
+		let mut contacts = Self::par_potential_contacts(self);
+		#[cfg(not(feature = "rayon"))]
+		let mut contacts = Self::potential_contacts(self);
+		sort_pairs(&mut contacts);
+		contacts
+	}
+
+	fn potential_contacts_into(&mut self, contacts: &mut Vec<PotentialContact>) {
+		#[cfg(feature = "rayon")]
+		{
+			let mut found = std::mem::take(&mut self.found);
+			Self::par_potential_contacts_into(self, &mut found, contacts);
+			self.found = found;
+		}
+		#[cfg(not(feature = "rayon"))]
+		Self::potential_contacts_into(self, contacts);
+		sort_pairs(contacts);
+	}
+
+	fn query(&self, bounds: &Aabb, groups: CollisionGroups) -> Vec<usize> {
+		Self::query(self, bounds, groups)
+	}
+
+	fn query_into(&self, bounds: &Aabb, groups: CollisionGroups, bodies: &mut Vec<usize>) {
+		Self::query_into(self, bounds, groups, bodies);
+	}
+
+	fn ray_cast(&self, ray: &Ray, max_distance: Real, groups: CollisionGroups) -> Vec<RayCandidate> {
+		Self::ray_cast(self, ray, max_distance, groups)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{real_from_count, vec::Vector3};
+
+	use super::*;
+
+	fn box_at(x: f32) -> Aabb {
+		Aabb::from_center(Vector3::new(x, 0.0, 0.0), Vector3::new(0.5, 0.5, 0.5))
+	}
+
+	fn sorted_pairs(broad_phase: &dyn BroadPhase) -> Vec<[usize; 2]> {
+		let mut pairs: Vec<[usize; 2]> = broad_phase
+			.potential_contacts()
+			.iter()
+			.map(|contact| {
+				let [first, second] = contact.bodies;
+				[first.min(second), first.max(second)]
+			})
+			.collect();
+		pairs.sort_unstable();
+		pairs
+	}
+
+	fn exercise(broad_phase: &mut dyn BroadPhase) {
+		for (body, x) in [0.0, 0.8, 5.0, 20.0].into_iter().enumerate() {
+			broad_phase.insert(body, box_at(x));
+		}
+		assert_eq!(sorted_pairs(broad_phase), vec![[0, 1]]);
+
+		broad_phase.update(3, box_at(5.5));
+		assert_eq!(sorted_pairs(broad_phase), vec![[0, 1], [2, 3]]);
+
+		assert!(broad_phase.remove(0));
+		assert!(!broad_phase.remove(0));
+		assert_eq!(sorted_pairs(broad_phase), vec![[2, 3]]);
+
+		let mut bodies = broad_phase.query(&box_at(1.5), CollisionGroups::default());
+		bodies.sort_unstable();
+		assert_eq!(bodies, vec![1]);
+
+		let ray = Ray::new(Vector3::new(-10.0, 0.0, 0.0), Vector3::x_axis());
+		let bodies: Vec<usize> = broad_phase
+			.ray_cast(&ray, 100.0, CollisionGroups::default())
+			.iter()
+			.map(|candidate| candidate.body)
+			.collect();
+		assert_eq!(bodies, vec![1, 2, 3]);
+	}
+
+	fn filter(broad_phase: &mut dyn BroadPhase) {
+		const PLAYER: u32 = 1;
+		const PROJECTILE: u32 = 2;
+		const DEBRIS: u32 = 4;
+		let groups = [
+			CollisionGroups::new(PLAYER, u32::MAX),
+			CollisionGroups::new(PROJECTILE, !PLAYER),
+			CollisionGroups::new(DEBRIS, !DEBRIS),
+			CollisionGroups::new(DEBRIS, !DEBRIS),
+		];
+		for (body, groups) in groups.into_iter().enumerate() {
+			broad_phase.set_groups(body, groups);
+			broad_phase.insert(body, box_at(0.1 * real_from_count(body)));
+		}
+		assert_eq!(sorted_pairs(broad_phase), vec![[0, 2], [0, 3], [1, 2], [1, 3]]);
+
+		let mut bodies = broad_phase.query(&box_at(0.0), CollisionGroups::new(PROJECTILE, !PLAYER));
+		bodies.sort_unstable();
+		assert_eq!(bodies, vec![1, 2, 3]);
+
+		let ray = Ray::new(Vector3::new(-10.0, 0.0, 0.0), Vector3::x_axis());
+		let hitscan = broad_phase.ray_cast(&ray, 100.0, CollisionGroups::new(PROJECTILE, !PLAYER));
+		assert!(hitscan.iter().all(|candidate| candidate.body != 0));
+	}
+
+	#[test]
+	pub fn implementations_are_interchangeable() {
+		let mut broad_phases: Vec<Box<dyn BroadPhase>> =
+			vec![Box::new(BvhBroadPhase::default()), Box::new(HashGrid::new(2.0))];
+		for broad_phase in &mut broad_phases {
+			exercise(broad_phase.as_mut());
+		}
+	}
+
+	#[test]
+	pub fn collision_groups_filter_pairs_and_queries() {
+		assert!(CollisionGroups::default().interacts_with(&CollisionGroups::default()));
+		let mut broad_phases: Vec<Box<dyn BroadPhase>> =
+			vec![Box::new(BvhBroadPhase::default()), Box::new(HashGrid::new(2.0))];
+		for broad_phase in &mut broad_phases {
+			filter(broad_phase.as_mut());
+		}
+	}
+
+	#[test]
+	pub fn contact_filters_reject_pairs() {
+		let mut broad_phase = BvhBroadPhase::default();
+		for (body, x) in [0.0, 0.5, 1.0].into_iter().enumerate() {
+			broad_phase.insert(body, box_at(x));
+		}
+		let pairs = |filter: &dyn ContactFilter| -> Vec<[usize; 2]> {
+			broad_phase
+				.filtered_potential_contacts(filter)
+				.iter()
+				.map(|contact| contact.bodies)
+				.collect()
+		};
+		assert_eq!(pairs(&|bodies: [usize; 2]| bodies[0] != 0), vec![[1, 2]]);
+
+		// The ragdoll, body 1, ignores the character that spawned it, body 0, for half a second
+		let mut exclusions = PairExclusions::default();
+		exclusions.exclude([1, 0], 0.5);
+		assert_eq!(pairs(&exclusions), vec![[0, 2], [1, 2]]);
+		exclusions.advance(0.3);
+		assert_eq!(pairs(&exclusions), vec![[0, 2], [1, 2]]);
+		exclusions.advance(0.3);
+		assert!(exclusions.is_empty());
+		assert_eq!(pairs(&exclusions), vec![[0, 1], [0, 2], [1, 2]]);
+	}
+
+	#[test]
+	pub fn pairs_are_sorted_and_unique() {
+		let mut contacts = [[3, 1], [0, 2], [1, 3], [0, 1]]
+			.into_iter()
+			.map(|bodies| PotentialContact { bodies })
+			.collect();
+		sort_pairs(&mut contacts);
+		let pairs: Vec<[usize; 2]> = contacts.iter().map(|contact| contact.bodies).collect();
+		assert_eq!(pairs, vec![[0, 1], [0, 2], [1, 3]]);
+
+		let mut broad_phase = BvhBroadPhase::default();
+		for body in (0..6).rev() {
+			broad_phase.insert(body, box_at(0.3 * real_from_count(body)));
+		}
+		let contacts = broad_phase.potential_contacts();
+		assert!(contacts.windows(2).all(|pair| pair[0] < pair[1]));
+		assert!(contacts.iter().all(|contact| contact.bodies[0] < contact.bodies[1]));
+	}
+
+	#[test]
+	pub fn batches_group_by_shape_types() {
+		let sphere = Shape::Sphere { radius: 1.0 };
+		let cuboid = Shape::default();
+		let shapes = [cuboid, sphere, cuboid, sphere];
+		let contacts: Vec<PotentialContact> = [[0, 1], [0, 2], [1, 2], [1, 3]]
+			.into_iter()
+			.map(|bodies| PotentialContact { bodies })
+			.collect();
+		let batches = batch_by_shape(&contacts, &shapes);
+		let summary: Vec<([usize; 2], Vec<[usize; 2]>)> = batches
+			.iter()
+			.map(|batch| {
+				(
+					batch.shape_types,
+					batch.contacts.iter().map(|contact| contact.bodies).collect(),
+				)
+			})
+			.collect();
+		assert_eq!(
+			summary,
+			vec![
+				([0, 0], vec![[1, 3]]),
+				([0, 1], vec![[1, 0], [1, 2]]),
+				([1, 1], vec![[0, 2]]),
+			]
+		);
+	}
+}
@@ -0,0 +1,691 @@
+use crate::{
+	bounding::{BoundingVolume, Ray},
+	Real,
+};
+
+/// A pair of bodies whose bounding volumes overlap, and which
+/// may therefore be in contact.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PotentialContact {
+	/// Holds the indices of the bodies that might be in contact.
+	pub bodies: [usize; 2],
+}
+
+/// A body whose bounding volume is hit by a ray, and which the ray
+/// may therefore hit.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RayCandidate {
+	pub body: usize,
+
+	/// Holds the distance along the ray at which it enters the body's bounding volume.
+	pub distance: Real,
+}
+
+/// Orders the given candidates from nearest to farthest.
+pub fn sort_candidates(candidates: &mut [RayCandidate]) {
+	candidates.sort_by(|first, second| {
+		first
+			.distance
+			.total_cmp(&second.distance)
+			.then(first.body.cmp(&second.body))
+	});
+}
+
+#[derive(Debug, Clone, Copy)]
+enum NodeContent {
+	/// A leaf, holding the index of its body.
+	Leaf(usize),
+
+	/// A branch, holding the indices of its two child nodes.
+	Branch([usize; 2]),
+
+	/// A node on the free list, waiting to be reused.
+	Free,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Node<V> {
+	volume: V,
+	parent: Option<usize>,
+	content: NodeContent,
+}
+
+/// A bounding volume hierarchy, used as a broad phase to find the pairs
+/// of bodies that may be in contact without testing every pair.
+///
+/// Each body is held in a leaf, and every branch holds a volume enclosing
+/// both of its children. New bodies are added beside the leaf whose volume
+/// would grow least. Inserting returns a handle to the body's leaf, which
+/// stays valid until the body is removed.
+///
+/// Bodies that move a little each step should be refitted rather than
+/// updated: refitting keeps the leaf where it is and grows or shrinks the
+/// volumes above it, rotating branches on the way up where that makes them
+/// smaller, so the hierarchy stays tight without being rebuilt.
+#[derive(Debug, Clone)]
+pub struct Bvh<V> {
+	nodes: Vec<Node<V>>,
+	free: Vec<usize>,
+	root: Option<usize>,
+	len: usize,
+}
+
+impl<V> Default for Bvh<V> {
+	fn default() -> Self {
+		Self {
+			nodes: Vec::new(),
+			free: Vec::new(),
+			root: None,
+			len: 0,
+		}
+	}
+}
+
+impl<V: BoundingVolume> Bvh<V> {
+	/// Returns the number of bodies in the hierarchy.
+	#[must_use]
+	pub const fn len(&self) -> usize {
+		self.len
+	}
+
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Returns the body held by the given leaf.
+	#[must_use]
+	pub fn body(&self, handle: usize) -> Option<usize> {
+		match self.nodes.get(handle)?.content {
+			NodeContent::Leaf(body) => Some(body),
+			_ => None,
+		}
+	}
+
+	/// Returns the bounding volume of the given leaf.
+	#[must_use]
+	pub fn volume(&self, handle: usize) -> Option<V> {
+		self.body(handle).map(|_| self.nodes[handle].volume)
+	}
+
+	/// Adds the given body with the given bounding volume,
+	/// returning the handle of its leaf.
+	pub fn insert(&mut self, body: usize, volume: V) -> usize {
+		let leaf = self.allocate(Node {
+			volume,
+			parent: None,
+			content: NodeContent::Leaf(body),
+		});
+		self.insert_leaf(leaf);
+		self.len += 1;
+		leaf
+	}
+
+	/// Removes the leaf with the given handle, returning its body.
+	pub fn remove(&mut self, handle: usize) -> Option<usize> {
+		let body = self.body(handle)?;
+		self.remove_leaf(handle);
+		self.nodes[handle].content = NodeContent::Free;
+		self.free.push(handle);
+		self.len -= 1;
+		Some(body)
+	}
+
+	/// Moves the given leaf to fit its new bounding volume. The leaf's handle is unchanged.
+	pub fn update(&mut self, handle: usize, volume: V) {
+		if self.body(handle).is_none() {
+			return;
+		}
+		self.remove_leaf(handle);
+		self.nodes[handle].volume = volume;
+		self.insert_leaf(handle);
+	}
+
+	/// Changes the bounding volume of the given leaf in place, refitting
+	/// and rotating the branches above it. The leaf's handle is unchanged.
+	pub fn refit(&mut self, handle: usize, volume: V) {
+		if self.body(handle).is_none() {
+			return;
+		}
+		self.nodes[handle].volume = volume;
+		self.refit_ancestors(self.nodes[handle].parent);
+	}
+
+	/// Returns the total size of the branch volumes, a measure of how well
+	/// the hierarchy fits its bodies. Smaller is better.
+	#[must_use]
+	pub fn cost(&self) -> Real {
+		self.nodes
+			.iter()
+			.filter(|node| matches!(node.content, NodeContent::Branch(_)))
+			.map(|node| node.volume.size())
+			.sum()
+	}
+
+	/// Returns every pair of bodies whose bounding volumes overlap.
+	#[must_use]
+	pub fn potential_contacts(&self) -> Vec<PotentialContact> {
+		let mut contacts = Vec::new();
+		self.potential_contacts_into(&mut contacts);
+		contacts
+	}
+
+	/// Replaces the contents of the given list with the same pairs as
+	/// [`Self::potential_contacts`], reusing its storage.
+	pub fn potential_contacts_into(&self, contacts: &mut Vec<PotentialContact>) {
+		contacts.clear();
+		if let Some(root) = self.root {
+			self.contacts_within(root, contacts);
+		}
+	}
+
+	/// Returns the bodies whose bounding volumes overlap the given volume.
+	#[must_use]
+	pub fn query(&self, volume: &V) -> Vec<usize> {
+		let mut bodies = Vec::new();
+		self.query_into(volume, &mut bodies);
+		bodies
+	}
+
+	/// Replaces the contents of the given list with the same bodies as
+	/// [`Self::query`], in the same order, reusing its storage.
+	///
+	/// The hierarchy is walked by following each node's parent back up, so
+	/// no stack of nodes to visit is kept.
+	pub fn query_into(&self, volume: &V, bodies: &mut Vec<usize>) {
+		bodies.clear();
+		let Some(root) = self.root else {
+			return;
+		};
+		let mut node = root;
+		loop {
+			// Go down the second child first, as a stack would
+			if self.nodes[node].volume.overlaps(volume) {
+				match self.nodes[node].content {
+					NodeContent::Leaf(body) => bodies.push(body),
+					NodeContent::Branch([_, second]) => {
+						node = second;
+						continue;
+					},
+					NodeContent::Free => {},
+				}
+			}
+			// Then climb to the first ancestor with a first child left to visit
+			loop {
+				if node == root {
+					return;
+				}
+				let Some(parent) = self.nodes[node].parent else {
+					return;
+				};
+				if let NodeContent::Branch([first, second]) = self.nodes[parent].content {
+					if node == second {
+						node = first;
+						break;
+					}
+				}
+				node = parent;
+			}
+		}
+	}
+
+	/// Returns the bodies whose bounding volumes the ray enters within
+	/// the given distance, nearest first.
+	#[must_use]
+	pub fn ray_cast(&self, ray: &Ray, max_distance: Real) -> Vec<RayCandidate> {
+		let mut candidates = Vec::new();
+		let mut stack: Vec<usize> = self.root.into_iter().collect();
+		while let Some(node) = stack.pop() {
+			let Some(distance) = self.nodes[node].volume.ray_distance(ray, max_distance) else {
+				continue;
+			};
+			match self.nodes[node].content {
+				NodeContent::Leaf(body) => candidates.push(RayCandidate { body, distance }),
+				NodeContent::Branch(children) => stack.extend(children),
+				NodeContent::Free => {},
+			}
+		}
+		sort_candidates(&mut candidates);
+		candidates
+	}
+
+	fn allocate(&mut self, node: Node<V>) -> usize {
+		if let Some(index) = self.free.pop() {
+			self.nodes[index] = node;
+			index
+		} else {
+			self.nodes.push(node);
+			self.nodes.len() - 1
+		}
+	}
+
+	/// Links an allocated leaf into the tree beside the leaf whose
+	/// volume would grow least by enclosing it.
+	fn insert_leaf(&mut self, leaf: usize) {
+		let Some(root) = self.root else {
+			self.nodes[leaf].parent = None;
+			self.root = Some(leaf);
+			return;
+		};
+		let volume = self.nodes[leaf].volume;
+		let mut sibling = root;
+		while let NodeContent::Branch([first, second]) = self.nodes[sibling].content {
+			let first_growth = self.nodes[first].volume.growth(&volume);
+			let second_growth = self.nodes[second].volume.growth(&volume);
+			sibling = if first_growth <= second_growth { first } else { second };
+		}
+
+		// The new branch takes the sibling's place in the tree
+		let parent = self.nodes[sibling].parent;
+		let branch = self.allocate(Node {
+			volume: self.nodes[sibling].volume.merge(&volume),
+			parent,
+			content: NodeContent::Branch([sibling, leaf]),
+		});
+		self.nodes[sibling].parent = Some(branch);
+		self.nodes[leaf].parent = Some(branch);
+		self.replace_child(parent, sibling, branch);
+		self.refit_ancestors(parent);
+	}
+
+	/// Unlinks a leaf from the tree without freeing it. Its sibling
+	/// takes the place of their parent, which is freed.
+	fn remove_leaf(&mut self, leaf: usize) {
+		let Some(parent) = self.nodes[leaf].parent else {
+			self.root = None;
+			return;
+		};
+		let NodeContent::Branch(children) = self.nodes[parent].content else {
+			return;
+		};
+		let sibling = if children[0] == leaf { children[1] } else { children[0] };
+		let grandparent = self.nodes[parent].parent;
+		self.nodes[sibling].parent = grandparent;
+		self.replace_child(grandparent, parent, sibling);
+		self.nodes[parent].content = NodeContent::Free;
+		self.free.push(parent);
+		self.nodes[leaf].parent = None;
+		self.refit_ancestors(grandparent);
+	}
+
+	/// Points the given parent, or the root if there is none, at a new child.
+	fn replace_child(&mut self, parent: Option<usize>, old: usize, new: usize) {
+		let Some(parent) = parent else {
+			self.root = Some(new);
+			return;
+		};
+		if let NodeContent::Branch(children) = &mut self.nodes[parent].content {
+			for child in children.iter_mut().filter(|child| **child == old) {
+				*child = new;
+			}
+		}
+	}
+
+	/// Recalculates the volumes of the given branch and all of its
+	/// ancestors, rotating each one if that makes a child smaller.
+	fn refit_ancestors(&mut self, mut node: Option<usize>) {
+		while let Some(index) = node {
+			self.rotate(index);
+			self.refit_branch(index);
+			node = self.nodes[index].parent;
+		}
+	}
+
+	fn refit_branch(&mut self, node: usize) {
+		if let NodeContent::Branch([first, second]) = self.nodes[node].content {
+			self.nodes[node].volume = self.nodes[first].volume.merge(&self.nodes[second].volume);
+		}
+	}
+
+	/// Swaps one child of the given branch with a grandchild on the other
+	/// side, if the swap shrinks the volume of the child that is kept.
+	fn rotate(&mut self, node: usize) {
+		let NodeContent::Branch(children) = self.nodes[node].content else {
+			return;
+		};
+		let mut best = None;
+		let mut best_saving = 0.0;
+		for slot in 0..2 {
+			let (child, other) = (children[slot], children[1 - slot]);
+			let NodeContent::Branch(grandchildren) = self.nodes[child].content else {
+				continue;
+			};
+			let current_size = self.nodes[child].volume.size();
+			for grandchild_slot in 0..2 {
+				let kept = grandchildren[1 - grandchild_slot];
+				let saving = current_size - self.nodes[other].volume.merge(&self.nodes[kept].volume).size();
+				if saving > best_saving {
+					best = Some((slot, grandchild_slot));
+					best_saving = saving;
+				}
+			}
+		}
+		let Some((slot, grandchild_slot)) = best else {
+			return;
+		};
+
+		let (child, other) = (children[slot], children[1 - slot]);
+		let NodeContent::Branch(mut grandchildren) = self.nodes[child].content else {
+			return;
+		};
+		let grandchild = std::mem::replace(&mut grandchildren[grandchild_slot], other);
+		let mut children = children;
+		children[1 - slot] = grandchild;
+		self.nodes[node].content = NodeContent::Branch(children);
+		self.nodes[child].content = NodeContent::Branch(grandchildren);
+		self.nodes[grandchild].parent = Some(node);
+		self.nodes[other].parent = Some(child);
+		self.refit_branch(child);
+	}
+
+	/// Finds the potential contacts between the descendants of the given node.
+	fn contacts_within(&self, node: usize, contacts: &mut Vec<PotentialContact>) {
+		if let NodeContent::Branch([first, second]) = self.nodes[node].content {
+			self.contacts_within(first, contacts);
+			self.contacts_within(second, contacts);
+			self.contacts_between(first, second, contacts);
+		}
+	}
+
+	/// Finds the potential contacts between the descendants of one node and those of another.
+	fn contacts_between(&self, first: usize, second: usize, contacts: &mut Vec<PotentialContact>) {
+		match self.split(first, second) {
+			Split::Separate => {},
+			Split::Contact(contact) => contacts.push(contact),
+			Split::Descend(pairs) => {
+				for (first, second) in pairs {
+					self.contacts_between(first, second, contacts);
+				}
+			},
+		}
+	}
+
+	/// Decides how to search two nodes for contacts, descending into the larger volume first.
+	fn split(&self, first: usize, second: usize) -> Split {
+		let (first_node, second_node) = (&self.nodes[first], &self.nodes[second]);
+		if !first_node.volume.overlaps(&second_node.volume) {
+			return Split::Separate;
+		}
+		match (first_node.content, second_node.content) {
+			(NodeContent::Leaf(first_body), NodeContent::Leaf(second_body)) => Split::Contact(PotentialContact {
+				bodies: [first_body, second_body],
+			}),
+			(NodeContent::Branch(children), NodeContent::Leaf(_)) => {
+				Split::Descend([(children[0], second), (children[1], second)])
+			},
+			(NodeContent::Branch(children), NodeContent::Branch(_))
+				if first_node.volume.size() >= second_node.volume.size() =>
+			{
+				Split::Descend([(children[0], second), (children[1], second)])
+			},
+			(_, NodeContent::Branch(children)) => Split::Descend([(first, children[0]), (first, children[1])]),
+			_ => Split::Separate,
+		}
+	}
+}
+
+/// How a pair of nodes is searched for potential contacts.
+enum Split {
+	/// The volumes do not overlap, so there are no contacts.
+	Separate,
+
+	/// Both nodes are leaves with overlapping volumes.
+	Contact(PotentialContact),
+
+	/// The pairs of nodes to search instead.
+	Descend([(usize, usize); 2]),
+}
+
+/// How many levels of the hierarchy are split across threads before
+/// the search continues on a single thread.
+#[cfg(feature = "rayon")]
+const PARALLEL_DEPTH: usize = 8;
+
+/// A part of the search for potential contacts, searched on one thread.
+#[cfg(feature = "rayon")]
+#[derive(Debug, Clone, Copy)]
+enum SearchPart {
+	/// The contacts among the descendants of a node.
+	Within(usize),
+
+	/// The contacts between the descendants of one node and those of another.
+	Between(usize, usize),
+}
+
+/// The storage a search for potential contacts on several threads reuses
+/// from search to search: the parts it is split into, and the pairs found
+/// in each part.
+#[cfg(feature = "rayon")]
+#[derive(Debug, Default, Clone)]
+pub struct ContactSearch {
+	parts: Vec<SearchPart>,
+	found: Vec<Vec<PotentialContact>>,
+}
+
+#[cfg(feature = "rayon")]
+impl<V: BoundingVolume + Send + Sync> Bvh<V> {
+	/// Returns the same pairs as [`Self::potential_contacts`], in the same
+	/// order, searching the hierarchy on several threads.
+	#[must_use]
+	pub fn par_potential_contacts(&self) -> Vec<PotentialContact> {
+		let mut contacts = Vec::new();
+		self.par_potential_contacts_into(&mut ContactSearch::default(), &mut contacts);
+		contacts
+	}
+
+	/// Replaces the contents of the given list with the same pairs as
+	/// [`Self::potential_contacts`], in the same order, searching the
+	/// hierarchy on several threads.
+	///
+	/// The top levels of the hierarchy are split into parts in the order a
+	/// search on one thread takes them, and the pairs found in each part are
+	/// joined in that order, so the output never depends on thread timing.
+	/// Once the search's storage has grown, searching again allocates nothing.
+	pub fn par_potential_contacts_into(&self, search: &mut ContactSearch, contacts: &mut Vec<PotentialContact>) {
+		use rayon::prelude::*;
+		search.parts.clear();
+		if let Some(root) = self.root {
+			self.split_within(root, PARALLEL_DEPTH, &mut search.parts);
+		}
+		let parts = search.parts.len();
+		if search.found.len() < parts {
+			search.found.resize_with(parts, Vec::new);
+		}
+		search
+			.parts
+			.par_iter()
+			.zip(search.found.par_iter_mut())
+			.for_each(|(part, found)| {
+				found.clear();
+				match *part {
+					SearchPart::Within(node) => self.contacts_within(node, found),
+					SearchPart::Between(first, second) => self.contacts_between(first, second, found),
+				}
+			});
+		contacts.clear();
+		for found in &search.found[..parts] {
+			contacts.extend_from_slice(found);
+		}
+	}
+
+	/// Splits the search within a node into parts, down to the given depth.
+	fn split_within(&self, node: usize, depth: usize, parts: &mut Vec<SearchPart>) {
+		match self.nodes[node].content {
+			NodeContent::Branch(_) if depth == 0 => parts.push(SearchPart::Within(node)),
+			NodeContent::Branch([first, second]) => {
+				self.split_within(first, depth - 1, parts);
+				self.split_within(second, depth - 1, parts);
+				self.split_between(first, second, depth - 1, parts);
+			},
+			_ => {},
+		}
+	}
+
+	/// Splits the search between two nodes into parts, down to the given depth.
+	fn split_between(&self, first: usize, second: usize, depth: usize, parts: &mut Vec<SearchPart>) {
+		match self.split(first, second) {
+			Split::Separate => {},
+			Split::Descend(pairs) if depth > 0 => {
+				for (first, second) in pairs {
+					self.split_between(first, second, depth - 1, parts);
+				}
+			},
+			_ => parts.push(SearchPart::Between(first, second)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{bounding::BoundingSphere, vec::Vector3};
+
+	use super::*;
+
+	fn sphere_at(x: f32) -> BoundingSphere {
+		BoundingSphere::new(Vector3::new(x, 0.0, 0.0), 1.0)
+	}
+
+	fn sorted_pairs(bvh: &Bvh<BoundingSphere>) -> Vec<[usize; 2]> {
+		let mut pairs: Vec<[usize; 2]> = bvh
+			.potential_contacts()
+			.iter()
+			.map(|contact| {
+				let [first, second] = contact.bodies;
+				[first.min(second), first.max(second)]
+			})
+			.collect();
+		pairs.sort_unstable();
+		pairs
+	}
+
+	#[test]
+	pub fn finds_overlapping_pairs() {
+		let mut bvh = Bvh::default();
+		for (body, x) in [0.0, 1.5, 3.0, 10.0, 11.0].into_iter().enumerate() {
+			bvh.insert(body, sphere_at(x));
+		}
+		assert_eq!(bvh.len(), 5);
+		assert_eq!(sorted_pairs(&bvh), vec![[0, 1], [1, 2], [3, 4]]);
+	}
+
+	#[test]
+	pub fn query() {
+		let mut bvh = Bvh::default();
+		for (body, x) in [0.0, 1.5, 3.0, 10.0].into_iter().enumerate() {
+			bvh.insert(body, sphere_at(x));
+		}
+		let mut bodies = bvh.query(&BoundingSphere::new(Vector3::new(2.25, 0.0, 0.0), 0.5));
+		bodies.sort_unstable();
+		assert_eq!(bodies, vec![1, 2]);
+		assert!(bvh.query(&sphere_at(6.0)).is_empty());
+	}
+
+	#[test]
+	pub fn query_into_finds_every_overlap() {
+		let mut bvh = Bvh::default();
+		let spheres: Vec<BoundingSphere> = (0..200)
+			.map(|body| {
+				let position = crate::real_from_count(body);
+				sphere_at((position * 0.37).sin() * 40.0)
+			})
+			.collect();
+		for (body, sphere) in spheres.iter().enumerate() {
+			bvh.insert(body, *sphere);
+		}
+		let mut bodies = Vec::new();
+		for x in [-30.0, 0.0, 12.5, 39.0] {
+			let volume = BoundingSphere::new(Vector3::new(x, 0.0, 0.0), 2.0);
+			bvh.query_into(&volume, &mut bodies);
+			bodies.sort_unstable();
+			let expected: Vec<usize> = (0..spheres.len()).filter(|body| spheres[*body].overlaps(&volume)).collect();
+			assert!(!expected.is_empty());
+			assert_eq!(bodies, expected);
+		}
+	}
+
+	#[test]
+	pub fn ray_cast() {
+		let mut bvh = Bvh::default();
+		for (body, x) in [0.0, 1.5, 3.0, 10.0].into_iter().enumerate() {
+			bvh.insert(body, BoundingSphere::new(Vector3::new(x, 0.0, 0.0), 0.5));
+		}
+		let ray = Ray::new(Vector3::new(5.0, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0));
+		let candidates = bvh.ray_cast(&ray, 4.0);
+		assert_eq!(
+			candidates,
+			vec![
+				RayCandidate { body: 2, distance: 1.5 },
+				RayCandidate { body: 1, distance: 3.0 },
+			]
+		);
+		assert!(bvh
+			.ray_cast(&Ray::new(Vector3::new(5.0, 2.0, 0.0), Vector3::x_axis()), 100.0)
+			.is_empty());
+	}
+
+	#[test]
+	pub fn remove_and_update() {
+		let mut bvh = Bvh::default();
+		let handles: Vec<usize> = [0.0, 1.5, 3.0, 10.0]
+			.into_iter()
+			.enumerate()
+			.map(|(body, x)| bvh.insert(body, sphere_at(x)))
+			.collect();
+
+		assert_eq!(bvh.remove(handles[1]), Some(1));
+		assert_eq!(bvh.remove(handles[1]), None);
+		assert!(sorted_pairs(&bvh).is_empty());
+
+		bvh.update(handles[3], sphere_at(4.0));
+		assert_eq!(bvh.volume(handles[3]), Some(sphere_at(4.0)));
+		assert_eq!(sorted_pairs(&bvh), vec![[2, 3]]);
+
+		// Freed nodes are reused for new bodies
+		bvh.insert(1, sphere_at(-1.0));
+		assert_eq!(sorted_pairs(&bvh), vec![[0, 1], [2, 3]]);
+		assert!(bvh.nodes.len() <= 7);
+
+		for handle in [handles[0], handles[2], handles[3]] {
+			bvh.remove(handle);
+		}
+		assert_eq!(bvh.len(), 1);
+		assert!(bvh.potential_contacts().is_empty());
+	}
+
+	#[test]
+	pub fn refit_rotates_to_keep_the_hierarchy_tight() {
+		let mut bvh = Bvh::default();
+		let first = bvh.insert(0, sphere_at(0.0));
+		let second = bvh.insert(1, sphere_at(1.0));
+		bvh.insert(2, sphere_at(100.0));
+
+		// Moving the second body beside the third leaves the first alone
+		bvh.refit(second, sphere_at(101.0));
+		assert_eq!(bvh.volume(second), Some(sphere_at(101.0)));
+		assert_eq!(sorted_pairs(&bvh), vec![[1, 2]]);
+		let tight = sphere_at(100.0).merge(&sphere_at(101.0));
+		let root = tight.merge(&sphere_at(0.0));
+		assert!((bvh.cost() - (tight.size() + root.size())).abs() < 1.0);
+
+		bvh.refit(first, sphere_at(100.5));
+		assert_eq!(sorted_pairs(&bvh), vec![[0, 1], [0, 2], [1, 2]]);
+	}
+
+	#[cfg(feature = "rayon")]
+	#[test]
+	pub fn parallel_pairs_match_serial_pairs() {
+		let mut bvh = Bvh::default();
+		for body in 0..500 {
+			let position = crate::real_from_count(body);
+			let center = Vector3::new(
+				(position * 0.37).sin() * 20.0,
+				(position * 0.11).cos() * 20.0,
+				position * 0.05,
+			);
+			bvh.insert(body, BoundingSphere::new(center, 1.0));
+		}
+		let serial = bvh.potential_contacts();
+		assert!(!serial.is_empty());
+		assert_eq!(bvh.par_potential_contacts(), serial);
+	}
+}
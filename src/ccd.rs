@@ -0,0 +1,213 @@
+use crate::{
+	body_set::BodySet,
+	bounding::{Aabb, BoundingVolume},
+	collider::Collider,
+	convex::{convex_cast, ShapeHit, SupportMap, Translated},
+	vec::Vector3,
+	Real,
+};
+
+/// How a body found to have passed into or through another during a step is handled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub enum CcdResolution {
+	/// The body is left where it was integrated to, and the impact is only reported.
+	Report,
+
+	/// The body is moved back along its path to where it first touched, so
+	/// that the next contact pass finds it touching rather than beyond. Its
+	/// velocity is kept for the contact to resolve.
+	#[default]
+	ClampMotion,
+}
+
+/// Where a body sweeping along its motion first touched a collider.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CcdImpact {
+	/// Holds the index of the body that was swept.
+	pub body: usize,
+
+	/// Holds the index of the collider it touched.
+	pub collider: usize,
+
+	/// Holds where it touched, with the distance measured from where it started the step.
+	pub hit: ShapeHit,
+}
+
+/// Sweeps the colliders of each body that has continuous collision enabled
+/// along the straight path it moved this step, returning the first collider
+/// each touched on the way, ordered by body.
+///
+/// Discrete detection misses a body that moves further than its own size
+/// in one step, as it can pass right through a thin wall. This is run
+/// after integration, with the positions the bodies had before it and the
+/// colliders calculated for the new positions. Only bodies that moved
+/// further than the smallest half extent of their colliders are swept, and
+/// colliders they already touched at the start are left to discrete detection.
+/// Colliders not attached to a body are passed over.
+pub fn sweep_bodies(
+	bodies: &mut BodySet,
+	previous_positions: &[Vector3],
+	colliders: &mut [Collider],
+	resolution: CcdResolution,
+) -> Vec<CcdImpact> {
+	let mut impacts = Vec::new();
+	sweep_bodies_into(bodies, previous_positions, colliders, resolution, &mut impacts);
+	impacts
+}
+
+/// Replaces the contents of the given list with the impacts
+/// [`sweep_bodies`] finds, reusing its storage.
+pub fn sweep_bodies_into(
+	bodies: &mut BodySet,
+	previous_positions: &[Vector3],
+	colliders: &mut [Collider],
+	resolution: CcdResolution,
+	impacts: &mut Vec<CcdImpact>,
+) {
+	impacts.clear();
+	let positions = bodies.positions();
+	impacts.extend(
+		(0..bodies.len())
+			.filter(|body| bodies.flags()[*body].continuous_collision)
+			.filter_map(|body| sweep_body(body, positions[body] - previous_positions[body], colliders)),
+	);
+	if resolution == CcdResolution::ClampMotion {
+		for impact in impacts.iter() {
+			let previous = previous_positions[impact.body];
+			bodies.update(impact.body, |body| {
+				let direction = (body.position - previous).normalize();
+				body.position = previous + direction * impact.hit.distance;
+				body.calculate_derived_data();
+			});
+		}
+		for collider in colliders.iter_mut() {
+			if impacts.iter().any(|impact| impact.body == collider.primitive().body) {
+				collider.calculate_internals(bodies);
+			}
+		}
+	}
+}
+
+/// Sweeps the colliders of one body back along its motion, returning the nearest impact.
+fn sweep_body(body: usize, motion: Vector3, colliders: &[Collider]) -> Option<CcdImpact> {
+	let distance = motion.magnitude();
+	let own = || colliders.iter().filter(|collider| collider.primitive().body == body);
+	let smallest = own()
+		.map(|collider| {
+			let half_size = collider.bounds().half_size();
+			half_size.x().min(half_size.y()).min(half_size.z())
+		})
+		.reduce(Real::min)?;
+	if distance <= smallest {
+		return None;
+	}
+
+	let mut nearest: Option<CcdImpact> = None;
+	for collider in own() {
+		let start = Translated {
+			shape: collider,
+			offset: -motion,
+		};
+		let from = start.bounds();
+		let swept = from.merge(&Aabb::new(from.min + motion, from.max + motion));
+		for (index, target) in colliders.iter().enumerate() {
+			if target.primitive().body == body || !target.is_attached() || !swept.overlaps(&target.bounds()) {
+				continue;
+			}
+			let limit = nearest.map_or(distance, |nearest| nearest.hit.distance);
+			let Some(hit) = convex_cast(&start, motion, limit, target) else {
+				continue;
+			};
+			if hit.distance > 0.0 && nearest.is_none_or(|nearest| hit.distance < nearest.hit.distance) {
+				nearest = Some(CcdImpact {
+					body,
+					collider: index,
+					hit,
+				});
+			}
+		}
+	}
+	nearest
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		body::{BodyKind, RigidBodyBuilder, DEFAULT_SLEEP_EPSILON},
+		collide::CollisionPrimitive,
+		shape::Shape,
+	};
+
+	use super::*;
+
+	/// A bullet flying at a thin wall at 100 m/s, and the wall.
+	fn range(continuous_collision: bool) -> (BodySet, Vec<Collider>) {
+		let bullet = Shape::Sphere { radius: 0.05 };
+		let wall = Shape::Cuboid {
+			half_size: Vector3::new(0.05, 2.0, 2.0),
+		};
+		let bodies: BodySet = [
+			RigidBodyBuilder::new(bullet)
+				.velocity(Vector3::new(100.0, 0.0, 0.0))
+				.continuous_collision(continuous_collision)
+				.build(),
+			RigidBodyBuilder::new(wall)
+				.kind(BodyKind::Static)
+				.position(Vector3::new(1.0, 0.0, 0.0))
+				.build(),
+		]
+		.into_iter()
+		.collect();
+		let mut colliders = vec![
+			Collider::from_shape(&bullet, CollisionPrimitive::new(0)),
+			Collider::from_shape(&wall, CollisionPrimitive::new(1)),
+		];
+		for collider in &mut colliders {
+			collider.calculate_internals(&bodies);
+		}
+		(bodies, colliders)
+	}
+
+	fn step(bodies: &mut BodySet, colliders: &mut [Collider], resolution: CcdResolution) -> Vec<CcdImpact> {
+		let previous_positions = bodies.positions().to_vec();
+		bodies.integrate(1.0 / 60.0, DEFAULT_SLEEP_EPSILON);
+		for collider in colliders.iter_mut() {
+			collider.calculate_internals(bodies);
+		}
+		sweep_bodies(bodies, &previous_positions, colliders, resolution)
+	}
+
+	#[test]
+	pub fn bullets_stop_at_thin_walls() {
+		let (mut bodies, mut colliders) = range(true);
+		let impacts = step(&mut bodies, &mut colliders, CcdResolution::ClampMotion);
+		assert_eq!(impacts.len(), 1);
+		assert_eq!(impacts[0].collider, 1);
+		assert!(impacts[0].hit.normal.dot(&-Vector3::x_axis()) > 0.999);
+
+		// The bullet is held against the near face of the wall, still flying at it
+		assert!((bodies.positions()[0].x() - 0.9).abs() < 1e-2);
+		assert!(bodies.velocities()[0].x() > 90.0);
+		assert_eq!(colliders[0].primitive().position(), bodies.positions()[0]);
+	}
+
+	#[test]
+	pub fn bullets_without_ccd_tunnel() {
+		let (mut bodies, mut colliders) = range(false);
+		assert!(step(&mut bodies, &mut colliders, CcdResolution::ClampMotion).is_empty());
+		assert!(bodies.positions()[0].x() > 1.5);
+
+		// Reporting leaves the bullet beyond the wall
+		let (mut bodies, mut colliders) = range(true);
+		assert_eq!(step(&mut bodies, &mut colliders, CcdResolution::Report).len(), 1);
+		assert!(bodies.positions()[0].x() > 1.5);
+	}
+
+	#[test]
+	pub fn slow_bodies_are_not_swept() {
+		let (mut bodies, mut colliders) = range(true);
+		bodies.update(0, |body| body.velocity = Vector3::new(1.0, 0.0, 0.0));
+		assert!(step(&mut bodies, &mut colliders, CcdResolution::ClampMotion).is_empty());
+	}
+}
@@ -0,0 +1,189 @@
+use crate::{body::RigidBody, quaternion::Quaternion, vec::Vector3, Real};
+
+/// Drives a simulation with fixed steps from the varying time between frames.
+///
+/// Stepping with the raw frame time makes a simulation depend on the frame
+/// rate, and bodies drawn where they were last stepped to appear to stutter
+/// whenever a frame takes one more or one fewer step than the last. The
+/// clock instead saves up the time given to it and hands it out in fixed
+/// steps, and the time left over gives the fraction of a step to blend the
+/// last two [`BodyTransform`]s by when drawing.
+///
+/// Slow motion and pausing change how much time is saved up rather than
+/// the duration of the steps, so the simulation behaves the same at any
+/// speed, and bodies settle and fall asleep after the same number of steps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct SimulationClock {
+	/// Holds the duration of each step.
+	pub fixed_step: Real,
+
+	/// Holds the most steps taken for one frame. Any more time is dropped,
+	/// so that a slow frame does not leave ever more steps to catch up on.
+	pub max_steps: usize,
+
+	accumulator: Real,
+	time_scale: Real,
+	paused: bool,
+}
+
+impl Default for SimulationClock {
+	fn default() -> Self {
+		Self::new(1.0 / 60.0)
+	}
+}
+
+impl SimulationClock {
+	/// Creates a clock taking steps of the given duration, at most eight a frame.
+	#[must_use]
+	pub const fn new(fixed_step: Real) -> Self {
+		Self {
+			fixed_step,
+			max_steps: 8,
+			accumulator: 0.0,
+			time_scale: 1.0,
+			paused: false,
+		}
+	}
+
+	/// Returns how much faster than real time the simulation runs.
+	#[must_use]
+	pub const fn time_scale(&self) -> Real {
+		self.time_scale
+	}
+
+	/// Sets how much faster than real time the simulation runs, so that
+	/// one half runs it in slow motion. Negative scales are taken as zero.
+	pub const fn set_time_scale(&mut self, time_scale: Real) {
+		self.time_scale = time_scale.max(0.0);
+	}
+
+	#[must_use]
+	pub const fn is_paused(&self) -> bool {
+		self.paused
+	}
+
+	/// Stops time from being saved up, so that no steps are taken and the
+	/// blend between transforms holds still until resumed.
+	pub const fn pause(&mut self) {
+		self.paused = true;
+	}
+
+	pub const fn resume(&mut self) {
+		self.paused = false;
+	}
+
+	/// Adds the time since the last frame, scaled by the time scale,
+	/// returning the number of steps now due. No time is added while paused.
+	pub fn advance(&mut self, frame_time: Real) -> usize {
+		if self.fixed_step <= 0.0 || self.paused {
+			return 0;
+		}
+		self.accumulator += frame_time.max(0.0) * self.time_scale;
+		let mut steps = 0;
+		while self.accumulator >= self.fixed_step && steps < self.max_steps {
+			self.accumulator -= self.fixed_step;
+			steps += 1;
+		}
+		self.accumulator %= self.fixed_step;
+		steps
+	}
+
+	/// Returns the fraction of a step saved up since the last step, by
+	/// which to blend the transforms before and after it.
+	#[must_use]
+	pub fn blend(&self) -> Real {
+		if self.fixed_step > 0.0 {
+			self.accumulator / self.fixed_step
+		} else {
+			0.0
+		}
+	}
+}
+
+/// The position and orientation of a body at one step.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct BodyTransform {
+	pub position: Vector3,
+	pub orientation: Quaternion,
+}
+
+impl BodyTransform {
+	#[must_use]
+	pub const fn of(body: &RigidBody) -> Self {
+		Self {
+			position: body.position,
+			orientation: body.orientation,
+		}
+	}
+
+	/// Blends from this transform to the other by the given fraction.
+	#[must_use]
+	pub fn interpolate(&self, other: &Self, fraction: Real) -> Self {
+		Self {
+			position: self.position + (other.position - self.position) * fraction,
+			orientation: self.orientation.nlerp(&other.orientation, fraction),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::assert_equal;
+
+	use super::*;
+
+	#[test]
+	pub fn time_is_handed_out_in_fixed_steps() {
+		let mut clock = SimulationClock::new(0.1);
+		assert_eq!(clock.advance(0.25), 2);
+		assert!((clock.blend() - 0.5).abs() < 1e-4);
+		assert_eq!(clock.advance(0.05), 1);
+		assert!(clock.blend() < 1e-4);
+		assert_eq!(clock.advance(-1.0), 0);
+	}
+
+	#[test]
+	pub fn slow_frames_drop_the_steps_beyond_the_most() {
+		let mut clock = SimulationClock::new(0.1);
+		clock.max_steps = 3;
+		assert_eq!(clock.advance(10.05), 3);
+		assert!((clock.blend() - 0.5).abs() < 1e-2);
+		assert_eq!(clock.advance(0.0), 0);
+	}
+
+	#[test]
+	pub fn scaled_time_takes_fewer_steps_of_the_same_duration() {
+		let mut clock = SimulationClock::new(0.1);
+		clock.set_time_scale(0.5);
+		assert_eq!(clock.advance(0.3), 1);
+		assert!((clock.blend() - 0.5).abs() < 1e-4);
+		clock.set_time_scale(-1.0);
+		assert_equal(clock.time_scale(), 0.0);
+		assert_eq!(clock.advance(1.0), 0);
+	}
+
+	#[test]
+	pub fn paused_clocks_hold_still() {
+		let mut clock = SimulationClock::new(0.1);
+		assert_eq!(clock.advance(0.15), 1);
+		clock.pause();
+		assert_eq!(clock.advance(1.0), 0);
+		assert!((clock.blend() - 0.5).abs() < 1e-4);
+		clock.resume();
+		assert_eq!(clock.advance(0.05), 1);
+	}
+
+	#[test]
+	pub fn transforms_blend_by_the_fraction() {
+		let start = BodyTransform::default();
+		let end = BodyTransform {
+			position: Vector3::new(2.0, 0.0, 0.0),
+			orientation: Quaternion::from_axis_angle(Vector3::y_axis(), 1.0),
+		};
+		let halfway = start.interpolate(&end, 0.5);
+		assert_eq!(halfway.position, Vector3::x_axis());
+		assert_equal(halfway.orientation.y, 0.25_f32.sin());
+	}
+}
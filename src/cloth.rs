@@ -0,0 +1,308 @@
+use std::ops::Range;
+
+use crate::{
+	forces::ParticleSpringLink, links::particle_distance, particle::Particle, real_from_count, vec::Vector3, Real,
+};
+
+/// The stiffness and damping of a spring, such as one class of cloth spring.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct SpringStiffness {
+	/// Holds the spring constant.
+	pub spring_constant: Real,
+
+	/// Holds the damping coefficient.
+	pub damping: Real,
+}
+
+impl SpringStiffness {
+	#[must_use]
+	pub const fn new(spring_constant: Real, damping: Real) -> Self {
+		Self {
+			spring_constant,
+			damping,
+		}
+	}
+
+	/// Creates the stiffness that makes the given mass oscillate at the
+	/// given frequency, in hertz, with the given damping ratio: one for
+	/// critical damping, where it settles fastest without overshooting.
+	#[must_use]
+	pub fn from_frequency(mass: Real, frequency: Real, damping_ratio: Real) -> Self {
+		let angular_frequency = std::f32::consts::TAU * frequency;
+		Self::new(
+			mass * angular_frequency * angular_frequency,
+			2.0 * mass * damping_ratio * angular_frequency,
+		)
+	}
+}
+
+/// Builds a rectangular grid of particles joined by springs.
+///
+/// Three classes of spring are created: structural springs join each particle to
+/// its horizontal and vertical neighbours, shear springs join diagonal
+/// neighbours, and bend springs join each particle to the particles two steps
+/// away horizontally and vertically. Each class has its own stiffness.
+#[derive(Debug, Clone)]
+pub struct ClothBuilder {
+	origin: Vector3,
+	columns: usize,
+	rows: usize,
+	spacing: Real,
+	across: Vector3,
+	down: Vector3,
+	total_mass: Real,
+	damping: Real,
+	structural: SpringStiffness,
+	shear: SpringStiffness,
+	bend: SpringStiffness,
+	pinned: Vec<(usize, usize)>,
+}
+
+impl ClothBuilder {
+	/// Creates a builder for a cloth with the given number of particles along
+	/// each side, spaced evenly and hanging from `origin` in the xy plane.
+	#[must_use]
+	pub fn new(origin: Vector3, columns: usize, rows: usize, spacing: Real) -> Self {
+		Self {
+			origin,
+			columns: columns.max(1),
+			rows: rows.max(1),
+			spacing,
+			across: Vector3::x_axis(),
+			down: -Vector3::y_axis(),
+			total_mass: 1.0,
+			damping: 0.99,
+			structural: SpringStiffness::new(50.0, 0.5),
+			shear: SpringStiffness::new(25.0, 0.25),
+			bend: SpringStiffness::new(10.0, 0.1),
+			pinned: Vec::new(),
+		}
+	}
+
+	/// Sets the directions in which columns and rows are laid out.
+	#[must_use]
+	pub fn axes(mut self, across: Vector3, down: Vector3) -> Self {
+		self.across = across.normalize();
+		self.down = down.normalize();
+		self
+	}
+
+	/// Sets the mass of the whole cloth, shared evenly between its free particles.
+	#[must_use]
+	pub const fn total_mass(mut self, total_mass: Real) -> Self {
+		self.total_mass = total_mass;
+		self
+	}
+
+	/// Sets the damping applied to every particle in the cloth.
+	#[must_use]
+	pub const fn damping(mut self, damping: Real) -> Self {
+		self.damping = damping;
+		self
+	}
+
+	/// Sets the stiffness of the springs joining horizontal and vertical neighbours.
+	#[must_use]
+	pub const fn structural(mut self, stiffness: SpringStiffness) -> Self {
+		self.structural = stiffness;
+		self
+	}
+
+	/// Sets the stiffness of the springs joining diagonal neighbours.
+	#[must_use]
+	pub const fn shear(mut self, stiffness: SpringStiffness) -> Self {
+		self.shear = stiffness;
+		self
+	}
+
+	/// Sets the stiffness of the springs joining particles two steps apart.
+	#[must_use]
+	pub const fn bend(mut self, stiffness: SpringStiffness) -> Self {
+		self.bend = stiffness;
+		self
+	}
+
+	/// Gives the particle at the given column and row infinite mass.
+	#[must_use]
+	pub fn pin(mut self, column: usize, row: usize) -> Self {
+		self.pinned.push((column, row));
+		self
+	}
+
+	/// Pins every particle along the first row.
+	#[must_use]
+	pub fn pin_top_row(mut self) -> Self {
+		self.pinned.extend((0..self.columns).map(|column| (column, 0)));
+		self
+	}
+
+	/// Pins the two corners of the first row.
+	#[must_use]
+	pub fn pin_top_corners(self) -> Self {
+		let last = self.columns - 1;
+		self.pin(0, 0).pin(last, 0)
+	}
+
+	fn is_pinned(&self, column: usize, row: usize) -> bool {
+		self.pinned.contains(&(column, row))
+	}
+
+	/// Appends the cloth's particles to the given list and returns
+	/// the cloth describing the springs between them.
+	pub fn build(self, particles: &mut Vec<Particle>) -> Cloth {
+		let first = particles.len();
+		let count = self.columns * self.rows;
+		let pinned = (0..self.rows)
+			.flat_map(|row| (0..self.columns).map(move |column| (column, row)))
+			.filter(|(column, row)| self.is_pinned(*column, *row))
+			.count();
+		let free = count - pinned;
+		let inverse_mass = if free > 0 && self.total_mass > 0.0 {
+			real_from_count(free) / self.total_mass
+		} else {
+			0.0
+		};
+
+		for row in 0..self.rows {
+			for column in 0..self.columns {
+				let offset = self.across * (real_from_count(column) * self.spacing)
+					+ self.down * (real_from_count(row) * self.spacing);
+				particles.push(Particle {
+					position: self.origin + offset,
+					damping: self.damping,
+					inverse_mass: if self.is_pinned(column, row) { 0.0 } else { inverse_mass },
+					..Default::default()
+				});
+			}
+		}
+
+		let mut cloth = Cloth {
+			particles: first..first + count,
+			columns: self.columns,
+			rows: self.rows,
+			..Default::default()
+		};
+		cloth.structural = self.springs(&cloth, particles, &[(1, 0), (0, 1)], self.structural);
+		cloth.shear = self.springs(&cloth, particles, &[(1, 1), (-1, 1)], self.shear);
+		cloth.bend = self.springs(&cloth, particles, &[(2, 0), (0, 2)], self.bend);
+		cloth
+	}
+
+	/// Creates a spring from every particle to each neighbour at the given offsets.
+	fn springs(
+		&self,
+		cloth: &Cloth,
+		particles: &[Particle],
+		offsets: &[(isize, usize)],
+		stiffness: SpringStiffness,
+	) -> Vec<ParticleSpringLink> {
+		let mut springs = Vec::new();
+		for row in 0..self.rows {
+			for column in 0..self.columns {
+				for (column_offset, row_offset) in offsets {
+					let Some(other_column) = column.checked_add_signed(*column_offset) else {
+						continue;
+					};
+					let other_row = row + row_offset;
+					if other_column >= self.columns || other_row >= self.rows {
+						continue;
+					}
+					let pair = [cloth.index(column, row), cloth.index(other_column, other_row)];
+					springs.push(ParticleSpringLink {
+						particles: pair,
+						spring_constant: stiffness.spring_constant,
+						damping: stiffness.damping,
+						rest_length: particle_distance(particles, pair),
+					});
+				}
+			}
+		}
+		springs
+	}
+}
+
+/// A grid of particles joined by springs, created by a [`ClothBuilder`].
+#[derive(Debug, Default, Clone)]
+pub struct Cloth {
+	/// Holds the indices of the cloth's particles, stored row by row.
+	pub particles: Range<usize>,
+
+	/// Holds the number of particles in each row.
+	pub columns: usize,
+
+	/// Holds the number of rows of particles.
+	pub rows: usize,
+
+	/// Holds the springs joining horizontal and vertical neighbours.
+	pub structural: Vec<ParticleSpringLink>,
+
+	/// Holds the springs joining diagonal neighbours.
+	pub shear: Vec<ParticleSpringLink>,
+
+	/// Holds the springs joining particles two steps apart.
+	pub bend: Vec<ParticleSpringLink>,
+}
+
+impl Cloth {
+	/// Returns the index of the particle at the given column and row.
+	#[must_use]
+	pub const fn index(&self, column: usize, row: usize) -> usize {
+		self.particles.start + row * self.columns + column
+	}
+
+	/// Iterates over every spring in the cloth.
+	pub fn springs(&self) -> impl Iterator<Item = &ParticleSpringLink> {
+		self.structural.iter().chain(self.shear.iter()).chain(self.bend.iter())
+	}
+
+	/// Adds the force of every spring in the cloth to its particles.
+	pub fn apply_forces(&self, particles: &mut [Particle]) {
+		for spring in self.springs() {
+			spring.apply_force(particles);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::assert_equal;
+
+	use super::*;
+
+	#[test]
+	pub fn spring_counts() {
+		let mut particles = Vec::new();
+		let cloth = ClothBuilder::new(Vector3::zero(), 4, 3, 1.0).build(&mut particles);
+		assert_eq!(particles.len(), 12);
+		assert_eq!(cloth.structural.len(), 3 * 3 + 4 * 2);
+		assert_eq!(cloth.shear.len(), 2 * 3 * 2);
+		assert_eq!(cloth.bend.len(), 2 * 3 + 4);
+		assert_equal(cloth.shear[0].rest_length, Real::sqrt(2.0));
+		assert_equal(cloth.bend[0].rest_length, 2.0);
+	}
+
+	#[test]
+	pub fn layout_and_pinning() {
+		let mut particles = vec![Particle::default()];
+		let cloth = ClothBuilder::new(Vector3::zero(), 3, 2, 0.5)
+			.total_mass(4.0)
+			.pin_top_corners()
+			.build(&mut particles);
+		assert_eq!(cloth.index(2, 1), 6);
+		assert_eq!(particles[cloth.index(2, 1)].position, Vector3::new(1.0, -0.5, 0.0));
+		assert_equal(particles[cloth.index(0, 0)].inverse_mass, 0.0);
+		assert_equal(particles[cloth.index(2, 0)].inverse_mass, 0.0);
+		assert_equal(particles[cloth.index(1, 0)].mass(), 1.0);
+	}
+
+	#[test]
+	pub fn cloth_at_rest_applies_no_force() {
+		let mut particles = Vec::new();
+		let cloth = ClothBuilder::new(Vector3::zero(), 3, 3, 1.0).build(&mut particles);
+		cloth.apply_forces(&mut particles);
+		assert!(particles
+			.iter()
+			.all(|particle| particle.force_accumulator == Vector3::zero()));
+	}
+}
@@ -0,0 +1,1868 @@
+use crate::{
+	body::BodyTransforms,
+	bounding::Aabb,
+	convex::{closest_points, penetration, SupportMap},
+	material::{CombineRule, Material},
+	matrix::Matrix4,
+	shape::Shape,
+	trimesh::{closest_point_on_segment, TriMesh, Triangle},
+	vec::Vector3,
+	Real,
+};
+
+/// The part of a collision shape that attaches it to a rigid body.
+///
+/// A primitive is held at a fixed offset from its body. The offset is
+/// combined with the body's transform by [`Self::calculate_internals`],
+/// which must be called after the body moves and before detecting contacts.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct CollisionPrimitive {
+	/// Holds the index of the rigid body this primitive is attached to.
+	pub body: usize,
+
+	/// Holds the offset of the primitive from the body's origin, in body coordinates.
+	pub offset: Matrix4,
+
+	/// Holds how far outside the shape contacts begin to be generated.
+	///
+	/// Contacts found within the margin have negative penetration, letting
+	/// the resolver slow bodies before they touch rather than after they
+	/// overlap, which reduces jitter and tunneling.
+	pub margin: Real,
+
+	/// Holds what the primitive is made of. Contacts with a primitive
+	/// without a material take the friction and restitution of the
+	/// [`CollisionData`] they are added to.
+	pub material: Option<Material>,
+
+	/// Holds a value of the user's choosing, such as the handle of the game
+	/// entity the primitive belongs to. It is passed back in query results.
+	pub user_data: u64,
+
+	/// Holds the transform of the primitive in world coordinates,
+	/// derived from the body's transform and the offset.
+	transform: Matrix4,
+}
+
+impl CollisionPrimitive {
+	/// Creates a primitive at the origin of the given body.
+	#[must_use]
+	pub const fn new(body: usize) -> Self {
+		Self::with_offset(body, Matrix4::identity())
+	}
+
+	/// Creates a primitive held at the given offset from the given body.
+	#[must_use]
+	pub const fn with_offset(body: usize, offset: Matrix4) -> Self {
+		Self {
+			body,
+			offset,
+			margin: 0.0,
+			material: None,
+			user_data: 0,
+			transform: offset,
+		}
+	}
+
+	/// Sets how far outside the shape contacts begin to be generated.
+	#[must_use]
+	pub const fn with_margin(mut self, margin: Real) -> Self {
+		self.margin = margin;
+		self
+	}
+
+	/// Sets what the primitive is made of.
+	#[must_use]
+	pub const fn with_material(mut self, material: Material) -> Self {
+		self.material = Some(material);
+		self
+	}
+
+	/// Sets the value of the user's choosing held by the primitive.
+	#[must_use]
+	pub const fn with_user_data(mut self, user_data: u64) -> Self {
+		self.user_data = user_data;
+		self
+	}
+
+	/// Calculates the world transform of the primitive from its body's transform.
+	pub fn calculate_internals(&mut self, bodies: &(impl BodyTransforms + ?Sized)) {
+		self.transform = bodies.transform(self.body).compose(&self.offset);
+	}
+
+	/// Returns the world transform of the primitive.
+	#[must_use]
+	pub const fn transform(&self) -> &Matrix4 {
+		&self.transform
+	}
+
+	/// Returns the given column of the world transform. Columns 0 to 2 are
+	/// the primitive's axes, and column 3 is its position.
+	#[must_use]
+	pub fn axis(&self, index: usize) -> Vector3 {
+		self.transform.axis(index)
+	}
+
+	/// Returns the position of the primitive in world coordinates.
+	#[must_use]
+	pub fn position(&self) -> Vector3 {
+		self.transform.axis(3)
+	}
+}
+
+/// A sphere attached to a rigid body.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct CollisionSphere {
+	pub primitive: CollisionPrimitive,
+	pub radius: Real,
+}
+
+impl CollisionSphere {
+	fn ball(&self) -> Ball {
+		Ball {
+			body: self.primitive.body,
+			center: self.primitive.position(),
+			radius: self.radius,
+		}
+	}
+}
+
+/// A sphere at a point in world coordinates, belonging to a body. Capsules
+/// are tested as the spheres at the closest points on their segments.
+struct Ball {
+	body: usize,
+	center: Vector3,
+	radius: Real,
+}
+
+/// A box attached to a rigid body, given by half its size along each of its axes.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct CollisionBox {
+	pub primitive: CollisionPrimitive,
+	pub half_size: Vector3,
+}
+
+impl CollisionBox {
+	/// Returns the corners of the box in world coordinates.
+	#[must_use]
+	pub fn vertices(&self) -> [Vector3; 8] {
+		let mut vertices = [Vector3::zero(); 8];
+		for (index, vertex) in vertices.iter_mut().enumerate() {
+			let mut corner = self.half_size;
+			for axis in 0..3 {
+				if index & (1 << axis) != 0 {
+					corner[axis] = -corner[axis];
+				}
+			}
+			*vertex = self.primitive.transform().transform(corner);
+		}
+		vertices
+	}
+
+	/// Returns half the length of the box's projection onto the given axis.
+	#[must_use]
+	pub fn projected_half_length(&self, axis: Vector3) -> Real {
+		(0..3)
+			.map(|index| self.half_size[index] * axis.dot(&self.primitive.axis(index)).abs())
+			.sum()
+	}
+}
+
+/// A capsule attached to a rigid body: a cylinder capped with hemispheres,
+/// lying along the primitive's y axis. The `half_height` is half the length
+/// of the cylindrical part.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct CollisionCapsule {
+	pub primitive: CollisionPrimitive,
+	pub radius: Real,
+	pub half_height: Real,
+}
+
+impl CollisionCapsule {
+	/// Returns the ends of the capsule's central segment in world coordinates.
+	#[must_use]
+	pub fn segment(&self) -> [Vector3; 2] {
+		let transform = self.primitive.transform();
+		[
+			transform.transform(Vector3::new(0.0, self.half_height, 0.0)),
+			transform.transform(Vector3::new(0.0, -self.half_height, 0.0)),
+		]
+	}
+
+	const fn ball_at(&self, center: Vector3) -> Ball {
+		Ball {
+			body: self.primitive.body,
+			center,
+			radius: self.radius,
+		}
+	}
+}
+
+/// A cylinder attached to a rigid body, lying along the primitive's y axis.
+/// The `half_height` is half the length of the cylinder.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct CollisionCylinder {
+	pub primitive: CollisionPrimitive,
+	pub radius: Real,
+	pub half_height: Real,
+}
+
+/// How far from the cylinder's axis a direction must lean before a single
+/// point on each rim is taken as the deepest. Closer to the axis, the rim
+/// is sampled at four points so that a standing cylinder rests steadily.
+const RIM_LEAN: Real = 1e-4;
+
+impl CollisionCylinder {
+	/// Returns the ends of the cylinder's axis in world coordinates.
+	#[must_use]
+	pub fn segment(&self) -> [Vector3; 2] {
+		let transform = self.primitive.transform();
+		[
+			transform.transform(Vector3::new(0.0, self.half_height, 0.0)),
+			transform.transform(Vector3::new(0.0, -self.half_height, 0.0)),
+		]
+	}
+
+	/// Returns the points on the rims of both caps that reach furthest in
+	/// the given world direction, in world coordinates.
+	#[must_use]
+	pub fn rim_points(&self, direction: Vector3) -> Vec<Vector3> {
+		let transform = self.primitive.transform();
+		let local = transform.transform_inverse_direction(direction);
+		let radial = Vector3::new(local.x(), 0.0, local.z());
+		let offsets = if radial.magnitude() > RIM_LEAN * local.magnitude() {
+			vec![radial.normalize() * self.radius]
+		} else {
+			vec![
+				Vector3::new(self.radius, 0.0, 0.0),
+				Vector3::new(-self.radius, 0.0, 0.0),
+				Vector3::new(0.0, 0.0, self.radius),
+				Vector3::new(0.0, 0.0, -self.radius),
+			]
+		};
+		[self.half_height, -self.half_height]
+			.into_iter()
+			.flat_map(|height| {
+				offsets
+					.iter()
+					.map(move |offset| transform.transform(*offset + Vector3::new(0.0, height, 0.0)))
+			})
+			.collect()
+	}
+
+	const fn ball_at(&self, center: Vector3, radius: Real) -> Ball {
+		Ball {
+			body: self.primitive.body,
+			center,
+			radius,
+		}
+	}
+
+	const fn shape(&self) -> Shape {
+		Shape::Cylinder {
+			radius: self.radius,
+			half_height: self.half_height,
+		}
+	}
+}
+
+/// A plane that is not attached to any body, used for immovable scenery
+/// such as floors and walls.
+///
+/// The plane holds every point whose distance along the normal is the offset.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct CollisionPlane {
+	/// Holds the unit normal of the plane.
+	pub normal: Vector3,
+
+	/// Holds the distance of the plane from the origin along its normal.
+	pub offset: Real,
+}
+
+impl CollisionPlane {
+	#[must_use]
+	pub const fn new(normal: Vector3, offset: Real) -> Self {
+		Self { normal, offset }
+	}
+
+	/// Returns how far the given point is above the plane, along its normal.
+	#[must_use]
+	pub fn distance_to(&self, point: Vector3) -> Real {
+		point.dot(&self.normal) - self.offset
+	}
+}
+
+/// A contact between two rigid bodies, or between a body and the scenery.
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct Contact {
+	/// Holds the indices of the bodies involved in the contact.
+	/// The second of these is `None` for contacts with the scenery.
+	pub bodies: [Option<usize>; 2],
+
+	/// Holds the lateral friction coefficient at the contact.
+	pub friction: Real,
+
+	/// Holds the rolling friction coefficient at the contact, as the
+	/// length by which the normal impulse is multiplied to give the most
+	/// angular impulse that resists rolling.
+	pub rolling_friction: Real,
+
+	/// Holds the normal restitution coefficient at the contact.
+	pub restitution: Real,
+
+	/// Holds the position of the contact in world coordinates.
+	pub contact_point: Vector3,
+
+	/// Holds the direction of the contact in world coordinates,
+	/// from the point of view of the first body.
+	pub contact_normal: Vector3,
+
+	/// Holds the depth of penetration at the contact point.
+	pub penetration: Real,
+
+	/// Identifies the features of the shapes that produced the contact, such
+	/// as a vertex of a box, so that it can be recognized in later frames.
+	/// It is unique among the contacts between one pair of shapes, and zero
+	/// when the detector gives no feature.
+	pub feature: usize,
+}
+
+/// Collects the contacts found by the collision detectors.
+///
+/// Every contact is given the friction and restitution held here, and
+/// detectors stop adding contacts once the limit has been reached.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct CollisionData {
+	/// Holds the contacts found so far.
+	pub contacts: Vec<Contact>,
+
+	/// Holds the most contacts that may be found.
+	pub max_contacts: usize,
+
+	/// Holds the friction given to each new contact.
+	pub friction: Real,
+
+	/// Holds the rolling friction given to each new contact.
+	pub rolling_friction: Real,
+
+	/// Holds the restitution given to each new contact.
+	pub restitution: Real,
+
+	/// Holds how far apart two shapes may be and still be reported as in
+	/// contact, in addition to the margins of the shapes themselves.
+	pub tolerance: Real,
+
+	/// Holds how the friction, and rolling friction, of two materials is combined.
+	pub friction_combine: CombineRule,
+
+	/// Holds how the restitution of two materials is combined.
+	pub restitution_combine: CombineRule,
+}
+
+impl CollisionData {
+	#[must_use]
+	pub fn new(max_contacts: usize) -> Self {
+		Self {
+			contacts: Vec::with_capacity(max_contacts),
+			max_contacts,
+			friction: 0.0,
+			rolling_friction: 0.0,
+			restitution: 1.0,
+			tolerance: 0.0,
+			friction_combine: CombineRule::default(),
+			restitution_combine: CombineRule::default(),
+		}
+	}
+
+	/// Returns how many more contacts may be added.
+	#[must_use]
+	pub const fn contacts_left(&self) -> usize {
+		self.max_contacts.saturating_sub(self.contacts.len())
+	}
+
+	#[must_use]
+	pub const fn has_more_contacts(&self) -> bool {
+		self.contacts_left() > 0
+	}
+
+	/// Removes every contact, ready for the next step.
+	pub fn reset(&mut self) {
+		self.contacts.clear();
+	}
+
+	/// Runs a detector with the tolerance widened by the margins of the
+	/// shapes being tested, restoring it afterwards.
+	fn with_margins(&mut self, margins: Real, detect: impl FnOnce(&mut Self) -> usize) -> usize {
+		let tolerance = self.tolerance;
+		self.tolerance += margins;
+		let added = detect(self);
+		self.tolerance = tolerance;
+		added
+	}
+
+	/// Runs the detector with the friction, rolling friction and restitution
+	/// given to new contacts combined from the given materials, returning the
+	/// number of contacts added.
+	///
+	/// A side without a material, such as the scenery, takes this data's
+	/// own values. If neither side has one, they are used unchanged.
+	pub fn with_materials(
+		&mut self,
+		materials: [Option<&Material>; 2],
+		detect: impl FnOnce(&mut Self) -> usize,
+	) -> usize {
+		if materials.iter().all(Option::is_none) {
+			return detect(self);
+		}
+		let own = Material {
+			friction: self.friction,
+			rolling_friction: self.rolling_friction,
+			restitution: self.restitution,
+			..Material::default()
+		};
+		let [first, second] = materials.map(|material| *material.unwrap_or(&own));
+		self.friction = self.friction_combine.combine(first.friction, second.friction);
+		self.rolling_friction = self
+			.friction_combine
+			.combine(first.rolling_friction, second.rolling_friction);
+		self.restitution = self.restitution_combine.combine(first.restitution, second.restitution);
+		let added = detect(self);
+		self.friction = own.friction;
+		self.rolling_friction = own.rolling_friction;
+		self.restitution = own.restitution;
+		added
+	}
+
+	/// Adds a contact between the given bodies with this data's friction
+	/// and restitution, returning the number of contacts added.
+	fn add_contact(
+		&mut self,
+		bodies: [Option<usize>; 2],
+		contact_point: Vector3,
+		contact_normal: Vector3,
+		penetration: Real,
+	) -> usize {
+		if !self.has_more_contacts() {
+			return 0;
+		}
+		self.contacts.push(Contact {
+			bodies,
+			friction: self.friction,
+			rolling_friction: self.rolling_friction,
+			restitution: self.restitution,
+			contact_point,
+			contact_normal,
+			penetration,
+			feature: 0,
+		});
+		1
+	}
+
+	/// Marks the contacts just added with the given feature, returning the number added.
+	fn mark_feature(&mut self, added: usize, feature: usize) -> usize {
+		let start = self.contacts.len() - added;
+		for contact in &mut self.contacts[start..] {
+			contact.feature = feature;
+		}
+		added
+	}
+}
+
+/// Detects contact between two spheres, returning the number of contacts added.
+///
+/// The contact point is midway between the two surfaces along the line
+/// joining the centers, and the normal points from the second sphere to the first.
+pub fn sphere_and_sphere(first: &CollisionSphere, second: &CollisionSphere, data: &mut CollisionData) -> usize {
+	data.with_margins(first.primitive.margin + second.primitive.margin, |data| {
+		ball_and_ball(&first.ball(), &second.ball(), data)
+	})
+}
+
+fn ball_and_ball(first: &Ball, second: &Ball, data: &mut CollisionData) -> usize {
+	let midline = first.center - second.center;
+	let size = midline.magnitude();
+	if size <= 0.0 || size >= first.radius + second.radius + data.tolerance {
+		return 0;
+	}
+
+	let normal = midline * size.recip();
+	let first_surface = first.center - normal * first.radius;
+	let second_surface = second.center + normal * second.radius;
+	data.add_contact(
+		[Some(first.body), Some(second.body)],
+		(first_surface + second_surface) * 0.5,
+		normal,
+		first.radius + second.radius - size,
+	)
+}
+
+/// Detects contact between a sphere and the half-space behind a plane,
+/// returning the number of contacts added.
+///
+/// Everything behind the plane is treated as solid, so a sphere that has
+/// passed right through it is pushed back out the front.
+pub fn sphere_and_half_space(sphere: &CollisionSphere, plane: &CollisionPlane, data: &mut CollisionData) -> usize {
+	data.with_margins(sphere.primitive.margin, |data| {
+		ball_and_half_space(&sphere.ball(), plane, data)
+	})
+}
+
+fn ball_and_half_space(ball: &Ball, plane: &CollisionPlane, data: &mut CollisionData) -> usize {
+	let distance = plane.distance_to(ball.center) - ball.radius;
+	if distance >= data.tolerance {
+		return 0;
+	}
+	data.add_contact(
+		[Some(ball.body), None],
+		ball.center - plane.normal * (distance + ball.radius),
+		plane.normal,
+		-distance,
+	)
+}
+
+/// Detects contact between a sphere and a plane that is solid on neither side,
+/// returning the number of contacts added.
+///
+/// The sphere is pushed out to whichever side its center is on.
+pub fn sphere_and_true_plane(sphere: &CollisionSphere, plane: &CollisionPlane, data: &mut CollisionData) -> usize {
+	data.with_margins(sphere.primitive.margin, |data| {
+		let position = sphere.primitive.position();
+		let center_distance = plane.distance_to(position);
+		if center_distance.abs() >= sphere.radius + data.tolerance {
+			return 0;
+		}
+		let normal = if center_distance < 0.0 {
+			-plane.normal
+		} else {
+			plane.normal
+		};
+		data.add_contact(
+			[Some(sphere.primitive.body), None],
+			position - plane.normal * center_distance,
+			normal,
+			sphere.radius - center_distance.abs(),
+		)
+	})
+}
+
+/// The most contacts generated between a box or cylinder and a half-space.
+/// Four points are enough to hold either steady on a flat face.
+const MAX_PLANE_CONTACTS: usize = 4;
+
+/// Detects contact between a box and the half-space behind a plane,
+/// returning the number of contacts added.
+///
+/// Each vertex at or behind the plane, within the data's tolerance, is a
+/// candidate, and the deepest four become contacts at the point on the
+/// plane beneath them.
+pub fn box_and_half_space(cuboid: &CollisionBox, plane: &CollisionPlane, data: &mut CollisionData) -> usize {
+	data.with_margins(cuboid.primitive.margin, |data| {
+		// Rule out a box that is clear of the plane before checking each vertex
+		let center_distance = plane.distance_to(cuboid.primitive.position());
+		if center_distance - cuboid.projected_half_length(plane.normal) > data.tolerance {
+			return 0;
+		}
+
+		points_and_half_space(cuboid.primitive.body, &cuboid.vertices(), plane, data)
+	})
+}
+
+/// Adds contacts for the deepest of the given points on a body that lie
+/// at or behind the plane, within the data's tolerance. Each contact's
+/// feature is one more than the index of its point.
+fn points_and_half_space(body: usize, points: &[Vector3], plane: &CollisionPlane, data: &mut CollisionData) -> usize {
+	let mut candidates: Vec<(usize, Vector3, Real)> = points
+		.iter()
+		.enumerate()
+		.map(|(index, point)| (index, *point, plane.distance_to(*point)))
+		.filter(|(_, _, distance)| *distance <= data.tolerance)
+		.collect();
+	candidates.sort_by(|first, second| first.2.total_cmp(&second.2));
+	candidates
+		.into_iter()
+		.take(MAX_PLANE_CONTACTS)
+		.map(|(index, point, distance)| {
+			let added = data.add_contact(
+				[Some(body), None],
+				point - plane.normal * distance,
+				plane.normal,
+				-distance,
+			);
+			data.mark_feature(added, index + 1)
+		})
+		.sum()
+}
+
+/// Detects contact between a box and a sphere, returning the number of contacts added.
+///
+/// The contact is at the point on the box closest to the sphere's center,
+/// with the normal pointing from the sphere to the box. When the center is
+/// inside the box, the sphere is pushed out through the nearest face.
+pub fn box_and_sphere(cuboid: &CollisionBox, sphere: &CollisionSphere, data: &mut CollisionData) -> usize {
+	data.with_margins(cuboid.primitive.margin + sphere.primitive.margin, |data| {
+		box_and_ball(cuboid, &sphere.ball(), data)
+	})
+}
+
+fn box_and_ball(cuboid: &CollisionBox, ball: &Ball, data: &mut CollisionData) -> usize {
+	let center = ball.center;
+	let transform = cuboid.primitive.transform();
+	let relative_center = transform.transform_inverse(center);
+
+	// Rule out a sphere that is clear of the box along any of its axes
+	let reach = ball.radius + data.tolerance;
+	if (0..3).any(|axis| relative_center[axis].abs() - reach > cuboid.half_size[axis]) {
+		return 0;
+	}
+
+	let mut closest = relative_center;
+	for axis in 0..3 {
+		closest[axis] = relative_center[axis].clamp(-cuboid.half_size[axis], cuboid.half_size[axis]);
+	}
+	let bodies = [Some(cuboid.primitive.body), Some(ball.body)];
+	let offset = closest - relative_center;
+	let distance_squared = offset.magnitude_squared();
+	if distance_squared > 0.0 {
+		if distance_squared > reach * reach {
+			return 0;
+		}
+		let closest_world = transform.transform(closest);
+		let distance = distance_squared.sqrt();
+		return data.add_contact(
+			bodies,
+			closest_world,
+			(closest_world - center) * distance.recip(),
+			ball.radius - distance,
+		);
+	}
+
+	// The center is inside the box, so push the sphere out of the nearest face
+	let depths = cuboid.half_size
+		- Vector3::new(
+			relative_center.x().abs(),
+			relative_center.y().abs(),
+			relative_center.z().abs(),
+		);
+	let axis = (0..3)
+		.min_by(|first, second| depths[*first].total_cmp(&depths[*second]))
+		.unwrap_or_default();
+	let side = if relative_center[axis] < 0.0 { -1.0 } else { 1.0 };
+	let mut face_point = relative_center;
+	face_point[axis] = side * cuboid.half_size[axis];
+	data.add_contact(
+		bodies,
+		transform.transform(face_point),
+		cuboid.primitive.axis(axis) * -side,
+		ball.radius + depths[axis],
+	)
+}
+
+/// Returns how far two boxes overlap along the given unit axis. A negative
+/// result means the axis separates them.
+fn penetration_on_axis(first: &CollisionBox, second: &CollisionBox, axis: Vector3, to_center: Vector3) -> Real {
+	first.projected_half_length(axis) + second.projected_half_length(axis) - to_center.dot(&axis).abs()
+}
+
+/// An edge of a box in world coordinates, given by its midpoint,
+/// its direction, and half its length.
+struct Edge {
+	midpoint: Vector3,
+	direction: Vector3,
+	half_length: Real,
+}
+
+/// Returns the point midway between the closest points on two edges. If the
+/// edges are parallel or their closest points lie beyond either end, the
+/// midpoint of the first or second edge is used instead, as chosen.
+fn closest_point_between_edges(first: &Edge, second: &Edge, use_first: bool) -> Vector3 {
+	let fallback = if use_first { first.midpoint } else { second.midpoint };
+	let to_start = first.midpoint - second.midpoint;
+	let first_projection = first.direction.dot(&to_start);
+	let second_projection = second.direction.dot(&to_start);
+	let first_squared = first.direction.magnitude_squared();
+	let second_squared = second.direction.magnitude_squared();
+	let directions = second.direction.dot(&first.direction);
+
+	let denominator = first_squared.mul_add(second_squared, -directions * directions);
+	if denominator.abs() < 0.0001 {
+		return fallback;
+	}
+	let first_along = directions.mul_add(second_projection, -second_squared * first_projection) / denominator;
+	let second_along = first_squared.mul_add(second_projection, -directions * first_projection) / denominator;
+	if first_along.abs() > first.half_length || second_along.abs() > second.half_length {
+		return fallback;
+	}
+	(first.midpoint + first.direction * first_along + second.midpoint + second.direction * second_along) * 0.5
+}
+
+/// Adds a contact between a vertex of the incident box and a face of the
+/// reference box, where the normal is the face's normal turned away from
+/// the incident box.
+///
+/// The contact's feature is made from the overlapping axis and the corner.
+fn box_vertex_and_face(
+	reference: &CollisionBox,
+	incident: &CollisionBox,
+	normal: Vector3,
+	overlap: &LeastOverlap,
+	data: &mut CollisionData,
+) -> usize {
+	// The vertex is the incident box's corner deepest into the face
+	let mut vertex = incident.half_size;
+	let mut corner = 0;
+	for index in 0..3 {
+		if incident.primitive.axis(index).dot(&normal) < 0.0 {
+			vertex[index] = -vertex[index];
+			corner |= 1 << index;
+		}
+	}
+	let added = data.add_contact(
+		[Some(reference.primitive.body), Some(incident.primitive.body)],
+		incident.primitive.transform().transform(vertex),
+		normal,
+		overlap.penetration,
+	);
+	data.mark_feature(added, overlap.index * 8 + corner + 1)
+}
+
+/// Returns the edge of the box along the given axis that lies furthest in the given direction.
+fn box_edge(cuboid: &CollisionBox, axis: usize, direction: Vector3) -> Edge {
+	let mut midpoint = cuboid.half_size;
+	for index in 0..3 {
+		if index == axis {
+			midpoint[index] = 0.0;
+		} else if cuboid.primitive.axis(index).dot(&direction) < 0.0 {
+			midpoint[index] = -midpoint[index];
+		}
+	}
+	Edge {
+		midpoint: cuboid.primitive.transform().transform(midpoint),
+		direction: cuboid.primitive.axis(axis),
+		half_length: cuboid.half_size[axis],
+	}
+}
+
+/// The axis along which two boxes overlap least.
+struct LeastOverlap {
+	/// Holds the index of the axis: 0 to 2 for the first box's faces, 3 to 5
+	/// for the second box's faces, and 6 to 14 for pairs of their edges.
+	index: usize,
+	axis: Vector3,
+	penetration: Real,
+
+	/// Holds the index of the face axis that overlapped least.
+	face_index: usize,
+}
+
+/// Tests the fifteen candidate separating axes of two boxes, returning the
+/// axis of least overlap, or `None` if any axis separates them by more
+/// than the tolerance.
+fn least_overlap(
+	first: &CollisionBox,
+	second: &CollisionBox,
+	to_center: Vector3,
+	tolerance: Real,
+) -> Option<LeastOverlap> {
+	let mut candidates = Vec::with_capacity(15);
+	candidates.extend((0..3).map(|index| first.primitive.axis(index)));
+	candidates.extend((0..3).map(|index| second.primitive.axis(index)));
+	for first_index in 0..3 {
+		for second_index in 0..3 {
+			candidates.push(
+				first
+					.primitive
+					.axis(first_index)
+					.cross(&second.primitive.axis(second_index)),
+			);
+		}
+	}
+
+	let mut best: Option<LeastOverlap> = None;
+	let mut face_index = 0;
+	for (index, axis) in candidates.into_iter().enumerate() {
+		// Parallel edges have no cross product, and are covered by the face axes
+		if axis.magnitude_squared() < 0.001 {
+			continue;
+		}
+		let axis = axis.normalize();
+		let penetration = penetration_on_axis(first, second, axis, to_center);
+		if penetration < -tolerance {
+			return None;
+		}
+		if best.as_ref().is_none_or(|best| penetration < best.penetration) {
+			best = Some(LeastOverlap {
+				index,
+				axis,
+				penetration,
+				face_index,
+			});
+		}
+		if index < 6 {
+			face_index = best.as_ref().map_or(0, |best| best.index);
+		}
+	}
+	best.map(|best| LeastOverlap { face_index, ..best })
+}
+
+/// Detects contact between two boxes with the separating axis test,
+/// returning the number of contacts added.
+///
+/// The fifteen candidate axes are the three face normals of each box and
+/// the nine cross products of their edges. If none separates the boxes,
+/// the axis of least overlap gives either a vertex-face contact or an
+/// edge-edge contact. The normal points towards the first body of the contact.
+pub fn box_and_box(first: &CollisionBox, second: &CollisionBox, data: &mut CollisionData) -> usize {
+	data.with_margins(first.primitive.margin + second.primitive.margin, |data| {
+		let to_center = second.primitive.position() - first.primitive.position();
+		let Some(overlap) = least_overlap(first, second, to_center, data.tolerance) else {
+			return 0;
+		};
+		// Turn the axis to point from the second box towards the first
+		let normal = if overlap.axis.dot(&to_center) > 0.0 {
+			-overlap.axis
+		} else {
+			overlap.axis
+		};
+		match overlap.index {
+			0..=2 => box_vertex_and_face(first, second, normal, &overlap, data),
+			3..=5 => box_vertex_and_face(second, first, -normal, &overlap, data),
+			index => {
+				let first_edge = box_edge(first, (index - 6) / 3, -normal);
+				let second_edge = box_edge(second, (index - 6) % 3, normal);
+				let point = closest_point_between_edges(&first_edge, &second_edge, overlap.face_index > 2);
+				let added = data.add_contact(
+					[Some(first.primitive.body), Some(second.primitive.body)],
+					point,
+					normal,
+					overlap.penetration,
+				);
+				data.mark_feature(added, index * 8 + 1)
+			},
+		}
+	})
+}
+
+/// Returns the closest points between two segments, one on each.
+fn closest_points_between_segments(first: [Vector3; 2], second: [Vector3; 2]) -> [Vector3; 2] {
+	let first_direction = first[1] - first[0];
+	let second_direction = second[1] - second[0];
+	let offset = first[0] - second[0];
+	let first_squared = first_direction.magnitude_squared();
+	let second_squared = second_direction.magnitude_squared();
+	let second_offset = second_direction.dot(&offset);
+
+	if first_squared <= Real::EPSILON {
+		return [first[0], closest_point_on_segment(second, first[0])];
+	}
+	let first_offset = first_direction.dot(&offset);
+	if second_squared <= Real::EPSILON {
+		return [closest_point_on_segment(first, second[0]), second[0]];
+	}
+	let directions = first_direction.dot(&second_direction);
+	let denominator = first_squared.mul_add(second_squared, -directions * directions);
+
+	// Parallel segments have no single closest pair, so start from the first segment's start
+	let mut first_along = if denominator > Real::EPSILON {
+		(directions.mul_add(second_offset, -first_offset * second_squared) / denominator).clamp(0.0, 1.0)
+	} else {
+		0.0
+	};
+	let mut second_along = directions.mul_add(first_along, second_offset) / second_squared;
+	if !(0.0..=1.0).contains(&second_along) {
+		second_along = second_along.clamp(0.0, 1.0);
+		first_along = (directions.mul_add(second_along, -first_offset) / first_squared).clamp(0.0, 1.0);
+	}
+	[
+		first[0] + first_direction * first_along,
+		second[0] + second_direction * second_along,
+	]
+}
+
+/// Detects contact between a capsule and a sphere, returning the number of contacts added.
+///
+/// The capsule is tested as the sphere centered on the closest point of
+/// its segment to the sphere's center.
+pub fn capsule_and_sphere(capsule: &CollisionCapsule, sphere: &CollisionSphere, data: &mut CollisionData) -> usize {
+	data.with_margins(capsule.primitive.margin + sphere.primitive.margin, |data| {
+		let center = sphere.primitive.position();
+		let closest = closest_point_on_segment(capsule.segment(), center);
+		ball_and_ball(&capsule.ball_at(closest), &sphere.ball(), data)
+	})
+}
+
+/// Detects contact between two capsules, returning the number of contacts added.
+///
+/// Each capsule is tested as the sphere centered on the closest point of its
+/// segment to the other's segment.
+pub fn capsule_and_capsule(first: &CollisionCapsule, second: &CollisionCapsule, data: &mut CollisionData) -> usize {
+	data.with_margins(first.primitive.margin + second.primitive.margin, |data| {
+		let [first_point, second_point] = closest_points_between_segments(first.segment(), second.segment());
+		ball_and_ball(&first.ball_at(first_point), &second.ball_at(second_point), data)
+	})
+}
+
+/// Detects contact between a capsule and the half-space behind a plane,
+/// returning the number of contacts added.
+///
+/// Each end of the capsule is tested as a sphere, so a capsule lying flat
+/// on the plane gets a contact at both ends and does not rock.
+pub fn capsule_and_half_space(capsule: &CollisionCapsule, plane: &CollisionPlane, data: &mut CollisionData) -> usize {
+	data.with_margins(capsule.primitive.margin, |data| {
+		capsule
+			.segment()
+			.into_iter()
+			.map(|end| ball_and_half_space(&capsule.ball_at(end), plane, data))
+			.sum()
+	})
+}
+
+/// How many times the closest points between a segment and a convex shape
+/// are refined. Each pass moves to the segment point nearest the point on
+/// the shape found by the last.
+const SEGMENT_REFINEMENTS: usize = 4;
+
+/// Returns the point on the segment that is approximately closest to a
+/// convex shape, given the shape's center and its closest point to any point.
+fn closest_point_on_segment_to(
+	segment: [Vector3; 2],
+	center: Vector3,
+	closest_on_shape: impl Fn(Vector3) -> Vector3,
+) -> Vector3 {
+	let mut point = closest_point_on_segment(segment, center);
+	for _ in 0..SEGMENT_REFINEMENTS {
+		point = closest_point_on_segment(segment, closest_on_shape(point));
+	}
+	point
+}
+
+fn closest_point_on_segment_to_box(segment: [Vector3; 2], cuboid: &CollisionBox) -> Vector3 {
+	let transform = cuboid.primitive.transform();
+	let shape = Shape::Cuboid {
+		half_size: cuboid.half_size,
+	};
+	closest_point_on_segment_to(segment, cuboid.primitive.position(), |point| {
+		transform.transform(shape.closest_point(transform.transform_inverse(point)))
+	})
+}
+
+/// Detects contact between a capsule and a box, returning the number of contacts added.
+///
+/// Each end of the capsule is tested as a sphere against the box. If
+/// neither end touches, the capsule may still cross an edge or face of the
+/// box, so the sphere at the segment point closest to the box is tested.
+pub fn capsule_and_box(capsule: &CollisionCapsule, cuboid: &CollisionBox, data: &mut CollisionData) -> usize {
+	data.with_margins(capsule.primitive.margin + cuboid.primitive.margin, |data| {
+		let segment = capsule.segment();
+		let added: usize = segment
+			.into_iter()
+			.map(|end| box_and_ball(cuboid, &capsule.ball_at(end), data))
+			.sum();
+		if added > 0 {
+			return added;
+		}
+
+		let point = closest_point_on_segment_to_box(segment, cuboid);
+		box_and_ball(cuboid, &capsule.ball_at(point), data)
+	})
+}
+
+/// Detects contact between a cylinder and the half-space behind a plane,
+/// returning the number of contacts added.
+///
+/// The deepest points on the rims of the caps become contacts, so a
+/// cylinder lying on its side touches along a line and one standing on
+/// its end rests on its rim.
+pub fn cylinder_and_half_space(
+	cylinder: &CollisionCylinder,
+	plane: &CollisionPlane,
+	data: &mut CollisionData,
+) -> usize {
+	data.with_margins(cylinder.primitive.margin, |data| {
+		let points = cylinder.rim_points(-plane.normal);
+		points_and_half_space(cylinder.primitive.body, &points, plane, data)
+	})
+}
+
+/// Detects contact between a cylinder and a sphere, returning the number of contacts added.
+///
+/// The contact is at the point on the cylinder closest to the sphere's
+/// center, with the normal pointing from the sphere to the cylinder. When
+/// the center is inside the cylinder, the sphere is pushed out through the
+/// nearest of its side and caps.
+pub fn cylinder_and_sphere(cylinder: &CollisionCylinder, sphere: &CollisionSphere, data: &mut CollisionData) -> usize {
+	data.with_margins(cylinder.primitive.margin + sphere.primitive.margin, |data| {
+		cylinder_and_ball(cylinder, &sphere.ball(), data)
+	})
+}
+
+fn cylinder_and_ball(cylinder: &CollisionCylinder, ball: &Ball, data: &mut CollisionData) -> usize {
+	let reach = ball.radius + data.tolerance;
+	let transform = cylinder.primitive.transform();
+	let relative_center = transform.transform_inverse(ball.center);
+	let closest = cylinder.shape().closest_point(relative_center);
+	let bodies = [Some(cylinder.primitive.body), Some(ball.body)];
+	let offset = closest - relative_center;
+	let distance_squared = offset.magnitude_squared();
+	if distance_squared > 0.0 {
+		if distance_squared > reach * reach {
+			return 0;
+		}
+		let closest_world = transform.transform(closest);
+		let distance = distance_squared.sqrt();
+		return data.add_contact(
+			bodies,
+			closest_world,
+			(closest_world - ball.center) * distance.recip(),
+			ball.radius - distance,
+		);
+	}
+
+	// The center is inside the cylinder, so push the sphere out of the nearest surface
+	let radial = Vector3::new(relative_center.x(), 0.0, relative_center.z());
+	let side_depth = cylinder.radius - radial.magnitude();
+	let cap_depth = cylinder.half_height - relative_center.y().abs();
+	let (outward, depth) = if side_depth < cap_depth {
+		let direction = if radial.magnitude_squared() > 0.0 {
+			radial.normalize()
+		} else {
+			Vector3::x_axis()
+		};
+		(direction, side_depth)
+	} else {
+		let side = if relative_center.y() < 0.0 { -1.0 } else { 1.0 };
+		(Vector3::new(0.0, side, 0.0), cap_depth)
+	};
+	data.add_contact(
+		bodies,
+		transform.transform(relative_center + outward * depth),
+		-transform.transform_direction(outward),
+		ball.radius + depth,
+	)
+}
+
+/// Detects contact between a cylinder and a box, returning the number of contacts added.
+///
+/// Points on the cylinder's rims that reach into the box and vertices of
+/// the box inside the cylinder become contacts, all named with the box
+/// first. If there are none, the cylinder may still cross an edge or face
+/// of the box with its side, so the sphere of the cylinder's radius around
+/// the point on its axis closest to the box is tested.
+pub fn cylinder_and_box(cylinder: &CollisionCylinder, cuboid: &CollisionBox, data: &mut CollisionData) -> usize {
+	data.with_margins(cylinder.primitive.margin + cuboid.primitive.margin, |data| {
+		// Take the rim points deepest into the face of the box the cylinder is beyond
+		let relative_center = cuboid
+			.primitive
+			.transform()
+			.transform_inverse(cylinder.primitive.position());
+		let face = (0..3)
+			.max_by(|first, second| {
+				(relative_center[*first].abs() / cuboid.half_size[*first])
+					.total_cmp(&(relative_center[*second].abs() / cuboid.half_size[*second]))
+			})
+			.unwrap_or_default();
+		let side = if relative_center[face] < 0.0 { -1.0 } else { 1.0 };
+		let rim_contacts: usize = cylinder
+			.rim_points(cuboid.primitive.axis(face) * -side)
+			.into_iter()
+			.map(|point| box_and_ball(cuboid, &cylinder.ball_at(point, 0.0), data))
+			.sum();
+
+		let start = data.contacts.len();
+		for vertex in cuboid.vertices() {
+			let corner = Ball {
+				body: cuboid.primitive.body,
+				center: vertex,
+				radius: 0.0,
+			};
+			cylinder_and_ball(cylinder, &corner, data);
+		}
+		for contact in &mut data.contacts[start..] {
+			contact.bodies.swap(0, 1);
+			contact.contact_normal = -contact.contact_normal;
+		}
+		let added = rim_contacts + data.contacts.len() - start;
+		if added > 0 {
+			return added;
+		}
+
+		let point = closest_point_on_segment_to_box(cylinder.segment(), cuboid);
+		box_and_ball(cuboid, &cylinder.ball_at(point, cylinder.radius), data)
+	})
+}
+
+/// Detects contact between any two convex shapes with support mappings,
+/// returning the number of contacts added.
+///
+/// This covers pairs without a dedicated detector, at the cost of finding a
+/// single contact, midway between the deepest point of each shape inside
+/// the other. The normal points from the second shape to the first, and the
+/// second body is `None` for scenery.
+pub fn convex_and_convex<F: SupportMap + ?Sized, S: SupportMap + ?Sized>(
+	first: &F,
+	second: &S,
+	bodies: [Option<usize>; 2],
+	data: &mut CollisionData,
+) -> usize {
+	data.with_margins(first.margin() + second.margin(), |data| {
+		convex_overlap(first, second, bodies, data)
+	})
+}
+
+fn convex_overlap<F: SupportMap + ?Sized, S: SupportMap + ?Sized>(
+	first: &F,
+	second: &S,
+	bodies: [Option<usize>; 2],
+	data: &mut CollisionData,
+) -> usize {
+	if let Some(overlap) = penetration(first, second) {
+		let [on_first, on_second] = overlap.points;
+		return data.add_contact(bodies, (on_first + on_second) * 0.5, -overlap.normal, overlap.depth);
+	}
+	if data.tolerance <= 0.0 {
+		return 0;
+	}
+	match closest_points(first, second) {
+		Some(closest) if closest.distance > 0.0 && closest.distance <= data.tolerance => {
+			let [on_first, on_second] = closest.points;
+			data.add_contact(
+				bodies,
+				(on_first + on_second) * 0.5,
+				(on_first - on_second) * closest.distance.recip(),
+				-closest.distance,
+			)
+		},
+		_ => 0,
+	}
+}
+
+/// Returns the triangles of the mesh near the shape whose front faces
+/// look toward the shape's center.
+fn facing_triangles<S: SupportMap + ?Sized>(shape: &S, mesh: &TriMesh, tolerance: Real) -> Vec<Triangle> {
+	let bounds = shape.bounds();
+	let margin = Vector3::new(tolerance, tolerance, tolerance);
+	let center = shape.center();
+	mesh.query(&Aabb::new(bounds.min - margin, bounds.max + margin))
+		.into_iter()
+		.map(|index| mesh.triangle(index))
+		.filter(|triangle| triangle.normal().dot(&(center - triangle.vertices[0])) >= 0.0)
+		.collect()
+}
+
+fn ball_and_triangle(ball: &Ball, triangle: &Triangle, data: &mut CollisionData) -> usize {
+	let reach = ball.radius + data.tolerance;
+	let closest = triangle.closest_point(ball.center);
+	let offset = ball.center - closest;
+	let distance_squared = offset.magnitude_squared();
+	if distance_squared >= reach * reach {
+		return 0;
+	}
+	let distance = distance_squared.sqrt();
+	let normal = if distance > 0.0 {
+		offset * distance.recip()
+	} else {
+		triangle.normal()
+	};
+	data.add_contact([Some(ball.body), None], closest, normal, ball.radius - distance)
+}
+
+/// Detects contact between a sphere and a static mesh, returning the number of contacts added.
+///
+/// Each nearby triangle facing the sphere's center gives a contact at its
+/// point closest to the center. A sphere over an edge shared by two
+/// triangles may be given a contact by each.
+pub fn sphere_and_mesh(sphere: &CollisionSphere, mesh: &TriMesh, data: &mut CollisionData) -> usize {
+	data.with_margins(sphere.primitive.margin, |data| {
+		let ball = sphere.ball();
+		facing_triangles(sphere, mesh, data.tolerance)
+			.iter()
+			.map(|triangle| ball_and_triangle(&ball, triangle, data))
+			.sum()
+	})
+}
+
+/// Detects contact between a capsule and a static mesh, returning the number of contacts added.
+///
+/// Against each nearby triangle facing the capsule, its ends are tested as
+/// spheres, so that a capsule lying on the mesh rests on both. If neither
+/// end touches, the sphere at the segment point closest to the triangle is tested.
+pub fn capsule_and_mesh(capsule: &CollisionCapsule, mesh: &TriMesh, data: &mut CollisionData) -> usize {
+	data.with_margins(capsule.primitive.margin, |data| {
+		let segment = capsule.segment();
+		facing_triangles(capsule, mesh, data.tolerance)
+			.iter()
+			.map(|triangle| {
+				let added: usize = segment
+					.into_iter()
+					.map(|end| ball_and_triangle(&capsule.ball_at(end), triangle, data))
+					.sum();
+				if added > 0 {
+					return added;
+				}
+				let point =
+					closest_point_on_segment_to(segment, triangle.centroid(), |point| triangle.closest_point(point));
+				ball_and_triangle(&capsule.ball_at(point), triangle, data)
+			})
+			.sum()
+	})
+}
+
+/// Detects contact between a box and a static mesh, returning the number of contacts added.
+///
+/// Against each nearby triangle facing the box, the deepest four vertices
+/// behind the triangle's plane that lie over the triangle become contacts,
+/// as they would against a half-space. If there are none, the box may
+/// still straddle an edge of the triangle, so the two are tested as convex shapes.
+pub fn box_and_mesh(cuboid: &CollisionBox, mesh: &TriMesh, data: &mut CollisionData) -> usize {
+	data.with_margins(cuboid.primitive.margin, |data| {
+		let body = cuboid.primitive.body;
+		facing_triangles(cuboid, mesh, data.tolerance)
+			.iter()
+			.map(|triangle| {
+				let normal = triangle.normal();
+				let plane = CollisionPlane::new(normal, normal.dot(&triangle.vertices[0]));
+				let over: Vec<Vector3> = cuboid
+					.vertices()
+					.into_iter()
+					.filter(|vertex| triangle.contains_projection(*vertex))
+					.collect();
+				match points_and_half_space(body, &over, &plane, data) {
+					0 => convex_overlap(cuboid, triangle, [Some(body), None], data),
+					added => added,
+				}
+			})
+			.sum()
+	})
+}
+
+/// Detects contact between any convex shape and a static mesh, returning
+/// the number of contacts added.
+///
+/// Each nearby triangle facing the shape is tested with [`convex_and_convex`],
+/// giving at most one contact per triangle.
+pub fn convex_and_mesh<S: SupportMap + ?Sized>(
+	shape: &S,
+	body: usize,
+	mesh: &TriMesh,
+	data: &mut CollisionData,
+) -> usize {
+	data.with_margins(shape.margin(), |data| {
+		facing_triangles(shape, mesh, data.tolerance)
+			.iter()
+			.map(|triangle| convex_overlap(shape, triangle, [Some(body), None], data))
+			.sum()
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{assert_equal, body::RigidBody, quaternion::Quaternion};
+
+	use super::*;
+
+	#[test]
+	pub fn primitive_follows_its_body() {
+		let mut body = RigidBody {
+			position: Vector3::new(1.0, 2.0, 3.0),
+			orientation: Quaternion::from_axis_angle(Vector3::z_axis(), std::f32::consts::FRAC_PI_2),
+			..Default::default()
+		};
+		body.calculate_derived_data();
+		let bodies = [body];
+
+		let offset = Matrix4::from_transform(&Quaternion::identity(), Vector3::x_axis());
+		let mut sphere = CollisionSphere {
+			primitive: CollisionPrimitive::with_offset(0, offset),
+			radius: 0.5,
+		};
+		sphere.primitive.calculate_internals(&bodies);
+		assert!((sphere.primitive.position() - Vector3::new(1.0, 3.0, 3.0)).magnitude() < 1e-5);
+		assert!((sphere.primitive.axis(0) - Vector3::y_axis()).magnitude() < 1e-5);
+
+		let mut cuboid = CollisionBox {
+			primitive: CollisionPrimitive::new(0),
+			half_size: Vector3::new(1.0, 1.0, 1.0),
+		};
+		cuboid.primitive.calculate_internals(&bodies);
+		assert_eq!(cuboid.primitive.transform(), &bodies[0].transform_matrix);
+	}
+
+	fn sphere(body: usize, radius: Real, bodies: &[RigidBody]) -> CollisionSphere {
+		let mut sphere = CollisionSphere {
+			primitive: CollisionPrimitive::new(body),
+			radius,
+		};
+		sphere.primitive.calculate_internals(bodies);
+		sphere
+	}
+
+	fn bodies_at(positions: &[Vector3]) -> Vec<RigidBody> {
+		positions
+			.iter()
+			.map(|position| {
+				let mut body = RigidBody {
+					position: *position,
+					inverse_mass: 1.0,
+					..Default::default()
+				};
+				body.calculate_derived_data();
+				body
+			})
+			.collect()
+	}
+
+	#[test]
+	pub fn sphere_and_sphere_contact() {
+		let bodies = bodies_at(&[
+			Vector3::new(1.5, 0.0, 0.0),
+			Vector3::zero(),
+			Vector3::new(5.0, 0.0, 0.0),
+		]);
+		let mut data = CollisionData::new(4);
+		data.friction = 0.5;
+		data.restitution = 0.25;
+		let first = sphere(0, 1.0, &bodies);
+		assert_eq!(sphere_and_sphere(&first, &sphere(1, 1.0, &bodies), &mut data), 1);
+		assert_eq!(sphere_and_sphere(&first, &sphere(2, 1.0, &bodies), &mut data), 0);
+
+		let contact = data.contacts[0];
+		assert_eq!(contact.bodies, [Some(0), Some(1)]);
+		assert_eq!(contact.contact_normal, Vector3::x_axis());
+		assert_eq!(contact.contact_point, Vector3::new(0.75, 0.0, 0.0));
+		assert_equal(contact.penetration, 0.5);
+		assert_equal(contact.friction, 0.5);
+		assert_equal(contact.restitution, 0.25);
+		assert_eq!(data.contacts_left(), 3);
+	}
+
+	#[test]
+	pub fn contact_limit_is_respected() {
+		let bodies = bodies_at(&[Vector3::zero(), Vector3::new(0.5, 0.0, 0.0)]);
+		let mut data = CollisionData::new(1);
+		let (first, second) = (sphere(0, 1.0, &bodies), sphere(1, 1.0, &bodies));
+		assert_eq!(sphere_and_sphere(&first, &second, &mut data), 1);
+		assert_eq!(sphere_and_sphere(&first, &second, &mut data), 0);
+		assert!(!data.has_more_contacts());
+		data.reset();
+		assert!(data.has_more_contacts());
+	}
+
+	#[test]
+	pub fn sphere_and_half_space_contact() {
+		let bodies = bodies_at(&[
+			Vector3::new(0.0, 0.5, 0.0),
+			Vector3::new(0.0, -3.0, 0.0),
+			Vector3::new(0.0, 2.0, 0.0),
+		]);
+		let floor = CollisionPlane::new(Vector3::y_axis(), 0.0);
+		let mut data = CollisionData::new(4);
+		assert_eq!(sphere_and_half_space(&sphere(0, 1.0, &bodies), &floor, &mut data), 1);
+		assert_eq!(sphere_and_half_space(&sphere(1, 1.0, &bodies), &floor, &mut data), 1);
+		assert_eq!(sphere_and_half_space(&sphere(2, 1.0, &bodies), &floor, &mut data), 0);
+
+		let resting = data.contacts[0];
+		assert_eq!(resting.bodies, [Some(0), None]);
+		assert_eq!(resting.contact_normal, Vector3::y_axis());
+		assert_eq!(resting.contact_point, Vector3::zero());
+		assert_equal(resting.penetration, 0.5);
+
+		// A sphere that has fallen through is still pushed up
+		let buried = data.contacts[1];
+		assert_eq!(buried.contact_normal, Vector3::y_axis());
+		assert_equal(buried.penetration, 4.0);
+	}
+
+	#[test]
+	pub fn sphere_and_true_plane_contact() {
+		let bodies = bodies_at(&[
+			Vector3::new(0.0, 0.5, 0.0),
+			Vector3::new(0.0, -0.75, 0.0),
+			Vector3::new(0.0, -3.0, 0.0),
+		]);
+		let wall = CollisionPlane::new(Vector3::y_axis(), 0.0);
+		let mut data = CollisionData::new(4);
+		assert_eq!(sphere_and_true_plane(&sphere(0, 1.0, &bodies), &wall, &mut data), 1);
+		assert_eq!(sphere_and_true_plane(&sphere(1, 1.0, &bodies), &wall, &mut data), 1);
+		assert_eq!(sphere_and_true_plane(&sphere(2, 1.0, &bodies), &wall, &mut data), 0);
+
+		assert_eq!(data.contacts[0].contact_normal, Vector3::y_axis());
+		assert_equal(data.contacts[0].penetration, 0.5);
+		assert_eq!(data.contacts[1].contact_normal, -Vector3::y_axis());
+		assert_eq!(data.contacts[1].contact_point, Vector3::zero());
+		assert_equal(data.contacts[1].penetration, 0.25);
+	}
+
+	fn cuboid(body: usize, half_size: Vector3, bodies: &[RigidBody]) -> CollisionBox {
+		let mut cuboid = CollisionBox {
+			primitive: CollisionPrimitive::new(body),
+			half_size,
+		};
+		cuboid.primitive.calculate_internals(bodies);
+		cuboid
+	}
+
+	#[test]
+	pub fn box_and_half_space_contact() {
+		let mut bodies = bodies_at(&[Vector3::new(0.0, 0.9, 0.0), Vector3::new(0.0, 3.0, 0.0)]);
+		let floor = CollisionPlane::new(Vector3::y_axis(), 0.0);
+		let mut data = CollisionData::new(8);
+		let half_size = Vector3::new(1.0, 1.0, 1.0);
+		assert_eq!(box_and_half_space(&cuboid(0, half_size, &bodies), &floor, &mut data), 4);
+		assert_eq!(box_and_half_space(&cuboid(1, half_size, &bodies), &floor, &mut data), 0);
+		for contact in &data.contacts {
+			assert_eq!(contact.contact_normal, Vector3::y_axis());
+			assert!((contact.penetration - 0.1).abs() < 1e-5);
+			assert_equal(contact.contact_point.y(), 0.0);
+		}
+		let mut features: Vec<usize> = data.contacts.iter().map(|contact| contact.feature).collect();
+		features.sort_unstable();
+		features.dedup();
+		assert_eq!(features.len(), 4);
+		assert!(!features.contains(&0));
+
+		// Tipped onto an edge, only the two lowest vertices touch
+		bodies[0].orientation = Quaternion::from_axis_angle(Vector3::z_axis(), std::f32::consts::FRAC_PI_4);
+		bodies[0].position = Vector3::new(0.0, 1.3, 0.0);
+		bodies[0].calculate_derived_data();
+		data.reset();
+		assert_eq!(box_and_half_space(&cuboid(0, half_size, &bodies), &floor, &mut data), 2);
+		let expected = 2.0_f32.sqrt() - 1.3;
+		assert!(data
+			.contacts
+			.iter()
+			.all(|contact| (contact.penetration - expected).abs() < 1e-5));
+	}
+
+	#[test]
+	pub fn box_and_sphere_contact() {
+		let bodies = bodies_at(&[
+			Vector3::zero(),
+			Vector3::new(0.0, 1.5, 0.0),
+			Vector3::new(1.5, 1.5, 0.0),
+			Vector3::new(0.0, 3.0, 0.0),
+		]);
+		let cuboid = cuboid(0, Vector3::new(1.0, 1.0, 1.0), &bodies);
+		let mut data = CollisionData::new(4);
+		assert_eq!(box_and_sphere(&cuboid, &sphere(1, 1.0, &bodies), &mut data), 1);
+		assert_eq!(box_and_sphere(&cuboid, &sphere(2, 1.0, &bodies), &mut data), 1);
+		assert_eq!(box_and_sphere(&cuboid, &sphere(3, 1.0, &bodies), &mut data), 0);
+
+		let face = data.contacts[0];
+		assert_eq!(face.bodies, [Some(0), Some(1)]);
+		assert_eq!(face.contact_point, Vector3::new(0.0, 1.0, 0.0));
+		assert_eq!(face.contact_normal, -Vector3::y_axis());
+		assert_equal(face.penetration, 0.5);
+
+		let edge = data.contacts[1];
+		assert_eq!(edge.contact_point, Vector3::new(1.0, 1.0, 0.0));
+		assert!((edge.penetration - (1.0 - 0.5_f32.sqrt())).abs() < 1e-5);
+	}
+
+	#[test]
+	pub fn sphere_inside_box_leaves_through_nearest_face() {
+		let bodies = bodies_at(&[Vector3::zero(), Vector3::new(0.2, 0.0, -0.8)]);
+		let cuboid = cuboid(0, Vector3::new(1.0, 1.0, 1.0), &bodies);
+		let mut data = CollisionData::new(1);
+		assert_eq!(box_and_sphere(&cuboid, &sphere(1, 0.5, &bodies), &mut data), 1);
+		let contact = data.contacts[0];
+		assert_eq!(contact.contact_normal, Vector3::z_axis());
+		assert_eq!(contact.contact_point, Vector3::new(0.2, 0.0, -1.0));
+		assert!((contact.penetration - 0.7).abs() < 1e-5);
+	}
+
+	#[test]
+	pub fn box_and_box_face_contact() {
+		let bodies = bodies_at(&[
+			Vector3::zero(),
+			Vector3::new(0.0, 1.8, 0.0),
+			Vector3::new(0.0, 2.5, 0.0),
+		]);
+		let half_size = Vector3::new(1.0, 1.0, 1.0);
+		let bottom = cuboid(0, half_size, &bodies);
+		let mut data = CollisionData::new(4);
+		assert_eq!(box_and_box(&bottom, &cuboid(2, half_size, &bodies), &mut data), 0);
+		assert_eq!(box_and_box(&cuboid(1, half_size, &bodies), &bottom, &mut data), 1);
+
+		let contact = data.contacts[0];
+		assert_eq!(contact.bodies, [Some(1), Some(0)]);
+		assert_eq!(contact.contact_normal, Vector3::y_axis());
+		assert!((contact.penetration - 0.2).abs() < 1e-5);
+		assert!((contact.contact_point.y() - 1.0).abs() < 1e-5);
+	}
+
+	#[test]
+	pub fn box_and_box_vertex_into_face() {
+		// The top box stands on one corner, pointing down into the lower box's top face
+		let mut bodies = bodies_at(&[Vector3::zero(), Vector3::new(0.0, 2.6, 0.0)]);
+		let diagonal_angle = 3.0_f32.sqrt().recip().acos();
+		bodies[1].orientation = Quaternion::from_axis_angle(Vector3::new(-1.0, 0.0, 1.0), diagonal_angle);
+		bodies[1].calculate_derived_data();
+		let half_size = Vector3::new(1.0, 1.0, 1.0);
+		let mut data = CollisionData::new(4);
+		assert_eq!(
+			box_and_box(
+				&cuboid(0, half_size, &bodies),
+				&cuboid(1, half_size, &bodies),
+				&mut data
+			),
+			1
+		);
+
+		let contact = data.contacts[0];
+		assert!((contact.contact_normal + Vector3::y_axis()).magnitude() < 1e-4);
+		assert!((contact.penetration - (3.0_f32.sqrt() - 1.6)).abs() < 1e-3);
+		assert!((contact.contact_point - Vector3::new(0.0, 2.6 - 3.0_f32.sqrt(), 0.0)).magnitude() < 1e-3);
+	}
+
+	#[test]
+	pub fn box_and_box_edge_contact() {
+		// Two boxes turned so that an edge of each crosses the other's at right angles
+		let mut bodies = bodies_at(&[Vector3::zero(), Vector3::new(0.0, 2.7, 0.0)]);
+		bodies[0].orientation = Quaternion::from_axis_angle(Vector3::x_axis(), std::f32::consts::FRAC_PI_4);
+		bodies[1].orientation = Quaternion::from_axis_angle(Vector3::z_axis(), std::f32::consts::FRAC_PI_4);
+		for body in &mut bodies {
+			body.calculate_derived_data();
+		}
+		let half_size = Vector3::new(1.0, 1.0, 1.0);
+		let mut data = CollisionData::new(4);
+		assert_eq!(
+			box_and_box(
+				&cuboid(0, half_size, &bodies),
+				&cuboid(1, half_size, &bodies),
+				&mut data
+			),
+			1
+		);
+
+		let contact = data.contacts[0];
+		assert!((contact.contact_normal + Vector3::y_axis()).magnitude() < 1e-4);
+		assert!((contact.penetration - (8.0_f32.sqrt() - 2.7)).abs() < 1e-4);
+		assert!((contact.contact_point - Vector3::new(0.0, 1.35, 0.0)).magnitude() < 1e-3);
+	}
+
+	fn capsule(body: usize, bodies: &[RigidBody]) -> CollisionCapsule {
+		let mut capsule = CollisionCapsule {
+			primitive: CollisionPrimitive::new(body),
+			radius: 0.5,
+			half_height: 1.0,
+		};
+		capsule.primitive.calculate_internals(bodies);
+		capsule
+	}
+
+	fn lying_capsule(body: usize, bodies: &mut [RigidBody], axis: Vector3) -> CollisionCapsule {
+		bodies[body].orientation = Quaternion::from_axis_angle(axis, std::f32::consts::FRAC_PI_2);
+		bodies[body].calculate_derived_data();
+		capsule(body, bodies)
+	}
+
+	#[test]
+	pub fn capsule_and_sphere_contact() {
+		let bodies = bodies_at(&[
+			Vector3::zero(),
+			Vector3::new(1.0, 0.8, 0.0),
+			Vector3::new(0.0, 2.0, 0.0),
+		]);
+		let mut data = CollisionData::new(4);
+		assert_eq!(
+			capsule_and_sphere(&capsule(0, &bodies), &sphere(1, 0.6, &bodies), &mut data),
+			1
+		);
+		assert_eq!(
+			capsule_and_sphere(&capsule(0, &bodies), &sphere(2, 0.6, &bodies), &mut data),
+			1
+		);
+
+		// Beside the cylinder the normal is horizontal, and past the end it is along the axis
+		assert!((data.contacts[0].contact_normal + Vector3::x_axis()).magnitude() < 1e-5);
+		assert!((data.contacts[0].penetration - 0.1).abs() < 1e-5);
+		assert!((data.contacts[1].contact_normal + Vector3::y_axis()).magnitude() < 1e-5);
+		assert!((data.contacts[1].penetration - 0.1).abs() < 1e-5);
+	}
+
+	#[test]
+	pub fn capsule_and_capsule_contact() {
+		let mut bodies = bodies_at(&[Vector3::zero(), Vector3::new(0.0, 0.0, 0.9)]);
+		let first = lying_capsule(0, &mut bodies, Vector3::z_axis());
+		let second = capsule(1, &bodies);
+		let mut data = CollisionData::new(4);
+		assert_eq!(capsule_and_capsule(&first, &second, &mut data), 1);
+		let contact = data.contacts[0];
+		assert!((contact.contact_normal + Vector3::z_axis()).magnitude() < 1e-5);
+		assert!((contact.penetration - 0.1).abs() < 1e-5);
+
+		let [first_point, second_point] = closest_points_between_segments(
+			[Vector3::zero(), Vector3::x_axis()],
+			[Vector3::new(2.0, 1.0, 0.0), Vector3::new(3.0, 1.0, 0.0)],
+		);
+		assert_eq!(first_point, Vector3::x_axis());
+		assert_eq!(second_point, Vector3::new(2.0, 1.0, 0.0));
+	}
+
+	#[test]
+	pub fn capsule_and_half_space_contact() {
+		let mut bodies = bodies_at(&[Vector3::new(0.0, 0.4, 0.0), Vector3::new(0.0, 1.4, 0.0)]);
+		let floor = CollisionPlane::new(Vector3::y_axis(), 0.0);
+		let mut data = CollisionData::new(4);
+		let lying = lying_capsule(0, &mut bodies, Vector3::z_axis());
+		assert_eq!(capsule_and_half_space(&lying, &floor, &mut data), 2);
+		assert_eq!(capsule_and_half_space(&capsule(1, &bodies), &floor, &mut data), 1);
+		assert!(data.contacts[..2]
+			.iter()
+			.all(|contact| (contact.penetration - 0.1).abs() < 1e-5));
+	}
+
+	#[test]
+	pub fn capsule_and_box_contact() {
+		let mut bodies = bodies_at(&[
+			Vector3::zero(),
+			Vector3::new(0.0, 1.4, 0.0),
+			Vector3::new(0.0, 1.4, 0.0),
+		]);
+		let table = cuboid(0, Vector3::new(1.0, 1.0, 1.0), &bodies);
+		let mut data = CollisionData::new(4);
+
+		// Lying along the top face, both ends rest on the box
+		let lying = lying_capsule(1, &mut bodies, Vector3::z_axis());
+		assert_eq!(capsule_and_box(&lying, &table, &mut data), 2);
+
+		// A long capsule across the top has its ends hanging over the sides
+		let mut long = lying_capsule(2, &mut bodies, Vector3::x_axis());
+		long.half_height = 3.0;
+		data.reset();
+		assert_eq!(capsule_and_box(&long, &table, &mut data), 1);
+		assert_eq!(data.contacts[0].bodies, [Some(0), Some(2)]);
+		assert!((data.contacts[0].contact_normal + Vector3::y_axis()).magnitude() < 1e-5);
+		assert!((data.contacts[0].penetration - 0.1).abs() < 1e-5);
+	}
+
+	fn cylinder(body: usize, bodies: &[RigidBody]) -> CollisionCylinder {
+		let mut cylinder = CollisionCylinder {
+			primitive: CollisionPrimitive::new(body),
+			radius: 0.5,
+			half_height: 1.0,
+		};
+		cylinder.primitive.calculate_internals(bodies);
+		cylinder
+	}
+
+	fn turned(body: usize, bodies: &mut [RigidBody], axis: Vector3, angle: Real) {
+		bodies[body].orientation = Quaternion::from_axis_angle(axis, angle);
+		bodies[body].calculate_derived_data();
+	}
+
+	#[test]
+	pub fn cylinder_and_half_space_contact() {
+		let mut bodies = bodies_at(&[Vector3::new(0.0, 0.9, 0.0), Vector3::new(0.0, 0.4, 0.0)]);
+		turned(1, &mut bodies, Vector3::z_axis(), std::f32::consts::FRAC_PI_2);
+		let floor = CollisionPlane::new(Vector3::y_axis(), 0.0);
+		let mut data = CollisionData::new(8);
+
+		// Standing on its end it rests on its rim, and lying down it rests along a line
+		assert_eq!(cylinder_and_half_space(&cylinder(0, &bodies), &floor, &mut data), 4);
+		assert_eq!(cylinder_and_half_space(&cylinder(1, &bodies), &floor, &mut data), 2);
+		assert!(data
+			.contacts
+			.iter()
+			.all(|contact| (contact.penetration - 0.1).abs() < 1e-5 && contact.contact_point.y().abs() < 1e-5));
+	}
+
+	#[test]
+	pub fn cylinder_and_sphere_contact() {
+		let bodies = bodies_at(&[
+			Vector3::zero(),
+			Vector3::new(1.0, 0.5, 0.0),
+			Vector3::new(0.0, 1.5, 0.0),
+			Vector3::new(0.3, 0.0, 0.0),
+		]);
+		let barrel = cylinder(0, &bodies);
+		let mut data = CollisionData::new(4);
+		assert_eq!(cylinder_and_sphere(&barrel, &sphere(1, 0.6, &bodies), &mut data), 1);
+		assert_eq!(cylinder_and_sphere(&barrel, &sphere(2, 0.6, &bodies), &mut data), 1);
+		assert_eq!(cylinder_and_sphere(&barrel, &sphere(3, 0.1, &bodies), &mut data), 1);
+
+		let expected = [
+			(-Vector3::x_axis(), 0.1),
+			(-Vector3::y_axis(), 0.1),
+			(-Vector3::x_axis(), 0.3),
+		];
+		for (contact, (normal, penetration)) in data.contacts.iter().zip(expected) {
+			assert!((contact.contact_normal - normal).magnitude() < 1e-5);
+			assert!((contact.penetration - penetration).abs() < 1e-5);
+		}
+	}
+
+	#[test]
+	pub fn cylinder_and_box_contact() {
+		let mut bodies = bodies_at(&[
+			Vector3::zero(),
+			Vector3::new(0.0, 1.4, 0.0),
+			Vector3::new(0.0, 1.65, 0.0),
+		]);
+		turned(1, &mut bodies, Vector3::x_axis(), std::f32::consts::FRAC_PI_2);
+		let table = cuboid(0, Vector3::new(2.0, 1.0, 2.0), &bodies);
+		let mut data = CollisionData::new(8);
+
+		// A barrel lying on the box touches along a line
+		assert_eq!(cylinder_and_box(&cylinder(1, &bodies), &table, &mut data), 2);
+		assert!(data.contacts.iter().all(|contact| contact.bodies == [Some(0), Some(1)]
+			&& (contact.contact_normal + Vector3::y_axis()).magnitude() < 1e-5
+			&& (contact.penetration - 0.1).abs() < 1e-5));
+
+		// The corners of a small box sink into the end of a standing cylinder
+		data.reset();
+		let crate_box = cuboid(2, Vector3::new(0.2, 0.2, 0.2), &bodies);
+		let mut standing = cylinder(0, &bodies);
+		standing.half_height = 1.5;
+		assert_eq!(cylinder_and_box(&standing, &crate_box, &mut data), 4);
+		assert!(data.contacts.iter().all(|contact| contact.bodies == [Some(2), Some(0)]
+			&& (contact.contact_normal - Vector3::y_axis()).magnitude() < 1e-5
+			&& (contact.penetration - 0.05).abs() < 1e-5));
+	}
+
+	#[test]
+	pub fn cylinder_across_box_edge() {
+		let mut bodies = bodies_at(&[Vector3::zero(), Vector3::new(0.0, 2.0_f32.sqrt() + 0.4, 0.0)]);
+		turned(0, &mut bodies, Vector3::z_axis(), std::f32::consts::FRAC_PI_4);
+		turned(1, &mut bodies, Vector3::x_axis(), std::f32::consts::FRAC_PI_2);
+		let ridge = cuboid(0, Vector3::new(1.0, 1.0, 3.0), &bodies);
+		let mut log = cylinder(1, &bodies);
+		log.half_height = 2.0;
+		let mut data = CollisionData::new(4);
+		assert_eq!(cylinder_and_box(&log, &ridge, &mut data), 1);
+		let contact = data.contacts[0];
+		assert_eq!(contact.bodies, [Some(0), Some(1)]);
+		assert!((contact.contact_normal + Vector3::y_axis()).magnitude() < 1e-4);
+		assert!((contact.penetration - 0.1).abs() < 1e-4);
+	}
+
+	#[test]
+	pub fn convex_capsule_on_cylinder() {
+		let mut bodies = bodies_at(&[Vector3::zero(), Vector3::new(0.0, 1.4, 0.0)]);
+		let mut drum = cylinder(0, &bodies);
+		drum.radius = 1.0;
+		let pole = lying_capsule(1, &mut bodies, Vector3::z_axis());
+		let mut data = CollisionData::new(4);
+		assert_eq!(convex_and_convex(&pole, &drum, [Some(1), Some(0)], &mut data), 1);
+		let contact = data.contacts[0];
+		assert!(contact.contact_normal.dot(&Vector3::y_axis()) > 0.999);
+		assert!((contact.penetration - 0.1).abs() < 1e-3);
+
+		// Within the tolerance, a near miss is reported with negative penetration
+		bodies[1].position = Vector3::new(0.0, 1.55, 0.0);
+		bodies[1].calculate_derived_data();
+		let pole = capsule(1, &bodies);
+		data.reset();
+		assert_eq!(convex_and_convex(&pole, &drum, [Some(1), Some(0)], &mut data), 0);
+		data.tolerance = 0.1;
+		assert_eq!(convex_and_convex(&pole, &drum, [Some(1), Some(0)], &mut data), 1);
+		assert!((data.contacts[0].penetration + 0.05).abs() < 1e-3);
+	}
+
+	/// A square floor of two triangles facing up, split along the line x = z.
+	fn floor_mesh() -> TriMesh {
+		TriMesh::new(
+			vec![
+				Vector3::new(-4.0, 0.0, -4.0),
+				Vector3::new(-4.0, 0.0, 4.0),
+				Vector3::new(4.0, 0.0, 4.0),
+				Vector3::new(4.0, 0.0, -4.0),
+			],
+			vec![[0, 1, 2], [0, 2, 3]],
+		)
+	}
+
+	fn rests_on_floor(contact: &Contact) -> bool {
+		contact.bodies[1].is_none()
+			&& contact.contact_normal.dot(&Vector3::y_axis()) > 0.999
+			&& (contact.penetration - 0.1).abs() < 1e-3
+	}
+
+	#[test]
+	pub fn sphere_and_mesh_contact() {
+		let bodies = bodies_at(&[Vector3::new(-1.0, 0.9, 1.0), Vector3::new(-1.0, -0.5, 1.0)]);
+		let floor = floor_mesh();
+		let mut data = CollisionData::new(4);
+		assert_eq!(sphere_and_mesh(&sphere(0, 1.0, &bodies), &floor, &mut data), 1);
+		assert!(rests_on_floor(&data.contacts[0]));
+		assert_eq!(data.contacts[0].contact_point, Vector3::new(-1.0, 0.0, 1.0));
+
+		// The mesh is one-sided, so a sphere below the floor is left alone
+		assert_eq!(sphere_and_mesh(&sphere(1, 1.0, &bodies), &floor, &mut data), 0);
+	}
+
+	#[test]
+	pub fn capsule_and_mesh_contact() {
+		let mut bodies = bodies_at(&[Vector3::new(0.0, 0.4, 2.5)]);
+		let pole = lying_capsule(0, &mut bodies, Vector3::z_axis());
+		let mut data = CollisionData::new(4);
+		assert_eq!(capsule_and_mesh(&pole, &floor_mesh(), &mut data), 2);
+		assert!(data.contacts.iter().all(rests_on_floor));
+	}
+
+	#[test]
+	pub fn box_and_mesh_contact() {
+		let bodies = bodies_at(&[Vector3::new(-1.0, 0.4, 1.5)]);
+		let block = cuboid(0, Vector3::new(0.5, 0.5, 0.5), &bodies);
+		let mut data = CollisionData::new(8);
+		assert_eq!(box_and_mesh(&block, &floor_mesh(), &mut data), 4);
+		assert!(data.contacts.iter().all(rests_on_floor));
+	}
+
+	#[test]
+	pub fn convex_and_mesh_contact() {
+		let bodies = bodies_at(&[Vector3::new(-1.0, 0.9, 1.5)]);
+		let barrel = cylinder(0, &bodies);
+		let mut data = CollisionData::new(4);
+		assert_eq!(convex_and_mesh(&barrel, 0, &floor_mesh(), &mut data), 1);
+		assert!(rests_on_floor(&data.contacts[0]));
+	}
+
+	#[test]
+	pub fn margins_report_contacts_before_touching() {
+		let bodies = bodies_at(&[
+			Vector3::zero(),
+			Vector3::new(2.05, 0.0, 0.0),
+			Vector3::new(0.0, 1.05, 0.0),
+		]);
+		let mut first = sphere(0, 1.0, &bodies);
+		let second = sphere(1, 1.0, &bodies);
+		let mut data = CollisionData::new(4);
+		assert_eq!(sphere_and_sphere(&first, &second, &mut data), 0);
+		first.primitive = first.primitive.with_margin(0.1);
+		assert_eq!(sphere_and_sphere(&first, &second, &mut data), 1);
+		assert!((data.contacts[0].penetration + 0.05).abs() < 1e-5);
+		assert_equal(data.tolerance, 0.0);
+
+		let mut left = cuboid(0, Vector3::new(1.0, 1.0, 1.0), &bodies);
+		let right = cuboid(1, Vector3::new(1.0, 1.0, 1.0), &bodies);
+		assert_eq!(box_and_box(&left, &right, &mut data), 0);
+		left.primitive.margin = 0.05;
+		data.tolerance = 0.05;
+		assert!(box_and_box(&left, &right, &mut data) > 0);
+		assert!((data.contacts[1].penetration + 0.05).abs() < 1e-5);
+
+		let floor = CollisionPlane::new(Vector3::y_axis(), 0.0);
+		let mut ball = sphere(2, 1.0, &bodies);
+		data.reset();
+		data.tolerance = 0.0;
+		assert_eq!(sphere_and_half_space(&ball, &floor, &mut data), 0);
+		ball.primitive.margin = 0.1;
+		assert_eq!(sphere_and_half_space(&ball, &floor, &mut data), 1);
+		assert!((data.contacts[0].penetration + 0.05).abs() < 1e-5);
+	}
+
+	#[test]
+	pub fn plane_distance() {
+		let plane = CollisionPlane::new(Vector3::y_axis(), 2.0);
+		assert_equal(plane.distance_to(Vector3::new(5.0, 3.0, 0.0)), 1.0);
+		assert_equal(plane.distance_to(Vector3::new(5.0, 0.0, 0.0)), -2.0);
+	}
+}
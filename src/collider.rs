@@ -0,0 +1,356 @@
+use crate::{
+	body::BodyTransforms,
+	bounding::{Aabb, BoundingVolume},
+	collide::{
+		box_and_box, box_and_half_space, box_and_mesh, box_and_sphere, capsule_and_box, capsule_and_capsule,
+		capsule_and_half_space, capsule_and_mesh, capsule_and_sphere, convex_and_convex, convex_and_mesh,
+		cylinder_and_box, cylinder_and_half_space, cylinder_and_sphere, sphere_and_half_space, sphere_and_mesh,
+		sphere_and_sphere, CollisionBox, CollisionCapsule, CollisionCylinder, CollisionData, CollisionPlane,
+		CollisionPrimitive, CollisionSphere,
+	},
+	convex::{convex_cast, ShapeHit, SupportMap},
+	matrix::Matrix4,
+	shape::Shape,
+	trimesh::TriMesh,
+	vec::Vector3,
+	Real,
+};
+
+/// A collision primitive of any kind, so that shapes can be stored together
+/// and paired without knowing their kinds in advance.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub enum Collider {
+	Sphere(CollisionSphere),
+	Box(CollisionBox),
+	Capsule(CollisionCapsule),
+	Cylinder(CollisionCylinder),
+}
+
+impl Collider {
+	/// Creates the collider matching the given shape, attached by the given primitive.
+	#[must_use]
+	pub const fn from_shape(shape: &Shape, primitive: CollisionPrimitive) -> Self {
+		match *shape {
+			Shape::Sphere { radius } => Self::Sphere(CollisionSphere { primitive, radius }),
+			Shape::Cuboid { half_size } => Self::Box(CollisionBox { primitive, half_size }),
+			Shape::Capsule { radius, half_height } => Self::Capsule(CollisionCapsule {
+				primitive,
+				radius,
+				half_height,
+			}),
+			Shape::Cylinder { radius, half_height } => Self::Cylinder(CollisionCylinder {
+				primitive,
+				radius,
+				half_height,
+			}),
+		}
+	}
+
+	/// Creates a collider standing alone at the given world transform, for
+	/// queries such as shape casts. It is not attached to any body, so
+	/// [`Self::calculate_internals`] must not be called on it.
+	#[must_use]
+	pub const fn from_shape_at(shape: &Shape, transform: &Matrix4) -> Self {
+		Self::from_shape(shape, CollisionPrimitive::with_offset(usize::MAX, *transform))
+	}
+
+	/// Returns whether the collider is attached to a body, rather than
+	/// standing alone like one made by [`Self::from_shape_at`].
+	#[must_use]
+	pub const fn is_attached(&self) -> bool {
+		self.primitive().body != usize::MAX
+	}
+
+	/// Detaches the collider from its body, leaving it standing alone where
+	/// it was, as a [`PhysicsWorld`](crate::world::PhysicsWorld) leaves the
+	/// colliders of a body it removes.
+	pub const fn detach(&mut self) {
+		self.primitive_mut().body = usize::MAX;
+	}
+
+	/// Returns the type index of the collider's shape, as
+	/// [`Shape::type_index`] numbers it.
+	#[must_use]
+	pub const fn type_index(&self) -> usize {
+		match self {
+			Self::Sphere(_) => 0,
+			Self::Box(_) => 1,
+			Self::Capsule(_) => 2,
+			Self::Cylinder(_) => 3,
+		}
+	}
+
+	#[must_use]
+	pub const fn primitive(&self) -> &CollisionPrimitive {
+		match self {
+			Self::Sphere(sphere) => &sphere.primitive,
+			Self::Box(cuboid) => &cuboid.primitive,
+			Self::Capsule(capsule) => &capsule.primitive,
+			Self::Cylinder(cylinder) => &cylinder.primitive,
+		}
+	}
+
+	pub const fn primitive_mut(&mut self) -> &mut CollisionPrimitive {
+		match self {
+			Self::Sphere(sphere) => &mut sphere.primitive,
+			Self::Box(cuboid) => &mut cuboid.primitive,
+			Self::Capsule(capsule) => &mut capsule.primitive,
+			Self::Cylinder(cylinder) => &mut cylinder.primitive,
+		}
+	}
+
+	/// Calculates the world transform of the collider from its body's transform.
+	pub fn calculate_internals(&mut self, bodies: &(impl BodyTransforms + ?Sized)) {
+		self.primitive_mut().calculate_internals(bodies);
+	}
+
+	fn as_support_map(&self) -> &dyn SupportMap {
+		match self {
+			Self::Sphere(sphere) => sphere,
+			Self::Box(cuboid) => cuboid,
+			Self::Capsule(capsule) => capsule,
+			Self::Cylinder(cylinder) => cylinder,
+		}
+	}
+}
+
+impl SupportMap for Collider {
+	fn support(&self, direction: Vector3) -> Vector3 {
+		self.as_support_map().support(direction)
+	}
+
+	fn center(&self) -> Vector3 {
+		self.primitive().position()
+	}
+
+	fn margin(&self) -> Real {
+		self.primitive().margin
+	}
+}
+
+/// Detects contact between two colliders of any kinds, returning the number
+/// of contacts added.
+///
+/// Pairs with a dedicated detector use it, and the rest are tested as convex
+/// shapes. Some detectors name the bodies in the other order, so each
+/// contact's bodies should be read from the contact itself.
+pub fn collider_and_collider(first: &Collider, second: &Collider, data: &mut CollisionData) -> usize {
+	let materials = [
+		first.primitive().material.as_ref(),
+		second.primitive().material.as_ref(),
+	];
+	data.with_materials(materials, |data| match (first, second) {
+		(Collider::Sphere(first), Collider::Sphere(second)) => sphere_and_sphere(first, second, data),
+		(Collider::Box(first), Collider::Box(second)) => box_and_box(first, second, data),
+		(Collider::Capsule(first), Collider::Capsule(second)) => capsule_and_capsule(first, second, data),
+		(Collider::Box(cuboid), Collider::Sphere(sphere)) | (Collider::Sphere(sphere), Collider::Box(cuboid)) => {
+			box_and_sphere(cuboid, sphere, data)
+		},
+		(Collider::Capsule(capsule), Collider::Sphere(sphere))
+		| (Collider::Sphere(sphere), Collider::Capsule(capsule)) => capsule_and_sphere(capsule, sphere, data),
+		(Collider::Capsule(capsule), Collider::Box(cuboid)) | (Collider::Box(cuboid), Collider::Capsule(capsule)) => {
+			capsule_and_box(capsule, cuboid, data)
+		},
+		(Collider::Cylinder(cylinder), Collider::Sphere(sphere))
+		| (Collider::Sphere(sphere), Collider::Cylinder(cylinder)) => cylinder_and_sphere(cylinder, sphere, data),
+		(Collider::Cylinder(cylinder), Collider::Box(cuboid))
+		| (Collider::Box(cuboid), Collider::Cylinder(cylinder)) => cylinder_and_box(cylinder, cuboid, data),
+		_ => convex_and_convex(
+			first,
+			second,
+			[Some(first.primitive().body), Some(second.primitive().body)],
+			data,
+		),
+	})
+}
+
+/// Detects contact between a collider and the half-space behind a plane,
+/// returning the number of contacts added. The plane takes the data's own
+/// friction and restitution as its material.
+pub fn collider_and_half_space(collider: &Collider, plane: &CollisionPlane, data: &mut CollisionData) -> usize {
+	data.with_materials([collider.primitive().material.as_ref(), None], |data| match collider {
+		Collider::Sphere(sphere) => sphere_and_half_space(sphere, plane, data),
+		Collider::Box(cuboid) => box_and_half_space(cuboid, plane, data),
+		Collider::Capsule(capsule) => capsule_and_half_space(capsule, plane, data),
+		Collider::Cylinder(cylinder) => cylinder_and_half_space(cylinder, plane, data),
+	})
+}
+
+/// Detects contact between a collider and a static mesh, returning the
+/// number of contacts added. The mesh takes the data's own friction and
+/// restitution as its material.
+pub fn collider_and_mesh(collider: &Collider, mesh: &TriMesh, data: &mut CollisionData) -> usize {
+	data.with_materials([collider.primitive().material.as_ref(), None], |data| match collider {
+		Collider::Sphere(sphere) => sphere_and_mesh(sphere, mesh, data),
+		Collider::Box(cuboid) => box_and_mesh(cuboid, mesh, data),
+		Collider::Capsule(capsule) => capsule_and_mesh(capsule, mesh, data),
+		Collider::Cylinder(cylinder) => convex_and_mesh(cylinder, cylinder.primitive.body, mesh, data),
+	})
+}
+
+/// Where a cast shape first touches one of a set of colliders.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColliderHit {
+	/// Holds the index of the collider hit.
+	pub collider: usize,
+
+	/// Holds the user data of the collider hit.
+	pub user_data: u64,
+
+	pub hit: ShapeHit,
+}
+
+/// Sweeps a shape from the given transform along the direction, returning
+/// the first of the colliders it touches within the given distance.
+///
+/// This serves character controllers and other queries that a ray is too
+/// thin for. Only colliders whose bounds overlap the bounds swept by the shape are tested.
+#[must_use]
+pub fn shape_cast(
+	shape: &Shape,
+	from_transform: &Matrix4,
+	direction: Vector3,
+	max_distance: Real,
+	colliders: &[Collider],
+) -> Option<ColliderHit> {
+	let cast = Collider::from_shape_at(shape, from_transform);
+	let start = cast.bounds();
+	let travel = direction.normalize() * max_distance;
+	let swept = start.merge(&Aabb::new(start.min + travel, start.max + travel));
+	let mut nearest: Option<ColliderHit> = None;
+	for (index, collider) in colliders.iter().enumerate() {
+		if !swept.overlaps(&collider.bounds()) {
+			continue;
+		}
+		let limit = nearest.map_or(max_distance, |nearest| nearest.hit.distance);
+		if let Some(hit) = convex_cast(&cast, direction, limit, collider) {
+			if nearest.is_none_or(|nearest| hit.distance < nearest.hit.distance) {
+				nearest = Some(ColliderHit {
+					collider: index,
+					user_data: collider.primitive().user_data,
+					hit,
+				});
+			}
+		}
+	}
+	nearest
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		assert_equal,
+		body::RigidBody,
+		material::{CombineRule, Material},
+	};
+
+	fn colliders(shapes: &[(Shape, Vector3)]) -> Vec<Collider> {
+		let bodies: Vec<RigidBody> = shapes
+			.iter()
+			.map(|(_, position)| {
+				let mut body = RigidBody {
+					position: *position,
+					inverse_mass: 1.0,
+					..Default::default()
+				};
+				body.calculate_derived_data();
+				body
+			})
+			.collect();
+		shapes
+			.iter()
+			.enumerate()
+			.map(|(body, (shape, _))| {
+				let mut collider = Collider::from_shape(shape, CollisionPrimitive::new(body));
+				collider.calculate_internals(&bodies);
+				collider
+			})
+			.collect()
+	}
+
+	#[test]
+	pub fn shape_cast_hits_the_nearest_collider() {
+		let wall = Shape::Cuboid {
+			half_size: Vector3::new(0.5, 2.0, 2.0),
+		};
+		let mut targets = colliders(&[
+			(wall, Vector3::new(8.0, 0.0, 0.0)),
+			(wall, Vector3::new(4.0, 0.0, 0.0)),
+			(wall, Vector3::new(-4.0, 0.0, 0.0)),
+		]);
+		targets[1].primitive_mut().user_data = 42;
+		let ball = Shape::Sphere { radius: 0.5 };
+		let start = Matrix4::identity();
+		let found = shape_cast(&ball, &start, Vector3::x_axis(), 20.0, &targets).expect("the ball hits a wall");
+		assert_eq!((found.collider, found.user_data), (1, 42));
+		assert!((found.hit.distance - 3.0).abs() < 1e-2);
+		assert!(shape_cast(&ball, &start, Vector3::x_axis(), 2.0, &targets).is_none());
+		assert!(shape_cast(&ball, &start, Vector3::z_axis(), 20.0, &targets).is_none());
+	}
+
+	#[test]
+	pub fn pairs_in_either_order() {
+		let pair = colliders(&[
+			(Shape::Sphere { radius: 1.0 }, Vector3::new(0.0, 1.4, 0.0)),
+			(Shape::default(), Vector3::zero()),
+		]);
+		let mut data = CollisionData::new(4);
+		assert_eq!(collider_and_collider(&pair[0], &pair[1], &mut data), 1);
+		assert_eq!(collider_and_collider(&pair[1], &pair[0], &mut data), 1);
+		assert_eq!(data.contacts[0].bodies, data.contacts[1].bodies);
+		assert!((data.contacts[0].penetration - 0.1).abs() < 1e-5);
+	}
+
+	#[test]
+	pub fn pairs_without_a_detector_are_tested_as_convex() {
+		let cylinder = Shape::Cylinder {
+			radius: 0.5,
+			half_height: 1.0,
+		};
+		let pair = colliders(&[(cylinder, Vector3::new(0.0, 1.9, 0.0)), (cylinder, Vector3::zero())]);
+		let mut data = CollisionData::new(8);
+		assert_eq!(collider_and_collider(&pair[0], &pair[1], &mut data), 1);
+		let contact = data.contacts[0];
+		assert_eq!(contact.bodies, [Some(0), Some(1)]);
+		assert!(contact.contact_normal.dot(&Vector3::y_axis()) > 0.999);
+		assert!((contact.penetration - 0.1).abs() < 1e-3);
+
+		let floor = CollisionPlane::new(Vector3::y_axis(), 0.0);
+		assert_eq!(collider_and_half_space(&pair[1], &floor, &mut data), 4);
+	}
+
+	#[test]
+	pub fn contacts_combine_the_colliders_materials() {
+		let ice = Material::new(0.02, 0.1, 0.9);
+		let rubber = Material::new(1.0, 0.8, 1.1).with_rolling_friction(0.04);
+		let mut pair = colliders(&[
+			(Shape::Sphere { radius: 1.0 }, Vector3::new(0.0, 1.9, 0.0)),
+			(Shape::Sphere { radius: 1.0 }, Vector3::zero()),
+		]);
+		pair[0].primitive_mut().material = Some(ice);
+		pair[1].primitive_mut().material = Some(rubber);
+		let mut data = CollisionData::new(8);
+		data.friction_combine = CombineRule::Min;
+		data.restitution_combine = CombineRule::Max;
+		collider_and_collider(&pair[0], &pair[1], &mut data);
+		assert_equal(data.contacts[0].friction, 0.02);
+		assert_equal(data.contacts[0].restitution, 0.8);
+		assert_equal(data.contacts[0].rolling_friction, 0.0);
+
+		// The floor takes the data's own values, which are left as they were
+		data.friction = 0.4;
+		data.friction_combine = CombineRule::Average;
+		let floor = CollisionPlane::new(Vector3::y_axis(), 0.5);
+		collider_and_half_space(&pair[1], &floor, &mut data);
+		assert_equal(data.contacts[1].friction, 0.7);
+		assert_equal(data.contacts[1].rolling_friction, 0.02);
+		assert_equal(data.friction, 0.4);
+
+		// Without materials the data's values are used as they are
+		pair[1].primitive_mut().material = None;
+		collider_and_half_space(&pair[1], &floor, &mut data);
+		assert_equal(data.contacts[2].friction, 0.4);
+	}
+}
@@ -0,0 +1,225 @@
+use crate::{
+	bounding::BoundingVolume,
+	collide::{CollisionData, CollisionPrimitive},
+	collider::{collider_and_collider, Collider},
+	convex::SupportMap,
+	matrix::{Matrix3, Matrix4},
+	shape::Shape,
+	vec::Vector3,
+	Real,
+};
+
+/// A shape held at a fixed transform within a compound.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct CompoundChild {
+	pub shape: Shape,
+
+	/// Holds the transform of the shape in the compound's coordinates.
+	pub offset: Matrix4,
+}
+
+/// A collider made of several shapes attached to a single rigid body, for
+/// objects such as vehicles and furniture that no one primitive fits.
+///
+/// The compound is treated as solid with a uniform density, so each child
+/// carries a share of the mass in proportion to its volume. Overlapping
+/// children count the shared volume twice.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct Compound {
+	pub children: Vec<CompoundChild>,
+}
+
+impl Compound {
+	/// Adds a shape held at the given transform in the compound's coordinates.
+	#[must_use]
+	pub fn with_child(mut self, shape: Shape, offset: Matrix4) -> Self {
+		self.children.push(CompoundChild { shape, offset });
+		self
+	}
+
+	#[must_use]
+	pub fn volume(&self) -> Real {
+		self.children.iter().map(|child| child.shape.volume()).sum()
+	}
+
+	/// Returns the center of mass in the compound's coordinates.
+	#[must_use]
+	pub fn center_of_mass(&self) -> Vector3 {
+		let volume = self.volume();
+		if volume <= 0.0 {
+			return Vector3::zero();
+		}
+		self.children.iter().fold(Vector3::zero(), |sum, child| {
+			sum + child.offset.translation() * (child.shape.volume() / volume)
+		})
+	}
+
+	/// Returns the inertia tensor of the compound about its center of mass,
+	/// in the compound's axes, when it has the given total mass.
+	///
+	/// Each child's own inertia tensor is turned into the compound's axes
+	/// and moved to the center of mass with the parallel axis theorem.
+	#[must_use]
+	pub fn inertia_tensor(&self, mass: Real) -> Matrix3 {
+		let volume = self.volume();
+		if volume <= 0.0 {
+			return Matrix3::zero();
+		}
+		let center_of_mass = self.center_of_mass();
+		self.children.iter().fold(Matrix3::zero(), |sum, child| {
+			let child_mass = mass * child.shape.volume() / volume;
+			let rotation = child.offset.rotation();
+			let turned = rotation * child.shape.inertia_tensor(child_mass) * rotation.transpose();
+			let arm = Matrix3::skew_symmetric(child.offset.translation() - center_of_mass);
+			sum + turned - arm * arm * child_mass
+		})
+	}
+
+	/// Creates a collider for each child, attached to the given body.
+	///
+	/// A body's position is its center of mass, so the colliders are held
+	/// at their offsets from the compound's center of mass.
+	#[must_use]
+	pub fn colliders(&self, body: usize) -> Vec<Collider> {
+		let center_of_mass = self.center_of_mass();
+		self.children
+			.iter()
+			.map(|child| {
+				let offset =
+					Matrix4::from_rotation(&child.offset.rotation(), child.offset.translation() - center_of_mass);
+				Collider::from_shape(&child.shape, CollisionPrimitive::with_offset(body, offset))
+			})
+			.collect()
+	}
+}
+
+/// Detects contact between every pair of colliders from two sets, such as
+/// the colliders of two compound bodies, returning the number of contacts added.
+///
+/// Pairs whose bounding boxes are apart are skipped without running their detector.
+pub fn colliders_and_colliders(first: &[Collider], second: &[Collider], data: &mut CollisionData) -> usize {
+	let second_bounds: Vec<_> = second.iter().map(SupportMap::bounds).collect();
+	let mut added = 0;
+	for first_collider in first {
+		let bounds = first_collider.bounds();
+		for (second_collider, other_bounds) in second.iter().zip(&second_bounds) {
+			if bounds.overlaps(other_bounds) {
+				added += collider_and_collider(first_collider, second_collider, data);
+			}
+		}
+	}
+	added
+}
+
+#[cfg(test)]
+mod tests {
+	use std::f32::consts::FRAC_PI_2;
+
+	use crate::{
+		body::{RigidBody, RigidBodyBuilder},
+		quaternion::Quaternion,
+	};
+
+	use super::*;
+
+	fn is_close(actual: &Matrix3, expected: &Matrix3) -> bool {
+		(0..3).all(|row| (actual.row(row) - expected.row(row)).magnitude() < 1e-4)
+	}
+
+	fn translation(position: Vector3) -> Matrix4 {
+		Matrix4::from_rotation(&Matrix3::identity(), position)
+	}
+
+	fn cube() -> Shape {
+		Shape::Cuboid {
+			half_size: Vector3::new(0.5, 0.5, 0.5),
+		}
+	}
+
+	/// Two unit cubes, one either side of the origin along x.
+	fn dumbbell() -> Compound {
+		Compound::default()
+			.with_child(cube(), translation(Vector3::new(-1.0, 0.0, 0.0)))
+			.with_child(cube(), translation(Vector3::x_axis()))
+	}
+
+	#[test]
+	pub fn parallel_axis_theorem() {
+		let compound = dumbbell();
+		assert!((compound.volume() - 2.0).abs() < 1e-5);
+		assert_eq!(compound.center_of_mass(), Vector3::zero());
+		let expected = Matrix3::diagonal(Vector3::new(1.0 / 3.0, 7.0 / 3.0, 7.0 / 3.0));
+		assert!(is_close(&compound.inertia_tensor(2.0), &expected));
+	}
+
+	#[test]
+	pub fn center_of_mass_follows_volume() {
+		let compound = Compound::default()
+			.with_child(cube(), translation(Vector3::zero()))
+			.with_child(
+				Shape::Cuboid {
+					half_size: Vector3::new(1.0, 0.5, 0.5),
+				},
+				translation(Vector3::new(3.0, 0.0, 0.0)),
+			);
+		assert!((compound.center_of_mass() - Vector3::new(2.0, 0.0, 0.0)).magnitude() < 1e-5);
+	}
+
+	#[test]
+	pub fn turned_child_turns_its_inertia() {
+		let turn = Matrix4::from_transform(
+			&Quaternion::from_axis_angle(Vector3::z_axis(), FRAC_PI_2),
+			Vector3::zero(),
+		);
+		let compound = Compound::default().with_child(
+			Shape::Cuboid {
+				half_size: Vector3::new(1.0, 2.0, 3.0),
+			},
+			turn,
+		);
+		let expected = Matrix3::diagonal(Vector3::new(10.0, 13.0, 5.0));
+		assert!(is_close(&compound.inertia_tensor(3.0), &expected));
+
+		let body = RigidBodyBuilder::compound(&compound).mass(3.0).build();
+		assert!(is_close(&body.inertia_tensor(), &expected));
+	}
+
+	#[test]
+	pub fn colliders_surround_the_center_of_mass() {
+		let compound = Compound::default()
+			.with_child(Shape::Sphere { radius: 0.5 }, translation(Vector3::zero()))
+			.with_child(Shape::Sphere { radius: 0.5 }, translation(Vector3::new(2.0, 0.0, 0.0)));
+		let mut bodies: Vec<RigidBody> = vec![
+			RigidBodyBuilder::compound(&compound)
+				.position(Vector3::new(5.0, 0.0, 0.0))
+				.build(),
+			RigidBodyBuilder::new(cube())
+				.position(Vector3::new(6.0, 0.9, 0.0))
+				.build(),
+		];
+		let mut colliders = compound.colliders(0);
+		for collider in &mut colliders {
+			collider.calculate_internals(&bodies);
+		}
+		let positions: Vec<Vector3> = colliders
+			.iter()
+			.map(|collider| collider.primitive().position())
+			.collect();
+		assert_eq!(
+			positions,
+			vec![Vector3::new(4.0, 0.0, 0.0), Vector3::new(6.0, 0.0, 0.0)]
+		);
+
+		// Only the sphere under the cube touches it
+		bodies[1].calculate_derived_data();
+		let mut cube_collider = [Collider::from_shape(&cube(), CollisionPrimitive::new(1))];
+		cube_collider[0].calculate_internals(&bodies);
+		let mut data = CollisionData::new(4);
+		assert_eq!(colliders_and_colliders(&colliders, &cube_collider, &mut data), 1);
+		let contact = data.contacts[0];
+		assert_eq!(contact.bodies, [Some(1), Some(0)]);
+		assert!((contact.penetration - 0.1).abs() < 1e-5);
+	}
+}
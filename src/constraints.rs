@@ -0,0 +1,179 @@
+use crate::{particle::Particle, pbd::PositionConstraint, vec::Vector3, Real};
+
+/// A surface that a particle can be constrained to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Surface {
+	/// The plane of points whose dot product with `normal` equals `offset`.
+	Plane { normal: Vector3, offset: Real },
+
+	/// The surface of a sphere.
+	Sphere { center: Vector3, radius: Real },
+
+	/// The interior of an axis-aligned box, including its faces.
+	Aabb { min: Vector3, max: Vector3 },
+}
+
+impl Surface {
+	/// Creates a plane passing through the given point with the given normal.
+	#[must_use]
+	pub fn plane(point: Vector3, normal: Vector3) -> Self {
+		let normal = normal.normalize();
+		Self::Plane {
+			normal,
+			offset: normal.dot(&point),
+		}
+	}
+
+	/// Returns the closest point on (or, for boxes, inside) the surface to the given point.
+	#[must_use]
+	pub fn closest_point(&self, point: Vector3) -> Vector3 {
+		match *self {
+			Self::Plane { normal, offset } => point - normal * (normal.dot(&point) - offset),
+			Self::Sphere { center, radius } => {
+				let direction = point - center;
+				// A point at the center is pushed out along an arbitrary axis
+				let direction = if direction.magnitude_squared() > 0.0 {
+					direction.normalize()
+				} else {
+					Vector3::y_axis()
+				};
+				center + direction * radius
+			},
+			Self::Aabb { min, max } => {
+				let mut clamped = point;
+				for axis in 0..3 {
+					clamped[axis] = point[axis].clamp(min[axis], max[axis]);
+				}
+				clamped
+			},
+		}
+	}
+
+	/// Removes the component of the velocity that would carry a particle at the
+	/// given point off the surface, leaving it free to slide along the surface.
+	#[must_use]
+	pub fn constrain_velocity(&self, point: Vector3, velocity: Vector3) -> Vector3 {
+		match *self {
+			Self::Plane { normal, .. } => velocity - normal * normal.dot(&velocity),
+			Self::Sphere { center, .. } => {
+				let normal = (point - center).normalize();
+				velocity - normal * normal.dot(&velocity)
+			},
+			Self::Aabb { min, max } => {
+				let mut constrained = velocity;
+				for axis in 0..3 {
+					let leaving_min = point[axis] <= min[axis] && velocity[axis] < 0.0;
+					let leaving_max = point[axis] >= max[axis] && velocity[axis] > 0.0;
+					if leaving_min || leaving_max {
+						constrained[axis] = 0.0;
+					}
+				}
+				constrained
+			},
+		}
+	}
+}
+
+/// Keeps a particle on a plane or sphere, or inside a box.
+///
+/// Applying the constraint moves the particle to the closest allowed point and
+/// zeroes the velocity along the surface normal, so beads slide freely along
+/// their wire and effects stay inside level bounds without collision geometry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceConstraint {
+	/// Holds the particle that is constrained.
+	pub particle: usize,
+
+	/// Holds the surface the particle is kept on.
+	pub surface: Surface,
+}
+
+impl SurfaceConstraint {
+	#[must_use]
+	pub const fn new(particle: usize, surface: Surface) -> Self {
+		Self { particle, surface }
+	}
+
+	/// Projects the particle onto the surface and removes its normal velocity.
+	/// Call this after integrating the particles each frame.
+	pub fn apply(&self, particles: &mut [Particle]) {
+		let particle = &mut particles[self.particle];
+		if !particle.has_finite_mass() {
+			return;
+		}
+		particle.position = self.surface.closest_point(particle.position);
+		particle.velocity = self.surface.constrain_velocity(particle.position, particle.velocity);
+	}
+}
+
+impl PositionConstraint for SurfaceConstraint {
+	fn project(&mut self, particles: &mut [Particle], _previous_positions: &[Vector3], _duration: Real) {
+		let particle = &mut particles[self.particle];
+		if particle.has_finite_mass() {
+			particle.position = self.surface.closest_point(particle.position);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn particle(position: Vector3, velocity: Vector3) -> Particle {
+		Particle {
+			position,
+			velocity,
+			inverse_mass: 1.0,
+			damping: 1.0,
+			..Default::default()
+		}
+	}
+
+	#[test]
+	pub fn plane() {
+		let mut particles = [particle(Vector3::new(1.0, 3.0, 2.0), Vector3::new(1.0, -1.0, 0.0))];
+		SurfaceConstraint::new(0, Surface::plane(Vector3::y_axis(), Vector3::y_axis())).apply(&mut particles);
+		assert_eq!(particles[0].position, Vector3::new(1.0, 1.0, 2.0));
+		assert_eq!(particles[0].velocity, Vector3::new(1.0, 0.0, 0.0));
+	}
+
+	#[test]
+	pub fn sphere() {
+		let mut particles = [particle(Vector3::new(0.0, 4.0, 0.0), Vector3::new(2.0, 3.0, 0.0))];
+		let surface = Surface::Sphere {
+			center: Vector3::zero(),
+			radius: 2.0,
+		};
+		SurfaceConstraint::new(0, surface).apply(&mut particles);
+		assert_eq!(particles[0].position, Vector3::new(0.0, 2.0, 0.0));
+		assert_eq!(particles[0].velocity, Vector3::new(2.0, 0.0, 0.0));
+	}
+
+	#[test]
+	pub fn aabb() {
+		let surface = Surface::Aabb {
+			min: Vector3::zero(),
+			max: Vector3::new(1.0, 1.0, 1.0),
+		};
+		let mut particles = [
+			particle(Vector3::new(2.0, 0.5, -1.0), Vector3::new(1.0, 1.0, -1.0)),
+			particle(Vector3::new(0.5, 0.5, 0.5), Vector3::new(1.0, 1.0, 1.0)),
+		];
+		SurfaceConstraint::new(0, surface).apply(&mut particles);
+		SurfaceConstraint::new(1, surface).apply(&mut particles);
+		assert_eq!(particles[0].position, Vector3::new(1.0, 0.5, 0.0));
+		assert_eq!(particles[0].velocity, Vector3::new(0.0, 1.0, 0.0));
+		assert_eq!(particles[1].position, Vector3::new(0.5, 0.5, 0.5));
+		assert_eq!(particles[1].velocity, Vector3::new(1.0, 1.0, 1.0));
+	}
+
+	#[test]
+	pub fn pinned_particles_are_not_moved() {
+		let mut particles = [Particle {
+			position: Vector3::new(0.0, 3.0, 0.0),
+			..Default::default()
+		}];
+		SurfaceConstraint::new(0, Surface::plane(Vector3::zero(), Vector3::y_axis())).apply(&mut particles);
+		assert_eq!(particles[0].position, Vector3::new(0.0, 3.0, 0.0));
+	}
+}
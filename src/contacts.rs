@@ -0,0 +1,460 @@
+use crate::{particle::Particle, vec::Vector3, Real};
+
+/// A contact represents two particles in contact (or a particle in
+/// contact with immovable scenery).
+///
+/// Resolving a contact removes their interpenetration and applies
+/// sufficient impulse to keep them apart. Colliding bodies may also rebound.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParticleContact {
+	/// Holds the indices of the particles involved in the contact.
+	/// The second of these is `None` for contacts with the scenery.
+	pub particles: [Option<usize>; 2],
+
+	/// Holds the normal restitution coefficient at the contact.
+	pub restitution: Real,
+
+	/// Holds the direction of the contact in world coordinates,
+	/// from the point of view of the first particle.
+	pub contact_normal: Vector3,
+
+	/// Holds the depth of penetration at the contact.
+	pub penetration: Real,
+}
+
+impl ParticleContact {
+	/// Resolves this contact for both velocity and interpenetration,
+	/// returning how far each particle was moved.
+	pub fn resolve(&self, particles: &mut [Particle], duration: Real) -> [Vector3; 2] {
+		self.resolve_stabilized(particles, duration, Stabilization::Projection)
+	}
+
+	/// Resolves this contact, correcting interpenetration with the given
+	/// stabilization method, and returns how far each particle was moved.
+	pub fn resolve_stabilized(
+		&self,
+		particles: &mut [Particle],
+		duration: Real,
+		stabilization: Stabilization,
+	) -> [Vector3; 2] {
+		match stabilization {
+			Stabilization::Projection => {
+				self.resolve_velocity(particles, duration, 0.0);
+				self.resolve_interpenetration(particles, self.penetration)
+			},
+			Stabilization::Baumgarte { factor, slop } => {
+				let bias = if duration > 0.0 {
+					factor * (self.penetration - slop).max(0.0) / duration
+				} else {
+					0.0
+				};
+				self.resolve_velocity(particles, duration, bias);
+				[Vector3::zero(); 2]
+			},
+			Stabilization::PseudoVelocity { factor, slop } => {
+				self.resolve_velocity(particles, duration, 0.0);
+				self.resolve_interpenetration(particles, factor * (self.penetration - slop).max(0.0))
+			},
+		}
+	}
+
+	/// Calculates the separating velocity at this contact.
+	#[must_use]
+	pub fn calculate_separating_velocity(&self, particles: &[Particle]) -> Real {
+		let mut relative_velocity = self.particles[0].map_or_else(Vector3::zero, |index| particles[index].velocity);
+		if let Some(index) = self.particles[1] {
+			relative_velocity -= particles[index].velocity;
+		}
+		relative_velocity.dot(&self.contact_normal)
+	}
+
+	/// Sums the inverse masses of the particles involved in the contact.
+	#[must_use]
+	pub fn total_inverse_mass(&self, particles: &[Particle]) -> Real {
+		self.particles
+			.iter()
+			.flatten()
+			.fold(0.0, |acc, index| acc + particles[*index].inverse_mass)
+	}
+
+	/// Adjusts the penetration of this contact to account for the
+	/// given particles having been moved during resolution.
+	pub fn update_penetration(&mut self, moved: &[Option<usize>; 2], movement: &[Vector3; 2]) {
+		for (slot, sign) in [(0, -1.0), (1, 1.0)] {
+			let Some(particle) = self.particles[slot] else {
+				continue;
+			};
+			if let Some(moved_index) = moved.iter().position(|index| *index == Some(particle)) {
+				self.penetration += sign * movement[moved_index].dot(&self.contact_normal);
+			}
+		}
+	}
+
+	/// Applies the impulse needed to reach the target separating velocity,
+	/// raised by the given bias to push interpenetrating particles apart.
+	fn resolve_velocity(&self, particles: &mut [Particle], duration: Real, bias: Real) {
+		let separating_velocity = self.calculate_separating_velocity(particles);
+
+		// The contact is either separating or stationary, no impulse is required
+		if separating_velocity > bias.max(0.0) {
+			return;
+		}
+
+		let mut new_separating_velocity = -separating_velocity * self.restitution;
+
+		// Check the velocity build-up due to acceleration only
+		let mut acceleration_caused_velocity =
+			self.particles[0].map_or_else(Vector3::zero, |index| particles[index].acceleration);
+		if let Some(index) = self.particles[1] {
+			acceleration_caused_velocity -= particles[index].acceleration;
+		}
+		let acceleration_caused_separating_velocity = acceleration_caused_velocity.dot(&self.contact_normal) * duration;
+
+		// If we've got a closing velocity due to acceleration build-up,
+		// remove it from the new separating velocity
+		if acceleration_caused_separating_velocity < 0.0 {
+			new_separating_velocity = self
+				.restitution
+				.mul_add(acceleration_caused_separating_velocity, new_separating_velocity)
+				.max(0.0);
+		}
+
+		let delta_velocity = new_separating_velocity + bias - separating_velocity;
+
+		// If all particles have infinite mass, impulses have no effect
+		let total_inverse_mass = self.total_inverse_mass(particles);
+		if total_inverse_mass <= 0.0 {
+			return;
+		}
+
+		let impulse_per_inverse_mass = self.contact_normal * (delta_velocity / total_inverse_mass);
+		if let Some(index) = self.particles[0] {
+			let particle = &mut particles[index];
+			particle.velocity += impulse_per_inverse_mass * particle.inverse_mass;
+		}
+		if let Some(index) = self.particles[1] {
+			let particle = &mut particles[index];
+			particle.velocity -= impulse_per_inverse_mass * particle.inverse_mass;
+		}
+	}
+
+	/// Moves the particles apart along the contact normal by the given depth.
+	fn resolve_interpenetration(&self, particles: &mut [Particle], penetration: Real) -> [Vector3; 2] {
+		let mut movement = [Vector3::zero(); 2];
+
+		if penetration <= 0.0 {
+			return movement;
+		}
+
+		// If all particles have infinite mass, they cannot be moved
+		let total_inverse_mass = self.total_inverse_mass(particles);
+		if total_inverse_mass <= 0.0 {
+			return movement;
+		}
+
+		// Each particle is moved in proportion to its inverse mass
+		let move_per_inverse_mass = self.contact_normal * (penetration / total_inverse_mass);
+		if let Some(index) = self.particles[0] {
+			movement[0] = move_per_inverse_mass * particles[index].inverse_mass;
+			particles[index].position += movement[0];
+		}
+		if let Some(index) = self.particles[1] {
+			movement[1] = -move_per_inverse_mass * particles[index].inverse_mass;
+			particles[index].position += movement[1];
+		}
+
+		movement
+	}
+}
+
+/// How contact resolution corrects interpenetration.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Stabilization {
+	/// Particles are moved directly out of interpenetration.
+	#[default]
+	Projection,
+
+	/// A bias is added to the separating velocity so particles drift apart over
+	/// the following steps. `factor` is the fraction of the penetration beyond
+	/// `slop` corrected per step. The bias adds real velocity, so it can make
+	/// resting contacts visibly jitter if the factor is too high.
+	Baumgarte { factor: Real, slop: Real },
+
+	/// Like `Baumgarte`, but the bias moves the particles without being added to
+	/// their velocities, so drift is corrected without injecting energy.
+	PseudoVelocity { factor: Real, slop: Real },
+}
+
+/// The contact resolution routine for particle contacts.
+/// One resolver instance can be shared for the whole simulation.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParticleContactResolver {
+	/// Holds the number of iterations allowed.
+	pub iterations: usize,
+
+	/// Records the number of iterations actually used
+	/// during the last call to `resolve_contacts`.
+	pub iterations_used: usize,
+
+	/// Holds how interpenetration is corrected.
+	pub stabilization: Stabilization,
+
+	/// Holds the up direction used for an extra shock-propagation sweep
+	/// after the iterative resolution, or `None` to skip the sweep.
+	///
+	/// The sweep resolves contacts once more from the bottom up, treating the
+	/// lower particle of each contact as if it had infinite mass. Weight is
+	/// then never pushed back down into a settled layer, so tall stacks of
+	/// particles stay standing instead of jittering and collapsing.
+	pub shock_propagation: Option<Vector3>,
+}
+
+impl ParticleContactResolver {
+	#[must_use]
+	pub const fn new(iterations: usize) -> Self {
+		Self {
+			iterations,
+			iterations_used: 0,
+			stabilization: Stabilization::Projection,
+			shock_propagation: None,
+		}
+	}
+
+	/// Resolves a set of particle contacts for both penetration and velocity.
+	/// Contacts are resolved in order of severity, the one with the largest
+	/// closing velocity first, until the iteration budget runs out.
+	pub fn resolve_contacts(&mut self, contacts: &mut [ParticleContact], particles: &mut [Particle], duration: Real) {
+		self.iterations_used = 0;
+		while self.iterations_used < self.iterations {
+			// Find the contact with the largest closing velocity
+			let mut max = Real::MAX;
+			let mut max_index = None;
+			for (index, contact) in contacts.iter().enumerate() {
+				let separating_velocity = contact.calculate_separating_velocity(particles);
+				if separating_velocity < max && (separating_velocity < 0.0 || contact.penetration > 0.0) {
+					max = separating_velocity;
+					max_index = Some(index);
+				}
+			}
+
+			// Nothing left worth resolving
+			let Some(max_index) = max_index else {
+				break;
+			};
+
+			let resolved = contacts[max_index];
+			let movement = resolved.resolve_stabilized(particles, duration, self.stabilization);
+			contacts[max_index].penetration = 0.0;
+
+			// Update the interpenetrations of the other contacts
+			// that share particles with the resolved one
+			for (index, contact) in contacts.iter_mut().enumerate() {
+				if index == max_index {
+					continue;
+				}
+				contact.update_penetration(&resolved.particles, &movement);
+			}
+
+			self.iterations_used += 1;
+		}
+
+		if let Some(up) = self.shock_propagation {
+			self.propagate_shock(contacts, particles, duration, up);
+		}
+	}
+
+	/// Resolves every contact once, lowest first, with the lower particle
+	/// of each contact temporarily given infinite mass.
+	fn propagate_shock(
+		&self,
+		contacts: &mut [ParticleContact],
+		particles: &mut [Particle],
+		duration: Real,
+		up: Vector3,
+	) {
+		let height = |index: &Option<usize>| index.map_or(Real::MIN, |index| particles[index].position.dot(&up));
+		let mut order: Vec<(Real, usize)> = contacts
+			.iter()
+			.enumerate()
+			.map(|(index, contact)| (height(&contact.particles[0]).min(height(&contact.particles[1])), index))
+			.collect();
+		order.sort_by(|first, second| first.0.total_cmp(&second.0));
+
+		for (_, contact_index) in order {
+			let contact = contacts[contact_index];
+			if contact.calculate_separating_velocity(particles) >= 0.0 && contact.penetration <= 0.0 {
+				continue;
+			}
+
+			// The lower particle is held fixed while this contact is resolved
+			let lower = match contact.particles {
+				[Some(first), Some(second)]
+					if particles[first].position.dot(&up) < particles[second].position.dot(&up) =>
+				{
+					Some(first)
+				},
+				[Some(_), Some(second)] => Some(second),
+				_ => None,
+			};
+			let inverse_mass = lower.map(|index| std::mem::take(&mut particles[index].inverse_mass));
+			let movement = contact.resolve_stabilized(particles, duration, self.stabilization);
+			if let (Some(index), Some(inverse_mass)) = (lower, inverse_mass) {
+				particles[index].inverse_mass = inverse_mass;
+			}
+
+			contacts[contact_index].penetration = 0.0;
+			for (index, other) in contacts.iter_mut().enumerate() {
+				if index != contact_index {
+					other.update_penetration(&contact.particles, &movement);
+				}
+			}
+		}
+	}
+}
+
+/// A contact generator is responsible for examining the state of the
+/// particles and creating the contacts that need resolving.
+pub trait ParticleContactGenerator {
+	/// Appends any contacts that currently exist to the given list,
+	/// returning the number of contacts that were added.
+	fn add_contact(&self, particles: &[Particle], contacts: &mut Vec<ParticleContact>) -> usize;
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::assert_equal;
+
+	use super::*;
+
+	fn particle(position: Vector3, velocity: Vector3) -> Particle {
+		Particle {
+			position,
+			velocity,
+			inverse_mass: 1.0,
+			damping: 1.0,
+			..Default::default()
+		}
+	}
+
+	#[test]
+	pub fn separating_velocity() {
+		let particles = [
+			particle(Vector3::zero(), Vector3::new(1.0, 0.0, 0.0)),
+			particle(Vector3::x_axis(), Vector3::new(-1.0, 0.0, 0.0)),
+		];
+		let contact = ParticleContact {
+			particles: [Some(0), Some(1)],
+			contact_normal: Vector3::new(-1.0, 0.0, 0.0),
+			..Default::default()
+		};
+		assert_equal(contact.calculate_separating_velocity(&particles), -2.0);
+	}
+
+	#[test]
+	pub fn resolve_velocity_with_restitution() {
+		let mut particles = [
+			particle(Vector3::zero(), Vector3::new(1.0, 0.0, 0.0)),
+			particle(Vector3::x_axis(), Vector3::new(-1.0, 0.0, 0.0)),
+		];
+		let contact = ParticleContact {
+			particles: [Some(0), Some(1)],
+			restitution: 1.0,
+			contact_normal: Vector3::new(-1.0, 0.0, 0.0),
+			penetration: 0.0,
+		};
+		contact.resolve(&mut particles, 1.0);
+		assert_eq!(particles[0].velocity, Vector3::new(-1.0, 0.0, 0.0));
+		assert_eq!(particles[1].velocity, Vector3::new(1.0, 0.0, 0.0));
+	}
+
+	#[test]
+	pub fn resolve_interpenetration_against_scenery() {
+		let mut particles = [particle(Vector3::new(0.0, -0.5, 0.0), Vector3::zero())];
+		let contact = ParticleContact {
+			particles: [Some(0), None],
+			contact_normal: Vector3::y_axis(),
+			penetration: 0.5,
+			..Default::default()
+		};
+		let movement = contact.resolve(&mut particles, 1.0);
+		assert_eq!(particles[0].position, Vector3::zero());
+		assert_eq!(movement[0], Vector3::new(0.0, 0.5, 0.0));
+	}
+
+	#[test]
+	pub fn resolver_stops_when_resolved() {
+		let mut particles = [particle(Vector3::new(0.0, -0.5, 0.0), Vector3::new(0.0, -1.0, 0.0))];
+		let mut contacts = [ParticleContact {
+			particles: [Some(0), None],
+			contact_normal: Vector3::y_axis(),
+			penetration: 0.5,
+			..Default::default()
+		}];
+		let mut resolver = ParticleContactResolver::new(10);
+		resolver.resolve_contacts(&mut contacts, &mut particles, 1.0);
+		assert_eq!(resolver.iterations_used, 1);
+		assert_eq!(particles[0].velocity, Vector3::zero());
+	}
+
+	#[test]
+	pub fn baumgarte_biases_velocity_instead_of_moving() {
+		let mut particles = [particle(Vector3::new(0.0, -0.5, 0.0), Vector3::zero())];
+		let contact = ParticleContact {
+			particles: [Some(0), None],
+			contact_normal: Vector3::y_axis(),
+			penetration: 0.5,
+			..Default::default()
+		};
+		let stabilization = Stabilization::Baumgarte { factor: 0.2, slop: 0.0 };
+		let movement = contact.resolve_stabilized(&mut particles, 0.1, stabilization);
+		assert_eq!(movement[0], Vector3::zero());
+		assert_eq!(particles[0].position, Vector3::new(0.0, -0.5, 0.0));
+		assert_eq!(particles[0].velocity, Vector3::y_axis());
+	}
+
+	#[test]
+	pub fn pseudo_velocity_moves_without_adding_velocity() {
+		let mut particles = [particle(Vector3::new(0.0, -0.5, 0.0), Vector3::zero())];
+		let contact = ParticleContact {
+			particles: [Some(0), None],
+			contact_normal: Vector3::y_axis(),
+			penetration: 0.5,
+			..Default::default()
+		};
+		let stabilization = Stabilization::PseudoVelocity { factor: 0.5, slop: 0.1 };
+		contact.resolve_stabilized(&mut particles, 0.1, stabilization);
+		assert_eq!(particles[0].position, Vector3::new(0.0, -0.3, 0.0));
+		assert_eq!(particles[0].velocity, Vector3::zero());
+	}
+
+	#[test]
+	pub fn shock_propagation_holds_lower_particles() {
+		// Two grains stacked on the ground, both sinking into what is below them
+		let mut particles = [
+			particle(Vector3::new(0.0, -0.1, 0.0), Vector3::zero()),
+			particle(Vector3::new(0.0, 0.8, 0.0), Vector3::new(0.0, -1.0, 0.0)),
+		];
+		let mut contacts = [
+			ParticleContact {
+				particles: [Some(0), None],
+				contact_normal: Vector3::y_axis(),
+				penetration: 0.1,
+				..Default::default()
+			},
+			ParticleContact {
+				particles: [Some(1), Some(0)],
+				contact_normal: Vector3::y_axis(),
+				penetration: 0.1,
+				..Default::default()
+			},
+		];
+		let mut resolver = ParticleContactResolver::new(0);
+		resolver.shock_propagation = Some(Vector3::y_axis());
+		resolver.resolve_contacts(&mut contacts, &mut particles, 1.0);
+		assert_eq!(particles[0].position, Vector3::zero());
+		assert_eq!(particles[0].velocity, Vector3::zero());
+		assert_equal(particles[1].position.y(), 1.0);
+		assert_eq!(particles[1].velocity, Vector3::zero());
+		assert!(particles[0].has_finite_mass());
+	}
+}
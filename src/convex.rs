@@ -0,0 +1,860 @@
+use crate::{
+	bounding::Aabb,
+	collide::{CollisionBox, CollisionCapsule, CollisionCylinder, CollisionPrimitive, CollisionSphere},
+	real_from_count,
+	trimesh::Triangle,
+	vec::Vector3,
+	Real,
+};
+
+/// A convex shape described by its support mapping.
+///
+/// The support mapping gives the point of the shape that reaches furthest
+/// in any direction. Any two shapes that provide one can be tested against
+/// each other with [`closest_points`] and [`penetration`], whatever their kinds.
+pub trait SupportMap {
+	/// Returns the point of the shape furthest along the given direction,
+	/// in world coordinates. The direction need not be of unit length.
+	fn support(&self, direction: Vector3) -> Vector3;
+
+	/// Returns a point inside the shape, in world coordinates.
+	fn center(&self) -> Vector3;
+
+	/// Returns how far outside the shape contacts begin to be generated.
+	fn margin(&self) -> Real {
+		0.0
+	}
+
+	/// Returns the smallest axis-aligned box enclosing the shape and its
+	/// margin, found from its support points along each axis.
+	fn bounds(&self) -> Aabb {
+		let mut bounds = Aabb::default();
+		for axis in 0..3 {
+			let mut direction = Vector3::zero();
+			direction[axis] = 1.0;
+			bounds.min[axis] = self.support(-direction)[axis] - self.margin();
+			bounds.max[axis] = self.support(direction)[axis] + self.margin();
+		}
+		bounds
+	}
+}
+
+impl SupportMap for CollisionSphere {
+	fn support(&self, direction: Vector3) -> Vector3 {
+		self.primitive.position() + direction.normalize() * self.radius
+	}
+
+	fn center(&self) -> Vector3 {
+		self.primitive.position()
+	}
+
+	fn margin(&self) -> Real {
+		self.primitive.margin
+	}
+}
+
+impl SupportMap for CollisionBox {
+	fn support(&self, direction: Vector3) -> Vector3 {
+		let transform = self.primitive.transform();
+		let local = transform.transform_inverse_direction(direction);
+		let mut vertex = self.half_size;
+		for axis in 0..3 {
+			if local[axis] < 0.0 {
+				vertex[axis] = -vertex[axis];
+			}
+		}
+		transform.transform(vertex)
+	}
+
+	fn center(&self) -> Vector3 {
+		self.primitive.position()
+	}
+
+	fn margin(&self) -> Real {
+		self.primitive.margin
+	}
+}
+
+impl SupportMap for CollisionCapsule {
+	fn support(&self, direction: Vector3) -> Vector3 {
+		let [top, bottom] = self.segment();
+		let end = if direction.dot(&(top - bottom)) < 0.0 {
+			bottom
+		} else {
+			top
+		};
+		end + direction.normalize() * self.radius
+	}
+
+	fn center(&self) -> Vector3 {
+		self.primitive.position()
+	}
+
+	fn margin(&self) -> Real {
+		self.primitive.margin
+	}
+}
+
+impl SupportMap for CollisionCylinder {
+	fn support(&self, direction: Vector3) -> Vector3 {
+		let transform = self.primitive.transform();
+		let local = transform.transform_inverse_direction(direction);
+		let radial = Vector3::new(local.x(), 0.0, local.z()).normalize() * self.radius;
+		let height = if local.y() < 0.0 {
+			-self.half_height
+		} else {
+			self.half_height
+		};
+		transform.transform(radial + Vector3::new(0.0, height, 0.0))
+	}
+
+	fn center(&self) -> Vector3 {
+		self.primitive.position()
+	}
+
+	fn margin(&self) -> Real {
+		self.primitive.margin
+	}
+}
+
+/// A point treated as a convex shape with no size.
+impl SupportMap for Vector3 {
+	fn support(&self, _direction: Vector3) -> Vector3 {
+		*self
+	}
+
+	fn center(&self) -> Vector3 {
+		*self
+	}
+}
+
+impl SupportMap for Aabb {
+	fn support(&self, direction: Vector3) -> Vector3 {
+		let mut corner = self.max;
+		for axis in 0..3 {
+			if direction[axis] < 0.0 {
+				corner[axis] = self.min[axis];
+			}
+		}
+		corner
+	}
+
+	fn center(&self) -> Vector3 {
+		Self::center(self)
+	}
+}
+
+impl SupportMap for Triangle {
+	fn support(&self, direction: Vector3) -> Vector3 {
+		self.vertices
+			.into_iter()
+			.max_by(|first, second| first.dot(&direction).total_cmp(&second.dot(&direction)))
+			.unwrap_or_default()
+	}
+
+	fn center(&self) -> Vector3 {
+		self.centroid()
+	}
+}
+
+/// A convex shape attached to a rigid body, given by points in the
+/// primitive's own coordinates. The shape is the convex hull of the points,
+/// so points inside the hull do no harm.
+#[derive(Debug, Clone)]
+pub struct ConvexHull {
+	pub primitive: CollisionPrimitive,
+	pub points: Vec<Vector3>,
+}
+
+impl SupportMap for ConvexHull {
+	fn support(&self, direction: Vector3) -> Vector3 {
+		let transform = self.primitive.transform();
+		let local = transform.transform_inverse_direction(direction);
+		let furthest = self
+			.points
+			.iter()
+			.max_by(|first, second| first.dot(&local).total_cmp(&second.dot(&local)))
+			.copied()
+			.unwrap_or_default();
+		transform.transform(furthest)
+	}
+
+	fn center(&self) -> Vector3 {
+		let sum = self.points.iter().fold(Vector3::zero(), |sum, point| sum + *point);
+		let count = real_from_count(self.points.len()).max(1.0);
+		self.primitive.transform().transform(sum * count.recip())
+	}
+
+	fn margin(&self) -> Real {
+		self.primitive.margin
+	}
+}
+
+/// The closest points between two separated convex shapes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClosestPoints {
+	/// Holds the distance between the shapes.
+	pub distance: Real,
+
+	/// Holds the closest point on the first shape and on the second, in world coordinates.
+	pub points: [Vector3; 2],
+}
+
+/// How deeply two convex shapes overlap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Penetration {
+	/// Holds the unit direction from the first shape into the second along
+	/// which they overlap least. Moving the first shape back along it by the
+	/// depth separates them.
+	pub normal: Vector3,
+
+	/// Holds how far the shapes overlap along the normal.
+	pub depth: Real,
+
+	/// Holds the deepest point of the first shape inside the second and of
+	/// the second inside the first, in world coordinates.
+	pub points: [Vector3; 2],
+}
+
+/// Returns true if the two convex shapes overlap.
+pub fn intersects<F: SupportMap + ?Sized, S: SupportMap + ?Sized>(first: &F, second: &S) -> bool {
+	matches!(gjk(first, second), Gjk::Intersecting(_))
+}
+
+/// Returns the closest points between two convex shapes, or `None` if they overlap.
+pub fn closest_points<F: SupportMap + ?Sized, S: SupportMap + ?Sized>(first: &F, second: &S) -> Option<ClosestPoints> {
+	match gjk(first, second) {
+		Gjk::Separated(simplex) => {
+			let on_first = weighted(&simplex, |point| point.on_first);
+			let on_second = weighted(&simplex, |point| point.on_second);
+			Some(ClosestPoints {
+				distance: (on_first - on_second).magnitude(),
+				points: [on_first, on_second],
+			})
+		},
+		Gjk::Intersecting(_) => None,
+	}
+}
+
+/// Returns how deeply two convex shapes overlap, or `None` if they are
+/// separated. Shapes that only touch may also give `None`, as they have no
+/// depth to measure.
+pub fn penetration<F: SupportMap + ?Sized, S: SupportMap + ?Sized>(first: &F, second: &S) -> Option<Penetration> {
+	match gjk(first, second) {
+		Gjk::Intersecting(simplex) => {
+			let support = |direction| support_point(first, second, direction);
+			let mut points: Vec<SupportPoint> = simplex.iter().map(|(point, _)| *point).collect();
+			complete_tetrahedron(&support, &mut points).then(|| epa(&support, points))
+		},
+		Gjk::Separated(_) => None,
+	}
+}
+
+/// Where a convex shape moving in a straight line first touches another.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapeHit {
+	/// Holds how far the shape travels before it touches, which is the
+	/// time of impact for a shape moving at unit speed.
+	pub distance: Real,
+
+	/// Holds the point of first touch, on the surface of the other shape.
+	pub point: Vector3,
+
+	/// Holds the unit normal of the other shape at the point, facing the moving shape.
+	pub normal: Vector3,
+}
+
+/// A convex shape moved by an offset, to test it where it will be.
+#[derive(Debug, Clone, Copy)]
+pub struct Translated<'a, S: SupportMap + ?Sized> {
+	pub shape: &'a S,
+	pub offset: Vector3,
+}
+
+impl<S: SupportMap + ?Sized> SupportMap for Translated<'_, S> {
+	fn support(&self, direction: Vector3) -> Vector3 {
+		self.shape.support(direction) + self.offset
+	}
+
+	fn center(&self) -> Vector3 {
+		self.shape.center() + self.offset
+	}
+
+	fn margin(&self) -> Real {
+		self.shape.margin()
+	}
+}
+
+/// The most steps a cast takes towards the other shape.
+const MAX_CAST_ITERATIONS: usize = 64;
+
+/// How close a cast must bring the shapes to count as touching.
+const CAST_TOLERANCE: Real = 1e-3;
+
+/// Sweeps the first shape along the direction, returning where it first
+/// touches the second within the given distance, or `None` if it misses.
+///
+/// The shape is advanced conservatively: each step moves it by the gap
+/// between the shapes divided by how fast it closes that gap, which can
+/// never carry it through the other shape. A shape that starts out
+/// overlapping hits at a distance of zero.
+pub fn convex_cast<F: SupportMap + ?Sized, S: SupportMap + ?Sized>(
+	shape: &F,
+	direction: Vector3,
+	max_distance: Real,
+	target: &S,
+) -> Option<ShapeHit> {
+	let direction = direction.normalize();
+	let mut travelled = 0.0;
+	let mut normal = -direction;
+	for _ in 0..MAX_CAST_ITERATIONS {
+		let moved = Translated {
+			shape,
+			offset: direction * travelled,
+		};
+		let Some(closest) = closest_points(&moved, target) else {
+			return Some(overlap_hit(&moved, target, travelled, normal));
+		};
+		let [on_shape, on_target] = closest.points;
+		if closest.distance > CAST_TOLERANCE * 0.5 {
+			normal = (on_shape - on_target) * closest.distance.recip();
+		}
+		if closest.distance <= CAST_TOLERANCE {
+			return Some(ShapeHit {
+				distance: travelled,
+				point: on_target,
+				normal,
+			});
+		}
+		let closing = -direction.dot(&normal);
+		if closing <= Real::EPSILON {
+			return None;
+		}
+		travelled += closest.distance / closing;
+		if travelled > max_distance {
+			return None;
+		}
+	}
+	None
+}
+
+/// Returns the hit for a cast shape found overlapping the other shape.
+fn overlap_hit<F: SupportMap + ?Sized, S: SupportMap + ?Sized>(
+	moved: &Translated<'_, F>,
+	target: &S,
+	travelled: Real,
+	normal: Vector3,
+) -> ShapeHit {
+	penetration(moved, target).map_or_else(
+		|| ShapeHit {
+			distance: travelled,
+			point: moved.center(),
+			normal,
+		},
+		|overlap| ShapeHit {
+			distance: travelled,
+			point: overlap.points[1],
+			normal: -overlap.normal,
+		},
+	)
+}
+
+/// The most iterations GJK takes to find the closest point to the origin.
+const MAX_GJK_ITERATIONS: usize = 64;
+
+/// The most points EPA adds to its polytope before settling for the nearest face found.
+const MAX_EPA_ITERATIONS: usize = 64;
+
+/// How close the Minkowski difference must come to the origin for two shapes to overlap.
+const CONTAINS_ORIGIN: Real = 1e-5;
+
+/// How little a new support point may improve the distance, relative to
+/// the distance itself, before GJK stops searching.
+const GJK_TOLERANCE: Real = 1e-4;
+
+/// How little a new support point may extend the polytope beyond its
+/// nearest face before EPA stops expanding.
+const EPA_TOLERANCE: Real = 1e-4;
+
+/// A point on the Minkowski difference of two shapes, remembering the
+/// support points on each shape it came from.
+#[derive(Debug, Default, Clone, Copy)]
+struct SupportPoint {
+	point: Vector3,
+	on_first: Vector3,
+	on_second: Vector3,
+}
+
+fn support_point<F: SupportMap + ?Sized, S: SupportMap + ?Sized>(
+	first: &F,
+	second: &S,
+	direction: Vector3,
+) -> SupportPoint {
+	let on_first = first.support(direction);
+	let on_second = second.support(-direction);
+	SupportPoint {
+		point: on_first - on_second,
+		on_first,
+		on_second,
+	}
+}
+
+/// Support points with the barycentric weights of the simplex's point
+/// closest to the origin. A simplex has at most four points, so they are
+/// held in place rather than in a list of their own.
+#[derive(Debug, Default, Clone, Copy)]
+struct Simplex {
+	points: [(SupportPoint, Real); 4],
+	len: usize,
+}
+
+impl Simplex {
+	fn new(points: &[(SupportPoint, Real)]) -> Self {
+		points.iter().copied().collect()
+	}
+}
+
+impl FromIterator<(SupportPoint, Real)> for Simplex {
+	fn from_iter<I: IntoIterator<Item = (SupportPoint, Real)>>(points: I) -> Self {
+		let mut simplex = Self::default();
+		for point in points.into_iter().take(4) {
+			simplex.points[simplex.len] = point;
+			simplex.len += 1;
+		}
+		simplex
+	}
+}
+
+impl std::ops::Deref for Simplex {
+	type Target = [(SupportPoint, Real)];
+
+	fn deref(&self) -> &Self::Target {
+		&self.points[..self.len]
+	}
+}
+
+fn weighted(simplex: &[(SupportPoint, Real)], part: impl Fn(&SupportPoint) -> Vector3) -> Vector3 {
+	simplex
+		.iter()
+		.fold(Vector3::zero(), |sum, (point, weight)| sum + part(point) * *weight)
+}
+
+enum Gjk {
+	/// The shapes are apart, and the simplex holds their closest points.
+	Separated(Simplex),
+
+	/// The shapes overlap, and the simplex's points enclose or touch the origin.
+	Intersecting(Simplex),
+}
+
+/// Searches the Minkowski difference of the two shapes for the point
+/// closest to the origin, which is inside it when the shapes overlap.
+fn gjk<F: SupportMap + ?Sized, S: SupportMap + ?Sized>(first: &F, second: &S) -> Gjk {
+	let start = first.center() - second.center();
+	let start = if start.magnitude_squared() > 0.0 {
+		start
+	} else {
+		Vector3::x_axis()
+	};
+	let mut simplex = Simplex::new(&[(support_point(first, second, start), 1.0)]);
+	for _ in 0..MAX_GJK_ITERATIONS {
+		let closest = weighted(&simplex, |point| point.point);
+		let distance_squared = closest.magnitude_squared();
+		if distance_squared <= CONTAINS_ORIGIN * CONTAINS_ORIGIN {
+			return Gjk::Intersecting(simplex);
+		}
+
+		// Stop once no point of the difference is meaningfully closer
+		let next = support_point(first, second, -closest);
+		if distance_squared - closest.dot(&next.point) <= GJK_TOLERANCE * distance_squared {
+			break;
+		}
+		let points = simplex.iter().copied().chain([(next, 0.0)]).collect();
+		match closest_on_simplex(&points) {
+			Some(reduced) => simplex = reduced,
+			None => return Gjk::Intersecting(points),
+		}
+	}
+	Gjk::Separated(simplex)
+}
+
+/// Returns the smallest part of the simplex holding its point closest to
+/// the origin, or `None` if the simplex is a tetrahedron enclosing the origin.
+fn closest_on_simplex(points: &Simplex) -> Option<Simplex> {
+	match **points {
+		[(a, _), (b, _)] => Some(closest_on_segment(a, b)),
+		[(a, _), (b, _), (c, _)] => Some(closest_on_triangle([a, b, c])),
+		[(a, _), (b, _), (c, _), (d, _)] => closest_on_tetrahedron([a, b, c, d]),
+		_ => Some(points.iter().map(|(point, _)| (*point, 1.0)).collect()),
+	}
+}
+
+fn closest_on_segment(a: SupportPoint, b: SupportPoint) -> Simplex {
+	let line = b.point - a.point;
+	let length_squared = line.magnitude_squared();
+	if length_squared <= 0.0 {
+		return Simplex::new(&[(a, 1.0)]);
+	}
+	let along = -a.point.dot(&line) / length_squared;
+	if along <= 0.0 {
+		Simplex::new(&[(a, 1.0)])
+	} else if along >= 1.0 {
+		Simplex::new(&[(b, 1.0)])
+	} else {
+		Simplex::new(&[(a, 1.0 - along), (b, along)])
+	}
+}
+
+fn closest_on_triangle(triangle: [SupportPoint; 3]) -> Simplex {
+	let corners = triangle.map(|point| point.point);
+	let normal = (corners[1] - corners[0]).cross(&(corners[2] - corners[0])).normalize();
+	let projection = normal * normal.dot(&corners[0]);
+	if let Some(weights) = Triangle::new(corners).barycentric(projection) {
+		if weights.iter().all(|weight| *weight >= 0.0) {
+			return triangle.into_iter().zip(weights).collect();
+		}
+	}
+
+	// The origin projects outside the triangle, so the closest point is on an edge
+	[(0, 1), (1, 2), (2, 0)]
+		.into_iter()
+		.map(|(start, end)| closest_on_segment(triangle[start], triangle[end]))
+		.min_by(|first, second| {
+			let first_distance = weighted(first, |point| point.point).magnitude_squared();
+			let second_distance = weighted(second, |point| point.point).magnitude_squared();
+			first_distance.total_cmp(&second_distance)
+		})
+		.unwrap_or_default()
+}
+
+fn closest_on_tetrahedron(tetrahedron: [SupportPoint; 4]) -> Option<Simplex> {
+	let faces = [[0, 1, 2, 3], [0, 3, 1, 2], [0, 2, 3, 1], [1, 3, 2, 0]];
+	faces
+		.into_iter()
+		.filter(|[a, b, c, opposite]| {
+			// Only faces with the origin on the far side from the opposite vertex can hold the closest point
+			let corner = tetrahedron[*a].point;
+			let normal = (tetrahedron[*b].point - corner).cross(&(tetrahedron[*c].point - corner));
+			normal.dot(&-corner) * normal.dot(&(tetrahedron[*opposite].point - corner)) <= 0.0
+		})
+		.map(|[a, b, c, _]| closest_on_triangle([tetrahedron[a], tetrahedron[b], tetrahedron[c]]))
+		.min_by(|first, second| {
+			let first_distance = weighted(first, |point| point.point).magnitude_squared();
+			let second_distance = weighted(second, |point| point.point).magnitude_squared();
+			first_distance.total_cmp(&second_distance)
+		})
+}
+
+/// The directions searched to grow a single point into a line.
+const AXES: [Vector3; 6] = [
+	Vector3::x_axis(),
+	Vector3::new(-1.0, 0.0, 0.0),
+	Vector3::y_axis(),
+	Vector3::new(0.0, -1.0, 0.0),
+	Vector3::z_axis(),
+	Vector3::new(0.0, 0.0, -1.0),
+];
+
+/// Grows the simplex GJK stopped at into a tetrahedron for EPA to start
+/// from, returning false if the Minkowski difference is too flat to hold one.
+fn complete_tetrahedron(support: &impl Fn(Vector3) -> SupportPoint, points: &mut Vec<SupportPoint>) -> bool {
+	while points.len() < 4 {
+		let base = points[0].point;
+		let directions = match points.len() {
+			1 => AXES.to_vec(),
+			2 => {
+				let line = points[1].point - base;
+				let axis = (0..3)
+					.min_by(|first, second| line[*first].abs().total_cmp(&line[*second].abs()))
+					.map_or_else(Vector3::x_axis, |axis| AXES[axis * 2]);
+				let across = line.cross(&axis);
+				let other = line.cross(&across);
+				vec![across, -across, other, -other]
+			},
+			_ => {
+				let normal = (points[1].point - base).cross(&(points[2].point - base));
+				vec![normal, -normal]
+			},
+		};
+		let Some(next) = directions
+			.into_iter()
+			.map(support)
+			.find(|candidate| adds_dimension(points, candidate.point))
+		else {
+			return false;
+		};
+		points.push(next);
+	}
+	true
+}
+
+/// Returns true if the point lies clear of the line or plane through the points.
+fn adds_dimension(points: &[SupportPoint], point: Vector3) -> bool {
+	let base = points[0].point;
+	let offset = point - base;
+	match points {
+		[_] => offset.magnitude() > CONTAINS_ORIGIN,
+		[_, second] => (second.point - base).normalize().cross(&offset).magnitude() > CONTAINS_ORIGIN,
+		_ => {
+			let normal = (points[1].point - base).cross(&(points[2].point - base)).normalize();
+			normal.dot(&offset).abs() > CONTAINS_ORIGIN
+		},
+	}
+}
+
+/// A face of the polytope EPA expands, wound so that its normal points outward.
+#[derive(Debug, Clone, Copy)]
+struct Face {
+	indices: [usize; 3],
+	normal: Vector3,
+	distance: Real,
+}
+
+impl Face {
+	fn new(vertices: &[SupportPoint], indices: [usize; 3]) -> Self {
+		let [a, b, c] = indices.map(|index| vertices[index].point);
+		let normal = (b - a).cross(&(c - a)).normalize();
+
+		// A degenerate face has no direction, so it is never the nearest
+		let distance = if normal.magnitude_squared() > 0.0 {
+			normal.dot(&a)
+		} else {
+			Real::MAX
+		};
+		Self {
+			indices,
+			normal,
+			distance,
+		}
+	}
+}
+
+/// Expands a polytope inside the Minkowski difference from the tetrahedron
+/// until its face nearest the origin lies on the surface of the difference.
+fn epa(support: &impl Fn(Vector3) -> SupportPoint, mut vertices: Vec<SupportPoint>) -> Penetration {
+	let centroid = vertices.iter().fold(Vector3::zero(), |sum, vertex| sum + vertex.point) * 0.25;
+	let mut faces: Vec<Face> = [[0, 1, 2], [0, 3, 1], [0, 2, 3], [1, 3, 2]]
+		.into_iter()
+		.map(|indices| {
+			let face = Face::new(&vertices, indices);
+			if face.normal.dot(&(vertices[indices[0]].point - centroid)) < 0.0 {
+				Face::new(&vertices, [indices[0], indices[2], indices[1]])
+			} else {
+				face
+			}
+		})
+		.collect();
+
+	let mut nearest = faces[0];
+	for _ in 0..MAX_EPA_ITERATIONS {
+		nearest = faces
+			.iter()
+			.copied()
+			.min_by(|first, second| first.distance.total_cmp(&second.distance))
+			.unwrap_or(nearest);
+		let next = support(nearest.normal);
+		if next.point.dot(&nearest.normal) - nearest.distance <= EPA_TOLERANCE {
+			break;
+		}
+		vertices.push(next);
+		expand(&mut faces, &vertices);
+	}
+	penetration_from_face(&nearest, &vertices)
+}
+
+/// Replaces every face that can see the newest vertex with faces joining
+/// the vertex to the edges around the hole they leave.
+fn expand(faces: &mut Vec<Face>, vertices: &[SupportPoint]) {
+	let newest = vertices.len() - 1;
+	let point = vertices[newest].point;
+	let mut horizon: Vec<[usize; 2]> = Vec::new();
+	faces.retain(|face| {
+		let visible = face.normal.dot(&(point - vertices[face.indices[0]].point)) > 0.0;
+		if visible {
+			for corner in 0..3 {
+				let edge = [face.indices[corner], face.indices[(corner + 1) % 3]];
+				// An edge shared with another visible face is inside the hole
+				match horizon.iter().position(|other| *other == [edge[1], edge[0]]) {
+					Some(shared) => {
+						horizon.swap_remove(shared);
+					},
+					None => horizon.push(edge),
+				}
+			}
+		}
+		!visible
+	});
+	faces.extend(
+		horizon
+			.into_iter()
+			.map(|[start, end]| Face::new(vertices, [start, end, newest])),
+	);
+}
+
+fn penetration_from_face(face: &Face, vertices: &[SupportPoint]) -> Penetration {
+	let triangle = face.indices.map(|index| vertices[index]);
+	let depth = face.distance.max(0.0);
+	let weights = Triangle::new(triangle.map(|vertex| vertex.point))
+		.barycentric(face.normal * depth)
+		.unwrap_or([1.0, 0.0, 0.0]);
+	let simplex: Simplex = triangle.into_iter().zip(weights).collect();
+	Penetration {
+		normal: face.normal,
+		depth,
+		points: [
+			weighted(&simplex, |point| point.on_first),
+			weighted(&simplex, |point| point.on_second),
+		],
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::f32::consts::FRAC_PI_4;
+
+	use crate::{assert_equal, body::RigidBody, quaternion::Quaternion};
+
+	use super::*;
+
+	fn primitive(position: Vector3, orientation: Quaternion) -> CollisionPrimitive {
+		let mut body = RigidBody {
+			position,
+			orientation,
+			inverse_mass: 1.0,
+			..Default::default()
+		};
+		body.calculate_derived_data();
+		let mut primitive = CollisionPrimitive::new(0);
+		primitive.calculate_internals(&[body]);
+		primitive
+	}
+
+	fn sphere(position: Vector3, radius: Real) -> CollisionSphere {
+		CollisionSphere {
+			primitive: primitive(position, Quaternion::default()),
+			radius,
+		}
+	}
+
+	fn cuboid(position: Vector3, orientation: Quaternion) -> CollisionBox {
+		CollisionBox {
+			primitive: primitive(position, orientation),
+			half_size: Vector3::new(1.0, 1.0, 1.0),
+		}
+	}
+
+	fn is_near(actual: Vector3, expected: Vector3) -> bool {
+		(actual - expected).magnitude() < 1e-3
+	}
+
+	#[test]
+	pub fn cast_finds_first_touch() {
+		let ball = sphere(Vector3::zero(), 0.5);
+		let wall = cuboid(Vector3::new(5.0, 0.0, 0.0), Quaternion::default());
+		let hit = convex_cast(&ball, Vector3::new(2.0, 0.0, 0.0), 10.0, &wall).expect("the ball reaches the wall");
+		assert!((hit.distance - 3.5).abs() < 1e-2);
+		assert!(is_near(hit.point, Vector3::new(4.0, 0.0, 0.0)));
+		assert!(hit.normal.dot(&-Vector3::x_axis()) > 0.999);
+
+		assert!(convex_cast(&ball, Vector3::x_axis(), 3.0, &wall).is_none());
+		assert!(convex_cast(&ball, -Vector3::x_axis(), 10.0, &wall).is_none());
+		assert!(convex_cast(&ball, Vector3::y_axis(), 10.0, &wall).is_none());
+
+		// A diamond slides into the wall point first
+		let diamond = cuboid(
+			Vector3::zero(),
+			Quaternion::from_axis_angle(Vector3::z_axis(), FRAC_PI_4),
+		);
+		let hit = convex_cast(&diamond, Vector3::x_axis(), 10.0, &wall).expect("the diamond reaches the wall");
+		assert!((hit.distance - (4.0 - 2.0_f32.sqrt())).abs() < 1e-2);
+	}
+
+	#[test]
+	pub fn cast_from_overlap_hits_at_once() {
+		let ball = sphere(Vector3::new(3.8, 0.0, 0.0), 0.5);
+		let wall = cuboid(Vector3::new(5.0, 0.0, 0.0), Quaternion::default());
+		let hit = convex_cast(&ball, Vector3::x_axis(), 10.0, &wall).expect("the ball starts in the wall");
+		assert_equal(hit.distance, 0.0);
+		assert!(hit.normal.dot(&-Vector3::x_axis()) > 0.99);
+	}
+
+	#[test]
+	pub fn separated_spheres() {
+		let first = sphere(Vector3::zero(), 1.0);
+		let second = sphere(Vector3::new(3.0, 0.0, 0.0), 1.0);
+		assert!(!intersects(&first, &second));
+		assert!(penetration(&first, &second).is_none());
+		let closest = closest_points(&first, &second).expect("spheres are apart");
+		assert!((closest.distance - 1.0).abs() < 1e-3);
+		assert!(is_near(closest.points[0], Vector3::x_axis()));
+		assert!(is_near(closest.points[1], Vector3::new(2.0, 0.0, 0.0)));
+	}
+
+	#[test]
+	pub fn overlapping_spheres() {
+		let first = sphere(Vector3::zero(), 1.0);
+		let second = sphere(Vector3::new(0.0, 1.5, 0.0), 1.0);
+		assert!(intersects(&first, &second));
+		assert!(closest_points(&first, &second).is_none());
+		let overlap = penetration(&first, &second).expect("spheres overlap");
+		assert!((overlap.depth - 0.5).abs() < 1e-2);
+		assert!(overlap.normal.dot(&Vector3::y_axis()) > 0.999);
+	}
+
+	#[test]
+	pub fn rotated_box_distance() {
+		let diamond = cuboid(
+			Vector3::zero(),
+			Quaternion::from_axis_angle(Vector3::z_axis(), FRAC_PI_4),
+		);
+		let ball = sphere(Vector3::new(3.0, 0.0, 0.0), 1.0);
+		let closest = closest_points(&diamond, &ball).expect("shapes are apart");
+		assert!((closest.distance - (2.0 - 2.0_f32.sqrt())).abs() < 1e-3);
+
+		// The nearest part of the box is an edge along z, so only x and y are fixed
+		let nearest = closest.points[0];
+		assert!((nearest.x() - 2.0_f32.sqrt()).abs() < 1e-3 && nearest.y().abs() < 1e-3);
+	}
+
+	#[test]
+	pub fn cylinder_standing_on_box() {
+		let table = cuboid(Vector3::zero(), Quaternion::default());
+		let barrel = CollisionCylinder {
+			primitive: primitive(Vector3::new(0.2, 1.9, 0.0), Quaternion::default()),
+			radius: 0.5,
+			half_height: 1.0,
+		};
+		let overlap = penetration(&barrel, &table).expect("the cylinder sinks into the box");
+		assert!((overlap.depth - 0.1).abs() < 1e-3);
+		assert!(is_near(overlap.normal, -Vector3::y_axis()));
+		assert!((overlap.points[0].y() - 0.9).abs() < 1e-3);
+		assert!((overlap.points[1].y() - 1.0).abs() < 1e-3);
+	}
+
+	#[test]
+	pub fn hull_against_capsule() {
+		let wedge = ConvexHull {
+			primitive: primitive(Vector3::zero(), Quaternion::default()),
+			points: vec![
+				Vector3::new(-1.0, 0.0, -1.0),
+				Vector3::new(1.0, 0.0, -1.0),
+				Vector3::new(0.0, 0.0, 1.0),
+				Vector3::new(0.0, 1.0, 0.0),
+				Vector3::new(0.0, 0.2, 0.0),
+			],
+		};
+		let mut capsule = CollisionCapsule {
+			primitive: primitive(Vector3::new(0.0, 3.0, 0.0), Quaternion::default()),
+			radius: 0.5,
+			half_height: 1.0,
+		};
+		let closest = closest_points(&wedge, &capsule).expect("shapes are apart");
+		assert!((closest.distance - 0.5).abs() < 1e-3);
+		assert!(is_near(closest.points[0], Vector3::y_axis()));
+
+		capsule.primitive = primitive(Vector3::new(0.0, 2.3, 0.0), Quaternion::default());
+		let overlap = penetration(&wedge, &capsule).expect("shapes overlap");
+		assert!((overlap.depth - 0.2).abs() < 1e-3);
+		assert!(overlap.normal.dot(&Vector3::y_axis()) > 0.999);
+	}
+}
@@ -0,0 +1,212 @@
+use std::f32::consts::TAU;
+
+use crate::{
+	bounding::Aabb, collider::Collider, matrix::Matrix4, real_from_count, trimesh::TriMesh, vec::Vector3, Real,
+};
+
+/// The number of segments a full circle is drawn with.
+const CIRCLE_SEGMENTS: usize = 24;
+
+/// What a debug primitive shows, so that a renderer can color each kind
+/// differently or leave some out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DebugKind {
+	/// The outline of a collider of an awake body.
+	Collider,
+
+	/// The outline of a collider of a sleeping body.
+	SleepingCollider,
+
+	Sensor,
+
+	/// The edges of a triangle mesh in the scenery.
+	Mesh,
+
+	/// The bounding box of a collider, as the broad phase sees it.
+	Bounds,
+
+	/// A contact point, or the normal drawn from it.
+	Contact,
+
+	/// A joint's anchor, or the line between its two anchors.
+	Joint,
+
+	/// The velocity of a body, drawn from its center of mass.
+	Velocity,
+}
+
+/// A renderer that physics internals can be drawn with, as lines and points
+/// in world coordinates, such as by
+/// [`PhysicsWorld::debug_draw`](crate::world::PhysicsWorld::debug_draw).
+///
+/// Only [`Self::line`] and [`Self::point`] need implementing, leaving the
+/// renderer free to draw them however it likes.
+pub trait DebugDraw {
+	fn line(&mut self, start: Vector3, end: Vector3, kind: DebugKind);
+
+	fn point(&mut self, position: Vector3, kind: DebugKind);
+
+	/// Returns whether primitives of the given kind should be drawn at all.
+	/// Everything is drawn by default.
+	fn draws(&self, _kind: DebugKind) -> bool {
+		true
+	}
+
+	/// Returns the length contact normals are drawn with.
+	fn normal_length(&self) -> Real {
+		0.25
+	}
+
+	/// Returns the time that velocities are drawn over, so that each is
+	/// drawn as far as the body would travel in that time.
+	fn velocity_scale(&self) -> Real {
+		0.1
+	}
+}
+
+/// Draws the outline of the collider, as edges for a box and as circles
+/// about its axes for the round shapes.
+pub fn draw_collider(draw: &mut impl DebugDraw, collider: &Collider, kind: DebugKind) {
+	let transform = collider.primitive().transform();
+	match collider {
+		Collider::Box(cuboid) => {
+			let half_size = cuboid.half_size;
+			let corner = |signs: [Real; 3]| {
+				transform.transform(Vector3::new(
+					signs[0] * half_size.x(),
+					signs[1] * half_size.y(),
+					signs[2] * half_size.z(),
+				))
+			};
+			draw_box_edges(draw, corner, kind);
+		},
+		Collider::Sphere(sphere) => {
+			for (first, second) in [(0, 1), (1, 2), (2, 0)] {
+				let axes = [
+					transform.axis(first) * sphere.radius,
+					transform.axis(second) * sphere.radius,
+				];
+				draw_arc(draw, collider.primitive().position(), axes, CIRCLE_SEGMENTS, kind);
+			}
+		},
+		Collider::Capsule(capsule) => {
+			let [top, bottom] = capsule.segment();
+			draw_round_sides(draw, transform, [top, bottom], capsule.radius, kind);
+			let up = transform.axis(1) * capsule.radius;
+			for across in [transform.axis(0), transform.axis(2)] {
+				let across = across * capsule.radius;
+				draw_arc(draw, top, [across, up], CIRCLE_SEGMENTS / 2, kind);
+				draw_arc(draw, bottom, [across, -up], CIRCLE_SEGMENTS / 2, kind);
+			}
+		},
+		Collider::Cylinder(cylinder) => {
+			let ends = [1.0, -1.0].map(|sign| transform.transform(Vector3::new(0.0, sign * cylinder.half_height, 0.0)));
+			draw_round_sides(draw, transform, ends, cylinder.radius, kind);
+		},
+	}
+}
+
+/// Draws the edges of the box.
+pub fn draw_bounds(draw: &mut impl DebugDraw, bounds: &Aabb, kind: DebugKind) {
+	let corner = |signs: [Real; 3]| {
+		let pick = |axis: usize| {
+			if signs[axis] < 0.0 {
+				bounds.min[axis]
+			} else {
+				bounds.max[axis]
+			}
+		};
+		Vector3::new(pick(0), pick(1), pick(2))
+	};
+	draw_box_edges(draw, corner, kind);
+}
+
+/// Draws the edges of every triangle in the mesh.
+pub fn draw_mesh(draw: &mut impl DebugDraw, mesh: &TriMesh, kind: DebugKind) {
+	for index in 0..mesh.len() {
+		let [a, b, c] = mesh.triangle(index).vertices;
+		draw.line(a, b, kind);
+		draw.line(b, c, kind);
+		draw.line(c, a, kind);
+	}
+}
+
+/// Draws the twelve edges of a box, given its corner on each side of each axis.
+fn draw_box_edges(draw: &mut impl DebugDraw, corner: impl Fn([Real; 3]) -> Vector3, kind: DebugKind) {
+	for axis in 0..3 {
+		for [first, second] in [[-1.0, -1.0], [-1.0, 1.0], [1.0, -1.0], [1.0, 1.0]] {
+			let mut signs = [0.0; 3];
+			signs[(axis + 1) % 3] = first;
+			signs[(axis + 2) % 3] = second;
+			signs[axis] = -1.0;
+			let start = corner(signs);
+			signs[axis] = 1.0;
+			draw.line(start, corner(signs), kind);
+		}
+	}
+}
+
+/// Draws a circle about each end of a round shape along its local y axis,
+/// and four lines joining them.
+fn draw_round_sides(draw: &mut impl DebugDraw, transform: &Matrix4, ends: [Vector3; 2], radius: Real, kind: DebugKind) {
+	let across = [transform.axis(0) * radius, transform.axis(2) * radius];
+	for end in ends {
+		draw_arc(draw, end, across, CIRCLE_SEGMENTS, kind);
+	}
+	for side in [across[0], across[1], -across[0], -across[1]] {
+		draw.line(ends[0] + side, ends[1] + side, kind);
+	}
+}
+
+/// Draws an arc of the given number of segments of a circle about the
+/// center, starting along the first of the axes and turning towards the
+/// second, each axis scaled to the radius of the circle.
+fn draw_arc(draw: &mut impl DebugDraw, center: Vector3, axes: [Vector3; 2], segments: usize, kind: DebugKind) {
+	let point = |segment: usize| {
+		let (sin, cos) = (TAU * real_from_count(segment) / real_from_count(CIRCLE_SEGMENTS)).sin_cos();
+		center + axes[0] * cos + axes[1] * sin
+	};
+	for segment in 0..segments {
+		draw.line(point(segment), point(segment + 1), kind);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{collide::CollisionPrimitive, shape::Shape};
+
+	use super::*;
+
+	#[derive(Default)]
+	struct Recorder {
+		lines: Vec<[Vector3; 2]>,
+	}
+
+	impl DebugDraw for Recorder {
+		fn line(&mut self, start: Vector3, end: Vector3, _kind: DebugKind) {
+			self.lines.push([start, end]);
+		}
+
+		fn point(&mut self, _position: Vector3, _kind: DebugKind) {}
+	}
+
+	#[test]
+	pub fn outlines_follow_the_shapes() {
+		let mut recorder = Recorder::default();
+		let cuboid = Collider::from_shape(&Shape::default(), CollisionPrimitive::new(0));
+		draw_collider(&mut recorder, &cuboid, DebugKind::Collider);
+		assert_eq!(recorder.lines.len(), 12);
+		for [start, end] in &recorder.lines {
+			assert!(((*end - *start).magnitude() - 1.0).abs() < 1e-5);
+		}
+
+		recorder.lines.clear();
+		let sphere = Collider::from_shape(&Shape::Sphere { radius: 2.0 }, CollisionPrimitive::new(0));
+		draw_collider(&mut recorder, &sphere, DebugKind::Collider);
+		assert_eq!(recorder.lines.len(), 3 * CIRCLE_SEGMENTS);
+		assert!(recorder
+			.lines
+			.iter()
+			.all(|[start, _]| (start.magnitude() - 2.0).abs() < 1e-5));
+	}
+}
@@ -0,0 +1,132 @@
+use crate::Real;
+
+/// The mechanical energy of a world, split into the energy of its motion
+/// and the energy stored by its conservative forces.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Energy {
+	/// Holds the energy of the bodies' linear and angular motion.
+	pub kinetic: Real,
+
+	/// Holds the energy stored by gravity, constant accelerations, and the
+	/// force generators that report it, such as springs.
+	pub potential: Real,
+}
+
+impl Energy {
+	#[must_use]
+	pub fn total(&self) -> Real {
+		self.kinetic + self.potential
+	}
+}
+
+/// Tracks the energy of a world from step to step, reporting how far it has
+/// drifted from where it started.
+///
+/// With no damping, friction, or collisions, the energy of a world should
+/// stay the same, so drift shows the error of the integrator. A step that
+/// gains energy shows the solver injecting it.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct EnergyAudit {
+	initial: Option<Energy>,
+	previous: Energy,
+	current: Energy,
+	largest_drift: Real,
+}
+
+impl EnergyAudit {
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Records the energy of the world after a step, returning its drift.
+	/// The first energy recorded is the one drift is measured from.
+	pub fn record(&mut self, energy: Energy) -> Real {
+		self.previous = self.initial.map_or(energy, |_| self.current);
+		let initial = *self.initial.get_or_insert(energy);
+		self.current = energy;
+		let drift = energy.total() - initial.total();
+		if drift.abs() > self.largest_drift.abs() {
+			self.largest_drift = drift;
+		}
+		drift
+	}
+
+	/// Forgets the recorded energies, so that drift is measured from the next one.
+	pub fn reset(&mut self) {
+		*self = Self::default();
+	}
+
+	/// Returns the first energy recorded, if any.
+	#[must_use]
+	pub const fn initial(&self) -> Option<Energy> {
+		self.initial
+	}
+
+	/// Returns the last energy recorded.
+	#[must_use]
+	pub const fn current(&self) -> Energy {
+		self.current
+	}
+
+	/// Returns the change in total energy since the first recording.
+	#[must_use]
+	pub fn drift(&self) -> Real {
+		self.initial
+			.map_or(0.0, |initial| self.current.total() - initial.total())
+	}
+
+	/// Returns the drift as a fraction of the initial total energy,
+	/// or the drift itself if the world started with none.
+	#[must_use]
+	pub fn relative_drift(&self) -> Real {
+		let initial = self.initial.map_or(0.0, |initial| initial.total().abs());
+		if initial > 0.0 {
+			self.drift() / initial
+		} else {
+			self.drift()
+		}
+	}
+
+	/// Returns the drift of the largest magnitude seen since the first recording.
+	#[must_use]
+	pub const fn largest_drift(&self) -> Real {
+		self.largest_drift
+	}
+
+	/// Returns the change in total energy over the last step recorded.
+	#[must_use]
+	pub fn step_drift(&self) -> Real {
+		self.current.total() - self.previous.total()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::assert_equal;
+
+	use super::*;
+
+	fn energy(kinetic: Real, potential: Real) -> Energy {
+		Energy { kinetic, potential }
+	}
+
+	#[test]
+	pub fn audit_measures_drift_from_the_first_recording() {
+		let mut audit = EnergyAudit::new();
+		assert_equal(audit.drift(), 0.0);
+		assert_equal(audit.record(energy(2.0, 8.0)), 0.0);
+		assert_equal(audit.step_drift(), 0.0);
+		assert_equal(audit.record(energy(6.0, 5.0)), 1.0);
+		assert_equal(audit.step_drift(), 1.0);
+		assert_equal(audit.record(energy(4.0, 5.5)), -0.5);
+		assert_equal(audit.step_drift(), -1.5);
+		assert_equal(audit.largest_drift(), 1.0);
+		assert_equal(audit.relative_drift(), -0.05);
+		assert_eq!(audit.initial(), Some(energy(2.0, 8.0)));
+
+		audit.reset();
+		assert_eq!(audit.initial(), None);
+		assert_equal(audit.record(energy(1.0, 0.0)), 0.0);
+	}
+}
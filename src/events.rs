@@ -0,0 +1,139 @@
+use std::{
+	sync::mpsc::{channel, Receiver, Sender},
+	vec::Drain,
+};
+
+use crate::Real;
+
+/// Something that happened in a [`PhysicsWorld`](crate::world::PhysicsWorld)
+/// during a step, for gameplay such as damage and sounds to react to.
+///
+/// Each event carries the user data of the bodies and sensors it names, so
+/// that it can be mapped back to game entities without a side table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WorldEvent {
+	/// Two bodies, or a body and the scenery named by `None`, came into contact.
+	ContactStarted {
+		bodies: [Option<usize>; 2],
+
+		/// Holds the user data of the bodies, or zero for the scenery.
+		user_data: [u64; 2],
+
+		/// Holds the total impulse the contact applied along its normal in
+		/// the step it started, a measure of how hard the bodies struck.
+		impulse: Real,
+	},
+
+	/// Two bodies, or a body and the scenery named by `None`, are no longer in contact.
+	ContactEnded {
+		bodies: [Option<usize>; 2],
+		user_data: [u64; 2],
+	},
+
+	/// A body began to overlap a sensor.
+	SensorEntered {
+		sensor: usize,
+		body: usize,
+
+		/// Holds the user data of the sensor and of the body.
+		user_data: [u64; 2],
+	},
+
+	/// A body stopped overlapping a sensor.
+	SensorExited {
+		sensor: usize,
+		body: usize,
+		user_data: [u64; 2],
+	},
+
+	/// A joint bore more than its strength and broke. It is left among the
+	/// world's joints, no longer solved.
+	JointBroken {
+		/// Holds the index of the joint among the joints.
+		joint: usize,
+
+		/// Holds the bodies the joint joined, with `None` for the scenery.
+		bodies: [Option<usize>; 2],
+		user_data: [u64; 2],
+	},
+
+	/// A body fell asleep.
+	BodySlept { body: usize, user_data: u64 },
+
+	/// A body was woken.
+	BodyWoke { body: usize, user_data: u64 },
+}
+
+/// Holds the events of a world until they are drained, or passes them on
+/// to a channel as they happen once one has been opened.
+#[derive(Debug, Default)]
+pub struct EventQueue {
+	events: Vec<WorldEvent>,
+	sender: Option<Sender<WorldEvent>>,
+}
+
+impl EventQueue {
+	/// Adds the event to the queue, or sends it if a channel is open.
+	///
+	/// If the receiver has been dropped, the channel is closed and the
+	/// event is queued instead.
+	pub fn push(&mut self, event: WorldEvent) {
+		if let Some(sender) = &self.sender {
+			if sender.send(event).is_ok() {
+				return;
+			}
+			self.sender = None;
+		}
+		self.events.push(event);
+	}
+
+	/// Removes and returns the events queued so far, oldest first.
+	pub fn drain(&mut self) -> Drain<'_, WorldEvent> {
+		self.events.drain(..)
+	}
+
+	/// Returns the number of events queued.
+	#[must_use]
+	pub const fn len(&self) -> usize {
+		self.events.len()
+	}
+
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.events.is_empty()
+	}
+
+	/// Returns the number of events the queue can hold before it grows.
+	#[must_use]
+	pub const fn capacity(&self) -> usize {
+		self.events.capacity()
+	}
+
+	/// Opens a channel that receives every event from now on, in place of
+	/// the queue. Opening another closes the last.
+	pub fn channel(&mut self) -> Receiver<WorldEvent> {
+		let (sender, receiver) = channel();
+		self.sender = Some(sender);
+		receiver
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	pub fn events_queue_until_a_channel_opens() {
+		let mut queue = EventQueue::default();
+		queue.push(WorldEvent::BodySlept { body: 0, user_data: 0 });
+		let receiver = queue.channel();
+		queue.push(WorldEvent::BodyWoke { body: 0, user_data: 0 });
+		assert_eq!(queue.drain().collect::<Vec<_>>(), [WorldEvent::BodySlept { body: 0, user_data: 0 }]);
+		assert_eq!(receiver.try_recv(), Ok(WorldEvent::BodyWoke { body: 0, user_data: 0 }));
+
+		// Events go back to the queue once the receiver is dropped
+		drop(receiver);
+		queue.push(WorldEvent::BodySlept { body: 1, user_data: 0 });
+		assert_eq!(queue.len(), 1);
+	}
+}
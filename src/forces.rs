@@ -0,0 +1,329 @@
+use crate::{particle::Particle, vec::Vector3, Real};
+
+/// A force generator can be asked to add a force to one or more particles.
+pub trait ParticleForceGenerator {
+	/// Calculates and updates the force applied to the particle at the given index.
+	fn update_force(&mut self, particles: &mut [Particle], index: usize, duration: Real);
+
+	/// Returns the potential energy stored by the force on the particle at
+	/// the given index. Forces that store none, such as drag, need not
+	/// implement this.
+	fn potential_energy(&self, _particles: &[Particle], _index: usize) -> Real {
+		0.0
+	}
+}
+
+/// A single pairing of a particle with the force generator that applies to it.
+pub struct ParticleForceRegistration {
+	pub particle: usize,
+	pub generator: Box<dyn ParticleForceGenerator>,
+}
+
+/// Holds all the force generators and the particles they apply to.
+#[derive(Default)]
+pub struct ParticleForceRegistry {
+	registrations: Vec<ParticleForceRegistration>,
+}
+
+impl ParticleForceRegistry {
+	/// Registers the given force generator to apply to the given particle.
+	pub fn add(&mut self, particle: usize, generator: Box<dyn ParticleForceGenerator>) {
+		self.registrations
+			.push(ParticleForceRegistration { particle, generator });
+	}
+
+	/// Removes every registration for the given particle.
+	pub fn remove(&mut self, particle: usize) {
+		self.registrations
+			.retain(|registration| registration.particle != particle);
+	}
+
+	/// Clears all registrations from the registry. This will not delete
+	/// the particles or the force generators themselves, just the records
+	/// of their connection.
+	pub fn clear(&mut self) {
+		self.registrations.clear();
+	}
+
+	#[must_use]
+	pub const fn len(&self) -> usize {
+		self.registrations.len()
+	}
+
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.registrations.is_empty()
+	}
+
+	/// Calls all the force generators to update the forces of their corresponding particles.
+	pub fn update_forces(&mut self, particles: &mut [Particle], duration: Real) {
+		for registration in &mut self.registrations {
+			registration
+				.generator
+				.update_force(particles, registration.particle, duration);
+		}
+	}
+
+	/// Returns the potential energy stored by all the registered force generators.
+	#[must_use]
+	pub fn potential_energy(&self, particles: &[Particle]) -> Real {
+		self.registrations
+			.iter()
+			.map(|registration| {
+				registration
+					.generator
+					.potential_energy(particles, registration.particle)
+			})
+			.sum()
+	}
+}
+
+/// A force generator that applies a gravitational force.
+/// One instance can be used for multiple particles.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParticleGravity {
+	/// Holds the acceleration due to gravity.
+	pub gravity: Vector3,
+}
+
+impl ParticleForceGenerator for ParticleGravity {
+	fn update_force(&mut self, particles: &mut [Particle], index: usize, _duration: Real) {
+		let particle = &mut particles[index];
+
+		// Check that we do not have infinite mass
+		if !particle.has_finite_mass() {
+			return;
+		}
+
+		// Apply the mass-scaled force to the particle
+		particle.add_force(self.gravity * particle.mass());
+	}
+
+	fn potential_energy(&self, particles: &[Particle], index: usize) -> Real {
+		let particle = &particles[index];
+		if !particle.has_finite_mass() {
+			return 0.0;
+		}
+		-particle.mass() * self.gravity.dot(&particle.position)
+	}
+}
+
+/// A force generator that applies a drag force.
+/// One instance can be used for multiple particles.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParticleDrag {
+	/// Holds the velocity drag coefficient.
+	pub k1: Real,
+
+	/// Holds the velocity squared drag coefficient.
+	pub k2: Real,
+}
+
+impl ParticleForceGenerator for ParticleDrag {
+	fn update_force(&mut self, particles: &mut [Particle], index: usize, _duration: Real) {
+		let particle = &mut particles[index];
+		let speed = particle.velocity.magnitude();
+		let drag = self.k2.mul_add(speed * speed, self.k1 * speed);
+		particle.add_force(particle.velocity.normalize() * -drag);
+	}
+}
+
+/// A force generator that applies a spring force pulling
+/// the particle towards another particle.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParticleSpring {
+	/// The particle at the other end of the spring.
+	pub other: usize,
+
+	/// Holds the spring constant.
+	pub spring_constant: Real,
+
+	/// Holds the rest length of the spring.
+	pub rest_length: Real,
+}
+
+impl ParticleForceGenerator for ParticleSpring {
+	fn update_force(&mut self, particles: &mut [Particle], index: usize, _duration: Real) {
+		let force = spring_force(
+			particles[index].position - particles[self.other].position,
+			self.spring_constant,
+			self.rest_length,
+		);
+		particles[index].add_force(force);
+	}
+
+	/// Returns half the energy stored in the spring, as a spring joining two
+	/// particles is registered once for each of them.
+	fn potential_energy(&self, particles: &[Particle], index: usize) -> Real {
+		0.5 * spring_energy(
+			particles[index].position - particles[self.other].position,
+			self.spring_constant,
+			self.rest_length,
+		)
+	}
+}
+
+/// A force generator that applies a spring force, where
+/// one end is attached to a fixed point in space.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParticleAnchoredSpring {
+	/// The location of the anchored end of the spring.
+	pub anchor: Vector3,
+
+	/// Holds the spring constant.
+	pub spring_constant: Real,
+
+	/// Holds the rest length of the spring.
+	pub rest_length: Real,
+}
+
+impl ParticleForceGenerator for ParticleAnchoredSpring {
+	fn update_force(&mut self, particles: &mut [Particle], index: usize, _duration: Real) {
+		let particle = &mut particles[index];
+		particle.add_force(spring_force(
+			particle.position - self.anchor,
+			self.spring_constant,
+			self.rest_length,
+		));
+	}
+
+	fn potential_energy(&self, particles: &[Particle], index: usize) -> Real {
+		spring_energy(
+			particles[index].position - self.anchor,
+			self.spring_constant,
+			self.rest_length,
+		)
+	}
+}
+
+/// Calculates the Hooke's law force on the end of a spring
+/// displaced from its other end by the given vector.
+#[must_use]
+pub fn spring_force(displacement: Vector3, spring_constant: Real, rest_length: Real) -> Vector3 {
+	let length = displacement.magnitude();
+	displacement.normalize() * (-spring_constant * (length - rest_length))
+}
+
+/// Calculates the energy stored in a spring whose
+/// ends are displaced by the given vector.
+#[must_use]
+pub fn spring_energy(displacement: Vector3, spring_constant: Real, rest_length: Real) -> Real {
+	let extension = displacement.magnitude() - rest_length;
+	0.5 * spring_constant * extension * extension
+}
+
+/// A damped spring joining two particles, applying equal
+/// and opposite forces to the particles at both of its ends.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParticleSpringLink {
+	/// Holds the pair of particles that are connected by this spring.
+	pub particles: [usize; 2],
+
+	/// Holds the spring constant.
+	pub spring_constant: Real,
+
+	/// Holds the damping coefficient applied to the relative
+	/// velocity of the two ends along the spring.
+	pub damping: Real,
+
+	/// Holds the rest length of the spring.
+	pub rest_length: Real,
+}
+
+impl ParticleSpringLink {
+	/// Calculates the force this spring applies to its first particle.
+	/// The second particle receives the opposite force.
+	#[must_use]
+	pub fn force(&self, particles: &[Particle]) -> Vector3 {
+		let [first, second] = self.particles;
+		let displacement = particles[first].position - particles[second].position;
+		let direction = displacement.normalize();
+		let relative_speed = (particles[first].velocity - particles[second].velocity).dot(&direction);
+		let magnitude = self.spring_constant.mul_add(
+			displacement.magnitude() - self.rest_length,
+			self.damping * relative_speed,
+		);
+		direction * -magnitude
+	}
+
+	/// Adds this spring's force to both of its particles.
+	pub fn apply_force(&self, particles: &mut [Particle]) {
+		let force = self.force(particles);
+		let [first, second] = self.particles;
+		particles[first].add_force(force);
+		particles[second].add_force(-force);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn particle_at(position: Vector3) -> Particle {
+		Particle {
+			position,
+			inverse_mass: 0.5,
+			damping: 1.0,
+			..Default::default()
+		}
+	}
+
+	#[test]
+	pub fn gravity() {
+		let mut particles = [particle_at(Vector3::zero())];
+		let mut registry = ParticleForceRegistry::default();
+		registry.add(
+			0,
+			Box::new(ParticleGravity {
+				gravity: Vector3::new(0.0, -10.0, 0.0),
+			}),
+		);
+		registry.update_forces(&mut particles, 1.0);
+		assert_eq!(particles[0].force_accumulator, Vector3::new(0.0, -20.0, 0.0));
+	}
+
+	#[test]
+	pub fn gravity_ignores_infinite_mass() {
+		let mut particles = [Particle::default()];
+		ParticleGravity {
+			gravity: Vector3::new(0.0, -10.0, 0.0),
+		}
+		.update_force(&mut particles, 0, 1.0);
+		assert_eq!(particles[0].force_accumulator, Vector3::zero());
+	}
+
+	#[test]
+	pub fn stretched_spring_pulls() {
+		let mut particles = [particle_at(Vector3::new(3.0, 0.0, 0.0)), particle_at(Vector3::zero())];
+		ParticleSpring {
+			other: 1,
+			spring_constant: 2.0,
+			rest_length: 1.0,
+		}
+		.update_force(&mut particles, 0, 1.0);
+		assert_eq!(particles[0].force_accumulator, Vector3::new(-4.0, 0.0, 0.0));
+	}
+
+	#[test]
+	pub fn spring_link_applies_equal_and_opposite_forces() {
+		let mut particles = [particle_at(Vector3::zero()), particle_at(Vector3::new(0.5, 0.0, 0.0))];
+		ParticleSpringLink {
+			particles: [0, 1],
+			spring_constant: 2.0,
+			damping: 0.0,
+			rest_length: 1.0,
+		}
+		.apply_force(&mut particles);
+		assert_eq!(particles[0].force_accumulator, Vector3::new(-1.0, 0.0, 0.0));
+		assert_eq!(particles[1].force_accumulator, Vector3::new(1.0, 0.0, 0.0));
+	}
+
+	#[test]
+	pub fn registry_remove() {
+		let mut registry = ParticleForceRegistry::default();
+		registry.add(0, Box::new(ParticleDrag { k1: 1.0, k2: 0.0 }));
+		registry.add(1, Box::new(ParticleDrag { k1: 1.0, k2: 0.0 }));
+		registry.remove(0);
+		assert_eq!(registry.len(), 1);
+	}
+}
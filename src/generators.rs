@@ -0,0 +1,618 @@
+use crate::{
+	body::RigidBody,
+	body_set::BodySet,
+	forces::{spring_energy, spring_force},
+	shape::Shape,
+	vec::Vector3,
+	Real,
+};
+
+/// A force generator can be asked to add a force to one or more rigid bodies.
+pub trait ForceGenerator {
+	/// Calculates and updates the force applied to the body with the given handle.
+	fn update_force(&mut self, bodies: &mut BodySet, index: usize, duration: Real);
+
+	/// Returns the potential energy stored by the force on the body with the
+	/// given handle. Forces that store none, such as wind, need not implement this.
+	fn potential_energy(&self, _bodies: &BodySet, _index: usize) -> Real {
+		0.0
+	}
+}
+
+/// A single pairing of a rigid body with the force generator that applies to it.
+pub struct ForceRegistration {
+	pub body: usize,
+	pub generator: Box<dyn ForceGenerator>,
+}
+
+/// Holds all the force generators and the rigid bodies they apply to.
+#[derive(Default)]
+pub struct ForceRegistry {
+	registrations: Vec<ForceRegistration>,
+}
+
+impl ForceRegistry {
+	/// Registers the given force generator to apply to the given body.
+	pub fn add(&mut self, body: usize, generator: Box<dyn ForceGenerator>) {
+		self.registrations.push(ForceRegistration { body, generator });
+	}
+
+	/// Removes every registration for the given body.
+	pub fn remove(&mut self, body: usize) {
+		self.registrations.retain(|registration| registration.body != body);
+	}
+
+	/// Clears all registrations from the registry. This will not delete
+	/// the bodies or the force generators themselves, just the records
+	/// of their connection.
+	pub fn clear(&mut self) {
+		self.registrations.clear();
+	}
+
+	#[must_use]
+	pub const fn len(&self) -> usize {
+		self.registrations.len()
+	}
+
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.registrations.is_empty()
+	}
+
+	/// Calls all the force generators to update the forces of their corresponding bodies.
+	pub fn update_forces(&mut self, bodies: &mut BodySet, duration: Real) {
+		for registration in &mut self.registrations {
+			registration.generator.update_force(bodies, registration.body, duration);
+		}
+	}
+
+	/// Returns the potential energy stored by all the registered force generators.
+	#[must_use]
+	pub fn potential_energy(&self, bodies: &BodySet) -> Real {
+		self.registrations
+			.iter()
+			.map(|registration| registration.generator.potential_energy(bodies, registration.body))
+			.sum()
+	}
+}
+
+/// A force generator that applies a gravitational force.
+/// One instance can be used for multiple rigid bodies.
+///
+/// Gravity is not applied to sleeping bodies, as it would wake them every step.
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct Gravity {
+	/// Holds the acceleration due to gravity.
+	pub gravity: Vector3,
+}
+
+impl ForceGenerator for Gravity {
+	fn update_force(&mut self, bodies: &mut BodySet, index: usize, _duration: Real) {
+		// Check that we do not have infinite mass, and that the body is awake
+		let inverse_mass = bodies.inverse_masses()[index];
+		if inverse_mass == 0.0 || bodies.flags()[index].sleeping {
+			return;
+		}
+
+		// Apply the mass-scaled force to the body's center of mass
+		bodies.add_force(index, self.gravity * inverse_mass.recip());
+	}
+
+	fn potential_energy(&self, bodies: &BodySet, index: usize) -> Real {
+		let inverse_mass = bodies.inverse_masses()[index];
+		if inverse_mass == 0.0 {
+			return 0.0;
+		}
+		-inverse_mass.recip() * self.gravity.dot(&bodies.positions()[index])
+	}
+}
+
+/// A force generator that applies a spring force between a point on
+/// the body and a point on another body or a fixed point in the world.
+///
+/// The force is applied at the connection point, so it turns the body as
+/// well as pulling it, which is what makes a hanging sign swing and settle.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Spring {
+	/// Holds the point of connection of the spring, in body coordinates.
+	pub connection_point: Vector3,
+
+	/// Holds the body at the other end of the spring,
+	/// or `None` if the other end is anchored in the world.
+	pub other: Option<usize>,
+
+	/// Holds the point of connection at the other end of the spring, in the
+	/// other body's coordinates, or in world coordinates if it is anchored.
+	pub other_connection_point: Vector3,
+
+	/// Holds the spring constant.
+	pub spring_constant: Real,
+
+	/// Holds the rest length of the spring.
+	pub rest_length: Real,
+}
+
+impl ForceGenerator for Spring {
+	fn update_force(&mut self, bodies: &mut BodySet, index: usize, _duration: Real) {
+		let other_end = self.other.map_or(self.other_connection_point, |other| {
+			bodies.transforms()[other].transform(self.other_connection_point)
+		});
+		let end = bodies.transforms()[index].transform(self.connection_point);
+		let force = spring_force(end - other_end, self.spring_constant, self.rest_length);
+		bodies.add_force_at_point(index, force, end);
+	}
+
+	/// Returns the energy stored in the spring, halved if it joins two
+	/// bodies, as such a spring is registered once for each of them.
+	fn potential_energy(&self, bodies: &BodySet, index: usize) -> Real {
+		let end = bodies.transforms()[index].transform(self.connection_point);
+		self.other.map_or_else(
+			|| spring_energy(end - self.other_connection_point, self.spring_constant, self.rest_length),
+			|other| {
+				let other_end = bodies.transforms()[other].transform(self.other_connection_point);
+				0.5 * spring_energy(end - other_end, self.spring_constant, self.rest_length)
+			},
+		)
+	}
+}
+
+/// A force generator that applies a buoyant force from a body of water,
+/// whose surface is a plane.
+///
+/// The force acts at a body-space center of buoyancy, so a boat given one
+/// generator per hull section is rolled back upright when it tips.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct Buoyancy {
+	/// Holds the center of buoyancy in body coordinates.
+	pub center_of_buoyancy: Vector3,
+
+	/// Holds the depth of the center below the surface at which the section
+	/// is fully submerged. It is partially submerged from this far above the
+	/// surface down to this far below it.
+	pub max_depth: Real,
+
+	/// Holds the volume of the section.
+	pub volume: Real,
+
+	/// Holds the height of the water surface along its normal.
+	pub water_height: Real,
+
+	/// Holds the unit upward normal of the water surface.
+	pub water_normal: Vector3,
+
+	/// Holds the density of the liquid. Pure water has a density of 1000 kg per cubic metre.
+	pub liquid_density: Real,
+
+	/// Holds the magnitude of the acceleration due to gravity.
+	pub gravity: Real,
+}
+
+impl Buoyancy {
+	/// Creates a generator for a section floating in water with a horizontal
+	/// surface at the given height, under standard gravity.
+	#[must_use]
+	pub const fn new(center_of_buoyancy: Vector3, max_depth: Real, volume: Real, water_height: Real) -> Self {
+		Self {
+			center_of_buoyancy,
+			max_depth,
+			volume,
+			water_height,
+			water_normal: Vector3::y_axis(),
+			liquid_density: 1000.0,
+			gravity: 9.81,
+		}
+	}
+
+	/// Returns the fraction of the section that is submerged, from 0 to 1.
+	#[must_use]
+	pub fn submerged(&self, body: &RigidBody) -> Real {
+		let height = body.get_point_in_world(self.center_of_buoyancy).dot(&self.water_normal);
+		if self.max_depth <= 0.0 {
+			return if height < self.water_height { 1.0 } else { 0.0 };
+		}
+		((self.water_height + self.max_depth - height) / (2.0 * self.max_depth)).clamp(0.0, 1.0)
+	}
+}
+
+impl ForceGenerator for Buoyancy {
+	fn update_force(&mut self, bodies: &mut BodySet, index: usize, _duration: Real) {
+		bodies.update(index, |body| {
+			let submerged = self.submerged(body);
+			if submerged <= 0.0 {
+				return;
+			}
+			let force = self.water_normal * (self.liquid_density * self.volume * submerged * self.gravity);
+			body.add_force_at_body_point(force, self.center_of_buoyancy);
+		});
+	}
+}
+
+/// A force generator that simulates an explosion.
+///
+/// An explosion first implodes, briefly pulling in bodies between two radii
+/// of the detonation point, and then sends out a concussion wave: a shell
+/// of outward force that expands at a fixed speed and weakens over time.
+///
+/// The force is applied at the point on the body's shape closest to the
+/// detonation, so nearby bodies tumble away rather than sliding. Each
+/// registration holds its own copy of the explosion with the shape of its
+/// body, and the explosion's clock advances each time its force is updated.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct Explosion {
+	/// Holds the location of the detonation of the weapon.
+	pub detonation: Vector3,
+
+	/// Holds the radius up to which bodies implode in the first stage.
+	pub implosion_max_radius: Real,
+
+	/// Holds the radius within which bodies are not affected by the implosion.
+	pub implosion_min_radius: Real,
+
+	/// Holds the length of time that the implosion stage lasts.
+	pub implosion_duration: Real,
+
+	/// Holds the force that the implosion applies.
+	pub implosion_force: Real,
+
+	/// Holds the speed that the shock wave is travelling.
+	pub shockwave_speed: Real,
+
+	/// Holds the thickness of the shock wave, which is at least
+	/// as much as the distance it travels in one step.
+	pub shockwave_thickness: Real,
+
+	/// Holds the force applied at the very center of the concussion
+	/// wave at the moment it begins.
+	pub peak_concussion_force: Real,
+
+	/// Holds the length of time the concussion wave is active.
+	pub concussion_duration: Real,
+
+	/// Holds the shape of the body the explosion acts on, in body coordinates.
+	pub shape: Shape,
+
+	/// Holds the time since the detonation.
+	pub time_passed: Real,
+}
+
+impl Explosion {
+	/// Creates an explosion with no implosion stage and a concussion
+	/// wave travelling at the given speed for the given duration.
+	#[must_use]
+	pub fn new(
+		detonation: Vector3,
+		peak_concussion_force: Real,
+		shockwave_speed: Real,
+		concussion_duration: Real,
+	) -> Self {
+		Self {
+			detonation,
+			implosion_max_radius: 0.0,
+			implosion_min_radius: 0.0,
+			implosion_duration: 0.0,
+			implosion_force: 0.0,
+			shockwave_speed,
+			shockwave_thickness: 1.0,
+			peak_concussion_force,
+			concussion_duration,
+			shape: Shape::default(),
+			time_passed: 0.0,
+		}
+	}
+
+	/// Returns the force the explosion applies at the given world-space
+	/// point at the current time, for the given step length.
+	#[must_use]
+	pub fn force_at(&self, point: Vector3, duration: Real) -> Vector3 {
+		let offset = point - self.detonation;
+		let distance = offset.magnitude();
+		let outward = offset.normalize();
+
+		if self.time_passed < self.implosion_duration {
+			let inside = (self.implosion_min_radius..=self.implosion_max_radius).contains(&distance);
+			return if inside {
+				outward * -self.implosion_force
+			} else {
+				Vector3::zero()
+			};
+		}
+
+		let time = self.time_passed - self.implosion_duration;
+		if time >= self.concussion_duration || self.concussion_duration <= 0.0 {
+			return Vector3::zero();
+		}
+		let front = self.shockwave_speed * time;
+		let thickness = self.shockwave_thickness.max(self.shockwave_speed * duration);
+		if (distance - front).abs() > thickness * 0.5 {
+			return Vector3::zero();
+		}
+		outward * (self.peak_concussion_force * (1.0 - time / self.concussion_duration))
+	}
+}
+
+impl ForceGenerator for Explosion {
+	fn update_force(&mut self, bodies: &mut BodySet, index: usize, duration: Real) {
+		let transform = bodies.transforms()[index];
+		let local_detonation = transform.transform_inverse(self.detonation);
+		let point = transform.transform(self.shape.closest_point(local_detonation));
+		let force = self.force_at(point, duration);
+		if force.magnitude_squared() > 0.0 {
+			bodies.add_force_at_point(index, force, point);
+		}
+		self.time_passed += duration;
+	}
+}
+
+/// A force generator that blows a steady wind over a body.
+///
+/// The drag force grows with the square of the body's speed relative to the
+/// wind and with the area the body's shape presents to it. It acts at the
+/// centroid of that area, so light debris whose centroid is away from its
+/// center of mass is tumbled by the wind.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct Wind {
+	/// Holds the velocity of the wind in world coordinates.
+	pub velocity: Vector3,
+
+	/// Holds the density of the air. Air at sea level has a density of about 1.2 kg per cubic metre.
+	pub air_density: Real,
+
+	/// Holds the drag coefficient of the body's shape.
+	pub drag_coefficient: Real,
+
+	/// Holds the shape used to estimate the area exposed to the wind.
+	pub shape: Shape,
+
+	/// Holds the centroid of the exposed area in body coordinates.
+	pub centroid: Vector3,
+}
+
+impl Wind {
+	/// Creates a wind in sea-level air, acting on a shape centered on the body.
+	#[must_use]
+	pub fn new(velocity: Vector3, shape: Shape) -> Self {
+		Self {
+			velocity,
+			air_density: 1.2,
+			drag_coefficient: 1.0,
+			shape,
+			centroid: Vector3::zero(),
+		}
+	}
+
+	/// Returns the force the wind exerts on the body, in world coordinates.
+	#[must_use]
+	pub fn force(&self, body: &RigidBody) -> Vector3 {
+		let centroid = body.get_point_in_world(self.centroid);
+		let relative_velocity = self.velocity - body.velocity_at_point(centroid);
+		let speed_squared = relative_velocity.magnitude_squared();
+		if speed_squared <= 0.0 {
+			return Vector3::zero();
+		}
+		let area = self
+			.shape
+			.projected_area(body.get_direction_in_local(relative_velocity));
+		relative_velocity.normalize() * (0.5 * self.air_density * self.drag_coefficient * area * speed_squared)
+	}
+}
+
+impl ForceGenerator for Wind {
+	fn update_force(&mut self, bodies: &mut BodySet, index: usize, _duration: Real) {
+		bodies.update(index, |body| {
+			if !body.has_finite_mass() {
+				return;
+			}
+			let force = self.force(body);
+			if force.magnitude_squared() > 0.0 {
+				body.add_force_at_body_point(force, self.centroid);
+			}
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{assert_equal, quaternion::Quaternion};
+
+	use super::*;
+
+	fn body() -> RigidBody {
+		RigidBody {
+			inverse_mass: 0.5,
+			..Default::default()
+		}
+	}
+
+	fn body_at(position: Vector3) -> RigidBody {
+		let mut body = RigidBody { position, ..body() };
+		body.calculate_derived_data();
+		body
+	}
+
+	#[test]
+	pub fn gravity() {
+		let mut bodies: BodySet = [body(), RigidBody::default()].into_iter().collect();
+		let mut registry = ForceRegistry::default();
+		for index in 0..bodies.len() {
+			registry.add(
+				index,
+				Box::new(Gravity {
+					gravity: Vector3::new(0.0, -10.0, 0.0),
+				}),
+			);
+		}
+		registry.update_forces(&mut bodies, 1.0);
+		assert_eq!(bodies.forces()[0], Vector3::new(0.0, -20.0, 0.0));
+		assert_eq!(bodies.torques()[0], Vector3::zero());
+		assert_eq!(bodies.forces()[1], Vector3::zero());
+	}
+
+	#[test]
+	pub fn registry_remove() {
+		let mut registry = ForceRegistry::default();
+		registry.add(0, Box::new(Gravity::default()));
+		registry.add(1, Box::new(Gravity::default()));
+		registry.remove(0);
+		assert_eq!(registry.len(), 1);
+		registry.clear();
+		assert!(registry.is_empty());
+	}
+
+	#[test]
+	pub fn anchored_spring_pulls_and_turns() {
+		// A sign hanging from one corner is pulled up and turned
+		let mut bodies: BodySet = std::iter::once(body_at(Vector3::zero())).collect();
+		Spring {
+			connection_point: Vector3::x_axis(),
+			other_connection_point: Vector3::new(1.0, 3.0, 0.0),
+			spring_constant: 2.0,
+			rest_length: 1.0,
+			..Default::default()
+		}
+		.update_force(&mut bodies, 0, 1.0);
+		assert_eq!(bodies.forces()[0], Vector3::new(0.0, 4.0, 0.0));
+		assert_eq!(bodies.torques()[0], Vector3::new(0.0, 0.0, 4.0));
+	}
+
+	#[test]
+	pub fn spring_between_body_points() {
+		let mut bodies: BodySet =
+			[body_at(Vector3::zero()), body_at(Vector3::new(4.0, 0.0, 0.0))].into_iter().collect();
+		Spring {
+			connection_point: Vector3::x_axis(),
+			other: Some(1),
+			other_connection_point: -Vector3::x_axis(),
+			spring_constant: 1.0,
+			rest_length: 1.0,
+		}
+		.update_force(&mut bodies, 0, 1.0);
+		assert_eq!(bodies.forces()[0], Vector3::x_axis());
+		assert_eq!(bodies.torques()[0], Vector3::zero());
+		assert_eq!(bodies.forces()[1], Vector3::zero());
+	}
+
+	#[test]
+	pub fn gravity_and_springs_store_energy() {
+		let bodies: BodySet =
+			[body_at(Vector3::new(0.0, 3.0, 0.0)), body_at(Vector3::new(4.0, 3.0, 0.0))].into_iter().collect();
+		let gravity = Gravity {
+			gravity: Vector3::new(0.0, -10.0, 0.0),
+		};
+		assert_equal(gravity.potential_energy(&bodies, 0), 60.0);
+		let anchored = Spring {
+			connection_point: Vector3::x_axis(),
+			other_connection_point: Vector3::new(1.0, 0.0, 0.0),
+			spring_constant: 2.0,
+			rest_length: 1.0,
+			..Default::default()
+		};
+		assert_equal(anchored.potential_energy(&bodies, 0), 4.0);
+
+		// A spring between two bodies is registered for each, so each holds half its energy
+		let between = Spring {
+			other: Some(1),
+			other_connection_point: -Vector3::x_axis(),
+			..anchored
+		};
+		assert_equal(between.potential_energy(&bodies, 0), 0.5);
+		assert_equal(Wind::new(Vector3::x_axis(), Shape::default()).potential_energy(&bodies, 0), 0.0);
+	}
+
+	#[test]
+	pub fn buoyancy_depends_on_depth() {
+		let mut buoyancy = Buoyancy::new(Vector3::zero(), 0.5, 0.1, 0.0);
+		buoyancy.gravity = 10.0;
+		for (height, force) in [(1.0, 0.0), (0.0, 500.0), (-0.25, 750.0), (-2.0, 1000.0)] {
+			let mut bodies: BodySet = std::iter::once(body_at(Vector3::new(0.0, height, 0.0))).collect();
+			buoyancy.update_force(&mut bodies, 0, 1.0);
+			assert_eq!(bodies.forces()[0], Vector3::new(0.0, force, 0.0));
+		}
+	}
+
+	#[test]
+	pub fn buoyancy_rights_a_tipped_hull() {
+		// Rolled to the right, the right side of the hull sinks and is pushed up harder
+		let mut hull = RigidBody {
+			orientation: Quaternion::from_axis_angle(Vector3::z_axis(), -0.2),
+			..body()
+		};
+		hull.calculate_derived_data();
+		let mut bodies: BodySet = std::iter::once(hull).collect();
+		for side in [-1.0, 1.0] {
+			Buoyancy::new(Vector3::new(side, 0.0, 0.0), 0.5, 0.1, 0.0).update_force(&mut bodies, 0, 1.0);
+		}
+		assert!(bodies.torques()[0].z() > 0.0);
+	}
+
+	#[test]
+	pub fn explosion_implodes_then_concusses() {
+		let explosion = Explosion {
+			implosion_max_radius: 10.0,
+			implosion_min_radius: 1.0,
+			implosion_duration: 0.5,
+			implosion_force: 5.0,
+			..Explosion::new(Vector3::zero(), 100.0, 10.0, 2.0)
+		};
+		let point = Vector3::new(5.0, 0.0, 0.0);
+		assert_eq!(explosion.force_at(point, 0.1), Vector3::new(-5.0, 0.0, 0.0));
+		assert_eq!(explosion.force_at(Vector3::new(0.5, 0.0, 0.0), 0.1), Vector3::zero());
+
+		// Half a second into the concussion, the wave front is five units out
+		let concussion = Explosion {
+			time_passed: 1.0,
+			..explosion
+		};
+		assert_eq!(concussion.force_at(point, 0.1), Vector3::new(75.0, 0.0, 0.0));
+		assert_eq!(concussion.force_at(Vector3::new(8.0, 0.0, 0.0), 0.1), Vector3::zero());
+	}
+
+	#[test]
+	pub fn explosion_tumbles_nearby_crate() {
+		// The blast is level with the crate's top edge, so it is pushed and spun
+		let mut bodies: BodySet = std::iter::once(body_at(Vector3::new(3.0, 0.0, 0.0))).collect();
+		let mut explosion = Explosion {
+			time_passed: 1.0,
+			..Explosion::new(Vector3::new(0.0, 0.5, 0.0), 100.0, 2.5, 2.0)
+		};
+		explosion.update_force(&mut bodies, 0, 0.1);
+		assert_eq!(bodies.forces()[0], Vector3::new(50.0, 0.0, 0.0));
+		assert_eq!(bodies.torques()[0], Vector3::new(0.0, 0.0, -25.0));
+		assert!((explosion.time_passed - 1.1).abs() < 1e-6);
+	}
+
+	#[test]
+	pub fn wind_pushes_by_exposed_area() {
+		let plate = Shape::Cuboid {
+			half_size: Vector3::new(0.05, 1.0, 0.5),
+		};
+		let mut wind = Wind::new(Vector3::new(10.0, 0.0, 0.0), plate);
+		wind.air_density = 1.0;
+		let mut bodies: BodySet = std::iter::once(body_at(Vector3::zero())).collect();
+		wind.update_force(&mut bodies, 0, 1.0);
+		assert_eq!(bodies.forces()[0], Vector3::new(100.0, 0.0, 0.0));
+		assert_eq!(bodies.torques()[0], Vector3::zero());
+
+		// Edge-on, the plate catches far less wind
+		wind.velocity = Vector3::new(0.0, 0.0, 10.0);
+		assert!(wind.force(&bodies.get(0)).magnitude() < 20.0);
+
+		// A body moving with the wind feels nothing
+		bodies.update(0, |body| body.velocity = wind.velocity);
+		assert_eq!(wind.force(&bodies.get(0)), Vector3::zero());
+	}
+
+	#[test]
+	pub fn offset_centroid_tumbles_debris() {
+		let mut wind = Wind::new(Vector3::new(10.0, 0.0, 0.0), Shape::Sphere { radius: 0.1 });
+		wind.centroid = Vector3::new(0.0, 0.5, 0.0);
+		let mut bodies: BodySet = std::iter::once(body_at(Vector3::zero())).collect();
+		wind.update_force(&mut bodies, 0, 1.0);
+		let torque = bodies.torques()[0];
+		assert!(torque.z() < 0.0);
+		assert!(torque.x().abs() < 1e-6 && torque.y().abs() < 1e-6);
+	}
+}
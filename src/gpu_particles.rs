@@ -0,0 +1,441 @@
+use std::{borrow::Cow, sync::mpsc};
+
+use wgpu::util::DeviceExt;
+
+use crate::{particle::Particle, vec::Vector3, Real};
+
+/// The number of particles each invocation group of the compute shader integrates.
+const WORKGROUP_SIZE: u32 = 64;
+
+/// The most invocation groups a dispatch may have along one dimension.
+const MAX_WORKGROUPS: u32 = 65_535;
+
+/// The size of a particle as the compute shader stores it.
+const PARTICLE_SIZE: usize = 64;
+
+/// The size of a force field as the compute shader stores it.
+const FORCE_FIELD_SIZE: usize = 32;
+
+const SHADER: &str = r"
+struct Particle {
+	position: vec3<f32>,
+	damping: f32,
+	velocity: vec3<f32>,
+	inverse_mass: f32,
+	acceleration: vec3<f32>,
+	padding: f32,
+	force: vec3<f32>,
+	more_padding: f32,
+}
+
+struct ForceField {
+	vector: vec3<f32>,
+	kind: u32,
+	first: f32,
+	second: f32,
+	padding: vec2<f32>,
+}
+
+struct Parameters {
+	duration: f32,
+	count: u32,
+	field_count: u32,
+	padding: u32,
+}
+
+@group(0) @binding(0) var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(1) var<storage, read> fields: array<ForceField>;
+@group(0) @binding(2) var<uniform> parameters: Parameters;
+
+fn field_acceleration(field: ForceField, particle: Particle) -> vec3<f32> {
+	switch field.kind {
+		case 0u: {
+			return field.vector;
+		}
+		case 1u: {
+			let speed = length(particle.velocity);
+			if speed == 0.0 {
+				return vec3<f32>(0.0);
+			}
+			let drag = fma(field.second, speed * speed, field.first * speed);
+			return particle.velocity / speed * -drag * particle.inverse_mass;
+		}
+		default: {
+			let offset = field.vector - particle.position;
+			let distance_squared = max(dot(offset, offset), 1.0);
+			return normalize(offset) * field.first / distance_squared;
+		}
+	}
+}
+
+@compute @workgroup_size(64)
+fn integrate(
+	@builtin(global_invocation_id) id: vec3<u32>,
+	@builtin(num_workgroups) groups: vec3<u32>,
+) {
+	let index = id.x + id.y * groups.x * 64u;
+	if index >= parameters.count {
+		return;
+	}
+	var particle = particles[index];
+	if particle.inverse_mass <= 0.0 {
+		return;
+	}
+	var acceleration = particle.acceleration + particle.force * particle.inverse_mass;
+	for (var field = 0u; field < parameters.field_count; field++) {
+		acceleration += field_acceleration(fields[field], particle);
+	}
+	let duration = parameters.duration;
+	particle.position += particle.velocity * duration;
+	particle.velocity += acceleration * duration;
+	particle.velocity *= pow(particle.damping, duration);
+	particle.force = vec3<f32>(0.0);
+	particles[index] = particle;
+}
+";
+
+/// A force acting on every particle integrated on the GPU. Fields stand in
+/// for the CPU's force generators, which cannot run in a compute shader.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GpuForceField {
+	/// Accelerates every particle alike, as [`ParticleGravity`](crate::forces::ParticleGravity) does.
+	Gravity(Vector3),
+
+	/// Slows the particles as [`ParticleDrag`](crate::forces::ParticleDrag) does,
+	/// with coefficients for their speed and its square.
+	Drag { k1: Real, k2: Real },
+
+	/// Pulls the particles towards a point with an acceleration of the
+	/// strength over the square of their distance, which is taken to be at
+	/// least one so that particles passing through the point are not flung away.
+	Attractor { position: Vector3, strength: Real },
+}
+
+impl GpuForceField {
+	/// Writes the field as the compute shader reads it.
+	fn encode(&self, bytes: &mut Vec<u8>) {
+		let (vector, kind, first, second) = match *self {
+			Self::Gravity(gravity) => (gravity, 0_u32, 0.0, 0.0),
+			Self::Drag { k1, k2 } => (Vector3::zero(), 1, k1, k2),
+			Self::Attractor { position, strength } => (position, 2, strength, 0.0),
+		};
+		encode_vector(bytes, vector, Real::from_bits(kind));
+		for value in [first, second, 0.0, 0.0] {
+			bytes.extend_from_slice(&value.to_le_bytes());
+		}
+	}
+}
+
+/// A set of particles integrated by a compute shader, for effects with far
+/// more particles than the CPU could step, such as sparks and smoke.
+///
+/// The particles live in a storage buffer on the GPU, which stays there
+/// from step to step and can be bound by the renderer to draw them without
+/// reading them back. Each particle takes 64 bytes: its position and
+/// damping, its velocity and inverse mass, its acceleration, and its
+/// accumulated force, each vector followed by one 32-bit value.
+///
+/// Only integration and the [`GpuForceField`]s run on the GPU. Contacts,
+/// links, and force generators need the particles on the CPU, through
+/// [`Self::read_back`] and [`Self::write`].
+pub struct GpuParticles {
+	particles: wgpu::Buffer,
+	fields: wgpu::Buffer,
+	parameters: wgpu::Buffer,
+	pipeline: wgpu::ComputePipeline,
+	bind_group: wgpu::BindGroup,
+	count: u32,
+	field_count: u32,
+}
+
+impl GpuParticles {
+	/// Copies the particles to the GPU, acted on by the given force fields.
+	///
+	/// # Panics
+	///
+	/// Panics if there are more particles than a `u32` can count.
+	#[must_use]
+	pub fn new(device: &wgpu::Device, particles: &[Particle], fields: &[GpuForceField]) -> Self {
+		let count = u32::try_from(particles.len()).expect("too many particles for the GPU");
+		let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+			label: Some("particle integration"),
+			source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER)),
+		});
+		let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+			label: Some("particle integration"),
+			layout: None,
+			module: &module,
+			entry_point: Some("integrate"),
+			compilation_options: wgpu::PipelineCompilationOptions::default(),
+			cache: None,
+		});
+		// A binding may not be empty, so an empty set keeps room for one particle
+		let mut contents = encode_particles(particles);
+		contents.resize(contents.len().max(PARTICLE_SIZE), 0);
+		let particles = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("particles"),
+			contents: &contents,
+			usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+		});
+		let parameters = device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("particle parameters"),
+			size: 16,
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+			mapped_at_creation: false,
+		});
+		let (fields, field_count) = create_fields(device, fields);
+		let bind_group = create_bind_group(device, &pipeline, [&particles, &fields, &parameters]);
+		Self {
+			particles,
+			fields,
+			parameters,
+			pipeline,
+			bind_group,
+			count,
+			field_count,
+		}
+	}
+
+	/// Returns the number of particles.
+	#[must_use]
+	pub const fn len(&self) -> usize {
+		self.count as usize
+	}
+
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.count == 0
+	}
+
+	/// Returns the storage buffer holding the particles, for drawing them.
+	#[must_use]
+	pub const fn buffer(&self) -> &wgpu::Buffer {
+		&self.particles
+	}
+
+	/// Replaces the force fields acting on the particles.
+	pub fn set_force_fields(&mut self, device: &wgpu::Device, fields: &[GpuForceField]) {
+		(self.fields, self.field_count) = create_fields(device, fields);
+		self.bind_group = create_bind_group(device, &self.pipeline, [&self.particles, &self.fields, &self.parameters]);
+	}
+
+	/// Integrates the particles forward in time by the given amount, the
+	/// same way [`Particle::integrate`] does, once the queue is next run.
+	pub fn step(&self, device: &wgpu::Device, queue: &wgpu::Queue, duration: Real) {
+		if self.count == 0 || duration <= 0.0 {
+			return;
+		}
+		let mut parameters = Vec::with_capacity(16);
+		for value in [duration.to_bits(), self.count, self.field_count, 0] {
+			parameters.extend_from_slice(&value.to_le_bytes());
+		}
+		queue.write_buffer(&self.parameters, 0, &parameters);
+		let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+			label: Some("particle integration"),
+		});
+		{
+			let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+				label: Some("particle integration"),
+				timestamp_writes: None,
+			});
+			pass.set_pipeline(&self.pipeline);
+			pass.set_bind_group(0, &self.bind_group, &[]);
+			let groups = self.count.div_ceil(WORKGROUP_SIZE);
+			let columns = groups.min(MAX_WORKGROUPS);
+			pass.dispatch_workgroups(columns, groups.div_ceil(columns), 1);
+		}
+		queue.submit([encoder.finish()]);
+	}
+
+	/// Replaces the particles on the GPU with the given ones, such as after
+	/// they have been changed on the CPU.
+	///
+	/// # Panics
+	///
+	/// Panics if the number of particles given differs from the number on the GPU.
+	pub fn write(&self, queue: &wgpu::Queue, particles: &[Particle]) {
+		assert_eq!(particles.len(), self.len(), "the number of particles cannot change");
+		if !particles.is_empty() {
+			queue.write_buffer(&self.particles, 0, &encode_particles(particles));
+		}
+	}
+
+	/// Copies the particles back from the GPU into the given ones, waiting
+	/// for the steps submitted so far to finish. Only the position and
+	/// velocity are read back, and the accumulated force is cleared, as
+	/// integration leaves it. The rest of each particle is kept.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the GPU's copy of the particles could not be read.
+	///
+	/// # Panics
+	///
+	/// Panics if the number of particles given differs from the number on the GPU.
+	pub fn read_back(
+		&self,
+		device: &wgpu::Device,
+		queue: &wgpu::Queue,
+		particles: &mut [Particle],
+	) -> Result<(), wgpu::BufferAsyncError> {
+		assert_eq!(particles.len(), self.len(), "the number of particles cannot change");
+		if particles.is_empty() {
+			return Ok(());
+		}
+		let size = self.particles.size();
+		let staging = device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("particle read back"),
+			size,
+			usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+			mapped_at_creation: false,
+		});
+		let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+			label: Some("particle read back"),
+		});
+		encoder.copy_buffer_to_buffer(&self.particles, 0, &staging, 0, size);
+		queue.submit([encoder.finish()]);
+		let slice = staging.slice(..);
+		let (sender, receiver) = mpsc::channel();
+		slice.map_async(wgpu::MapMode::Read, move |result| {
+			let _ = sender.send(result);
+		});
+		let _ = device.poll(wgpu::Maintain::Wait);
+		receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError))?;
+		decode_particles(&slice.get_mapped_range(), particles);
+		staging.unmap();
+		Ok(())
+	}
+}
+
+/// Writes the vector followed by one more value.
+fn encode_vector(bytes: &mut Vec<u8>, vector: Vector3, last: Real) {
+	for value in [vector.x(), vector.y(), vector.z(), last] {
+		bytes.extend_from_slice(&value.to_le_bytes());
+	}
+}
+
+/// Writes the particles as the compute shader reads them.
+fn encode_particles(particles: &[Particle]) -> Vec<u8> {
+	let mut bytes = Vec::with_capacity(particles.len() * PARTICLE_SIZE);
+	for particle in particles {
+		encode_vector(&mut bytes, particle.position, particle.damping);
+		encode_vector(&mut bytes, particle.velocity, particle.inverse_mass);
+		encode_vector(&mut bytes, particle.acceleration, 0.0);
+		encode_vector(&mut bytes, particle.force_accumulator, 0.0);
+	}
+	bytes
+}
+
+/// Reads the positions and velocities of the particles written by the compute shader.
+fn decode_particles(bytes: &[u8], particles: &mut [Particle]) {
+	let read = |offset: usize| {
+		let mut value = [0; 4];
+		value.copy_from_slice(&bytes[offset..offset + 4]);
+		Real::from_le_bytes(value)
+	};
+	let vector = |offset: usize| Vector3::new(read(offset), read(offset + 4), read(offset + 8));
+	for (index, particle) in particles.iter_mut().enumerate() {
+		let offset = index * PARTICLE_SIZE;
+		particle.position = vector(offset);
+		particle.velocity = vector(offset + 16);
+		particle.force_accumulator = Vector3::zero();
+	}
+}
+
+/// Creates the buffer holding the force fields, returning it with their number.
+fn create_fields(device: &wgpu::Device, fields: &[GpuForceField]) -> (wgpu::Buffer, u32) {
+	let mut contents = Vec::with_capacity(fields.len().max(1) * FORCE_FIELD_SIZE);
+	for field in fields {
+		field.encode(&mut contents);
+	}
+	contents.resize(contents.len().max(FORCE_FIELD_SIZE), 0);
+	let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+		label: Some("particle force fields"),
+		contents: &contents,
+		usage: wgpu::BufferUsages::STORAGE,
+	});
+	(buffer, u32::try_from(fields.len()).unwrap_or(u32::MAX))
+}
+
+fn create_bind_group(
+	device: &wgpu::Device,
+	pipeline: &wgpu::ComputePipeline,
+	buffers: [&wgpu::Buffer; 3],
+) -> wgpu::BindGroup {
+	let entries = buffers.map(|buffer| buffer.as_entire_binding());
+	device.create_bind_group(&wgpu::BindGroupDescriptor {
+		label: Some("particles"),
+		layout: &pipeline.get_bind_group_layout(0),
+		entries: &[0, 1, 2].map(|binding| wgpu::BindGroupEntry {
+			binding,
+			resource: entries[binding as usize].clone(),
+		}),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::forces::{ParticleDrag, ParticleForceGenerator};
+
+	use super::*;
+
+	fn particles() -> Vec<Particle> {
+		(0..100_u8)
+			.map(|index| Particle {
+				position: Vector3::new(Real::from(index), 10.0, 0.0),
+				velocity: Vector3::new(0.0, 1.0, Real::from(index) * 0.1),
+				damping: 0.99,
+				inverse_mass: if index == 0 { 0.0 } else { 0.5 },
+				..Default::default()
+			})
+			.collect()
+	}
+
+	#[test]
+	pub fn particles_read_back_as_written() {
+		let mut particles = particles();
+		let bytes = encode_particles(&particles);
+		assert_eq!(bytes.len(), particles.len() * PARTICLE_SIZE);
+		let expected = particles.clone();
+		for particle in &mut particles {
+			*particle = Particle::default();
+		}
+		decode_particles(&bytes, &mut particles);
+		for (particle, expected) in particles.iter().zip(&expected) {
+			assert_eq!(particle.position, expected.position);
+			assert_eq!(particle.velocity, expected.velocity);
+		}
+	}
+
+	#[test]
+	pub fn gpu_integrates_as_the_cpu_does() {
+		let Some((device, queue)) = pollster::block_on(async {
+			let adapter = wgpu::Instance::default()
+				.request_adapter(&wgpu::RequestAdapterOptions::default())
+				.await?;
+			adapter.request_device(&wgpu::DeviceDescriptor::default(), None).await.ok()
+		}) else {
+			// Without an adapter, such as on a machine with no GPU, there is nothing to test
+			return;
+		};
+		let gravity = Vector3::new(0.0, -9.81, 0.0);
+		let mut drag = ParticleDrag { k1: 0.1, k2: 0.01 };
+		let mut expected = particles();
+		let fields = [GpuForceField::Gravity(gravity), GpuForceField::Drag { k1: drag.k1, k2: drag.k2 }];
+		let gpu = GpuParticles::new(&device, &expected, &fields);
+		for _ in 0..60 {
+			gpu.step(&device, &queue, 1.0 / 60.0);
+			for index in 0..expected.len() {
+				drag.update_force(&mut expected, index, 1.0 / 60.0);
+				expected[index].acceleration = gravity;
+				expected[index].integrate(1.0 / 60.0);
+			}
+		}
+		let mut particles = particles();
+		gpu.read_back(&device, &queue, &mut particles).expect("the particles are readable");
+		for (particle, expected) in particles.iter().zip(&expected) {
+			assert!((particle.position - expected.position).magnitude() < 1e-3);
+			assert!((particle.velocity - expected.velocity).magnitude() < 1e-3);
+		}
+	}
+}
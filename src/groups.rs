@@ -0,0 +1,156 @@
+use crate::{
+	contacts::{ParticleContact, ParticleContactGenerator, ParticleContactResolver},
+	particle::Particle,
+	pbd::PositionConstraint,
+	vec::Vector3,
+	Real,
+};
+
+/// A set of constraints and contact generators solved together
+/// with their own iteration budget.
+///
+/// Groups are solved in ascending `order`, so stiff machinery can be solved
+/// first with many iterations while decorative ropes get only a few. Groups
+/// with the same order are solved in the order they were given.
+#[derive(Default)]
+pub struct ConstraintGroup {
+	/// Holds the position of this group in the solve order, lowest first.
+	pub order: i32,
+
+	/// Holds the number of passes made over the group's position constraints
+	/// each step. Contacts are resolved with this many iterations per contact.
+	pub iterations: usize,
+
+	/// Holds the position constraints in the group.
+	pub constraints: Vec<Box<dyn PositionConstraint>>,
+
+	/// Holds the contact generators in the group.
+	pub contact_generators: Vec<Box<dyn ParticleContactGenerator>>,
+
+	/// Holds the resolver used for the group's contacts.
+	/// Its iteration budget is set from `iterations` every step.
+	pub resolver: ParticleContactResolver,
+
+	contacts: Vec<ParticleContact>,
+}
+
+impl ConstraintGroup {
+	#[must_use]
+	pub fn new(order: i32, iterations: usize) -> Self {
+		Self {
+			order,
+			iterations,
+			..Default::default()
+		}
+	}
+
+	/// Adds a position constraint to the group.
+	pub fn add_constraint(&mut self, constraint: impl PositionConstraint + 'static) {
+		self.constraints.push(Box::new(constraint));
+	}
+
+	/// Adds a contact generator to the group.
+	pub fn add_contact_generator(&mut self, generator: impl ParticleContactGenerator + 'static) {
+		self.contact_generators.push(Box::new(generator));
+	}
+
+	/// Calls `begin_step` on every position constraint in the group.
+	pub fn begin_step(&mut self) {
+		for constraint in &mut self.constraints {
+			constraint.begin_step();
+		}
+	}
+
+	/// Projects every position constraint in the group `iterations` times.
+	pub fn project(&mut self, particles: &mut [Particle], previous_positions: &[Vector3], duration: Real) {
+		for _ in 0..self.iterations {
+			for constraint in &mut self.constraints {
+				constraint.project(particles, previous_positions, duration);
+			}
+		}
+	}
+
+	/// Generates the group's contacts and resolves them within the group's budget.
+	pub fn resolve_contacts(&mut self, particles: &mut [Particle], duration: Real) {
+		self.contacts.clear();
+		for generator in &self.contact_generators {
+			generator.add_contact(particles, &mut self.contacts);
+		}
+		self.resolver.iterations = self.iterations * self.contacts.len();
+		self.resolver.resolve_contacts(&mut self.contacts, particles, duration);
+	}
+}
+
+/// Returns the indices of the given groups in the order they should be solved.
+#[must_use]
+pub fn solve_order(groups: &[ConstraintGroup]) -> Vec<usize> {
+	let mut order: Vec<usize> = (0..groups.len()).collect();
+	order.sort_by_key(|index| groups[*index].order);
+	order
+}
+
+/// Resolves the contacts of every group in solve order.
+pub fn resolve_group_contacts(groups: &mut [ConstraintGroup], particles: &mut [Particle], duration: Real) {
+	for index in solve_order(groups) {
+		groups[index].resolve_contacts(particles, duration);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{links::ParticleRod, pbd::DistanceConstraint};
+
+	use super::*;
+
+	fn particles_at(positions: &[Vector3]) -> Vec<Particle> {
+		positions
+			.iter()
+			.map(|position| Particle {
+				position: *position,
+				inverse_mass: 1.0,
+				damping: 1.0,
+				..Default::default()
+			})
+			.collect()
+	}
+
+	#[test]
+	pub fn groups_are_solved_in_order() {
+		let groups = [
+			ConstraintGroup::new(2, 1),
+			ConstraintGroup::new(-1, 1),
+			ConstraintGroup::new(2, 1),
+			ConstraintGroup::new(0, 1),
+		];
+		assert_eq!(solve_order(&groups), vec![1, 3, 0, 2]);
+	}
+
+	#[test]
+	pub fn group_projects_its_iterations() {
+		let mut particles = particles_at(&[Vector3::zero(), Vector3::new(2.0, 0.0, 0.0)]);
+		let previous_positions: Vec<Vector3> = particles.iter().map(|particle| particle.position).collect();
+		let mut group = ConstraintGroup::new(0, 2);
+		group.add_constraint(DistanceConstraint {
+			stiffness: 0.5,
+			..DistanceConstraint::rod([0, 1], 1.0)
+		});
+		group.project(&mut particles, &previous_positions, 1.0);
+		// Each pass halves the remaining error of 1
+		let length = (particles[1].position - particles[0].position).magnitude();
+		assert!((length - 1.25).abs() < 1e-6);
+	}
+
+	#[test]
+	pub fn group_resolves_its_contacts() {
+		let mut particles = particles_at(&[Vector3::zero(), Vector3::new(2.0, 0.0, 0.0)]);
+		let mut group = ConstraintGroup::new(0, 1);
+		group.add_contact_generator(ParticleRod {
+			particles: [0, 1],
+			length: 1.0,
+		});
+		resolve_group_contacts(std::slice::from_mut(&mut group), &mut particles, 1.0);
+		assert_eq!(group.resolver.iterations_used, 1);
+		let length = (particles[1].position - particles[0].position).magnitude();
+		assert!((length - 1.0).abs() < 1e-6);
+	}
+}
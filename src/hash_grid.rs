@@ -0,0 +1,479 @@
+use std::collections::HashMap;
+
+use crate::{
+	bounding::{Aabb, BoundingSphere, BoundingVolume, Ray},
+	broad_phase::CollisionGroups,
+	bvh::{sort_candidates, PotentialContact, RayCandidate},
+	vec::Vector3,
+	Real,
+};
+
+/// The key of a grid cell, holding the bits of its integer coordinates.
+type CellKey = [u32; 3];
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+	center: Vector3,
+	radius: Real,
+	min_cell: Vector3,
+	max_cell: Vector3,
+}
+
+/// A uniform grid broad phase, hashed so that only occupied cells use memory.
+///
+/// Each body is a sphere registered in every cell its bounding box touches.
+/// The grid works best when the cells are about as large as the bodies,
+/// as is the case for particles, debris, or fluid samples, so that each
+/// body touches only a handful of cells. Bodies are identified by index.
+#[derive(Debug, Clone)]
+pub struct HashGrid {
+	cell_size: Real,
+	cells: HashMap<CellKey, Vec<usize>>,
+	entries: Vec<Option<Entry>>,
+	groups: Vec<CollisionGroups>,
+
+	/// Holds a box enclosing every body added since the grid was last cleared,
+	/// which bounds how far a ray needs to be followed.
+	occupied: Option<Aabb>,
+
+	/// Holds the pairs found by each thread, kept from search to search.
+	#[cfg(feature = "rayon")]
+	pub(crate) found: Vec<Vec<PotentialContact>>,
+}
+
+impl HashGrid {
+	/// Creates an empty grid with cells of the given size.
+	#[must_use]
+	pub fn new(cell_size: Real) -> Self {
+		Self {
+			cell_size,
+			cells: HashMap::new(),
+			entries: Vec::new(),
+			groups: Vec::new(),
+			occupied: None,
+			#[cfg(feature = "rayon")]
+			found: Vec::new(),
+		}
+	}
+
+	#[must_use]
+	pub const fn cell_size(&self) -> Real {
+		self.cell_size
+	}
+
+	/// Returns the number of bodies in the grid.
+	#[must_use]
+	pub fn len(&self) -> usize {
+		self.entries.iter().flatten().count()
+	}
+
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.entries.iter().all(Option::is_none)
+	}
+
+	/// Returns the collision groups of the given body.
+	#[must_use]
+	pub fn groups(&self, body: usize) -> CollisionGroups {
+		self.groups.get(body).copied().unwrap_or_default()
+	}
+
+	/// Sets the collision groups of the given body, which are kept if it is removed and added again.
+	pub fn set_groups(&mut self, body: usize, groups: CollisionGroups) {
+		if self.groups.len() <= body {
+			self.groups.resize(body + 1, CollisionGroups::default());
+		}
+		self.groups[body] = groups;
+	}
+
+	/// Removes every body from the grid.
+	pub fn clear(&mut self) {
+		self.cells.clear();
+		self.entries.clear();
+		self.occupied = None;
+	}
+
+	/// Adds the given body as a sphere, replacing it if it is already present.
+	pub fn insert(&mut self, body: usize, center: Vector3, radius: Real) {
+		self.remove(body);
+		let entry = Entry {
+			center,
+			radius,
+			min_cell: self.cell_of(center - Vector3::new(radius, radius, radius)),
+			max_cell: self.cell_of(center + Vector3::new(radius, radius, radius)),
+		};
+		for_each_cell(entry.min_cell, entry.max_cell, |cell| {
+			self.cells.entry(cell_key(cell)).or_default().push(body);
+		});
+		if self.entries.len() <= body {
+			self.entries.resize(body + 1, None);
+		}
+		self.entries[body] = Some(entry);
+		let bounds = Aabb::from_center(center, Vector3::new(radius, radius, radius));
+		self.occupied = Some(self.occupied.map_or(bounds, |occupied| occupied.merge(&bounds)));
+	}
+
+	/// Removes the given body, returning true if it was in the grid.
+	pub fn remove(&mut self, body: usize) -> bool {
+		let Some(entry) = self.entries.get_mut(body).and_then(Option::take) else {
+			return false;
+		};
+		for_each_cell(entry.min_cell, entry.max_cell, |cell| {
+			let key = cell_key(cell);
+			if let Some(bodies) = self.cells.get_mut(&key) {
+				bodies.retain(|other| *other != body);
+				if bodies.is_empty() {
+					self.cells.remove(&key);
+				}
+			}
+		});
+		true
+	}
+
+	/// Moves the given body, only touching the cells if it has changed cells.
+	pub fn update(&mut self, body: usize, center: Vector3, radius: Real) {
+		let min_cell = self.cell_of(center - Vector3::new(radius, radius, radius));
+		let max_cell = self.cell_of(center + Vector3::new(radius, radius, radius));
+		match self.entries.get_mut(body) {
+			Some(Some(entry)) if entry.min_cell == min_cell && entry.max_cell == max_cell => {
+				entry.center = center;
+				entry.radius = radius;
+			},
+			_ => self.insert(body, center, radius),
+		}
+	}
+
+	/// Returns every pair of bodies whose spheres overlap and whose groups
+	/// interact, each pair once, ordered by the lower body index.
+	#[must_use]
+	pub fn potential_contacts(&self) -> Vec<PotentialContact> {
+		let mut contacts = Vec::new();
+		self.potential_contacts_into(&mut contacts);
+		contacts
+	}
+
+	/// Replaces the contents of the given list with the same pairs as
+	/// [`Self::potential_contacts`], reusing its storage.
+	pub fn potential_contacts_into(&self, contacts: &mut Vec<PotentialContact>) {
+		contacts.clear();
+		for body in 0..self.entries.len() {
+			self.contacts_of(body, contacts);
+		}
+	}
+
+	/// Returns the same pairs as [`Self::potential_contacts`], in the same
+	/// order, with the bodies split across several threads.
+	#[cfg(feature = "rayon")]
+	#[must_use]
+	pub fn par_potential_contacts(&self) -> Vec<PotentialContact> {
+		let mut contacts = Vec::new();
+		self.par_potential_contacts_into(&mut Vec::new(), &mut contacts);
+		contacts
+	}
+
+	/// Replaces the contents of the given list with the same pairs as
+	/// [`Self::potential_contacts`], in the same order, with the bodies split
+	/// into a run for each thread.
+	///
+	/// Each thread finds the pairs of its run in a list of the given lists,
+	/// and the lists are joined in the order of their runs. Once those lists
+	/// have grown, searching again allocates nothing.
+	#[cfg(feature = "rayon")]
+	pub fn par_potential_contacts_into(
+		&self,
+		found: &mut Vec<Vec<PotentialContact>>,
+		contacts: &mut Vec<PotentialContact>,
+	) {
+		use rayon::prelude::*;
+		let bodies = self.entries.len();
+		let threads = rayon::current_num_threads().max(1);
+		let run = bodies.div_ceil(threads).max(1);
+		found.resize_with(threads, Vec::new);
+		found.par_iter_mut().enumerate().for_each(|(index, found)| {
+			found.clear();
+			for body in (index * run).min(bodies)..((index + 1) * run).min(bodies) {
+				self.contacts_of(body, found);
+			}
+		});
+		contacts.clear();
+		for found in found.iter() {
+			contacts.extend_from_slice(found);
+		}
+	}
+
+	/// Finds the potential contacts between the given body and those with higher indices.
+	fn contacts_of(&self, body: usize, contacts: &mut Vec<PotentialContact>) {
+		let Some(entry) = self.entries[body] else { return };
+		for_each_cell(entry.min_cell, entry.max_cell, |cell| {
+			for &other in self.cells.get(&cell_key(cell)).into_iter().flatten() {
+				if other <= body || !self.groups(body).interacts_with(&self.groups(other)) {
+					continue;
+				}
+				let Some(other_entry) = self.entries[other] else {
+					continue;
+				};
+				// Report the pair only from the first cell both bodies share
+				if cell == max_components(entry.min_cell, other_entry.min_cell)
+					&& spheres_overlap(entry.center, entry.radius, other_entry.center, other_entry.radius)
+				{
+					contacts.push(PotentialContact { bodies: [body, other] });
+				}
+			}
+		});
+	}
+
+	/// Returns the bodies whose spheres come within the given radius of the point.
+	#[must_use]
+	pub fn neighbors(&self, point: Vector3, radius: Real) -> Vec<usize> {
+		let min_cell = self.cell_of(point - Vector3::new(radius, radius, radius));
+		let max_cell = self.cell_of(point + Vector3::new(radius, radius, radius));
+		let mut neighbors = Vec::new();
+		for_each_cell(min_cell, max_cell, |cell| {
+			for &body in self.cells.get(&cell_key(cell)).into_iter().flatten() {
+				let Some(entry) = self.entries[body] else { continue };
+				if cell == max_components(min_cell, entry.min_cell)
+					&& spheres_overlap(point, radius, entry.center, entry.radius)
+				{
+					neighbors.push(body);
+				}
+			}
+		});
+		neighbors
+	}
+
+	/// Returns the bodies whose spheres overlap the given box and whose
+	/// groups interact with the given groups.
+	#[must_use]
+	pub fn query(&self, bounds: &Aabb, groups: CollisionGroups) -> Vec<usize> {
+		let mut bodies = Vec::new();
+		self.query_into(bounds, groups, &mut bodies);
+		bodies
+	}
+
+	/// Replaces the contents of the given list with the same bodies as
+	/// [`Self::query`], reusing its storage.
+	pub fn query_into(&self, bounds: &Aabb, groups: CollisionGroups, bodies: &mut Vec<usize>) {
+		bodies.clear();
+		let min_cell = self.cell_of(bounds.min);
+		let max_cell = self.cell_of(bounds.max);
+		for_each_cell(min_cell, max_cell, |cell| {
+			for &body in self.cells.get(&cell_key(cell)).into_iter().flatten() {
+				let Some(entry) = self.entries[body] else { continue };
+				if !self.groups(body).interacts_with(&groups) {
+					continue;
+				}
+				let closest = bounds.closest_point(entry.center);
+				if cell == max_components(min_cell, entry.min_cell)
+					&& (closest - entry.center).magnitude_squared() <= entry.radius * entry.radius
+				{
+					bodies.push(body);
+				}
+			}
+		});
+	}
+
+	/// Returns the bodies whose spheres the ray enters within the given distance
+	/// and whose groups interact with the given groups, nearest first.
+	///
+	/// Only the cells the ray passes through are visited, and the walk stops
+	/// once the ray has passed every body in the grid.
+	#[must_use]
+	pub fn ray_cast(&self, ray: &Ray, max_distance: Real, groups: CollisionGroups) -> Vec<RayCandidate> {
+		let Some(occupied) = self.occupied else {
+			return Vec::new();
+		};
+		let Some(entry_distance) = occupied.ray_distance(ray, max_distance) else {
+			return Vec::new();
+		};
+		let walk_distance = max_distance.min(entry_distance + (occupied.max - occupied.min).magnitude());
+
+		let mut candidates = Vec::new();
+		self.for_each_cell_on_ray(ray, walk_distance, |cell| {
+			for &body in self.cells.get(&cell_key(cell)).into_iter().flatten() {
+				let Some(entry) = self.entries[body] else { continue };
+				if !self.groups(body).interacts_with(&groups) {
+					continue;
+				}
+				let sphere = BoundingSphere::new(entry.center, entry.radius);
+				if let Some(distance) = sphere.ray_distance(ray, max_distance) {
+					candidates.push(RayCandidate { body, distance });
+				}
+			}
+		});
+		sort_candidates(&mut candidates);
+		candidates.dedup_by_key(|candidate| candidate.body);
+		candidates
+	}
+
+	/// Calls the given function with each cell the ray passes through within
+	/// the given distance, in order, stepping into the next cell across
+	/// whichever cell boundary the ray reaches first.
+	fn for_each_cell_on_ray(&self, ray: &Ray, max_distance: Real, mut function: impl FnMut(Vector3)) {
+		let mut cell = self.cell_of(ray.origin);
+		let mut step = Vector3::zero();
+		let mut next_boundary = Vector3::new(Real::INFINITY, Real::INFINITY, Real::INFINITY);
+		let mut boundary_spacing = next_boundary;
+		for axis in 0..3 {
+			let direction = ray.direction[axis];
+			if direction > 0.0 {
+				step[axis] = 1.0;
+				next_boundary[axis] = (cell[axis] + 1.0).mul_add(self.cell_size, -ray.origin[axis]) / direction;
+			} else if direction < 0.0 {
+				step[axis] = -1.0;
+				next_boundary[axis] = cell[axis].mul_add(self.cell_size, -ray.origin[axis]) / direction;
+			} else {
+				continue;
+			}
+			boundary_spacing[axis] = self.cell_size / direction.abs();
+		}
+		loop {
+			function(cell);
+			let axis = (0..3)
+				.min_by(|first, second| next_boundary[*first].total_cmp(&next_boundary[*second]))
+				.unwrap_or_default();
+			if next_boundary[axis] > max_distance {
+				break;
+			}
+			cell[axis] += step[axis];
+			next_boundary[axis] += boundary_spacing[axis];
+		}
+	}
+
+	/// Returns the integer coordinates of the cell holding the given point.
+	fn cell_of(&self, point: Vector3) -> Vector3 {
+		let mut cell = point * self.cell_size.recip();
+		for axis in 0..3 {
+			// Adding zero turns a negative zero into a positive one, so both hash alike
+			cell[axis] = cell[axis].floor() + 0.0;
+		}
+		cell
+	}
+}
+
+fn cell_key(cell: Vector3) -> CellKey {
+	[cell.x().to_bits(), cell.y().to_bits(), cell.z().to_bits()]
+}
+
+fn max_components(first: Vector3, second: Vector3) -> Vector3 {
+	Vector3::new(
+		first.x().max(second.x()),
+		first.y().max(second.y()),
+		first.z().max(second.z()),
+	)
+}
+
+fn spheres_overlap(first_center: Vector3, first_radius: Real, second_center: Vector3, second_radius: Real) -> bool {
+	let radii = first_radius + second_radius;
+	(first_center - second_center).magnitude_squared() < radii * radii
+}
+
+/// Calls the given function with the coordinates of every cell in the range, inclusive.
+fn for_each_cell(min_cell: Vector3, max_cell: Vector3, mut function: impl FnMut(Vector3)) {
+	for x in cell_range(min_cell.x(), max_cell.x()) {
+		for y in cell_range(min_cell.y(), max_cell.y()) {
+			for z in cell_range(min_cell.z(), max_cell.z()) {
+				function(Vector3::new(x, y, z));
+			}
+		}
+	}
+}
+
+/// Returns the integer coordinates from the first to the last, inclusive.
+fn cell_range(first: Real, last: Real) -> impl Iterator<Item = Real> {
+	std::iter::successors(Some(first), |coordinate| Some(coordinate + 1.0))
+		.take_while(move |coordinate| *coordinate <= last)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn grid() -> HashGrid {
+		let mut grid = HashGrid::new(1.0);
+		for (body, x) in [-0.7, 0.0, 0.7, 2.5, 5.0].into_iter().enumerate() {
+			grid.insert(body, Vector3::new(x, 0.0, 0.0), 0.4);
+		}
+		grid
+	}
+
+	#[test]
+	pub fn finds_each_overlapping_pair_once() {
+		let grid = grid();
+		assert_eq!(grid.len(), 5);
+		let pairs: Vec<[usize; 2]> = grid.potential_contacts().iter().map(|contact| contact.bodies).collect();
+		assert_eq!(pairs, vec![[0, 1], [1, 2]]);
+	}
+
+	#[test]
+	pub fn neighbor_queries() {
+		let grid = grid();
+		let mut neighbors = grid.neighbors(Vector3::new(0.35, 0.0, 0.0), 0.1);
+		neighbors.sort_unstable();
+		assert_eq!(neighbors, vec![1, 2]);
+		assert!(grid.neighbors(Vector3::new(4.0, 0.0, 0.0), 0.5).is_empty());
+
+		let mut bodies = grid.query(
+			&Aabb::new(Vector3::new(0.2, -1.0, -1.0), Vector3::new(2.2, 1.0, 1.0)),
+			CollisionGroups::default(),
+		);
+		bodies.sort_unstable();
+		assert_eq!(bodies, vec![1, 2, 3]);
+	}
+
+	#[test]
+	pub fn ray_cast_walks_the_cells_on_the_ray() {
+		let grid = grid();
+		let ray = Ray::new(Vector3::new(3.0, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0));
+		let bodies: Vec<usize> = grid
+			.ray_cast(&ray, Real::INFINITY, CollisionGroups::default())
+			.iter()
+			.map(|candidate| candidate.body)
+			.collect();
+		assert_eq!(bodies, vec![3, 2, 1, 0]);
+
+		let candidates = grid.ray_cast(&ray, 2.0, CollisionGroups::default());
+		assert_eq!(candidates.len(), 2);
+		assert!((candidates[1].distance - 1.9).abs() < 1e-5);
+
+		let diagonal = Ray::new(Vector3::new(-3.0, -3.0, 0.0), Vector3::new(1.0, 1.0, 0.0));
+		let bodies: Vec<usize> = grid
+			.ray_cast(&diagonal, 100.0, CollisionGroups::default())
+			.iter()
+			.map(|candidate| candidate.body)
+			.collect();
+		assert_eq!(bodies, vec![1]);
+		assert!(HashGrid::new(1.0)
+			.ray_cast(&ray, Real::INFINITY, CollisionGroups::default())
+			.is_empty());
+	}
+
+	#[test]
+	pub fn update_and_remove() {
+		let mut grid = grid();
+		grid.update(4, Vector3::new(2.9, 0.0, 0.0), 0.4);
+		let pairs: Vec<[usize; 2]> = grid.potential_contacts().iter().map(|contact| contact.bodies).collect();
+		assert_eq!(pairs, vec![[0, 1], [1, 2], [3, 4]]);
+
+		assert!(grid.remove(1));
+		assert!(!grid.remove(1));
+		let pairs: Vec<[usize; 2]> = grid.potential_contacts().iter().map(|contact| contact.bodies).collect();
+		assert_eq!(pairs, vec![[3, 4]]);
+
+		grid.clear();
+		assert!(grid.is_empty());
+	}
+
+	#[cfg(feature = "rayon")]
+	#[test]
+	pub fn parallel_pairs_match_serial_pairs() {
+		let mut grid = HashGrid::new(1.0);
+		for body in 0..500 {
+			let position = crate::real_from_count(body);
+			let center = Vector3::new((position * 0.37).sin() * 10.0, (position * 0.11).cos() * 10.0, 0.0);
+			grid.insert(body, center, 0.5);
+		}
+		let serial = grid.potential_contacts();
+		assert!(!serial.is_empty());
+		assert_eq!(grid.par_potential_contacts(), serial);
+	}
+}
@@ -0,0 +1,223 @@
+use crate::{body::BodyKind, body_set::BodySet, Real};
+
+/// A set of dynamic bodies connected to each other, directly or through
+/// other bodies, by contacts or joints.
+///
+/// Nothing done to one island can affect another within a step, so each can
+/// be solved on its own, and an island whose bodies have all settled can
+/// sleep as one. Static and kinematic bodies do not join islands, as nothing
+/// passes through them: two boxes resting on the same floor are in separate islands.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Island {
+	/// Holds the indices of the bodies, in ascending order.
+	pub bodies: Vec<usize>,
+
+	/// Holds the indices of the links between the bodies, in the order they were given.
+	pub links: Vec<usize>,
+}
+
+impl Island {
+	/// Returns whether every body in the island is asleep.
+	#[must_use]
+	pub fn is_sleeping(&self, bodies: &BodySet) -> bool {
+		self.bodies.iter().all(|body| bodies.flags()[*body].sleeping)
+	}
+
+	/// Puts every body in the island to sleep if all of them have settled
+	/// below the given sleep epsilon, or wakes them all otherwise, so that
+	/// the island sleeps and wakes as one.
+	///
+	/// A body that is about to fall asleep while another it rests on is
+	/// still moving would otherwise be left hanging where it is.
+	pub fn update_sleep(&self, bodies: &mut BodySet, sleep_epsilon: Real) {
+		let settled = self.bodies.iter().all(|body| {
+			let flags = bodies.flags()[*body];
+			flags.can_sleep && (flags.sleeping || bodies.motions()[*body] < sleep_epsilon)
+		});
+		for body in &self.bodies {
+			if bodies.flags()[*body].sleeping != settled {
+				bodies.set_awake(*body, !settled);
+			}
+		}
+	}
+}
+
+/// Groups the dynamic bodies into islands by the links between them,
+/// ordered by their lowest body.
+///
+/// Each link names the bodies it connects, with `None` for the scenery. A
+/// link joins the island of each dynamic body it names, and links naming no
+/// dynamic body are left out. A dynamic body with no links is an island of its own.
+#[must_use]
+pub fn find_islands(bodies: &BodySet, links: &[[Option<usize>; 2]]) -> Vec<Island> {
+	let mut islands = IslandSet::default();
+	islands.find(bodies, links);
+	islands.islands.truncate(islands.len);
+	islands.islands
+}
+
+/// The islands found by [`find_islands`], kept along with the storage used
+/// to find them so that they can be found again step after step without
+/// allocating, once it has grown to fit the scene.
+///
+/// The storage of islands no longer found is kept for those found later.
+#[derive(Debug, Default, Clone)]
+pub struct IslandSet {
+	/// Holds the islands found, followed by the storage of those no longer found.
+	islands: Vec<Island>,
+
+	/// Holds the number of islands found.
+	len: usize,
+
+	/// Holds the body each body was joined to, while the islands are found.
+	parents: Vec<usize>,
+
+	/// Holds the island of each body, while the islands are found.
+	island_of: Vec<Option<usize>>,
+}
+
+impl IslandSet {
+	/// Replaces the islands with those the links group the bodies into, as
+	/// [`find_islands`] does.
+	pub fn find(&mut self, bodies: &BodySet, links: &[[Option<usize>; 2]]) {
+		let flags = bodies.flags();
+		let dynamic = |body: &Option<usize>| body.filter(|body| flags[*body].kind == BodyKind::Dynamic);
+		let parents = &mut self.parents;
+		parents.clear();
+		parents.extend(0..bodies.len());
+		for link in links {
+			if let [Some(first), Some(second)] = link.map(|body| dynamic(&body)) {
+				let (first, second) = (root(parents, first), root(parents, second));
+				parents[first.max(second)] = first.min(second);
+			}
+		}
+
+		// Each root is its island's lowest body, so islands are found in order
+		self.island_of.clear();
+		self.island_of.resize(bodies.len(), None);
+		self.len = 0;
+		for body in (0..bodies.len()).filter(|body| flags[*body].kind == BodyKind::Dynamic) {
+			let root = root(parents, body);
+			let island = *self.island_of[root].get_or_insert_with(|| {
+				if self.islands.len() == self.len {
+					self.islands.push(Island::default());
+				}
+				let island = &mut self.islands[self.len];
+				island.bodies.clear();
+				island.links.clear();
+				self.len += 1;
+				self.len - 1
+			});
+			self.island_of[body] = Some(island);
+			self.islands[island].bodies.push(body);
+		}
+		for (index, link) in links.iter().enumerate() {
+			if let Some(body) = link.iter().find_map(&dynamic) {
+				if let Some(island) = self.island_of[body] {
+					self.islands[island].links.push(index);
+				}
+			}
+		}
+	}
+
+	/// Returns the islands found, ordered by their lowest body.
+	#[must_use]
+	pub fn islands(&self) -> &[Island] {
+		&self.islands[..self.len]
+	}
+
+	/// Returns the number of bodies and links the storage can hold, across
+	/// every island and the lists used to find them.
+	#[must_use]
+	pub fn capacity(&self) -> usize {
+		self.islands
+			.iter()
+			.map(|island| island.bodies.capacity() + island.links.capacity())
+			.sum::<usize>()
+			+ self.islands.capacity()
+			+ self.parents.capacity()
+			+ self.island_of.capacity()
+	}
+}
+
+/// Returns the root of the set holding the body, halving the path to it on the way.
+fn root(parents: &mut [usize], mut body: usize) -> usize {
+	while parents[body] != body {
+		parents[body] = parents[parents[body]];
+		body = parents[body];
+	}
+	body
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		body::{RigidBodyBuilder, DEFAULT_SLEEP_EPSILON},
+		shape::Shape,
+	};
+
+	use super::*;
+
+	fn bodies(kinds: &[BodyKind]) -> BodySet {
+		kinds
+			.iter()
+			.map(|kind| RigidBodyBuilder::new(Shape::default()).kind(*kind).build())
+			.collect()
+	}
+
+	#[test]
+	pub fn links_join_bodies_into_islands() {
+		use BodyKind::{Dynamic, Static};
+		let bodies = bodies(&[Dynamic, Dynamic, Static, Dynamic, Dynamic, Dynamic]);
+		let links = [
+			[Some(3), Some(1)],
+			[Some(0), Some(2)],
+			[Some(4), Some(2)],
+			[Some(1), None],
+			[Some(2), None],
+		];
+		let islands = find_islands(&bodies, &links);
+
+		// The static body does not join the boxes resting on it
+		assert_eq!(islands.len(), 4);
+		assert_eq!(islands[0].bodies, [0]);
+		assert_eq!(islands[0].links, [1]);
+		assert_eq!(islands[1].bodies, [1, 3]);
+		assert_eq!(islands[1].links, [0, 3]);
+		assert_eq!(islands[2].bodies, [4]);
+		assert_eq!(islands[3].bodies, [5]);
+		assert!(islands[3].links.is_empty());
+	}
+
+	#[test]
+	pub fn island_sets_reuse_their_storage() {
+		let bodies = bodies(&[BodyKind::Dynamic; 4]);
+		let (joined, apart) = ([[Some(0), Some(1)], [Some(2), Some(3)]], [[None; 2]; 2]);
+		let mut islands = IslandSet::default();
+		islands.find(&bodies, &joined);
+		islands.find(&bodies, &apart);
+		assert_eq!(islands.islands().len(), 4);
+		let capacity = islands.capacity();
+		for links in [joined, apart, joined] {
+			islands.find(&bodies, &links);
+			assert_eq!(islands.capacity(), capacity);
+		}
+		assert_eq!(islands.islands(), find_islands(&bodies, &joined));
+	}
+
+	#[test]
+	pub fn islands_sleep_as_one() {
+		let mut bodies = bodies(&[BodyKind::Dynamic, BodyKind::Dynamic]);
+		let island = find_islands(&bodies, &[[Some(0), Some(1)]]).remove(0);
+		bodies.set_awake(0, false);
+		bodies.update(1, |body| body.motion = DEFAULT_SLEEP_EPSILON * 5.0);
+		island.update_sleep(&mut bodies, DEFAULT_SLEEP_EPSILON);
+		assert!(!bodies.flags()[0].sleeping);
+		assert!(!island.is_sleeping(&bodies));
+
+		bodies.update(0, |body| body.motion = 0.0);
+		bodies.update(1, |body| body.motion = 0.0);
+		island.update_sleep(&mut bodies, DEFAULT_SLEEP_EPSILON);
+		assert!(island.is_sleeping(&bodies));
+	}
+}
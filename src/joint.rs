@@ -0,0 +1,1328 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::{body::RigidBody, cloth::SpringStiffness, portable::atan2, vec::Vector3, Real};
+
+/// One degree of freedom a joint removes.
+///
+/// A row is a velocity of the first body relative to the second, measured
+/// along linear and angular directions, that the solver drives to a target
+/// with an accumulated impulse. The measured velocity is the first body's
+/// linear velocity along `linear`, less the second's, plus each body's
+/// angular velocity along its own `angular` direction. Applying an impulse
+/// along the row pushes the bodies the way that raises it.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct JointRow {
+	/// Holds the bodies the row acts on, with `None` for the scenery.
+	pub bodies: [Option<usize>; 2],
+
+	/// Holds the direction the linear velocities are measured along.
+	pub linear: Vector3,
+
+	/// Holds the direction each body's angular velocity is measured along.
+	pub angular: [Vector3; 2],
+
+	/// Holds the impulse that changes the measured velocity by one.
+	pub mass: Real,
+
+	/// Holds the velocity the row is driven to.
+	pub target_velocity: Real,
+
+	/// Holds the least and the most impulse the row may accumulate.
+	pub bounds: [Real; 2],
+
+	/// Holds how far the row gives under the impulse it has accumulated,
+	/// in velocity per unit of impulse, or zero for a rigid row.
+	pub softness: Real,
+
+	/// Holds the impulse accumulated so far.
+	pub impulse: Real,
+}
+
+impl JointRow {
+	/// Creates a row holding together two points, at the given offsets from
+	/// the bodies' centers, along the given direction.
+	#[must_use]
+	pub fn linear(bodies: [Option<usize>; 2], offsets: [Vector3; 2], direction: Vector3) -> Self {
+		Self {
+			bodies,
+			linear: direction,
+			angular: [offsets[0].cross(&direction), -offsets[1].cross(&direction)],
+			bounds: [Real::NEG_INFINITY, Real::INFINITY],
+			..Default::default()
+		}
+	}
+
+	/// Creates a row stopping the bodies turning relative to each other about the given axis.
+	#[must_use]
+	pub fn angular(bodies: [Option<usize>; 2], axis: Vector3) -> Self {
+		Self {
+			bodies,
+			linear: Vector3::zero(),
+			angular: [axis, -axis],
+			bounds: [Real::NEG_INFINITY, Real::INFINITY],
+			..Default::default()
+		}
+	}
+
+	/// Finds the row's mass from the bodies, and sets it to remove the
+	/// given fraction of its position error each second.
+	#[must_use]
+	pub fn prepare(mut self, bodies: &[RigidBody], error: Real, correction_rate: Real) -> Self {
+		let inverse = self.inverse_mass(bodies);
+		self.mass = if inverse > 0.0 { inverse.recip() } else { 0.0 };
+		self.target_velocity = -correction_rate * error;
+		self
+	}
+
+	/// Finds the row's mass from the bodies, and softens it into a spring
+	/// and damper pulling its position error back to zero.
+	///
+	/// The spring is solved implicitly, as the impulse that leaves the row
+	/// where the spring would be at the end of the step, so it stays stable
+	/// however stiff it is. A spring with no stiffness and no damping leaves
+	/// the row slack.
+	#[must_use]
+	pub fn soften(mut self, bodies: &[RigidBody], error: Real, stiffness: SpringStiffness, duration: Real) -> Self {
+		let give = duration * duration.mul_add(stiffness.spring_constant, stiffness.damping);
+		if give <= 0.0 {
+			self.bounds = [0.0, 0.0];
+			return self;
+		}
+		self.softness = give.recip();
+		self.mass = (self.inverse_mass(bodies) + self.softness).recip();
+		self.target_velocity = -error * duration * stiffness.spring_constant * self.softness;
+		self
+	}
+
+	/// Returns the change in the measured velocity made by an impulse of one.
+	fn inverse_mass(&self, bodies: &[RigidBody]) -> Real {
+		[0, 1]
+			.into_iter()
+			.filter_map(|side| self.bodies[side].map(|body| (side, &bodies[body])))
+			.map(|(side, body)| {
+				let angular = self.angular[side];
+				body.effective_inverse_mass().mul_add(
+					self.linear.magnitude_squared(),
+					(body.effective_inverse_inertia_tensor_world() * angular).dot(&angular),
+				)
+			})
+			.sum()
+	}
+
+	/// Returns the velocity the row measures.
+	#[must_use]
+	pub fn velocity(&self, bodies: &[RigidBody]) -> Real {
+		let mut velocity = 0.0;
+		for (side, sign) in [(0, 1.0), (1, -1.0)] {
+			if let Some(body) = self.bodies[side] {
+				let body = &bodies[body];
+				velocity += body
+					.velocity
+					.dot(&self.linear)
+					.mul_add(sign, body.angular_velocity.dot(&self.angular[side]));
+			}
+		}
+		velocity
+	}
+
+	/// Applies the impulse that brings the measured velocity to the
+	/// target, with the accumulated impulse held within the bounds.
+	pub fn solve(&mut self, bodies: &mut [RigidBody]) {
+		let impulse = self.mass
+			* self
+				.softness
+				.mul_add(-self.impulse, self.target_velocity - self.velocity(bodies));
+		let accumulated = (self.impulse + impulse).clamp(self.bounds[0], self.bounds[1]);
+		let change = accumulated - self.impulse;
+		self.impulse = accumulated;
+		self.apply_impulse(bodies, change);
+	}
+
+	/// Applies the given impulse along the row.
+	pub fn apply_impulse(&self, bodies: &mut [RigidBody], impulse: Real) {
+		for (side, sign) in [(0, 1.0), (1, -1.0)] {
+			if let Some(body) = self.bodies[side] {
+				let body = &mut bodies[body];
+				body.velocity += self.linear * (sign * impulse * body.effective_inverse_mass());
+				body.angular_velocity += body.effective_inverse_inertia_tensor_world() * (self.angular[side] * impulse);
+			}
+		}
+	}
+}
+
+/// The rows a joint is solved as in one step, held in place rather than
+/// allocated, as no joint has more than seven.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct JointRows {
+	rows: [JointRow; 7],
+	len: usize,
+}
+
+impl JointRows {
+	const fn push(&mut self, row: JointRow) {
+		self.rows[self.len] = row;
+		self.len += 1;
+	}
+}
+
+impl Extend<JointRow> for JointRows {
+	fn extend<I: IntoIterator<Item = JointRow>>(&mut self, rows: I) {
+		for row in rows {
+			self.push(row);
+		}
+	}
+}
+
+impl Deref for JointRows {
+	type Target = [JointRow];
+
+	fn deref(&self) -> &Self::Target {
+		&self.rows[..self.len]
+	}
+}
+
+impl DerefMut for JointRows {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.rows[..self.len]
+	}
+}
+
+/// A joint between two bodies, or a body and the scenery, solved alongside
+/// the contacts by the [`SequentialImpulseSolver`](crate::solver::SequentialImpulseSolver).
+///
+/// Each joint is solved as a set of [`JointRow`]s, one for each degree of
+/// freedom it removes, limits, or drives. Drift is corrected by a bias
+/// velocity, even when the solver corrects contacts with position iterations.
+///
+/// A joint with a strength breaks when it bears more than that strength
+/// in a step. A broken joint is no longer solved, but is left where it is
+/// among the joints, so that the others keep their indices.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct Joint {
+	pub kind: JointKind,
+
+	/// Holds the most the joint can bear before it breaks, or `None` if it never breaks.
+	pub strength: Option<JointStrength>,
+
+	broken: bool,
+}
+
+/// The kinds of joint, each holding its own bodies and anchors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub enum JointKind {
+	Hinge(HingeJoint),
+	Fixed(FixedJoint),
+	Distance(DistanceJoint),
+	Prismatic(PrismaticJoint),
+	Spring(SpringJoint),
+}
+
+/// The most force and torque a joint can bear before it breaks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct JointStrength {
+	pub force: Real,
+	pub torque: Real,
+}
+
+impl JointStrength {
+	#[must_use]
+	pub const fn new(force: Real, torque: Real) -> Self {
+		Self { force, torque }
+	}
+}
+
+impl Joint {
+	#[must_use]
+	pub const fn new(kind: JointKind) -> Self {
+		Self {
+			kind,
+			strength: None,
+			broken: false,
+		}
+	}
+
+	/// Sets the most the joint can bear before it breaks.
+	#[must_use]
+	pub const fn with_strength(mut self, force: Real, torque: Real) -> Self {
+		self.strength = Some(JointStrength::new(force, torque));
+		self
+	}
+
+	/// Returns the bodies joined, with `None` for the scenery.
+	#[must_use]
+	pub const fn bodies(&self) -> [Option<usize>; 2] {
+		match &self.kind {
+			JointKind::Hinge(hinge) => hinge.bodies,
+			JointKind::Fixed(fixed) => fixed.bodies,
+			JointKind::Distance(distance) => distance.bodies,
+			JointKind::Prismatic(prismatic) => prismatic.bodies,
+			JointKind::Spring(spring) => spring.bodies,
+		}
+	}
+
+	/// Breaks the joint and lets go of its bodies, leaving it joining
+	/// nothing, as a [`PhysicsWorld`](crate::world::PhysicsWorld) leaves the
+	/// joints of a body it removes.
+	pub const fn detach(&mut self) {
+		self.broken = true;
+		match &mut self.kind {
+			JointKind::Hinge(hinge) => hinge.bodies = [None; 2],
+			JointKind::Fixed(fixed) => fixed.bodies = [None; 2],
+			JointKind::Distance(distance) => distance.bodies = [None; 2],
+			JointKind::Prismatic(prismatic) => prismatic.bodies = [None; 2],
+			JointKind::Spring(spring) => spring.bodies = [None; 2],
+		}
+	}
+
+	/// Returns the anchors in each body's own space, or in world space for the scenery.
+	#[must_use]
+	pub const fn anchors(&self) -> [Vector3; 2] {
+		match &self.kind {
+			JointKind::Hinge(hinge) => hinge.anchors,
+			JointKind::Fixed(fixed) => fixed.anchors,
+			JointKind::Distance(distance) => distance.anchors,
+			JointKind::Prismatic(prismatic) => prismatic.anchors,
+			JointKind::Spring(spring) => spring.anchors,
+		}
+	}
+
+	/// Returns the anchors in world space, as the bodies are now.
+	#[must_use]
+	pub fn world_anchors(&self, bodies: &[RigidBody]) -> [Vector3; 2] {
+		JointAnchors::new(self.bodies(), bodies, |body| body).points(self.anchors())
+	}
+
+	/// Returns whether the joint has broken.
+	#[must_use]
+	pub const fn is_broken(&self) -> bool {
+		self.broken
+	}
+
+	/// Returns the joint's rows, prepared from the bodies, which are found
+	/// in the given slice by the given mapping from their indices. Each row
+	/// corrects the given fraction of its error each step, and starts from
+	/// the impulse it accumulated last step. A broken joint has no rows.
+	#[must_use]
+	pub fn rows(
+		&self,
+		bodies: &[RigidBody],
+		local: impl Fn(usize) -> usize,
+		bias_factor: Real,
+		duration: Real,
+	) -> JointRows {
+		let mut rows = JointRows::default();
+		if self.broken {
+			return rows;
+		}
+		let mut anchors = JointAnchors::new(self.bodies(), bodies, &local);
+		anchors.correction_rate = bias_factor / duration;
+		anchors.duration = duration;
+		let impulses = match &self.kind {
+			JointKind::Hinge(hinge) => {
+				hinge.rows(&anchors, &mut rows);
+				&hinge.impulses[..]
+			},
+			JointKind::Fixed(fixed) => {
+				fixed.rows(&anchors, &mut rows);
+				&fixed.impulses[..]
+			},
+			JointKind::Distance(distance) => {
+				distance.rows(&anchors, &mut rows);
+				&distance.impulses[..]
+			},
+			JointKind::Prismatic(prismatic) => {
+				prismatic.rows(&anchors, &mut rows);
+				&prismatic.impulses[..]
+			},
+			JointKind::Spring(spring) => {
+				spring.rows(&anchors, &mut rows);
+				&spring.impulses[..]
+			},
+		};
+		// A row whose bounds have changed, as a slack rope, keeps only what they allow
+		for (row, impulse) in rows.iter_mut().zip(impulses) {
+			row.impulse = impulse.clamp(row.bounds[0], row.bounds[1]);
+		}
+		rows
+	}
+
+	/// Keeps the impulses the rows accumulated over a step of the given
+	/// duration, to start from next step, and breaks the joint if they were
+	/// more than it can bear.
+	pub fn store_impulses(&mut self, rows: &[JointRow], duration: Real) {
+		let impulses = match &mut self.kind {
+			JointKind::Hinge(hinge) => &mut hinge.impulses[..],
+			JointKind::Fixed(fixed) => &mut fixed.impulses[..],
+			JointKind::Distance(distance) => &mut distance.impulses[..],
+			JointKind::Prismatic(prismatic) => &mut prismatic.impulses[..],
+			JointKind::Spring(spring) => &mut spring.impulses[..],
+		};
+		for (impulse, row) in impulses.iter_mut().zip(rows) {
+			*impulse = row.impulse;
+		}
+		let Some(strength) = self.strength else {
+			return;
+		};
+		// Rows that push the bodies bear force, and rows that only turn them bear torque
+		let [linear, angular] = rows.iter().fold([0.0; 2], |[linear, angular], row| {
+			let squared = row.impulse * row.impulse;
+			if row.linear == Vector3::zero() {
+				[linear, angular + squared]
+			} else {
+				[linear + squared, angular]
+			}
+		});
+		if linear.sqrt() > strength.force * duration || angular.sqrt() > strength.torque * duration {
+			self.broken = true;
+		}
+	}
+}
+
+impl From<JointKind> for Joint {
+	fn from(kind: JointKind) -> Self {
+		Self::new(kind)
+	}
+}
+
+impl From<HingeJoint> for Joint {
+	fn from(hinge: HingeJoint) -> Self {
+		Self::new(JointKind::Hinge(hinge))
+	}
+}
+
+impl From<FixedJoint> for Joint {
+	fn from(fixed: FixedJoint) -> Self {
+		Self::new(JointKind::Fixed(fixed))
+	}
+}
+
+impl From<DistanceJoint> for Joint {
+	fn from(distance: DistanceJoint) -> Self {
+		Self::new(JointKind::Distance(distance))
+	}
+}
+
+impl From<PrismaticJoint> for Joint {
+	fn from(prismatic: PrismaticJoint) -> Self {
+		Self::new(JointKind::Prismatic(prismatic))
+	}
+}
+
+impl From<SpringJoint> for Joint {
+	fn from(spring: SpringJoint) -> Self {
+		Self::new(JointKind::Spring(spring))
+	}
+}
+
+/// The range a joint's free motion is held within: an angle in radians
+/// for a hinge, or a distance for a prismatic joint.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct JointLimit {
+	pub lower: Real,
+	pub upper: Real,
+}
+
+impl JointLimit {
+	#[must_use]
+	pub const fn new(lower: Real, upper: Real) -> Self {
+		Self { lower, upper }
+	}
+}
+
+/// How a joint is driven along its free motion, by the solver rather than
+/// by forces applied from outside, so that a drive is never overpowered by
+/// the joint it drives.
+///
+/// Speeds are of the first body relative to the second, in radians per
+/// second for a hinge or length per second for a prismatic joint, and the
+/// most force is a torque for a hinge.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub enum JointMotor {
+	/// Drives the joint at the given speed, with at most the given force.
+	Velocity { speed: Real, max_force: Real },
+
+	/// Drives the joint toward the target position, at a speed of the gain
+	/// times the distance left, with at most the given force.
+	Servo { target: Real, gain: Real, max_force: Real },
+}
+
+/// The bodies of a joint as found for one step, able to turn the joint's
+/// local points and directions into world space and to prepare its rows.
+struct JointAnchors<'a> {
+	/// Holds the indices of the bodies in the slice being solved.
+	bodies: [Option<usize>; 2],
+
+	/// Holds the bodies themselves.
+	rigid_bodies: [Option<&'a RigidBody>; 2],
+
+	/// Holds the slice being solved.
+	all: &'a [RigidBody],
+
+	/// Holds the fraction of each row's error corrected each second.
+	correction_rate: Real,
+
+	/// Holds the length of the step.
+	duration: Real,
+}
+
+impl<'a> JointAnchors<'a> {
+	fn new(joined: [Option<usize>; 2], bodies: &'a [RigidBody], local: impl Fn(usize) -> usize) -> Self {
+		let joined = joined.map(|body| body.map(&local));
+		Self {
+			bodies: joined,
+			rigid_bodies: joined.map(|body| body.map(|body| &bodies[body])),
+			all: bodies,
+			correction_rate: 0.0,
+			duration: 0.0,
+		}
+	}
+
+	/// Returns the given points, each local to its body, in world space.
+	fn points(&self, points: [Vector3; 2]) -> [Vector3; 2] {
+		[0, 1].map(|side| self.rigid_bodies[side].map_or(points[side], |body| body.get_point_in_world(points[side])))
+	}
+
+	/// Returns the given directions, each local to its body, in world space.
+	fn directions(&self, directions: [Vector3; 2]) -> [Vector3; 2] {
+		[0, 1].map(|side| {
+			self.rigid_bodies[side].map_or(directions[side], |body| body.get_direction_in_world(directions[side]))
+		})
+	}
+
+	/// Returns the offsets of the given world points from the bodies' centers.
+	fn offsets(&self, points: [Vector3; 2]) -> [Vector3; 2] {
+		[0, 1].map(|side| self.rigid_bodies[side].map_or_else(Vector3::zero, |body| points[side] - body.position))
+	}
+
+	/// Returns how far the first body has turned from where it was held
+	/// relative to the second, given the world axes as they were then in
+	/// each body's own space, as an axis scaled by the angle for small angles.
+	fn rotation(&self, bases: &[[Vector3; 3]; 2]) -> Vector3 {
+		// Each axis turned by a small rotation is crossed into twice the rotation over all three
+		let mut rotation = Vector3::zero();
+		for (first, second) in bases[0].iter().zip(&bases[1]) {
+			let axes = self.directions([*first, *second]);
+			rotation += axes[1].cross(&axes[0]) * 0.5;
+		}
+		rotation
+	}
+
+	/// Prepares the row to correct the given error.
+	fn prepare(&self, row: JointRow, error: Real) -> JointRow {
+		row.prepare(self.all, error, self.correction_rate)
+	}
+
+	/// Returns the three rows holding the given points, each local to its
+	/// body, together.
+	fn point_rows(&self, points: [Vector3; 2]) -> [JointRow; 3] {
+		let points = self.points(points);
+		let offsets = self.offsets(points);
+		let error = points[0] - points[1];
+		[Vector3::x_axis(), Vector3::y_axis(), Vector3::z_axis()]
+			.map(|axis| self.prepare(JointRow::linear(self.bodies, offsets, axis), error.dot(&axis)))
+	}
+
+	/// Returns the three rows holding the bodies' orientations as they were
+	/// when the given bases were taken.
+	fn orientation_rows(&self, bases: &[[Vector3; 3]; 2]) -> [JointRow; 3] {
+		let error = self.rotation(bases);
+		[Vector3::x_axis(), Vector3::y_axis(), Vector3::z_axis()]
+			.map(|axis| self.prepare(JointRow::angular(self.bodies, axis), error.dot(&axis)))
+	}
+
+	/// Returns the row holding the joint, with the given error along it, as the axis says.
+	fn spring_row(&self, row: JointRow, error: Real, axis: SpringAxis) -> JointRow {
+		match axis {
+			SpringAxis::Free => JointRow {
+				bounds: [0.0, 0.0],
+				..self.prepare(row, 0.0)
+			},
+			SpringAxis::Rigid => self.prepare(row, error),
+			SpringAxis::Spring(stiffness) => row.soften(self.all, error, stiffness, self.duration),
+		}
+	}
+
+	/// Returns the row keeping the joint, at the given position along it,
+	/// within the limit. The row only pushes back from the end passed, and
+	/// is slack between the ends or without a limit.
+	fn limit_row(&self, row: JointRow, position: Real, limit: Option<JointLimit>) -> JointRow {
+		let (error, bounds) = match limit {
+			Some(JointLimit { lower, upper }) if lower >= upper => {
+				(position - lower, [Real::NEG_INFINITY, Real::INFINITY])
+			},
+			Some(JointLimit { lower, .. }) if position < lower => (position - lower, [0.0, Real::INFINITY]),
+			Some(JointLimit { upper, .. }) if position > upper => (position - upper, [Real::NEG_INFINITY, 0.0]),
+			_ => (0.0, [0.0, 0.0]),
+		};
+		JointRow {
+			bounds,
+			..self.prepare(row, error)
+		}
+	}
+
+	/// Returns the row driving the joint, at the given position along it,
+	/// by the motor, which is slack without one.
+	fn motor_row(&self, row: JointRow, position: Real, motor: Option<JointMotor>) -> JointRow {
+		let (speed, max_force) = match motor {
+			Some(JointMotor::Velocity { speed, max_force }) => (speed, max_force),
+			Some(JointMotor::Servo {
+				target,
+				gain,
+				max_force,
+			}) => (gain * (target - position), max_force),
+			None => (0.0, 0.0),
+		};
+		let max_impulse = max_force * self.duration;
+		JointRow {
+			target_velocity: speed,
+			bounds: [-max_impulse, max_impulse],
+			..self.prepare(row, 0.0)
+		}
+	}
+}
+
+/// Returns two unit directions perpendicular to the given unit axis and to each other.
+fn perpendiculars(axis: Vector3) -> [Vector3; 2] {
+	// Start from whichever world axis is furthest from the axis
+	let guess = if axis.x().abs() > axis.y().abs() {
+		Vector3::y_axis()
+	} else {
+		Vector3::x_axis()
+	};
+	let second = axis.cross(&guess).normalize();
+	[second.cross(&axis), second]
+}
+
+/// Returns the world axes in the body's own space, or as they are for the scenery.
+fn local_basis(body: Option<&RigidBody>) -> [Vector3; 3] {
+	[Vector3::x_axis(), Vector3::y_axis(), Vector3::z_axis()]
+		.map(|axis| body.map_or(axis, |body| body.get_direction_in_local(axis)))
+}
+
+/// A joint that lets two bodies turn relative to each other about a single
+/// axis through a shared anchor, as a door turns on its hinges, a wheel on
+/// its axle, or a pendulum about its pivot.
+///
+/// The angle turned can be limited, and driven by a motor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct HingeJoint {
+	/// Holds the bodies joined, with `None` for the scenery.
+	pub bodies: [Option<usize>; 2],
+
+	/// Holds the anchor in each body's own space, or in world space for the scenery.
+	pub anchors: [Vector3; 2],
+
+	/// Holds the unit axis in each body's own space, or in world space for the scenery.
+	pub axes: [Vector3; 2],
+
+	/// Holds a unit direction across the axis in each body's own space, or
+	/// in world space for the scenery, which line up at an angle of zero.
+	pub references: [Vector3; 2],
+
+	/// Holds the range of angles the first body may turn to relative to the second.
+	pub limit: Option<JointLimit>,
+
+	pub motor: Option<JointMotor>,
+
+	/// Holds the impulse each row accumulated last step.
+	impulses: [Real; 7],
+}
+
+impl HingeJoint {
+	/// Creates a hinge joining the bodies at the given anchor, turning about
+	/// the given axis, both in world space and taken from the bodies as they
+	/// are now, which is an angle of zero.
+	#[must_use]
+	pub fn new(joined: [Option<usize>; 2], bodies: &[RigidBody], anchor: Vector3, axis: Vector3) -> Self {
+		let axis = axis.normalize();
+		let reference = perpendiculars(axis)[0];
+		let local = |side: usize| joined[side].map(|body| &bodies[body]);
+		Self {
+			bodies: joined,
+			anchors: [0, 1].map(|side| local(side).map_or(anchor, |body| body.get_point_in_local(anchor))),
+			axes: [0, 1].map(|side| local(side).map_or(axis, |body| body.get_direction_in_local(axis))),
+			references: [0, 1].map(|side| local(side).map_or(reference, |body| body.get_direction_in_local(reference))),
+			limit: None,
+			motor: None,
+			impulses: [0.0; 7],
+		}
+	}
+
+	/// Sets the range of angles, in radians, the hinge may turn through.
+	#[must_use]
+	pub const fn with_limit(mut self, lower: Real, upper: Real) -> Self {
+		self.limit = Some(JointLimit::new(lower, upper));
+		self
+	}
+
+	#[must_use]
+	pub const fn with_motor(mut self, motor: JointMotor) -> Self {
+		self.motor = Some(motor);
+		self
+	}
+
+	/// Returns the angle, in radians, the first body has turned about the
+	/// axis relative to the second since the hinge was made.
+	#[must_use]
+	pub fn angle(&self, bodies: &[RigidBody]) -> Real {
+		self.angle_from(&JointAnchors::new(self.bodies, bodies, |body| body))
+	}
+
+	/// Returns the world-space angle the second body's axis strays from the first's.
+	#[must_use]
+	pub fn misalignment(&self, bodies: &[RigidBody]) -> Real {
+		let axes = JointAnchors::new(self.bodies, bodies, |body| body).directions(self.axes);
+		atan2(axes[0].cross(&axes[1]).magnitude(), axes[0].dot(&axes[1]))
+	}
+
+	/// Returns the world-space distance between the anchors.
+	#[must_use]
+	pub fn separation(&self, bodies: &[RigidBody]) -> Real {
+		let points = JointAnchors::new(self.bodies, bodies, |body| body).points(self.anchors);
+		(points[0] - points[1]).magnitude()
+	}
+
+	fn angle_from(&self, anchors: &JointAnchors) -> Real {
+		let axis = anchors.directions(self.axes)[0];
+		let references = anchors.directions(self.references);
+		atan2(
+			axis.dot(&references[1].cross(&references[0])),
+			references[1].dot(&references[0]),
+		)
+	}
+
+	fn rows(&self, anchors: &JointAnchors, rows: &mut JointRows) {
+		let axes = anchors.directions(self.axes);
+		let error = axes[1].cross(&axes[0]);
+		rows.extend(anchors.point_rows(self.anchors));
+		rows.extend(
+			perpendiculars(axes[0])
+				.map(|axis| anchors.prepare(JointRow::angular(anchors.bodies, axis), error.dot(&axis))),
+		);
+
+		// The limit comes after the motor, so it has the last word
+		let angle = self.angle_from(anchors);
+		let turn = JointRow::angular(anchors.bodies, axes[0]);
+		rows.push(anchors.motor_row(turn, angle, self.motor));
+		rows.push(anchors.limit_row(turn, angle, self.limit));
+	}
+}
+
+/// A joint that locks two bodies' relative position and orientation
+/// together, as if welded, so that debris can be glued together and
+/// composite structures built from parts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct FixedJoint {
+	/// Holds the bodies joined, with `None` for the scenery.
+	pub bodies: [Option<usize>; 2],
+
+	/// Holds the anchor in each body's own space, or in world space for the scenery.
+	pub anchors: [Vector3; 2],
+
+	/// Holds the world axes as they were when the joint was made, in each
+	/// body's own space, or in world space for the scenery.
+	pub bases: [[Vector3; 3]; 2],
+
+	/// Holds the impulse each row accumulated last step.
+	impulses: [Real; 6],
+}
+
+impl FixedJoint {
+	/// Creates a joint holding the bodies as they are now, anchored at the given world point.
+	///
+	/// The anchor does not change how the bodies are held, but the joint is
+	/// stiffest with it between them.
+	#[must_use]
+	pub fn new(joined: [Option<usize>; 2], bodies: &[RigidBody], anchor: Vector3) -> Self {
+		let local = |side: usize| joined[side].map(|body| &bodies[body]);
+		Self {
+			bodies: joined,
+			anchors: [0, 1].map(|side| local(side).map_or(anchor, |body| body.get_point_in_local(anchor))),
+			bases: [0, 1].map(|side| local_basis(local(side))),
+			impulses: [0.0; 6],
+		}
+	}
+
+	/// Returns the world-space distance between the anchors.
+	#[must_use]
+	pub fn separation(&self, bodies: &[RigidBody]) -> Real {
+		let points = JointAnchors::new(self.bodies, bodies, |body| body).points(self.anchors);
+		(points[0] - points[1]).magnitude()
+	}
+
+	/// Returns how far the first body has turned from where it was held
+	/// relative to the second, as an axis scaled by the angle in radians, for small angles.
+	#[must_use]
+	pub fn rotation(&self, bodies: &[RigidBody]) -> Vector3 {
+		JointAnchors::new(self.bodies, bodies, |body| body).rotation(&self.bases)
+	}
+
+	fn rows(&self, anchors: &JointAnchors, rows: &mut JointRows) {
+		rows.extend(anchors.point_rows(self.anchors));
+		rows.extend(anchors.orientation_rows(&self.bases));
+	}
+}
+
+/// A joint that keeps two anchors at a fixed distance apart, or within a range of distances.
+///
+/// This is the [`ParticleRod`](crate::links::ParticleRod) of bodies, or
+/// with a range the [`ParticleCable`](crate::links::ParticleCable), with a
+/// least length as well as a most. The bodies are free to turn about the
+/// anchors, so a body hung from the scenery by a distance joint swings as a
+/// pendulum, and two joined by one with no least length are towed as if by a rope.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct DistanceJoint {
+	/// Holds the bodies joined, with `None` for the scenery.
+	pub bodies: [Option<usize>; 2],
+
+	/// Holds the anchor in each body's own space, or in world space for the scenery.
+	pub anchors: [Vector3; 2],
+
+	/// Holds the least distance allowed between the anchors.
+	pub min_length: Real,
+
+	/// Holds the most distance allowed between the anchors.
+	pub max_length: Real,
+
+	/// Holds the impulse the row accumulated last step.
+	impulses: [Real; 1],
+}
+
+impl DistanceJoint {
+	/// Creates a joint keeping the given world anchors at the distance they are apart now.
+	#[must_use]
+	pub fn new(joined: [Option<usize>; 2], bodies: &[RigidBody], anchors: [Vector3; 2]) -> Self {
+		let length = (anchors[0] - anchors[1]).magnitude();
+		Self {
+			bodies: joined,
+			anchors: [0, 1]
+				.map(|side| joined[side].map_or(anchors[side], |body| bodies[body].get_point_in_local(anchors[side]))),
+			min_length: length,
+			max_length: length,
+			impulses: [0.0],
+		}
+	}
+
+	/// Sets the range of distances the anchors are allowed apart.
+	#[must_use]
+	pub const fn with_range(mut self, min_length: Real, max_length: Real) -> Self {
+		self.min_length = min_length;
+		self.max_length = max_length;
+		self
+	}
+
+	/// Returns the world-space distance between the anchors.
+	#[must_use]
+	pub fn length(&self, bodies: &[RigidBody]) -> Real {
+		let points = JointAnchors::new(self.bodies, bodies, |body| body).points(self.anchors);
+		(points[0] - points[1]).magnitude()
+	}
+
+	fn rows(&self, anchors: &JointAnchors, rows: &mut JointRows) {
+		let points = anchors.points(self.anchors);
+		let separation = points[0] - points[1];
+		let length = separation.magnitude();
+		// Anchors on top of each other are pushed apart along an arbitrary axis
+		let direction = if length > 0.0 {
+			separation * length.recip()
+		} else {
+			Vector3::y_axis()
+		};
+		let row = JointRow::linear(anchors.bodies, anchors.offsets(points), direction);
+		let range = JointLimit::new(self.min_length, self.max_length);
+		rows.push(anchors.limit_row(row, length, Some(range)));
+	}
+}
+
+/// A joint that lets two bodies slide relative to each other along a
+/// single axis without turning, as a piston slides in its cylinder or a
+/// drawer on its runners.
+///
+/// The distance slid can be limited, and driven by a motor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct PrismaticJoint {
+	/// Holds the bodies joined, with `None` for the scenery.
+	pub bodies: [Option<usize>; 2],
+
+	/// Holds the anchor in each body's own space, or in world space for the
+	/// scenery, which meet at a translation of zero.
+	pub anchors: [Vector3; 2],
+
+	/// Holds the unit axis in the first body's own space, or in world space for the scenery.
+	pub axis: Vector3,
+
+	/// Holds the world axes as they were when the joint was made, in each
+	/// body's own space, or in world space for the scenery.
+	pub bases: [[Vector3; 3]; 2],
+
+	/// Holds the range of distances the first body may slide to along the axis relative to the second.
+	pub limit: Option<JointLimit>,
+
+	pub motor: Option<JointMotor>,
+
+	/// Holds the impulse each row accumulated last step.
+	impulses: [Real; 7],
+}
+
+impl PrismaticJoint {
+	/// Creates a joint letting the bodies slide along the given axis from
+	/// the given anchor, both in world space and taken from the bodies as
+	/// they are now, which is a translation of zero.
+	#[must_use]
+	pub fn new(joined: [Option<usize>; 2], bodies: &[RigidBody], anchor: Vector3, axis: Vector3) -> Self {
+		let axis = axis.normalize();
+		let local = |side: usize| joined[side].map(|body| &bodies[body]);
+		Self {
+			bodies: joined,
+			anchors: [0, 1].map(|side| local(side).map_or(anchor, |body| body.get_point_in_local(anchor))),
+			axis: local(0).map_or(axis, |body| body.get_direction_in_local(axis)),
+			bases: [0, 1].map(|side| local_basis(local(side))),
+			limit: None,
+			motor: None,
+			impulses: [0.0; 7],
+		}
+	}
+
+	/// Sets the range of distances the joint may slide through.
+	#[must_use]
+	pub const fn with_limit(mut self, lower: Real, upper: Real) -> Self {
+		self.limit = Some(JointLimit::new(lower, upper));
+		self
+	}
+
+	#[must_use]
+	pub const fn with_motor(mut self, motor: JointMotor) -> Self {
+		self.motor = Some(motor);
+		self
+	}
+
+	/// Returns the distance the first body has slid along the axis relative
+	/// to the second since the joint was made.
+	#[must_use]
+	pub fn translation(&self, bodies: &[RigidBody]) -> Real {
+		let anchors = JointAnchors::new(self.bodies, bodies, |body| body);
+		let points = anchors.points(self.anchors);
+		(points[0] - points[1]).dot(&anchors.directions([self.axis; 2])[0])
+	}
+
+	/// Returns the world-space distance between the anchors across the axis.
+	#[must_use]
+	pub fn drift(&self, bodies: &[RigidBody]) -> Real {
+		let anchors = JointAnchors::new(self.bodies, bodies, |body| body);
+		let points = anchors.points(self.anchors);
+		let axis = anchors.directions([self.axis; 2])[0];
+		let separation = points[0] - points[1];
+		(separation - axis * separation.dot(&axis)).magnitude()
+	}
+
+	fn rows(&self, anchors: &JointAnchors, rows: &mut JointRows) {
+		let points = anchors.points(self.anchors);
+		let axis = anchors.directions([self.axis; 2])[0];
+		let separation = points[0] - points[1];
+
+		// Both bodies are pushed at the first anchor, where the second's point on the axis would be
+		let offsets = anchors.offsets([points[0]; 2]);
+		rows.extend(perpendiculars(axis).map(|across| {
+			anchors.prepare(
+				JointRow::linear(anchors.bodies, offsets, across),
+				separation.dot(&across),
+			)
+		}));
+		rows.extend(anchors.orientation_rows(&self.bases));
+		let translation = separation.dot(&axis);
+		let slide = JointRow::linear(anchors.bodies, offsets, axis);
+		rows.push(anchors.motor_row(slide, translation, self.motor));
+		rows.push(anchors.limit_row(slide, translation, self.limit));
+	}
+}
+
+/// How a [`SpringJoint`] holds the bodies along or about one axis.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub enum SpringAxis {
+	/// The bodies move freely.
+	Free,
+
+	/// The bodies are held rigidly.
+	#[default]
+	Rigid,
+
+	/// The bodies are pulled back by a spring and damper.
+	Spring(SpringStiffness),
+}
+
+/// A soft joint holding two bodies together by a spring and damper along
+/// and about each axis, as a car body rides on its suspension or an
+/// antenna sways on its base.
+///
+/// Each axis is taken from the world axes as they were when the joint was
+/// made, turning with the first body, and can be free, rigid, or sprung on
+/// its own. With every axis rigid this is a [`FixedJoint`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct SpringJoint {
+	/// Holds the bodies joined, with `None` for the scenery.
+	pub bodies: [Option<usize>; 2],
+
+	/// Holds the anchor in each body's own space, or in world space for the scenery.
+	pub anchors: [Vector3; 2],
+
+	/// Holds the world axes as they were when the joint was made, in each
+	/// body's own space, or in world space for the scenery.
+	pub bases: [[Vector3; 3]; 2],
+
+	/// Holds how the anchors are held together along each axis.
+	pub linear: [SpringAxis; 3],
+
+	/// Holds how the bodies are held from turning about each axis.
+	pub angular: [SpringAxis; 3],
+
+	/// Holds the impulse each row accumulated last step.
+	impulses: [Real; 6],
+}
+
+impl SpringJoint {
+	/// Creates a joint holding the bodies as they are now, anchored at the
+	/// given world point, rigid along and about every axis until sprung.
+	#[must_use]
+	pub fn new(joined: [Option<usize>; 2], bodies: &[RigidBody], anchor: Vector3) -> Self {
+		let local = |side: usize| joined[side].map(|body| &bodies[body]);
+		Self {
+			bodies: joined,
+			anchors: [0, 1].map(|side| local(side).map_or(anchor, |body| body.get_point_in_local(anchor))),
+			bases: [0, 1].map(|side| local_basis(local(side))),
+			linear: [SpringAxis::Rigid; 3],
+			angular: [SpringAxis::Rigid; 3],
+			impulses: [0.0; 6],
+		}
+	}
+
+	/// Sets how the anchors are held together along each axis.
+	#[must_use]
+	pub const fn with_linear(mut self, linear: [SpringAxis; 3]) -> Self {
+		self.linear = linear;
+		self
+	}
+
+	/// Sets how the bodies are held from turning about each axis.
+	#[must_use]
+	pub const fn with_angular(mut self, angular: [SpringAxis; 3]) -> Self {
+		self.angular = angular;
+		self
+	}
+
+	/// Returns how far the first body's anchor is from the second's, in world space.
+	#[must_use]
+	pub fn offset(&self, bodies: &[RigidBody]) -> Vector3 {
+		let points = JointAnchors::new(self.bodies, bodies, |body| body).points(self.anchors);
+		points[0] - points[1]
+	}
+
+	/// Returns how far the first body has turned from where it was held
+	/// relative to the second, as an axis scaled by the angle in radians, for small angles.
+	#[must_use]
+	pub fn rotation(&self, bodies: &[RigidBody]) -> Vector3 {
+		JointAnchors::new(self.bodies, bodies, |body| body).rotation(&self.bases)
+	}
+
+	fn rows(&self, anchors: &JointAnchors, rows: &mut JointRows) {
+		let points = anchors.points(self.anchors);
+		let offsets = anchors.offsets(points);
+		let separation = points[0] - points[1];
+		let rotation = anchors.rotation(&self.bases);
+		let axes = self.bases[0].map(|axis| anchors.directions([axis; 2])[0]);
+		let linear = (0..3).map(|axis| {
+			let row = JointRow::linear(anchors.bodies, offsets, axes[axis]);
+			anchors.spring_row(row, separation.dot(&axes[axis]), self.linear[axis])
+		});
+		let angular = (0..3).map(|axis| {
+			let row = JointRow::angular(anchors.bodies, axes[axis]);
+			anchors.spring_row(row, rotation.dot(&axes[axis]), self.angular[axis])
+		});
+		rows.extend(linear.chain(angular));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		body::{BodyKind, RigidBodyBuilder},
+		cloth::SpringStiffness,
+		manifold::ManifoldCache,
+		shape::Shape,
+		solver::SequentialImpulseSolver,
+	};
+
+	use super::*;
+
+	const STEP: Real = 1.0 / 60.0;
+
+	fn bar(position: Vector3) -> RigidBody {
+		RigidBodyBuilder::new(Shape::Cuboid {
+			half_size: Vector3::new(0.5, 0.1, 0.1),
+		})
+		.position(position)
+		.acceleration(Vector3::new(0.0, -10.0, 0.0))
+		.can_sleep(false)
+		.build()
+	}
+
+	fn simulate(bodies: &mut [RigidBody], joints: &mut [Joint], steps: usize) {
+		let solver = SequentialImpulseSolver::default();
+		let mut manifolds = ManifoldCache::default();
+		for _ in 0..steps {
+			for body in bodies.iter_mut() {
+				body.integrate(STEP);
+			}
+			solver.solve(&mut manifolds, joints, bodies, STEP);
+		}
+	}
+
+	#[test]
+	pub fn pendulum_swings_about_its_pivot() {
+		// A bar hinged to the scenery at one end, about the z axis, starting level.
+		// The bias velocity leaves the anchors apart by about a dt^2 / bias as the bar accelerates
+		let mut bodies = [bar(Vector3::new(0.5, 0.0, 0.0))];
+		let hinge = HingeJoint::new([Some(0), None], &bodies, Vector3::zero(), Vector3::z_axis());
+		let mut joints = [hinge.into()];
+		let mut lowest: Real = 0.0;
+		for _ in 0..120 {
+			simulate(&mut bodies, &mut joints, 1);
+			lowest = lowest.min(bodies[0].position.y());
+			assert!(hinge.separation(&bodies) < 0.03);
+			assert!(hinge.misalignment(&bodies) < 0.01);
+		}
+
+		// The bar swings down through the pivot's height less its half length
+		assert!(lowest < -0.45);
+		assert!((bodies[0].position.magnitude() - 0.5).abs() < 0.01);
+		assert!(bodies[0].position.z().abs() < 1e-3);
+	}
+
+	#[test]
+	pub fn hinged_bodies_turn_only_about_the_axis() {
+		let mut bodies = [bar(Vector3::new(0.5, 0.0, 0.0)), bar(Vector3::new(1.5, 0.0, 0.0))];
+		bodies[0].kind = BodyKind::Static;
+		bodies[1].velocity = Vector3::new(0.0, 0.0, -1.0);
+		bodies[1].angular_velocity = Vector3::new(3.0, 2.0, 1.0);
+		let hinge = HingeJoint::new(
+			[Some(1), Some(0)],
+			&bodies,
+			Vector3::new(1.0, 0.0, 0.0),
+			Vector3::y_axis(),
+		);
+		let mut joints = [hinge.into()];
+		simulate(&mut bodies, &mut joints, 60);
+		assert!(hinge.separation(&bodies) < 0.03);
+		assert!(hinge.misalignment(&bodies) < 0.01);
+
+		// Spin about the vertical axis is kept, and about the others stopped
+		let spin = bodies[1].angular_velocity;
+		assert!(spin.x().abs() < 0.05 && spin.z().abs() < 0.05);
+		assert!(spin.y() > 1.9);
+	}
+
+	#[test]
+	pub fn welded_bodies_move_as_one() {
+		let mut bodies = [bar(Vector3::new(0.5, 0.0, 0.0)), bar(Vector3::new(1.5, 0.0, 0.0))];
+		let fixed = FixedJoint::new([Some(1), Some(0)], &bodies, Vector3::new(1.0, 0.0, 0.0));
+		let mut joints = [fixed.into()];
+
+		// Thrown spinning, the pair turns about their shared center
+		bodies[0].velocity = Vector3::new(0.0, 1.0, 0.0);
+		bodies[1].velocity = Vector3::new(0.0, -1.0, 0.0);
+		simulate(&mut bodies, &mut joints, 60);
+		assert!(fixed.separation(&bodies) < 0.03);
+		assert!(fixed.rotation(&bodies).magnitude() < 0.03);
+		assert!(((bodies[1].position - bodies[0].position).magnitude() - 1.0).abs() < 0.03);
+		assert!(bodies[0].angular_velocity.z() < -1.0);
+		assert!((bodies[0].angular_velocity - bodies[1].angular_velocity).magnitude() < 0.05);
+	}
+
+	#[test]
+	pub fn welded_bar_holds_out_from_the_wall() {
+		let mut bodies = [bar(Vector3::new(0.5, 0.0, 0.0))];
+		let fixed = FixedJoint::new([Some(0), None], &bodies, Vector3::zero());
+		let mut joints = [fixed.into()];
+		simulate(&mut bodies, &mut joints, 120);
+		assert!(fixed.rotation(&bodies).magnitude() < 0.05);
+		assert!((bodies[0].position - Vector3::new(0.5, 0.0, 0.0)).magnitude() < 0.05);
+	}
+
+	#[test]
+	pub fn distance_joint_keeps_its_length() {
+		// A box hung off to the side by its corner swings without stretching the joint
+		let mut bodies = [bar(Vector3::new(1.5, 0.0, 0.0))];
+		let distance = DistanceJoint::new([None, Some(0)], &bodies, [Vector3::zero(), Vector3::new(1.0, 0.0, 0.0)]);
+		let mut joints = [distance.into()];
+		let mut lowest: Real = 0.0;
+		for _ in 0..60 {
+			simulate(&mut bodies, &mut joints, 1);
+			lowest = lowest.min(bodies[0].position.y());
+			assert!((distance.length(&bodies) - 1.0).abs() < 0.03);
+		}
+		assert!(lowest < -1.0);
+	}
+
+	#[test]
+	pub fn distance_joint_range_is_slack_within() {
+		let mut bodies = [bar(Vector3::new(0.0, -1.0, 0.0))];
+		let anchors = [Vector3::zero(), Vector3::new(0.0, -1.0, 0.0)];
+		let rope = DistanceJoint::new([None, Some(0)], &bodies, anchors).with_range(0.0, 2.0);
+		let mut joints = [rope.into()];
+
+		// The bar falls freely, by 0.2 m in 0.2 s, until the rope is taut
+		simulate(&mut bodies, &mut joints, 12);
+		assert!((bodies[0].position.y() + 1.2).abs() < 0.03);
+		simulate(&mut bodies, &mut joints, 120);
+		assert!((rope.length(&bodies) - 2.0).abs() < 0.03);
+	}
+
+	#[test]
+	pub fn hinge_limit_stops_the_swing() {
+		let mut bodies = [bar(Vector3::new(0.5, 0.0, 0.0))];
+		let hinge = HingeJoint::new([Some(0), None], &bodies, Vector3::zero(), Vector3::z_axis()).with_limit(-0.5, 0.5);
+		let mut joints = [hinge.into()];
+		for _ in 0..120 {
+			simulate(&mut bodies, &mut joints, 1);
+			assert!(hinge.angle(&bodies) > -0.55);
+		}
+
+		// The bar comes to rest against the lower limit
+		assert!((hinge.angle(&bodies) + 0.5).abs() < 0.05);
+		assert!(bodies[0].angular_velocity.magnitude() < 0.1);
+	}
+
+	#[test]
+	pub fn hinge_motor_drives_the_wheel() {
+		let wheel = || {
+			RigidBodyBuilder::new(Shape::Cylinder {
+				radius: 0.5,
+				half_height: 0.1,
+			})
+			.damping(1.0, 1.0)
+			.can_sleep(false)
+			.build()
+		};
+		let mut bodies = [wheel()];
+		let hinge = HingeJoint::new([Some(0), None], &bodies, Vector3::zero(), Vector3::y_axis());
+		let motor = JointMotor::Velocity {
+			speed: 2.0,
+			max_force: 10.0,
+		};
+		let mut joints = [hinge.with_motor(motor).into()];
+		simulate(&mut bodies, &mut joints, 30);
+		assert!((bodies[0].angular_velocity.y() - 2.0).abs() < 1e-3);
+
+		// A weak motor takes longer to reach its speed
+		let mut bodies = [wheel()];
+		let motor = JointMotor::Velocity {
+			speed: 2.0,
+			max_force: 0.01,
+		};
+		let mut joints = [hinge.with_motor(motor).into()];
+		simulate(&mut bodies, &mut joints, 30);
+		assert!(bodies[0].angular_velocity.y() < 1.0);
+
+		// A servo turns the wheel to the target angle and holds it there
+		let mut bodies = [wheel()];
+		let servo = JointMotor::Servo {
+			target: 1.0,
+			gain: 5.0,
+			max_force: 10.0,
+		};
+		let mut joints = [hinge.with_motor(servo).into()];
+		simulate(&mut bodies, &mut joints, 120);
+		assert!((hinge.angle(&bodies) - 1.0).abs() < 0.01);
+	}
+
+	#[test]
+	pub fn prismatic_joint_slides_to_its_limit() {
+		let mut bodies = [bar(Vector3::zero())];
+		let motor = JointMotor::Velocity {
+			speed: 1.0,
+			max_force: 100.0,
+		};
+		let slider = PrismaticJoint::new([Some(0), None], &bodies, Vector3::zero(), Vector3::x_axis())
+			.with_limit(0.0, 0.5)
+			.with_motor(motor);
+		let mut joints = [slider.into()];
+
+		// Driven along the axis against gravity across it, the bar neither falls nor turns
+		simulate(&mut bodies, &mut joints, 15);
+		assert!((slider.translation(&bodies) - 0.25).abs() < 0.02);
+		simulate(&mut bodies, &mut joints, 45);
+		assert!((slider.translation(&bodies) - 0.5).abs() < 0.02);
+		assert!(slider.drift(&bodies) < 0.03);
+		assert!(bodies[0].angular_velocity.magnitude() < 0.01);
+		assert!(bodies[0].orientation.rotate(Vector3::x_axis()).x() > 0.999);
+	}
+
+	#[test]
+	pub fn overloaded_joints_break() {
+		// Two bars weighing 0.4 hang from welds, of which only the first bears a force of 1
+		let mut bodies = [bar(Vector3::new(0.0, -0.5, 0.0)), bar(Vector3::new(3.0, -0.5, 0.0))];
+		let weld = |body: usize, strength: Real| {
+			let anchor = bodies[body].position + Vector3::new(0.0, 0.5, 0.0);
+			Joint::from(FixedJoint::new([Some(body), None], &bodies, anchor)).with_strength(strength, 10.0)
+		};
+		let mut joints = [weld(0, 1.0), weld(1, 0.1)];
+		simulate(&mut bodies, &mut joints, 30);
+		assert!(!joints[0].is_broken());
+		assert!(joints[1].is_broken());
+		assert!(bodies[0].position.y() > -0.55);
+		assert!(bodies[1].position.y() < -1.5);
+	}
+
+	#[test]
+	pub fn spring_joint_settles_where_the_spring_holds_the_weight() {
+		// A bar hung from a vertical spring bouncing at 2 Hz stretches it by g / (4 pi)^2.
+		// The solver's damping lags the integration a step, so the bar settles a little lower
+		let mut bodies = [bar(Vector3::zero())];
+		let stiffness = SpringStiffness::from_frequency(bodies[0].mass(), 2.0, 0.5);
+		let spring = SpringJoint::new([Some(0), None], &bodies, Vector3::zero()).with_linear([
+			SpringAxis::Rigid,
+			SpringAxis::Spring(stiffness),
+			SpringAxis::Rigid,
+		]);
+		let mut joints = [spring.into()];
+		simulate(&mut bodies, &mut joints, 180);
+		let stretch = bodies[0].mass() * 10.0 / stiffness.spring_constant;
+		assert!((spring.offset(&bodies).y() + stretch).abs() < 0.02);
+		assert!(spring.offset(&bodies).x().abs() < 0.01);
+
+		// And stays there
+		let settled = bodies[0].position;
+		simulate(&mut bodies, &mut joints, 30);
+		assert!((bodies[0].position - settled).magnitude() < 0.001);
+	}
+
+	#[test]
+	pub fn stiff_springs_stay_stable() {
+		// Far stiffer than the step could follow explicitly, the spring still settles
+		let mut bodies = [bar(Vector3::new(0.5, 0.0, 0.0))];
+		let stiff = SpringAxis::Spring(SpringStiffness::new(1e6, 0.0));
+		let spring = SpringJoint::new([Some(0), None], &bodies, Vector3::zero()).with_angular([stiff; 3]);
+		let mut joints = [spring.into()];
+		bodies[0].angular_velocity = Vector3::new(0.0, 0.0, 5.0);
+		simulate(&mut bodies, &mut joints, 120);
+		assert!(spring.rotation(&bodies).magnitude() < 0.01);
+		assert!(bodies[0].angular_velocity.magnitude() < 1.0);
+
+		// Free axes are left alone
+		let mut bodies = [bar(Vector3::new(0.5, 0.0, 0.0))];
+		let spring = spring.with_linear([SpringAxis::Free; 3]);
+		let mut joints = [spring.into()];
+		simulate(&mut bodies, &mut joints, 30);
+		assert!(bodies[0].position.y() < -1.2);
+	}
+}
@@ -1,10 +1,73 @@
 #![forbid(unsafe_code)]
+// The serde derives name the crate with `serde(crate = "serde")`, as otherwise
+// the code they generate allows a lint, which the forbid below would reject
 #![forbid(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
 
+pub mod aero;
+pub mod blob;
+pub mod body;
+pub mod body_set;
+pub mod bounding;
+pub mod broad_phase;
+pub mod bvh;
+pub mod ccd;
+pub mod clock;
+pub mod cloth;
+pub mod collide;
+pub mod collider;
+pub mod compound;
+pub mod constraints;
+pub mod contacts;
+pub mod convex;
+pub mod debug_draw;
+pub mod energy;
+pub mod events;
+pub mod forces;
+pub mod generators;
+#[cfg(feature = "wgpu")]
+pub mod gpu_particles;
+pub mod groups;
+pub mod hash_grid;
+pub mod implicit;
+pub mod island;
+pub mod joint;
+pub mod links;
+pub mod manifold;
+pub mod material;
+pub mod matrix;
 pub mod particle;
+pub mod particle_world;
+pub mod path;
+pub mod pbd;
+pub mod portable;
+pub mod quaternion;
+pub mod query;
+pub mod ragdoll;
+pub mod raycast_vehicle;
+pub mod replay;
+pub mod resolver;
+pub mod rigid_body_world;
+pub mod rope;
+#[cfg(feature = "scene")]
+pub mod scene;
+pub mod shape;
+pub mod solver;
+pub mod state_hash;
+pub mod stats;
+pub mod strain;
+pub mod trimesh;
 pub mod vec;
+pub mod vehicle;
+pub mod world;
 
-pub use self::{particle::*, vec::*};
+pub use self::{
+	aero::*, blob::*, body::*, bounding::*, broad_phase::*, bvh::*, ccd::*, clock::*, cloth::*, collide::*,
+	collider::*, compound::*, constraints::*, contacts::*, convex::*, debug_draw::*, energy::*, events::*, forces::*,
+	generators::*, groups::*, hash_grid::*, implicit::*, island::*, joint::*, links::*, manifold::*, material::*,
+	matrix::*, particle::*, particle_world::*, path::*, pbd::*, portable::*, quaternion::*, query::*, ragdoll::*,
+	raycast_vehicle::*, resolver::*, rigid_body_world::*, rope::*, shape::*, solver::*, state_hash::*, stats::*,
+	strain::*, trimesh::*, vec::*, vehicle::*, world::*,
+};
 
 pub type Real = f32;
 
@@ -23,3 +86,36 @@ pub fn assert_equal(actual: Real, expected: Real) {
 		"left: {actual:?} not equal right: {expected:?}",
 	);
 }
+
+/// Converts an element count into a `Real`, exactly for counts up to 2^24
+/// and rounded to the nearest `Real` above that, saturating at `u32::MAX`.
+#[must_use]
+pub fn real_from_count(count: usize) -> Real {
+	let count = u32::try_from(count).unwrap_or(u32::MAX);
+	let [high, low] = [count >> 16, count & 0xffff].map(|half| Real::from(u16::try_from(half).unwrap_or(u16::MAX)));
+	// Each half converts exactly, and the fused multiply-add rounds their sum only once
+	high.mul_add(65536.0, low)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	pub fn counts_convert_exactly_up_to_the_precision_of_a_real() {
+		for (count, expected) in [
+			(0, 0.0),
+			(65_535, 65_535.0),
+			(65_536, 65_536.0),
+			(65_537, 65_537.0),
+			(16_777_215, 16_777_215.0),
+			(16_777_216, 16_777_216.0),
+			// Above that, counts round to the nearest real, ties to even
+			(16_777_217, 16_777_216.0),
+			(16_777_219, 16_777_220.0),
+			(usize::MAX, 4_294_967_296.0),
+		] {
+			assert_eq!(real_from_count(count).to_bits(), Real::to_bits(expected), "{count}");
+		}
+	}
+}
@@ -0,0 +1,236 @@
+use crate::{
+	contacts::{ParticleContact, ParticleContactGenerator},
+	particle::Particle,
+	reals_are_equal,
+	vec::Vector3,
+	Real,
+};
+
+/// Returns the distance between two particles.
+#[must_use]
+pub fn particle_distance(particles: &[Particle], link: [usize; 2]) -> Real {
+	(particles[link[0]].position - particles[link[1]].position).magnitude()
+}
+
+/// Cables link a pair of particles, generating a contact if they stray too far apart.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParticleCable {
+	/// Holds the pair of particles that are connected by this link.
+	pub particles: [usize; 2],
+
+	/// Holds the maximum length of the cable.
+	pub max_length: Real,
+
+	/// Holds the restitution (bounciness) of the cable.
+	pub restitution: Real,
+}
+
+impl ParticleContactGenerator for ParticleCable {
+	fn add_contact(&self, particles: &[Particle], contacts: &mut Vec<ParticleContact>) -> usize {
+		let length = particle_distance(particles, self.particles);
+
+		// Check if we're overextended
+		if length < self.max_length {
+			return 0;
+		}
+
+		let [first, second] = self.particles;
+		contacts.push(ParticleContact {
+			particles: [Some(first), Some(second)],
+			restitution: self.restitution,
+			contact_normal: (particles[second].position - particles[first].position).normalize(),
+			penetration: length - self.max_length,
+		});
+		1
+	}
+}
+
+/// Rods link a pair of particles, generating a contact if they
+/// stray too far apart or too close together.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParticleRod {
+	/// Holds the pair of particles that are connected by this link.
+	pub particles: [usize; 2],
+
+	/// Holds the length of the rod.
+	pub length: Real,
+}
+
+impl ParticleContactGenerator for ParticleRod {
+	fn add_contact(&self, particles: &[Particle], contacts: &mut Vec<ParticleContact>) -> usize {
+		let current_length = particle_distance(particles, self.particles);
+
+		// Check if we're at the correct length
+		if reals_are_equal(current_length, self.length) {
+			return 0;
+		}
+
+		let [first, second] = self.particles;
+		let normal = (particles[second].position - particles[first].position).normalize();
+
+		// The contact normal depends on whether we're extending or compressing
+		let (contact_normal, penetration) = if current_length > self.length {
+			(normal, current_length - self.length)
+		} else {
+			(-normal, self.length - current_length)
+		};
+
+		// Rods have no bounciness
+		contacts.push(ParticleContact {
+			particles: [Some(first), Some(second)],
+			restitution: 0.0,
+			contact_normal,
+			penetration,
+		});
+		1
+	}
+}
+
+/// Cable constraints link a particle to an immovable anchor point,
+/// generating a contact if they stray too far apart.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParticleCableConstraint {
+	/// Holds the particle that is connected to the anchor.
+	pub particle: usize,
+
+	/// The point to which the particle is anchored.
+	pub anchor: Vector3,
+
+	/// Holds the maximum length of the cable.
+	pub max_length: Real,
+
+	/// Holds the restitution (bounciness) of the cable.
+	pub restitution: Real,
+}
+
+impl ParticleContactGenerator for ParticleCableConstraint {
+	fn add_contact(&self, particles: &[Particle], contacts: &mut Vec<ParticleContact>) -> usize {
+		let length = (particles[self.particle].position - self.anchor).magnitude();
+
+		// Check if we're overextended
+		if length < self.max_length {
+			return 0;
+		}
+
+		contacts.push(ParticleContact {
+			particles: [Some(self.particle), None],
+			restitution: self.restitution,
+			contact_normal: (self.anchor - particles[self.particle].position).normalize(),
+			penetration: length - self.max_length,
+		});
+		1
+	}
+}
+
+/// Rod constraints link a particle to an immovable anchor point,
+/// generating a contact if they stray too far apart or too close together.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParticleRodConstraint {
+	/// Holds the particle that is connected to the anchor.
+	pub particle: usize,
+
+	/// The point to which the particle is anchored.
+	pub anchor: Vector3,
+
+	/// Holds the length of the rod.
+	pub length: Real,
+}
+
+impl ParticleContactGenerator for ParticleRodConstraint {
+	fn add_contact(&self, particles: &[Particle], contacts: &mut Vec<ParticleContact>) -> usize {
+		let current_length = (particles[self.particle].position - self.anchor).magnitude();
+
+		if reals_are_equal(current_length, self.length) {
+			return 0;
+		}
+
+		let normal = (self.anchor - particles[self.particle].position).normalize();
+		let (contact_normal, penetration) = if current_length > self.length {
+			(normal, current_length - self.length)
+		} else {
+			(-normal, self.length - current_length)
+		};
+
+		contacts.push(ParticleContact {
+			particles: [Some(self.particle), None],
+			restitution: 0.0,
+			contact_normal,
+			penetration,
+		});
+		1
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::assert_equal;
+
+	use super::*;
+
+	fn particles_at(positions: &[Vector3]) -> Vec<Particle> {
+		positions
+			.iter()
+			.map(|position| Particle {
+				position: *position,
+				inverse_mass: 1.0,
+				damping: 1.0,
+				..Default::default()
+			})
+			.collect()
+	}
+
+	#[test]
+	pub fn slack_cable_generates_no_contact() {
+		let particles = particles_at(&[Vector3::zero(), Vector3::x_axis()]);
+		let cable = ParticleCable {
+			particles: [0, 1],
+			max_length: 2.0,
+			restitution: 0.5,
+		};
+		let mut contacts = Vec::new();
+		assert_eq!(cable.add_contact(&particles, &mut contacts), 0);
+		assert!(contacts.is_empty());
+	}
+
+	#[test]
+	pub fn taut_cable_pulls_particles_together() {
+		let particles = particles_at(&[Vector3::zero(), Vector3::new(3.0, 0.0, 0.0)]);
+		let cable = ParticleCable {
+			particles: [0, 1],
+			max_length: 2.0,
+			restitution: 0.5,
+		};
+		let mut contacts = Vec::new();
+		assert_eq!(cable.add_contact(&particles, &mut contacts), 1);
+		assert_eq!(contacts[0].contact_normal, Vector3::x_axis());
+		assert_equal(contacts[0].penetration, 1.0);
+	}
+
+	#[test]
+	pub fn compressed_rod_pushes_particles_apart() {
+		let particles = particles_at(&[Vector3::zero(), Vector3::x_axis()]);
+		let rod = ParticleRod {
+			particles: [0, 1],
+			length: 2.0,
+		};
+		let mut contacts = Vec::new();
+		assert_eq!(rod.add_contact(&particles, &mut contacts), 1);
+		assert_eq!(contacts[0].contact_normal, Vector3::new(-1.0, 0.0, 0.0));
+		assert_equal(contacts[0].penetration, 1.0);
+	}
+
+	#[test]
+	pub fn anchored_cable_pulls_towards_anchor() {
+		let particles = particles_at(&[Vector3::new(0.0, -3.0, 0.0)]);
+		let cable = ParticleCableConstraint {
+			particle: 0,
+			anchor: Vector3::zero(),
+			max_length: 2.0,
+			restitution: 0.0,
+		};
+		let mut contacts = Vec::new();
+		assert_eq!(cable.add_contact(&particles, &mut contacts), 1);
+		assert_eq!(contacts[0].contact_normal, Vector3::y_axis());
+		assert_equal(contacts[0].penetration, 1.0);
+	}
+}
@@ -0,0 +1,465 @@
+use std::collections::BTreeMap;
+
+use crate::{body::BodyTransforms, collide::Contact, vec::Vector3, Real};
+
+/// The most points kept in a manifold. Four are enough to hold a box
+/// steady on a face.
+pub const MAX_MANIFOLD_POINTS: usize = 4;
+
+/// A contact kept from one frame to the next.
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct ManifoldPoint {
+	pub contact: Contact,
+
+	/// Holds the point on the surface of each body, in that body's
+	/// coordinates, or in world coordinates for the scenery.
+	pub local_points: [Vector3; 2],
+
+	/// Holds the impulse applied at the contact when it was last resolved,
+	/// along the contact normal and its two tangents, so that the solver
+	/// can start from it in the next frame.
+	pub impulse: Vector3,
+}
+
+impl ManifoldPoint {
+	fn new(contact: Contact, bodies: &(impl BodyTransforms + ?Sized)) -> Self {
+		let mut point = Self {
+			contact,
+			..Default::default()
+		};
+		point.anchor(bodies);
+		point
+	}
+
+	/// Stores where the contact lies on the surface of each body.
+	fn anchor(&mut self, bodies: &(impl BodyTransforms + ?Sized)) {
+		let half_depth = self.contact.contact_normal * (self.contact.penetration * 0.5);
+		let surfaces = [
+			self.contact.contact_point - half_depth,
+			self.contact.contact_point + half_depth,
+		];
+		for (side, surface) in surfaces.into_iter().enumerate() {
+			self.local_points[side] =
+				self.contact.bodies[side].map_or(surface, |body| bodies.transform(body).transform_inverse(surface));
+		}
+	}
+
+	fn world_points(&self, bodies: &(impl BodyTransforms + ?Sized)) -> [Vector3; 2] {
+		let mut points = self.local_points;
+		for (side, point) in points.iter_mut().enumerate() {
+			if let Some(body) = self.contact.bodies[side] {
+				*point = bodies.transform(body).transform(*point);
+			}
+		}
+		points
+	}
+
+	/// Moves the contact with its bodies, returning false once they have
+	/// drifted apart, along the normal or across it, by more than the given distance.
+	fn refresh(&mut self, bodies: &(impl BodyTransforms + ?Sized), breaking_distance: Real) -> bool {
+		let [first, second] = self.world_points(bodies);
+		let offset = first - second;
+		let separation = offset.dot(&self.contact.contact_normal);
+		let drift = offset - self.contact.contact_normal * separation;
+		if separation > breaking_distance || drift.magnitude_squared() > breaking_distance * breaking_distance {
+			return false;
+		}
+		self.contact.contact_point = (first + second) * 0.5;
+		self.contact.penetration = -separation;
+		true
+	}
+}
+
+/// The contacts between one pair of bodies, kept from one frame to the next.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct ContactManifold {
+	/// Holds the indices of the bodies, the lower index first, with `None`
+	/// second for the scenery.
+	pub bodies: [Option<usize>; 2],
+
+	pub points: Vec<ManifoldPoint>,
+}
+
+impl ContactManifold {
+	/// Updates the point matching the contact, or adds the contact as a new point.
+	///
+	/// A point matches if it has the same feature and lies within the
+	/// breaking distance, so that contacts without features are matched by
+	/// position alone. A matched point keeps its impulse.
+	fn merge(&mut self, contact: Contact, bodies: &(impl BodyTransforms + ?Sized), breaking_distance: Real) {
+		let reach = breaking_distance * breaking_distance;
+		let matched = self
+			.points
+			.iter_mut()
+			.filter(|point| point.contact.feature == contact.feature)
+			.map(|point| {
+				let distance = (point.contact.contact_point - contact.contact_point).magnitude_squared();
+				(point, distance)
+			})
+			.filter(|(_, distance)| *distance <= reach)
+			.min_by(|first, second| first.1.total_cmp(&second.1));
+		match matched {
+			Some((point, _)) => {
+				point.contact = contact;
+				point.anchor(bodies);
+			},
+			None => self.points.push(ManifoldPoint::new(contact, bodies)),
+		}
+	}
+
+	/// Drops points until no more than the most allowed remain.
+	///
+	/// The deepest point is always kept, and each point dropped is the one
+	/// whose loss leaves the remaining points spread over the largest area.
+	fn reduce(&mut self) {
+		while self.points.len() > MAX_MANIFOLD_POINTS {
+			let deepest = (0..self.points.len())
+				.max_by(|first, second| {
+					self.points[*first]
+						.contact
+						.penetration
+						.total_cmp(&self.points[*second].contact.penetration)
+				})
+				.unwrap_or(0);
+			let dropped = (0..self.points.len())
+				.filter(|index| *index != deepest)
+				.max_by(|first, second| self.spread_without(*first).total_cmp(&self.spread_without(*second)))
+				.unwrap_or(0);
+			self.points.remove(dropped);
+		}
+	}
+
+	/// Measures how widely the first four points other than the given one
+	/// are spread, by the largest cross product of their diagonals.
+	fn spread_without(&self, dropped: usize) -> Real {
+		let mut corners = [Vector3::zero(); MAX_MANIFOLD_POINTS];
+		let kept = self
+			.points
+			.iter()
+			.enumerate()
+			.filter(|(index, _)| *index != dropped)
+			.map(|(_, point)| point.contact.contact_point);
+		for (corner, point) in corners.iter_mut().zip(kept) {
+			*corner = point;
+		}
+		let [a, b, c, d] = corners;
+		[
+			(a - b).cross(&(c - d)),
+			(a - c).cross(&(b - d)),
+			(a - d).cross(&(b - c)),
+		]
+		.iter()
+		.map(Vector3::magnitude_squared)
+		.fold(0.0, Real::max)
+	}
+}
+
+/// Keeps the contacts between each pair of bodies from one frame to the next.
+///
+/// The solver can start each frame from the impulses of the last, and a
+/// detector that finds one contact a frame builds up enough points over a
+/// few frames to hold a body steady.
+///
+/// Each update moves the points already held with their bodies, dropping
+/// those whose bodies have drifted apart by more than the breaking
+/// distance, then merges in the contacts newly found.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(crate = "serde", from = "CacheData", into = "CacheData")
+)]
+pub struct ManifoldCache {
+	manifolds: BTreeMap<[Option<usize>; 2], ContactManifold>,
+
+	/// Holds how far the two surfaces at a point may drift apart, along
+	/// the normal or across it, before the point is dropped.
+	pub breaking_distance: Real,
+}
+
+/// The manifolds a cache is written as, each keyed again by its bodies when it is read.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(crate = "serde")]
+struct CacheData {
+	manifolds: Vec<ContactManifold>,
+	breaking_distance: Real,
+}
+
+#[cfg(feature = "serde")]
+impl From<ManifoldCache> for CacheData {
+	fn from(cache: ManifoldCache) -> Self {
+		Self {
+			manifolds: cache.manifolds.into_values().collect(),
+			breaking_distance: cache.breaking_distance,
+		}
+	}
+}
+
+#[cfg(feature = "serde")]
+impl From<CacheData> for ManifoldCache {
+	fn from(data: CacheData) -> Self {
+		Self {
+			manifolds: data
+				.manifolds
+				.into_iter()
+				.map(|manifold| (manifold.bodies, manifold))
+				.collect(),
+			breaking_distance: data.breaking_distance,
+		}
+	}
+}
+
+impl Default for ManifoldCache {
+	fn default() -> Self {
+		Self::new(0.02)
+	}
+}
+
+impl ManifoldCache {
+	#[must_use]
+	pub const fn new(breaking_distance: Real) -> Self {
+		Self {
+			manifolds: BTreeMap::new(),
+			breaking_distance,
+		}
+	}
+
+	/// Returns the number of pairs of bodies with a manifold.
+	#[must_use]
+	pub fn len(&self) -> usize {
+		self.manifolds.len()
+	}
+
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.manifolds.is_empty()
+	}
+
+	pub fn clear(&mut self) {
+		self.manifolds.clear();
+	}
+
+	/// Drops the manifolds of every pair the given body is part of.
+	pub fn remove_body(&mut self, body: usize) {
+		self.manifolds.retain(|bodies, _| !bodies.contains(&Some(body)));
+	}
+
+	/// Makes this cache a copy of the other, such as a saved state of a
+	/// world being restored.
+	///
+	/// The storage of each manifold between bodies in contact in both is
+	/// reused, so that only pairs not held here already need allocating.
+	pub fn copy_from(&mut self, other: &Self) {
+		self.breaking_distance = other.breaking_distance;
+		self.manifolds.retain(|bodies, _| other.manifolds.contains_key(bodies));
+		for (bodies, manifold) in &other.manifolds {
+			match self.manifolds.get_mut(bodies) {
+				Some(own) => own.points.clone_from(&manifold.points),
+				None => {
+					self.manifolds.insert(*bodies, manifold.clone());
+				},
+			}
+		}
+	}
+
+	/// Returns the manifold between the given bodies, named in either order.
+	#[must_use]
+	pub fn manifold(&self, bodies: [Option<usize>; 2]) -> Option<&ContactManifold> {
+		self.manifolds.get(&pair_key(bodies).0)
+	}
+
+	/// Returns the manifolds ordered by their bodies.
+	pub fn manifolds(&self) -> impl Iterator<Item = &ContactManifold> {
+		self.manifolds.values()
+	}
+
+	/// Returns the manifolds ordered by their bodies, so that the solver
+	/// can store the impulses it applies.
+	pub fn manifolds_mut(&mut self) -> impl Iterator<Item = &mut ContactManifold> {
+		self.manifolds.values_mut()
+	}
+
+	/// Returns the contacts of every manifold.
+	#[must_use]
+	pub fn contacts(&self) -> Vec<Contact> {
+		self.manifolds()
+			.flat_map(|manifold| manifold.points.iter().map(|point| point.contact))
+			.collect()
+	}
+
+	/// Moves the points held with their bodies, drops those that have
+	/// broken, and merges in the contacts found this frame.
+	///
+	/// The bodies must have had their derived data calculated.
+	pub fn update(&mut self, contacts: &[Contact], bodies: &(impl BodyTransforms + ?Sized)) {
+		let breaking_distance = self.breaking_distance;
+		for manifold in self.manifolds.values_mut() {
+			manifold
+				.points
+				.retain_mut(|point| point.refresh(bodies, breaking_distance));
+		}
+		for contact in contacts {
+			let (key, swapped) = pair_key(contact.bodies);
+			let contact = if swapped { reversed(*contact) } else { *contact };
+			self.manifolds
+				.entry(key)
+				.or_insert_with(|| ContactManifold {
+					bodies: key,
+					points: Vec::new(),
+				})
+				.merge(contact, bodies, breaking_distance);
+		}
+		self.manifolds.retain(|_, manifold| {
+			manifold.reduce();
+			!manifold.points.is_empty()
+		});
+	}
+}
+
+/// Returns the bodies with the lower index first and the scenery last, and
+/// whether they had to be swapped.
+const fn pair_key(bodies: [Option<usize>; 2]) -> ([Option<usize>; 2], bool) {
+	match bodies {
+		[None, Some(_)] => ([bodies[1], None], true),
+		[Some(first), Some(second)] if second < first => ([bodies[1], bodies[0]], true),
+		_ => (bodies, false),
+	}
+}
+
+/// Returns the contact seen from the point of view of its second body.
+fn reversed(contact: Contact) -> Contact {
+	Contact {
+		bodies: [contact.bodies[1], contact.bodies[0]],
+		contact_normal: -contact.contact_normal,
+		..contact
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{assert_equal, body::RigidBody, real_from_count};
+
+	use super::*;
+
+	fn bodies_at(positions: &[Vector3]) -> Vec<RigidBody> {
+		positions
+			.iter()
+			.map(|position| {
+				let mut body = RigidBody {
+					position: *position,
+					inverse_mass: 1.0,
+					..Default::default()
+				};
+				body.calculate_derived_data();
+				body
+			})
+			.collect()
+	}
+
+	fn floor_contact(point: Vector3, feature: usize) -> Contact {
+		Contact {
+			bodies: [Some(0), None],
+			contact_point: point,
+			contact_normal: Vector3::y_axis(),
+			penetration: 0.01,
+			feature,
+			..Default::default()
+		}
+	}
+
+	#[test]
+	pub fn matched_points_keep_their_impulse() {
+		let bodies = bodies_at(&[Vector3::new(0.0, 0.5, 0.0)]);
+		let mut cache = ManifoldCache::default();
+		cache.update(&[floor_contact(Vector3::new(0.5, 0.0, 0.5), 1)], &bodies);
+		for manifold in cache.manifolds_mut() {
+			manifold.points[0].impulse = Vector3::new(2.0, 0.0, 0.0);
+		}
+
+		// The same feature a little way off is the same point, and a new feature is a new point
+		let contacts = [
+			floor_contact(Vector3::new(0.505, 0.0, 0.5), 1),
+			floor_contact(Vector3::new(-0.5, 0.0, 0.5), 2),
+		];
+		cache.update(&contacts, &bodies);
+		let manifold = cache.manifold([None, Some(0)]).expect("the body touches the floor");
+		assert_eq!(manifold.points.len(), 2);
+		assert_eq!(manifold.points[0].impulse, Vector3::new(2.0, 0.0, 0.0));
+		assert_eq!(manifold.points[0].contact.contact_point, Vector3::new(0.505, 0.0, 0.5));
+		assert_eq!(manifold.points[1].impulse, Vector3::zero());
+	}
+
+	#[test]
+	pub fn points_build_up_over_frames() {
+		let bodies = bodies_at(&[Vector3::new(0.0, 0.5, 0.0)]);
+		let mut cache = ManifoldCache::default();
+		let corners = [(-0.5, -0.5), (0.5, -0.5), (0.5, 0.5), (-0.5, 0.5), (0.0, 0.0)];
+		for (feature, (x, z)) in corners.into_iter().enumerate() {
+			let mut contact = floor_contact(Vector3::new(x, 0.0, z), feature + 1);
+			contact.penetration -= 0.001 * real_from_count(feature);
+			cache.update(&[contact], &bodies);
+		}
+		assert_eq!(cache.len(), 1);
+
+		// The point in the middle is shallowest and adds least to the spread, so it is dropped
+		let contacts = cache.contacts();
+		assert_eq!(contacts.len(), MAX_MANIFOLD_POINTS);
+		assert!(contacts.iter().all(|contact| contact.feature != 5));
+	}
+
+	#[test]
+	pub fn copies_match_the_original() {
+		let bodies = bodies_at(&[Vector3::new(0.0, 0.5, 0.0), Vector3::new(3.0, 0.5, 0.0)]);
+		let mut original = ManifoldCache::default();
+		original.update(&[floor_contact(Vector3::new(0.5, 0.0, 0.5), 1)], &bodies);
+		let mut copy = ManifoldCache::new(0.1);
+		let mut other = floor_contact(Vector3::new(3.0, 0.0, 0.0), 1);
+		other.bodies = [Some(1), None];
+		copy.update(&[other], &bodies);
+		copy.copy_from(&original);
+		assert_eq!(copy.contacts().len(), 1);
+		assert!(copy.manifold([Some(0), None]).is_some());
+		assert!(copy.manifold([Some(1), None]).is_none());
+		assert_equal(copy.breaking_distance, original.breaking_distance);
+	}
+
+	#[test]
+	pub fn points_expire_when_bodies_part() {
+		let mut bodies = bodies_at(&[Vector3::new(0.0, 0.5, 0.0), Vector3::new(0.0, 1.49, 0.0)]);
+		let mut cache = ManifoldCache::default();
+		let between = Contact {
+			bodies: [Some(1), Some(0)],
+			contact_point: Vector3::new(0.0, 1.0, 0.0),
+			contact_normal: Vector3::y_axis(),
+			penetration: 0.01,
+			..Default::default()
+		};
+		cache.update(&[between, floor_contact(Vector3::zero(), 1)], &bodies);
+		assert_eq!(cache.len(), 2);
+
+		// The pair is held with the lower body first, so the normal is turned around
+		let manifold = cache.manifold([Some(1), Some(0)]).expect("the bodies touch");
+		assert_eq!(manifold.bodies, [Some(0), Some(1)]);
+		assert_eq!(manifold.points[0].contact.contact_normal, -Vector3::y_axis());
+
+		// Lifting the top body a little keeps its point but reduces its depth
+		bodies[1].position = Vector3::new(0.0, 1.5, 0.0);
+		bodies[1].calculate_derived_data();
+		cache.update(&[], &bodies);
+		assert_eq!(cache.len(), 2);
+		let depth = cache.manifold([Some(0), Some(1)]).expect("still touching").points[0]
+			.contact
+			.penetration;
+		assert!(depth.abs() < 1e-5);
+
+		// Lifting it further breaks the contact, while the floor contact stays
+		bodies[1].position = Vector3::new(0.0, 1.6, 0.0);
+		bodies[1].calculate_derived_data();
+		cache.update(&[], &bodies);
+		assert_eq!(cache.len(), 1);
+		assert!(cache.manifold([Some(0), Some(1)]).is_none());
+	}
+}
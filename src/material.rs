@@ -0,0 +1,95 @@
+use crate::Real;
+
+/// How the values of two materials are combined where they meet.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub enum CombineRule {
+	/// The smaller of the two values is used.
+	Min,
+
+	/// The larger of the two values is used.
+	Max,
+
+	/// The product of the two values is used.
+	Multiply,
+
+	/// The mean of the two values is used.
+	#[default]
+	Average,
+}
+
+impl CombineRule {
+	/// Combines the values of two materials by this rule.
+	#[must_use]
+	pub const fn combine(self, first: Real, second: Real) -> Real {
+		match self {
+			Self::Min => first.min(second),
+			Self::Max => first.max(second),
+			Self::Multiply => first * second,
+			Self::Average => (first + second) * 0.5,
+		}
+	}
+}
+
+/// The surface and bulk properties of what a collider is made of.
+///
+/// Where two colliders with materials touch, their friction and restitution
+/// are combined by the rules held in [`CollisionData`](crate::collide::CollisionData),
+/// and the density is used to derive a body's mass from its shape with
+/// [`RigidBodyBuilder::material`](crate::body::RigidBodyBuilder::material).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct Material {
+	/// Holds the coefficient of friction.
+	pub friction: Real,
+
+	/// Holds the coefficient of rolling friction, a length that is zero
+	/// for a perfectly hard ball and grows with how far it flattens.
+	pub rolling_friction: Real,
+
+	/// Holds the fraction of the closing speed kept on rebound.
+	pub restitution: Real,
+
+	/// Holds the mass per unit volume.
+	pub density: Real,
+}
+
+impl Default for Material {
+	fn default() -> Self {
+		Self::new(0.5, 0.0, 1.0)
+	}
+}
+
+impl Material {
+	/// Creates a material with no rolling friction.
+	#[must_use]
+	pub const fn new(friction: Real, restitution: Real, density: Real) -> Self {
+		Self {
+			friction,
+			rolling_friction: 0.0,
+			restitution,
+			density,
+		}
+	}
+
+	/// Sets the coefficient of rolling friction.
+	#[must_use]
+	pub const fn with_rolling_friction(mut self, rolling_friction: Real) -> Self {
+		self.rolling_friction = rolling_friction;
+		self
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::assert_equal;
+
+	#[test]
+	pub fn combine_rules() {
+		assert_equal(CombineRule::Min.combine(0.1, 0.9), 0.1);
+		assert_equal(CombineRule::Max.combine(0.1, 0.9), 0.9);
+		assert_equal(CombineRule::Multiply.combine(0.5, 0.4), 0.2);
+		assert_equal(CombineRule::Average.combine(0.1, 0.9), 0.5);
+	}
+}
@@ -0,0 +1,437 @@
+use crate::{quaternion::Quaternion, reals_are_equal, vec::Vector3, Real};
+use std::ops::{Add, Index, IndexMut, Mul, Sub};
+
+/// A 3x3 matrix stored in row-major order, used for rotations and inertia tensors.
+#[derive(Debug, Default, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct Matrix3 {
+	elements: [Real; 9],
+}
+
+impl PartialEq for Matrix3 {
+	fn eq(&self, rhs: &Self) -> bool {
+		self.elements
+			.iter()
+			.zip(rhs.elements.iter())
+			.all(|(a, b)| reals_are_equal(*a, *b))
+	}
+}
+
+impl Index<(usize, usize)> for Matrix3 {
+	type Output = Real;
+
+	fn index(&self, (row, column): (usize, usize)) -> &Self::Output {
+		&self.elements[row * 3 + column]
+	}
+}
+
+impl IndexMut<(usize, usize)> for Matrix3 {
+	fn index_mut(&mut self, (row, column): (usize, usize)) -> &mut Self::Output {
+		&mut self.elements[row * 3 + column]
+	}
+}
+
+impl Mul<Vector3> for Matrix3 {
+	type Output = Vector3;
+
+	fn mul(self, rhs: Vector3) -> Self::Output {
+		Vector3::new(self.row(0).dot(&rhs), self.row(1).dot(&rhs), self.row(2).dot(&rhs))
+	}
+}
+
+impl Mul for Matrix3 {
+	type Output = Self;
+
+	fn mul(self, rhs: Self) -> Self::Output {
+		let mut elements = [0.0; 9];
+		for (index, element) in elements.iter_mut().enumerate() {
+			*element = self.row(index / 3).dot(&rhs.column(index % 3));
+		}
+		Self { elements }
+	}
+}
+
+impl Mul<Real> for Matrix3 {
+	type Output = Self;
+
+	fn mul(self, rhs: Real) -> Self::Output {
+		let mut elements = self.elements;
+		for element in &mut elements {
+			*element *= rhs;
+		}
+		Self { elements }
+	}
+}
+
+impl Add for Matrix3 {
+	type Output = Self;
+
+	fn add(self, rhs: Self) -> Self::Output {
+		let mut elements = self.elements;
+		for (element, other) in elements.iter_mut().zip(rhs.elements.iter()) {
+			*element += *other;
+		}
+		Self { elements }
+	}
+}
+
+impl Sub for Matrix3 {
+	type Output = Self;
+
+	fn sub(self, rhs: Self) -> Self::Output {
+		let mut elements = self.elements;
+		for (element, other) in elements.iter_mut().zip(rhs.elements.iter()) {
+			*element -= *other;
+		}
+		Self { elements }
+	}
+}
+
+impl Matrix3 {
+	/// Creates a matrix from its elements in row-major order.
+	#[must_use]
+	pub const fn new(elements: [Real; 9]) -> Self {
+		Self { elements }
+	}
+
+	#[must_use]
+	pub const fn zero() -> Self {
+		Self::new([0.0; 9])
+	}
+
+	#[must_use]
+	pub const fn identity() -> Self {
+		Self::new([1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0])
+	}
+
+	/// Creates a matrix with the given diagonal and zeroes elsewhere.
+	#[must_use]
+	pub fn diagonal(diagonal: Vector3) -> Self {
+		Self::new([diagonal.x(), 0.0, 0.0, 0.0, diagonal.y(), 0.0, 0.0, 0.0, diagonal.z()])
+	}
+
+	/// Creates a matrix whose columns are the given vectors.
+	#[must_use]
+	pub fn from_columns(columns: [Vector3; 3]) -> Self {
+		let [first, second, third] = columns;
+		Self::new([
+			first.x(),
+			second.x(),
+			third.x(),
+			first.y(),
+			second.y(),
+			third.y(),
+			first.z(),
+			second.z(),
+			third.z(),
+		])
+	}
+
+	/// Creates the matrix that multiplies a vector by taking
+	/// the cross product of the given vector with it.
+	#[must_use]
+	pub fn skew_symmetric(vector: Vector3) -> Self {
+		let (x, y, z) = (vector.x(), vector.y(), vector.z());
+		Self::new([0.0, -z, y, z, 0.0, -x, -y, x, 0.0])
+	}
+
+	/// Creates the rotation matrix equivalent to the given unit quaternion.
+	#[must_use]
+	pub fn from_quaternion(quaternion: &Quaternion) -> Self {
+		let Quaternion { w, x, y, z } = *quaternion;
+		Self::new([
+			y.mul_add(y, z * z).mul_add(-2.0, 1.0),
+			2.0 * x.mul_add(y, -w * z),
+			2.0 * x.mul_add(z, w * y),
+			2.0 * x.mul_add(y, w * z),
+			x.mul_add(x, z * z).mul_add(-2.0, 1.0),
+			2.0 * y.mul_add(z, -w * x),
+			2.0 * x.mul_add(z, -w * y),
+			2.0 * y.mul_add(z, w * x),
+			x.mul_add(x, y * y).mul_add(-2.0, 1.0),
+		])
+	}
+
+	/// Interpolates between two matrices, element by element,
+	/// by the given proportion from zero at `self` to one at `other`.
+	#[must_use]
+	pub fn linear_interpolate(&self, other: &Self, proportion: Real) -> Self {
+		let mut elements = self.elements;
+		for (element, target) in elements.iter_mut().zip(other.elements.iter()) {
+			*element = (*target - *element).mul_add(proportion, *element);
+		}
+		Self { elements }
+	}
+
+	/// Returns the given row as a vector.
+	#[must_use]
+	pub fn row(&self, row: usize) -> Vector3 {
+		Vector3::new(self[(row, 0)], self[(row, 1)], self[(row, 2)])
+	}
+
+	/// Returns the given column as a vector.
+	#[must_use]
+	pub fn column(&self, column: usize) -> Vector3 {
+		Vector3::new(self[(0, column)], self[(1, column)], self[(2, column)])
+	}
+
+	#[must_use]
+	pub fn transpose(&self) -> Self {
+		let mut elements = [0.0; 9];
+		for (index, element) in elements.iter_mut().enumerate() {
+			*element = self[(index % 3, index / 3)];
+		}
+		Self { elements }
+	}
+
+	#[must_use]
+	pub fn determinant(&self) -> Real {
+		self.row(0).dot(&self.row(1).cross(&self.row(2)))
+	}
+
+	/// Returns the inverse of the matrix, or `None` if it is singular.
+	#[must_use]
+	pub fn inverse(&self) -> Option<Self> {
+		let determinant = self.determinant();
+		if determinant == 0.0 {
+			return None;
+		}
+
+		// The cross products of pairs of rows are the rows of the cofactor matrix,
+		// and the inverse is its transpose divided by the determinant
+		let [first, second, third] = [self.row(0), self.row(1), self.row(2)];
+		let cofactors = [second.cross(&third), third.cross(&first), first.cross(&second)];
+		let mut elements = [0.0; 9];
+		for (index, element) in elements.iter_mut().enumerate() {
+			*element = cofactors[index % 3][index / 3];
+		}
+		Some(Self { elements } * determinant.recip())
+	}
+
+	/// Transforms the given vector by the transpose of this matrix,
+	/// which for a rotation is the inverse rotation.
+	#[must_use]
+	pub fn transform_transpose(&self, vector: Vector3) -> Vector3 {
+		Vector3::new(
+			self.column(0).dot(&vector),
+			self.column(1).dot(&vector),
+			self.column(2).dot(&vector),
+		)
+	}
+}
+
+/// A 3x4 matrix holding a rotation and a translation, stored in row-major order.
+///
+/// It behaves as a 4x4 transform whose bottom row is always `[0, 0, 0, 1]`,
+/// so the bottom row is not stored.
+#[derive(Debug, Default, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct Matrix4 {
+	elements: [Real; 12],
+}
+
+impl PartialEq for Matrix4 {
+	fn eq(&self, rhs: &Self) -> bool {
+		self.elements
+			.iter()
+			.zip(rhs.elements.iter())
+			.all(|(a, b)| reals_are_equal(*a, *b))
+	}
+}
+
+impl Index<(usize, usize)> for Matrix4 {
+	type Output = Real;
+
+	fn index(&self, (row, column): (usize, usize)) -> &Self::Output {
+		&self.elements[row * 4 + column]
+	}
+}
+
+impl IndexMut<(usize, usize)> for Matrix4 {
+	fn index_mut(&mut self, (row, column): (usize, usize)) -> &mut Self::Output {
+		&mut self.elements[row * 4 + column]
+	}
+}
+
+impl Matrix4 {
+	/// Creates a matrix from its top three rows in row-major order.
+	#[must_use]
+	pub const fn new(elements: [Real; 12]) -> Self {
+		Self { elements }
+	}
+
+	#[must_use]
+	pub const fn identity() -> Self {
+		Self::new([1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0])
+	}
+
+	/// Creates the transform that rotates by the given unit
+	/// quaternion and then translates by the given position.
+	#[must_use]
+	pub fn from_transform(orientation: &Quaternion, position: Vector3) -> Self {
+		Self::from_rotation(&Matrix3::from_quaternion(orientation), position)
+	}
+
+	/// Creates the transform that rotates by the given rotation
+	/// matrix and then translates by the given position.
+	#[must_use]
+	pub fn from_rotation(rotation: &Matrix3, position: Vector3) -> Self {
+		let mut elements = [0.0; 12];
+		for (row, chunk) in elements.chunks_exact_mut(4).enumerate() {
+			chunk[..3].copy_from_slice(&[rotation[(row, 0)], rotation[(row, 1)], rotation[(row, 2)]]);
+			chunk[3] = position[row];
+		}
+		Self { elements }
+	}
+
+	/// Returns the rotation part of the transform.
+	#[must_use]
+	pub fn rotation(&self) -> Matrix3 {
+		let mut elements = [0.0; 9];
+		for (index, element) in elements.iter_mut().enumerate() {
+			*element = self[(index / 3, index % 3)];
+		}
+		Matrix3::new(elements)
+	}
+
+	/// Returns the translation part of the transform.
+	#[must_use]
+	pub fn translation(&self) -> Vector3 {
+		Vector3::new(self[(0, 3)], self[(1, 3)], self[(2, 3)])
+	}
+
+	/// Returns the given column of the transform. Columns 0 to 2 are the
+	/// transformed x, y, and z axes, and column 3 is the translation.
+	#[must_use]
+	pub fn axis(&self, column: usize) -> Vector3 {
+		Vector3::new(self[(0, column)], self[(1, column)], self[(2, column)])
+	}
+
+	/// Returns the transform that applies the other transform and then this one.
+	#[must_use]
+	pub fn compose(&self, other: &Self) -> Self {
+		Self::from_rotation(
+			&(self.rotation() * other.rotation()),
+			self.transform(other.translation()),
+		)
+	}
+
+	/// Transforms the given point, rotating and then translating it.
+	#[must_use]
+	pub fn transform(&self, point: Vector3) -> Vector3 {
+		self.transform_direction(point) + self.translation()
+	}
+
+	/// Rotates the given direction without translating it.
+	#[must_use]
+	pub fn transform_direction(&self, direction: Vector3) -> Vector3 {
+		self.rotation() * direction
+	}
+
+	/// Transforms the given point by the inverse of this transform.
+	/// The rotation part is assumed to be a pure rotation.
+	#[must_use]
+	pub fn transform_inverse(&self, point: Vector3) -> Vector3 {
+		self.transform_inverse_direction(point - self.translation())
+	}
+
+	/// Rotates the given direction by the inverse of this transform's rotation.
+	#[must_use]
+	pub fn transform_inverse_direction(&self, direction: Vector3) -> Vector3 {
+		self.rotation().transform_transpose(direction)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::assert_equal;
+
+	use super::*;
+
+	fn sample() -> Matrix3 {
+		Matrix3::new([2.0, 0.0, 1.0, 1.0, 3.0, 0.0, 0.0, 1.0, 4.0])
+	}
+
+	#[test]
+	pub fn multiply_vector() {
+		assert_eq!(sample() * Vector3::new(1.0, 2.0, 3.0), Vector3::new(5.0, 7.0, 14.0));
+	}
+
+	#[test]
+	pub fn multiply_identity() {
+		assert_eq!(sample() * Matrix3::identity(), sample());
+		assert_eq!(Matrix3::identity() * sample(), sample());
+	}
+
+	#[test]
+	pub fn skew_symmetric_takes_cross_product() {
+		let first = Vector3::new(1.0, 2.0, 3.0);
+		let second = Vector3::new(-2.0, 0.5, 4.0);
+		assert_eq!(Matrix3::skew_symmetric(first) * second, first.cross(&second));
+		assert_eq!(sample() - sample(), Matrix3::zero());
+	}
+
+	#[test]
+	pub fn linear_interpolate() {
+		let halfway = Matrix3::zero().linear_interpolate(&sample(), 0.5);
+		assert_eq!(halfway, sample() * 0.5);
+		assert_eq!(Matrix3::zero().linear_interpolate(&sample(), 1.0), sample());
+	}
+
+	#[test]
+	pub fn from_columns() {
+		let matrix = Matrix3::from_columns([Vector3::new(1.0, 2.0, 3.0), Vector3::y_axis(), Vector3::z_axis()]);
+		assert_eq!(matrix.column(0), Vector3::new(1.0, 2.0, 3.0));
+		assert_eq!(matrix.row(1), Vector3::new(2.0, 1.0, 0.0));
+	}
+
+	#[test]
+	pub fn transpose() {
+		let transposed = sample().transpose();
+		assert_equal(transposed[(0, 1)], 1.0);
+		assert_equal(transposed[(2, 0)], 1.0);
+		assert_eq!(transposed.transpose(), sample());
+	}
+
+	#[test]
+	pub fn inverse() {
+		assert_equal(sample().determinant(), 25.0);
+		let inverse = sample().inverse().unwrap();
+		assert_eq!(sample() * inverse, Matrix3::identity());
+		assert_eq!(Matrix3::default().inverse(), None);
+	}
+
+	#[test]
+	pub fn rotation_from_quaternion() {
+		let quarter_turn = Quaternion::from_axis_angle(Vector3::z_axis(), std::f32::consts::FRAC_PI_2);
+		let rotation = Matrix3::from_quaternion(&quarter_turn);
+		assert_eq!(rotation * Vector3::x_axis(), Vector3::y_axis());
+		assert_eq!(rotation.transform_transpose(Vector3::y_axis()), Vector3::x_axis());
+	}
+
+	#[test]
+	pub fn transform_round_trip() {
+		let orientation = Quaternion::from_axis_angle(Vector3::z_axis(), std::f32::consts::FRAC_PI_2);
+		let transform = Matrix4::from_transform(&orientation, Vector3::new(1.0, 2.0, 3.0));
+		let point = transform.transform(Vector3::x_axis());
+		assert_eq!(point, Vector3::new(1.0, 3.0, 3.0));
+		assert_eq!(transform.transform_inverse(point), Vector3::x_axis());
+		assert_eq!(transform.transform_direction(Vector3::x_axis()), Vector3::y_axis());
+		assert_eq!(
+			Matrix4::from_transform(&Quaternion::identity(), Vector3::zero()),
+			Matrix4::identity()
+		);
+	}
+
+	#[test]
+	pub fn compose_transforms() {
+		let orientation = Quaternion::from_axis_angle(Vector3::z_axis(), std::f32::consts::FRAC_PI_2);
+		let body = Matrix4::from_transform(&orientation, Vector3::new(1.0, 2.0, 3.0));
+		let offset = Matrix4::from_transform(&Quaternion::identity(), Vector3::x_axis());
+		let composed = body.compose(&offset);
+		let point = Vector3::new(0.0, 1.0, 0.0);
+		assert_eq!(composed.transform(point), body.transform(offset.transform(point)));
+		assert_eq!(composed.axis(0), Vector3::y_axis());
+		assert_eq!(composed.axis(3), Vector3::new(1.0, 3.0, 3.0));
+	}
+}
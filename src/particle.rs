@@ -1,6 +1,7 @@
-use crate::{vec::Vector3, Real};
+use crate::{portable::powf, vec::Vector3, Real};
 
 #[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
 pub struct Particle {
 	/// Holds the linear position of the particle in world space
 	pub position: Vector3,
@@ -31,11 +32,15 @@ pub struct Particle {
 	/// simulation iteration only. This value is zeroed at each
 	/// integration step.
 	pub force_accumulator: Vector3,
+
+	/// Holds a value of the user's choosing, such as the handle of the game
+	/// entity the particle belongs to. The simulation never reads it.
+	pub user_data: u64,
 }
 
 impl Particle {
 	#[must_use]
-	pub fn mass(&self) -> Real {
+	pub const fn mass(&self) -> Real {
 		self.inverse_mass.recip()
 	}
 
@@ -48,6 +53,16 @@ impl Particle {
 		self.force_accumulator += force;
 	}
 
+	/// Returns the energy of the particle's motion, which is zero
+	/// for particles with infinite mass.
+	#[must_use]
+	pub fn kinetic_energy(&self) -> Real {
+		if !self.has_finite_mass() {
+			return 0.0;
+		}
+		0.5 * self.mass() * self.velocity.dot(&self.velocity)
+	}
+
 	/// Integrates the particle forward in time by the given amount.
 	/// This function uses a Newton-Euler integration method, which is a
 	/// linear approximation to the correct integral. For this reason it
@@ -66,7 +81,7 @@ impl Particle {
 		self.velocity += acceleration * duration;
 
 		// Impose drag
-		self.velocity *= self.damping.powf(duration);
+		self.velocity *= powf(self.damping, duration);
 
 		// Clear any accumulated forces
 		self.force_accumulator = Vector3::zero();
@@ -0,0 +1,265 @@
+use crate::{
+	contacts::{ParticleContact, ParticleContactGenerator, ParticleContactResolver},
+	energy::{Energy, EnergyAudit},
+	forces::ParticleForceRegistry,
+	particle::Particle,
+	vec::Vector3,
+	Real,
+};
+
+/// Keeps track of a set of particles, and provides the means to update them all.
+///
+/// Each frame, [`Self::start_frame`] clears the forces left from the last
+/// frame, and [`Self::run_physics`] applies the registered forces,
+/// integrates the particles, and generates and resolves their contacts.
+#[derive(Default)]
+pub struct ParticleWorld {
+	pub particles: Vec<Particle>,
+
+	/// Holds the force generators for the particles in this world.
+	pub registry: ParticleForceRegistry,
+
+	/// Holds the contact generators run each step.
+	pub contact_generators: Vec<Box<dyn ParticleContactGenerator>>,
+
+	pub resolver: ParticleContactResolver,
+
+	/// Holds the most contacts generated each step. Once it is reached,
+	/// the remaining generators are skipped and any extra contacts dropped.
+	pub max_contacts: usize,
+
+	/// Holds whether the resolver is given two iterations per contact each
+	/// step, rather than keeping the budget it was created with.
+	pub calculate_iterations: bool,
+
+	/// Holds the audit recording the world's energy after each step, if any.
+	pub energy_audit: Option<EnergyAudit>,
+
+	contacts: Vec<ParticleContact>,
+}
+
+impl ParticleWorld {
+	/// Creates a world that generates at most the given number of contacts
+	/// each step and resolves them with the given number of iterations.
+	/// With no iterations given, two per contact are used.
+	#[must_use]
+	pub fn new(max_contacts: usize, iterations: usize) -> Self {
+		Self {
+			resolver: ParticleContactResolver::new(iterations),
+			max_contacts,
+			calculate_iterations: iterations == 0,
+			..Default::default()
+		}
+	}
+
+	/// Adds the particle to the world, returning its index.
+	pub fn add_particle(&mut self, particle: Particle) -> usize {
+		self.particles.push(particle);
+		self.particles.len() - 1
+	}
+
+	/// Adds a contact generator, run each step after the particles are integrated.
+	pub fn add_contact_generator(&mut self, generator: impl ParticleContactGenerator + 'static) {
+		self.contact_generators.push(Box::new(generator));
+	}
+
+	/// Returns the contacts generated by the last step.
+	#[must_use]
+	pub fn contacts(&self) -> &[ParticleContact] {
+		&self.contacts
+	}
+
+	/// Initializes the world for a simulation frame. This clears the force
+	/// accumulators of every particle. The forces for the frame can be
+	/// added once this has been called.
+	pub fn start_frame(&mut self) {
+		for particle in &mut self.particles {
+			particle.force_accumulator = Vector3::zero();
+		}
+	}
+
+	/// Calls each of the registered contact generators to report their
+	/// contacts, and returns the number of contacts generated.
+	pub fn generate_contacts(&mut self) -> usize {
+		self.contacts.clear();
+		for generator in &self.contact_generators {
+			if self.contacts.len() >= self.max_contacts {
+				break;
+			}
+			generator.add_contact(&self.particles, &mut self.contacts);
+		}
+		self.contacts.truncate(self.max_contacts);
+		self.contacts.len()
+	}
+
+	/// Integrates all the particles in this world forward in time by the given duration.
+	pub fn integrate(&mut self, duration: Real) {
+		for particle in &mut self.particles {
+			particle.integrate(duration);
+		}
+	}
+
+	/// Returns the energy of the particles' motion and the energy stored by
+	/// their constant accelerations and registered forces.
+	#[must_use]
+	pub fn energy(&self) -> Energy {
+		let finite = self.particles.iter().filter(|particle| particle.has_finite_mass());
+		Energy {
+			kinetic: finite.clone().map(Particle::kinetic_energy).sum(),
+			potential: self.registry.potential_energy(&self.particles)
+				- finite
+					.map(|particle| particle.mass() * particle.acceleration.dot(&particle.position))
+					.sum::<Real>(),
+		}
+	}
+
+	/// Processes all the physics for the particle world.
+	pub fn run_physics(&mut self, duration: Real) {
+		self.registry.update_forces(&mut self.particles, duration);
+		self.integrate(duration);
+
+		let used = self.generate_contacts();
+		if used > 0 {
+			if self.calculate_iterations {
+				self.resolver.iterations = used * 2;
+			}
+			self.resolver
+				.resolve_contacts(&mut self.contacts, &mut self.particles, duration);
+		}
+		if let Some(mut audit) = self.energy_audit.take() {
+			audit.record(self.energy());
+			self.energy_audit = Some(audit);
+		}
+	}
+}
+
+/// A contact generator that keeps every particle above a level ground
+/// at the given height.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParticleGroundContacts {
+	/// Holds the height of the ground along the y axis.
+	pub height: Real,
+
+	/// Holds the restitution (bounciness) of the ground.
+	pub restitution: Real,
+}
+
+impl ParticleContactGenerator for ParticleGroundContacts {
+	fn add_contact(&self, particles: &[Particle], contacts: &mut Vec<ParticleContact>) -> usize {
+		let before = contacts.len();
+		for (index, particle) in particles.iter().enumerate() {
+			let depth = self.height - particle.position.y();
+			if depth > 0.0 {
+				contacts.push(ParticleContact {
+					particles: [Some(index), None],
+					restitution: self.restitution,
+					contact_normal: Vector3::y_axis(),
+					penetration: depth,
+				});
+			}
+		}
+		contacts.len() - before
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{assert_equal, forces::ParticleGravity, links::ParticleRod};
+
+	use super::*;
+
+	fn particle(position: Vector3) -> Particle {
+		Particle {
+			position,
+			inverse_mass: 1.0,
+			damping: 1.0,
+			..Default::default()
+		}
+	}
+
+	#[test]
+	pub fn registered_forces_move_particles() {
+		let mut world = ParticleWorld::new(16, 0);
+		let ball = world.add_particle(particle(Vector3::new(0.0, 10.0, 0.0)));
+		world.registry.add(
+			ball,
+			Box::new(ParticleGravity {
+				gravity: Vector3::new(0.0, -10.0, 0.0),
+			}),
+		);
+		for _ in 0..2 {
+			world.start_frame();
+			world.run_physics(0.5);
+		}
+		assert_eq!(world.particles[ball].velocity, Vector3::new(0.0, -10.0, 0.0));
+		assert_eq!(world.particles[ball].position, Vector3::new(0.0, 7.5, 0.0));
+		assert!(world.contacts().is_empty());
+	}
+
+	#[test]
+	pub fn ground_holds_falling_particles() {
+		let mut world = ParticleWorld::new(16, 0);
+		for x in [0.0, 1.0, 2.0] {
+			let mut ball = particle(Vector3::new(x, 1.0, 0.0));
+			ball.acceleration = Vector3::new(0.0, -10.0, 0.0);
+			world.add_particle(ball);
+		}
+		world.add_contact_generator(ParticleGroundContacts::default());
+		for _ in 0..120 {
+			world.start_frame();
+			world.run_physics(1.0 / 60.0);
+		}
+		assert_eq!(world.contacts().len(), 3);
+		assert_eq!(world.resolver.iterations, 6);
+		assert!(world.particles.iter().all(|ball| ball.position.y().abs() < 0.01));
+	}
+
+	#[test]
+	pub fn contacts_are_capped() {
+		let mut world = ParticleWorld::new(2, 4);
+		for x in [0.0, 1.0, 2.0] {
+			world.add_particle(particle(Vector3::new(x, -1.0, 0.0)));
+		}
+		world.add_contact_generator(ParticleGroundContacts::default());
+		world.add_contact_generator(ParticleRod {
+			particles: [0, 1],
+			length: 2.0,
+		});
+		assert_eq!(world.generate_contacts(), 2);
+		world.run_physics(1.0 / 60.0);
+		assert_eq!(world.resolver.iterations, 4);
+		assert_equal(world.particles[2].position.y(), -1.0);
+	}
+
+	#[test]
+	pub fn energy_audit_reveals_integrator_drift() {
+		let mut world = ParticleWorld::new(16, 0);
+		let ball = world.add_particle(particle(Vector3::new(0.0, 10.0, 0.0)));
+		world.registry.add(
+			ball,
+			Box::new(ParticleGravity {
+				gravity: Vector3::new(0.0, -10.0, 0.0),
+			}),
+		);
+		assert_eq!(
+			world.energy(),
+			Energy {
+				kinetic: 0.0,
+				potential: 100.0
+			}
+		);
+		let mut audit = EnergyAudit::new();
+		audit.record(world.energy());
+		world.energy_audit = Some(audit);
+		for _ in 0..2 {
+			world.start_frame();
+			world.run_physics(0.5);
+		}
+
+		// Explicit Euler gains half of m g² dt² each step
+		let audit = world.energy_audit.unwrap();
+		assert_equal(audit.step_drift(), 12.5);
+		assert_equal(audit.drift(), 25.0);
+		assert_equal(audit.current().kinetic, 50.0);
+	}
+}
@@ -0,0 +1,275 @@
+use crate::{particle::Particle, pbd::PositionConstraint, real_from_count, vec::Vector3, Real};
+
+/// The number of samples taken along each segment when
+/// searching for the closest point on a curved path.
+const SAMPLES_PER_SEGMENT: usize = 16;
+
+/// The number of refinement steps taken around the closest sample.
+const REFINEMENT_STEPS: usize = 16;
+
+/// A parametric path through a list of points.
+///
+/// Paths are parameterised from zero at the first point to the number of
+/// segments at the last, so the whole part of a parameter is the segment
+/// index and the fractional part is the distance along that segment.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Path {
+	/// Straight segments joining each point to the next.
+	Polyline(Vec<Vector3>),
+
+	/// A smooth uniform Catmull-Rom spline passing through every point.
+	CatmullRom(Vec<Vector3>),
+}
+
+impl Path {
+	/// Returns the points the path passes through.
+	#[must_use]
+	pub fn points(&self) -> &[Vector3] {
+		match self {
+			Self::Polyline(points) | Self::CatmullRom(points) => points,
+		}
+	}
+
+	/// Returns the number of segments in the path.
+	#[must_use]
+	pub fn segments(&self) -> usize {
+		self.points().len().saturating_sub(1)
+	}
+
+	/// Returns the parameter at the end of the path.
+	#[must_use]
+	pub fn end(&self) -> Real {
+		real_from_count(self.segments())
+	}
+
+	/// Splits a parameter into a segment index and the fraction along that segment.
+	fn segment(&self, parameter: Real) -> (usize, Real) {
+		let parameter = parameter.clamp(0.0, self.end());
+		let mut segment = 0;
+		while segment + 1 < self.segments() && real_from_count(segment + 1) <= parameter {
+			segment += 1;
+		}
+		(segment, parameter - real_from_count(segment))
+	}
+
+	/// Returns the four control points used to evaluate the given spline segment.
+	fn control_points(points: &[Vector3], segment: usize) -> [Vector3; 4] {
+		let last = points.len() - 1;
+		[
+			points[segment.saturating_sub(1)],
+			points[segment],
+			points[(segment + 1).min(last)],
+			points[(segment + 2).min(last)],
+		]
+	}
+
+	/// Returns the point on the path at the given parameter.
+	#[must_use]
+	pub fn point(&self, parameter: Real) -> Vector3 {
+		let points = self.points();
+		match points.len() {
+			0 => return Vector3::zero(),
+			1 => return points[0],
+			_ => {},
+		}
+		let (segment, fraction) = self.segment(parameter);
+		match self {
+			Self::Polyline(points) => points[segment] + (points[segment + 1] - points[segment]) * fraction,
+			Self::CatmullRom(points) => {
+				let [p0, p1, p2, p3] = Self::control_points(points, segment);
+				let constant = p1 * 2.0;
+				let linear = p2 - p0;
+				let quadratic = p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3;
+				let cubic = p1 * 3.0 - p0 - p2 * 3.0 + p3;
+				(constant + (linear + (quadratic + cubic * fraction) * fraction) * fraction) * 0.5
+			},
+		}
+	}
+
+	/// Returns the unit direction of travel along the path at the given parameter.
+	#[must_use]
+	pub fn tangent(&self, parameter: Real) -> Vector3 {
+		if self.segments() == 0 {
+			return Vector3::zero();
+		}
+		let (segment, fraction) = self.segment(parameter);
+		match self {
+			Self::Polyline(points) => (points[segment + 1] - points[segment]).normalize(),
+			Self::CatmullRom(points) => {
+				let [p0, p1, p2, p3] = Self::control_points(points, segment);
+				let linear = p2 - p0;
+				let quadratic = p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3;
+				let cubic = p1 * 3.0 - p0 - p2 * 3.0 + p3;
+				(linear + (quadratic * 2.0 + cubic * (3.0 * fraction)) * fraction).normalize()
+			},
+		}
+	}
+
+	/// Returns the parameter of the point on the path closest to the given point.
+	#[must_use]
+	pub fn closest_parameter(&self, point: Vector3) -> Real {
+		match self {
+			Self::Polyline(points) => {
+				let mut best = (Real::MAX, 0.0);
+				for (segment, pair) in points.windows(2).enumerate() {
+					let along = pair[1] - pair[0];
+					let length_squared = along.magnitude_squared();
+					let t = if length_squared > 0.0 {
+						((point - pair[0]).dot(&along) / length_squared).clamp(0.0, 1.0)
+					} else {
+						0.0
+					};
+					let distance = (pair[0] + along * t - point).magnitude_squared();
+					if distance < best.0 {
+						best = (distance, real_from_count(segment) + t);
+					}
+				}
+				best.1
+			},
+			Self::CatmullRom(_) => self.closest_parameter_on_curve(point),
+		}
+	}
+
+	/// Finds the closest parameter on a curved path by sampling it
+	/// coarsely and then refining around the best sample.
+	fn closest_parameter_on_curve(&self, point: Vector3) -> Real {
+		let distance = |parameter: Real| (self.point(parameter) - point).magnitude_squared();
+		let samples = self.segments() * SAMPLES_PER_SEGMENT;
+		let step = real_from_count(SAMPLES_PER_SEGMENT).recip();
+		let mut best = 0.0;
+		for sample in 0..=samples {
+			let parameter = real_from_count(sample) * step;
+			if distance(parameter) < distance(best) {
+				best = parameter;
+			}
+		}
+
+		// Ternary search within one sample either side of the best sample
+		let (mut low, mut high) = ((best - step).max(0.0), (best + step).min(self.end()));
+		for _ in 0..REFINEMENT_STEPS {
+			let first = low + (high - low) / 3.0;
+			let second = high - (high - low) / 3.0;
+			if distance(first) < distance(second) {
+				high = second;
+			} else {
+				low = first;
+			}
+		}
+		(low + high) * 0.5
+	}
+}
+
+/// Restricts a particle to a path while leaving it free to move along it.
+///
+/// Applying the constraint moves the particle to the closest point on the path
+/// and removes any velocity that is not along the path. The parameter of that
+/// point is recorded, so cable cars and roller coasters can read how far along
+/// their track they are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathConstraint {
+	/// Holds the particle that is constrained.
+	pub particle: usize,
+
+	/// Holds the path the particle follows.
+	pub path: Path,
+
+	/// Holds the parameter of the particle's position along the path,
+	/// updated every time the constraint is applied.
+	pub parameter: Real,
+}
+
+impl PathConstraint {
+	#[must_use]
+	pub const fn new(particle: usize, path: Path) -> Self {
+		Self {
+			particle,
+			path,
+			parameter: 0.0,
+		}
+	}
+
+	/// Projects the particle onto the path and removes its velocity across the path.
+	/// Call this after integrating the particles each frame.
+	pub fn apply(&mut self, particles: &mut [Particle]) {
+		if !particles[self.particle].has_finite_mass() {
+			return;
+		}
+		self.snap(particles);
+		let tangent = self.path.tangent(self.parameter);
+		let particle = &mut particles[self.particle];
+		particle.velocity = tangent * tangent.dot(&particle.velocity);
+	}
+
+	/// Moves the particle to the closest point on the path and records its parameter.
+	fn snap(&mut self, particles: &mut [Particle]) {
+		let particle = &mut particles[self.particle];
+		self.parameter = self.path.closest_parameter(particle.position);
+		particle.position = self.path.point(self.parameter);
+	}
+}
+
+impl PositionConstraint for PathConstraint {
+	fn project(&mut self, particles: &mut [Particle], _previous_positions: &[Vector3], _duration: Real) {
+		if particles[self.particle].has_finite_mass() {
+			self.snap(particles);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::assert_equal;
+
+	use super::*;
+
+	fn corner() -> Vec<Vector3> {
+		vec![
+			Vector3::zero(),
+			Vector3::new(2.0, 0.0, 0.0),
+			Vector3::new(2.0, 2.0, 0.0),
+		]
+	}
+
+	#[test]
+	pub fn polyline_points() {
+		let path = Path::Polyline(corner());
+		assert_eq!(path.point(0.5), Vector3::x_axis());
+		assert_eq!(path.point(1.5), Vector3::new(2.0, 1.0, 0.0));
+		assert_eq!(path.point(5.0), Vector3::new(2.0, 2.0, 0.0));
+		assert_eq!(path.tangent(1.5), Vector3::y_axis());
+	}
+
+	#[test]
+	pub fn spline_passes_through_points() {
+		let path = Path::CatmullRom(corner());
+		for (index, point) in corner().iter().enumerate() {
+			assert_eq!(path.point(real_from_count(index)), *point);
+		}
+	}
+
+	#[test]
+	pub fn closest_parameter() {
+		let polyline = Path::Polyline(corner());
+		assert_equal(polyline.closest_parameter(Vector3::new(1.5, 0.25, 0.0)), 0.75);
+		let spline = Path::CatmullRom(corner());
+		let parameter = spline.closest_parameter(Vector3::new(3.0, 1.0, 0.0));
+		assert!((spline.point(parameter) - Vector3::new(3.0, 1.0, 0.0)).magnitude() < 1.0);
+		assert!(parameter > 1.0);
+	}
+
+	#[test]
+	pub fn constraint_keeps_velocity_along_path() {
+		let mut particles = [Particle {
+			position: Vector3::new(1.0, 1.0, 0.0),
+			velocity: Vector3::new(3.0, 4.0, 0.0),
+			inverse_mass: 1.0,
+			damping: 1.0,
+			..Default::default()
+		}];
+		let mut constraint = PathConstraint::new(0, Path::Polyline(corner()));
+		constraint.apply(&mut particles);
+		assert_equal(constraint.parameter, 0.5);
+		assert_eq!(particles[0].position, Vector3::x_axis());
+		assert_eq!(particles[0].velocity, Vector3::new(3.0, 0.0, 0.0));
+	}
+}
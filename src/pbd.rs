@@ -0,0 +1,508 @@
+use crate::{
+	cloth::Cloth,
+	forces::ParticleSpringLink,
+	groups::{resolve_group_contacts, solve_order, ConstraintGroup},
+	links::{ParticleCable, ParticleRod},
+	particle::Particle,
+	portable::powf,
+	rope::Rope,
+	vec::Vector3,
+	Real,
+};
+
+/// A constraint that is satisfied by moving particle positions directly,
+/// rather than by generating contacts or forces.
+pub trait PositionConstraint {
+	/// Called once at the start of every step, before any projections.
+	fn begin_step(&mut self) {}
+
+	/// Moves the constrained particles towards satisfying the constraint.
+	/// The positions the particles held at the start of the step are
+	/// given for constraints that depend on how far they have moved.
+	fn project(&mut self, particles: &mut [Particle], previous_positions: &[Vector3], duration: Real);
+}
+
+impl<T: PositionConstraint + ?Sized> PositionConstraint for Box<T> {
+	fn begin_step(&mut self) {
+		(**self).begin_step();
+	}
+
+	fn project(&mut self, particles: &mut [Particle], previous_positions: &[Vector3], duration: Real) {
+		(**self).project(particles, previous_positions, duration);
+	}
+}
+
+/// Keeps the distance between two particles within a range.
+///
+/// A rod keeps the particles at exactly one distance apart, while a cable
+/// only stops them moving further apart than its length.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DistanceConstraint {
+	/// Holds the pair of particles that are constrained.
+	pub particles: [usize; 2],
+
+	/// Holds the shortest allowed distance between the particles.
+	pub min_length: Real,
+
+	/// Holds the longest allowed distance between the particles.
+	pub max_length: Real,
+
+	/// Holds the fraction of the error corrected by each projection, from 0 to 1.
+	pub stiffness: Real,
+}
+
+impl DistanceConstraint {
+	/// Creates a constraint holding the particles exactly the given distance apart.
+	#[must_use]
+	pub const fn rod(particles: [usize; 2], length: Real) -> Self {
+		Self {
+			particles,
+			min_length: length,
+			max_length: length,
+			stiffness: 1.0,
+		}
+	}
+
+	/// Creates a constraint stopping the particles moving further apart than the given distance.
+	#[must_use]
+	pub const fn cable(particles: [usize; 2], max_length: Real) -> Self {
+		Self {
+			particles,
+			min_length: 0.0,
+			max_length,
+			stiffness: 1.0,
+		}
+	}
+
+	/// Returns how far the particles are from the allowed range, positive
+	/// when stretched and negative when compressed, and the unit direction
+	/// from the first particle to the second.
+	#[must_use]
+	pub fn error(&self, particles: &[Particle]) -> (Real, Vector3) {
+		let [first, second] = self.particles;
+		let delta = particles[second].position - particles[first].position;
+		let length = delta.magnitude();
+		let target = length.clamp(self.min_length, self.max_length);
+		(length - target, delta.normalize())
+	}
+}
+
+impl PositionConstraint for DistanceConstraint {
+	fn project(&mut self, particles: &mut [Particle], _previous_positions: &[Vector3], _duration: Real) {
+		let (error, direction) = self.error(particles);
+		if error == 0.0 {
+			return;
+		}
+
+		let [first, second] = self.particles;
+		let total_inverse_mass = particles[first].inverse_mass + particles[second].inverse_mass;
+		if total_inverse_mass <= 0.0 {
+			return;
+		}
+
+		// Each particle moves in proportion to its inverse mass
+		let correction = direction * (self.stiffness * error / total_inverse_mass);
+		let first_inverse_mass = particles[first].inverse_mass;
+		let second_inverse_mass = particles[second].inverse_mass;
+		particles[first].position += correction * first_inverse_mass;
+		particles[second].position -= correction * second_inverse_mass;
+	}
+}
+
+/// An XPBD distance constraint with physical compliance.
+///
+/// Compliance is the inverse of stiffness (metres per newton), so unlike the
+/// plain [`DistanceConstraint`] the resulting stiffness does not depend on the
+/// timestep or the number of solver iterations. A compliance of zero gives a
+/// perfectly rigid constraint, and an infinite compliance one that only damps.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CompliantDistanceConstraint {
+	/// Holds the pair of particles that are constrained.
+	pub particles: [usize; 2],
+
+	/// Holds the distance the constraint tries to maintain.
+	pub rest_length: Real,
+
+	/// Holds the inverse stiffness of the constraint.
+	pub compliance: Real,
+
+	/// Holds the damping coefficient opposing the relative
+	/// motion of the particles along the constraint.
+	pub damping: Real,
+
+	/// Holds the Lagrange multiplier accumulated during the current step.
+	pub lambda: Real,
+}
+
+impl CompliantDistanceConstraint {
+	#[must_use]
+	pub const fn new(particles: [usize; 2], rest_length: Real, compliance: Real) -> Self {
+		Self {
+			particles,
+			rest_length,
+			compliance,
+			damping: 0.0,
+			lambda: 0.0,
+		}
+	}
+}
+
+impl PositionConstraint for CompliantDistanceConstraint {
+	fn begin_step(&mut self) {
+		self.lambda = 0.0;
+	}
+
+	fn project(&mut self, particles: &mut [Particle], previous_positions: &[Vector3], duration: Real) {
+		let [first, second] = self.particles;
+		let first_inverse_mass = particles[first].inverse_mass;
+		let second_inverse_mass = particles[second].inverse_mass;
+		let total_inverse_mass = first_inverse_mass + second_inverse_mass;
+		if total_inverse_mass <= 0.0 {
+			return;
+		}
+
+		let delta = particles[second].position - particles[first].position;
+		let error = delta.magnitude() - self.rest_length;
+		// The gradient of the constraint with respect to the first particle
+		let gradient = -delta.normalize();
+
+		// How fast the constraint is changing, from the motion made during this step
+		let first_motion = particles[first].position - previous_positions[first];
+		let second_motion = particles[second].position - previous_positions[second];
+		let rate = gradient.dot(&(first_motion - second_motion));
+
+		let delta_lambda = if self.compliance.is_infinite() {
+			// Only the damping is left, as the update below tends to when divided through by the compliance
+			let beta = self.damping * duration;
+			beta.mul_add(-rate, -self.lambda) / beta.mul_add(total_inverse_mass, 1.0)
+		} else {
+			// Scale compliance and damping by the timestep so stiffness is timestep independent
+			let alpha = self.compliance / (duration * duration);
+			let gamma = alpha * self.damping * duration;
+			let numerator = gamma.mul_add(-rate, alpha.mul_add(-self.lambda, -error));
+			numerator / (1.0 + gamma).mul_add(total_inverse_mass, alpha)
+		};
+		self.lambda += delta_lambda;
+
+		let correction = gradient * delta_lambda;
+		particles[first].position += correction * first_inverse_mass;
+		particles[second].position -= correction * second_inverse_mass;
+	}
+}
+
+impl From<ParticleSpringLink> for CompliantDistanceConstraint {
+	/// Converts a spring into a constraint with the same physical stiffness
+	/// and damping. A spring with no stiffness becomes infinitely compliant,
+	/// acting as a damper alone.
+	fn from(spring: ParticleSpringLink) -> Self {
+		Self {
+			damping: spring.damping,
+			..Self::new(
+				spring.particles,
+				spring.rest_length,
+				if spring.spring_constant > 0.0 {
+					spring.spring_constant.recip()
+				} else {
+					Real::INFINITY
+				},
+			)
+		}
+	}
+}
+
+impl From<ParticleRod> for DistanceConstraint {
+	fn from(rod: ParticleRod) -> Self {
+		Self::rod(rod.particles, rod.length)
+	}
+}
+
+impl From<ParticleCable> for DistanceConstraint {
+	fn from(cable: ParticleCable) -> Self {
+		Self::cable(cable.particles, cable.max_length)
+	}
+}
+
+impl Rope {
+	/// Converts the rope's links into position constraints for the [`PositionSolver`].
+	#[must_use]
+	pub fn distance_constraints(&self) -> Vec<DistanceConstraint> {
+		self.rods
+			.iter()
+			.map(|rod| DistanceConstraint::from(*rod))
+			.chain(self.cables.iter().map(|cable| DistanceConstraint::from(*cable)))
+			.collect()
+	}
+}
+
+impl Cloth {
+	/// Converts the cloth's springs into rod constraints for the [`PositionSolver`],
+	/// scaling each constraint's stiffness by its spring constant relative to the stiffest spring.
+	#[must_use]
+	pub fn distance_constraints(&self) -> Vec<DistanceConstraint> {
+		let strongest = self
+			.springs()
+			.fold(0.0, |acc: Real, spring| acc.max(spring.spring_constant));
+		self.springs()
+			.map(|spring| DistanceConstraint {
+				stiffness: if strongest > 0.0 {
+					spring.spring_constant / strongest
+				} else {
+					1.0
+				},
+				..DistanceConstraint::rod(spring.particles, spring.rest_length)
+			})
+			.collect()
+	}
+
+	/// Converts the cloth's springs into XPBD constraints with the same
+	/// physical stiffness, which stays consistent as the timestep changes.
+	#[must_use]
+	pub fn compliant_constraints(&self) -> Vec<CompliantDistanceConstraint> {
+		self.springs()
+			.map(|spring| CompliantDistanceConstraint::from(*spring))
+			.collect()
+	}
+}
+
+/// A position-based dynamics solver.
+///
+/// Instead of resolving constraints through impulses, each step the particles
+/// are moved to predicted positions, the constraints are projected on those
+/// positions directly, and velocities are recovered from the distance moved.
+/// This stays stable for stiff ropes and cloth at game timesteps.
+#[derive(Debug, Default, Clone)]
+pub struct PositionSolver {
+	/// Holds the number of times every constraint is projected each step.
+	pub iterations: usize,
+
+	previous_positions: Vec<Vector3>,
+}
+
+impl PositionSolver {
+	#[must_use]
+	pub const fn new(iterations: usize) -> Self {
+		Self {
+			iterations,
+			previous_positions: Vec::new(),
+		}
+	}
+
+	/// Advances the particles by the given duration, projecting every
+	/// constraint onto the predicted positions.
+	pub fn step<C: PositionConstraint>(&mut self, particles: &mut [Particle], constraints: &mut [C], duration: Real) {
+		if duration <= 0.0 {
+			return;
+		}
+		self.predict(particles, duration);
+		for constraint in constraints.iter_mut() {
+			constraint.begin_step();
+		}
+		for _ in 0..self.iterations {
+			for constraint in constraints.iter_mut() {
+				constraint.project(particles, &self.previous_positions, duration);
+			}
+		}
+		self.update_velocities(particles, duration);
+	}
+
+	/// Advances the particles by the given duration, solving each group in
+	/// order with its own iteration budget instead of the solver's.
+	///
+	/// Position constraints are projected onto the predicted positions, and
+	/// once velocities have been recovered the groups' contacts are resolved.
+	pub fn step_groups(&mut self, particles: &mut [Particle], groups: &mut [ConstraintGroup], duration: Real) {
+		if duration <= 0.0 {
+			return;
+		}
+		self.predict(particles, duration);
+		for group in groups.iter_mut() {
+			group.begin_step();
+		}
+		for index in solve_order(groups) {
+			groups[index].project(particles, &self.previous_positions, duration);
+		}
+		self.update_velocities(particles, duration);
+		resolve_group_contacts(groups, particles, duration);
+	}
+
+	/// Integrates velocities and moves every particle to its predicted position,
+	/// remembering where it started.
+	pub fn predict(&mut self, particles: &mut [Particle], duration: Real) {
+		self.previous_positions.clear();
+		self.previous_positions
+			.extend(particles.iter().map(|particle| particle.position));
+		for particle in particles.iter_mut() {
+			if !particle.has_finite_mass() {
+				continue;
+			}
+			let acceleration = particle.acceleration + particle.force_accumulator * particle.inverse_mass;
+			particle.velocity += acceleration * duration;
+			particle.velocity *= powf(particle.damping, duration);
+			particle.position += particle.velocity * duration;
+			particle.force_accumulator = Vector3::zero();
+		}
+	}
+
+	/// Recovers each particle's velocity from how far it moved during the step.
+	pub fn update_velocities(&self, particles: &mut [Particle], duration: Real) {
+		for (particle, previous) in particles.iter_mut().zip(self.previous_positions.iter()) {
+			if particle.has_finite_mass() {
+				particle.velocity = (particle.position - *previous) * duration.recip();
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{assert_equal, RopeBuilder};
+
+	use super::*;
+
+	fn particles_at(positions: &[Vector3]) -> Vec<Particle> {
+		positions
+			.iter()
+			.map(|position| Particle {
+				position: *position,
+				inverse_mass: 1.0,
+				damping: 1.0,
+				..Default::default()
+			})
+			.collect()
+	}
+
+	#[test]
+	pub fn rod_projection_restores_length() {
+		let mut particles = particles_at(&[Vector3::zero(), Vector3::new(3.0, 0.0, 0.0)]);
+		DistanceConstraint::rod([0, 1], 1.0).project(&mut particles, &[], 1.0);
+		assert_eq!(particles[0].position, Vector3::new(1.0, 0.0, 0.0));
+		assert_eq!(particles[1].position, Vector3::new(2.0, 0.0, 0.0));
+	}
+
+	#[test]
+	pub fn slack_cable_is_not_projected() {
+		let mut particles = particles_at(&[Vector3::zero(), Vector3::new(0.5, 0.0, 0.0)]);
+		DistanceConstraint::cable([0, 1], 1.0).project(&mut particles, &[], 1.0);
+		assert_eq!(particles[1].position, Vector3::new(0.5, 0.0, 0.0));
+	}
+
+	#[test]
+	pub fn pinned_particle_is_not_moved() {
+		let mut particles = particles_at(&[Vector3::zero(), Vector3::new(2.0, 0.0, 0.0)]);
+		particles[0].inverse_mass = 0.0;
+		DistanceConstraint::rod([0, 1], 1.0).project(&mut particles, &[], 1.0);
+		assert_eq!(particles[0].position, Vector3::zero());
+		assert_eq!(particles[1].position, Vector3::x_axis());
+	}
+
+	#[test]
+	pub fn groups_use_their_own_iterations() {
+		let mut particles = particles_at(&[
+			Vector3::zero(),
+			Vector3::new(2.0, 0.0, 0.0),
+			Vector3::new(4.0, 0.0, 0.0),
+		]);
+		particles[0].inverse_mass = 0.0;
+		let half_stiff = |pair| DistanceConstraint {
+			stiffness: 0.5,
+			..DistanceConstraint::rod(pair, 1.0)
+		};
+		// The stiff group is solved last, so its constraint is satisfied exactly
+		let mut stiff = ConstraintGroup::new(1, 20);
+		stiff.add_constraint(half_stiff([0, 1]));
+		let mut soft = ConstraintGroup::new(0, 1);
+		soft.add_constraint(half_stiff([1, 2]));
+		let mut groups = [soft, stiff];
+		PositionSolver::new(0).step_groups(&mut particles, &mut groups, 1.0);
+		assert!((particles[1].position.x() - 1.0).abs() < 1e-4);
+		assert!(particles[2].position.x() > 2.0);
+	}
+
+	#[test]
+	pub fn hanging_rope_keeps_its_length() {
+		let mut particles = Vec::new();
+		let rope = RopeBuilder::new(Vector3::zero(), Vector3::new(5.0, 0.0, 0.0))
+			.segments(5)
+			.pin_start(true)
+			.build(&mut particles);
+		for particle in &mut particles {
+			particle.acceleration = Vector3::new(0.0, -10.0, 0.0);
+		}
+		let mut constraints = rope.distance_constraints();
+		let mut solver = PositionSolver::new(50);
+		for _ in 0..60 {
+			solver.step(&mut particles, &mut constraints, 1.0 / 60.0);
+		}
+		for constraint in &constraints {
+			assert!(constraint.error(&particles).0.abs() < 1e-3);
+		}
+		assert_equal(particles[0].position.y(), 0.0);
+		assert!(particles[5].position.y() < 0.0);
+	}
+
+	#[test]
+	pub fn rigid_compliant_constraint_restores_length() {
+		let mut particles = particles_at(&[Vector3::zero(), Vector3::new(3.0, 0.0, 0.0)]);
+		let previous = [Vector3::zero(), Vector3::new(3.0, 0.0, 0.0)];
+		let mut constraint = CompliantDistanceConstraint::new([0, 1], 1.0, 0.0);
+		constraint.project(&mut particles, &previous, 1.0 / 60.0);
+		assert_eq!(particles[0].position, Vector3::new(1.0, 0.0, 0.0));
+		assert_eq!(particles[1].position, Vector3::new(2.0, 0.0, 0.0));
+	}
+
+	#[test]
+	pub fn springs_without_stiffness_do_not_pull() {
+		let mut particles = particles_at(&[Vector3::zero(), Vector3::new(3.0, 0.0, 0.0)]);
+		let previous = [Vector3::zero(), Vector3::new(3.0, 0.0, 0.0)];
+		let mut constraint = CompliantDistanceConstraint::from(ParticleSpringLink {
+			particles: [0, 1],
+			spring_constant: 0.0,
+			damping: 0.5,
+			rest_length: 1.0,
+		});
+		assert!(constraint.compliance.is_infinite());
+		constraint.project(&mut particles, &previous, 1.0 / 60.0);
+		assert_eq!(particles[0].position, Vector3::zero());
+		assert_eq!(particles[1].position, Vector3::new(3.0, 0.0, 0.0));
+	}
+
+	#[test]
+	pub fn springs_without_stiffness_still_damp() {
+		// The particles move apart by 0.2 this step, which a damper of 30 halves
+		let mut particles = particles_at(&[Vector3::new(-0.1, 0.0, 0.0), Vector3::new(3.1, 0.0, 0.0)]);
+		let previous = [Vector3::zero(), Vector3::new(3.0, 0.0, 0.0)];
+		let mut constraint = CompliantDistanceConstraint::from(ParticleSpringLink {
+			particles: [0, 1],
+			spring_constant: 0.0,
+			damping: 30.0,
+			rest_length: 1.0,
+		});
+		let mut iterate = || constraint.project(&mut particles, &previous, 1.0 / 60.0);
+		iterate();
+		// And further iterations leave them be
+		iterate();
+		assert!((particles[0].position.x() + 0.05).abs() < 1e-5);
+		assert!((particles[1].position.x() - 3.05).abs() < 1e-5);
+	}
+
+	#[test]
+	pub fn compliance_is_timestep_independent() {
+		// A mass hanging from a compliant constraint should settle at the
+		// same spring extension regardless of the timestep used
+		let settle = |duration: Real, steps: usize| {
+			let mut particles = particles_at(&[Vector3::zero(), Vector3::new(0.0, -1.0, 0.0)]);
+			particles[0].inverse_mass = 0.0;
+			particles[1].acceleration = Vector3::new(0.0, -10.0, 0.0);
+			particles[1].damping = 0.5;
+			let mut constraints = [CompliantDistanceConstraint::new([0, 1], 1.0, 0.01)];
+			let mut solver = PositionSolver::new(10);
+			for _ in 0..steps {
+				solver.step(&mut particles, &mut constraints, duration);
+			}
+			particles[1].position.y()
+		};
+		// Extension under gravity is m * g * compliance = 0.1
+		assert!((settle(1.0 / 30.0, 120) + 1.1).abs() < 1e-2);
+		assert!((settle(1.0 / 120.0, 480) + 1.1).abs() < 1e-2);
+	}
+}
@@ -0,0 +1,57 @@
+//! The transcendental functions used while stepping, made portable with
+//! the `determinism` feature.
+//!
+//! Rust never fuses or reorders floating point operations on its own, and
+//! addition, multiplication, division, square roots, and `mul_add` are
+//! correctly rounded on every platform, so they give the same bits
+//! everywhere. Functions such as powers, sines, and arc tangents are left
+//! to the platform's math library, which may round differently from one
+//! machine to the next. With the `determinism` feature these are computed
+//! by the `libm` crate in plain Rust instead, so that a simulation run on
+//! one machine can be run again on another and stay in step, as lockstep
+//! multiplayer needs.
+
+use crate::Real;
+
+/// Returns the base raised to the given power.
+#[must_use]
+pub fn powf(base: Real, exponent: Real) -> Real {
+	#[cfg(feature = "determinism")]
+	return libm::powf(base, exponent);
+	#[cfg(not(feature = "determinism"))]
+	return base.powf(exponent);
+}
+
+/// Returns the angle in radians of the point (x, y) from the x axis, between -π and π.
+#[must_use]
+pub fn atan2(y: Real, x: Real) -> Real {
+	#[cfg(feature = "determinism")]
+	return libm::atan2f(y, x);
+	#[cfg(not(feature = "determinism"))]
+	return y.atan2(x);
+}
+
+/// Returns the sine and cosine of the angle in radians.
+#[must_use]
+pub fn sin_cos(angle: Real) -> (Real, Real) {
+	#[cfg(feature = "determinism")]
+	return libm::sincosf(angle);
+	#[cfg(not(feature = "determinism"))]
+	return angle.sin_cos();
+}
+
+#[cfg(test)]
+mod tests {
+	use std::f32::consts::FRAC_PI_4;
+
+	use super::*;
+
+	#[test]
+	pub fn portable_functions_match_the_platform() {
+		assert!((powf(0.99, 1.0 / 60.0) - 0.99_f32.powf(1.0 / 60.0)).abs() < 1e-6);
+		assert!((atan2(1.0, 1.0) - FRAC_PI_4).abs() < 1e-6);
+		let (sin, cos) = sin_cos(FRAC_PI_4);
+		assert!((sin - FRAC_PI_4.sin()).abs() < 1e-6);
+		assert!((cos - FRAC_PI_4.cos()).abs() < 1e-6);
+	}
+}
@@ -0,0 +1,203 @@
+use crate::{portable::sin_cos, reals_are_equal, vec::Vector3, Real};
+use std::ops::{Mul, MulAssign};
+
+/// A quaternion, used to hold the orientation of a rigid body.
+///
+/// Orientation quaternions are kept at unit length. The `w` component is the
+/// real part, and `x`, `y`, and `z` are the imaginary parts.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct Quaternion {
+	pub w: Real,
+	pub x: Real,
+	pub y: Real,
+	pub z: Real,
+}
+
+impl Default for Quaternion {
+	fn default() -> Self {
+		Self::identity()
+	}
+}
+
+impl PartialEq for Quaternion {
+	fn eq(&self, rhs: &Self) -> bool {
+		reals_are_equal(self.w, rhs.w)
+			&& reals_are_equal(self.x, rhs.x)
+			&& reals_are_equal(self.y, rhs.y)
+			&& reals_are_equal(self.z, rhs.z)
+	}
+}
+
+impl Mul for Quaternion {
+	type Output = Self;
+
+	fn mul(self, rhs: Self) -> Self::Output {
+		let vector = self.vector();
+		let rhs_vector = rhs.vector();
+		let imaginary = rhs_vector * self.w + vector * rhs.w + vector.cross(&rhs_vector);
+		Self::new(
+			self.w.mul_add(rhs.w, -vector.dot(&rhs_vector)),
+			imaginary.x(),
+			imaginary.y(),
+			imaginary.z(),
+		)
+	}
+}
+
+impl MulAssign for Quaternion {
+	fn mul_assign(&mut self, rhs: Self) {
+		*self = *self * rhs;
+	}
+}
+
+impl Quaternion {
+	#[must_use]
+	pub const fn new(w: Real, x: Real, y: Real, z: Real) -> Self {
+		Self { w, x, y, z }
+	}
+
+	/// Creates the quaternion representing no rotation.
+	#[must_use]
+	pub const fn identity() -> Self {
+		Self::new(1.0, 0.0, 0.0, 0.0)
+	}
+
+	/// Creates a rotation by the given angle in radians about the given axis.
+	#[must_use]
+	pub fn from_axis_angle(axis: Vector3, angle: Real) -> Self {
+		let (sin, cos) = sin_cos(angle * 0.5);
+		let axis = axis.normalize() * sin;
+		Self::new(cos, axis.x(), axis.y(), axis.z())
+	}
+
+	/// Returns the imaginary part of the quaternion as a vector.
+	#[must_use]
+	pub const fn vector(&self) -> Vector3 {
+		Vector3::new(self.x, self.y, self.z)
+	}
+
+	#[must_use]
+	pub fn magnitude_squared(&self) -> Real {
+		self.w
+			.mul_add(self.w, self.x.mul_add(self.x, self.y.mul_add(self.y, self.z * self.z)))
+	}
+
+	/// Returns the quaternion scaled to unit length. A zero quaternion
+	/// becomes the identity, as it cannot represent any orientation.
+	#[must_use]
+	pub fn normalize(&self) -> Self {
+		let magnitude_squared = self.magnitude_squared();
+		if magnitude_squared <= 0.0 {
+			return Self::identity();
+		}
+		let scale = magnitude_squared.sqrt().recip();
+		Self::new(self.w * scale, self.x * scale, self.y * scale, self.z * scale)
+	}
+
+	/// Returns the conjugate, which for a unit quaternion is the inverse rotation.
+	#[must_use]
+	pub const fn conjugate(&self) -> Self {
+		Self::new(self.w, -self.x, -self.y, -self.z)
+	}
+
+	/// Rotates the given vector by this unit quaternion.
+	#[must_use]
+	pub fn rotate(&self, vector: Vector3) -> Vector3 {
+		let imaginary = self.vector();
+		let twice_cross = imaginary.cross(&vector) * 2.0;
+		vector + twice_cross * self.w + imaginary.cross(&twice_cross)
+	}
+
+	/// Blends from this unit quaternion to the other by the given fraction,
+	/// along the shorter way round, and normalizes the result.
+	///
+	/// This is close to spherical interpolation for the small rotations
+	/// between one step and the next, and much cheaper.
+	#[must_use]
+	pub fn nlerp(&self, other: &Self, fraction: Real) -> Self {
+		let dot = self.w.mul_add(
+			other.w,
+			self.x.mul_add(other.x, self.y.mul_add(other.y, self.z * other.z)),
+		);
+		let sign = if dot < 0.0 { -1.0 } else { 1.0 };
+		let blend = |from: Real, to: Real| to.mul_add(sign, -from).mul_add(fraction, from);
+		Self::new(
+			blend(self.w, other.w),
+			blend(self.x, other.x),
+			blend(self.y, other.y),
+			blend(self.z, other.z),
+		)
+		.normalize()
+	}
+
+	/// Adds the given vector, scaled by the given amount, as a rotation.
+	///
+	/// This is used to update an orientation by an angular velocity over a
+	/// timestep. The result is not normalized.
+	pub fn add_scaled_vector(&mut self, vector: Vector3, scale: Real) {
+		let scaled = vector * scale;
+		let change = Self::new(0.0, scaled.x(), scaled.y(), scaled.z()) * *self;
+		self.w = change.w.mul_add(0.5, self.w);
+		self.x = change.x.mul_add(0.5, self.x);
+		self.y = change.y.mul_add(0.5, self.y);
+		self.z = change.z.mul_add(0.5, self.z);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::f32::consts::FRAC_PI_2;
+
+	use crate::assert_equal;
+
+	use super::*;
+
+	#[test]
+	pub fn identity_does_not_rotate() {
+		let vector = Vector3::new(1.0, 2.0, 3.0);
+		assert_eq!(Quaternion::default().rotate(vector), vector);
+	}
+
+	#[test]
+	pub fn rotate() {
+		let quarter_turn = Quaternion::from_axis_angle(Vector3::z_axis(), FRAC_PI_2);
+		assert_eq!(quarter_turn.rotate(Vector3::x_axis()), Vector3::y_axis());
+		assert_eq!(quarter_turn.conjugate().rotate(Vector3::y_axis()), Vector3::x_axis());
+	}
+
+	#[test]
+	pub fn multiply_composes_rotations() {
+		let quarter_turn = Quaternion::from_axis_angle(Vector3::z_axis(), FRAC_PI_2);
+		let half_turn = quarter_turn * quarter_turn;
+		assert!((half_turn.rotate(Vector3::x_axis()) + Vector3::x_axis()).magnitude() < 1e-6);
+	}
+
+	#[test]
+	pub fn normalize() {
+		let quaternion = Quaternion::new(2.0, 0.0, 0.0, 0.0).normalize();
+		assert_eq!(quaternion, Quaternion::identity());
+		assert_equal(Quaternion::new(1.0, 1.0, 1.0, 1.0).normalize().magnitude_squared(), 1.0);
+		assert_eq!(Quaternion::new(0.0, 0.0, 0.0, 0.0).normalize(), Quaternion::identity());
+	}
+
+	#[test]
+	pub fn nlerp_takes_the_shorter_way() {
+		let start = Quaternion::identity();
+		let end = Quaternion::from_axis_angle(Vector3::z_axis(), FRAC_PI_2);
+		let halfway = Quaternion::from_axis_angle(Vector3::z_axis(), FRAC_PI_2 * 0.5);
+		assert_eq!(start.nlerp(&end, 0.5), halfway);
+
+		// The negated quaternion is the same rotation, and is blended the same way
+		let negated = Quaternion::new(-end.w, -end.x, -end.y, -end.z);
+		assert_eq!(start.nlerp(&negated, 0.5), halfway);
+		assert_eq!(start.nlerp(&end, 1.0), end);
+	}
+
+	#[test]
+	pub fn add_scaled_vector() {
+		let mut quaternion = Quaternion::identity();
+		quaternion.add_scaled_vector(Vector3::new(0.0, 0.0, 2.0), 0.5);
+		assert_eq!(quaternion, Quaternion::new(1.0, 0.0, 0.0, 0.5));
+	}
+}
@@ -0,0 +1,504 @@
+use crate::{
+	bounding::Aabb,
+	broad_phase::{BroadPhase, CollisionGroups},
+	collide::{CollisionBox, CollisionCapsule, CollisionCylinder, CollisionSphere},
+	collider::Collider,
+	convex::{closest_points, intersects, penetration, ConvexHull, ShapeHit, SupportMap},
+	matrix::Matrix4,
+	shape::Shape,
+	trimesh::{closest_point_on_segment, TriMesh},
+	vec::Vector3,
+	Real,
+};
+
+/// The point on the surface of a shape nearest to a given point.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PointProjection {
+	/// Holds the point on the surface, in world coordinates.
+	pub point: Vector3,
+
+	/// Holds whether the given point was inside the shape.
+	pub is_inside: bool,
+}
+
+/// A collider found by a query of a [`PhysicsWorld`](crate::world::PhysicsWorld).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct QueryHit {
+	/// Holds the index of the collider found.
+	pub collider: usize,
+
+	/// Holds the index of the body the collider is attached to.
+	pub body: usize,
+
+	/// Holds the user data of the body, to map it back to its game entity.
+	pub user_data: u64,
+}
+
+/// Where a ray cast into a [`PhysicsWorld`](crate::world::PhysicsWorld)
+/// first hits a collider.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+	/// Holds the index of the collider hit.
+	pub collider: usize,
+
+	/// Holds the index of the body the collider is attached to.
+	pub body: usize,
+
+	/// Holds the user data of the body, to map it back to its game entity.
+	pub user_data: u64,
+
+	pub hit: ShapeHit,
+}
+
+/// Point queries against a shape in world coordinates, used to check that
+/// a spawn position is clear and to snap objects onto surfaces.
+pub trait PointQuery {
+	/// Returns the point on the surface of the shape nearest to the given point.
+	fn project_point(&self, point: Vector3) -> PointProjection;
+
+	/// Returns true if the point is inside the shape or on its surface.
+	fn contains_point(&self, point: Vector3) -> bool {
+		self.project_point(point).is_inside
+	}
+
+	/// Returns the point in or on the shape nearest to the given point,
+	/// which is the point itself if it is inside.
+	fn closest_point(&self, point: Vector3) -> Vector3 {
+		let projection = self.project_point(point);
+		if projection.is_inside {
+			point
+		} else {
+			projection.point
+		}
+	}
+}
+
+/// Projects a point onto a ball, pushing a point at its center straight up.
+fn ball_projection(center: Vector3, radius: Real, point: Vector3) -> PointProjection {
+	let offset = point - center;
+	let distance = offset.magnitude();
+	let direction = if distance > 0.0 {
+		offset * distance.recip()
+	} else {
+		Vector3::y_axis()
+	};
+	PointProjection {
+		point: center + direction * radius,
+		is_inside: distance <= radius,
+	}
+}
+
+impl PointQuery for CollisionSphere {
+	fn project_point(&self, point: Vector3) -> PointProjection {
+		ball_projection(self.primitive.position(), self.radius, point)
+	}
+}
+
+impl PointQuery for CollisionCapsule {
+	fn project_point(&self, point: Vector3) -> PointProjection {
+		let center = closest_point_on_segment(self.segment(), point);
+		ball_projection(center, self.radius, point)
+	}
+}
+
+impl PointQuery for CollisionBox {
+	/// A point inside the box is pushed out through the nearest face.
+	fn project_point(&self, point: Vector3) -> PointProjection {
+		let transform = self.primitive.transform();
+		let local = transform.transform_inverse(point);
+		let is_inside = (0..3).all(|axis| local[axis].abs() <= self.half_size[axis]);
+		let mut surface = local;
+		if is_inside {
+			let axis = (0..3)
+				.min_by(|first, second| {
+					let room = |axis: usize| self.half_size[axis] - local[axis].abs();
+					room(*first).total_cmp(&room(*second))
+				})
+				.unwrap_or(0);
+			surface[axis] = self.half_size[axis].copysign(local[axis]);
+		} else {
+			for axis in 0..3 {
+				surface[axis] = local[axis].clamp(-self.half_size[axis], self.half_size[axis]);
+			}
+		}
+		PointProjection {
+			point: transform.transform(surface),
+			is_inside,
+		}
+	}
+}
+
+impl PointQuery for CollisionCylinder {
+	/// A point inside the cylinder is pushed out through the side or the
+	/// cap, whichever is nearer.
+	fn project_point(&self, point: Vector3) -> PointProjection {
+		let transform = self.primitive.transform();
+		let local = transform.transform_inverse(point);
+		let radial = Vector3::new(local.x(), 0.0, local.z());
+		let distance = radial.magnitude();
+		let outward = if distance > 0.0 {
+			radial * distance.recip()
+		} else {
+			Vector3::x_axis()
+		};
+		let height = local.y();
+		let is_inside = distance <= self.radius && height.abs() <= self.half_height;
+		let surface = if !is_inside {
+			outward * distance.min(self.radius)
+				+ Vector3::new(0.0, height.clamp(-self.half_height, self.half_height), 0.0)
+		} else if self.radius - distance < self.half_height - height.abs() {
+			outward * self.radius + Vector3::new(0.0, height, 0.0)
+		} else {
+			radial + Vector3::new(0.0, self.half_height.copysign(height), 0.0)
+		};
+		PointProjection {
+			point: transform.transform(surface),
+			is_inside,
+		}
+	}
+}
+
+impl PointQuery for ConvexHull {
+	fn project_point(&self, point: Vector3) -> PointProjection {
+		if let Some(closest) = closest_points(&point, self) {
+			return PointProjection {
+				point: closest.points[1],
+				is_inside: false,
+			};
+		}
+		// Moving the point back along the normal by the depth brings it to the surface
+		let surface = penetration(&point, self).map_or(point, |overlap| point - overlap.normal * overlap.depth);
+		PointProjection {
+			point: surface,
+			is_inside: true,
+		}
+	}
+}
+
+impl PointQuery for Collider {
+	fn project_point(&self, point: Vector3) -> PointProjection {
+		match self {
+			Self::Sphere(sphere) => sphere.project_point(point),
+			Self::Box(cuboid) => cuboid.project_point(point),
+			Self::Capsule(capsule) => capsule.project_point(point),
+			Self::Cylinder(cylinder) => cylinder.project_point(point),
+		}
+	}
+}
+
+impl PointQuery for TriMesh {
+	/// The point is inside if it lies behind the nearest triangle, which is
+	/// only meaningful for a closed mesh. An empty mesh projects every point
+	/// onto itself.
+	fn project_point(&self, point: Vector3) -> PointProjection {
+		if self.is_empty() {
+			return PointProjection {
+				point,
+				is_inside: false,
+			};
+		}
+		// Any triangle bounds the search, and only triangles within that reach can be nearer
+		let reach = (self.triangle(0).closest_point(point) - point).magnitude();
+		let region = Aabb::from_center(point, Vector3::new(reach, reach, reach));
+		let (triangle, surface) = self
+			.query(&region)
+			.into_iter()
+			.map(|index| {
+				let triangle = self.triangle(index);
+				(triangle, triangle.closest_point(point))
+			})
+			.min_by(|first, second| {
+				(first.1 - point)
+					.magnitude_squared()
+					.total_cmp(&(second.1 - point).magnitude_squared())
+			})
+			.unwrap_or_else(|| (self.triangle(0), self.triangle(0).closest_point(point)));
+		PointProjection {
+			point: surface,
+			is_inside: triangle.normal().dot(&(point - surface)) < 0.0,
+		}
+	}
+}
+
+/// Returns the indices of the colliders that contain the point and that
+/// the filter accepts, in order.
+pub fn point_query(point: Vector3, colliders: &[Collider], filter: impl Fn(&Collider) -> bool) -> Vec<usize> {
+	colliders
+		.iter()
+		.enumerate()
+		.filter(|(_, collider)| filter(collider) && collider.contains_point(point))
+		.map(|(index, _)| index)
+		.collect()
+}
+
+/// Returns the index of the collider accepted by the filter whose surface
+/// is nearest the point, with the projection onto it.
+pub fn nearest_collider(
+	point: Vector3,
+	colliders: &[Collider],
+	filter: impl Fn(&Collider) -> bool,
+) -> Option<(usize, PointProjection)> {
+	colliders
+		.iter()
+		.enumerate()
+		.filter(|(_, collider)| filter(collider))
+		.map(|(index, collider)| (index, collider.project_point(point)))
+		.min_by(|first, second| {
+			(first.1.point - point)
+				.magnitude_squared()
+				.total_cmp(&(second.1.point - point).magnitude_squared())
+		})
+}
+
+/// Returns the indices of the colliders that overlap the box and whose
+/// bodies' groups interact with the given groups, in order.
+///
+/// This serves explosion damage, awareness zones and other regions tested
+/// every frame. The broad phase, which holds the bounds and groups of each collider's
+/// body, rules out most colliders before the rest are tested exactly.
+#[must_use]
+pub fn intersections_with_aabb(
+	broad_phase: &dyn BroadPhase,
+	colliders: &[Collider],
+	bounds: &Aabb,
+	groups: CollisionGroups,
+) -> Vec<usize> {
+	intersections_with(broad_phase, colliders, bounds, groups)
+}
+
+/// Returns the indices of the colliders that overlap the shape held at the
+/// given transform and whose bodies' groups interact with the given groups, in order.
+#[must_use]
+pub fn intersections_with_shape(
+	broad_phase: &dyn BroadPhase,
+	colliders: &[Collider],
+	shape: &Shape,
+	transform: &Matrix4,
+	groups: CollisionGroups,
+) -> Vec<usize> {
+	let region = Collider::from_shape_at(shape, transform);
+	intersections_with(broad_phase, colliders, &region, groups)
+}
+
+fn intersections_with<S: SupportMap + ?Sized>(
+	broad_phase: &dyn BroadPhase,
+	colliders: &[Collider],
+	region: &S,
+	groups: CollisionGroups,
+) -> Vec<usize> {
+	let mut bodies = broad_phase.query(&region.bounds(), groups);
+	bodies.sort_unstable();
+	colliders
+		.iter()
+		.enumerate()
+		.filter(|(_, collider)| bodies.binary_search(&collider.primitive().body).is_ok())
+		.filter(|(_, collider)| intersects(*collider, region))
+		.map(|(index, _)| index)
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::f32::consts::FRAC_PI_2;
+
+	use crate::{body::RigidBody, collide::CollisionPrimitive, quaternion::Quaternion, shape::Shape};
+
+	use super::*;
+
+	fn collider(shape: Shape, position: Vector3, orientation: Quaternion) -> Collider {
+		let mut body = RigidBody {
+			position,
+			orientation,
+			inverse_mass: 1.0,
+			..Default::default()
+		};
+		body.calculate_derived_data();
+		let mut collider = Collider::from_shape(&shape, CollisionPrimitive::new(0));
+		collider.calculate_internals(&[body]);
+		collider
+	}
+
+	fn is_near(actual: Vector3, expected: Vector3) -> bool {
+		(actual - expected).magnitude() < 1e-3
+	}
+
+	#[test]
+	pub fn sphere_and_capsule_points() {
+		let ball = collider(Shape::Sphere { radius: 1.0 }, Vector3::zero(), Quaternion::default());
+		assert!(ball.contains_point(Vector3::new(0.5, 0.5, 0.0)));
+		assert!(!ball.contains_point(Vector3::new(1.0, 1.0, 0.0)));
+		assert!(is_near(
+			ball.closest_point(Vector3::new(3.0, 0.0, 0.0)),
+			Vector3::x_axis()
+		));
+		assert!(is_near(
+			ball.project_point(Vector3::new(0.0, 0.0, 0.5)).point,
+			Vector3::z_axis()
+		));
+
+		// Lying along x, the capsule reaches to 1.5 either side of its center
+		let capsule = collider(
+			Shape::Capsule {
+				radius: 0.5,
+				half_height: 1.0,
+			},
+			Vector3::zero(),
+			Quaternion::from_axis_angle(Vector3::z_axis(), FRAC_PI_2),
+		);
+		assert!(capsule.contains_point(Vector3::new(1.4, 0.0, 0.0)));
+		assert!(!capsule.contains_point(Vector3::new(0.0, 0.6, 0.0)));
+		assert!(is_near(
+			capsule.closest_point(Vector3::new(0.5, 2.0, 0.0)),
+			Vector3::new(0.5, 0.5, 0.0)
+		));
+	}
+
+	#[test]
+	pub fn box_and_cylinder_points() {
+		let cube = Shape::Cuboid {
+			half_size: Vector3::new(1.0, 1.0, 1.0),
+		};
+		let cuboid = collider(cube, Vector3::new(0.0, 1.0, 0.0), Quaternion::default());
+		assert!(cuboid.contains_point(Vector3::new(0.5, 1.5, 0.5)));
+		assert!(is_near(
+			cuboid.closest_point(Vector3::new(3.0, 1.5, 0.0)),
+			Vector3::new(1.0, 1.5, 0.0)
+		));
+		let inside = cuboid.project_point(Vector3::new(0.1, 1.8, 0.0));
+		assert!(inside.is_inside);
+		assert!(is_near(inside.point, Vector3::new(0.1, 2.0, 0.0)));
+
+		let cylinder = collider(
+			Shape::Cylinder {
+				radius: 1.0,
+				half_height: 2.0,
+			},
+			Vector3::zero(),
+			Quaternion::default(),
+		);
+		assert!(cylinder.contains_point(Vector3::new(0.6, 1.9, 0.6)));
+		assert!(!cylinder.contains_point(Vector3::new(0.8, 0.0, 0.8)));
+		assert!(is_near(
+			cylinder.closest_point(Vector3::new(0.0, 5.0, 3.0)),
+			Vector3::new(0.0, 2.0, 1.0)
+		));
+		assert!(is_near(
+			cylinder.project_point(Vector3::new(0.0, 1.9, 0.5)).point,
+			Vector3::new(0.0, 2.0, 0.5)
+		));
+		assert!(is_near(
+			cylinder.project_point(Vector3::new(0.9, 0.0, 0.0)).point,
+			Vector3::x_axis()
+		));
+	}
+
+	#[test]
+	pub fn hull_and_mesh_points() {
+		let hull = ConvexHull {
+			primitive: CollisionPrimitive::new(0),
+			points: vec![
+				Vector3::zero(),
+				Vector3::new(2.0, 0.0, 0.0),
+				Vector3::new(0.0, 2.0, 0.0),
+				Vector3::new(0.0, 0.0, 2.0),
+			],
+		};
+		assert!(hull.contains_point(Vector3::new(0.2, 0.2, 0.2)));
+		assert!(!hull.contains_point(Vector3::new(1.0, 1.0, 1.0)));
+		assert!(is_near(
+			hull.closest_point(Vector3::new(0.5, 0.5, -1.0)),
+			Vector3::new(0.5, 0.5, 0.0)
+		));
+		assert!(is_near(
+			hull.project_point(Vector3::new(0.5, 0.5, 0.1)).point,
+			Vector3::new(0.5, 0.5, 0.0)
+		));
+
+		let floor = TriMesh::new(
+			vec![
+				Vector3::new(-2.0, 0.0, -2.0),
+				Vector3::new(-2.0, 0.0, 2.0),
+				Vector3::new(2.0, 0.0, 2.0),
+				Vector3::new(2.0, 0.0, -2.0),
+			],
+			vec![[0, 1, 2], [0, 2, 3]],
+		);
+		let above = floor.project_point(Vector3::new(1.0, 3.0, -1.0));
+		assert!(!above.is_inside);
+		assert!(is_near(above.point, Vector3::new(1.0, 0.0, -1.0)));
+		assert!(floor.contains_point(Vector3::new(0.5, -0.5, 0.5)));
+	}
+
+	#[test]
+	pub fn region_overlaps() {
+		use crate::broad_phase::BvhBroadPhase;
+
+		let positions = [
+			Vector3::zero(),
+			Vector3::new(2.5, 0.0, 0.0),
+			Vector3::new(5.0, 0.0, 0.0),
+			Vector3::new(2.4, 2.4, 0.0),
+		];
+		let mut bodies: Vec<RigidBody> = positions
+			.iter()
+			.map(|position| RigidBody {
+				position: *position,
+				..Default::default()
+			})
+			.collect();
+		let mut broad_phase = BvhBroadPhase::default();
+		let mut colliders = Vec::new();
+		for (index, body) in bodies.iter_mut().enumerate() {
+			body.calculate_derived_data();
+			colliders.push(Collider::from_shape(
+				&Shape::Sphere { radius: 1.0 },
+				CollisionPrimitive::new(index),
+			));
+		}
+		for collider in &mut colliders {
+			collider.calculate_internals(&bodies);
+			broad_phase.insert(collider.primitive().body, collider.bounds());
+		}
+		broad_phase.set_groups(1, CollisionGroups::new(2, u32::MAX));
+
+		// The box reaches the corner of the bounds of the sphere up and to the side, but not the sphere itself
+		let blast = Aabb::from_center(Vector3::zero(), Vector3::new(1.6, 1.6, 1.6));
+		let everyone = CollisionGroups::default();
+		assert_eq!(
+			intersections_with_aabb(&broad_phase, &colliders, &blast, everyone),
+			vec![0, 1]
+		);
+		assert_eq!(
+			intersections_with_aabb(&broad_phase, &colliders, &blast, CollisionGroups::new(u32::MAX, 1)),
+			vec![0]
+		);
+
+		let zone = Shape::Sphere { radius: 1.6 };
+		let at = Matrix4::from_transform(&Quaternion::default(), Vector3::new(5.0, 0.0, 0.0));
+		assert_eq!(
+			intersections_with_shape(&broad_phase, &colliders, &zone, &at, everyone),
+			vec![1, 2]
+		);
+	}
+
+	#[test]
+	pub fn queries_over_colliders() {
+		let colliders = [
+			collider(Shape::Sphere { radius: 1.0 }, Vector3::zero(), Quaternion::default()),
+			collider(Shape::default(), Vector3::new(0.5, 0.0, 0.0), Quaternion::default()),
+			collider(Shape::default(), Vector3::new(5.0, 0.0, 0.0), Quaternion::default()),
+		];
+		let point = Vector3::new(0.8, 0.0, 0.0);
+		assert_eq!(point_query(point, &colliders, |_| true), vec![0, 1]);
+		assert_eq!(
+			point_query(point, &colliders, |collider| matches!(collider, Collider::Box(_))),
+			vec![1]
+		);
+
+		let spawn = Vector3::new(3.5, 0.0, 0.0);
+		let (nearest, projection) = nearest_collider(spawn, &colliders, |_| true).expect("there are colliders");
+		assert_eq!(nearest, 2);
+		assert!(is_near(projection.point, Vector3::new(4.5, 0.0, 0.0)));
+		assert!(nearest_collider(spawn, &colliders, |_| false).is_none());
+	}
+}
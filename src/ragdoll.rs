@@ -0,0 +1,397 @@
+use std::{f32::consts::PI, ops::Range};
+
+use crate::{
+	body::{RigidBody, RigidBodyBuilder},
+	broad_phase::ContactFilter,
+	cloth::SpringStiffness,
+	collide::CollisionPrimitive,
+	collider::Collider,
+	joint::{FixedJoint, HingeJoint, Joint, JointLimit, SpringAxis, SpringJoint},
+	portable::atan2,
+	quaternion::Quaternion,
+	shape::Shape,
+	vec::Vector3,
+	Real,
+};
+
+/// How a bone is joined to its parent, at the bone's first end.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoneJoint {
+	/// A ball and socket, sprung back toward the rest pose at the given
+	/// frequency in hertz and damping ratio, as a spine or shoulder bends
+	/// and gives. A frequency of zero leaves the bone free to turn.
+	Ball { frequency: Real, damping_ratio: Real },
+
+	/// A hinge about the given world axis of the rest pose, turning through
+	/// the given range of angles from it, as a knee or elbow bends one way only.
+	Hinge { axis: Vector3, limit: JointLimit },
+
+	/// A weld, holding the bone rigidly to its parent.
+	Fixed,
+}
+
+/// A bone of a ragdoll's skeleton: a capsule running between two points of
+/// the rest pose, joined to its parent at the first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bone {
+	/// Holds the index of the parent bone, which must come before this
+	/// one, or `None` for the root.
+	pub parent: Option<usize>,
+
+	/// Holds how the bone is joined to its parent.
+	pub joint: BoneJoint,
+
+	/// Holds the ends of the bone, relative to the ragdoll's position.
+	pub ends: [Vector3; 2],
+
+	pub radius: Real,
+}
+
+impl Bone {
+	/// Creates the root bone, which has no parent.
+	#[must_use]
+	pub const fn root(ends: [Vector3; 2], radius: Real) -> Self {
+		Self {
+			parent: None,
+			joint: BoneJoint::Fixed,
+			ends,
+			radius,
+		}
+	}
+
+	/// Creates a bone joined to the given parent bone.
+	#[must_use]
+	pub const fn child(parent: usize, joint: BoneJoint, ends: [Vector3; 2], radius: Real) -> Self {
+		Self {
+			parent: Some(parent),
+			joint,
+			ends,
+			radius,
+		}
+	}
+
+	#[must_use]
+	pub fn length(&self) -> Real {
+		(self.ends[1] - self.ends[0]).magnitude()
+	}
+
+	/// Returns the capsule spanning the bone from end to end. A bone no
+	/// longer than it is wide is a ball.
+	#[must_use]
+	pub fn shape(&self) -> Shape {
+		Shape::Capsule {
+			radius: self.radius,
+			half_height: self.length().mul_add(0.5, -self.radius).max(0.0),
+		}
+	}
+
+	/// Returns the rotation turning the capsule's y axis along the bone.
+	fn orientation(&self) -> Quaternion {
+		let direction = (self.ends[1] - self.ends[0]).normalize();
+		let axis = Vector3::y_axis().cross(&direction);
+		let dot = Vector3::y_axis().dot(&direction);
+		if axis.magnitude() > Real::EPSILON {
+			Quaternion::from_axis_angle(axis, atan2(axis.magnitude(), dot))
+		} else if dot < 0.0 {
+			Quaternion::from_axis_angle(Vector3::x_axis(), PI)
+		} else {
+			Quaternion::identity()
+		}
+	}
+}
+
+/// Builds a ragdoll from a skeleton of bones, creating a capsule body for
+/// each bone and a joint tying each to its parent.
+///
+/// Bones are given in the rest pose, relative to `position`, with each
+/// parent before its children.
+#[derive(Debug, Clone)]
+pub struct RagdollBuilder {
+	position: Vector3,
+	bones: Vec<Bone>,
+	density: Real,
+	acceleration: Vector3,
+}
+
+impl RagdollBuilder {
+	#[must_use]
+	pub const fn new(position: Vector3) -> Self {
+		Self {
+			position,
+			bones: Vec::new(),
+			density: 1.0,
+			acceleration: Vector3::new(0.0, 0.0, 0.0),
+		}
+	}
+
+	/// Creates a builder for a person of the given height, standing with
+	/// their feet at `position`, facing along the positive z axis with
+	/// their left toward the positive x axis and their arms at their sides.
+	///
+	/// The bones are, in order: pelvis, abdomen, chest, head, left upper
+	/// arm, left forearm, right upper arm, right forearm, left thigh, left
+	/// shin, right thigh, and right shin. Knees and elbows are hinges that
+	/// bend only the natural way, and the rest are sprung ball joints.
+	#[must_use]
+	pub fn humanoid(position: Vector3, height: Real) -> Self {
+		let point = |x: Real, y: Real| Vector3::new(x, y, 0.0) * height;
+		let ball = |frequency: Real| BoneJoint::Ball {
+			frequency,
+			damping_ratio: 1.0,
+		};
+		// Elbows bend forward and knees back
+		let hinge = |axis: Vector3, upper: Real| BoneJoint::Hinge {
+			axis,
+			limit: JointLimit::new(0.0, upper),
+		};
+		let elbow = hinge(-Vector3::x_axis(), 2.6);
+		let knee = hinge(Vector3::x_axis(), 2.4);
+
+		// Each bone's parent, joint, and across, from, to, and radius as fractions of the height
+		let skeleton = [
+			(None, BoneJoint::Fixed, [0.0, 0.48, 0.58, 0.07]),
+			(Some(0), ball(3.0), [0.0, 0.58, 0.68, 0.065]),
+			(Some(1), ball(3.0), [0.0, 0.68, 0.82, 0.08]),
+			(Some(2), ball(2.0), [0.0, 0.82, 0.98, 0.06]),
+			(Some(2), ball(0.5), [0.13, 0.81, 0.63, 0.03]),
+			(Some(4), elbow, [0.13, 0.63, 0.44, 0.028]),
+			(Some(2), ball(0.5), [-0.13, 0.81, 0.63, 0.03]),
+			(Some(6), elbow, [-0.13, 0.63, 0.44, 0.028]),
+			(Some(0), ball(1.0), [0.055, 0.48, 0.26, 0.04]),
+			(Some(8), knee, [0.055, 0.26, 0.0, 0.035]),
+			(Some(0), ball(1.0), [-0.055, 0.48, 0.26, 0.04]),
+			(Some(10), knee, [-0.055, 0.26, 0.0, 0.035]),
+		];
+		skeleton.into_iter().fold(
+			Self::new(position),
+			|builder, (parent, joint, [across, from, to, radius])| {
+				builder.bone(Bone {
+					parent,
+					joint,
+					ends: [point(across, from), point(across, to)],
+					radius: radius * height,
+				})
+			},
+		)
+	}
+
+	/// Adds a bone to the skeleton.
+	///
+	/// # Panics
+	///
+	/// Will panic if the bone's parent has not been added yet.
+	#[must_use]
+	pub fn bone(mut self, bone: Bone) -> Self {
+		assert!(
+			bone.parent.is_none_or(|parent| parent < self.bones.len()),
+			"a bone's parent must be added before it"
+		);
+		self.bones.push(bone);
+		self
+	}
+
+	/// Sets the density the bones' masses are derived from.
+	#[must_use]
+	pub const fn density(mut self, density: Real) -> Self {
+		self.density = density;
+		self
+	}
+
+	/// Sets the acceleration, such as gravity, given to every bone.
+	#[must_use]
+	pub const fn acceleration(mut self, acceleration: Vector3) -> Self {
+		self.acceleration = acceleration;
+		self
+	}
+
+	/// Appends a body for each bone and a joint for each bone with a parent
+	/// to the given lists, and returns the ragdoll describing them.
+	pub fn build(self, bodies: &mut Vec<RigidBody>, joints: &mut Vec<Joint>) -> Ragdoll {
+		let first_body = bodies.len();
+		let first_joint = joints.len();
+		for bone in &self.bones {
+			let center = self.position + (bone.ends[0] + bone.ends[1]) * 0.5;
+			bodies.push(
+				RigidBodyBuilder::new(bone.shape())
+					.density(self.density)
+					.position(center)
+					.orientation(bone.orientation())
+					.acceleration(self.acceleration)
+					.build(),
+			);
+		}
+		for (index, bone) in self.bones.iter().enumerate() {
+			if let Some(parent) = bone.parent {
+				let joined = [Some(first_body + index), Some(first_body + parent)];
+				joints.push(self.joint(bone, joined, bodies));
+			}
+		}
+
+		Ragdoll {
+			bodies: first_body..bodies.len(),
+			joints: first_joint..joints.len(),
+			shapes: self.bones.iter().map(Bone::shape).collect(),
+			parents: self
+				.bones
+				.iter()
+				.map(|bone| bone.parent.map(|parent| first_body + parent))
+				.collect(),
+		}
+	}
+
+	/// Joins the bone's body to its parent's at the bone's first end. A
+	/// ball's springs are tuned to the turning inertia of the bone.
+	fn joint(&self, bone: &Bone, joined: [Option<usize>; 2], bodies: &[RigidBody]) -> Joint {
+		let anchor = self.position + bone.ends[0];
+		match bone.joint {
+			BoneJoint::Ball {
+				frequency,
+				damping_ratio,
+			} => {
+				let axis = if frequency > 0.0 {
+					let inertia = joined[0].map_or(0.0, |body| bodies[body].inertia_tensor()[(0, 0)]);
+					SpringAxis::Spring(SpringStiffness::from_frequency(inertia, frequency, damping_ratio))
+				} else {
+					SpringAxis::Free
+				};
+				SpringJoint::new(joined, bodies, anchor).with_angular([axis; 3]).into()
+			},
+			BoneJoint::Hinge { axis, limit } => HingeJoint::new(joined, bodies, anchor, axis)
+				.with_limit(limit.lower, limit.upper)
+				.into(),
+			BoneJoint::Fixed => FixedJoint::new(joined, bodies, anchor).into(),
+		}
+	}
+}
+
+/// A ragdoll created by a [`RagdollBuilder`].
+///
+/// The ragdoll is a [`ContactFilter`] that keeps each bone from colliding
+/// with its parent, whose capsule it overlaps at the joint.
+#[derive(Debug, Default, Clone)]
+pub struct Ragdoll {
+	/// Holds the indices of the bones' bodies, in the order the bones were added.
+	pub bodies: Range<usize>,
+
+	/// Holds the indices of the joints, in the order the bones were added, skipping the root.
+	pub joints: Range<usize>,
+
+	/// Holds the capsule of each bone, centered on its body.
+	pub shapes: Vec<Shape>,
+
+	/// Holds the body of each bone's parent, or `None` for the root.
+	pub parents: Vec<Option<usize>>,
+}
+
+impl Ragdoll {
+	/// Returns the body of the given bone.
+	#[must_use]
+	pub const fn body(&self, bone: usize) -> usize {
+		self.bodies.start + bone
+	}
+
+	/// Returns a collider for each bone, attached to its body.
+	#[must_use]
+	pub fn colliders(&self) -> Vec<Collider> {
+		self.bodies
+			.clone()
+			.zip(&self.shapes)
+			.map(|(body, shape)| Collider::from_shape(shape, CollisionPrimitive::new(body)))
+			.collect()
+	}
+
+	fn parent(&self, body: usize) -> Option<usize> {
+		if self.bodies.contains(&body) {
+			self.parents[body - self.bodies.start]
+		} else {
+			None
+		}
+	}
+}
+
+impl ContactFilter for Ragdoll {
+	fn accepts(&self, bodies: [usize; 2]) -> bool {
+		self.parent(bodies[0]) != Some(bodies[1]) && self.parent(bodies[1]) != Some(bodies[0])
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{joint::JointKind, manifold::ManifoldCache, solver::SequentialImpulseSolver};
+
+	use super::*;
+
+	const STEP: Real = 1.0 / 60.0;
+
+	fn humanoid() -> (Vec<RigidBody>, Vec<Joint>, Ragdoll) {
+		let mut bodies = Vec::new();
+		let mut joints = Vec::new();
+		let ragdoll = RagdollBuilder::humanoid(Vector3::new(0.0, 1.0, 0.0), 1.8)
+			.density(1000.0)
+			.acceleration(Vector3::new(0.0, -10.0, 0.0))
+			.build(&mut bodies, &mut joints);
+		(bodies, joints, ragdoll)
+	}
+
+	/// Returns how far apart the anchors of the joint are.
+	fn separation(joint: &Joint, bodies: &[RigidBody]) -> Real {
+		match joint.kind {
+			JointKind::Hinge(hinge) => hinge.separation(bodies),
+			JointKind::Spring(spring) => spring.offset(bodies).magnitude(),
+			_ => unreachable!(),
+		}
+	}
+
+	#[test]
+	pub fn humanoid_is_laid_out_standing() {
+		let (bodies, joints, ragdoll) = humanoid();
+		assert_eq!(bodies.len(), 12);
+		assert_eq!(joints.len(), 11);
+		assert_eq!(ragdoll.colliders().len(), 12);
+		assert!((bodies[ragdoll.body(3)].position - Vector3::new(0.0, 2.62, 0.0)).magnitude() < 1e-5);
+
+		// Bones lie along their capsules, and the left arm is toward positive x
+		let forearm = &bodies[ragdoll.body(5)];
+		let hand = forearm.get_point_in_world(Vector3::new(0.0, 0.171, 0.0));
+		assert!((hand - Vector3::new(0.234, 1.792, 0.0)).magnitude() < 0.01);
+		for joint in &joints {
+			assert!(separation(joint, &bodies) < 1e-5);
+		}
+	}
+
+	#[test]
+	pub fn bones_do_not_collide_with_their_parents() {
+		let (_, _, ragdoll) = humanoid();
+		assert!(!ragdoll.accepts([ragdoll.body(0), ragdoll.body(1)]));
+		assert!(!ragdoll.accepts([ragdoll.body(9), ragdoll.body(8)]));
+		assert!(ragdoll.accepts([ragdoll.body(9), ragdoll.body(11)]));
+		assert!(ragdoll.accepts([ragdoll.body(0), 20]));
+	}
+
+	#[test]
+	pub fn ragdoll_holds_together_as_it_tumbles() {
+		// Thrown spinning, with the shins kicked forward against the knees
+		let (mut bodies, mut joints, ragdoll) = humanoid();
+		for body in &mut bodies {
+			body.angular_velocity = Vector3::new(0.0, 2.0, 1.0);
+		}
+		for shin in [9, 11] {
+			bodies[ragdoll.body(shin)].angular_velocity = Vector3::new(-5.0, 0.0, 0.0);
+		}
+		let solver = SequentialImpulseSolver::default();
+		let mut manifolds = ManifoldCache::default();
+		for _ in 0..120 {
+			for body in &mut bodies {
+				body.integrate(STEP);
+			}
+			solver.solve(&mut manifolds, &mut joints, &mut bodies, STEP);
+			for joint in &joints {
+				assert!(separation(joint, &bodies) < 0.05);
+				if let JointKind::Hinge(hinge) = joint.kind {
+					let angle = hinge.angle(&bodies);
+					assert!((-0.1..2.7).contains(&angle));
+				}
+			}
+		}
+	}
+}
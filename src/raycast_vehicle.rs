@@ -0,0 +1,446 @@
+use crate::{
+	body::RigidBody, bounding::Ray, cloth::SpringStiffness, collide::CollisionPlane, portable::sin_cos,
+	real_from_count, trimesh::TriMesh, vec::Vector3, Real,
+};
+
+/// Where a wheel's ray meets the ground.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct WheelHit {
+	/// Holds the distance along the ray to the hit.
+	pub distance: Real,
+
+	/// Holds the point hit, in world coordinates.
+	pub point: Vector3,
+
+	/// Holds the unit normal of the ground at the point, facing the wheel.
+	pub normal: Vector3,
+
+	/// Holds the body the ground belongs to, or `None` for the scenery.
+	pub body: Option<usize>,
+}
+
+/// Something the wheels of a [`RaycastVehicle`] can stand on.
+///
+/// Closures taking the ray and the farthest distance to look are ground,
+/// so that the wheels can be cast against whatever the game keeps its
+/// level in, leaving out the vehicle's own colliders.
+pub trait WheelGround {
+	/// Returns where the ray first meets the ground within the given distance.
+	fn cast(&self, ray: &Ray, max_distance: Real) -> Option<WheelHit>;
+}
+
+impl<F: Fn(&Ray, Real) -> Option<WheelHit>> WheelGround for F {
+	fn cast(&self, ray: &Ray, max_distance: Real) -> Option<WheelHit> {
+		self(ray, max_distance)
+	}
+}
+
+impl WheelGround for CollisionPlane {
+	/// The plane is the surface of a half-space, so a ray starting below it hits at once.
+	fn cast(&self, ray: &Ray, max_distance: Real) -> Option<WheelHit> {
+		let height = self.distance_to(ray.origin);
+		let closing = -ray.direction.dot(&self.normal);
+		let distance = if height <= 0.0 {
+			0.0
+		} else if closing > 0.0 {
+			height / closing
+		} else {
+			return None;
+		};
+		(distance <= max_distance).then(|| WheelHit {
+			distance,
+			point: ray.point_at(distance),
+			normal: self.normal,
+			body: None,
+		})
+	}
+}
+
+impl WheelGround for TriMesh {
+	fn cast(&self, ray: &Ray, max_distance: Real) -> Option<WheelHit> {
+		self.ray_cast(ray, max_distance).map(|hit| WheelHit {
+			distance: hit.distance,
+			point: hit.point,
+			normal: if hit.normal.dot(&ray.direction) > 0.0 {
+				-hit.normal
+			} else {
+				hit.normal
+			},
+			body: None,
+		})
+	}
+}
+
+/// A wheel of a [`RaycastVehicle`], hung from the chassis on a spring and
+/// damper that push along the chassis' up axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wheel {
+	/// Holds where the suspension is mounted, in the chassis' own space.
+	pub mount: Vector3,
+
+	pub radius: Real,
+
+	/// Holds how far below the mount the wheel's center hangs when unloaded.
+	pub rest_length: Real,
+
+	pub suspension: SpringStiffness,
+
+	/// Holds the coefficient of friction of the tire, which limits the
+	/// force it can push with to that times the load it bears.
+	pub grip: Real,
+
+	/// Holds whether the wheel turns with the steering.
+	pub steered: bool,
+
+	/// Holds whether the engine drives the wheel.
+	pub driven: bool,
+
+	/// Holds where the wheel touched the ground last update, or `None` if it was in the air.
+	pub contact: Option<WheelHit>,
+
+	/// Holds how far below the mount the wheel's center was last update.
+	pub length: Real,
+
+	/// Holds the force the suspension pushed the chassis up with last update.
+	pub load: Real,
+
+	/// Holds how fast the wheel rolls, in radians per second.
+	pub spin: Real,
+
+	/// Holds the angle the wheel has rolled through, in radians, for drawing it.
+	pub angle: Real,
+}
+
+impl Wheel {
+	/// Creates an undriven, unsteered wheel with a grip of one.
+	#[must_use]
+	pub const fn new(mount: Vector3, radius: Real, suspension: SpringStiffness, rest_length: Real) -> Self {
+		Self {
+			mount,
+			radius,
+			rest_length,
+			suspension,
+			grip: 1.0,
+			steered: false,
+			driven: false,
+			contact: None,
+			length: rest_length,
+			load: 0.0,
+			spin: 0.0,
+			angle: 0.0,
+		}
+	}
+
+	/// Makes the wheel turn with the steering.
+	#[must_use]
+	pub const fn steered(mut self) -> Self {
+		self.steered = true;
+		self
+	}
+
+	/// Makes the engine drive the wheel.
+	#[must_use]
+	pub const fn driven(mut self) -> Self {
+		self.driven = true;
+		self
+	}
+
+	#[must_use]
+	pub const fn with_grip(mut self, grip: Real) -> Self {
+		self.grip = grip;
+		self
+	}
+
+	/// Returns the center of the wheel in world coordinates, as of the last update.
+	#[must_use]
+	pub fn center(&self, chassis: &RigidBody) -> Vector3 {
+		let up = chassis.get_direction_in_world(Vector3::y_axis());
+		chassis.get_point_in_world(self.mount) - up * self.length
+	}
+
+	/// Casts the wheel's ray down from its mount, finding where it stands.
+	fn cast(&mut self, chassis: &RigidBody, ground: &(impl WheelGround + ?Sized)) -> Option<WheelHit> {
+		let down = -chassis.get_direction_in_world(Vector3::y_axis());
+		let ray = Ray::new(chassis.get_point_in_world(self.mount), down);
+		self.contact = ground.cast(&ray, self.rest_length + self.radius);
+		self.length = self.contact.map_or(self.rest_length, |hit| {
+			(hit.distance - self.radius).clamp(0.0, self.rest_length)
+		});
+		self.contact
+	}
+
+	/// Finds the load on the wheel from how far the suspension is compressed
+	/// and how fast, given the velocity of the chassis over the ground. The
+	/// suspension pushes but never pulls.
+	fn suspend(&mut self, up: Vector3, velocity: Vector3) -> Real {
+		let compression = self.rest_length - self.length;
+		let spring = self.suspension.spring_constant * compression;
+		self.load = (-self.suspension.damping).mul_add(velocity.dot(&up), spring).max(0.0);
+		self.load
+	}
+
+	/// Returns the force the tire pushes the chassis with, along the ground,
+	/// given its heading and the velocity of the chassis over the ground.
+	///
+	/// The engine and brakes push along the heading, and the tire pushes back
+	/// against sliding across it. Together these are held within the friction
+	/// circle: no more than the grip times the load.
+	fn tire_force(&self, hit: &WheelHit, heading: Vector3, velocity: Vector3, traction: &Traction) -> Vector3 {
+		let forward = (heading - hit.normal * heading.dot(&hit.normal)).normalize();
+		let side = hit.normal.cross(&forward);
+		let mut longitudinal = if self.driven { traction.drive / self.radius } else { 0.0 };
+		if traction.brake > 0.0 {
+			let most = traction.brake / self.radius;
+			longitudinal -= (velocity.dot(&forward) * traction.stopping).clamp(-most, most);
+		}
+		let lateral = -velocity.dot(&side) * traction.stopping;
+		let force = forward * longitudinal + side * lateral;
+		let most = self.grip * self.load;
+		if force.magnitude() > most {
+			force.normalize() * most
+		} else {
+			force
+		}
+	}
+}
+
+/// What turns each grounded wheel this update.
+struct Traction {
+	/// Holds the engine torque on each driven wheel.
+	drive: Real,
+
+	/// Holds the brake torque on each wheel.
+	brake: Real,
+
+	/// Holds the force per unit of velocity that stops a wheel's share of
+	/// the chassis within a step.
+	stopping: Real,
+}
+
+/// A car driven by casting a ray down from each wheel mount instead of
+/// simulating the wheels as bodies, which is the usual fast path for
+/// driving games.
+///
+/// Each update, before the chassis is integrated, the wheels find the
+/// ground and push the chassis with their suspension and tires, and push
+/// back on any body they stand on. The chassis faces along its own positive
+/// x axis with its right side along its positive z axis.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RaycastVehicle {
+	/// Holds the index of the chassis body.
+	pub chassis: usize,
+
+	pub wheels: Vec<Wheel>,
+
+	/// Holds the torque on each driven wheel at full throttle.
+	pub engine_torque: Real,
+
+	/// Holds the torque on each wheel at full brake.
+	pub brake_torque: Real,
+
+	/// Holds the throttle, from -1 for full reverse to 1 for full forward.
+	pub throttle: Real,
+
+	/// Holds the brake, from 0 for none to 1 for full.
+	pub brake: Real,
+
+	/// Holds the angle of the steered wheels in radians, positive to the right.
+	pub steering: Real,
+}
+
+impl RaycastVehicle {
+	#[must_use]
+	pub const fn new(chassis: usize, engine_torque: Real, brake_torque: Real) -> Self {
+		Self {
+			chassis,
+			wheels: Vec::new(),
+			engine_torque,
+			brake_torque,
+			throttle: 0.0,
+			brake: 0.0,
+			steering: 0.0,
+		}
+	}
+
+	#[must_use]
+	pub fn with_wheel(mut self, wheel: Wheel) -> Self {
+		self.wheels.push(wheel);
+		self
+	}
+
+	/// Returns the unit direction the chassis is facing.
+	#[must_use]
+	pub fn forward(&self, bodies: &[RigidBody]) -> Vector3 {
+		bodies[self.chassis].get_direction_in_world(Vector3::x_axis())
+	}
+
+	/// Returns how fast the chassis is moving in the direction it faces.
+	#[must_use]
+	pub fn speed(&self, bodies: &[RigidBody]) -> Real {
+		bodies[self.chassis].velocity.dot(&self.forward(bodies))
+	}
+
+	/// Casts each wheel onto the ground and adds its suspension and tire
+	/// forces to the chassis and to the body it stands on, if any. This
+	/// should be called each step before the bodies are integrated.
+	pub fn update(&mut self, bodies: &mut [RigidBody], ground: &(impl WheelGround + ?Sized), duration: Real) {
+		if duration <= 0.0 || self.wheels.is_empty() {
+			return;
+		}
+		let chassis = bodies[self.chassis];
+		let up = chassis.get_direction_in_world(Vector3::y_axis());
+		let forward = chassis.get_direction_in_world(Vector3::x_axis());
+		let (sin, cos) = sin_cos(self.steering);
+		let steered = forward * cos + chassis.get_direction_in_world(Vector3::z_axis()) * sin;
+		let traction = Traction {
+			drive: self.engine_torque * self.throttle.clamp(-1.0, 1.0),
+			brake: self.brake_torque * self.brake.clamp(0.0, 1.0),
+			stopping: chassis.mass() / (real_from_count(self.wheels.len()) * duration),
+		};
+
+		for wheel in &mut self.wheels {
+			let Some(hit) = wheel.cast(&chassis, ground) else {
+				wheel.load = 0.0;
+				wheel.angle += wheel.spin * duration;
+				continue;
+			};
+			let ground_velocity = hit
+				.body
+				.map_or_else(Vector3::zero, |body| bodies[body].velocity_at_point(hit.point));
+			let velocity = chassis.velocity_at_point(hit.point) - ground_velocity;
+			let heading = if wheel.steered { steered } else { forward };
+			let suspension = up * wheel.suspend(up, velocity);
+			let tire = wheel.tire_force(&hit, heading, velocity, &traction);
+			wheel.spin = velocity.dot(&heading) / wheel.radius;
+			wheel.angle += wheel.spin * duration;
+
+			// Tires push level with the center of mass, so that they do not tip the chassis over
+			let level = hit.point + up * up.dot(&(chassis.position - hit.point));
+			bodies[self.chassis].add_force_at_point(suspension, hit.point);
+			bodies[self.chassis].add_force_at_point(tire, level);
+			if let Some(body) = hit.body.filter(|body| *body != self.chassis) {
+				bodies[body].add_force_at_point(-(suspension + tire), hit.point);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{body::RigidBodyBuilder, shape::Shape};
+
+	use super::*;
+
+	const STEP: Real = 1.0 / 60.0;
+
+	/// A 400 kg car with its wheels resting on the ground at the origin, steered
+	/// at the front and driven at the rear.
+	fn car() -> (Vec<RigidBody>, RaycastVehicle) {
+		let chassis = RigidBodyBuilder::new(Shape::Cuboid {
+			half_size: Vector3::new(2.0, 0.25, 1.0),
+		})
+		.mass(400.0)
+		.position(Vector3::new(0.0, 0.9, 0.0))
+		.acceleration(Vector3::new(0.0, -10.0, 0.0))
+		.can_sleep(false)
+		.build();
+		let suspension = SpringStiffness::from_frequency(100.0, 1.5, 0.7);
+		let wheel = |x: Real, z: Real| Wheel::new(Vector3::new(x, -0.25, z), 0.35, suspension, 0.3);
+		let mut vehicle = RaycastVehicle::new(0, 300.0, 1000.0);
+		for z in [-0.9, 0.9] {
+			vehicle = vehicle
+				.with_wheel(wheel(1.5, z).steered())
+				.with_wheel(wheel(-1.5, z).driven());
+		}
+		(vec![chassis], vehicle)
+	}
+
+	fn drive(bodies: &mut [RigidBody], vehicle: &mut RaycastVehicle, steps: usize) {
+		let ground = CollisionPlane::new(Vector3::y_axis(), 0.0);
+		for _ in 0..steps {
+			vehicle.update(bodies, &ground, STEP);
+			for body in bodies.iter_mut() {
+				body.integrate(STEP);
+			}
+		}
+	}
+
+	#[test]
+	pub fn suspension_holds_up_the_chassis() {
+		let (mut bodies, mut vehicle) = car();
+		drive(&mut bodies, &mut vehicle, 300);
+		let load: Real = vehicle.wheels.iter().map(|wheel| wheel.load).sum();
+		assert!((load - 4000.0).abs() < 100.0);
+		assert!(bodies[0].velocity.magnitude() < 0.05);
+
+		// Each spring is compressed by its share of the weight
+		let compression = 1000.0 / vehicle.wheels[0].suspension.spring_constant;
+		let wheel = &vehicle.wheels[0];
+		assert!((wheel.rest_length - wheel.length - compression).abs() < 0.01);
+		assert!((wheel.center(&bodies[0]).y() - wheel.radius).abs() < 0.01);
+	}
+
+	#[test]
+	pub fn throttle_and_brake_drive_the_car() {
+		let (mut bodies, mut vehicle) = car();
+		drive(&mut bodies, &mut vehicle, 60);
+		vehicle.throttle = 1.0;
+		drive(&mut bodies, &mut vehicle, 120);
+		assert!(vehicle.speed(&bodies) > 6.0);
+		assert!(bodies[0].get_direction_in_world(Vector3::y_axis()).y() > 0.95);
+		assert!(vehicle.wheels[1].spin > 15.0);
+
+		vehicle.throttle = 0.0;
+		vehicle.brake = 1.0;
+		drive(&mut bodies, &mut vehicle, 120);
+		assert!(vehicle.speed(&bodies).abs() < 0.1);
+	}
+
+	#[test]
+	pub fn steering_turns_the_car() {
+		let (mut bodies, mut vehicle) = car();
+		drive(&mut bodies, &mut vehicle, 60);
+		vehicle.throttle = 0.5;
+		vehicle.steering = 0.3;
+		drive(&mut bodies, &mut vehicle, 120);
+		let forward = vehicle.forward(&bodies);
+		assert!(forward.z() > 0.3);
+		assert!(bodies[0].velocity.z() > 1.0);
+		assert!(bodies[0].get_direction_in_world(Vector3::y_axis()).y() > 0.9);
+	}
+
+	#[test]
+	pub fn wheels_in_the_air_push_nothing() {
+		let (mut bodies, mut vehicle) = car();
+		bodies[0].position = Vector3::new(0.0, 5.0, 0.0);
+		bodies[0].calculate_derived_data();
+		vehicle.throttle = 1.0;
+		drive(&mut bodies, &mut vehicle, 1);
+		assert!(vehicle
+			.wheels
+			.iter()
+			.all(|wheel| wheel.contact.is_none() && wheel.load == 0.0));
+		assert!(bodies[0].velocity.x().abs() < 1e-6);
+	}
+
+	#[test]
+	pub fn wheels_stand_on_meshes_and_planes() {
+		let ray = Ray::new(Vector3::new(0.0, 1.0, 0.0), -Vector3::y_axis());
+		let plane = CollisionPlane::new(Vector3::y_axis(), 0.25);
+		let hit = plane.cast(&ray, 2.0).expect("the ray reaches the plane");
+		assert!((hit.distance - 0.75).abs() < 1e-6);
+		assert!(plane.cast(&ray, 0.5).is_none());
+
+		let floor = TriMesh::new(
+			vec![
+				Vector3::new(-1.0, 0.0, -1.0),
+				Vector3::new(1.0, 0.0, -1.0),
+				Vector3::new(0.0, 0.0, 1.0),
+			],
+			vec![[0, 1, 2]],
+		);
+		let hit = floor.cast(&ray, 2.0).expect("the ray reaches the mesh");
+		assert_eq!(hit.normal, Vector3::y_axis());
+		assert!((hit.distance - 1.0).abs() < 1e-6);
+	}
+}
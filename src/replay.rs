@@ -0,0 +1,283 @@
+use crate::{body::RigidBody, body_set::BodySet, shape::Shape, vec::Vector3, world::PhysicsWorld, Real};
+
+/// Something done to a world between steps, recorded so that it can be done again.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub enum ReplayInput {
+	/// A force applied to a point on the body for the step, both in world coordinates.
+	Force {
+		body: usize,
+		force: Vector3,
+		point: Vector3,
+	},
+
+	/// A torque applied to the body for the step, in world coordinates.
+	Torque { body: usize, torque: Vector3 },
+
+	/// An impulse applied to a point on the body, both in world coordinates.
+	Impulse {
+		body: usize,
+		impulse: Vector3,
+		point: Vector3,
+	},
+
+	/// A body added to the world with a collider of the given shape, taking the next index.
+	Spawn { body: Box<RigidBody>, shape: Shape },
+
+	/// A body taken out of the world, as by [`PhysicsWorld::remove_body`].
+	Remove { body: usize },
+}
+
+impl ReplayInput {
+	/// Does to the world what happens at once, leaving the forces and
+	/// torques to [`Self::apply_force`] once the step has cleared the last.
+	fn apply(&self, world: &mut PhysicsWorld) {
+		match self {
+			Self::Impulse { body, impulse, point } => {
+				world.bodies.update(*body, |body| body.apply_impulse_at_point(*impulse, *point));
+			},
+			Self::Spawn { body, shape } => {
+				let body = world.add_body(**body);
+				world.add_collider(body, shape);
+			},
+			Self::Remove { body } => world.remove_body(*body),
+			Self::Force { .. } | Self::Torque { .. } => {},
+		}
+	}
+
+	fn apply_force(&self, bodies: &mut BodySet) {
+		match self {
+			Self::Force { body, force, point } => bodies.add_force_at_point(*body, *force, *point),
+			Self::Torque { body, torque } => bodies.add_torque(*body, *torque),
+			Self::Impulse { .. } | Self::Spawn { .. } | Self::Remove { .. } => {},
+		}
+	}
+
+	const fn is_force(&self) -> bool {
+		matches!(self, Self::Force { .. } | Self::Torque { .. })
+	}
+}
+
+/// A step of a [`Replay`], with the inputs given to the world before it.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct ReplayStep {
+	pub duration: Real,
+	pub inputs: Vec<ReplayInput>,
+
+	/// Holds the [`PhysicsWorld::state_hash`] of the world after the step.
+	pub state_hash: u64,
+}
+
+/// The steps of a world recorded by a [`ReplayRecorder`], in order.
+///
+/// Played back with a [`ReplayPlayer`] onto a world set up as the recorded
+/// one was when recording began, such as one built from the same scene or
+/// restored from a save, it goes the same way, step by step. With the
+/// `serde` feature a replay can be saved, for debugging or demo playback.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct Replay {
+	pub steps: Vec<ReplayStep>,
+}
+
+/// Gives the inputs to the world and steps it.
+///
+/// The forces and torques are applied by a pre-step hook added for the
+/// step, so that they come after the world's own hooks and are not
+/// cleared along with the forces of the last step.
+fn play_step(world: &mut PhysicsWorld, inputs: &[ReplayInput], duration: Real) {
+	for input in inputs {
+		input.apply(world);
+	}
+	let forces: Vec<ReplayInput> = inputs.iter().filter(|input| input.is_force()).cloned().collect();
+	if forces.is_empty() {
+		world.step(duration);
+		return;
+	}
+	world.add_pre_step_hook(move |bodies: &mut BodySet, _| {
+		for force in &forces {
+			force.apply_force(bodies);
+		}
+	});
+	world.step(duration);
+	world.pre_step_hooks.pop();
+}
+
+/// Steps a world on behalf of the game, recording the inputs given to it
+/// before each step into a [`Replay`].
+#[derive(Debug, Default, Clone)]
+pub struct ReplayRecorder {
+	replay: Replay,
+	pending: Vec<ReplayInput>,
+}
+
+impl ReplayRecorder {
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Queues the input, to be given to the world at the start of the next step.
+	pub fn push(&mut self, input: ReplayInput) {
+		self.pending.push(input);
+	}
+
+	/// Gives the world the queued inputs and steps it, recording the step.
+	pub fn step(&mut self, world: &mut PhysicsWorld, duration: Real) {
+		let inputs = std::mem::take(&mut self.pending);
+		play_step(world, &inputs, duration);
+		self.replay.steps.push(ReplayStep {
+			duration,
+			inputs,
+			state_hash: world.state_hash(),
+		});
+	}
+
+	/// Returns the steps recorded so far.
+	#[must_use]
+	pub const fn replay(&self) -> &Replay {
+		&self.replay
+	}
+
+	/// Stops recording, returning the replay. Any inputs still queued are dropped.
+	#[must_use]
+	pub fn finish(self) -> Replay {
+		self.replay
+	}
+}
+
+/// How a step played by a [`ReplayPlayer`] went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Playback {
+	/// The world ended the step in the state it was recorded in.
+	Matched,
+
+	/// The world ended the step in a different state than it was recorded
+	/// in, and will likely go further astray from here on.
+	Diverged,
+
+	/// Every step has been played.
+	Finished,
+}
+
+/// Plays a [`Replay`] back onto a world, one step at a time.
+#[derive(Debug, Clone)]
+pub struct ReplayPlayer {
+	replay: Replay,
+	next: usize,
+}
+
+impl ReplayPlayer {
+	#[must_use]
+	pub const fn new(replay: Replay) -> Self {
+		Self { replay, next: 0 }
+	}
+
+	/// Returns the index of the next step to be played.
+	#[must_use]
+	pub const fn position(&self) -> usize {
+		self.next
+	}
+
+	#[must_use]
+	pub const fn is_finished(&self) -> bool {
+		self.next >= self.replay.steps.len()
+	}
+
+	/// Gives the world the inputs of the next step and steps it, returning
+	/// whether it ended the step as recorded.
+	pub fn step(&mut self, world: &mut PhysicsWorld) -> Playback {
+		let Some(step) = self.replay.steps.get(self.next) else {
+			return Playback::Finished;
+		};
+		self.next += 1;
+		play_step(world, &step.inputs, step.duration);
+		if world.state_hash() == step.state_hash {
+			Playback::Matched
+		} else {
+			Playback::Diverged
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{body::RigidBodyBuilder, collide::CollisionPlane};
+
+	use super::*;
+
+	const STEP: Real = 1.0 / 60.0;
+
+	fn world() -> PhysicsWorld {
+		let mut world = PhysicsWorld::new();
+		world.add_plane(CollisionPlane::new(Vector3::y_axis(), 0.0));
+		for x in [0.0, 2.0] {
+			let body = world.add_body(
+				RigidBodyBuilder::new(Shape::default())
+					.position(Vector3::new(x, 0.5, 0.0))
+					.build(),
+			);
+			world.add_collider(body, &Shape::default());
+		}
+		world
+	}
+
+	fn record(world: &mut PhysicsWorld) -> Replay {
+		let mut recorder = ReplayRecorder::new();
+		for step in 0..90 {
+			match step {
+				10 => recorder.push(ReplayInput::Impulse {
+					body: 0,
+					impulse: Vector3::new(3.0, 0.0, 0.0),
+					point: Vector3::new(0.0, 0.8, 0.0),
+				}),
+				20..=40 => recorder.push(ReplayInput::Force {
+					body: 1,
+					force: Vector3::new(0.0, 30.0, 0.0),
+					point: world.bodies.get(1).position,
+				}),
+				50 => recorder.push(ReplayInput::Spawn {
+					body: Box::new(
+						RigidBodyBuilder::new(Shape::Sphere { radius: 0.25 })
+							.position(Vector3::new(2.0, 4.0, 0.0))
+							.build(),
+					),
+					shape: Shape::Sphere { radius: 0.25 },
+				}),
+				70 => recorder.push(ReplayInput::Remove { body: 0 }),
+				_ => {},
+			}
+			recorder.step(world, STEP);
+		}
+		recorder.finish()
+	}
+
+	#[test]
+	pub fn replays_reproduce_the_recording() {
+		let mut recorded = world();
+		let replay = record(&mut recorded);
+		assert_eq!(recorded.bodies.len(), 3);
+		// The removed body's collider is left detached in its place
+		assert_eq!(recorded.colliders.len(), 3);
+		assert_eq!(recorded.colliders.iter().filter(|collider| collider.is_attached()).count(), 2);
+		assert!(recorded.bodies.get(1).position.y() > 0.6);
+
+		let mut replayed = world();
+		let mut player = ReplayPlayer::new(replay);
+		while !player.is_finished() {
+			assert_eq!(player.step(&mut replayed), Playback::Matched);
+		}
+		assert_eq!(player.step(&mut replayed), Playback::Finished);
+		assert_eq!(replayed.state_hash(), recorded.state_hash());
+		assert!(replayed.pre_step_hooks.is_empty());
+	}
+
+	#[test]
+	pub fn players_report_divergence() {
+		let replay = record(&mut world());
+		let mut replayed = world();
+		replayed.bodies.update(1, |body| body.position += Vector3::new(0.0, 1e-3, 0.0));
+		assert_eq!(ReplayPlayer::new(replay).step(&mut replayed), Playback::Diverged);
+	}
+}
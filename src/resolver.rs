@@ -0,0 +1,759 @@
+use crate::{
+	body::{BodyKind, RigidBody},
+	collide::Contact,
+	matrix::Matrix3,
+	solver::SolverReport,
+	vec::Vector3,
+	Real,
+};
+
+/// The most a body is turned to resolve a penetration, as a fraction of
+/// the distance from its center to the contact. The rest is resolved by moving it.
+const ANGULAR_LIMIT: Real = 0.2;
+
+impl Contact {
+	/// Returns an orthonormal basis for the contact, whose first column is
+	/// the contact normal and whose other two are tangents across it.
+	///
+	/// Multiplying by the basis converts a vector from contact coordinates
+	/// into world coordinates.
+	#[must_use]
+	pub fn contact_basis(&self) -> Matrix3 {
+		let normal = self.contact_normal;
+		// Start from whichever world axis is furthest from the normal
+		let guess = if normal.x().abs() > normal.y().abs() {
+			Vector3::y_axis()
+		} else {
+			Vector3::x_axis()
+		};
+		let second_tangent = normal.cross(&guess).normalize();
+		let first_tangent = second_tangent.cross(&normal);
+		Matrix3::from_columns([normal, first_tangent, second_tangent])
+	}
+}
+
+/// A contact with the data derived from its bodies that the resolver needs.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PreparedContact {
+	pub contact: Contact,
+
+	/// Holds the basis from [`Contact::contact_basis`].
+	pub contact_to_world: Matrix3,
+
+	/// Holds the position of the contact point relative to the center of each body.
+	pub relative_positions: [Vector3; 2],
+
+	/// Holds the velocity of the first body relative to the second at the
+	/// contact point, in contact coordinates. The first component is
+	/// negative while the bodies are closing.
+	pub contact_velocity: Vector3,
+
+	/// Holds the change in velocity along the normal needed to resolve the contact.
+	pub desired_delta_velocity: Real,
+
+	/// Holds whether the bodies were closing too slowly to rebound when
+	/// the contact was prepared, as when one rests on the other.
+	pub resting: bool,
+}
+
+/// How a resolution step changed the bodies of a contact.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct BodyChanges {
+	/// Holds the change in velocity, or position, of each body.
+	pub linear: [Vector3; 2],
+
+	/// Holds the change in angular velocity, or orientation, of each body.
+	pub angular: [Vector3; 2],
+}
+
+impl PreparedContact {
+	/// Derives the data needed to resolve the contact from its bodies. The
+	/// contact is resting if the bodies close slower than the given threshold.
+	#[must_use]
+	pub fn new(contact: Contact, bodies: &[RigidBody], duration: Real, restitution_threshold: Real) -> Self {
+		let mut prepared = Self {
+			contact,
+			contact_to_world: contact.contact_basis(),
+			..Default::default()
+		};
+		for (side, body) in contact.bodies.iter().enumerate() {
+			if let Some(body) = body {
+				prepared.relative_positions[side] = contact.contact_point - bodies[*body].position;
+			}
+		}
+		prepared.contact_velocity = prepared.local_velocity(bodies, 0) - prepared.local_velocity(bodies, 1);
+		prepared.resting = prepared.contact_velocity.x().abs() < restitution_threshold;
+		prepared.calculate_desired_delta_velocity(bodies, duration);
+		prepared
+	}
+
+	/// Returns the velocity of the contact point on the given side's body,
+	/// in contact coordinates, or zero for the scenery.
+	fn local_velocity(&self, bodies: &[RigidBody], side: usize) -> Vector3 {
+		self.contact.bodies[side].map_or_else(Vector3::zero, |body| {
+			let body = &bodies[body];
+			let velocity = body.angular_velocity.cross(&self.relative_positions[side]) + body.velocity;
+			self.contact_to_world.transform_transpose(velocity)
+		})
+	}
+
+	/// Calculates the change in velocity along the normal that brings the
+	/// bodies to their rebound speed.
+	///
+	/// The velocity the bodies' accelerations built up over the last step is
+	/// not rebounded, and resting contacts are not rebounded at all, so that
+	/// bodies resting under gravity do not vibrate.
+	pub fn calculate_desired_delta_velocity(&mut self, bodies: &[RigidBody], duration: Real) {
+		let mut velocity_from_acceleration = 0.0;
+		for (side, sign) in [(0, 1.0), (1, -1.0)] {
+			if let Some(body) = self.contact.bodies[side] {
+				let body = &bodies[body];
+				if body.kind == BodyKind::Dynamic && !body.sleeping {
+					velocity_from_acceleration += sign * body.acceleration.dot(&self.contact.contact_normal) * duration;
+				}
+			}
+		}
+		let closing = self.contact_velocity.x();
+		let restitution = if self.resting { 0.0 } else { self.contact.restitution };
+		self.desired_delta_velocity = -restitution.mul_add(closing - velocity_from_acceleration, closing);
+	}
+
+	/// Returns the change in velocity along the normal, at the contact,
+	/// caused by a unit impulse along the normal.
+	fn velocity_per_unit_impulse(&self, bodies: &[RigidBody]) -> Real {
+		let normal = self.contact.contact_normal;
+		self.contact
+			.bodies
+			.iter()
+			.zip(self.relative_positions)
+			.filter_map(|(body, relative_position)| body.map(|body| (&bodies[body], relative_position)))
+			.map(|(body, relative_position)| {
+				let rotation = body.effective_inverse_inertia_tensor_world() * relative_position.cross(&normal);
+				rotation.cross(&relative_position).dot(&normal) + body.effective_inverse_mass()
+			})
+			.sum()
+	}
+
+	/// Returns the impulse, in contact coordinates, along the normal alone
+	/// that gives the desired change in velocity.
+	fn frictionless_impulse(&self, bodies: &[RigidBody]) -> Vector3 {
+		let velocity_per_impulse = self.velocity_per_unit_impulse(bodies);
+		if velocity_per_impulse <= 0.0 {
+			return Vector3::zero();
+		}
+		Vector3::new(self.desired_delta_velocity / velocity_per_impulse, 0.0, 0.0)
+	}
+
+	/// Returns the impulse, in contact coordinates, that gives the desired
+	/// change in velocity along the normal and stops the contact sliding.
+	///
+	/// If stopping the sliding needs more than the friction cone allows, the
+	/// contact slides instead: the impulse across the normal is held to the
+	/// friction times the impulse along it, against the sliding, and the
+	/// impulse along the normal is found again to allow for it.
+	fn friction_impulse(&self, bodies: &[RigidBody]) -> Vector3 {
+		// Find the change in velocity at the contact from a unit impulse along each world axis
+		let mut velocity_per_impulse = Matrix3::zero();
+		let mut inverse_mass = 0.0;
+		for (side, body) in self.contact.bodies.iter().enumerate() {
+			if let Some(body) = body {
+				let body = &bodies[*body];
+				let impulse_to_torque = Matrix3::skew_symmetric(self.relative_positions[side]);
+				velocity_per_impulse = velocity_per_impulse
+					- impulse_to_torque * body.effective_inverse_inertia_tensor_world() * impulse_to_torque;
+				inverse_mass += body.effective_inverse_mass();
+			}
+		}
+		let basis = self.contact_to_world;
+		let velocity_per_impulse =
+			basis.transpose() * velocity_per_impulse * basis + Matrix3::identity() * inverse_mass;
+		let Some(impulse_per_velocity) = velocity_per_impulse.inverse() else {
+			return Vector3::zero();
+		};
+
+		let velocity_to_remove = Vector3::new(
+			self.desired_delta_velocity,
+			-self.contact_velocity.y(),
+			-self.contact_velocity.z(),
+		);
+		let impulse = impulse_per_velocity * velocity_to_remove;
+		let friction = self.contact.friction;
+		let planar = impulse.y().hypot(impulse.z());
+		if planar <= impulse.x() * friction {
+			return impulse;
+		}
+
+		// The contact slides, with the friction impulse on the edge of the cone
+		let sliding = [impulse.y() / planar * friction, impulse.z() / planar * friction];
+		let normal_velocity_per_impulse = velocity_per_impulse[(0, 1)].mul_add(
+			sliding[0],
+			velocity_per_impulse[(0, 2)].mul_add(sliding[1], velocity_per_impulse[(0, 0)]),
+		);
+		if normal_velocity_per_impulse <= 0.0 {
+			return self.frictionless_impulse(bodies);
+		}
+		let normal_impulse = self.desired_delta_velocity / normal_velocity_per_impulse;
+		Vector3::new(normal_impulse, sliding[0] * normal_impulse, sliding[1] * normal_impulse)
+	}
+
+	/// Applies the impulse that gives the desired change in velocity along
+	/// the normal, with friction across it, returning how each body's
+	/// velocities changed.
+	pub fn apply_velocity_change(&self, bodies: &mut [RigidBody]) -> BodyChanges {
+		let mut changes = BodyChanges::default();
+		let impulse = if self.contact.friction > 0.0 {
+			self.friction_impulse(bodies)
+		} else {
+			self.frictionless_impulse(bodies)
+		};
+		let normal_impulse = impulse.x();
+		let impulse = self.contact_to_world * impulse;
+		for (side, sign) in [(0, 1.0), (1, -1.0)] {
+			let Some(body) = self.contact.bodies[side] else {
+				continue;
+			};
+			let body = &mut bodies[body];
+			let impulse = impulse * sign;
+			changes.linear[side] = impulse * body.effective_inverse_mass();
+			changes.angular[side] =
+				body.effective_inverse_inertia_tensor_world() * self.relative_positions[side].cross(&impulse);
+			body.velocity += changes.linear[side];
+			body.angular_velocity += changes.angular[side];
+		}
+		if self.contact.rolling_friction > 0.0 {
+			self.resist_rolling(bodies, normal_impulse, &mut changes);
+		}
+		changes
+	}
+
+	/// Applies the angular impulse about the tangents that stops the bodies
+	/// rolling over each other, limited to the rolling friction times the
+	/// normal impulse, adding the change in spin to the changes.
+	fn resist_rolling(&self, bodies: &mut [RigidBody], normal_impulse: Real, changes: &mut BodyChanges) {
+		let normal = self.contact.contact_normal;
+		let mut spin = Vector3::zero();
+		let mut inverse_inertia = Matrix3::zero();
+		for (side, sign) in [(0, 1.0), (1, -1.0)] {
+			if let Some(body) = self.contact.bodies[side] {
+				spin += bodies[body].angular_velocity * sign;
+				inverse_inertia = inverse_inertia + bodies[body].effective_inverse_inertia_tensor_world();
+			}
+		}
+		let Some(inertia) = inverse_inertia.inverse() else {
+			return;
+		};
+
+		// Spinning about the normal is not rolling, so it is left alone
+		let mut impulse = -(inertia * (spin - normal * spin.dot(&normal)));
+		impulse -= normal * impulse.dot(&normal);
+		let limit = self.contact.rolling_friction * normal_impulse.abs();
+		if impulse.magnitude() > limit {
+			impulse = impulse.normalize() * limit;
+		}
+		for (side, sign) in [(0, 1.0), (1, -1.0)] {
+			if let Some(body) = self.contact.bodies[side] {
+				let body = &mut bodies[body];
+				let change = body.effective_inverse_inertia_tensor_world() * (impulse * sign);
+				body.angular_velocity += change;
+				changes.angular[side] += change;
+			}
+		}
+	}
+
+	/// Moves and turns the bodies apart to resolve the penetration, in
+	/// proportion to their inertia along the normal, returning how each was
+	/// moved and turned.
+	///
+	/// Turning is limited so that a body is never spun far to resolve a
+	/// contact at its edge; the rest of the penetration is resolved by moving it.
+	pub fn apply_position_change(&self, bodies: &mut [RigidBody]) -> BodyChanges {
+		let normal = self.contact.contact_normal;
+		let mut inertia = [[0.0; 2]; 2];
+		for (side, body) in self.contact.bodies.iter().enumerate() {
+			if let Some(body) = body {
+				let body = &bodies[*body];
+				let rotation =
+					body.effective_inverse_inertia_tensor_world() * self.relative_positions[side].cross(&normal);
+				inertia[side] = [
+					body.effective_inverse_mass(),
+					rotation.cross(&self.relative_positions[side]).dot(&normal),
+				];
+			}
+		}
+		let total_inertia: Real = inertia.iter().flatten().sum();
+		let mut changes = BodyChanges::default();
+		if total_inertia <= 0.0 {
+			return changes;
+		}
+		for (side, sign) in [(0, 1.0), (1, -1.0)] {
+			let Some(body) = self.contact.bodies[side] else {
+				continue;
+			};
+			let [linear_inertia, angular_inertia] = inertia[side];
+			let share = sign * self.contact.penetration / total_inertia;
+			let limit = ANGULAR_LIMIT * self.relative_positions[side].magnitude();
+			let angular_move = (share * angular_inertia).clamp(-limit, limit);
+			let linear_move = share.mul_add(linear_inertia + angular_inertia, -angular_move);
+			self.move_body(&mut bodies[body], side, [linear_move, angular_move], &mut changes);
+		}
+		changes
+	}
+
+	/// Moves the body on the given side along the normal and turns it, by
+	/// the given amounts at the contact point, recording the changes.
+	fn move_body(&self, body: &mut RigidBody, side: usize, moves: [Real; 2], changes: &mut BodyChanges) {
+		let [linear_move, angular_move] = moves;
+		let normal = self.contact.contact_normal;
+		if angular_move != 0.0 {
+			let relative_position = self.relative_positions[side];
+			let rotation = body.effective_inverse_inertia_tensor_world() * relative_position.cross(&normal);
+			let angular_inertia = rotation.cross(&relative_position).dot(&normal);
+			if angular_inertia > 0.0 {
+				changes.angular[side] = rotation * (angular_move / angular_inertia);
+			}
+		}
+		changes.linear[side] = normal * linear_move;
+		body.position += changes.linear[side];
+		body.orientation.add_scaled_vector(changes.angular[side], 1.0);
+		body.calculate_derived_data();
+	}
+}
+
+/// The contact resolution routine for rigid-body contacts.
+/// One resolver instance can be shared for the whole simulation.
+///
+/// Penetrations are resolved first, the deepest each time, by moving and
+/// turning the bodies apart. Velocities are then resolved, the contact
+/// needing the largest change each time, by applying impulses. After each
+/// step the contacts sharing a body with the one resolved are updated.
+///
+/// Contacts closing slower than the restitution threshold are resting:
+/// they do not rebound, and their penetration is resolved down to the
+/// smaller resting epsilon. This keeps a resting body touching from one
+/// step to the next, rather than sinking in over several steps and being
+/// pushed back out.
+#[derive(Debug, Clone, Copy)]
+pub struct ContactResolver {
+	/// Holds the number of iterations allowed when resolving velocity.
+	pub velocity_iterations: usize,
+
+	/// Holds the number of iterations allowed when resolving penetration.
+	pub position_iterations: usize,
+
+	/// Holds the smallest change in velocity worth resolving.
+	pub velocity_epsilon: Real,
+
+	/// Holds the smallest penetration worth resolving.
+	pub position_epsilon: Real,
+
+	/// Holds the closing speed below which a contact is resting.
+	pub restitution_threshold: Real,
+
+	/// Holds the smallest penetration worth resolving at a resting contact.
+	pub resting_epsilon: Real,
+
+	/// Records the number of velocity iterations used in the last call to `resolve_contacts`.
+	pub velocity_iterations_used: usize,
+
+	/// Records the number of position iterations used in the last call to `resolve_contacts`.
+	pub position_iterations_used: usize,
+}
+
+impl Default for ContactResolver {
+	fn default() -> Self {
+		Self::new(0)
+	}
+}
+
+impl ContactResolver {
+	/// Creates a resolver allowed the given number of iterations for both velocity and penetration.
+	#[must_use]
+	pub const fn new(iterations: usize) -> Self {
+		Self {
+			velocity_iterations: iterations,
+			position_iterations: iterations,
+			velocity_epsilon: 0.01,
+			position_epsilon: 0.01,
+			restitution_threshold: 0.25,
+			resting_epsilon: 0.001,
+			velocity_iterations_used: 0,
+			position_iterations_used: 0,
+		}
+	}
+
+	/// Resolves a set of contacts for both penetration and velocity,
+	/// leaving each contact's penetration as it was after resolution, and
+	/// returns how closely they were resolved.
+	///
+	/// A sleeping body touching one that is awake is woken first, as it is
+	/// about to be pushed.
+	pub fn resolve_contacts(
+		&mut self,
+		contacts: &mut [Contact],
+		bodies: &mut [RigidBody],
+		duration: Real,
+	) -> SolverReport {
+		self.velocity_iterations_used = 0;
+		self.position_iterations_used = 0;
+		if contacts.is_empty() {
+			return SolverReport::default();
+		}
+		for contact in contacts.iter() {
+			match_awake_state(contact, bodies);
+		}
+		let mut prepared: Vec<PreparedContact> = contacts
+			.iter()
+			.map(|contact| PreparedContact::new(*contact, bodies, duration, self.restitution_threshold))
+			.collect();
+		self.adjust_positions(&mut prepared, bodies);
+		self.adjust_velocities(&mut prepared, bodies, duration);
+		for (contact, prepared) in contacts.iter_mut().zip(&prepared) {
+			contact.penetration = prepared.contact.penetration;
+		}
+		SolverReport {
+			velocity_iterations: self.velocity_iterations_used,
+			position_iterations: self.position_iterations_used,
+			residual_penetration: prepared
+				.iter()
+				.map(|contact| contact.contact.penetration)
+				.fold(0.0, Real::max),
+			residual_velocity: prepared
+				.iter()
+				.map(|contact| contact.desired_delta_velocity)
+				.fold(0.0, Real::max),
+		}
+	}
+
+	/// Resolves the deepest penetration until none is worth resolving or the iterations run out.
+	fn adjust_positions(&mut self, contacts: &mut [PreparedContact], bodies: &mut [RigidBody]) {
+		while self.position_iterations_used < self.position_iterations {
+			let epsilon = |contact: &PreparedContact| {
+				if contact.resting {
+					self.resting_epsilon
+				} else {
+					self.position_epsilon
+				}
+			};
+			let Some(deepest) = most_severe(contacts, epsilon, |contact| contact.contact.penetration) else {
+				break;
+			};
+			let resolved = contacts[deepest].contact.bodies;
+			let changes = contacts[deepest].apply_position_change(bodies);
+			for contact in contacts.iter_mut() {
+				for (side, sign) in [(0, -1.0), (1, 1.0)] {
+					let Some(moved) = shared_side(&resolved, contact.contact.bodies[side]) else {
+						continue;
+					};
+					let movement =
+						changes.linear[moved] + changes.angular[moved].cross(&contact.relative_positions[side]);
+					contact.contact.penetration += sign * movement.dot(&contact.contact.contact_normal);
+				}
+			}
+			self.position_iterations_used += 1;
+		}
+	}
+
+	/// Resolves the contact needing the largest change in velocity until
+	/// none is worth resolving or the iterations run out.
+	fn adjust_velocities(&mut self, contacts: &mut [PreparedContact], bodies: &mut [RigidBody], duration: Real) {
+		while self.velocity_iterations_used < self.velocity_iterations {
+			let Some(severest) = most_severe(
+				contacts,
+				|_| self.velocity_epsilon,
+				|contact| contact.desired_delta_velocity,
+			) else {
+				break;
+			};
+			let resolved = contacts[severest].contact.bodies;
+			let changes = contacts[severest].apply_velocity_change(bodies);
+			for contact in contacts.iter_mut() {
+				let mut touched = false;
+				for (side, sign) in [(0, 1.0), (1, -1.0)] {
+					let Some(moved) = shared_side(&resolved, contact.contact.bodies[side]) else {
+						continue;
+					};
+					let change =
+						changes.linear[moved] + changes.angular[moved].cross(&contact.relative_positions[side]);
+					contact.contact_velocity += contact.contact_to_world.transform_transpose(change) * sign;
+					touched = true;
+				}
+				if touched {
+					contact.calculate_desired_delta_velocity(bodies, duration);
+				}
+			}
+			self.velocity_iterations_used += 1;
+		}
+	}
+}
+
+/// Returns the index of the contact with the largest measure above its
+/// epsilon, or `None` if there is none.
+fn most_severe(
+	contacts: &[PreparedContact],
+	epsilon: impl Fn(&PreparedContact) -> Real,
+	measure: impl Fn(&PreparedContact) -> Real,
+) -> Option<usize> {
+	contacts
+		.iter()
+		.enumerate()
+		.filter(|(_, contact)| measure(contact) > epsilon(contact))
+		.map(|(index, contact)| (index, measure(contact)))
+		.max_by(|first, second| first.1.total_cmp(&second.1))
+		.map(|(index, _)| index)
+}
+
+/// Returns the side of the resolved contact holding the given body, if it is one of them.
+fn shared_side(resolved: &[Option<usize>; 2], body: Option<usize>) -> Option<usize> {
+	body.and_then(|body| resolved.iter().position(|other| *other == Some(body)))
+}
+
+/// Wakes the sleeping body of a contact between two bodies if the other is awake.
+fn match_awake_state(contact: &Contact, bodies: &mut [RigidBody]) {
+	let [Some(first), Some(second)] = contact.bodies else {
+		return;
+	};
+	let dynamic = |body: &RigidBody| body.kind == BodyKind::Dynamic;
+	if !dynamic(&bodies[first]) || !dynamic(&bodies[second]) {
+		return;
+	}
+	if bodies[first].sleeping != bodies[second].sleeping {
+		bodies[first].set_awake(true);
+		bodies[second].set_awake(true);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		assert_equal,
+		body::RigidBodyBuilder,
+		collide::{
+			box_and_half_space, sphere_and_half_space, CollisionBox, CollisionData, CollisionPlane, CollisionPrimitive,
+			CollisionSphere,
+		},
+		shape::Shape,
+	};
+
+	use super::*;
+
+	fn ball(position: Vector3, velocity: Vector3) -> RigidBody {
+		RigidBodyBuilder::new(Shape::Sphere { radius: 0.5 })
+			.position(position)
+			.velocity(velocity)
+			.can_sleep(false)
+			.build()
+	}
+
+	fn contact(bodies: [Option<usize>; 2], point: Vector3, normal: Vector3, penetration: Real) -> Contact {
+		Contact {
+			bodies,
+			restitution: 1.0,
+			contact_point: point,
+			contact_normal: normal,
+			penetration,
+			..Default::default()
+		}
+	}
+
+	#[test]
+	pub fn contact_basis_is_orthonormal() {
+		for normal in [
+			Vector3::y_axis(),
+			Vector3::x_axis(),
+			Vector3::new(1.0, 2.0, -3.0).normalize(),
+		] {
+			let basis = contact([Some(0), None], Vector3::zero(), normal, 0.0).contact_basis();
+			assert_eq!(basis.column(0), normal);
+			for (first, second) in [(0, 1), (1, 2), (0, 2)] {
+				assert!(basis.column(first).dot(&basis.column(second)).abs() < 1e-5);
+			}
+			assert!((basis.column(1).magnitude() - 1.0).abs() < 1e-5);
+			assert!((basis.column(1).cross(&basis.column(2)) - normal).magnitude() < 1e-5);
+		}
+	}
+
+	#[test]
+	pub fn elastic_head_on_collision_exchanges_velocities() {
+		let mut bodies = vec![
+			ball(Vector3::zero(), Vector3::new(2.0, 0.0, 0.0)),
+			ball(Vector3::new(0.9, 0.0, 0.0), Vector3::zero()),
+		];
+		let mut contacts = [contact(
+			[Some(0), Some(1)],
+			Vector3::new(0.45, 0.0, 0.0),
+			-Vector3::x_axis(),
+			0.1,
+		)];
+		let mut resolver = ContactResolver::new(4);
+		let report = resolver.resolve_contacts(&mut contacts, &mut bodies, 1.0 / 60.0);
+		assert!(report.converged(1e-4, 1e-4));
+		assert!((bodies[0].velocity - Vector3::zero()).magnitude() < 1e-4);
+		assert!((bodies[1].velocity - Vector3::new(2.0, 0.0, 0.0)).magnitude() < 1e-4);
+		assert!((bodies[1].position.x() - bodies[0].position.x() - 1.0).abs() < 1e-4);
+		assert!(contacts[0].penetration.abs() < 1e-4);
+		assert_eq!(resolver.position_iterations_used, 1);
+		assert_eq!(resolver.velocity_iterations_used, 1);
+		assert_eq!(report.position_iterations, 1);
+
+		// Without iterations nothing is resolved, and the report says so
+		let mut contacts = [contact(
+			[Some(0), Some(1)],
+			Vector3::new(0.45, 0.0, 0.0),
+			-Vector3::x_axis(),
+			0.1,
+		)];
+		bodies[0].velocity = Vector3::new(2.0, 0.0, 0.0);
+		bodies[1].velocity = Vector3::zero();
+		let report = ContactResolver::new(0).resolve_contacts(&mut contacts, &mut bodies, 1.0 / 60.0);
+		assert_equal(report.residual_penetration, 0.1);
+		assert!((report.residual_velocity - 4.0).abs() < 1e-4);
+		assert!(!report.converged(1e-2, 1e-2));
+	}
+
+	#[test]
+	pub fn slow_contacts_do_not_bounce() {
+		let mut bodies = vec![ball(Vector3::new(0.0, 0.5, 0.0), Vector3::new(0.0, -0.1, 0.0))];
+		let mut contacts = [contact([Some(0), None], Vector3::zero(), Vector3::y_axis(), 0.0)];
+		ContactResolver::new(4).resolve_contacts(&mut contacts, &mut bodies, 1.0 / 60.0);
+		assert!(bodies[0].velocity.magnitude() < 1e-5);
+	}
+
+	#[test]
+	pub fn static_friction_stops_sliding() {
+		let mut bodies = vec![ball(Vector3::new(0.0, 0.5, 0.0), Vector3::new(0.5, -2.0, 0.0))];
+		let mut contacts = [Contact {
+			friction: 1.0,
+			restitution: 0.0,
+			..contact([Some(0), None], Vector3::zero(), Vector3::y_axis(), 0.0)
+		}];
+		ContactResolver::new(4).resolve_contacts(&mut contacts, &mut bodies, 1.0 / 60.0);
+
+		// The ball is left rolling, with its contact point still
+		assert!(bodies[0].velocity_at_point(Vector3::zero()).magnitude() < 1e-4);
+		assert!(bodies[0].velocity.x() > 0.0);
+		assert!(bodies[0].angular_velocity.z() < 0.0);
+	}
+
+	#[test]
+	pub fn dynamic_friction_is_limited_by_the_cone() {
+		let mut bodies = vec![ball(Vector3::new(0.0, 0.5, 0.0), Vector3::new(5.0, -1.0, 0.0))];
+		let mut contacts = [Contact {
+			friction: 0.5,
+			restitution: 0.0,
+			..contact([Some(0), None], Vector3::zero(), Vector3::y_axis(), 0.0)
+		}];
+		ContactResolver::new(4).resolve_contacts(&mut contacts, &mut bodies, 1.0 / 60.0);
+
+		// Stopping the fall takes an impulse of one per unit mass, so friction takes off a half
+		assert!(bodies[0].velocity.y().abs() < 1e-4);
+		assert!((bodies[0].velocity.x() - 4.5).abs() < 1e-4);
+		assert!(bodies[0].velocity_at_point(Vector3::zero()).x() > 0.0);
+	}
+
+	#[test]
+	pub fn box_settles_flat_on_the_floor() {
+		let mut bodies = vec![RigidBodyBuilder::new(Shape::default())
+			.position(Vector3::new(0.0, 0.4, 0.0))
+			.velocity(Vector3::new(0.0, -3.0, 0.0))
+			.build()];
+		let mut cuboid = CollisionBox {
+			primitive: CollisionPrimitive::new(0),
+			half_size: Vector3::new(0.5, 0.5, 0.5),
+		};
+		cuboid.primitive.calculate_internals(&bodies);
+		let mut data = CollisionData::new(8);
+		box_and_half_space(&cuboid, &CollisionPlane::new(Vector3::y_axis(), 0.0), &mut data);
+		assert_eq!(data.contacts.len(), 4);
+
+		ContactResolver::new(16).resolve_contacts(&mut data.contacts, &mut bodies, 1.0 / 60.0);
+		let body = bodies[0];
+		assert!((body.position.y() - 0.5).abs() < 0.02);
+		assert!(body.angular_velocity.magnitude() < 1e-3);
+		assert!(body.velocity.y() >= -1e-3);
+		assert!(body.orientation.x.abs() < 1e-2 && body.orientation.z.abs() < 1e-2);
+	}
+
+	/// Drops a bouncy ball on the floor, returning the lowest and highest
+	/// it rests at over the tenth second.
+	fn drop_ball(resolver: &mut ContactResolver) -> (Real, Real) {
+		let mut bodies = vec![RigidBodyBuilder::new(Shape::Sphere { radius: 0.5 })
+			.position(Vector3::new(0.0, 2.0, 0.0))
+			.acceleration(Vector3::new(0.0, -10.0, 0.0))
+			.can_sleep(false)
+			.build()];
+		let floor = CollisionPlane::new(Vector3::y_axis(), 0.0);
+		let (mut lowest, mut highest) = (Real::MAX, Real::MIN);
+		for step in 0..600 {
+			bodies[0].integrate(1.0 / 60.0);
+			let mut sphere = CollisionSphere {
+				primitive: CollisionPrimitive::new(0),
+				radius: 0.5,
+			};
+			sphere.primitive.calculate_internals(&bodies);
+			let mut data = CollisionData::new(1);
+			data.restitution = 0.5;
+			sphere_and_half_space(&sphere, &floor, &mut data);
+			resolver.resolve_contacts(&mut data.contacts, &mut bodies, 1.0 / 60.0);
+			if step >= 540 {
+				lowest = lowest.min(bodies[0].position.y());
+				highest = highest.max(bodies[0].position.y());
+			}
+		}
+		(lowest, highest)
+	}
+
+	#[test]
+	pub fn resting_ball_stays_touching() {
+		let (lowest, highest) = drop_ball(&mut ContactResolver::new(8));
+		assert!(highest - lowest < 1e-3);
+		assert!((highest - 0.5).abs() < 1e-3);
+
+		// Resolving resting contacts no more closely than others lets the ball sink and jump
+		let mut resolver = ContactResolver::new(8);
+		resolver.resting_epsilon = resolver.position_epsilon;
+		let (lowest, highest) = drop_ball(&mut resolver);
+		assert!(highest - lowest > 5e-3);
+	}
+
+	/// Rolls a ball across the floor for three seconds, returning it.
+	fn roll_ball(rolling_friction: Real) -> RigidBody {
+		let mut bodies = vec![RigidBodyBuilder::new(Shape::Sphere { radius: 0.5 })
+			.position(Vector3::new(0.0, 0.5, 0.0))
+			.velocity(Vector3::new(2.0, 0.0, 0.0))
+			.angular_velocity(Vector3::new(0.0, 0.0, -4.0))
+			.acceleration(Vector3::new(0.0, -10.0, 0.0))
+			.can_sleep(false)
+			.build()];
+		let floor = CollisionPlane::new(Vector3::y_axis(), 0.0);
+		let mut resolver = ContactResolver::new(8);
+		for _ in 0..180 {
+			bodies[0].integrate(1.0 / 60.0);
+			let mut sphere = CollisionSphere {
+				primitive: CollisionPrimitive::new(0),
+				radius: 0.5,
+			};
+			sphere.primitive.calculate_internals(&bodies);
+			let mut data = CollisionData::new(1);
+			data.friction = 0.5;
+			data.rolling_friction = rolling_friction;
+			data.restitution = 0.0;
+			sphere_and_half_space(&sphere, &floor, &mut data);
+			resolver.resolve_contacts(&mut data.contacts, &mut bodies, 1.0 / 60.0);
+		}
+		bodies.remove(0)
+	}
+
+	#[test]
+	pub fn rolling_friction_stops_rolling_ball() {
+		let ball = roll_ball(0.05);
+		assert!(ball.velocity.magnitude() < 0.05);
+		assert!(ball.angular_velocity.magnitude() < 0.1);
+
+		// A solid ball slows at 5/7 of the rolling friction times gravity over its radius
+		assert!((ball.position.x() - 2.8).abs() < 0.3);
+
+		// Without it, only damping slows the ball
+		let ball = roll_ball(0.0);
+		assert!(ball.velocity.x() > 1.9);
+	}
+}
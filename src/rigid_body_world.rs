@@ -0,0 +1,130 @@
+use crate::{
+	body::{RigidBody, DEFAULT_SLEEP_EPSILON},
+	body_set::BodySet,
+	energy::{Energy, EnergyAudit},
+	generators::{ForceGenerator, ForceRegistry},
+	Real,
+};
+
+/// Keeps track of a set of rigid bodies and the forces acting on them, and
+/// provides the means to update them all.
+///
+/// Each frame, [`Self::start_frame`] clears the forces left from the last
+/// frame, and [`Self::run_physics`] applies the registered forces and
+/// integrates the bodies. Contacts and joints are left to the caller.
+pub struct RigidBodyWorld {
+	pub bodies: BodySet,
+
+	/// Holds the kinetic energy level below which the bodies are put to sleep.
+	pub sleep_epsilon: Real,
+
+	/// Holds the force generators for the bodies in this world.
+	pub registry: ForceRegistry,
+
+	/// Holds the audit recording the world's energy after each step, if any.
+	pub energy_audit: Option<EnergyAudit>,
+}
+
+impl Default for RigidBodyWorld {
+	fn default() -> Self {
+		Self {
+			bodies: BodySet::new(),
+			sleep_epsilon: DEFAULT_SLEEP_EPSILON,
+			registry: ForceRegistry::default(),
+			energy_audit: None,
+		}
+	}
+}
+
+impl RigidBodyWorld {
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds the body to the world, returning its index.
+	pub fn add_body(&mut self, body: RigidBody) -> usize {
+		self.bodies.push(&body)
+	}
+
+	/// Registers the given force generator to apply to the given body.
+	pub fn add_force_generator(&mut self, body: usize, generator: impl ForceGenerator + 'static) {
+		self.registry.add(body, Box::new(generator));
+	}
+
+	/// Initializes the world for a simulation frame. This clears the force
+	/// and torque accumulators of every body and calculates their derived
+	/// data, in case they were moved since the last frame. The forces for
+	/// the frame can be added once this has been called.
+	pub fn start_frame(&mut self) {
+		self.bodies.clear_accumulators();
+		self.bodies.calculate_derived_data();
+	}
+
+	/// Integrates all the bodies in this world forward in time by the given duration.
+	pub fn integrate(&mut self, duration: Real) {
+		self.bodies.integrate(duration, self.sleep_epsilon);
+	}
+
+	/// Returns the energy of the dynamic bodies' motion and the energy stored
+	/// by their constant accelerations and registered forces.
+	#[must_use]
+	pub fn energy(&self) -> Energy {
+		Energy {
+			kinetic: self.bodies.kinetic_energy(),
+			potential: self.registry.potential_energy(&self.bodies) + self.bodies.potential_energy(None),
+		}
+	}
+
+	/// Applies the registered forces, then integrates the bodies.
+	pub fn run_physics(&mut self, duration: Real) {
+		self.registry.update_forces(&mut self.bodies, duration);
+		self.integrate(duration);
+		if let Some(mut audit) = self.energy_audit.take() {
+			audit.record(self.energy());
+			self.energy_audit = Some(audit);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{body::RigidBodyBuilder, generators::Gravity, shape::Shape, vec::Vector3};
+
+	use super::*;
+
+	#[test]
+	pub fn registered_forces_move_bodies() {
+		let mut world = RigidBodyWorld::new();
+		let ball = world.add_body(
+			RigidBodyBuilder::new(Shape::Sphere { radius: 0.5 })
+				.position(Vector3::new(0.0, 10.0, 0.0))
+				.damping(1.0, 1.0)
+				.can_sleep(false)
+				.build(),
+		);
+		world.add_force_generator(
+			ball,
+			Gravity {
+				gravity: Vector3::new(0.0, -10.0, 0.0),
+			},
+		);
+		for _ in 0..2 {
+			world.start_frame();
+			world.run_physics(0.5);
+		}
+		assert!((world.bodies.velocities()[ball] - Vector3::new(0.0, -10.0, 0.0)).magnitude() < 1e-4);
+		assert!((world.bodies.positions()[ball] - Vector3::new(0.0, 2.5, 0.0)).magnitude() < 1e-4);
+	}
+
+	#[test]
+	pub fn start_frame_clears_forces_and_follows_moved_bodies() {
+		let mut world = RigidBodyWorld::new();
+		let body = world.add_body(RigidBodyBuilder::new(Shape::default()).build());
+		world.bodies.add_force(body, Vector3::x_axis());
+		world.bodies.update(body, |body| body.position = Vector3::new(1.0, 2.0, 3.0));
+		world.start_frame();
+		assert_eq!(world.bodies.forces()[body], Vector3::zero());
+		assert_eq!(world.bodies.transforms()[body].transform(Vector3::zero()), Vector3::new(1.0, 2.0, 3.0));
+	}
+}
@@ -0,0 +1,458 @@
+use std::ops::Range;
+
+use crate::{
+	contacts::{ParticleContact, ParticleContactGenerator},
+	links::{particle_distance, ParticleCable, ParticleCableConstraint, ParticleRod},
+	particle::Particle,
+	real_from_count,
+	vec::Vector3,
+	Real,
+};
+
+/// The kind of link used to join neighbouring particles in a rope.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RopeLink {
+	/// Neighbours are held at a fixed distance.
+	#[default]
+	Rod,
+
+	/// Neighbours may move closer together but not further apart.
+	Cable,
+}
+
+/// Builds a chain of particles joined by rods or cables.
+///
+/// The particles are laid out from `start` to `end`, hanging below the straight
+/// line between them by up to `sag` (measured along the negative y axis at the
+/// middle of the rope). Each link is sized to the initial distance between its
+/// particles, so the rope starts at rest in its sagging shape.
+#[derive(Debug, Clone, Copy)]
+pub struct RopeBuilder {
+	start: Vector3,
+	end: Vector3,
+	segments: usize,
+	total_mass: Real,
+	sag: Real,
+	damping: Real,
+	link: RopeLink,
+	restitution: Real,
+	pin_start: bool,
+	pin_end: bool,
+}
+
+impl RopeBuilder {
+	#[must_use]
+	pub const fn new(start: Vector3, end: Vector3) -> Self {
+		Self {
+			start,
+			end,
+			segments: 10,
+			total_mass: 1.0,
+			sag: 0.0,
+			damping: 0.99,
+			link: RopeLink::Rod,
+			restitution: 0.3,
+			pin_start: false,
+			pin_end: false,
+		}
+	}
+
+	/// Sets the number of links in the rope. A rope always has at least one segment.
+	#[must_use]
+	pub fn segments(mut self, segments: usize) -> Self {
+		self.segments = segments.max(1);
+		self
+	}
+
+	/// Sets the mass of the whole rope, shared evenly between its free particles.
+	#[must_use]
+	pub const fn total_mass(mut self, total_mass: Real) -> Self {
+		self.total_mass = total_mass;
+		self
+	}
+
+	/// Sets how far the middle of the rope hangs below the line from start to end.
+	#[must_use]
+	pub const fn sag(mut self, sag: Real) -> Self {
+		self.sag = sag;
+		self
+	}
+
+	/// Sets the damping applied to every particle in the rope.
+	#[must_use]
+	pub const fn damping(mut self, damping: Real) -> Self {
+		self.damping = damping;
+		self
+	}
+
+	/// Sets the kind of link used between neighbouring particles.
+	#[must_use]
+	pub const fn link(mut self, link: RopeLink) -> Self {
+		self.link = link;
+		self
+	}
+
+	/// Sets the restitution used by cable links.
+	#[must_use]
+	pub const fn restitution(mut self, restitution: Real) -> Self {
+		self.restitution = restitution;
+		self
+	}
+
+	/// Gives the first particle infinite mass so it stays where it is placed.
+	#[must_use]
+	pub const fn pin_start(mut self, pin_start: bool) -> Self {
+		self.pin_start = pin_start;
+		self
+	}
+
+	/// Gives the last particle infinite mass so it stays where it is placed.
+	#[must_use]
+	pub const fn pin_end(mut self, pin_end: bool) -> Self {
+		self.pin_end = pin_end;
+		self
+	}
+
+	/// Returns the position of the particle at the given index along the rope.
+	#[must_use]
+	pub fn position(&self, index: usize) -> Vector3 {
+		let t = real_from_count(index) / real_from_count(self.segments);
+		let drop = self.sag * 4.0 * t * (1.0 - t);
+		self.start + (self.end - self.start) * t - Vector3::y_axis() * drop
+	}
+
+	/// Appends the rope's particles to the given list and returns
+	/// the rope describing the links between them.
+	pub fn build(self, particles: &mut Vec<Particle>) -> Rope {
+		let first = particles.len();
+		let count = self.segments + 1;
+		let pinned = usize::from(self.pin_start) + usize::from(self.pin_end);
+		let free = count.saturating_sub(pinned);
+		let inverse_mass = if free > 0 && self.total_mass > 0.0 {
+			real_from_count(free) / self.total_mass
+		} else {
+			0.0
+		};
+
+		particles.extend((0..count).map(|index| {
+			let is_pinned = (index == 0 && self.pin_start) || (index == self.segments && self.pin_end);
+			Particle {
+				position: self.position(index),
+				damping: self.damping,
+				inverse_mass: if is_pinned { 0.0 } else { inverse_mass },
+				..Default::default()
+			}
+		}));
+
+		let mut rope = Rope {
+			particles: first..first + count,
+			..Default::default()
+		};
+		for index in first..first + self.segments {
+			let pair = [index, index + 1];
+			let length = particle_distance(particles, pair);
+			match self.link {
+				RopeLink::Rod => rope.rods.push(ParticleRod {
+					particles: pair,
+					length,
+				}),
+				RopeLink::Cable => rope.cables.push(ParticleCable {
+					particles: pair,
+					max_length: length,
+					restitution: self.restitution,
+				}),
+			}
+		}
+		rope
+	}
+}
+
+/// A chain of particles created by a [`RopeBuilder`].
+#[derive(Debug, Default, Clone)]
+pub struct Rope {
+	/// Holds the indices of the rope's particles, ordered from start to end.
+	pub particles: Range<usize>,
+
+	/// Holds the rods joining neighbouring particles.
+	pub rods: Vec<ParticleRod>,
+
+	/// Holds the cables joining neighbouring particles.
+	pub cables: Vec<ParticleCable>,
+}
+
+impl ParticleContactGenerator for Rope {
+	fn add_contact(&self, particles: &[Particle], contacts: &mut Vec<ParticleContact>) -> usize {
+		self.rods
+			.iter()
+			.map(|rod| rod.add_contact(particles, contacts))
+			.sum::<usize>()
+			+ self
+				.cables
+				.iter()
+				.map(|cable| cable.add_contact(particles, contacts))
+				.sum::<usize>()
+	}
+}
+
+/// Builds a suspension bridge.
+///
+/// The deck is made of paired particles running from `start` to `end`, with
+/// rods across the deck, cables along each side, and optional support cables
+/// hanging from anchors above the deck.
+#[derive(Debug, Clone, Copy)]
+pub struct BridgeBuilder {
+	start: Vector3,
+	end: Vector3,
+	segments: usize,
+	width: Real,
+	total_mass: Real,
+	sag: Real,
+	damping: Real,
+	restitution: Real,
+	support_height: Option<Real>,
+	pin_ends: bool,
+}
+
+impl BridgeBuilder {
+	#[must_use]
+	pub const fn new(start: Vector3, end: Vector3) -> Self {
+		Self {
+			start,
+			end,
+			segments: 5,
+			width: 2.0,
+			total_mass: 1.0,
+			sag: 0.0,
+			damping: 0.9,
+			restitution: 0.3,
+			support_height: None,
+			pin_ends: false,
+		}
+	}
+
+	/// Sets the number of deck sections. A bridge always has at least one section.
+	#[must_use]
+	pub fn segments(mut self, segments: usize) -> Self {
+		self.segments = segments.max(1);
+		self
+	}
+
+	/// Sets the distance between the two sides of the deck.
+	#[must_use]
+	pub const fn width(mut self, width: Real) -> Self {
+		self.width = width;
+		self
+	}
+
+	/// Sets the mass of the whole deck, shared evenly between its free particles.
+	#[must_use]
+	pub const fn total_mass(mut self, total_mass: Real) -> Self {
+		self.total_mass = total_mass;
+		self
+	}
+
+	/// Sets how far the middle of the deck hangs below the line from start to end.
+	#[must_use]
+	pub const fn sag(mut self, sag: Real) -> Self {
+		self.sag = sag;
+		self
+	}
+
+	/// Sets the damping applied to every particle in the deck.
+	#[must_use]
+	pub const fn damping(mut self, damping: Real) -> Self {
+		self.damping = damping;
+		self
+	}
+
+	/// Sets the restitution used by the side and support cables.
+	#[must_use]
+	pub const fn restitution(mut self, restitution: Real) -> Self {
+		self.restitution = restitution;
+		self
+	}
+
+	/// Hangs every deck particle from an anchor the given height above it.
+	#[must_use]
+	pub const fn supports(mut self, height: Real) -> Self {
+		self.support_height = Some(height);
+		self
+	}
+
+	/// Gives the four corner particles infinite mass so the deck is held at both ends.
+	#[must_use]
+	pub const fn pin_ends(mut self, pin_ends: bool) -> Self {
+		self.pin_ends = pin_ends;
+		self
+	}
+
+	/// Appends the deck particles to the given list and returns the bridge
+	/// describing the constraints between them. Particles are stored in pairs,
+	/// one from each side of the deck.
+	pub fn build(self, particles: &mut Vec<Particle>) -> Bridge {
+		let first = particles.len();
+		let side = (self.end - self.start).cross(&Vector3::y_axis()).normalize() * (self.width * 0.5);
+		let deck = RopeBuilder::new(self.start, self.end)
+			.segments(self.segments)
+			.sag(self.sag);
+
+		let pinned = if self.pin_ends { 4 } else { 0 };
+		let free = (2 * (self.segments + 1)).saturating_sub(pinned);
+		let inverse_mass = if free > 0 && self.total_mass > 0.0 {
+			real_from_count(free) / self.total_mass
+		} else {
+			0.0
+		};
+
+		for index in 0..=self.segments {
+			let is_pinned = self.pin_ends && (index == 0 || index == self.segments);
+			let center = deck.position(index);
+			for position in [center + side, center - side] {
+				particles.push(Particle {
+					position,
+					damping: self.damping,
+					inverse_mass: if is_pinned { 0.0 } else { inverse_mass },
+					..Default::default()
+				});
+			}
+		}
+
+		self.link(particles, first)
+	}
+
+	fn link(&self, particles: &[Particle], first: usize) -> Bridge {
+		let mut bridge = Bridge {
+			particles: first..particles.len(),
+			..Default::default()
+		};
+		for index in 0..=self.segments {
+			let left = first + index * 2;
+			bridge.rods.push(ParticleRod {
+				particles: [left, left + 1],
+				length: self.width,
+			});
+			if index < self.segments {
+				for pair in [[left, left + 2], [left + 1, left + 3]] {
+					bridge.cables.push(ParticleCable {
+						particles: pair,
+						max_length: particle_distance(particles, pair),
+						restitution: self.restitution,
+					});
+				}
+			}
+			if let Some(height) = self.support_height {
+				for particle in [left, left + 1] {
+					bridge.supports.push(ParticleCableConstraint {
+						particle,
+						anchor: particles[particle].position + Vector3::y_axis() * height,
+						max_length: height,
+						restitution: self.restitution,
+					});
+				}
+			}
+		}
+		bridge
+	}
+}
+
+/// A suspension bridge created by a [`BridgeBuilder`].
+#[derive(Debug, Default, Clone)]
+pub struct Bridge {
+	/// Holds the indices of the deck particles, stored in pairs across the deck.
+	pub particles: Range<usize>,
+
+	/// Holds the rods running across the deck.
+	pub rods: Vec<ParticleRod>,
+
+	/// Holds the cables running along each side of the deck.
+	pub cables: Vec<ParticleCable>,
+
+	/// Holds the cables hanging the deck from its anchors.
+	pub supports: Vec<ParticleCableConstraint>,
+}
+
+impl ParticleContactGenerator for Bridge {
+	fn add_contact(&self, particles: &[Particle], contacts: &mut Vec<ParticleContact>) -> usize {
+		let rods = self
+			.rods
+			.iter()
+			.map(|rod| rod.add_contact(particles, contacts))
+			.sum::<usize>();
+		let cables = self
+			.cables
+			.iter()
+			.map(|cable| cable.add_contact(particles, contacts))
+			.sum::<usize>();
+		let supports = self
+			.supports
+			.iter()
+			.map(|support| support.add_contact(particles, contacts))
+			.sum::<usize>();
+		rods + cables + supports
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::assert_equal;
+
+	use super::*;
+
+	#[test]
+	pub fn rope_layout() {
+		let mut particles = vec![Particle::default()];
+		let rope = RopeBuilder::new(Vector3::zero(), Vector3::new(4.0, 0.0, 0.0))
+			.segments(4)
+			.total_mass(2.0)
+			.pin_start(true)
+			.build(&mut particles);
+		assert_eq!(rope.particles, 1..6);
+		assert_eq!(rope.rods.len(), 4);
+		assert!(rope.cables.is_empty());
+		assert_equal(particles[1].inverse_mass, 0.0);
+		assert_equal(particles[2].mass(), 0.5);
+		assert_eq!(particles[5].position, Vector3::new(4.0, 0.0, 0.0));
+		assert_equal(rope.rods[0].length, 1.0);
+	}
+
+	#[test]
+	pub fn rope_sag() {
+		let mut particles = Vec::new();
+		let rope = RopeBuilder::new(Vector3::zero(), Vector3::new(2.0, 0.0, 0.0))
+			.segments(2)
+			.sag(1.0)
+			.link(RopeLink::Cable)
+			.build(&mut particles);
+		assert_eq!(particles[1].position, Vector3::new(1.0, -1.0, 0.0));
+		assert_eq!(rope.cables.len(), 2);
+		assert_equal(rope.cables[0].max_length, Real::sqrt(2.0));
+	}
+
+	#[test]
+	pub fn rope_at_rest_generates_no_contacts() {
+		let mut particles = Vec::new();
+		let rope = RopeBuilder::new(Vector3::zero(), Vector3::new(3.0, 0.0, 0.0))
+			.segments(3)
+			.build(&mut particles);
+		let mut contacts = Vec::new();
+		assert_eq!(rope.add_contact(&particles, &mut contacts), 0);
+	}
+
+	#[test]
+	pub fn bridge_layout() {
+		let mut particles = Vec::new();
+		let bridge = BridgeBuilder::new(Vector3::zero(), Vector3::new(4.0, 0.0, 0.0))
+			.segments(4)
+			.supports(3.0)
+			.pin_ends(true)
+			.build(&mut particles);
+		assert_eq!(bridge.particles, 0..10);
+		assert_eq!(bridge.rods.len(), 5);
+		assert_eq!(bridge.cables.len(), 8);
+		assert_eq!(bridge.supports.len(), 10);
+		assert_equal(particles[0].inverse_mass, 0.0);
+		assert_equal(particles[9].inverse_mass, 0.0);
+		assert_equal(particles[2].mass(), 1.0 / 6.0);
+		assert_equal((particles[0].position - particles[1].position).magnitude(), 2.0);
+	}
+}
@@ -0,0 +1,430 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	body::{BodyKind, RigidBody, RigidBodyBuilder, DEFAULT_SLEEP_EPSILON},
+	collide::CollisionPlane,
+	generators::{Buoyancy, Explosion, Gravity, Spring, Wind},
+	joint::{
+		DistanceJoint, FixedJoint, HingeJoint, Joint, JointKind, JointLimit, JointStrength, PrismaticJoint, SpringAxis,
+		SpringJoint,
+	},
+	material::Material,
+	quaternion::Quaternion,
+	shape::Shape,
+	vec::Vector3,
+	world::{PhysicsWorld, DEFAULT_GRAVITY},
+	Real,
+};
+
+/// Why a scene could not be read or built.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SceneError {
+	/// The description could not be read, for the given reason.
+	Parse(String),
+
+	/// More than one body was given the same name.
+	DuplicateName(String),
+
+	/// A joint or force named a body that does not exist.
+	UnknownBody(String),
+}
+
+impl fmt::Display for SceneError {
+	fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Parse(reason) => write!(formatter, "the scene could not be read: {reason}"),
+			Self::DuplicateName(name) => write!(formatter, "more than one body is named {name:?}"),
+			Self::UnknownBody(name) => write!(formatter, "no body is named {name:?}"),
+		}
+	}
+}
+
+impl std::error::Error for SceneError {}
+
+/// A physics scene written as data, so that it can be authored in a RON or
+/// JSON file and built into a [`PhysicsWorld`] without recompiling.
+///
+/// Bodies are referred to by name from the joints and forces, and every
+/// field but a body's shape may be left out to take its default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "serde", deny_unknown_fields)]
+pub struct SceneDescription {
+	#[serde(default = "default_gravity")]
+	pub gravity: Vector3,
+
+	#[serde(default = "default_sleep_epsilon")]
+	pub sleep_epsilon: Real,
+
+	#[serde(default)]
+	pub planes: Vec<CollisionPlane>,
+
+	#[serde(default)]
+	pub bodies: Vec<BodyDescription>,
+
+	#[serde(default)]
+	pub joints: Vec<JointDescription>,
+
+	#[serde(default)]
+	pub forces: Vec<ForceDescription>,
+}
+
+const fn default_gravity() -> Vector3 {
+	DEFAULT_GRAVITY
+}
+
+const fn default_sleep_epsilon() -> Real {
+	DEFAULT_SLEEP_EPSILON
+}
+
+const fn default_gravity_scale() -> Real {
+	1.0
+}
+
+/// A body with a collider of its shape.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "serde", deny_unknown_fields)]
+pub struct BodyDescription {
+	/// Holds the name the joints and forces refer to the body by, if any.
+	#[serde(default)]
+	pub name: Option<String>,
+
+	pub shape: Shape,
+
+	#[serde(default)]
+	pub kind: BodyKind,
+
+	#[serde(default)]
+	pub position: Vector3,
+
+	#[serde(default)]
+	pub orientation: Quaternion,
+
+	#[serde(default)]
+	pub velocity: Vector3,
+
+	#[serde(default)]
+	pub angular_velocity: Vector3,
+
+	/// Holds how much of the scene's gravity the body feels.
+	#[serde(default = "default_gravity_scale")]
+	pub gravity_scale: Real,
+
+	/// Holds the gravity the body feels in place of the scene's, if any.
+	#[serde(default)]
+	pub gravity_override: Option<Vector3>,
+
+	/// Holds what the body is made of, giving its mass from its density
+	/// and its contacts their friction and restitution.
+	#[serde(default)]
+	pub material: Option<Material>,
+
+	/// Holds the mass of the body, in place of the one its density gives.
+	#[serde(default)]
+	pub mass: Option<Real>,
+
+	#[serde(default)]
+	pub continuous_collision: bool,
+
+	/// Holds a value of the user's choosing, passed back in the world's events.
+	#[serde(default)]
+	pub user_data: u64,
+}
+
+/// A joint between two bodies named in the scene, or between a body and
+/// the scenery named by `None`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "serde", deny_unknown_fields)]
+pub struct JointDescription {
+	pub bodies: [Option<String>; 2],
+
+	pub kind: JointKindDescription,
+
+	/// Holds the most the joint can bear before it breaks, or `None` if it never breaks.
+	#[serde(default)]
+	pub strength: Option<JointStrength>,
+}
+
+/// The kinds of joint, each anchored at points in world coordinates as
+/// the bodies are placed in the scene.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(crate = "serde")]
+pub enum JointKindDescription {
+	Hinge {
+		anchor: Vector3,
+		axis: Vector3,
+		#[serde(default)]
+		limit: Option<JointLimit>,
+	},
+	Fixed {
+		anchor: Vector3,
+	},
+	Distance {
+		anchors: [Vector3; 2],
+	},
+	Prismatic {
+		anchor: Vector3,
+		axis: Vector3,
+	},
+
+	/// A joint holding the anchors together while leaving the bodies free to turn.
+	Ball {
+		anchor: Vector3,
+	},
+}
+
+/// A force generator applied to a body named in the scene.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "serde", deny_unknown_fields)]
+pub struct ForceDescription {
+	pub body: String,
+	pub generator: GeneratorDescription,
+}
+
+/// The force generators a scene can apply.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "serde")]
+pub enum GeneratorDescription {
+	Gravity(Gravity),
+	Buoyancy(Buoyancy),
+	Explosion(Explosion),
+	Wind(Wind),
+
+	/// A [`Spring`] to another body named in the scene, or to a fixed point in
+	/// the world if there is none.
+	Spring {
+		connection_point: Vector3,
+		#[serde(default)]
+		other: Option<String>,
+		other_connection_point: Vector3,
+		spring_constant: Real,
+		rest_length: Real,
+	},
+}
+
+/// A world built from a [`SceneDescription`], along with the index of each named body.
+pub struct Scene {
+	pub world: PhysicsWorld,
+	names: BTreeMap<String, usize>,
+}
+
+impl Scene {
+	/// Returns the index of the body with the given name.
+	#[must_use]
+	pub fn body(&self, name: &str) -> Option<usize> {
+		self.names.get(name).copied()
+	}
+}
+
+impl SceneDescription {
+	/// Reads a scene written in RON.
+	///
+	/// # Errors
+	///
+	/// Will return [`SceneError::Parse`] if the text is not a valid scene.
+	pub fn from_ron(text: &str) -> Result<Self, SceneError> {
+		ron::from_str(text).map_err(|error| SceneError::Parse(error.to_string()))
+	}
+
+	/// Reads a scene written in JSON.
+	///
+	/// # Errors
+	///
+	/// Will return [`SceneError::Parse`] if the text is not a valid scene.
+	pub fn from_json(text: &str) -> Result<Self, SceneError> {
+		serde_json::from_str(text).map_err(|error| SceneError::Parse(error.to_string()))
+	}
+
+	/// Builds a world holding the scene, with the bodies in the order they are given.
+	///
+	/// # Errors
+	///
+	/// Will return an error if two bodies share a name, or if a joint or
+	/// force names a body that does not exist.
+	pub fn build(&self) -> Result<Scene, SceneError> {
+		let mut scene = Scene {
+			world: PhysicsWorld::new(),
+			names: BTreeMap::new(),
+		};
+		scene.world.gravity = self.gravity;
+		scene.world.sleep_epsilon = self.sleep_epsilon;
+		scene.world.planes.clone_from(&self.planes);
+		for description in &self.bodies {
+			let body = scene.world.add_body(description.build());
+			let collider = scene.world.add_collider(body, &description.shape);
+			scene.world.colliders[collider].primitive_mut().material = description.material;
+			if let Some(name) = &description.name {
+				if scene.names.insert(name.clone(), body).is_some() {
+					return Err(SceneError::DuplicateName(name.clone()));
+				}
+			}
+		}
+		let bodies: Vec<RigidBody> = scene.world.bodies.iter().collect();
+		for description in &self.joints {
+			let joined = [
+				scene.find(description.bodies[0].as_deref())?,
+				scene.find(description.bodies[1].as_deref())?,
+			];
+			let mut joint = Joint::new(description.kind.build(joined, &bodies));
+			joint.strength = description.strength;
+			scene.world.add_joint(joint);
+		}
+		for description in &self.forces {
+			let body = scene.find_named(&description.body)?;
+			scene.add_generator(body, &description.generator)?;
+		}
+		Ok(scene)
+	}
+}
+
+impl Scene {
+	fn find_named(&self, name: &str) -> Result<usize, SceneError> {
+		self.body(name).ok_or_else(|| SceneError::UnknownBody(name.to_owned()))
+	}
+
+	fn find(&self, name: Option<&str>) -> Result<Option<usize>, SceneError> {
+		name.map(|name| self.find_named(name)).transpose()
+	}
+
+	fn add_generator(&mut self, body: usize, generator: &GeneratorDescription) -> Result<(), SceneError> {
+		match generator {
+			GeneratorDescription::Gravity(gravity) => self.world.add_force_generator(body, *gravity),
+			GeneratorDescription::Buoyancy(buoyancy) => self.world.add_force_generator(body, *buoyancy),
+			GeneratorDescription::Explosion(explosion) => self.world.add_force_generator(body, *explosion),
+			GeneratorDescription::Wind(wind) => self.world.add_force_generator(body, *wind),
+			GeneratorDescription::Spring {
+				connection_point,
+				other,
+				other_connection_point,
+				spring_constant,
+				rest_length,
+			} => {
+				let spring = Spring {
+					connection_point: *connection_point,
+					other: self.find(other.as_deref())?,
+					other_connection_point: *other_connection_point,
+					spring_constant: *spring_constant,
+					rest_length: *rest_length,
+				};
+				self.world.add_force_generator(body, spring);
+			},
+		}
+		Ok(())
+	}
+}
+
+impl BodyDescription {
+	fn build(&self) -> RigidBody {
+		let mut builder = RigidBodyBuilder::new(self.shape)
+			.kind(self.kind)
+			.position(self.position)
+			.orientation(self.orientation)
+			.velocity(self.velocity)
+			.angular_velocity(self.angular_velocity)
+			.gravity_scale(self.gravity_scale)
+			.continuous_collision(self.continuous_collision)
+			.user_data(self.user_data);
+		if let Some(material) = &self.material {
+			builder = builder.material(material);
+		}
+		if let Some(mass) = self.mass {
+			builder = builder.mass(mass);
+		}
+		if let Some(gravity) = self.gravity_override {
+			builder = builder.gravity_override(gravity);
+		}
+		builder.build()
+	}
+}
+
+impl JointKindDescription {
+	fn build(self, joined: [Option<usize>; 2], bodies: &[RigidBody]) -> JointKind {
+		match self {
+			Self::Hinge { anchor, axis, limit } => {
+				let hinge = HingeJoint::new(joined, bodies, anchor, axis);
+				JointKind::Hinge(limit.map_or(hinge, |limit| hinge.with_limit(limit.lower, limit.upper)))
+			},
+			Self::Fixed { anchor } => JointKind::Fixed(FixedJoint::new(joined, bodies, anchor)),
+			Self::Distance { anchors } => JointKind::Distance(DistanceJoint::new(joined, bodies, anchors)),
+			Self::Prismatic { anchor, axis } => JointKind::Prismatic(PrismaticJoint::new(joined, bodies, anchor, axis)),
+			Self::Ball { anchor } => {
+				JointKind::Spring(SpringJoint::new(joined, bodies, anchor).with_angular([SpringAxis::Free; 3]))
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::assert_equal;
+
+	use super::*;
+
+	const SCENE: &str = r#"(
+		planes: [(normal: (0.0, 1.0, 0.0), offset: 0.0)],
+		bodies: [
+			(name: Some("post"), shape: Cuboid(half_size: (0.1, 1.0, 0.1)), kind: Static, position: (0.0, 1.5, 0.0)),
+			(
+				name: Some("door"),
+				shape: Cuboid(half_size: (0.5, 1.0, 0.05)),
+				position: (0.7, 1.5, 0.0),
+				velocity: (0.0, 0.0, 1.0),
+				material: Some((friction: 0.2, rolling_friction: 0.0, restitution: 0.0, density: 500.0)),
+			),
+			(name: Some("ball"), shape: Sphere(radius: 0.25), position: (3.0, 2.0, 0.0), mass: Some(2.0)),
+		],
+		joints: [(
+			bodies: (Some("post"), Some("door")),
+			kind: Hinge(anchor: (0.15, 1.5, 0.0), axis: (0.0, 1.0, 0.0)),
+		)],
+		forces: [(body: "ball", generator: Gravity((gravity: (0.0, 15.0, 0.0))))],
+	)"#;
+
+	#[test]
+	pub fn scenes_are_built_from_ron() {
+		let mut scene = SceneDescription::from_ron(SCENE)
+			.and_then(|description| description.build())
+			.expect("the scene is valid");
+		let [door, ball] = ["door", "ball"].map(|name| scene.body(name).expect("the body is named"));
+		assert_eq!(scene.world.bodies.len(), 3);
+		assert_eq!(scene.world.colliders.len(), 3);
+		assert_eq!(scene.world.joints.len(), 1);
+		assert_eq!(scene.world.bodies.get(door).velocity, Vector3::new(0.0, 0.0, 1.0));
+		assert!((scene.world.bodies.get(door).mass() - 100.0).abs() < 1e-2);
+		assert!((scene.world.bodies.get(ball).mass() - 2.0).abs() < 1e-4);
+
+		// The door swings about its hinge, and the ball's own force outweighs gravity
+		for _ in 0..30 {
+			scene.world.step(1.0 / 60.0);
+		}
+		assert!(scene.world.bodies.get(door).angular_velocity.y() < -0.5);
+		assert!(scene.world.bodies.get(ball).position.y() > 2.0);
+	}
+
+	#[test]
+	pub fn scenes_are_read_from_json() {
+		let text = r#"{"gravity": [0.0, 0.0, 0.0], "bodies": [{"shape": {"Sphere": {"radius": 1.0}}}]}"#;
+		let scene = SceneDescription::from_json(text).expect("the scene is valid");
+		assert_eq!(scene.gravity, Vector3::zero());
+		assert_eq!(scene.bodies[0].kind, BodyKind::Dynamic);
+		assert_equal(scene.bodies[0].gravity_scale, 1.0);
+		assert_equal(scene.sleep_epsilon, DEFAULT_SLEEP_EPSILON);
+		assert!(SceneDescription::from_json(r#"{"bodies": [{}]}"#).is_err());
+	}
+
+	#[test]
+	pub fn names_must_be_unique_and_known() {
+		let mut description = SceneDescription::from_ron(SCENE).expect("the scene is valid");
+		description.forces[0].body = "lamp".to_owned();
+		assert_eq!(
+			description.build().err(),
+			Some(SceneError::UnknownBody("lamp".to_owned()))
+		);
+		description.bodies[1].name = Some("post".to_owned());
+		assert_eq!(
+			description.build().err(),
+			Some(SceneError::DuplicateName("post".to_owned()))
+		);
+	}
+}
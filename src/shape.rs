@@ -0,0 +1,279 @@
+use std::f32::consts::PI;
+
+use crate::{matrix::Matrix3, vec::Vector3, Real};
+
+/// A solid shape used to derive the mass properties of a rigid body.
+///
+/// Shapes are centered on the body's center of mass. Capsules and cylinders
+/// are aligned with the body's y axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub enum Shape {
+	Sphere {
+		radius: Real,
+	},
+
+	/// A box, given by half its size along each axis.
+	Cuboid {
+		half_size: Vector3,
+	},
+
+	/// A cylinder capped with hemispheres, where `half_height`
+	/// is half the length of the cylindrical part.
+	Capsule {
+		radius: Real,
+		half_height: Real,
+	},
+
+	Cylinder {
+		radius: Real,
+		half_height: Real,
+	},
+}
+
+impl Default for Shape {
+	fn default() -> Self {
+		Self::Cuboid {
+			half_size: Vector3::new(0.5, 0.5, 0.5),
+		}
+	}
+}
+
+impl Shape {
+	/// Returns a number identifying the kind of shape, used to order and
+	/// group work by shape type.
+	#[must_use]
+	pub const fn type_index(&self) -> usize {
+		match self {
+			Self::Sphere { .. } => 0,
+			Self::Cuboid { .. } => 1,
+			Self::Capsule { .. } => 2,
+			Self::Cylinder { .. } => 3,
+		}
+	}
+
+	#[must_use]
+	pub fn volume(&self) -> Real {
+		match *self {
+			Self::Sphere { radius } => sphere_volume(radius),
+			Self::Cuboid { half_size } => 8.0 * half_size.x() * half_size.y() * half_size.z(),
+			Self::Capsule { radius, half_height } => cylinder_volume(radius, half_height) + sphere_volume(radius),
+			Self::Cylinder { radius, half_height } => cylinder_volume(radius, half_height),
+		}
+	}
+
+	/// Returns the inertia tensor of the shape about its center
+	/// when it is solid with the given total mass.
+	#[must_use]
+	pub fn inertia_tensor(&self, mass: Real) -> Matrix3 {
+		match *self {
+			Self::Sphere { radius } => Matrix3::diagonal(Vector3::new(1.0, 1.0, 1.0) * (0.4 * mass * radius * radius)),
+			Self::Cuboid { half_size } => {
+				let squared = half_size * half_size;
+				Matrix3::diagonal(
+					Vector3::new(
+						squared.y() + squared.z(),
+						squared.x() + squared.z(),
+						squared.x() + squared.y(),
+					) * (mass / 3.0),
+				)
+			},
+			Self::Capsule { radius, half_height } => capsule_inertia_tensor(radius, half_height, mass),
+			Self::Cylinder { radius, half_height } => {
+				let axial = 0.5 * mass * radius * radius;
+				let transverse = mass * radius.mul_add(radius * 0.25, half_height * half_height / 3.0);
+				Matrix3::diagonal(Vector3::new(transverse, axial, transverse))
+			},
+		}
+	}
+}
+
+impl Shape {
+	/// Returns the point in or on the shape closest to the given point,
+	/// both in the shape's own coordinates.
+	#[must_use]
+	pub fn closest_point(&self, point: Vector3) -> Vector3 {
+		match *self {
+			Self::Sphere { radius } => clamp_length(point, radius),
+			Self::Cuboid { half_size } => {
+				let mut clamped = point;
+				for axis in 0..3 {
+					clamped[axis] = point[axis].clamp(-half_size[axis], half_size[axis]);
+				}
+				clamped
+			},
+			Self::Capsule { radius, half_height } => {
+				let center = Vector3::new(0.0, point.y().clamp(-half_height, half_height), 0.0);
+				center + clamp_length(point - center, radius)
+			},
+			Self::Cylinder { radius, half_height } => {
+				let radial = clamp_length(Vector3::new(point.x(), 0.0, point.z()), radius);
+				Vector3::new(radial.x(), point.y().clamp(-half_height, half_height), radial.z())
+			},
+		}
+	}
+}
+
+impl Shape {
+	/// Returns the area of the shape's silhouette when viewed along the given
+	/// direction, in the shape's own coordinates. This is the area a flow in
+	/// that direction would strike.
+	#[must_use]
+	pub fn projected_area(&self, direction: Vector3) -> Real {
+		let direction = direction.normalize();
+		match *self {
+			Self::Sphere { radius } => PI * radius * radius,
+			Self::Cuboid { half_size } => {
+				let face_areas = Vector3::new(
+					half_size.y() * half_size.z(),
+					half_size.x() * half_size.z(),
+					half_size.x() * half_size.y(),
+				) * 4.0;
+				(0..3).map(|axis| face_areas[axis] * direction[axis].abs()).sum()
+			},
+			Self::Capsule { radius, half_height } => {
+				let side = direction.y().mul_add(-direction.y(), 1.0).max(0.0).sqrt();
+				(4.0 * radius * half_height).mul_add(side, PI * radius * radius)
+			},
+			Self::Cylinder { radius, half_height } => {
+				let side = direction.y().mul_add(-direction.y(), 1.0).max(0.0).sqrt();
+				(4.0 * radius * half_height).mul_add(side, PI * radius * radius * direction.y().abs())
+			},
+		}
+	}
+}
+
+/// Shortens the given vector to the given length if it is longer.
+fn clamp_length(vector: Vector3, length: Real) -> Vector3 {
+	if vector.magnitude_squared() > length * length {
+		vector.normalize() * length
+	} else {
+		vector
+	}
+}
+
+fn sphere_volume(radius: Real) -> Real {
+	4.0 / 3.0 * PI * radius.powi(3)
+}
+
+fn cylinder_volume(radius: Real, half_height: Real) -> Real {
+	PI * radius * radius * 2.0 * half_height
+}
+
+/// Sums the inertia of the cylindrical part and the two hemispherical caps,
+/// with the caps moved out to the ends of the cylinder.
+fn capsule_inertia_tensor(radius: Real, half_height: Real, mass: Real) -> Matrix3 {
+	let volume = cylinder_volume(radius, half_height) + sphere_volume(radius);
+	if volume <= 0.0 {
+		return Matrix3::zero();
+	}
+	let cylinder_mass = mass * cylinder_volume(radius, half_height) / volume;
+	let cap_mass = (mass - cylinder_mass) * 0.5;
+	let radius_squared = radius * radius;
+	let height = 2.0 * half_height;
+
+	let axial = radius_squared * cylinder_mass.mul_add(0.5, cap_mass * 0.8);
+	let cylinder_transverse = cylinder_mass * radius_squared.mul_add(0.25, height * height / 12.0);
+	let cap_transverse =
+		cap_mass * (0.375 * height).mul_add(radius, radius_squared.mul_add(0.4, half_height * half_height));
+	let transverse = cap_transverse.mul_add(2.0, cylinder_transverse);
+	Matrix3::diagonal(Vector3::new(transverse, axial, transverse))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::assert_equal;
+
+	use super::*;
+
+	#[test]
+	pub fn cuboid() {
+		let shape = Shape::Cuboid {
+			half_size: Vector3::new(1.0, 2.0, 3.0),
+		};
+		assert_equal(shape.volume(), 48.0);
+		assert_eq!(
+			shape.inertia_tensor(3.0),
+			Matrix3::diagonal(Vector3::new(13.0, 10.0, 5.0))
+		);
+	}
+
+	#[test]
+	pub fn sphere() {
+		let shape = Shape::Sphere { radius: 2.0 };
+		assert!((shape.volume() / PI - 32.0 / 3.0).abs() < 1e-5);
+		assert_eq!(
+			shape.inertia_tensor(5.0),
+			Matrix3::diagonal(Vector3::new(8.0, 8.0, 8.0))
+		);
+	}
+
+	#[test]
+	pub fn capsule_without_cylinder_is_a_sphere() {
+		let capsule = Shape::Capsule {
+			radius: 2.0,
+			half_height: 0.0,
+		};
+		let sphere = Shape::Sphere { radius: 2.0 };
+		assert_equal(capsule.volume(), sphere.volume());
+		assert_eq!(capsule.inertia_tensor(5.0), sphere.inertia_tensor(5.0));
+	}
+
+	#[test]
+	pub fn cylinder() {
+		let shape = Shape::Cylinder {
+			radius: 1.0,
+			half_height: 1.5,
+		};
+		assert!((shape.volume() / PI - 3.0).abs() < 1e-5);
+		assert_eq!(
+			shape.inertia_tensor(4.0),
+			Matrix3::diagonal(Vector3::new(4.0, 2.0, 4.0))
+		);
+	}
+
+	#[test]
+	pub fn closest_point() {
+		let point = Vector3::new(3.0, 4.0, 0.0);
+		let sphere = Shape::Sphere { radius: 1.0 };
+		assert_eq!(sphere.closest_point(point), Vector3::new(0.6, 0.8, 0.0));
+		assert_eq!(sphere.closest_point(Vector3::zero()), Vector3::zero());
+		let cuboid = Shape::Cuboid {
+			half_size: Vector3::new(1.0, 2.0, 3.0),
+		};
+		assert_eq!(cuboid.closest_point(point), Vector3::new(1.0, 2.0, 0.0));
+		let capsule = Shape::Capsule {
+			radius: 1.0,
+			half_height: 1.0,
+		};
+		assert_eq!(
+			capsule.closest_point(Vector3::new(3.0, 0.5, 0.0)),
+			Vector3::new(1.0, 0.5, 0.0)
+		);
+		let cylinder = Shape::Cylinder {
+			radius: 1.0,
+			half_height: 1.0,
+		};
+		assert_eq!(cylinder.closest_point(point), Vector3::new(1.0, 1.0, 0.0));
+	}
+
+	#[test]
+	pub fn projected_area() {
+		let cuboid = Shape::Cuboid {
+			half_size: Vector3::new(1.0, 2.0, 3.0),
+		};
+		assert_equal(cuboid.projected_area(Vector3::x_axis()), 24.0);
+		assert_equal(cuboid.projected_area(Vector3::new(0.0, 0.0, -2.0)), 8.0);
+		let cylinder = Shape::Cylinder {
+			radius: 1.0,
+			half_height: 2.0,
+		};
+		assert!((cylinder.projected_area(Vector3::y_axis()) - PI).abs() < 1e-5);
+		assert_equal(cylinder.projected_area(Vector3::z_axis()), 8.0);
+		let capsule = Shape::Capsule {
+			radius: 1.0,
+			half_height: 2.0,
+		};
+		assert!((capsule.projected_area(Vector3::z_axis()) - (8.0 + PI)).abs() < 1e-5);
+	}
+}
@@ -0,0 +1,1304 @@
+use std::ops::Range;
+
+use crate::{
+	body::{BodyKind, RigidBody},
+	body_set::BodySet,
+	island::{Island, IslandSet},
+	joint::{Joint, JointRow},
+	manifold::{ManifoldCache, ManifoldPoint},
+	vec::Vector3,
+	Real,
+};
+
+/// A contact point prepared for the solver, with the impulse accumulated
+/// over the solver's iterations.
+#[derive(Debug, Default, Clone, Copy)]
+struct ContactConstraint {
+	bodies: [Option<usize>; 2],
+	relative_positions: [Vector3; 2],
+	normal: Vector3,
+	tangents: [Vector3; 2],
+	friction: Real,
+	rolling_friction: Real,
+
+	/// Holds the impulse along the normal that changes the normal velocity by one.
+	normal_mass: Real,
+
+	/// Holds the impulse along each tangent that changes the velocity along it by one.
+	tangent_masses: [Real; 2],
+
+	/// Holds the angular impulse about each tangent that changes the spin about it by one.
+	rolling_masses: [Real; 2],
+
+	/// Holds the normal velocity the contact should separate at, at least.
+	target_velocity: Real,
+
+	/// Holds the penetration when the contact was prepared.
+	penetration: Real,
+
+	/// Holds the impulse applied along the normal so far.
+	normal_impulse: Real,
+
+	/// Holds the friction impulse applied along each tangent so far.
+	tangent_impulses: [Real; 2],
+
+	/// Holds the angular impulse applied about each tangent to resist rolling so far.
+	rolling_impulses: [Real; 2],
+}
+
+impl ContactConstraint {
+	/// Returns the velocity of the first body relative to the second at the contact point.
+	fn relative_velocity(&self, bodies: &[RigidBody]) -> Vector3 {
+		let mut velocity = Vector3::zero();
+		for (side, sign) in [(0, 1.0), (1, -1.0)] {
+			if let Some(body) = self.bodies[side] {
+				let body = &bodies[body];
+				velocity += (body.velocity + body.angular_velocity.cross(&self.relative_positions[side])) * sign;
+			}
+		}
+		velocity
+	}
+
+	/// Returns the angular velocity of the first body relative to the second.
+	fn relative_spin(&self, bodies: &[RigidBody]) -> Vector3 {
+		let mut spin = Vector3::zero();
+		for (side, sign) in [(0, 1.0), (1, -1.0)] {
+			if let Some(body) = self.bodies[side] {
+				spin += bodies[body].angular_velocity * sign;
+			}
+		}
+		spin
+	}
+
+	/// Returns the angular impulse about the given axis that changes the
+	/// relative spin about it by one.
+	fn angular_mass(&self, bodies: &[RigidBody], axis: Vector3) -> Real {
+		let inverse: Real = self
+			.bodies
+			.iter()
+			.flatten()
+			.map(|body| (bodies[*body].effective_inverse_inertia_tensor_world() * axis).dot(&axis))
+			.sum();
+		if inverse > 0.0 {
+			inverse.recip()
+		} else {
+			0.0
+		}
+	}
+
+	/// Returns the impulse along the given direction that changes the
+	/// relative velocity along it by one.
+	fn effective_mass(&self, bodies: &[RigidBody], direction: Vector3) -> Real {
+		let inverse: Real = (0..2)
+			.filter_map(|side| self.bodies[side].map(|body| (&bodies[body], self.relative_positions[side])))
+			.map(|(body, relative_position)| {
+				let rotation = body.effective_inverse_inertia_tensor_world() * relative_position.cross(&direction);
+				rotation.cross(&relative_position).dot(&direction) + body.effective_inverse_mass()
+			})
+			.sum();
+		if inverse > 0.0 {
+			inverse.recip()
+		} else {
+			0.0
+		}
+	}
+
+	/// Returns the impulse accumulated so far, in world coordinates.
+	fn accumulated_impulse(&self) -> Vector3 {
+		self.normal * self.normal_impulse
+			+ self.tangents[0] * self.tangent_impulses[0]
+			+ self.tangents[1] * self.tangent_impulses[1]
+	}
+
+	/// Applies the friction impulse that stops the contact sliding, with
+	/// the accumulated friction held inside the friction cone.
+	///
+	/// While the friction needed is inside the cone the contact sticks;
+	/// beyond it the contact slides, and the accumulated friction is scaled
+	/// back onto the edge of the cone, keeping its direction across the two tangents.
+	fn solve_friction(&mut self, bodies: &mut [RigidBody]) {
+		let velocity = self.relative_velocity(bodies);
+		let mut accumulated = self.tangent_impulses;
+		for (tangent, impulse) in accumulated.iter_mut().enumerate() {
+			*impulse -= self.tangent_masses[tangent] * velocity.dot(&self.tangents[tangent]);
+		}
+		let accumulated = clamp_to_cone(accumulated, self.friction * self.normal_impulse);
+		let change = self.tangents[0] * (accumulated[0] - self.tangent_impulses[0])
+			+ self.tangents[1] * (accumulated[1] - self.tangent_impulses[1]);
+		self.tangent_impulses = accumulated;
+		self.apply_impulse(bodies, change);
+	}
+
+	/// Applies the angular impulse about the tangents that stops the
+	/// bodies rolling over each other, with the accumulated impulse held
+	/// within the rolling friction times the normal impulse.
+	fn solve_rolling(&mut self, bodies: &mut [RigidBody]) {
+		let spin = self.relative_spin(bodies);
+		let mut accumulated = self.rolling_impulses;
+		for (tangent, impulse) in accumulated.iter_mut().enumerate() {
+			*impulse -= self.rolling_masses[tangent] * spin.dot(&self.tangents[tangent]);
+		}
+		let accumulated = clamp_to_cone(accumulated, self.rolling_friction * self.normal_impulse);
+		let change = self.tangents[0] * (accumulated[0] - self.rolling_impulses[0])
+			+ self.tangents[1] * (accumulated[1] - self.rolling_impulses[1]);
+		self.rolling_impulses = accumulated;
+		for (side, sign) in [(0, 1.0), (1, -1.0)] {
+			if let Some(body) = self.bodies[side] {
+				let body = &mut bodies[body];
+				body.angular_velocity += body.effective_inverse_inertia_tensor_world() * (change * sign);
+			}
+		}
+	}
+
+	/// Applies the impulse along the normal that brings the contact to its
+	/// target velocity, with the accumulated impulse never pulling the bodies together.
+	fn solve_normal(&mut self, bodies: &mut [RigidBody]) {
+		let normal_velocity = self.relative_velocity(bodies).dot(&self.normal);
+		let impulse = self.normal_mass * (self.target_velocity - normal_velocity);
+		let accumulated = (self.normal_impulse + impulse).max(0.0);
+		let change = accumulated - self.normal_impulse;
+		self.normal_impulse = accumulated;
+		self.apply_impulse(bodies, self.normal * change);
+	}
+
+	/// Returns how much faster than its target velocity the contact is closing, or zero.
+	fn velocity_error(&self, bodies: &[RigidBody]) -> Real {
+		let normal_velocity = self.relative_velocity(bodies).dot(&self.normal);
+		(self.target_velocity - normal_velocity).max(0.0)
+	}
+
+	/// Returns the penetration once the bodies have been moved and turned
+	/// by the given amounts, measured from where they were prepared.
+	fn moved_penetration(&self, moves: &[[Vector3; 2]]) -> Real {
+		let mut separation = 0.0;
+		for (side, sign) in [(0, 1.0), (1, -1.0)] {
+			if let Some(body) = self.bodies[side] {
+				let [linear, angular] = moves[body];
+				separation += sign * (linear + angular.cross(&self.relative_positions[side])).dot(&self.normal);
+			}
+		}
+		self.penetration - separation
+	}
+
+	/// Applies the impulse to the first body and its opposite to the second.
+	fn apply_impulse(&self, bodies: &mut [RigidBody], impulse: Vector3) {
+		for (side, sign) in [(0, 1.0), (1, -1.0)] {
+			if let Some(body) = self.bodies[side] {
+				let body = &mut bodies[body];
+				let impulse = impulse * sign;
+				body.velocity += impulse * body.effective_inverse_mass();
+				body.angular_velocity +=
+					body.effective_inverse_inertia_tensor_world() * self.relative_positions[side].cross(&impulse);
+			}
+		}
+	}
+}
+
+/// How closely a contact solver met its contacts in one step.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SolverReport {
+	/// Holds the number of passes made to solve velocities.
+	pub velocity_iterations: usize,
+
+	/// Holds the number of passes made to correct penetration.
+	pub position_iterations: usize,
+
+	/// Holds the deepest penetration left at any contact.
+	pub residual_penetration: Real,
+
+	/// Holds the fastest any contact is left closing beyond what it should.
+	pub residual_velocity: Real,
+}
+
+impl SolverReport {
+	/// Returns whether every contact was left within the given tolerances.
+	#[must_use]
+	pub fn converged(&self, penetration_tolerance: Real, velocity_tolerance: Real) -> bool {
+		self.residual_penetration <= penetration_tolerance && self.residual_velocity <= velocity_tolerance
+	}
+
+	/// Combines the reports of parts solved apart, such as islands, keeping
+	/// the most passes and the largest residuals.
+	#[must_use]
+	pub fn merge(self, other: Self) -> Self {
+		Self {
+			velocity_iterations: self.velocity_iterations.max(other.velocity_iterations),
+			position_iterations: self.position_iterations.max(other.position_iterations),
+			residual_penetration: self.residual_penetration.max(other.residual_penetration),
+			residual_velocity: self.residual_velocity.max(other.residual_velocity),
+		}
+	}
+}
+
+/// The storage a [`SequentialImpulseSolver`] reuses from step to step in
+/// [`SequentialImpulseSolver::solve_islands_into`], so that once it has
+/// grown to fit the scene, solving allocates nothing.
+///
+/// It keeps the islands found by the last solve, along with the lists each
+/// island was solved with, which the island in the same place reuses next time.
+#[derive(Debug, Default, Clone)]
+pub struct SolverWorkspace {
+	/// Holds the bodies linked by each manifold, followed by those linked by each joint.
+	links: Vec<[Option<usize>; 2]>,
+
+	islands: IslandSet,
+
+	/// Holds the storage the islands are solved with.
+	scratch: IslandScratch,
+}
+
+impl SolverWorkspace {
+	/// Returns the islands found by the last solve, ordered by their lowest body.
+	#[must_use]
+	pub fn islands(&self) -> &[Island] {
+		self.islands.islands()
+	}
+
+	/// Returns the number of elements the storage can hold, across every
+	/// list it keeps, so that its growth can be watched.
+	#[must_use]
+	pub fn capacity(&self) -> usize {
+		self.links.capacity() + self.islands.capacity() + self.scratch.capacity()
+	}
+}
+
+/// The storage shared by the islands solved together, and that of each island.
+#[derive(Debug, Default, Clone)]
+struct IslandScratch {
+	/// Holds the indices of the islands being solved, in order.
+	awake: Vec<usize>,
+
+	/// Holds the range of each manifold's points among the copies.
+	manifold_points: Vec<Range<usize>>,
+
+	/// Holds a copy of every manifold's points, in order, so that the
+	/// islands can read them side by side.
+	points: Vec<ManifoldPoint>,
+
+	/// Holds the storage each island is solved with, in the order they are solved.
+	solves: Vec<IslandSolve>,
+}
+
+impl IslandScratch {
+	fn capacity(&self) -> usize {
+		self.awake.capacity()
+			+ self.manifold_points.capacity()
+			+ self.points.capacity()
+			+ self.solves.capacity()
+			+ self.solves.iter().map(IslandSolve::capacity).sum::<usize>()
+	}
+}
+
+/// The storage one island is solved with: copies of its bodies and of the
+/// static and kinematic bodies it touches, and its contacts and joint rows.
+#[derive(Debug, Default, Clone)]
+struct IslandSolve {
+	/// Holds the static and kinematic bodies the island touches, in ascending order.
+	others: Vec<usize>,
+
+	/// Holds copies of the island's bodies, followed by copies of the others.
+	bodies: Vec<RigidBody>,
+
+	/// Holds the index among the copied points of each contact's point.
+	points: Vec<usize>,
+
+	constraints: Vec<ContactConstraint>,
+
+	/// Holds the impulses the contacts accumulated over the velocity passes.
+	impulses: Vec<Vector3>,
+
+	/// Holds the index of each joint solved, with the end of its rows.
+	joints: Vec<(usize, usize)>,
+
+	rows: Vec<JointRow>,
+
+	/// Holds how far each body has been moved and turned to correct penetration.
+	moves: Vec<[Vector3; 2]>,
+
+	/// Holds the height of each body, for shock propagation.
+	heights: Vec<Real>,
+
+	/// Holds the height of each contact with its index, for shock propagation.
+	order: Vec<(Real, usize)>,
+
+	report: SolverReport,
+}
+
+impl IslandSolve {
+	const fn capacity(&self) -> usize {
+		self.others.capacity()
+			+ self.bodies.capacity()
+			+ self.points.capacity()
+			+ self.constraints.capacity()
+			+ self.impulses.capacity()
+			+ self.joints.capacity()
+			+ self.rows.capacity()
+			+ self.moves.capacity()
+			+ self.heights.capacity()
+			+ self.order.capacity()
+	}
+
+	/// Finds the island's contacts and joints, and copies its bodies
+	/// followed by the static and kinematic bodies they touch.
+	fn gather(&mut self, island: &Island, shared: Shared) {
+		let manifold_count = shared.manifold_points.len();
+		self.points.clear();
+		self.joints.clear();
+		for link in &island.links {
+			match shared.manifold_points.get(*link) {
+				Some(points) => self.points.extend(points.clone()),
+				None => self.joints.push((link - manifold_count, 0)),
+			}
+		}
+		let touched = self
+			.points
+			.iter()
+			.map(|point| shared.points[*point].contact.bodies)
+			.chain(self.joints.iter().map(|(joint, _)| shared.joints[*joint].bodies()));
+		self.others.clear();
+		for body in touched.flat_map(IntoIterator::into_iter).flatten() {
+			if island.bodies.binary_search(&body).is_err() {
+				self.others.push(body);
+			}
+		}
+		self.others.sort_unstable();
+		self.others.dedup();
+		self.bodies.clear();
+		let copies = island.bodies.iter().chain(&self.others).map(|body| shared.bodies.get(*body));
+		self.bodies.extend(copies);
+	}
+}
+
+/// What the islands solved together read from, while each is solved on its own copies.
+#[derive(Debug, Clone, Copy)]
+struct Shared<'a> {
+	/// Holds the range of each manifold's points among the copied points.
+	manifold_points: &'a [Range<usize>],
+
+	points: &'a [ManifoldPoint],
+	joints: &'a [Joint],
+	bodies: &'a BodySet,
+}
+
+/// The contacts and joints a [`SequentialImpulseSolver`] solves, with the
+/// bodies they act on.
+#[derive(Debug)]
+pub struct SolverInput<'a> {
+	/// Holds the contacts, whose impulses are stored back for the next frame.
+	pub manifolds: &'a mut ManifoldCache,
+
+	/// Holds the joints, whose impulses are stored back for the next frame.
+	pub joints: &'a mut [Joint],
+
+	pub bodies: &'a mut BodySet,
+}
+
+/// A sequential impulse solver, which resolves contacts by applying
+/// impulses to each in turn over several iterations, clamping the impulse
+/// accumulated at each contact rather than each one applied.
+///
+/// Clamping the accumulated impulse lets a later iteration take back
+/// part of an earlier one, so the impulses converge on those that hold a
+/// stack up together. Starting each frame from the impulses of the last,
+/// kept in a [`ManifoldCache`], makes that convergence take only a few
+/// iterations for contacts that persist. Contacts that have not yet
+/// touched only stop the bodies closing faster than the gap allows.
+/// Friction is accumulated the same way, held inside the cone given by
+/// each contact's friction. [`Joint`]s are solved in the same passes,
+/// ahead of the contacts, and warm started the same way.
+///
+/// Penetration is corrected by a bias velocity when there are no position
+/// iterations. With them, it is corrected by moving the bodies directly
+/// after the velocities are solved, which adds no energy to the bodies but
+/// costs more.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct SequentialImpulseSolver {
+	/// Holds the number of passes made over the contacts to solve their velocities.
+	pub velocity_iterations: usize,
+
+	/// Holds the most passes made over the contacts to correct their
+	/// penetration. Fewer are made once every contact is within twice the slop.
+	pub position_iterations: usize,
+
+	/// Holds whether each contact starts from the impulse it ended the last frame with.
+	pub warm_starting: bool,
+
+	/// Holds the fraction of the penetration beyond the slop corrected each
+	/// step, or each position iteration if there are any.
+	pub bias_factor: Real,
+
+	/// Holds the penetration allowed without correction, so that resting
+	/// contacts stay touching from one frame to the next.
+	pub slop: Real,
+
+	/// Holds the closing speed below which a contact is given no
+	/// restitution, so that bodies resting under gravity settle instead of
+	/// bouncing ever lower.
+	pub restitution_threshold: Real,
+
+	/// Holds the up direction used for an extra shock-propagation pass
+	/// after the velocity passes, or `None` to skip the pass.
+	///
+	/// The pass solves the contacts once more from the bottom up, treating
+	/// the lower body of each contact as kinematic. Weight is then never
+	/// pushed back down into a settled layer, so tall stacks and pyramids
+	/// stay standing instead of slowly splaying apart. The impulses of this
+	/// pass are not kept for warm starting, as they do not push back on the
+	/// bodies below.
+	pub shock_propagation: Option<Vector3>,
+}
+
+impl Default for SequentialImpulseSolver {
+	fn default() -> Self {
+		Self::new(8)
+	}
+}
+
+impl SequentialImpulseSolver {
+	/// Creates a solver making the given number of velocity passes, with
+	/// penetration corrected by a bias velocity.
+	#[must_use]
+	pub const fn new(velocity_iterations: usize) -> Self {
+		Self {
+			velocity_iterations,
+			position_iterations: 0,
+			warm_starting: true,
+			bias_factor: 0.2,
+			slop: 0.005,
+			restitution_threshold: 1.0,
+			shock_propagation: None,
+		}
+	}
+
+	/// Solves the contacts held in the cache and the joints, changing the
+	/// bodies' velocities, and stores each contact's and joint's
+	/// accumulated impulse back for the next frame.
+	///
+	/// As with the [`ContactResolver`](crate::resolver::ContactResolver), this runs after the bodies have
+	/// been integrated and the cache updated with their contacts.
+	pub fn solve(
+		&self,
+		manifolds: &mut ManifoldCache,
+		joints: &mut [Joint],
+		bodies: &mut [RigidBody],
+		duration: Real,
+	) -> SolverReport {
+		if duration <= 0.0 {
+			return SolverReport::default();
+		}
+		// Every body and link is solved as one island, sleeping or not
+		let whole = Island {
+			bodies: (0..bodies.len()).collect(),
+			links: (0..manifolds.len() + joints.len()).collect(),
+		};
+		let mut scratch = IslandScratch::default();
+		scratch.awake.push(0);
+		let mut set: BodySet = bodies.iter().copied().collect();
+		let input = SolverInput {
+			manifolds,
+			joints,
+			bodies: &mut set,
+		};
+		let report = self.solve_each(input, &[whole], duration, &mut scratch);
+		for (body, solved) in bodies.iter_mut().zip(set.iter()) {
+			*body = solved;
+		}
+		report
+	}
+
+	/// Solves the contacts held in the cache and the joints as
+	/// [`Self::solve`] does, but island by island, returning the islands
+	/// found from the cache and the joints, and the reports of the islands
+	/// solved merged into one.
+	///
+	/// This allocates afresh each call; [`Self::solve_islands_into`] reuses
+	/// the storage of the last.
+	pub fn solve_islands(
+		&self,
+		manifolds: &mut ManifoldCache,
+		joints: &mut [Joint],
+		bodies: &mut [RigidBody],
+		duration: Real,
+	) -> (Vec<Island>, SolverReport) {
+		let mut workspace = SolverWorkspace::default();
+		let mut set: BodySet = bodies.iter().copied().collect();
+		let input = SolverInput {
+			manifolds,
+			joints,
+			bodies: &mut set,
+		};
+		let report = self.solve_islands_into(input, duration, &mut workspace);
+		for (body, solved) in bodies.iter_mut().zip(set.iter()) {
+			*body = solved;
+		}
+		(workspace.islands().to_vec(), report)
+	}
+
+	/// Solves the contacts and joints island by island as
+	/// [`Self::solve_islands`] does, leaving the islands found in the
+	/// workspace, whose storage is reused from the last call.
+	///
+	/// Each island is solved on its own copy of its bodies, so islands are
+	/// solved in parallel with the `rayon` feature. Islands that are asleep
+	/// are skipped, and the sleeping bodies of the rest are woken first, as
+	/// they are about to be pushed. The islands found can then be put to
+	/// sleep as a whole with [`Island::update_sleep`].
+	pub fn solve_islands_into(
+		&self,
+		input: SolverInput,
+		duration: Real,
+		workspace: &mut SolverWorkspace,
+	) -> SolverReport {
+		// A broken joint no longer links its bodies
+		let joint_links = input
+			.joints
+			.iter()
+			.map(|joint| if joint.is_broken() { [None; 2] } else { joint.bodies() });
+		workspace.links.clear();
+		workspace
+			.links
+			.extend(input.manifolds.manifolds().map(|manifold| manifold.bodies).chain(joint_links));
+		workspace.islands.find(input.bodies, &workspace.links);
+		if duration <= 0.0 {
+			return SolverReport::default();
+		}
+		let islands = workspace.islands.islands();
+		let awake = &mut workspace.scratch.awake;
+		awake.clear();
+		awake.extend((0..islands.len()).filter(|island| !islands[*island].is_sleeping(input.bodies)));
+		for island in awake.iter() {
+			for body in &islands[*island].bodies {
+				if input.bodies.flags()[*body].sleeping {
+					input.bodies.set_awake(*body, true);
+				}
+			}
+		}
+		self.solve_each(input, islands, duration, &mut workspace.scratch)
+	}
+
+	/// Solves each of the awake islands on its own copy of its bodies,
+	/// then copies the bodies back and stores the impulses for the next frame.
+	fn solve_each(
+		&self,
+		input: SolverInput,
+		islands: &[Island],
+		duration: Real,
+		scratch: &mut IslandScratch,
+	) -> SolverReport {
+		let SolverInput { manifolds, joints, bodies } = input;
+		scratch.manifold_points.clear();
+		scratch.points.clear();
+		for manifold in manifolds.manifolds() {
+			let start = scratch.points.len();
+			scratch.points.extend_from_slice(&manifold.points);
+			scratch.manifold_points.push(start..scratch.points.len());
+		}
+		if scratch.solves.len() < scratch.awake.len() {
+			scratch.solves.resize_with(scratch.awake.len(), IslandSolve::default);
+		}
+		let shared = Shared {
+			manifold_points: &scratch.manifold_points,
+			points: &scratch.points,
+			joints,
+			bodies,
+		};
+		let solves = &mut scratch.solves[..scratch.awake.len()];
+		#[cfg(feature = "rayon")]
+		{
+			use rayon::prelude::*;
+			solves
+				.par_iter_mut()
+				.zip(scratch.awake.par_iter())
+				.for_each(|(solve, island)| self.solve_island(&islands[*island], shared, duration, solve));
+		}
+		#[cfg(not(feature = "rayon"))]
+		for (solve, island) in solves.iter_mut().zip(&scratch.awake) {
+			self.solve_island(&islands[*island], shared, duration, solve);
+		}
+
+		let mut report = SolverReport::default();
+		for (solve, island) in scratch.solves.iter().zip(&scratch.awake) {
+			for (index, body) in islands[*island].bodies.iter().zip(&solve.bodies) {
+				bodies.set(*index, body);
+			}
+			for (point, impulse) in solve.points.iter().zip(&solve.impulses) {
+				scratch.points[*point].impulse = *impulse;
+			}
+			let mut start = 0;
+			for (joint, end) in &solve.joints {
+				joints[*joint].store_impulses(&solve.rows[start..*end], duration);
+				start = *end;
+			}
+			report = report.merge(solve.report);
+		}
+		let points = manifolds.manifolds_mut().flat_map(|manifold| manifold.points.iter_mut());
+		for (point, solved) in points.zip(&scratch.points) {
+			point.impulse = solved.impulse;
+		}
+		report
+	}
+
+	/// Copies an island's bodies, and the static and kinematic bodies they
+	/// touch, and prepares its contacts and joint rows against the copies.
+	fn prepare_island(&self, island: &Island, shared: Shared, duration: Real, solve: &mut IslandSolve) {
+		solve.gather(island, shared);
+		let local = |body| local_index(&island.bodies, &solve.others, body);
+		solve.constraints.clear();
+		solve.constraints.extend(
+			solve
+				.points
+				.iter()
+				.map(|point| self.prepare(&shared.points[*point], &solve.bodies, duration, local)),
+		);
+		solve.rows.clear();
+		for (joint, end) in &mut solve.joints {
+			let rows = shared.joints[*joint].rows(&solve.bodies, local, self.bias_factor, duration);
+			solve.rows.extend_from_slice(&rows);
+			*end = solve.rows.len();
+		}
+	}
+
+	/// Solves one island's contacts and joints on a copy of its bodies,
+	/// leaving the bodies, impulses and report in its storage.
+	fn solve_island(&self, island: &Island, shared: Shared, duration: Real, solve: &mut IslandSolve) {
+		self.prepare_island(island, shared, duration, solve);
+		let bodies = &mut solve.bodies;
+		if self.warm_starting {
+			for constraint in &solve.constraints {
+				constraint.apply_impulse(bodies, constraint.accumulated_impulse());
+			}
+			for row in &solve.rows {
+				row.apply_impulse(bodies, row.impulse);
+			}
+		} else {
+			for row in &mut solve.rows {
+				row.impulse = 0.0;
+			}
+		}
+		for _ in 0..self.velocity_iterations {
+			Self::iterate(&mut solve.constraints, &mut solve.rows, bodies);
+		}
+		solve.impulses.clear();
+		solve.impulses.extend(solve.constraints.iter().map(|constraint| {
+			let [first, second] = constraint.tangent_impulses;
+			Vector3::new(constraint.normal_impulse, first, second)
+		}));
+		if let Some(up) = self.shock_propagation {
+			propagate_shock(solve, up);
+		}
+		let residual_velocity = solve
+			.constraints
+			.iter()
+			.map(|constraint| constraint.velocity_error(&solve.bodies))
+			.fold(0.0, Real::max);
+		let (position_iterations, residual_penetration) = self.correct_positions(solve);
+		solve.report = SolverReport {
+			velocity_iterations: self.velocity_iterations,
+			position_iterations,
+			residual_penetration,
+			residual_velocity,
+		};
+	}
+
+	/// Makes one velocity pass over the joints and then the contacts.
+	fn iterate(constraints: &mut [ContactConstraint], rows: &mut [JointRow], bodies: &mut [RigidBody]) {
+		for row in rows {
+			row.solve(bodies);
+		}
+		// Friction is solved first, as the normal impulses matter more and the last pass wins
+		for constraint in constraints {
+			if constraint.rolling_friction > 0.0 {
+				constraint.solve_rolling(bodies);
+			}
+			if constraint.friction > 0.0 {
+				constraint.solve_friction(bodies);
+			}
+			constraint.solve_normal(bodies);
+		}
+	}
+
+	/// Moves and turns the island's bodies apart to correct the penetration
+	/// beyond the slop, returning the number of passes made and the deepest
+	/// penetration left.
+	fn correct_positions(&self, solve: &mut IslandSolve) -> (usize, Real) {
+		let (constraints, bodies, moves) = (&solve.constraints, &mut solve.bodies, &mut solve.moves);
+		moves.clear();
+		moves.resize(bodies.len(), [Vector3::zero(); 2]);
+		let deepest = |moves: &[[Vector3; 2]]| {
+			constraints
+				.iter()
+				.map(|constraint| constraint.moved_penetration(moves))
+				.fold(0.0, Real::max)
+		};
+		let mut passes = 0;
+		while passes < self.position_iterations && deepest(moves) > 2.0 * self.slop {
+			for constraint in constraints {
+				let error = constraint.moved_penetration(moves) - self.slop;
+				if error <= 0.0 {
+					continue;
+				}
+				let push = constraint.normal * (self.bias_factor * error * constraint.normal_mass);
+				for (side, sign) in [(0, 1.0), (1, -1.0)] {
+					let Some(index) = constraint.bodies[side] else {
+						continue;
+					};
+					let body = &mut bodies[index];
+					let push = push * sign;
+					let linear = push * body.effective_inverse_mass();
+					let angular = body.effective_inverse_inertia_tensor_world()
+						* constraint.relative_positions[side].cross(&push);
+					moves[index][0] += linear;
+					moves[index][1] += angular;
+					body.position += linear;
+					body.orientation.add_scaled_vector(angular, 1.0);
+				}
+			}
+			passes += 1;
+		}
+		if passes > 0 {
+			for (body, [linear, angular]) in bodies.iter_mut().zip(moves.iter()) {
+				if *linear != Vector3::zero() || *angular != Vector3::zero() {
+					body.calculate_derived_data();
+				}
+			}
+		}
+		(passes, deepest(moves))
+	}
+
+	/// Prepares a manifold point for solving, finding the velocity it
+	/// should separate at from its restitution and penetration.
+	fn prepare(
+		&self,
+		point: &ManifoldPoint,
+		bodies: &[RigidBody],
+		duration: Real,
+		local: impl Fn(usize) -> usize,
+	) -> ContactConstraint {
+		let contact = &point.contact;
+		let basis = contact.contact_basis();
+		let impulse = if self.warm_starting {
+			point.impulse
+		} else {
+			Vector3::zero()
+		};
+		let mut constraint = ContactConstraint {
+			bodies: contact.bodies.map(|body| body.map(&local)),
+			normal: contact.contact_normal,
+			tangents: [basis.column(1), basis.column(2)],
+			friction: contact.friction,
+			rolling_friction: contact.rolling_friction,
+			penetration: contact.penetration,
+			normal_impulse: impulse.x(),
+			tangent_impulses: [impulse.y(), impulse.z()],
+			..Default::default()
+		};
+		for (side, body) in constraint.bodies.iter().enumerate() {
+			if let Some(body) = body {
+				constraint.relative_positions[side] = contact.contact_point - bodies[*body].position;
+			}
+		}
+		constraint.normal_mass = constraint.effective_mass(bodies, constraint.normal);
+		constraint.tangent_masses = constraint
+			.tangents
+			.map(|tangent| constraint.effective_mass(bodies, tangent));
+		constraint.rolling_masses = constraint
+			.tangents
+			.map(|tangent| constraint.angular_mass(bodies, tangent));
+
+		let closing = constraint.relative_velocity(bodies).dot(&constraint.normal);
+		let rebound = if closing < -self.restitution_threshold {
+			-contact.restitution * closing
+		} else {
+			0.0
+		};
+		let correction = if contact.penetration < 0.0 {
+			// Not yet touching, so the bodies may close by the gap this step
+			contact.penetration / duration
+		} else if self.position_iterations > 0 {
+			0.0
+		} else {
+			self.bias_factor * (contact.penetration - self.slop).max(0.0) / duration
+		};
+		constraint.target_velocity = rebound.max(correction);
+		constraint
+	}
+}
+
+/// Returns where the given body is among the copies of an island's bodies
+/// followed by the others it touches.
+fn local_index(island: &[usize], others: &[usize], body: usize) -> usize {
+	island.binary_search(&body).unwrap_or_else(|_| {
+		let other = others.binary_search(&body);
+		island.len() + other.expect("every body an island touches is copied")
+	})
+}
+
+/// Scales the accumulated impulses along two axes back onto the edge of
+/// the cone of the given radius if they lie beyond it, keeping their direction.
+fn clamp_to_cone(accumulated: [Real; 2], limit: Real) -> [Real; 2] {
+	let magnitude = accumulated[0].hypot(accumulated[1]);
+	if magnitude > limit {
+		let scale = limit / magnitude;
+		accumulated.map(|impulse| impulse * scale)
+	} else {
+		accumulated
+	}
+}
+
+/// Solves every contact of the island once, lowest first, with the lower
+/// body of each contact treated as kinematic while it is solved. Each
+/// contact's masses are put back afterwards, as both bodies move again.
+fn propagate_shock(solve: &mut IslandSolve, up: Vector3) {
+	let (constraints, bodies) = (&mut solve.constraints, &mut solve.bodies);
+	let heights = &mut solve.heights;
+	heights.clear();
+	heights.extend(bodies.iter().map(|body| body.position.dot(&up)));
+	let height = |body: &Option<usize>| body.map_or(Real::MIN, |body| heights[body]);
+	solve.order.clear();
+	solve.order.extend(
+		constraints
+			.iter()
+			.enumerate()
+			.map(|(index, constraint)| (height(&constraint.bodies[0]).min(height(&constraint.bodies[1])), index)),
+	);
+	// Ties keep the order of the contacts
+	solve
+		.order
+		.sort_unstable_by(|first, second| first.0.total_cmp(&second.0).then(first.1.cmp(&second.1)));
+
+	for (_, index) in &solve.order {
+		let constraint = &mut constraints[*index];
+		let lower = match constraint.bodies {
+			[Some(first), Some(second)] => Some(if heights[first] < heights[second] {
+				first
+			} else {
+				second
+			}),
+			_ => None,
+		};
+		let Some(lower) = lower.filter(|body| bodies[*body].kind == BodyKind::Dynamic) else {
+			constraint.solve_friction(bodies);
+			constraint.solve_normal(bodies);
+			continue;
+		};
+		// The masses with both bodies free are put back for position correction
+		let masses = (constraint.normal_mass, constraint.tangent_masses);
+		bodies[lower].kind = BodyKind::Kinematic;
+		constraint.normal_mass = constraint.effective_mass(bodies, constraint.normal);
+		constraint.tangent_masses = constraint
+			.tangents
+			.map(|tangent| constraint.effective_mass(bodies, tangent));
+		constraint.solve_friction(bodies);
+		constraint.solve_normal(bodies);
+		bodies[lower].kind = BodyKind::Dynamic;
+		(constraint.normal_mass, constraint.tangent_masses) = masses;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		body::{BodyKind, RigidBodyBuilder},
+		collide::{
+			box_and_box, box_and_half_space, sphere_and_half_space, CollisionBox, CollisionData, CollisionPlane,
+			CollisionPrimitive, CollisionSphere,
+		},
+		joint::HingeJoint,
+		shape::Shape,
+	};
+
+	use super::*;
+
+	const GRAVITY: Vector3 = Vector3::new(0.0, -10.0, 0.0);
+	const STEP: Real = 1.0 / 60.0;
+
+	fn cube(height: Real) -> RigidBody {
+		RigidBodyBuilder::new(Shape::default())
+			.position(Vector3::new(0.0, height, 0.0))
+			.acceleration(GRAVITY)
+			.can_sleep(false)
+			.build()
+	}
+
+	/// Detects the contacts of a stack of unit cubes on the floor, and merges them into the cache.
+	fn detect(bodies: &[RigidBody], manifolds: &mut ManifoldCache) {
+		let boxes: Vec<CollisionBox> = (0..bodies.len())
+			.map(|body| {
+				let mut cuboid = CollisionBox {
+					primitive: CollisionPrimitive::new(body),
+					half_size: Vector3::new(0.5, 0.5, 0.5),
+				};
+				cuboid.primitive.calculate_internals(bodies);
+				cuboid
+			})
+			.collect();
+		let mut data = CollisionData::new(64);
+		data.friction = 0.5;
+		data.restitution = 0.0;
+		for cuboid in &boxes {
+			box_and_half_space(cuboid, &CollisionPlane::new(Vector3::y_axis(), 0.0), &mut data);
+		}
+		for pair in boxes.windows(2) {
+			box_and_box(&pair[1], &pair[0], &mut data);
+		}
+		manifolds.update(&data.contacts, bodies);
+	}
+
+	fn simulate(
+		bodies: &mut [RigidBody],
+		manifolds: &mut ManifoldCache,
+		solver: &SequentialImpulseSolver,
+		steps: usize,
+	) {
+		for _ in 0..steps {
+			for body in bodies.iter_mut() {
+				body.integrate(STEP);
+			}
+			detect(bodies, manifolds);
+			solver.solve(manifolds, &mut [], bodies, STEP);
+		}
+	}
+
+	/// Asserts that the bodies stay put over a further second.
+	fn assert_at_rest(bodies: &mut [RigidBody], manifolds: &mut ManifoldCache, solver: &SequentialImpulseSolver) {
+		let before: Vec<Vector3> = bodies.iter().map(|body| body.position).collect();
+		simulate(bodies, manifolds, solver, 60);
+		for (body, before) in bodies.iter().zip(before) {
+			assert!(
+				(body.position - before).magnitude() < 1e-2,
+				"{:?} moved from {before:?}",
+				body.position
+			);
+		}
+	}
+
+	#[test]
+	pub fn falling_box_comes_to_rest() {
+		let solver = SequentialImpulseSolver::default();
+		let mut bodies = [cube(2.0)];
+		let mut manifolds = ManifoldCache::default();
+		simulate(&mut bodies, &mut manifolds, &solver, 120);
+		assert_at_rest(&mut bodies, &mut manifolds, &solver);
+		assert!((bodies[0].position.y() - 0.5).abs() < 0.025);
+		let manifold = manifolds.manifold([Some(0), None]).expect("the box rests on the floor");
+		assert_eq!(manifold.points.len(), 4);
+
+		// Each corner carries a quarter of the weight each step
+		let weight = bodies[0].mass() * 10.0 * STEP;
+		for point in &manifold.points {
+			assert!((point.impulse.x() - weight / 4.0).abs() < weight * 0.1);
+		}
+	}
+
+	#[test]
+	pub fn friction_stops_sliding_box() {
+		let solver = SequentialImpulseSolver::default();
+		let mut bodies = [cube(0.5)];
+		bodies[0].velocity = Vector3::new(3.0, 0.0, 0.0);
+		let mut manifolds = ManifoldCache::default();
+		simulate(&mut bodies, &mut manifolds, &solver, 60);
+		assert_at_rest(&mut bodies, &mut manifolds, &solver);
+
+		// Sliding friction slows the box at half its weight, over 0.9 m
+		assert!((bodies[0].position.x() - 0.9).abs() < 0.05);
+		assert!(bodies[0].angular_velocity.magnitude() < 1e-2);
+	}
+
+	#[test]
+	pub fn slow_contacts_do_not_bounce() {
+		let solver = SequentialImpulseSolver::default();
+		let mut bodies = [cube(0.5)];
+		bodies[0].velocity = Vector3::new(0.0, -0.5, 0.0);
+		let mut manifolds = ManifoldCache::default();
+		let mut data = CollisionData::new(4);
+		data.restitution = 1.0;
+		let mut cuboid = CollisionBox {
+			primitive: CollisionPrimitive::new(0),
+			half_size: Vector3::new(0.5, 0.5, 0.5),
+		};
+		cuboid.primitive.calculate_internals(&bodies);
+		box_and_half_space(&cuboid, &CollisionPlane::new(Vector3::y_axis(), 0.0), &mut data);
+		manifolds.update(&data.contacts, &bodies);
+		solver.solve(&mut manifolds, &mut [], &mut bodies, STEP);
+		assert!(bodies[0].velocity.y().abs() < 1e-3);
+
+		// Above the threshold the box rebounds
+		bodies[0].velocity = Vector3::new(0.0, -2.0, 0.0);
+		solver.solve(&mut manifolds, &mut [], &mut bodies, STEP);
+		assert!((bodies[0].velocity.y() - 2.0).abs() < 1e-2);
+	}
+
+	#[test]
+	pub fn position_iterations_correct_penetration_without_speed() {
+		let mut bodies = [cube(0.4)];
+		bodies[0].acceleration = Vector3::zero();
+		let mut manifolds = ManifoldCache::default();
+		detect(&bodies, &mut manifolds);
+		let solver = SequentialImpulseSolver {
+			position_iterations: 20,
+			bias_factor: 0.5,
+			..SequentialImpulseSolver::default()
+		};
+		let report = solver.solve(&mut manifolds, &mut [], &mut bodies, STEP);
+		assert_eq!(report.velocity_iterations, 8);
+		assert!(report.position_iterations > 0 && report.position_iterations < 20);
+		assert!(report.converged(2.0 * solver.slop, 1e-3));
+
+		// The box is moved out rather than launched out
+		assert!((bodies[0].position.y() - 0.5).abs() < 2.0 * solver.slop);
+		assert!(bodies[0].velocity.magnitude() < 1e-3);
+
+		// The bias velocity leaves the box in, moving out
+		let mut bodies = [cube(0.4)];
+		let mut manifolds = ManifoldCache::default();
+		detect(&bodies, &mut manifolds);
+		let report = SequentialImpulseSolver::default().solve(&mut manifolds, &mut [], &mut bodies, STEP);
+		assert_eq!(report.position_iterations, 0);
+		assert!((report.residual_penetration - 0.1).abs() < 1e-4);
+		assert!(bodies[0].velocity.y() > 1.0);
+	}
+
+	#[test]
+	pub fn stack_stands() {
+		let solver = SequentialImpulseSolver::default();
+		let mut bodies = [cube(0.5), cube(1.5), cube(2.5)];
+		let mut manifolds = ManifoldCache::default();
+		simulate(&mut bodies, &mut manifolds, &solver, 120);
+		assert_at_rest(&mut bodies, &mut manifolds, &solver);
+		for (level, body) in bodies.iter().enumerate() {
+			let expected = crate::real_from_count(level) + 0.5;
+			assert!(
+				(body.position.y() - expected).abs() < 0.05,
+				"box {level} is at {:?}",
+				body.position
+			);
+		}
+	}
+
+	#[test]
+	pub fn shock_propagation_holds_tall_stacks_up() {
+		let sag = |shock_propagation: Option<Vector3>| {
+			let solver = SequentialImpulseSolver {
+				shock_propagation,
+				..SequentialImpulseSolver::new(2)
+			};
+			let mut bodies: Vec<RigidBody> = (0..8).map(|level| cube(crate::real_from_count(level) + 0.5)).collect();
+			let mut manifolds = ManifoldCache::default();
+			simulate(&mut bodies, &mut manifolds, &solver, 120);
+			7.5 - bodies[7].position.y()
+		};
+
+		// With only two passes the stack sinks and wobbles unless held up from below
+		let held = sag(Some(Vector3::y_axis()));
+		assert!(held < 0.06);
+		assert!(sag(None) > 2.0 * held);
+	}
+
+	#[test]
+	pub fn shock_propagation_leaves_position_correction_alone() {
+		let correct = |shock_propagation: Option<Vector3>| {
+			let solver = SequentialImpulseSolver {
+				shock_propagation,
+				position_iterations: 20,
+				bias_factor: 0.5,
+				..SequentialImpulseSolver::default()
+			};
+			// Each box sunk a tenth into the one below
+			let mut bodies: Vec<RigidBody> = (0..3)
+				.map(|level| {
+					let mut body = cube(crate::real_from_count(level).mul_add(0.9, 0.4));
+					body.acceleration = Vector3::zero();
+					body
+				})
+				.collect();
+			let mut manifolds = ManifoldCache::default();
+			detect(&bodies, &mut manifolds);
+			let report = solver.solve(&mut manifolds, &mut [], &mut bodies, STEP);
+			(report, bodies.iter().map(|body| body.position).collect::<Vec<_>>())
+		};
+
+		// The stack is pushed most of the way apart, and by the same moves either way
+		let (held, positions) = correct(Some(Vector3::y_axis()));
+		let (free, expected) = correct(None);
+		assert!(held.residual_penetration < 0.05);
+		assert_eq!(held.residual_penetration.to_bits(), free.residual_penetration.to_bits());
+		assert_eq!(positions, expected);
+	}
+
+	#[test]
+	pub fn warm_starting_converges_faster() {
+		let mut settled = [cube(0.5), cube(1.5), cube(2.5)];
+		let mut manifolds = ManifoldCache::default();
+		simulate(&mut settled, &mut manifolds, &SequentialImpulseSolver::new(8), 120);
+
+		// From a settled stack, one iteration holds it up only if it starts from the last impulses
+		let sink = |warm_starting: bool| {
+			let solver = SequentialImpulseSolver {
+				warm_starting,
+				..SequentialImpulseSolver::new(1)
+			};
+			let mut bodies = settled;
+			simulate(&mut bodies, &mut manifolds.clone(), &solver, 30);
+			settled
+				.iter()
+				.zip(&bodies)
+				.map(|(settled, body)| settled.position.y() - body.position.y())
+				.sum::<Real>()
+		};
+		assert!(sink(true) < sink(false));
+	}
+
+	#[test]
+	pub fn islands_solve_as_the_whole_does() {
+		let mut bodies = [cube(0.5), cube(1.5), cube(0.5)];
+		bodies[2].position = Vector3::new(3.0, 0.5, 0.0);
+		bodies[2].calculate_derived_data();
+		let mut whole = bodies;
+		let mut manifolds = ManifoldCache::default();
+		let mut island_manifolds = ManifoldCache::default();
+		let solver = SequentialImpulseSolver::default();
+		for _ in 0..30 {
+			for body in whole.iter_mut().chain(bodies.iter_mut()) {
+				body.integrate(STEP);
+			}
+			detect(&whole, &mut manifolds);
+			solver.solve(&mut manifolds, &mut [], &mut whole, STEP);
+			detect(&bodies, &mut island_manifolds);
+			let (islands, _) = solver.solve_islands(&mut island_manifolds, &mut [], &mut bodies, STEP);
+			assert_eq!(islands.len(), 2);
+			assert_eq!(islands[0].bodies, [0, 1]);
+		}
+		for (body, expected) in bodies.iter().zip(&whole) {
+			assert!((body.position - expected.position).magnitude() < 1e-5);
+			assert!((body.velocity - expected.velocity).magnitude() < 1e-5);
+		}
+	}
+
+	#[test]
+	pub fn islands_solved_in_parallel_match_the_serial_solve() {
+		// Eight piles of three, each its own island, solved in parallel with the `rayon` feature
+		let mut bodies: Vec<RigidBody> = (0..24_u8)
+			.map(|index| {
+				let (pile, level) = (Real::from(index / 3), Real::from(index % 3));
+				let mut body = cube(level.mul_add(1.1, 0.5));
+				body.position = Vector3::new(pile.mul_add(3.0, level * 0.1), body.position.y(), 0.0);
+				body.calculate_derived_data();
+				body
+			})
+			.collect();
+		let mut serial = bodies.clone();
+		let [mut manifolds, mut serial_manifolds] = [ManifoldCache::default(), ManifoldCache::default()];
+		let solver = SequentialImpulseSolver::default();
+		let mut islands = Vec::new();
+		for _ in 0..60 {
+			for body in serial.iter_mut().chain(bodies.iter_mut()) {
+				body.integrate(STEP);
+			}
+			detect(&serial, &mut serial_manifolds);
+			solver.solve(&mut serial_manifolds, &mut [], &mut serial, STEP);
+			detect(&bodies, &mut manifolds);
+			islands = solver.solve_islands(&mut manifolds, &mut [], &mut bodies, STEP).0;
+			for (body, expected) in bodies.iter().zip(&serial) {
+				assert_eq!(body.position, expected.position);
+				assert_eq!(body.orientation, expected.orientation);
+				assert_eq!(body.velocity, expected.velocity);
+				assert_eq!(body.angular_velocity, expected.angular_velocity);
+			}
+		}
+		assert_eq!(islands.len(), 8);
+	}
+
+	#[test]
+	pub fn joints_join_islands() {
+		// A chain of two cubes hung from the scenery, off the floor
+		let mut bodies = [cube(3.0), cube(2.0)];
+		let mut joints: [Joint; 2] = [
+			HingeJoint::new([Some(0), None], &bodies, Vector3::new(0.5, 3.5, 0.0), Vector3::z_axis()).into(),
+			HingeJoint::new(
+				[Some(1), Some(0)],
+				&bodies,
+				Vector3::new(0.5, 2.5, 0.0),
+				Vector3::z_axis(),
+			)
+			.into(),
+		];
+		let mut whole = bodies;
+		let mut whole_joints = joints;
+		let solver = SequentialImpulseSolver::default();
+		for _ in 0..30 {
+			for body in whole.iter_mut().chain(bodies.iter_mut()) {
+				body.integrate(STEP);
+			}
+			solver.solve(&mut ManifoldCache::default(), &mut whole_joints, &mut whole, STEP);
+			let (islands, _) = solver.solve_islands(&mut ManifoldCache::default(), &mut joints, &mut bodies, STEP);
+			assert_eq!(islands.len(), 1);
+			assert_eq!(islands[0].links, [0, 1]);
+		}
+		assert_eq!(joints, whole_joints);
+		for (body, expected) in bodies.iter().zip(&whole) {
+			assert!((body.position - expected.position).magnitude() < 1e-5);
+		}
+	}
+
+	#[test]
+	pub fn static_bodies_are_not_pushed() {
+		let mut bodies = [
+			RigidBodyBuilder::new(Shape::default()).kind(BodyKind::Static).build(),
+			cube(0.95),
+		];
+		bodies[1].velocity = Vector3::new(0.0, -2.0, 0.0);
+		let mut manifolds = ManifoldCache::default();
+		let mut data = CollisionData::new(8);
+		let boxes: Vec<CollisionBox> = (0..2)
+			.map(|body| {
+				let mut cuboid = CollisionBox {
+					primitive: CollisionPrimitive::new(body),
+					half_size: Vector3::new(0.5, 0.5, 0.5),
+				};
+				cuboid.primitive.calculate_internals(&bodies);
+				cuboid
+			})
+			.collect();
+		box_and_box(&boxes[1], &boxes[0], &mut data);
+		manifolds.update(&data.contacts, &bodies);
+		SequentialImpulseSolver::default().solve(&mut manifolds, &mut [], &mut bodies, STEP);
+		assert_eq!(bodies[0].velocity, Vector3::zero());
+
+		// The corner that struck the static box now moves away from it
+		let contact = manifolds.contacts()[0];
+		assert!(bodies[1].velocity_at_point(contact.contact_point).y() > 0.0);
+	}
+
+	#[test]
+	pub fn rolling_friction_stops_rolling_ball() {
+		for (rolling_friction, moving) in [(0.05, false), (0.0, true)] {
+			let mut bodies = [RigidBodyBuilder::new(Shape::Sphere { radius: 0.5 })
+				.position(Vector3::new(0.0, 0.5, 0.0))
+				.velocity(Vector3::new(2.0, 0.0, 0.0))
+				.angular_velocity(Vector3::new(0.0, 0.0, -4.0))
+				.acceleration(GRAVITY)
+				.can_sleep(false)
+				.build()];
+			let solver = SequentialImpulseSolver::default();
+			let mut manifolds = ManifoldCache::default();
+			for _ in 0..180 {
+				bodies[0].integrate(STEP);
+				let mut sphere = CollisionSphere {
+					primitive: CollisionPrimitive::new(0),
+					radius: 0.5,
+				};
+				sphere.primitive.calculate_internals(&bodies);
+				let mut data = CollisionData::new(1);
+				data.friction = 0.5;
+				data.rolling_friction = rolling_friction;
+				data.restitution = 0.0;
+				sphere_and_half_space(&sphere, &CollisionPlane::new(Vector3::y_axis(), 0.0), &mut data);
+				manifolds.update(&data.contacts, &bodies);
+				solver.solve(&mut manifolds, &mut [], &mut bodies, STEP);
+			}
+			assert_eq!(bodies[0].velocity.x() > 1.9, moving);
+			assert_eq!(bodies[0].velocity.x().abs() < 0.05, !moving);
+		}
+	}
+}
@@ -0,0 +1,106 @@
+use std::hash::Hasher;
+
+use crate::{quaternion::Quaternion, vec::Vector3, Real};
+
+const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const PRIME: u64 = 0x0100_0000_01b3;
+
+/// A hasher whose hashes are the same on every platform and every Rust
+/// release, for hashing the state of a simulation with
+/// [`PhysicsWorld::state_hash`](crate::world::PhysicsWorld::state_hash).
+///
+/// It is the 64 bit FNV-1a hash. Integers are written little endian and
+/// `usize` as 64 bits, and reals are written as their bits, so two states
+/// hash the same only if they are equal bit for bit.
+#[derive(Debug, Clone, Copy)]
+pub struct StateHasher {
+	hash: u64,
+}
+
+impl Default for StateHasher {
+	fn default() -> Self {
+		Self { hash: OFFSET_BASIS }
+	}
+}
+
+impl Hasher for StateHasher {
+	fn finish(&self) -> u64 {
+		self.hash
+	}
+
+	fn write(&mut self, bytes: &[u8]) {
+		for byte in bytes {
+			self.hash = (self.hash ^ u64::from(*byte)).wrapping_mul(PRIME);
+		}
+	}
+
+	fn write_u16(&mut self, value: u16) {
+		self.write(&value.to_le_bytes());
+	}
+
+	fn write_u32(&mut self, value: u32) {
+		self.write(&value.to_le_bytes());
+	}
+
+	fn write_u64(&mut self, value: u64) {
+		self.write(&value.to_le_bytes());
+	}
+
+	fn write_usize(&mut self, value: usize) {
+		self.write_u64(value as u64);
+	}
+}
+
+impl StateHasher {
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn write_real(&mut self, value: Real) {
+		self.write_u32(value.to_bits());
+	}
+
+	pub fn write_vector(&mut self, vector: Vector3) {
+		for axis in 0..3 {
+			self.write_real(vector[axis]);
+		}
+	}
+
+	pub fn write_quaternion(&mut self, quaternion: Quaternion) {
+		for value in [quaternion.w, quaternion.x, quaternion.y, quaternion.z] {
+			self.write_real(value);
+		}
+	}
+
+	/// Writes a body index, or `None` for the scenery.
+	pub fn write_body(&mut self, body: Option<usize>) {
+		match body {
+			Some(body) => {
+				self.write_u8(1);
+				self.write_usize(body);
+			},
+			None => self.write_u8(0),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	pub fn hashes_are_fixed_and_bit_exact() {
+		let mut hasher = StateHasher::new();
+		hasher.write(b"a");
+		assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+
+		let hash = |value: Real| {
+			let mut hasher = StateHasher::new();
+			hasher.write_real(value);
+			hasher.finish()
+		};
+		assert_eq!(hash(1.0), hash(1.0));
+		assert_ne!(hash(0.0), hash(-0.0));
+	}
+}
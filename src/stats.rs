@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use crate::solver::SolverReport;
+
+/// How long each stage of a step took.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StageTimings {
+	/// Holds the time taken to apply the forces, integrate the bodies, and
+	/// sweep those with continuous collision.
+	pub integrate: Duration,
+
+	/// Holds the time taken to update the broad phase with the moved colliders.
+	pub broad_phase: Duration,
+
+	/// Holds the time taken to find the contacts of the pairs given by the
+	/// broad phase and of the bodies with the scenery.
+	pub narrow_phase: Duration,
+
+	/// Holds the time taken to update the manifolds, solve the islands, and
+	/// put settled islands to sleep.
+	pub solve: Duration,
+
+	/// Holds the time taken to update the sensors and report the step's events.
+	pub events: Duration,
+
+	/// Holds the time the whole step took, hooks included.
+	pub total: Duration,
+}
+
+/// What a step of a [`PhysicsWorld`](crate::world::PhysicsWorld) did and
+/// how long it took, for tuning a scene.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct StepStats {
+	/// Holds the number of pairs of bodies the broad phase found that the
+	/// contact filter accepted.
+	pub broad_phase_pairs: usize,
+
+	/// Holds the number of contacts the narrow phase found.
+	pub contacts: usize,
+
+	/// Holds the number of contact points held in the manifolds after the step.
+	pub manifold_points: usize,
+
+	/// Holds the number of islands the solver solved.
+	pub islands: usize,
+
+	/// Holds the solver's report, with the iterations it used.
+	pub solver: SolverReport,
+
+	/// Holds the number of dynamic bodies awake after the step.
+	pub awake_bodies: usize,
+
+	/// Holds the number of dynamic bodies asleep after the step.
+	pub sleeping_bodies: usize,
+
+	/// Holds how long each stage of the step took.
+	pub timings: StageTimings,
+}
+
+/// How much the storage a [`PhysicsWorld`](crate::world::PhysicsWorld)
+/// reuses from step to step can hold before it grows, to check that a
+/// scene has stopped allocating once it has warmed up.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PoolCapacities {
+	/// Holds the number of contacts the narrow phase can find.
+	pub contacts: usize,
+
+	/// Holds the number of pairs the broad phase can give.
+	pub pairs: usize,
+
+	/// Holds the number of bodies the lists kept for each body can hold.
+	pub bodies: usize,
+
+	/// Holds the number of pairs in contact that can be compared from one step to the next.
+	pub touching: usize,
+
+	/// Holds the number of bodies overlapping sensors that can be compared
+	/// from one step to the next.
+	pub overlaps: usize,
+
+	/// Holds the number of events that can be queued.
+	pub events: usize,
+
+	/// Holds the number of contacts the threads of the narrow phase can
+	/// find with the `rayon` feature, along with the number of threads.
+	pub contact_buffers: usize,
+
+	/// Holds the number of elements the solver's islands and the storage
+	/// they are solved with can hold, across all of its lists.
+	pub solver: usize,
+}
@@ -0,0 +1,158 @@
+use crate::{cloth::Cloth, forces::ParticleSpringLink, particle::Particle, Real};
+
+/// A post-solve pass that stops springs stretching or compressing
+/// beyond a fraction of their rest length.
+///
+/// Soft springs under load can stretch arbitrarily far. After the springs have
+/// been integrated, the limiter moves any particles whose spring is outside the
+/// allowed range back to its boundary, in proportion to their inverse masses,
+/// and removes the relative velocity that would carry them further out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StrainLimiter {
+	/// Holds the largest allowed extension as a fraction of rest length.
+	pub max_stretch: Real,
+
+	/// Holds the largest allowed compression as a fraction of rest length.
+	pub max_compression: Real,
+
+	/// Holds the number of passes made over the springs.
+	/// Connected springs fight each other, so several passes may be needed.
+	pub iterations: usize,
+}
+
+impl Default for StrainLimiter {
+	fn default() -> Self {
+		Self {
+			max_stretch: 0.1,
+			max_compression: 1.0,
+			iterations: 1,
+		}
+	}
+}
+
+impl StrainLimiter {
+	/// Creates a limiter that stops springs stretching more than
+	/// the given fraction of their rest length.
+	#[must_use]
+	pub fn new(max_stretch: Real) -> Self {
+		Self {
+			max_stretch,
+			..Default::default()
+		}
+	}
+
+	/// Limits the strain of every given spring.
+	pub fn limit_springs(&self, particles: &mut [Particle], springs: &[ParticleSpringLink]) {
+		for _ in 0..self.iterations {
+			for spring in springs {
+				self.limit(particles, spring.particles, spring.rest_length);
+			}
+		}
+	}
+
+	/// Limits the strain between a single pair of particles with the given rest length.
+	pub fn limit(&self, particles: &mut [Particle], pair: [usize; 2], rest_length: Real) {
+		let [first, second] = pair;
+		let first_inverse_mass = particles[first].inverse_mass;
+		let second_inverse_mass = particles[second].inverse_mass;
+		let total_inverse_mass = first_inverse_mass + second_inverse_mass;
+		if total_inverse_mass <= 0.0 {
+			return;
+		}
+
+		let delta = particles[second].position - particles[first].position;
+		let length = delta.magnitude();
+		let min_length = rest_length * (1.0 - self.max_compression).max(0.0);
+		let max_length = rest_length * (1.0 + self.max_stretch);
+		let target = length.clamp(min_length, max_length);
+		let error = length - target;
+		if error == 0.0 {
+			return;
+		}
+
+		// Move the particles back to the boundary of the allowed range
+		let direction = delta.normalize();
+		let correction = direction * (error / total_inverse_mass);
+		particles[first].position += correction * first_inverse_mass;
+		particles[second].position -= correction * second_inverse_mass;
+
+		// Remove any relative velocity carrying them further out of range
+		let separating = (particles[second].velocity - particles[first].velocity).dot(&direction);
+		if separating * error > 0.0 {
+			let impulse = direction * (separating / total_inverse_mass);
+			particles[first].velocity += impulse * first_inverse_mass;
+			particles[second].velocity -= impulse * second_inverse_mass;
+		}
+	}
+}
+
+impl Cloth {
+	/// Limits the strain of every spring in the cloth.
+	pub fn limit_strain(&self, particles: &mut [Particle], limiter: &StrainLimiter) {
+		for _ in 0..limiter.iterations {
+			for spring in self.springs() {
+				limiter.limit(particles, spring.particles, spring.rest_length);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{vec::Vector3, ClothBuilder};
+
+	use super::*;
+
+	fn particle(position: Vector3, velocity: Vector3) -> Particle {
+		Particle {
+			position,
+			velocity,
+			inverse_mass: 1.0,
+			damping: 1.0,
+			..Default::default()
+		}
+	}
+
+	#[test]
+	pub fn overstretched_pair_is_clamped() {
+		let mut particles = [
+			particle(Vector3::zero(), Vector3::new(-1.0, 0.0, 0.0)),
+			particle(Vector3::new(2.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0)),
+		];
+		StrainLimiter::new(0.1).limit(&mut particles, [0, 1], 1.0);
+		assert!((particle_length(&particles, 0, 1) - 1.1).abs() < 1e-6);
+		assert_eq!(particles[0].velocity, Vector3::zero());
+		assert_eq!(particles[1].velocity, Vector3::zero());
+	}
+
+	#[test]
+	pub fn pair_within_limit_is_untouched() {
+		let mut particles = [
+			particle(Vector3::zero(), Vector3::zero()),
+			particle(Vector3::new(1.05, 0.0, 0.0), Vector3::x_axis()),
+		];
+		StrainLimiter::new(0.1).limit(&mut particles, [0, 1], 1.0);
+		assert_eq!(particles[1].position, Vector3::new(1.05, 0.0, 0.0));
+		assert_eq!(particles[1].velocity, Vector3::x_axis());
+	}
+
+	#[test]
+	pub fn limits_cloth_springs() {
+		let mut particles = Vec::new();
+		let cloth = ClothBuilder::new(Vector3::zero(), 2, 2, 1.0)
+			.pin_top_row()
+			.build(&mut particles);
+		particles[cloth.index(0, 1)].position = Vector3::new(0.0, -5.0, 0.0);
+		let limiter = StrainLimiter {
+			iterations: 10,
+			..StrainLimiter::new(0.2)
+		};
+		cloth.limit_strain(&mut particles, &limiter);
+		let stretched = particle_length(&particles, cloth.index(0, 0), cloth.index(0, 1));
+		assert!(stretched <= 1.2 + 1e-4);
+	}
+
+	fn particle_length(particles: &[Particle], first: usize, second: usize) -> Real {
+		(particles[second].position - particles[first].position).magnitude()
+	}
+}
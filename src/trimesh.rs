@@ -0,0 +1,360 @@
+use crate::{
+	bounding::{Aabb, Ray},
+	bvh::Bvh,
+	vec::Vector3,
+	Real,
+};
+
+/// A triangle in world coordinates. Its front face is the side from which
+/// the vertices appear counter-clockwise.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Triangle {
+	pub vertices: [Vector3; 3],
+}
+
+impl Triangle {
+	#[must_use]
+	pub const fn new(vertices: [Vector3; 3]) -> Self {
+		Self { vertices }
+	}
+
+	/// Returns the unit normal of the front face, or zero if the triangle is degenerate.
+	#[must_use]
+	pub fn normal(&self) -> Vector3 {
+		let [a, b, c] = self.vertices;
+		(b - a).cross(&(c - a)).normalize()
+	}
+
+	#[must_use]
+	pub fn centroid(&self) -> Vector3 {
+		let [a, b, c] = self.vertices;
+		(a + b + c) * (1.0 / 3.0)
+	}
+
+	/// Returns the smallest axis-aligned box enclosing the triangle.
+	#[must_use]
+	pub fn bounds(&self) -> Aabb {
+		let [a, b, c] = self.vertices;
+		let mut bounds = Aabb::new(a, a);
+		for axis in 0..3 {
+			bounds.min[axis] = a[axis].min(b[axis]).min(c[axis]);
+			bounds.max[axis] = a[axis].max(b[axis]).max(c[axis]);
+		}
+		bounds
+	}
+
+	/// Returns the barycentric weights of a point in the plane of the
+	/// triangle, or `None` if the triangle is degenerate.
+	#[must_use]
+	pub fn barycentric(&self, point: Vector3) -> Option<[Real; 3]> {
+		let [a, b, c] = self.vertices;
+		let first_edge = b - a;
+		let second_edge = c - a;
+		let offset = point - a;
+		let first_squared = first_edge.magnitude_squared();
+		let edges = first_edge.dot(&second_edge);
+		let second_squared = second_edge.magnitude_squared();
+		let first_offset = offset.dot(&first_edge);
+		let second_offset = offset.dot(&second_edge);
+		let denominator = first_squared.mul_add(second_squared, -edges * edges);
+		if denominator <= Real::EPSILON * first_squared * second_squared {
+			return None;
+		}
+		let second = second_squared.mul_add(first_offset, -edges * second_offset) / denominator;
+		let third = first_squared.mul_add(second_offset, -edges * first_offset) / denominator;
+		Some([1.0 - second - third, second, third])
+	}
+
+	/// Returns true if the point, projected onto the plane of the triangle, falls inside it.
+	#[must_use]
+	pub fn contains_projection(&self, point: Vector3) -> bool {
+		self.barycentric(point)
+			.is_some_and(|weights| weights.iter().all(|weight| *weight >= 0.0))
+	}
+
+	/// Returns the point on the triangle closest to the given point.
+	#[must_use]
+	pub fn closest_point(&self, point: Vector3) -> Vector3 {
+		let normal = self.normal();
+		let projection = point - normal * normal.dot(&(point - self.vertices[0]));
+		if self.contains_projection(projection) {
+			return projection;
+		}
+
+		// The point lies beyond an edge, so the closest point is on the nearest edge
+		let [a, b, c] = self.vertices;
+		[[a, b], [b, c], [c, a]]
+			.into_iter()
+			.map(|edge| closest_point_on_segment(edge, point))
+			.min_by(|first, second| {
+				(*first - point)
+					.magnitude_squared()
+					.total_cmp(&(*second - point).magnitude_squared())
+			})
+			.unwrap_or(a)
+	}
+
+	/// Returns the distance along the ray at which it hits either face of
+	/// the triangle, or `None` if it misses or hits beyond the given distance.
+	#[must_use]
+	pub fn ray_distance(&self, ray: &Ray, max_distance: Real) -> Option<Real> {
+		let [a, b, c] = self.vertices;
+		let first_edge = b - a;
+		let second_edge = c - a;
+		let across = ray.direction.cross(&second_edge);
+		let determinant = first_edge.dot(&across);
+		if determinant.abs() < Real::EPSILON {
+			return None;
+		}
+		let inverse = determinant.recip();
+		let offset = ray.origin - a;
+		let first_weight = offset.dot(&across) * inverse;
+		if !(0.0..=1.0).contains(&first_weight) {
+			return None;
+		}
+		let upward = offset.cross(&first_edge);
+		let second_weight = ray.direction.dot(&upward) * inverse;
+		if second_weight < 0.0 || first_weight + second_weight > 1.0 {
+			return None;
+		}
+		let distance = second_edge.dot(&upward) * inverse;
+		(0.0..=max_distance).contains(&distance).then_some(distance)
+	}
+}
+
+/// Returns the point on the segment closest to the given point.
+#[must_use]
+pub fn closest_point_on_segment(segment: [Vector3; 2], point: Vector3) -> Vector3 {
+	let direction = segment[1] - segment[0];
+	let length_squared = direction.magnitude_squared();
+	if length_squared <= Real::EPSILON {
+		return segment[0];
+	}
+	let along = ((point - segment[0]).dot(&direction) / length_squared).clamp(0.0, 1.0);
+	segment[0] + direction * along
+}
+
+/// Where a ray hits a mesh.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct MeshHit {
+	/// Holds the index of the triangle that was hit.
+	pub triangle: usize,
+
+	/// Holds the distance along the ray to the hit.
+	pub distance: Real,
+
+	/// Holds the point hit, in world coordinates.
+	pub point: Vector3,
+
+	/// Holds the unit normal of the front face of the triangle hit.
+	pub normal: Vector3,
+}
+
+/// A static mesh of triangles in world coordinates, used for level geometry.
+///
+/// The mesh holds a hierarchy of bounding boxes over its triangles, so that
+/// only the triangles near a shape or along a ray are tested. Meshes are
+/// one-sided: shapes behind a triangle are not pushed out through it.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(crate = "serde", try_from = "MeshData", into = "MeshData")
+)]
+pub struct TriMesh {
+	vertices: Vec<Vector3>,
+	indices: Vec<[usize; 3]>,
+	bvh: Bvh<Aabb>,
+}
+
+/// The vertices and triangles a mesh is written as, its hierarchy being
+/// built again when it is read.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(crate = "serde")]
+struct MeshData {
+	vertices: Vec<Vector3>,
+	indices: Vec<[usize; 3]>,
+}
+
+#[cfg(feature = "serde")]
+impl From<TriMesh> for MeshData {
+	fn from(mesh: TriMesh) -> Self {
+		Self {
+			vertices: mesh.vertices,
+			indices: mesh.indices,
+		}
+	}
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<MeshData> for TriMesh {
+	type Error = String;
+
+	fn try_from(data: MeshData) -> Result<Self, Self::Error> {
+		match data
+			.indices
+			.iter()
+			.flatten()
+			.find(|vertex| **vertex >= data.vertices.len())
+		{
+			Some(vertex) => Err(format!("a triangle refers to vertex {vertex}, which does not exist")),
+			None => Ok(Self::new(data.vertices, data.indices)),
+		}
+	}
+}
+
+impl TriMesh {
+	/// Creates a mesh from shared vertices and triangles given by the
+	/// indices of their vertices, counter-clockwise seen from the front.
+	///
+	/// # Panics
+	///
+	/// Will panic if a triangle refers to a vertex that does not exist.
+	#[must_use]
+	pub fn new(vertices: Vec<Vector3>, indices: Vec<[usize; 3]>) -> Self {
+		let mut mesh = Self {
+			vertices,
+			indices,
+			bvh: Bvh::default(),
+		};
+		for index in 0..mesh.indices.len() {
+			let bounds = mesh.triangle(index).bounds();
+			mesh.bvh.insert(index, bounds);
+		}
+		mesh
+	}
+
+	/// Returns the number of triangles in the mesh.
+	#[must_use]
+	pub const fn len(&self) -> usize {
+		self.indices.len()
+	}
+
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.indices.is_empty()
+	}
+
+	/// Returns the triangle with the given index.
+	#[must_use]
+	pub fn triangle(&self, index: usize) -> Triangle {
+		Triangle::new(self.indices[index].map(|vertex| self.vertices[vertex]))
+	}
+
+	/// Returns the indices of the triangles whose bounds overlap the given box, in order.
+	#[must_use]
+	pub fn query(&self, bounds: &Aabb) -> Vec<usize> {
+		let mut triangles = self.bvh.query(bounds);
+		triangles.sort_unstable();
+		triangles
+	}
+
+	/// Returns the nearest triangle the ray hits within the given distance.
+	#[must_use]
+	pub fn ray_cast(&self, ray: &Ray, max_distance: Real) -> Option<MeshHit> {
+		let mut nearest: Option<MeshHit> = None;
+		for candidate in self.bvh.ray_cast(ray, max_distance) {
+			let limit = nearest.map_or(max_distance, |hit| hit.distance);
+			// Candidates are ordered by where the ray enters their bounds, so none further can be nearer
+			if candidate.distance > limit {
+				break;
+			}
+			let triangle = self.triangle(candidate.body);
+			if let Some(distance) = triangle.ray_distance(ray, limit) {
+				nearest = Some(MeshHit {
+					triangle: candidate.body,
+					distance,
+					point: ray.point_at(distance),
+					normal: triangle.normal(),
+				});
+			}
+		}
+		nearest
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A square floor of two triangles facing up, and a ramp beyond it.
+	fn level() -> TriMesh {
+		TriMesh::new(
+			vec![
+				Vector3::new(-2.0, 0.0, -2.0),
+				Vector3::new(-2.0, 0.0, 2.0),
+				Vector3::new(2.0, 0.0, 2.0),
+				Vector3::new(2.0, 0.0, -2.0),
+				Vector3::new(4.0, 2.0, 2.0),
+				Vector3::new(4.0, 2.0, -2.0),
+			],
+			vec![[0, 1, 2], [0, 2, 3], [3, 2, 4], [3, 4, 5]],
+		)
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	pub fn meshes_are_rebuilt_when_read() {
+		let mesh: TriMesh = serde_json::from_str(&serde_json::to_string(&level()).expect("the mesh is written"))
+			.expect("the mesh is read");
+		assert_eq!(
+			mesh.query(&level().triangle(2).bounds()),
+			level().query(&level().triangle(2).bounds())
+		);
+		let broken = r#"{"vertices":[[0.0,0.0,0.0]],"indices":[[0,0,1]]}"#;
+		assert!(serde_json::from_str::<TriMesh>(broken).is_err());
+	}
+
+	#[test]
+	pub fn triangle_geometry() {
+		let triangle = level().triangle(0);
+		assert_eq!(triangle.normal(), Vector3::y_axis());
+		assert_eq!(
+			triangle.bounds(),
+			Aabb::new(Vector3::new(-2.0, 0.0, -2.0), Vector3::new(2.0, 0.0, 2.0))
+		);
+		assert_eq!(
+			triangle.closest_point(Vector3::new(-1.0, 3.0, 1.0)),
+			Vector3::new(-1.0, 0.0, 1.0)
+		);
+		assert_eq!(
+			triangle.closest_point(Vector3::new(1.0, 1.0, -1.0)),
+			Vector3::new(0.0, 0.0, 0.0)
+		);
+		assert_eq!(
+			triangle.closest_point(Vector3::new(-3.0, 0.0, -3.0)),
+			Vector3::new(-2.0, 0.0, -2.0)
+		);
+	}
+
+	#[test]
+	pub fn query_finds_nearby_triangles() {
+		let mesh = level();
+		assert_eq!(mesh.len(), 4);
+		let near_ramp = Aabb::from_center(Vector3::new(3.0, 1.0, 0.0), Vector3::new(0.5, 0.5, 0.5));
+		assert_eq!(mesh.query(&near_ramp), vec![2, 3]);
+		let far_away = Aabb::from_center(Vector3::new(0.0, 10.0, 0.0), Vector3::new(0.5, 0.5, 0.5));
+		assert!(mesh.query(&far_away).is_empty());
+	}
+
+	#[test]
+	pub fn ray_cast_hits_nearest_triangle() {
+		let mesh = level();
+		let down = Ray::new(Vector3::new(-1.0, 5.0, 1.0), -Vector3::y_axis());
+		let hit = mesh.ray_cast(&down, 10.0).expect("the ray hits the floor");
+		assert_eq!(hit.triangle, 0);
+		assert!((hit.distance - 5.0).abs() < 1e-5);
+		assert_eq!(hit.normal, Vector3::y_axis());
+		assert!(mesh.ray_cast(&down, 4.0).is_none());
+
+		// Across the floor the ray reaches the ramp, whose bounds overlap the floor's
+		let across = Ray::new(Vector3::new(-5.0, 0.5, 0.0), Vector3::x_axis());
+		let hit = mesh.ray_cast(&across, 20.0).expect("the ray hits the ramp");
+		assert!(hit.triangle == 2 || hit.triangle == 3);
+		assert!((hit.point.x() - 2.5).abs() < 1e-5);
+		assert!(mesh
+			.ray_cast(&Ray::new(Vector3::new(0.0, 5.0, 0.0), Vector3::y_axis()), 20.0)
+			.is_none());
+	}
+}
@@ -1,5 +1,5 @@
 use crate::{reals_are_equal, Real};
-use std::ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub};
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign};
 
 #[derive(Debug, Copy, Clone)]
 pub struct Vector<T, const LEN: usize>
@@ -26,24 +26,46 @@ impl<T: Default + Copy, const LEN: usize> Default for Vector<T, { LEN }> {
 	}
 }
 
+impl<T: Copy, const LEN: usize> Vector<T, { LEN }> {
+	/// Applies the operation to each element. Building the elements from a
+	/// function of their index, rather than updating them in a loop, is
+	/// what lets a short vector such as a [`Vector3`] be unrolled into
+	/// straight-line code once inlined.
+	#[inline]
+	fn map(self, operation: impl Fn(T) -> T) -> Self {
+		Self {
+			elements: self.elements.map(operation),
+		}
+	}
+
+	/// Applies the operation to each pair of elements at the same index.
+	#[inline]
+	fn zip_map(self, rhs: Self, operation: impl Fn(T, T) -> T) -> Self {
+		Self {
+			elements: std::array::from_fn(|index| operation(self.elements[index], rhs.elements[index])),
+		}
+	}
+}
+
 impl<T: Copy + Neg<Output = T>, const LEN: usize> Vector<T, { LEN }> {
 	#[must_use]
+	#[inline]
 	pub fn inverse(&self) -> Self {
-		let mut elements: [T; LEN] = self.elements;
-		elements.iter_mut().for_each(|a| *a = -*a);
-		Self { elements }
+		self.map(Neg::neg)
 	}
 }
 
 impl<T, const LEN: usize> Index<usize> for Vector<T, { LEN }> {
 	type Output = T;
 
+	#[inline]
 	fn index(&self, index: usize) -> &Self::Output {
 		&self.elements[index]
 	}
 }
 
 impl<T, const LEN: usize> IndexMut<usize> for Vector<T, { LEN }> {
+	#[inline]
 	fn index_mut(&mut self, index: usize) -> &mut Self::Output {
 		&mut self.elements[index]
 	}
@@ -51,81 +73,66 @@ impl<T, const LEN: usize> IndexMut<usize> for Vector<T, { LEN }> {
 
 impl<T: Copy + Mul<T, Output = T>, const LEN: usize> Mul<T> for Vector<T, { LEN }> {
 	type Output = Self;
-	fn mul(self, rhs: T) -> Self::Output {
-		let mut elements: [T; LEN] = self.elements;
-		elements.iter_mut().for_each(|a| *a = *a * rhs);
-		Self { elements }
-	}
-}
-
-impl<T: Copy + Mul<T, Output = T>, const LEN: usize> Mul<Self> for Vector<T, { LEN }> {
-	type Output = Self;
-
-	fn mul(self, rhs: Self) -> Self::Output {
-		let mut elements: [T; LEN] = self.elements;
-		elements
-			.iter_mut()
-			.zip(rhs.elements.iter())
-			.for_each(|(a, b)| *a = *a * *b);
-		Self { elements }
-	}
-}
 
-impl<T: Mul<Output = T> + Copy, const LEN: usize> MulAssign for Vector<T, { LEN }> {
-	fn mul_assign(&mut self, rhs: Self) {
-		self.elements
-			.iter_mut()
-			.zip(rhs.elements.iter())
-			.for_each(|(a, b)| *a = *a * *b);
+	#[inline]
+	fn mul(self, rhs: T) -> Self::Output {
+		self.map(|a| a * rhs)
 	}
 }
 
 impl<T: Copy + Mul<T, Output = T>, const LEN: usize> MulAssign<T> for Vector<T, { LEN }> {
+	#[inline]
 	fn mul_assign(&mut self, rhs: T) {
-		self.elements.iter_mut().for_each(|a| *a = *a * rhs);
+		for element in &mut self.elements {
+			*element = *element * rhs;
+		}
 	}
 }
 
-impl<T: Add<Output = T> + Copy, const LEN: usize> Add for Vector<T, { LEN }> {
-	type Output = Self;
-	fn add(self, rhs: Self) -> Self {
-		let mut elements: [T; LEN] = self.elements;
-		elements
-			.iter_mut()
-			.zip(rhs.elements.iter())
-			.for_each(|(a, b)| *a = *a + *b);
-		Self { elements }
-	}
-}
+/// Implements an elementwise operator between two vectors, along with its
+/// assigning form.
+macro_rules! elementwise {
+	($operator:ident, $method:ident, $assign_operator:ident, $assign_method:ident) => {
+		impl<T: Copy + $operator<Output = T>, const LEN: usize> $operator for Vector<T, { LEN }> {
+			type Output = Self;
+
+			#[inline]
+			fn $method(self, rhs: Self) -> Self::Output {
+				self.zip_map(rhs, $operator::$method)
+			}
+		}
 
-impl<T: Add<Output = T> + Copy, const LEN: usize> AddAssign for Vector<T, { LEN }> {
-	fn add_assign(&mut self, rhs: Self) {
-		self.elements
-			.iter_mut()
-			.zip(rhs.elements.iter())
-			.for_each(|(a, b)| *a = *a + *b);
-	}
+		impl<T: Copy + $operator<Output = T>, const LEN: usize> $assign_operator for Vector<T, { LEN }> {
+			#[inline]
+			fn $assign_method(&mut self, rhs: Self) {
+				*self = $operator::$method(*self, rhs);
+			}
+		}
+	};
 }
 
-impl<T: Sub<Output = T> + Copy, const LEN: usize> Sub for Vector<T, { LEN }> {
+elementwise!(Add, add, AddAssign, add_assign);
+elementwise!(Sub, sub, SubAssign, sub_assign);
+elementwise!(Mul, mul, MulAssign, mul_assign);
+
+impl<T: Copy + Neg<Output = T>, const LEN: usize> Neg for Vector<T, { LEN }> {
 	type Output = Self;
-	fn sub(self, rhs: Self) -> Self {
-		let mut elements: [T; LEN] = self.elements;
-		elements
-			.iter_mut()
-			.zip(rhs.elements.iter())
-			.for_each(|(a, b)| *a = *a - *b);
-		Self { elements }
+
+	#[inline]
+	fn neg(self) -> Self::Output {
+		self.inverse()
 	}
 }
 
 impl<const LEN: usize> Vector<Real, { LEN }> {
 	#[must_use]
+	#[inline]
 	pub fn magnitude(&self) -> Real {
 		self.magnitude_squared().sqrt()
 	}
 
 	#[must_use]
+	#[inline]
 	pub fn magnitude_squared(&self) -> Real {
 		self.elements.iter().fold(0.0 as Real, |acc, e| acc + e.powi(2))
 	}
@@ -136,17 +143,68 @@ impl<const LEN: usize> Vector<Real, { LEN }> {
 	}
 
 	#[must_use]
+	#[inline]
 	pub fn normalize(&self) -> Self {
 		let length = self.magnitude();
 		if length > 0.0 { *self * length.recip() } else { *self }
 	}
 
 	#[must_use]
+	#[inline]
 	pub fn dot(&self, rhs: &Self) -> Real {
-		self.elements
-			.iter()
-			.zip(rhs.elements.iter())
-			.fold(0.0 as Real, |acc, (a, b)| (*a).mul_add(*b, acc))
+		// Starting from the first product rather than from zero saves a fused
+		// multiply-add, which is a call rather than an instruction on targets without one
+		let mut products = self.elements.iter().zip(rhs.elements.iter());
+		products.next().map_or(0.0, |(a, b)| {
+			products.fold(a * b, |acc, (a, b)| (*a).mul_add(*b, acc))
+		})
+	}
+}
+
+/// Vectors are written as a sequence of their elements.
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize, const LEN: usize> serde::Serialize for Vector<T, { LEN }> {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		use serde::ser::SerializeTuple;
+		let mut tuple = serializer.serialize_tuple(LEN)?;
+		for element in &self.elements {
+			tuple.serialize_element(element)?;
+		}
+		tuple.end()
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de> + Default + Copy, const LEN: usize> serde::Deserialize<'de>
+	for Vector<T, { LEN }>
+{
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		deserializer.deserialize_tuple(LEN, ElementsVisitor(std::marker::PhantomData))
+	}
+}
+
+/// Reads the elements of a vector from a sequence of exactly its length.
+#[cfg(feature = "serde")]
+struct ElementsVisitor<T, const LEN: usize>(std::marker::PhantomData<T>);
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de> + Default + Copy, const LEN: usize> serde::de::Visitor<'de>
+	for ElementsVisitor<T, { LEN }>
+{
+	type Value = Vector<T, { LEN }>;
+
+	fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(formatter, "a sequence of {LEN} elements")
+	}
+
+	fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut sequence: A) -> Result<Self::Value, A::Error> {
+		let mut elements = [T::default(); LEN];
+		for (index, element) in elements.iter_mut().enumerate() {
+			*element = sequence
+				.next_element()?
+				.ok_or_else(|| serde::de::Error::invalid_length(index, &self))?;
+		}
+		Ok(Vector { elements })
 	}
 }
 
@@ -174,21 +232,25 @@ impl Vector3 {
 	}
 
 	#[must_use]
+	#[inline]
 	pub fn x(&self) -> Real {
 		self[0]
 	}
 
 	#[must_use]
+	#[inline]
 	pub fn y(&self) -> Real {
 		self[1]
 	}
 
 	#[must_use]
+	#[inline]
 	pub fn z(&self) -> Real {
 		self[2]
 	}
 
 	#[must_use]
+	#[inline]
 	pub fn cross(&self, rhs: &Self) -> Self {
 		Self::new(
 			self.y().mul_add(rhs.z(), -self.z() * rhs.y()),
@@ -260,6 +322,18 @@ mod tests {
 		);
 	}
 
+	#[test]
+	pub fn sub_assign() {
+		let mut vector = Vector3::new(2.0, 4.0, 6.0);
+		vector -= Vector3::new(1.0, 2.0, 3.0);
+		assert_eq!(vector, Vector3::new(1.0, 2.0, 3.0));
+	}
+
+	#[test]
+	pub fn neg() {
+		assert_eq!(-Vector3::new(1.0, -2.0, 3.0), Vector3::new(-1.0, 2.0, -3.0));
+	}
+
 	#[test]
 	pub fn multiply_scalar() {
 		assert_eq!(Vector3::new(1.0, 2.0, 3.0) * 2.0, Vector3::new(2.0, 4.0, 6.0));
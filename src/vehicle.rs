@@ -0,0 +1,330 @@
+use std::ops::Range;
+
+use crate::{
+	cloth::SpringStiffness,
+	contacts::{ParticleContact, ParticleContactGenerator},
+	forces::ParticleSpringLink,
+	links::{particle_distance, ParticleRod},
+	particle::Particle,
+	portable::sin_cos,
+	real_from_count,
+	vec::Vector3,
+	Real,
+};
+
+/// Builds a simple car from particles: a chassis of eight corner particles
+/// braced rigidly by rods, with a wheel particle hung on a suspension spring
+/// below each of the four bottom corners.
+///
+/// The car is laid out facing along the positive x axis with its right side
+/// along the positive z axis, and `position` is the center of the chassis floor.
+#[derive(Debug, Clone, Copy)]
+pub struct VehicleBuilder {
+	position: Vector3,
+	length: Real,
+	width: Real,
+	height: Real,
+	chassis_mass: Real,
+	wheel_mass: Real,
+	suspension: SpringStiffness,
+	suspension_length: Real,
+	damping: Real,
+	drive_force: Real,
+	grip: Real,
+}
+
+impl VehicleBuilder {
+	#[must_use]
+	pub const fn new(position: Vector3) -> Self {
+		Self {
+			position,
+			length: 4.0,
+			width: 2.0,
+			height: 1.0,
+			chassis_mass: 8.0,
+			wheel_mass: 1.0,
+			suspension: SpringStiffness::new(200.0, 10.0),
+			suspension_length: 0.5,
+			damping: 0.99,
+			drive_force: 40.0,
+			grip: 10.0,
+		}
+	}
+
+	/// Sets the length, width, and height of the chassis.
+	#[must_use]
+	pub const fn size(mut self, length: Real, width: Real, height: Real) -> Self {
+		self.length = length;
+		self.width = width;
+		self.height = height;
+		self
+	}
+
+	/// Sets the mass of the chassis, shared evenly between its eight corners.
+	#[must_use]
+	pub const fn chassis_mass(mut self, mass: Real) -> Self {
+		self.chassis_mass = mass;
+		self
+	}
+
+	/// Sets the mass of each wheel.
+	#[must_use]
+	pub const fn wheel_mass(mut self, mass: Real) -> Self {
+		self.wheel_mass = mass;
+		self
+	}
+
+	/// Sets the stiffness and rest length of the springs holding the wheels.
+	#[must_use]
+	pub const fn suspension(mut self, stiffness: SpringStiffness, length: Real) -> Self {
+		self.suspension = stiffness;
+		self.suspension_length = length;
+		self
+	}
+
+	/// Sets the damping applied to every particle in the vehicle.
+	#[must_use]
+	pub const fn damping(mut self, damping: Real) -> Self {
+		self.damping = damping;
+		self
+	}
+
+	/// Sets the force applied at each driven wheel at full throttle.
+	#[must_use]
+	pub const fn drive_force(mut self, force: Real) -> Self {
+		self.drive_force = force;
+		self
+	}
+
+	/// Sets how strongly the wheels resist sliding sideways.
+	#[must_use]
+	pub const fn grip(mut self, grip: Real) -> Self {
+		self.grip = grip;
+		self
+	}
+
+	/// Appends the vehicle's particles to the given list and returns
+	/// the vehicle describing the links between them.
+	pub fn build(self, particles: &mut Vec<Particle>) -> Vehicle {
+		let first = particles.len();
+		let particle = |position: Vector3, mass: Real| Particle {
+			position,
+			damping: self.damping,
+			inverse_mass: if mass > 0.0 { mass.recip() } else { 0.0 },
+			..Default::default()
+		};
+
+		// Corners are indexed by their bits: front, top, right
+		let corner_mass = self.chassis_mass / 8.0;
+		let chassis: [usize; 8] = std::array::from_fn(|corner| {
+			let along = if corner & 4 == 0 { -0.5 } else { 0.5 };
+			let up = if corner & 2 == 0 { 0.0 } else { 1.0 };
+			let across = if corner & 1 == 0 { -0.5 } else { 0.5 };
+			let offset = Vector3::new(along * self.length, up * self.height, across * self.width);
+			particles.push(particle(self.position + offset, corner_mass));
+			first + corner
+		});
+
+		// Wheels hang below the bottom corners: rear left, rear right, front left, front right
+		let mut wheels = [0; 4];
+		for (wheel, corner) in [0, 1, 4, 5].into_iter().enumerate() {
+			let position = particles[chassis[corner]].position - Vector3::y_axis() * self.suspension_length;
+			particles.push(particle(position, self.wheel_mass));
+			wheels[wheel] = particles.len() - 1;
+		}
+
+		let rods = Self::brace(particles, &chassis);
+		let springs = self.suspend(particles, &chassis, &wheels);
+
+		Vehicle {
+			particles: first..particles.len(),
+			chassis,
+			wheels,
+			rods,
+			springs,
+			steering: 0.0,
+			drive_force: self.drive_force,
+			grip: self.grip,
+		}
+	}
+
+	/// Braces every pair of chassis corners so the chassis stays rigid.
+	fn brace(particles: &[Particle], chassis: &[usize; 8]) -> Vec<ParticleRod> {
+		let mut rods = Vec::new();
+		for (index, first_corner) in chassis.iter().enumerate() {
+			for second_corner in &chassis[index + 1..] {
+				let pair = [*first_corner, *second_corner];
+				rods.push(ParticleRod {
+					particles: pair,
+					length: particle_distance(particles, pair),
+				});
+			}
+		}
+		rods
+	}
+
+	/// Hangs each wheel from its corner, and ties it to the neighbouring
+	/// bottom corners so it cannot swing out from under the chassis.
+	fn suspend(&self, particles: &[Particle], chassis: &[usize; 8], wheels: &[usize; 4]) -> Vec<ParticleSpringLink> {
+		let mut springs = Vec::new();
+		for (wheel, [corner, along, across]) in wheels.iter().zip([[0, 4, 1], [1, 5, 0], [4, 0, 5], [5, 1, 4]]) {
+			for corner in [corner, along, across] {
+				let pair = [chassis[corner], *wheel];
+				springs.push(ParticleSpringLink {
+					particles: pair,
+					spring_constant: self.suspension.spring_constant,
+					damping: self.suspension.damping,
+					rest_length: particle_distance(particles, pair),
+				});
+			}
+		}
+		springs
+	}
+}
+
+/// A particle car created by a [`VehicleBuilder`].
+#[derive(Debug, Default, Clone)]
+pub struct Vehicle {
+	/// Holds the indices of all the vehicle's particles.
+	pub particles: Range<usize>,
+
+	/// Holds the chassis corners, indexed by their bits: front, top, right.
+	pub chassis: [usize; 8],
+
+	/// Holds the wheels: rear left, rear right, front left, front right.
+	pub wheels: [usize; 4],
+
+	/// Holds the rods bracing the chassis.
+	pub rods: Vec<ParticleRod>,
+
+	/// Holds the suspension springs joining the wheels to the chassis.
+	pub springs: Vec<ParticleSpringLink>,
+
+	/// Holds the angle of the front wheels in radians, positive to the right.
+	pub steering: Real,
+
+	/// Holds the force applied at each driven wheel at full throttle.
+	pub drive_force: Real,
+
+	/// Holds how strongly the wheels resist sliding sideways.
+	pub grip: Real,
+}
+
+impl Vehicle {
+	fn average(particles: &[Particle], indices: &[usize]) -> Vector3 {
+		let sum = indices
+			.iter()
+			.fold(Vector3::zero(), |acc, index| acc + particles[*index].position);
+		sum * real_from_count(indices.len()).recip()
+	}
+
+	/// Returns the unit direction the chassis is facing.
+	#[must_use]
+	pub fn forward(&self, particles: &[Particle]) -> Vector3 {
+		let [c0, c1, c2, c3, c4, c5, c6, c7] = self.chassis;
+		(Self::average(particles, &[c4, c5, c6, c7]) - Self::average(particles, &[c0, c1, c2, c3])).normalize()
+	}
+
+	/// Returns the unit direction to the right of the chassis.
+	#[must_use]
+	pub fn right(&self, particles: &[Particle]) -> Vector3 {
+		let [c0, c1, c2, c3, c4, c5, c6, c7] = self.chassis;
+		(Self::average(particles, &[c1, c3, c5, c7]) - Self::average(particles, &[c0, c2, c4, c6])).normalize()
+	}
+
+	/// Returns the unit direction out of the top of the chassis.
+	#[must_use]
+	pub fn up(&self, particles: &[Particle]) -> Vector3 {
+		self.right(particles).cross(&self.forward(particles)).normalize()
+	}
+
+	/// Returns the direction the given wheel is rolling in, accounting for steering.
+	#[must_use]
+	pub fn wheel_heading(&self, particles: &[Particle], wheel: usize) -> Vector3 {
+		let forward = self.forward(particles);
+		if wheel < 2 {
+			return forward;
+		}
+		let (sin, cos) = sin_cos(self.steering);
+		(forward * cos + self.right(particles) * sin).normalize()
+	}
+
+	/// Adds the suspension, drive, and grip forces to the vehicle's particles.
+	///
+	/// The throttle, from -1 for full reverse to 1 for full forward, drives the
+	/// rear wheels along the chassis. Every wheel then pushes back against any
+	/// velocity across its heading, which is what turns the car when steering.
+	pub fn apply_forces(&self, particles: &mut [Particle], throttle: Real) {
+		for spring in &self.springs {
+			spring.apply_force(particles);
+		}
+
+		let drive = self.drive_force * throttle.clamp(-1.0, 1.0);
+		for (wheel, index) in self.wheels.iter().enumerate() {
+			let heading = self.wheel_heading(particles, wheel);
+			let up = self.up(particles);
+			let across = heading.cross(&up).normalize();
+			let particle = &mut particles[*index];
+			if !particle.has_finite_mass() {
+				continue;
+			}
+			if wheel < 2 {
+				particle.add_force(heading * drive);
+			}
+			let sliding = across.dot(&particle.velocity);
+			particle.add_force(across * (-sliding * self.grip * particle.mass()));
+		}
+	}
+}
+
+impl ParticleContactGenerator for Vehicle {
+	fn add_contact(&self, particles: &[Particle], contacts: &mut Vec<ParticleContact>) -> usize {
+		self.rods.iter().map(|rod| rod.add_contact(particles, contacts)).sum()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::assert_equal;
+
+	use super::*;
+
+	#[test]
+	pub fn layout() {
+		let mut particles = Vec::new();
+		let vehicle = VehicleBuilder::new(Vector3::zero()).build(&mut particles);
+		assert_eq!(particles.len(), 12);
+		assert_eq!(vehicle.rods.len(), 28);
+		assert_eq!(vehicle.springs.len(), 12);
+		assert_eq!(vehicle.forward(&particles), Vector3::x_axis());
+		assert_eq!(vehicle.right(&particles), Vector3::z_axis());
+		assert_eq!(vehicle.up(&particles), Vector3::y_axis());
+		assert_eq!(particles[vehicle.wheels[3]].position, Vector3::new(2.0, -0.5, 1.0));
+		let mut contacts = Vec::new();
+		assert_eq!(vehicle.add_contact(&particles, &mut contacts), 0);
+	}
+
+	#[test]
+	pub fn throttle_drives_rear_wheels() {
+		let mut particles = Vec::new();
+		let vehicle = VehicleBuilder::new(Vector3::zero())
+			.drive_force(10.0)
+			.build(&mut particles);
+		vehicle.apply_forces(&mut particles, 0.5);
+		assert_eq!(
+			particles[vehicle.wheels[0]].force_accumulator,
+			Vector3::new(5.0, 0.0, 0.0)
+		);
+		assert_eq!(particles[vehicle.wheels[2]].force_accumulator, Vector3::zero());
+	}
+
+	#[test]
+	pub fn steering_turns_front_wheels() {
+		let mut particles = Vec::new();
+		let mut vehicle = VehicleBuilder::new(Vector3::zero()).build(&mut particles);
+		vehicle.steering = std::f32::consts::FRAC_PI_2;
+		let heading = vehicle.wheel_heading(&particles, 3);
+		assert_equal(heading.dot(&Vector3::z_axis()), 1.0);
+		assert_eq!(vehicle.wheel_heading(&particles, 0), Vector3::x_axis());
+	}
+}
@@ -0,0 +1,1793 @@
+use std::{hash::Hasher, time::Instant};
+
+use crate::{
+	body::{BodyKind, RigidBody, DEFAULT_SLEEP_EPSILON},
+	body_set::BodySet,
+	bounding::{Aabb, BoundingVolume, Ray},
+	broad_phase::{shape_batches, sort_by_shape, BroadPhase, BvhBroadPhase, CollisionGroups, ContactFilter},
+	bvh::PotentialContact,
+	ccd::{sweep_bodies_into, CcdImpact, CcdResolution},
+	clock::{BodyTransform, SimulationClock},
+	collide::{CollisionData, CollisionPlane, CollisionPrimitive},
+	collider::{collider_and_collider, collider_and_half_space, collider_and_mesh, Collider},
+	convex::{convex_cast, intersects, SupportMap},
+	debug_draw::{draw_bounds, draw_collider, draw_mesh, DebugDraw, DebugKind},
+	energy::{Energy, EnergyAudit},
+	events::{EventQueue, WorldEvent},
+	generators::{ForceGenerator, ForceRegistry},
+	island::Island,
+	joint::Joint,
+	manifold::ManifoldCache,
+	query::{intersections_with_aabb, PointQuery, QueryHit, RayHit},
+	shape::Shape,
+	solver::{SequentialImpulseSolver, SolverInput, SolverReport, SolverWorkspace},
+	state_hash::StateHasher,
+	stats::{PoolCapacities, StageTimings, StepStats},
+	trimesh::TriMesh,
+	vec::Vector3,
+	Real,
+};
+
+/// The gravity of a new [`PhysicsWorld`]: earth's, along the negative y axis.
+pub const DEFAULT_GRAVITY: Vector3 = Vector3::new(0.0, -9.81, 0.0);
+
+/// A callback run by a [`PhysicsWorld`] each step, given the bodies by
+/// handle and the duration of the step.
+///
+/// Closures taking the bodies and the duration are hooks, so control
+/// forces and telemetry can be added without splitting the step apart.
+/// A hook reads and changes only the bodies it names, through the columns
+/// or a copy of each whole body it takes out and puts back.
+pub trait StepHook {
+	fn run(&mut self, bodies: &mut BodySet, duration: Real);
+}
+
+impl<F: FnMut(&mut BodySet, Real)> StepHook for F {
+	fn run(&mut self, bodies: &mut BodySet, duration: Real) {
+		self(bodies, duration);
+	}
+}
+
+/// A complete rigid body simulation, running every stage of the pipeline
+/// in one call to [`Self::step`].
+///
+/// Each step applies the registered forces and gravity and integrates the
+/// bodies, sweeps the bodies with continuous collision, finds the pairs of
+/// bodies that may touch with the broad phase, finds their contacts and
+/// those with the scenery in the narrow phase, and solves the contacts and
+/// joints island by island before putting settled islands to sleep. As
+/// elsewhere in the crate, the solver runs after integration, so the
+/// velocities it leaves are those the bodies start the next step with.
+///
+/// Every stage is held in a public field, so any of them can be configured
+/// or swapped, such as the broad phase for a [`HashGrid`](crate::hash_grid::HashGrid).
+///
+/// A game drives the world with [`Self::update`], giving it the time
+/// since the last frame so that its [`SimulationClock`] takes fixed steps,
+/// and draws each body at [`Self::interpolated_transform`].
+///
+/// Once the step is solved, the contacts that started and ended, the
+/// bodies entering and leaving sensors, the joints that broke, and the
+/// bodies that fell asleep or woke are reported as [`WorldEvent`]s in
+/// [`Self::events`]. Broken joints are left in [`Self::joints`], no longer
+/// solved, so that the others keep their indices.
+///
+/// With the `serde` feature, the world can be saved and restored whole,
+/// bodies keeping their indices so that nothing naming them needs to be
+/// remapped. The force generators, hooks, and contact filter cannot be
+/// written, so they are left out along with the events and the results of
+/// the last step, and must be registered again on the restored world. Its
+/// broad phase is a new [`BvhBroadPhase`], filled on the first step.
+///
+/// Stepping is deterministic: the same world given the same steps ends in
+/// the same state, bit for bit. The broad phase gives its pairs in a fixed
+/// order whichever one is used, islands are found and solved in order of
+/// their lowest body, and results worked out in parallel with the `rayon`
+/// feature are merged in that same order. Hashing is only used to look
+/// things up, never to decide an order. To stay in step across machines,
+/// as lockstep multiplayer needs, enable the `determinism` feature, which
+/// computes the functions described in [`portable`](crate::portable) the
+/// same way everywhere.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+pub struct PhysicsWorld {
+	/// Holds the bodies, each named by its handle, as a column per part of
+	/// a body.
+	///
+	/// Integration, sleep checks, and moving the colliders stream through
+	/// the columns. The hooks, force generators, and sweeps for continuous
+	/// collision are given the set itself, and touch only the bodies they
+	/// name. The rows of removed bodies are given to the bodies added next.
+	pub bodies: BodySet,
+
+	/// Holds the colliders giving the bodies their shapes. A body may have
+	/// any number of them, and a body with none takes no part in collision.
+	///
+	/// The colliders of a removed body are detached and left where they
+	/// are, so that the others keep their indices, and their places are
+	/// given to the colliders added next.
+	pub colliders: Vec<Collider>,
+
+	/// Holds the sensors, which report the bodies overlapping them but do
+	/// not touch them. Each follows the body it is attached to, and those of
+	/// a removed body are left detached like its colliders.
+	pub sensors: Vec<Collider>,
+
+	/// Holds the planes bounding the world, each keeping the bodies on the side its normal faces.
+	pub planes: Vec<CollisionPlane>,
+
+	/// Holds the fixed triangle meshes making up the scenery.
+	pub meshes: Vec<TriMesh>,
+
+	/// Holds the joints, including those that broke. The joints of a
+	/// removed body are detached, joining nothing, and their places are
+	/// given to the joints added next.
+	pub joints: Vec<Joint>,
+
+	/// Holds the places left by the colliders, sensors, and joints of removed bodies.
+	vacant: VacantSlots,
+
+	/// Holds the force generators for the bodies in this world.
+	#[cfg_attr(feature = "serde", serde(skip))]
+	pub registry: ForceRegistry,
+
+	/// Holds the hooks run at the start of each step, once the forces left
+	/// from the last step are cleared and before any others are applied,
+	/// so that the forces they add are kept.
+	#[cfg_attr(feature = "serde", serde(skip))]
+	pub pre_step_hooks: Vec<Box<dyn StepHook>>,
+
+	/// Holds the hooks run at the end of each step, once it is solved.
+	#[cfg_attr(feature = "serde", serde(skip))]
+	pub post_step_hooks: Vec<Box<dyn StepHook>>,
+
+	/// Holds the acceleration applied to every dynamic body, on top of its own.
+	pub gravity: Vector3,
+
+	/// Holds the kinetic energy level below which the bodies are put to sleep.
+	pub sleep_epsilon: Real,
+
+	#[cfg_attr(feature = "serde", serde(skip, default = "default_broad_phase"))]
+	pub broad_phase: Box<dyn BroadPhase>,
+
+	/// Holds the filter rejecting pairs of bodies found by the broad phase, if any.
+	#[cfg_attr(feature = "serde", serde(skip))]
+	pub contact_filter: Option<Box<dyn ContactFilter>>,
+
+	/// Holds the contacts found each step, along with the most that may be
+	/// found and the friction and restitution given to them.
+	pub collision: CollisionData,
+
+	pub manifolds: ManifoldCache,
+
+	pub solver: SequentialImpulseSolver,
+
+	/// Holds how bodies with continuous collision that passed into another are handled.
+	pub ccd: CcdResolution,
+
+	/// Holds the clock turning frame times into fixed steps in [`Self::update`].
+	pub clock: SimulationClock,
+
+	/// Holds the events of the steps taken since it was last drained.
+	#[cfg_attr(feature = "serde", serde(skip))]
+	pub events: EventQueue,
+
+	/// Holds the audit recording the world's energy after each step, if any.
+	#[cfg_attr(feature = "serde", serde(skip))]
+	pub energy_audit: Option<EnergyAudit>,
+
+	/// Holds each sensor and body overlapping it after the last step, in order.
+	overlaps: Vec<[usize; 2]>,
+
+	/// Holds the transform of each body before the last step.
+	previous: Vec<BodyTransform>,
+
+	#[cfg_attr(feature = "serde", serde(skip))]
+	impacts: Vec<CcdImpact>,
+	#[cfg_attr(feature = "serde", serde(skip))]
+	stats: StepStats,
+	#[cfg_attr(feature = "serde", serde(skip))]
+	pools: StepPools,
+}
+
+/// The indices of the colliders, sensors, and joints of removed bodies,
+/// each list with the last vacated last, which are given out again first.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(crate = "serde"))]
+struct VacantSlots {
+	colliders: Vec<usize>,
+	sensors: Vec<usize>,
+	joints: Vec<usize>,
+}
+
+impl Clone for VacantSlots {
+	fn clone(&self) -> Self {
+		let mut slots = Self::default();
+		slots.clone_from(self);
+		slots
+	}
+
+	/// Copies each list into the storage already held, as a saved state is reused.
+	fn clone_from(&mut self, source: &Self) {
+		self.colliders.clone_from(&source.colliders);
+		self.sensors.clone_from(&source.sensors);
+		self.joints.clone_from(&source.joints);
+	}
+}
+
+/// Puts the item in the place last vacated among the items, if any is
+/// left, or after them, returning its index.
+fn fill<T>(items: &mut Vec<T>, vacant: &mut Vec<usize>, item: T) -> usize {
+	if let Some(index) = vacant.pop() {
+		items[index] = item;
+		index
+	} else {
+		items.push(item);
+		items.len() - 1
+	}
+}
+
+/// The storage a [`PhysicsWorld`] reuses from step to step, so that once it
+/// has grown to fit the scene, stepping need not allocate it again.
+#[derive(Debug, Default, Clone)]
+struct StepPools {
+	/// Holds whether each body was asleep before the step.
+	sleeping: Vec<bool>,
+
+	/// Holds whether each joint was broken before the step.
+	broken: Vec<bool>,
+
+	/// Holds the position of each body before it was integrated.
+	positions: Vec<Vector3>,
+
+	/// Holds the bounds of each body's colliders.
+	bounds: Vec<Option<Aabb>>,
+
+	/// Holds the indices of each body's colliders.
+	owned: Vec<Vec<usize>>,
+
+	/// Holds the pairs of bodies found by the broad phase.
+	pairs: Vec<PotentialContact>,
+
+	/// Holds the pairs of colliders of those bodies, by collider index,
+	/// sorted into runs of the same shape types.
+	collider_pairs: Vec<PotentialContact>,
+
+	/// Holds the pairs in contact before the step and after it, in order.
+	touching: [Vec<[Option<usize>; 2]>; 2],
+
+	/// Holds the sensor overlaps found by the step, before they replace the last.
+	overlaps: Vec<[usize; 2]>,
+
+	/// Holds the bodies the broad phase found in a sensor's bounds.
+	queried: Vec<usize>,
+
+	/// Holds the contacts found by each thread with the `rayon` feature,
+	/// before they are gathered in order.
+	contact_buffers: Vec<CollisionData>,
+
+	/// Holds the islands found by the last step, with the storage they were solved with.
+	solver: SolverWorkspace,
+}
+
+/// The state of a [`PhysicsWorld`] that changes as it steps, saved so that
+/// the world can be put back as it was, as rollback netcode does.
+///
+/// The manifolds are saved along with the bodies, so that the contacts are
+/// warm started as they were and the world carries on exactly as it did.
+/// The state of the force generators is not saved. A state is meant to be
+/// kept and saved into again and again, reusing its storage.
+#[derive(Debug, Default, Clone)]
+pub struct WorldState {
+	bodies: BodySet,
+	colliders: Vec<Collider>,
+	sensors: Vec<Collider>,
+	joints: Vec<Joint>,
+	vacant: VacantSlots,
+	manifolds: ManifoldCache,
+	overlaps: Vec<[usize; 2]>,
+	previous: Vec<BodyTransform>,
+	clock: SimulationClock,
+}
+
+fn default_broad_phase() -> Box<dyn BroadPhase> {
+	Box::new(BvhBroadPhase::default())
+}
+
+impl Default for PhysicsWorld {
+	fn default() -> Self {
+		Self {
+			bodies: BodySet::default(),
+			colliders: Vec::new(),
+			sensors: Vec::new(),
+			planes: Vec::new(),
+			meshes: Vec::new(),
+			joints: Vec::new(),
+			vacant: VacantSlots::default(),
+			registry: ForceRegistry::default(),
+			pre_step_hooks: Vec::new(),
+			post_step_hooks: Vec::new(),
+			gravity: DEFAULT_GRAVITY,
+			sleep_epsilon: DEFAULT_SLEEP_EPSILON,
+			broad_phase: default_broad_phase(),
+			contact_filter: None,
+			collision: CollisionData {
+				friction: 0.5,
+				restitution: 0.0,
+				..CollisionData::new(1024)
+			},
+			manifolds: ManifoldCache::default(),
+			solver: SequentialImpulseSolver::default(),
+			ccd: CcdResolution::default(),
+			clock: SimulationClock::default(),
+			events: EventQueue::default(),
+			energy_audit: None,
+			overlaps: Vec::new(),
+			previous: Vec::new(),
+			impacts: Vec::new(),
+			stats: StepStats::default(),
+			pools: StepPools::default(),
+		}
+	}
+}
+
+impl PhysicsWorld {
+	/// Creates an empty world with earth's gravity along the negative y axis,
+	/// whose contacts have some friction and do not bounce unless the
+	/// colliders' materials say otherwise.
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds the body to the world, returning its handle, which is that of
+	/// the body removed last if there is one not yet given out again.
+	pub fn add_body(&mut self, body: RigidBody) -> usize {
+		let handle = self.bodies.push(&body);
+		// A body taking a removed body's place is drawn where it starts
+		if let Some(previous) = self.previous.get_mut(handle) {
+			*previous = BodyTransform {
+				position: body.position,
+				orientation: body.orientation,
+			};
+		}
+		handle
+	}
+
+	/// Gives the body the given shape, centered on it, returning the index of its collider.
+	pub fn add_collider(&mut self, body: usize, shape: &Shape) -> usize {
+		let mut collider = Collider::from_shape(shape, CollisionPrimitive::new(body));
+		collider.calculate_internals(&self.bodies);
+		fill(&mut self.colliders, &mut self.vacant.colliders, collider)
+	}
+
+	/// Attaches a sensor of the given shape to the body, centered on it,
+	/// returning the index of the sensor.
+	pub fn add_sensor(&mut self, body: usize, shape: &Shape) -> usize {
+		let sensor = Collider::from_shape(shape, CollisionPrimitive::new(body));
+		fill(&mut self.sensors, &mut self.vacant.sensors, sensor)
+	}
+
+	/// Takes the body out of the simulation, dropping its colliders,
+	/// sensors, joints, force generators, and contacts.
+	///
+	/// Everything else keeps its index. The body's handle, and the indices
+	/// of its colliders, sensors, and joints, are given to those added next.
+	pub fn remove_body(&mut self, body: usize) {
+		for (index, collider) in self.colliders.iter_mut().enumerate() {
+			if collider.primitive().body == body {
+				collider.detach();
+				self.vacant.colliders.push(index);
+			}
+		}
+		for (index, sensor) in self.sensors.iter_mut().enumerate() {
+			if sensor.primitive().body == body {
+				sensor.detach();
+				self.vacant.sensors.push(index);
+			}
+		}
+		for (index, joint) in self.joints.iter_mut().enumerate() {
+			if joint.bodies().contains(&Some(body)) {
+				joint.detach();
+				self.vacant.joints.push(index);
+			}
+		}
+		let sensors = &self.sensors;
+		self.overlaps
+			.retain(|[sensor, overlapping]| *overlapping != body && sensors[*sensor].is_attached());
+		self.registry.remove(body);
+		self.manifolds.remove_body(body);
+		self.broad_phase.remove(body);
+		self.bodies.remove(body);
+	}
+
+	/// Adds a plane that the bodies are kept on the side its normal faces.
+	pub fn add_plane(&mut self, plane: CollisionPlane) {
+		self.planes.push(plane);
+	}
+
+	/// Adds the joint to the world, returning its index.
+	pub fn add_joint(&mut self, joint: Joint) -> usize {
+		fill(&mut self.joints, &mut self.vacant.joints, joint)
+	}
+
+	/// Registers the given force generator to apply to the given body.
+	pub fn add_force_generator(&mut self, body: usize, generator: impl ForceGenerator + 'static) {
+		self.registry.add(body, Box::new(generator));
+	}
+
+	/// Adds a hook run at the start of each step, before the forces are applied.
+	pub fn add_pre_step_hook(&mut self, hook: impl StepHook + 'static) {
+		self.pre_step_hooks.push(Box::new(hook));
+	}
+
+	/// Adds a hook run at the end of each step, once it is solved.
+	pub fn add_post_step_hook(&mut self, hook: impl StepHook + 'static) {
+		self.post_step_hooks.push(Box::new(hook));
+	}
+
+	/// Returns the transform of the body before and after the last step.
+	/// A body added since then is given its current transform for both.
+	#[must_use]
+	pub fn transforms(&self, body: usize) -> [BodyTransform; 2] {
+		let current = BodyTransform {
+			position: self.bodies.positions()[body],
+			orientation: self.bodies.orientations()[body],
+		};
+		[self.previous.get(body).copied().unwrap_or(current), current]
+	}
+
+	/// Returns where to draw the body, blended between its transforms
+	/// before and after the last step by the time the clock has saved up.
+	#[must_use]
+	pub fn interpolated_transform(&self, body: usize) -> BodyTransform {
+		let [previous, current] = self.transforms(body);
+		previous.interpolate(&current, self.clock.blend())
+	}
+
+	/// Returns the islands found by the last step.
+	#[must_use]
+	pub fn islands(&self) -> &[Island] {
+		self.pools.solver.islands()
+	}
+
+	/// Returns the impacts found by sweeping the bodies with continuous collision in the last step.
+	#[must_use]
+	pub fn impacts(&self) -> &[CcdImpact] {
+		&self.impacts
+	}
+
+	/// Returns the report of the solver from the last step.
+	#[must_use]
+	pub const fn report(&self) -> &SolverReport {
+		&self.stats.solver
+	}
+
+	/// Returns what the last step did and how long each of its stages took.
+	#[must_use]
+	pub const fn stats(&self) -> &StepStats {
+		&self.stats
+	}
+
+	/// Returns how much the storage reused from step to step can hold.
+	#[must_use]
+	pub fn pool_capacities(&self) -> PoolCapacities {
+		let pools = &self.pools;
+		PoolCapacities {
+			contacts: self.collision.contacts.capacity(),
+			pairs: pools.pairs.capacity().min(pools.collider_pairs.capacity()),
+			bodies: [
+				self.previous.capacity(),
+				pools.sleeping.capacity(),
+				pools.positions.capacity(),
+				pools.bounds.capacity(),
+				pools.owned.capacity(),
+			]
+			.into_iter()
+			.min()
+			.unwrap_or_default(),
+			touching: pools.touching[0].capacity().min(pools.touching[1].capacity()),
+			overlaps: [self.overlaps.capacity(), pools.overlaps.capacity(), pools.queried.capacity()]
+				.into_iter()
+				.min()
+				.unwrap_or_default(),
+			events: self.events.capacity(),
+			contact_buffers: pools
+				.contact_buffers
+				.iter()
+				.map(|buffer| buffer.contacts.capacity())
+				.sum::<usize>()
+				+ pools.contact_buffers.capacity(),
+			solver: pools.solver.capacity(),
+		}
+	}
+
+	/// Returns the energy of the dynamic bodies' motion and the energy stored
+	/// by gravity, their constant accelerations, and the registered forces.
+	///
+	/// Joints and contacts store no energy, so with them solved exactly and no
+	/// damping or friction, the energy stays the same as the world steps.
+	#[must_use]
+	pub fn energy(&self) -> Energy {
+		Energy {
+			kinetic: self.bodies.kinetic_energy(),
+			potential: self.registry.potential_energy(&self.bodies) + self.bodies.potential_energy(Some(self.gravity)),
+		}
+	}
+
+	/// Returns where the ray first hits a collider within the given distance,
+	/// of those the filter accepts whose bodies' groups interact with the
+	/// given groups. Sensors and the scenery are not hit.
+	///
+	/// The ray is tested only against the colliders of the bodies whose
+	/// bounds in the broad phase it enters, nearest first. The broad phase
+	/// holds the bounds as of the last step, so bodies added since are missed.
+	#[must_use]
+	pub fn cast_ray(
+		&self,
+		ray: &Ray,
+		max_distance: Real,
+		groups: CollisionGroups,
+		filter: impl Fn(&Collider) -> bool,
+	) -> Option<RayHit> {
+		let mut nearest: Option<RayHit> = None;
+		for candidate in self.broad_phase.ray_cast(ray, max_distance, groups) {
+			if nearest.is_some_and(|nearest| nearest.hit.distance < candidate.distance) {
+				break;
+			}
+			for (index, collider) in self.colliders.iter().enumerate() {
+				if collider.primitive().body != candidate.body || !filter(collider) {
+					continue;
+				}
+				let limit = nearest.map_or(max_distance, |nearest| nearest.hit.distance);
+				if let Some(hit) = convex_cast(&ray.origin, ray.direction, limit, collider) {
+					if nearest.is_none_or(|nearest| hit.distance < nearest.hit.distance) {
+						let QueryHit { body, user_data, .. } = self.query_hit(index);
+						nearest = Some(RayHit {
+							collider: index,
+							body,
+							user_data,
+							hit,
+						});
+					}
+				}
+			}
+		}
+		nearest
+	}
+
+	/// Returns the colliders that contain the point, of those the filter
+	/// accepts whose bodies' groups interact with the given groups, in order.
+	/// Like [`Self::cast_ray`], this goes through the broad phase as of the last step.
+	#[must_use]
+	pub fn query_point(
+		&self,
+		point: Vector3,
+		groups: CollisionGroups,
+		filter: impl Fn(&Collider) -> bool,
+	) -> Vec<QueryHit> {
+		let mut bodies = self.broad_phase.query(&Aabb::new(point, point), groups);
+		bodies.sort_unstable();
+		(0..self.colliders.len())
+			.filter(|index| {
+				let collider = &self.colliders[*index];
+				bodies.binary_search(&collider.primitive().body).is_ok()
+					&& filter(collider)
+					&& collider.contains_point(point)
+			})
+			.map(|index| self.query_hit(index))
+			.collect()
+	}
+
+	/// Returns the colliders that overlap the box, of those the filter
+	/// accepts whose bodies' groups interact with the given groups, in order.
+	/// Like [`Self::cast_ray`], this goes through the broad phase as of the last step.
+	#[must_use]
+	pub fn query_aabb(
+		&self,
+		bounds: &Aabb,
+		groups: CollisionGroups,
+		filter: impl Fn(&Collider) -> bool,
+	) -> Vec<QueryHit> {
+		intersections_with_aabb(self.broad_phase.as_ref(), &self.colliders, bounds, groups)
+			.into_iter()
+			.filter(|index| filter(&self.colliders[*index]))
+			.map(|index| self.query_hit(index))
+			.collect()
+	}
+
+	/// Returns the hit naming the given collider, its body, and the body's user data.
+	fn query_hit(&self, collider: usize) -> QueryHit {
+		let body = self.colliders[collider].primitive().body;
+		QueryHit {
+			collider,
+			body,
+			user_data: self.bodies.user_data(body),
+		}
+	}
+
+	/// Draws the colliders, sensors, and meshes, the bounds of the colliders,
+	/// the contact points and normals, the joints' anchors, and the bodies'
+	/// velocities with the given renderer, leaving out any kinds it does not draw.
+	pub fn debug_draw(&self, draw: &mut impl DebugDraw) {
+		for collider in self.colliders.iter().filter(|collider| collider.is_attached()) {
+			let kind = if self.bodies.flags()[collider.primitive().body].sleeping {
+				DebugKind::SleepingCollider
+			} else {
+				DebugKind::Collider
+			};
+			if draw.draws(kind) {
+				draw_collider(draw, collider, kind);
+			}
+			if draw.draws(DebugKind::Bounds) {
+				draw_bounds(draw, &collider.bounds(), DebugKind::Bounds);
+			}
+		}
+		if draw.draws(DebugKind::Sensor) {
+			for sensor in self.sensors.iter().filter(|sensor| sensor.is_attached()) {
+				draw_collider(draw, sensor, DebugKind::Sensor);
+			}
+		}
+		if draw.draws(DebugKind::Mesh) {
+			for mesh in &self.meshes {
+				draw_mesh(draw, mesh, DebugKind::Mesh);
+			}
+		}
+		if draw.draws(DebugKind::Contact) {
+			for point in self.manifolds.manifolds().flat_map(|manifold| &manifold.points) {
+				let contact = &point.contact;
+				draw.point(contact.contact_point, DebugKind::Contact);
+				let tip = contact.contact_point + contact.contact_normal * draw.normal_length();
+				draw.line(contact.contact_point, tip, DebugKind::Contact);
+			}
+		}
+		if draw.draws(DebugKind::Joint) {
+			let bodies: Vec<RigidBody> = self.bodies.iter().collect();
+			for joint in self.joints.iter().filter(|joint| !joint.is_broken()) {
+				let [first, second] = joint.world_anchors(&bodies);
+				draw.point(first, DebugKind::Joint);
+				draw.point(second, DebugKind::Joint);
+				draw.line(first, second, DebugKind::Joint);
+			}
+		}
+		if draw.draws(DebugKind::Velocity) {
+			let bodies = &self.bodies;
+			for body in (0..bodies.len()).filter(|body| bodies.flags()[*body].kind != BodyKind::Static) {
+				let position = bodies.positions()[body];
+				let tip = position + bodies.velocities()[body] * draw.velocity_scale();
+				draw.line(position, tip, DebugKind::Velocity);
+			}
+		}
+	}
+
+	/// Sets how much faster than real time [`Self::update`] runs the world,
+	/// so that one half runs it in slow motion.
+	pub const fn set_time_scale(&mut self, time_scale: Real) {
+		self.clock.set_time_scale(time_scale);
+	}
+
+	#[must_use]
+	pub const fn time_scale(&self) -> Real {
+		self.clock.time_scale()
+	}
+
+	/// Stops [`Self::update`] from taking steps until resumed. The bodies
+	/// are left as they are, awake or asleep, and are drawn where they were.
+	pub const fn pause(&mut self) {
+		self.clock.pause();
+	}
+
+	pub const fn resume(&mut self) {
+		self.clock.resume();
+	}
+
+	#[must_use]
+	pub const fn is_paused(&self) -> bool {
+		self.clock.is_paused()
+	}
+
+	/// Saves the state of the world into the given state, replacing what it held.
+	///
+	/// Once the state has grown to the size of the world, saving into it
+	/// again allocates nothing unless new pairs of bodies have come into contact.
+	pub fn save_state(&self, state: &mut WorldState) {
+		state.bodies.clone_from(&self.bodies);
+		state.colliders.clone_from(&self.colliders);
+		state.sensors.clone_from(&self.sensors);
+		state.joints.clone_from(&self.joints);
+		state.vacant.clone_from(&self.vacant);
+		state.manifolds.copy_from(&self.manifolds);
+		state.overlaps.clone_from(&self.overlaps);
+		state.previous.clone_from(&self.previous);
+		state.clock = self.clock;
+	}
+
+	/// Puts the world back in the given state, saved from it earlier.
+	///
+	/// The world's own storage is reused, so restoring allocates nothing
+	/// unless the state holds more bodies, or pairs of bodies in contact,
+	/// than the world has now. Bodies added since the state was saved are removed.
+	pub fn load_state(&mut self, state: &WorldState) {
+		for body in state.bodies.len()..self.bodies.len() {
+			self.broad_phase.remove(body);
+		}
+		self.bodies.clone_from(&state.bodies);
+		self.colliders.clone_from(&state.colliders);
+		self.sensors.clone_from(&state.sensors);
+		self.joints.clone_from(&state.joints);
+		self.vacant.clone_from(&state.vacant);
+		self.manifolds.copy_from(&state.manifolds);
+		self.overlaps.clone_from(&state.overlaps);
+		self.previous.clone_from(&state.previous);
+		self.clock = state.clock;
+	}
+
+	/// Returns a hash of the state of the world that changes as it steps,
+	/// the same on every platform, so that two runs can be checked for
+	/// having gone the same way, as lockstep games do to find desyncs.
+	///
+	/// The bodies' motion, the joints that broke, the contacts with the
+	/// impulses they are warm started with, the sensors' overlaps, and the
+	/// time saved up by the clock are hashed, each real number bit for bit.
+	#[must_use]
+	pub fn state_hash(&self) -> u64 {
+		let mut hasher = StateHasher::new();
+		let bodies = &self.bodies;
+		hasher.write_usize(bodies.len());
+		for body in 0..bodies.len() {
+			hasher.write_u8(bodies.contains(body).into());
+			hasher.write_vector(bodies.positions()[body]);
+			hasher.write_quaternion(bodies.orientations()[body]);
+			hasher.write_vector(bodies.velocities()[body]);
+			hasher.write_vector(bodies.angular_velocities()[body]);
+			hasher.write_real(bodies.motions()[body]);
+			hasher.write_u8(bodies.flags()[body].sleeping.into());
+		}
+		for joint in &self.joints {
+			hasher.write_u8(joint.is_broken().into());
+		}
+		hasher.write_usize(self.manifolds.len());
+		for manifold in self.manifolds.manifolds() {
+			for body in manifold.bodies {
+				hasher.write_body(body);
+			}
+			hasher.write_usize(manifold.points.len());
+			for point in &manifold.points {
+				hasher.write_vector(point.contact.contact_point);
+				hasher.write_vector(point.contact.contact_normal);
+				hasher.write_real(point.contact.penetration);
+				for local in point.local_points {
+					hasher.write_vector(local);
+				}
+				hasher.write_vector(point.impulse);
+			}
+		}
+		hasher.write_usize(self.overlaps.len());
+		for [sensor, body] in &self.overlaps {
+			hasher.write_usize(*sensor);
+			hasher.write_usize(*body);
+		}
+		hasher.write_real(self.clock.blend());
+		hasher.finish()
+	}
+
+	/// Adds the time since the last frame to the clock, and takes the
+	/// fixed steps now due, returning how many were taken.
+	pub fn update(&mut self, frame_time: Real) -> usize {
+		let steps = self.clock.advance(frame_time);
+		for _ in 0..steps {
+			self.step(self.clock.fixed_step);
+		}
+		steps
+	}
+
+	/// Advances the world by the given duration.
+	///
+	/// The lists the step works with are kept in the world and reused, so
+	/// once they have grown to fit the scene, as [`Self::pool_capacities`]
+	/// shows, stepping allocates nothing. That includes the solver's islands
+	/// and the storage they are solved with, the broad phase's search and
+	/// its queries for the sensors, the sweeps of bodies with continuous
+	/// collision, and the contacts found by each thread with the `rayon`
+	/// feature. Hooks, force generators and contact filters may still
+	/// allocate as they please, and with the `rayon` feature so may rayon
+	/// itself, now and then, when the world is stepped from a thread
+	/// outside its pool.
+	pub fn step(&mut self, duration: Real) {
+		if duration <= 0.0 {
+			return;
+		}
+		let start = Instant::now();
+		let mut last = start;
+		let mut lap = || {
+			let now = Instant::now();
+			let elapsed = now - last;
+			last = now;
+			elapsed
+		};
+		self.record_before_step();
+		self.integrate(duration);
+		let integrate = lap();
+		self.update_broad_phase();
+		let broad_phase = lap();
+		self.detect_contacts();
+		let narrow_phase = lap();
+		self.manifolds.update(&self.collision.contacts, &self.bodies);
+		let input = SolverInput {
+			manifolds: &mut self.manifolds,
+			joints: &mut self.joints,
+			bodies: &mut self.bodies,
+		};
+		let report = self.solver.solve_islands_into(input, duration, &mut self.pools.solver);
+		for island in self.pools.solver.islands() {
+			island.update_sleep(&mut self.bodies, self.sleep_epsilon);
+		}
+		let solve = lap();
+		self.update_sensors();
+		self.report_contacts();
+		self.report_broken_joints();
+		self.report_sleep();
+		let events = lap();
+		self.run_post_step_hooks(duration);
+		self.update_stats(
+			report,
+			StageTimings {
+				integrate,
+				broad_phase,
+				narrow_phase,
+				solve,
+				events,
+				total: start.elapsed(),
+			},
+		);
+		self.audit_energy();
+	}
+
+	/// Runs the post-step hooks on the bodies, once the step is solved.
+	fn run_post_step_hooks(&mut self, duration: Real) {
+		for hook in &mut self.post_step_hooks {
+			hook.run(&mut self.bodies, duration);
+		}
+	}
+
+	/// Records the transform of each body, whether it is asleep, which
+	/// joints are broken, and the pairs in contact before the step, to
+	/// compare with them after it.
+	fn record_before_step(&mut self) {
+		let transforms = self.bodies.positions().iter().zip(self.bodies.orientations());
+		self.previous.clear();
+		self.previous.extend(transforms.map(|(position, orientation)| BodyTransform {
+			position: *position,
+			orientation: *orientation,
+		}));
+		self.pools.sleeping.clear();
+		self.pools.sleeping.extend(self.bodies.flags().iter().map(|flags| flags.sleeping));
+		self.pools.broken.clear();
+		self.pools.broken.extend(self.joints.iter().map(Joint::is_broken));
+		// The manifolds are kept in order of their bodies
+		self.pools.touching[0].clear();
+		self.pools.touching[0].extend(self.manifolds.manifolds().map(|manifold| manifold.bodies));
+	}
+
+	/// Counts what the step did, given the solver's report and the timings of its stages.
+	fn update_stats(&mut self, solver: SolverReport, timings: StageTimings) {
+		let dynamic = self.bodies.flags().iter().filter(|flags| flags.kind == BodyKind::Dynamic);
+		let sleeping_bodies = dynamic.clone().filter(|flags| flags.sleeping).count();
+		self.stats = StepStats {
+			contacts: self.collision.contacts.len(),
+			manifold_points: self.manifolds.manifolds().map(|manifold| manifold.points.len()).sum(),
+			islands: self.pools.solver.islands().len(),
+			solver,
+			awake_bodies: dynamic.count() - sleeping_bodies,
+			sleeping_bodies,
+			timings,
+			..self.stats
+		};
+	}
+
+	/// Records the world's energy in its audit, if it has one.
+	fn audit_energy(&mut self) {
+		if let Some(mut audit) = self.energy_audit.take() {
+			audit.record(self.energy());
+			self.energy_audit = Some(audit);
+		}
+	}
+
+	/// Applies the forces, integrates the bodies, and moves their colliders
+	/// with them, sweeping those with continuous collision.
+	fn integrate(&mut self, duration: Real) {
+		self.bodies.clear_accumulators();
+		self.bodies.calculate_derived_data();
+		for hook in &mut self.pre_step_hooks {
+			hook.run(&mut self.bodies, duration);
+		}
+		self.registry.update_forces(&mut self.bodies, duration);
+		// Sleeping bodies are left asleep rather than woken by their own weight
+		self.bodies.apply_gravity(self.gravity);
+		self.pools.positions.clear();
+		self.pools.positions.extend_from_slice(self.bodies.positions());
+		self.bodies.integrate(duration, self.sleep_epsilon);
+		for collider in self.colliders.iter_mut().filter(|collider| collider.is_attached()) {
+			collider.calculate_internals(&self.bodies);
+		}
+		if self.bodies.flags().iter().any(|flags| flags.continuous_collision) {
+			let (positions, impacts) = (&self.pools.positions, &mut self.impacts);
+			sweep_bodies_into(&mut self.bodies, positions, &mut self.colliders, self.ccd, impacts);
+		} else {
+			self.impacts.clear();
+		}
+	}
+
+	/// Gives the broad phase the bounds of each body's colliders, removing
+	/// the bodies that have none.
+	fn update_broad_phase(&mut self) {
+		let bounds = &mut self.pools.bounds;
+		bounds.clear();
+		bounds.resize(self.bodies.len(), None);
+		for collider in self.colliders.iter().filter(|collider| collider.is_attached()) {
+			let body = &mut bounds[collider.primitive().body];
+			let collider = collider.bounds();
+			*body = Some(body.map_or(collider, |body| body.merge(&collider)));
+		}
+		for (body, bounds) in self.pools.bounds.iter().enumerate() {
+			match bounds {
+				Some(bounds) => self.broad_phase.update(body, *bounds),
+				None => {
+					self.broad_phase.remove(body);
+				},
+			}
+		}
+	}
+
+	/// Finds the contacts between the pairs of bodies given by the broad
+	/// phase, and between the bodies and the scenery.
+	///
+	/// Only pairs with an awake dynamic body are tested, as the contacts of
+	/// sleeping bodies are kept in the manifolds until they are woken.
+	fn detect_contacts(&mut self) {
+		self.collision.reset();
+		let owned = &mut self.pools.owned;
+		owned.truncate(self.bodies.len());
+		owned.iter_mut().for_each(Vec::clear);
+		owned.resize_with(self.bodies.len(), Vec::new);
+		for (index, collider) in self.colliders.iter().enumerate() {
+			if collider.is_attached() {
+				owned[collider.primitive().body].push(index);
+			}
+		}
+		let mut pairs = std::mem::take(&mut self.pools.pairs);
+		self.broad_phase.potential_contacts_into(&mut pairs);
+		if let Some(filter) = &self.contact_filter {
+			pairs.retain(|pair| filter.accepts(pair.bodies));
+		}
+		self.stats.broad_phase_pairs = pairs.len();
+		self.detect_pair_contacts(&pairs);
+		self.pools.pairs = pairs;
+		for collider in self.colliders.iter().filter(|collider| collider.is_attached()) {
+			if !self.bodies.is_active(collider.primitive().body) {
+				continue;
+			}
+			for plane in &self.planes {
+				collider_and_half_space(collider, plane, &mut self.collision);
+			}
+			for mesh in &self.meshes {
+				collider_and_mesh(collider, mesh, &mut self.collision);
+			}
+		}
+	}
+
+	/// Gathers the pairs of colliders of each pair of bodies from the broad
+	/// phase, unless neither body is awake, sorted into runs of the same
+	/// shape types so that each detector is run over a run of pairs at once.
+	fn pair_colliders(&mut self, pairs: &[PotentialContact]) {
+		let (bodies, colliders, owned) = (&self.bodies, &self.colliders, &self.pools.owned);
+		let collider_pairs = &mut self.pools.collider_pairs;
+		collider_pairs.clear();
+		for pair in pairs.iter().filter(|pair| pair.bodies.iter().any(|body| bodies.is_active(*body))) {
+			for first in &owned[pair.bodies[0]] {
+				collider_pairs.extend(owned[pair.bodies[1]].iter().map(|second| PotentialContact {
+					bodies: [*first, *second],
+				}));
+			}
+		}
+		sort_by_shape(collider_pairs, |collider| colliders[collider].type_index());
+	}
+
+	/// Finds the contacts between the colliders of each pair of bodies from
+	/// the broad phase, a run of pairs of the same shape types at a time.
+	///
+	/// With the `rayon` feature, the pairs are split between threads, each
+	/// adding the contacts it finds to a buffer of its own. The buffers are
+	/// joined in the order of their pairs and cut off at the limit, so the
+	/// contacts are the same, and in the same order, as when the pairs are
+	/// tested one after another.
+	fn detect_pair_contacts(&mut self, pairs: &[PotentialContact]) {
+		self.pair_colliders(pairs);
+		let (colliders, pairs) = (&self.colliders, &self.pools.collider_pairs);
+		let shape_type = |collider: usize| colliders[collider].type_index();
+		let detect = |data: &mut CollisionData, pair: &PotentialContact| {
+			collider_and_collider(&colliders[pair.bodies[0]], &colliders[pair.bodies[1]], data);
+		};
+		#[cfg(feature = "rayon")]
+		{
+			use rayon::prelude::*;
+			// Each thread takes a run of the pairs, so the contacts are gathered in order
+			let threads = rayon::current_num_threads().max(1);
+			let buffers = &mut self.pools.contact_buffers;
+			buffers.resize_with(threads, || CollisionData::new(0));
+			for buffer in buffers.iter_mut() {
+				let mut contacts = std::mem::take(&mut buffer.contacts);
+				contacts.clear();
+				*buffer = CollisionData {
+					contacts,
+					..self.collision
+				};
+			}
+			pairs
+				.par_chunks(pairs.len().div_ceil(threads).max(1))
+				.zip(buffers.par_iter_mut())
+				.for_each(|(pairs, data)| {
+					for (_, batch) in shape_batches(pairs, shape_type) {
+						for pair in batch {
+							detect(data, pair);
+						}
+					}
+				});
+			for buffer in buffers.iter() {
+				let room = self.collision.contacts_left().min(buffer.contacts.len());
+				self.collision.contacts.extend_from_slice(&buffer.contacts[..room]);
+			}
+		}
+		#[cfg(not(feature = "rayon"))]
+		for (_, batch) in shape_batches(pairs, shape_type) {
+			for pair in batch {
+				detect(&mut self.collision, pair);
+			}
+		}
+	}
+
+	/// Finds the bodies overlapping each sensor, reporting those that
+	/// entered or left it since the last step.
+	fn update_sensors(&mut self) {
+		let mut overlaps = std::mem::take(&mut self.pools.overlaps);
+		overlaps.clear();
+		let queried = &mut self.pools.queried;
+		for (index, sensor) in self.sensors.iter_mut().enumerate() {
+			if !sensor.is_attached() {
+				continue;
+			}
+			sensor.calculate_internals(&self.bodies);
+			let owner = sensor.primitive().body;
+			self.broad_phase.query_into(&sensor.bounds(), CollisionGroups::default(), queried);
+			for &body in queried.iter() {
+				let overlapping = self
+					.colliders
+					.iter()
+					.any(|collider| collider.primitive().body == body && intersects(sensor, collider));
+				if body != owner && overlapping {
+					overlaps.push([index, body]);
+				}
+			}
+		}
+		overlaps.sort_unstable();
+		for [sensor, body] in overlaps
+			.iter()
+			.filter(|pair| self.overlaps.binary_search(pair).is_err())
+		{
+			self.events.push(WorldEvent::SensorEntered {
+				sensor: *sensor,
+				body: *body,
+				user_data: [self.sensors[*sensor].primitive().user_data, self.bodies.user_data(*body)],
+			});
+		}
+		for [sensor, body] in self
+			.overlaps
+			.iter()
+			.filter(|pair| overlaps.binary_search(pair).is_err())
+		{
+			self.events.push(WorldEvent::SensorExited {
+				sensor: *sensor,
+				body: *body,
+				user_data: [self.sensors[*sensor].primitive().user_data, self.bodies.user_data(*body)],
+			});
+		}
+		self.pools.overlaps = std::mem::replace(&mut self.overlaps, overlaps);
+	}
+
+	/// Reports the pairs that have come into contact this step, and those
+	/// touching before the step that no longer are.
+	fn report_contacts(&mut self) {
+		let [before, mut after] = std::mem::take(&mut self.pools.touching);
+		after.clear();
+		after.extend(self.manifolds.manifolds().map(|manifold| manifold.bodies));
+		for manifold in self.manifolds.manifolds() {
+			if before.binary_search(&manifold.bodies).is_err() {
+				self.events.push(WorldEvent::ContactStarted {
+					bodies: manifold.bodies,
+					user_data: self.user_data(manifold.bodies),
+					impulse: manifold.points.iter().map(|point| point.impulse.x()).sum(),
+				});
+			}
+		}
+		for bodies in before.iter().filter(|bodies| after.binary_search(bodies).is_err()) {
+			self.events.push(WorldEvent::ContactEnded {
+				bodies: *bodies,
+				user_data: self.user_data(*bodies),
+			});
+		}
+		self.pools.touching = [before, after];
+	}
+
+	/// Reports the joints that broke this step, by their indices.
+	fn report_broken_joints(&mut self) {
+		for (index, (joint, before)) in self.joints.iter().zip(&self.pools.broken).enumerate() {
+			if joint.is_broken() && !before {
+				let bodies = joint.bodies();
+				self.events.push(WorldEvent::JointBroken {
+					joint: index,
+					bodies,
+					user_data: self.user_data(bodies),
+				});
+			}
+		}
+	}
+
+	/// Reports the bodies that fell asleep or woke this step.
+	fn report_sleep(&mut self) {
+		for (body, (before, after)) in self.pools.sleeping.iter().zip(self.bodies.flags()).enumerate() {
+			let user_data = self.bodies.user_data(body);
+			match (*before, after.sleeping) {
+				(false, true) => self.events.push(WorldEvent::BodySlept { body, user_data }),
+				(true, false) => self.events.push(WorldEvent::BodyWoke { body, user_data }),
+				_ => {},
+			}
+		}
+	}
+
+	/// Returns the user data of the bodies, or zero for the scenery.
+	fn user_data(&self, bodies: [Option<usize>; 2]) -> [u64; 2] {
+		bodies.map(|body| body.map_or(0, |body| self.bodies.user_data(body)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+	use crate::{
+		body::RigidBodyBuilder,
+		hash_grid::HashGrid,
+		joint::{DistanceJoint, JointKind},
+		quaternion::Quaternion,
+	};
+
+	use super::*;
+
+	const STEP: Real = 1.0 / 60.0;
+
+	fn ball(world: &mut PhysicsWorld, position: Vector3) -> usize {
+		let shape = Shape::Sphere { radius: 0.5 };
+		let body = world.add_body(RigidBodyBuilder::new(shape).position(position).build());
+		world.add_collider(body, &shape);
+		body
+	}
+
+	#[test]
+	pub fn boxes_fall_onto_the_ground_and_sleep() {
+		let mut world = PhysicsWorld::new();
+		world.add_plane(CollisionPlane::new(Vector3::y_axis(), 0.0));
+		for height in [0.5, 1.5, 2.5] {
+			let body = world.add_body(
+				RigidBodyBuilder::new(Shape::default())
+					.position(Vector3::new(0.0, height, 0.0))
+					.build(),
+			);
+			world.add_collider(body, &Shape::default());
+		}
+		for _ in 0..300 {
+			world.step(STEP);
+		}
+		for (body, height) in world.bodies.iter().zip([0.5, 1.5, 2.5]) {
+			assert!((body.position.y() - height).abs() < 0.05, "{:?}", body.position);
+			assert!(body.sleeping);
+		}
+		assert_eq!(world.islands().len(), 1);
+
+		let stats = world.stats();
+		assert_eq!((stats.broad_phase_pairs, stats.contacts), (2, 0));
+		assert_eq!(stats.manifold_points, 12);
+		assert_eq!((stats.awake_bodies, stats.sleeping_bodies), (0, 3));
+		assert!(stats.timings.total >= stats.timings.solve);
+	}
+
+	#[test]
+	pub fn each_world_has_its_own_sleep_epsilon() {
+		let mut worlds = [PhysicsWorld::new(), PhysicsWorld::new()];
+		worlds[1].sleep_epsilon = 0.0;
+		for world in &mut worlds {
+			world.add_plane(CollisionPlane::new(Vector3::y_axis(), 0.0));
+			ball(world, Vector3::new(0.0, 0.5, 0.0));
+			for _ in 0..300 {
+				world.step(STEP);
+			}
+		}
+
+		// Nothing settles below no motion at all, so the second ball stays awake
+		assert!(worlds[0].bodies.get(0).sleeping);
+		assert!(!worlds[1].bodies.get(0).sleeping);
+	}
+
+	#[test]
+	pub fn bodies_collide_through_the_broad_phase() {
+		let mut world = PhysicsWorld::new();
+		world.gravity = Vector3::zero();
+		world.collision.restitution = 1.0;
+		let left = ball(&mut world, Vector3::new(-2.0, 0.0, 0.0));
+		let right = ball(&mut world, Vector3::new(2.0, 0.0, 0.0));
+		world.bodies.update(left, |body| body.velocity = Vector3::new(2.0, 0.0, 0.0));
+		world.bodies.update(right, |body| body.velocity = Vector3::new(-2.0, 0.0, 0.0));
+		for _ in 0..120 {
+			world.step(STEP);
+		}
+		assert!((world.bodies.get(left).velocity.x() + 2.0).abs() < 0.1);
+		assert!((world.bodies.get(right).velocity.x() - 2.0).abs() < 0.1);
+		assert!(world.bodies.get(right).position.x() - world.bodies.get(left).position.x() > 1.0);
+	}
+
+	#[test]
+	pub fn filtered_pairs_pass_through_each_other() {
+		let mut world = PhysicsWorld::new();
+		world.gravity = Vector3::zero();
+		let left = ball(&mut world, Vector3::new(-2.0, 0.0, 0.0));
+		ball(&mut world, Vector3::new(2.0, 0.0, 0.0));
+		world.bodies.update(left, |body| body.velocity = Vector3::new(4.0, 0.0, 0.0));
+		world.contact_filter = Some(Box::new(|_: [usize; 2]| false));
+		for _ in 0..120 {
+			world.step(STEP);
+		}
+		assert!(world.bodies.get(left).position.x() > 5.0);
+		assert!(world.manifolds.is_empty());
+	}
+
+	#[test]
+	pub fn steps_report_their_events() {
+		let mut world = PhysicsWorld::new();
+		world.add_plane(CollisionPlane::new(Vector3::y_axis(), 0.0));
+		let post = world.add_body(
+			RigidBodyBuilder::new(Shape::default())
+				.kind(BodyKind::Static)
+				.position(Vector3::new(0.0, 3.0, 0.0))
+				.build(),
+		);
+		let sensor = world.add_sensor(post, &Shape::Sphere { radius: 1.0 });
+		world.sensors[sensor].primitive_mut().user_data = 3;
+		let falling = ball(&mut world, Vector3::new(0.0, 6.0, 0.0));
+		world.bodies.update(falling, |body| body.user_data = 7);
+		let receiver = world.events.channel();
+		for _ in 0..300 {
+			world.step(STEP);
+		}
+		let events: Vec<WorldEvent> = receiver.try_iter().collect();
+		assert_eq!(
+			events[..2],
+			[
+				WorldEvent::SensorEntered {
+					sensor,
+					body: falling,
+					user_data: [3, 7]
+				},
+				WorldEvent::SensorExited {
+					sensor,
+					body: falling,
+					user_data: [3, 7]
+				},
+			]
+		);
+		assert!(matches!(
+			events[2],
+			WorldEvent::ContactStarted { bodies: [Some(body), None], user_data: [7, 0], .. } if body == falling
+		));
+		assert!(matches!(
+			events.last(),
+			Some(WorldEvent::BodySlept { body, user_data: 7 }) if *body == falling
+		));
+		assert!(world.events.is_empty());
+
+		// Lifting the ball wakes it and parts it from the ground
+		world.bodies.update(falling, |body| body.position = Vector3::new(0.0, 10.0, 0.0));
+		world.bodies.set_awake(falling, true);
+		world.step(STEP);
+		world.step(STEP);
+		assert!(matches!(
+			receiver.try_iter().collect::<Vec<_>>()[..],
+			[WorldEvent::ContactEnded { bodies: [Some(body), None], user_data: [7, 0] }] if body == falling
+		));
+	}
+
+	#[test]
+	pub fn hooks_run_around_each_step() {
+		let mut world = PhysicsWorld::new();
+		let hovering = ball(&mut world, Vector3::new(0.0, 5.0, 0.0));
+		let gravity = world.gravity;
+		world.add_pre_step_hook(move |bodies: &mut BodySet, _| {
+			let weight = gravity * bodies.inverse_masses()[hovering].recip();
+			bodies.add_force(hovering, -weight);
+		});
+		let heights = Rc::new(RefCell::new(Vec::new()));
+		let log = Rc::clone(&heights);
+		world.add_post_step_hook(move |bodies: &mut BodySet, _| {
+			log.borrow_mut().push(bodies.positions()[hovering].y());
+		});
+		for _ in 0..60 {
+			world.step(STEP);
+		}
+		assert_eq!(heights.borrow().len(), 60);
+		assert!(heights.borrow().iter().all(|height| (height - 5.0).abs() < 1e-4));
+	}
+
+	#[test]
+	pub fn frames_are_stepped_fixed_and_drawn_between_steps() {
+		let mut world = PhysicsWorld::new();
+		world.gravity = Vector3::zero();
+		world.clock = SimulationClock::new(0.1);
+		let moving = ball(&mut world, Vector3::zero());
+		world.bodies.update(moving, |body| {
+			body.velocity = Vector3::x_axis();
+			body.linear_damping = 1.0;
+		});
+		assert_eq!(world.update(0.25), 2);
+		let [previous, current] = world.transforms(moving);
+		assert!((previous.position.x() - 0.1).abs() < 1e-4);
+		assert!((current.position.x() - 0.2).abs() < 1e-4);
+		assert!((world.interpolated_transform(moving).position.x() - 0.15).abs() < 1e-4);
+	}
+
+	#[test]
+	pub fn paused_worlds_stand_still() {
+		let mut world = PhysicsWorld::new();
+		let falling = ball(&mut world, Vector3::new(0.0, 5.0, 0.0));
+		world.update(0.05);
+		let drawn = world.interpolated_transform(falling);
+		world.pause();
+		assert_eq!(world.update(1.0), 0);
+		assert_eq!(world.interpolated_transform(falling), drawn);
+		assert!(!world.bodies.get(falling).sleeping);
+
+		// Slow motion takes the same steps, just fewer of them each frame
+		world.resume();
+		world.set_time_scale(0.25);
+		assert_eq!(world.update(4.0 / 60.0), 1);
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	pub fn saved_worlds_carry_on_as_the_original() {
+		let mut world = PhysicsWorld::new();
+		world.sleep_epsilon = 0.5;
+		world.add_plane(CollisionPlane::new(Vector3::y_axis(), 0.0));
+		for height in [0.6, 1.8] {
+			let body = world.add_body(
+				RigidBodyBuilder::new(Shape::default())
+					.position(Vector3::new(0.0, height, 0.0))
+					.build(),
+			);
+			world.add_collider(body, &Shape::default());
+		}
+		world.meshes.push(TriMesh::new(
+			vec![
+				Vector3::new(5.0, 0.0, 0.0),
+				Vector3::new(6.0, 0.0, 0.0),
+				Vector3::new(5.0, 0.0, -1.0),
+			],
+			vec![[0, 1, 2]],
+		));
+		for _ in 0..30 {
+			world.step(STEP);
+		}
+		let saved = serde_json::to_string(&world).expect("the world is written");
+		let mut restored: PhysicsWorld = serde_json::from_str(&saved).expect("the world is read");
+		assert_eq!(restored.manifolds.len(), world.manifolds.len());
+		crate::assert_equal(restored.sleep_epsilon, 0.5);
+		for _ in 0..30 {
+			world.step(STEP);
+			restored.step(STEP);
+		}
+		for (body, restored) in world.bodies.iter().zip(restored.bodies.iter()) {
+			assert_eq!(body.position, restored.position);
+			assert_eq!(body.orientation, restored.orientation);
+		}
+	}
+
+	#[test]
+	pub fn restored_states_replay_exactly() {
+		let mut world = PhysicsWorld::new();
+		world.add_plane(CollisionPlane::new(Vector3::y_axis(), 0.0));
+		for x in [0.0, 0.8] {
+			let body = world.add_body(
+				RigidBodyBuilder::new(Shape::default())
+					.position(Vector3::new(x, 1.0 + x, 0.0))
+					.orientation(Quaternion::from_axis_angle(Vector3::z_axis(), x))
+					.build(),
+			);
+			world.add_collider(body, &Shape::default());
+		}
+		for _ in 0..30 {
+			world.step(STEP);
+		}
+		let mut state = WorldState::default();
+		world.save_state(&mut state);
+		let run = |world: &mut PhysicsWorld| {
+			(0..30)
+				.map(|_| {
+					world.step(STEP);
+					world.bodies.iter().map(|body| body.position).collect::<Vec<_>>()
+				})
+				.collect::<Vec<_>>()
+		};
+		let first = run(&mut world);
+
+		// A body added after saving is removed along with everything else that changed
+		ball(&mut world, Vector3::new(0.0, 5.0, 0.0));
+		world.step(STEP);
+		world.load_state(&state);
+		assert_eq!(world.bodies.len(), 2);
+		assert_eq!(run(&mut world), first);
+	}
+
+	#[test]
+	pub fn hashes_follow_the_state() {
+		let mut world = PhysicsWorld::new();
+		world.add_plane(CollisionPlane::new(Vector3::y_axis(), 0.0));
+		ball(&mut world, Vector3::new(0.0, 0.6, 0.0));
+		ball(&mut world, Vector3::new(0.2, 1.8, 0.0));
+		for _ in 0..20 {
+			world.step(STEP);
+		}
+		let mut state = WorldState::default();
+		world.save_state(&mut state);
+		let hash = world.state_hash();
+		world.step(STEP);
+		assert_ne!(world.state_hash(), hash);
+		world.load_state(&state);
+		assert_eq!(world.state_hash(), hash);
+
+		// A change to a single bit shows
+		world.bodies.update(1, |body| body.position[0] = Real::from_bits(body.position[0].to_bits() ^ 1));
+		assert_ne!(world.state_hash(), hash);
+	}
+
+	#[test]
+	pub fn broad_phases_give_the_same_results() {
+		let worlds = [PhysicsWorld::new(), PhysicsWorld::new()].map(|mut world| {
+			world.add_plane(CollisionPlane::new(Vector3::y_axis(), 0.0));
+			for index in 0..6_u8 {
+				let offset = Real::from(index);
+				ball(
+					&mut world,
+					Vector3::new(offset.mul_add(0.3, -0.8), offset.mul_add(1.1, 0.5), 0.0),
+				);
+			}
+			world
+		});
+		let [mut first, mut second] = worlds;
+		second.broad_phase = Box::new(HashGrid::new(1.0));
+		for _ in 0..120 {
+			first.step(STEP);
+			second.step(STEP);
+		}
+		for (first, second) in first.bodies.iter().zip(second.bodies.iter()) {
+			assert_eq!(first.position, second.position);
+			assert_eq!(first.orientation, second.orientation);
+		}
+	}
+
+	#[test]
+	pub fn contacts_are_found_in_order_of_their_pairs() {
+		// Enough touching pairs for the `rayon` feature to split them between threads
+		let row = |max_contacts| {
+			let mut world = PhysicsWorld::new();
+			world.collision.max_contacts = max_contacts;
+			for index in 0..64_u8 {
+				ball(&mut world, Vector3::new(Real::from(index) * 0.9, 5.0, 0.0));
+			}
+			world.step(STEP);
+			world.collision.contacts
+		};
+		let (all, limited) = (row(1024), row(20));
+		assert_eq!(all.len(), 63);
+		assert_eq!(limited.len(), 20);
+		assert!(all.windows(2).all(|pair| pair[0].bodies < pair[1].bodies));
+		for (contact, expected) in limited.iter().zip(&all) {
+			assert_eq!(contact.bodies, expected.bodies);
+			assert_eq!(contact.contact_point, expected.contact_point);
+		}
+	}
+
+	#[test]
+	pub fn step_buffers_stop_growing_once_warm() {
+		let mut world = PhysicsWorld::new();
+		world.add_plane(CollisionPlane::new(Vector3::y_axis(), 0.0));
+		for level in 0..4_u8 {
+			ball(&mut world, Vector3::new(Real::from(level) * 0.1, Real::from(level).mul_add(1.1, 0.5), 0.0));
+		}
+		let sensor = world.add_body(RigidBodyBuilder::new(Shape::default()).kind(BodyKind::Static).build());
+		world.add_sensor(sensor, &Shape::Cuboid { half_size: Vector3::new(2.0, 1.0, 2.0) });
+		// A pendulum, so that joint rows are solved too
+		let bob = ball(&mut world, Vector3::new(8.0, 5.0, 0.0));
+		let anchors = [Vector3::new(6.0, 5.0, 0.0), Vector3::new(8.0, 5.0, 0.0)];
+		let joint = DistanceJoint::new([None, Some(bob)], &world.bodies.iter().collect::<Vec<_>>(), anchors);
+		world.add_joint(Joint::new(JointKind::Distance(joint)));
+		let step = |world: &mut PhysicsWorld| {
+			world.step(STEP);
+			world.events.drain().for_each(drop);
+		};
+		for _ in 0..240 {
+			step(&mut world);
+		}
+		let warm = world.pool_capacities();
+		assert!(warm.contacts > 0 && warm.pairs > 0 && warm.bodies >= world.bodies.len());
+		assert!(warm.touching > 0 && warm.overlaps > 0 && warm.events > 0 && warm.solver > 0);
+		assert!(cfg!(not(feature = "rayon")) || warm.contact_buffers > 0);
+		for _ in 0..120 {
+			step(&mut world);
+			assert_eq!(world.pool_capacities(), warm);
+		}
+	}
+
+	#[test]
+	pub fn debug_drawing_shows_each_kind() {
+		#[derive(Default)]
+		struct Counter {
+			lines: BTreeMap<DebugKind, usize>,
+			points: BTreeMap<DebugKind, usize>,
+		}
+
+		impl DebugDraw for Counter {
+			fn line(&mut self, _start: Vector3, _end: Vector3, kind: DebugKind) {
+				*self.lines.entry(kind).or_default() += 1;
+			}
+
+			fn point(&mut self, _position: Vector3, kind: DebugKind) {
+				*self.points.entry(kind).or_default() += 1;
+			}
+
+			fn draws(&self, kind: DebugKind) -> bool {
+				kind != DebugKind::Bounds
+			}
+		}
+
+		let mut world = PhysicsWorld::new();
+		world.add_plane(CollisionPlane::new(Vector3::y_axis(), 0.0));
+		let resting = world.add_body(
+			RigidBodyBuilder::new(Shape::default())
+				.position(Vector3::new(0.0, 0.5, 0.0))
+				.build(),
+		);
+		world.add_collider(resting, &Shape::default());
+		let bob = ball(&mut world, Vector3::new(3.0, 5.0, 0.0));
+		let joint = DistanceJoint::new(
+			[None, Some(bob)],
+			&world.bodies.iter().collect::<Vec<_>>(),
+			[Vector3::new(3.0, 7.0, 0.0), Vector3::new(3.0, 5.0, 0.0)],
+		);
+		world.add_joint(Joint::new(JointKind::Distance(joint)));
+		world.step(STEP);
+
+		let mut counter = Counter::default();
+		world.debug_draw(&mut counter);
+		assert_eq!(counter.lines[&DebugKind::Collider], 12 + 72);
+		assert_eq!(counter.lines[&DebugKind::Contact], 4);
+		assert_eq!(counter.points[&DebugKind::Contact], 4);
+		assert_eq!(counter.points[&DebugKind::Joint], 2);
+		assert_eq!(counter.lines[&DebugKind::Velocity], 2);
+		assert!(!counter.lines.contains_key(&DebugKind::Bounds));
+	}
+
+	#[test]
+	pub fn joints_hold_bodies_together() {
+		let mut world = PhysicsWorld::new();
+		let anchor = world.add_body(
+			RigidBodyBuilder::new(Shape::default())
+				.kind(BodyKind::Static)
+				.position(Vector3::new(0.0, 5.0, 0.0))
+				.build(),
+		);
+		let bob = ball(&mut world, Vector3::new(2.0, 5.0, 0.0));
+		let joint = DistanceJoint::new(
+			[Some(anchor), Some(bob)],
+			&world.bodies.iter().collect::<Vec<_>>(),
+			[Vector3::new(0.0, 5.0, 0.0), Vector3::new(2.0, 5.0, 0.0)],
+		);
+		world.add_joint(Joint::new(JointKind::Distance(joint)));
+		for _ in 0..120 {
+			world.step(STEP);
+			let length = (world.bodies.get(bob).position - world.bodies.get(anchor).position).magnitude();
+			assert!((length - 2.0).abs() < 0.05, "{length}");
+		}
+	}
+
+	#[test]
+	pub fn overloaded_joints_break_and_are_reported() {
+		let mut world = PhysicsWorld::new();
+		let mut hang = |x: Real, strength: Real| {
+			let bob = ball(&mut world, Vector3::new(x, 5.0, 0.0));
+			world.bodies.update(bob, |body| body.user_data = 9);
+			let anchors = [Vector3::new(x, 7.0, 0.0), Vector3::new(x, 5.0, 0.0)];
+			let joint = DistanceJoint::new([None, Some(bob)], &world.bodies.iter().collect::<Vec<_>>(), anchors);
+			world.add_joint(Joint::new(JointKind::Distance(joint)).with_strength(strength, 10.0));
+			bob
+		};
+		// Each ball weighs about 5, which only the second joint bears
+		let (weak, strong) = (hang(0.0, 1.0), hang(3.0, 100.0));
+		let mut broken = Vec::new();
+		for _ in 0..30 {
+			world.step(STEP);
+			broken.extend(
+				world
+					.events
+					.drain()
+					.filter(|event| matches!(event, WorldEvent::JointBroken { .. })),
+			);
+		}
+		assert_eq!(
+			broken,
+			[WorldEvent::JointBroken {
+				joint: 0,
+				bodies: [None, Some(weak)],
+				user_data: [0, 9],
+			}]
+		);
+		// The broken joint is left in place, so the other keeps its index
+		assert_eq!(world.joints.len(), 2);
+		assert!(world.joints[0].is_broken());
+		assert!(!world.joints[1].is_broken());
+		assert_eq!(world.joints[1].bodies(), [None, Some(strong)]);
+		assert!(world.bodies.get(weak).position.y() < 4.0);
+		assert!((world.bodies.get(strong).position.y() - 5.0).abs() < 0.05);
+	}
+
+	#[test]
+	pub fn removed_bodies_give_their_places_to_those_added_next() {
+		let mut world = PhysicsWorld::new();
+		world.add_plane(CollisionPlane::new(Vector3::y_axis(), 0.0));
+		let hang = |world: &mut PhysicsWorld, body: usize| {
+			let position = world.bodies.positions()[body];
+			let anchors = [position + Vector3::new(0.0, 2.0, 0.0), position];
+			let joint = DistanceJoint::new([None, Some(body)], &world.bodies.iter().collect::<Vec<_>>(), anchors);
+			world.add_joint(Joint::new(JointKind::Distance(joint)))
+		};
+		let first = ball(&mut world, Vector3::new(0.0, 3.0, 0.0));
+		let removed = ball(&mut world, Vector3::new(3.0, 3.0, 0.0));
+		let last = ball(&mut world, Vector3::new(6.0, 3.0, 0.0));
+		let sensor = world.add_sensor(removed, &Shape::Sphere { radius: 1.0 });
+		let joints = [hang(&mut world, first), hang(&mut world, removed), hang(&mut world, last)];
+		for _ in 0..10 {
+			world.step(STEP);
+		}
+		world.remove_body(removed);
+		for _ in 0..10 {
+			world.step(STEP);
+		}
+
+		// Everything else keeps its index
+		assert_eq!(world.colliders.len(), 3);
+		assert_eq!(world.colliders[2].primitive().body, last);
+		assert!(!world.colliders[1].is_attached());
+		assert!(!world.sensors[sensor].is_attached());
+		assert_eq!(world.joints[joints[2]].bodies(), [None, Some(last)]);
+		assert_eq!(world.joints[joints[1]].bodies(), [None; 2]);
+
+		// And the places of the removed body's parts are given out again
+		let added = ball(&mut world, Vector3::new(3.0, 3.0, 0.0));
+		assert_eq!(added, removed);
+		assert_eq!(world.colliders.len(), 3);
+		assert_eq!(world.colliders[1].primitive().body, added);
+		assert_eq!(world.add_sensor(added, &Shape::Sphere { radius: 1.0 }), sensor);
+		assert_eq!(hang(&mut world, added), joints[1]);
+		assert_eq!(world.add_body(RigidBodyBuilder::new(Shape::default()).build()), 3);
+		for _ in 0..30 {
+			world.step(STEP);
+		}
+		for body in [first, added, last] {
+			assert!((world.bodies.positions()[body].y() - 3.0).abs() < 0.05);
+		}
+	}
+
+	#[test]
+	pub fn bodies_feel_their_own_gravity() {
+		let mut world = PhysicsWorld::new();
+		world.gravity = Vector3::new(0.0, -10.0, 0.0);
+		let falling = ball(&mut world, Vector3::zero());
+		let balloon = ball(&mut world, Vector3::new(2.0, 0.0, 0.0));
+		let floating = ball(&mut world, Vector3::new(4.0, 0.0, 0.0));
+		let walking = ball(&mut world, Vector3::new(6.0, 0.0, 0.0));
+		world.bodies.update(balloon, |body| body.gravity_scale = -0.5);
+		world.bodies.update(floating, |body| body.gravity_scale = 0.0);
+		world.bodies.update(walking, |body| body.gravity_override = Some(Vector3::new(10.0, 0.0, 0.0)));
+		for body in 0..world.bodies.len() {
+			world.bodies.update(body, |body| body.linear_damping = 1.0);
+		}
+		world.step(0.5);
+		let velocity = |body: usize| world.bodies.get(body).velocity;
+		assert!((velocity(falling) - Vector3::new(0.0, -5.0, 0.0)).magnitude() < 1e-4);
+		assert!((velocity(balloon) - Vector3::new(0.0, 2.5, 0.0)).magnitude() < 1e-4);
+		assert!(velocity(floating).magnitude() < 1e-4);
+		assert!((velocity(walking) - Vector3::new(5.0, 0.0, 0.0)).magnitude() < 1e-4);
+	}
+
+	#[test]
+	pub fn queries_go_through_the_broad_phase() {
+		let mut world = PhysicsWorld::new();
+		world.gravity = Vector3::zero();
+		let bodies: Vec<usize> = [0.0, 3.0, 6.0]
+			.into_iter()
+			.map(|x| ball(&mut world, Vector3::new(x, 0.0, 0.0)))
+			.collect();
+		for (body, user_data) in bodies.iter().zip([10, 11, 12]) {
+			world.bodies.update(*body, |body| body.user_data = user_data);
+		}
+		let ghosts = CollisionGroups::new(2, u32::MAX);
+		world.broad_phase.set_groups(bodies[1], ghosts);
+		world.step(STEP);
+
+		// The ray passes through the first ball, and skips the ghost unless asked for it
+		let ray = Ray::new(Vector3::new(-5.0, 0.0, 0.0), Vector3::x_axis());
+		let solid = CollisionGroups::new(1, 1);
+		let everything = |_: &Collider| true;
+		let first = world.cast_ray(&ray, 20.0, solid, everything).expect("the ray hits a ball");
+		assert_eq!((first.body, first.user_data), (bodies[0], 10));
+		assert!((first.hit.distance - 4.5).abs() < 1e-2);
+		let past_first = |collider: &Collider| collider.primitive().body != bodies[0];
+		let hit = world.cast_ray(&ray, 20.0, solid, past_first).expect("the ray hits the last ball");
+		assert_eq!((hit.body, hit.user_data), (bodies[2], 12));
+		let hit = world
+			.cast_ray(&ray, 20.0, CollisionGroups::default(), past_first)
+			.expect("the ray hits the ghost");
+		assert_eq!(hit.body, bodies[1]);
+		assert!(world.cast_ray(&ray, 4.0, solid, everything).is_none());
+
+		let found = world.query_point(Vector3::new(3.2, 0.0, 0.0), CollisionGroups::default(), everything);
+		assert_eq!(
+			found,
+			[QueryHit {
+				collider: 1,
+				body: bodies[1],
+				user_data: 11
+			}]
+		);
+		assert!(world.query_point(Vector3::new(3.2, 0.0, 0.0), solid, everything).is_empty());
+		let region = Aabb::new(Vector3::new(-1.0, -1.0, -1.0), Vector3::new(4.0, 1.0, 1.0));
+		let found = world.query_aabb(&region, CollisionGroups::default(), everything);
+		assert_eq!(found.iter().map(|hit| hit.user_data).collect::<Vec<_>>(), [10, 11]);
+		assert_eq!(world.query_aabb(&region, solid, everything).len(), 1);
+	}
+
+	#[test]
+	pub fn energy_audit_tracks_a_falling_spinning_ball() {
+		let mut world = PhysicsWorld::new();
+		let body = world.add_body(
+			RigidBodyBuilder::new(Shape::Sphere { radius: 0.5 })
+				.position(Vector3::new(0.0, 10.0, 0.0))
+				.angular_velocity(Vector3::new(0.0, 2.0, 0.0))
+				.damping(1.0, 1.0)
+				.can_sleep(false)
+				.build(),
+		);
+		let mass = world.bodies.get(body).mass();
+		let energy = world.energy();
+		assert!((energy.kinetic / mass - 0.2).abs() < 1e-4, "{energy:?}");
+		assert!((energy.potential / mass - 98.1).abs() < 1e-3, "{energy:?}");
+
+		world.energy_audit = Some(EnergyAudit::new());
+		world.energy_audit.as_mut().unwrap().record(energy);
+		for _ in 0..60 {
+			world.step(STEP);
+		}
+
+		// Semi-implicit Euler loses half of m g² dt² each step
+		let audit = world.energy_audit.unwrap();
+		let expected = -60.0 * 0.5 * mass * 9.81 * 9.81 * STEP * STEP;
+		assert!((audit.drift() - expected).abs() < 1e-3, "{}", audit.drift());
+		assert!((audit.largest_drift() - expected).abs() < 1e-3);
+		assert!(audit.step_drift() < 0.0);
+	}
+}
@@ -0,0 +1,105 @@
+//! Steps a world that has warmed up and counts the allocations made, with a
+//! global allocator of this test's own. The test is alone in its binary, so
+//! nothing else allocates while it counts.
+
+use std::{
+	alloc::{GlobalAlloc, Layout, System},
+	sync::atomic::{AtomicUsize, Ordering},
+};
+
+use impulse::{
+	body::{BodyKind, RigidBodyBuilder},
+	collide::CollisionPlane,
+	energy::EnergyAudit,
+	joint::{DistanceJoint, Joint, JointKind},
+	shape::Shape,
+	vec::Vector3,
+	world::PhysicsWorld,
+	Real,
+};
+
+/// Counts every allocation and reallocation made through the system allocator.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		System.alloc(layout)
+	}
+
+	unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		System.alloc_zeroed(layout)
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		System.realloc(ptr, layout, new_size)
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		System.dealloc(ptr, layout);
+	}
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const STEP: Real = 1.0 / 60.0;
+
+fn ball(world: &mut PhysicsWorld, position: Vector3, continuous_collision: bool) -> usize {
+	let shape = Shape::Sphere { radius: 0.5 };
+	let body = world.add_body(
+		RigidBodyBuilder::new(shape)
+			.position(position)
+			.continuous_collision(continuous_collision)
+			.build(),
+	);
+	world.add_collider(body, &shape);
+	body
+}
+
+/// Builds a world with contacts, a sensor, a joint, a body with continuous
+/// collision and an energy audit, and returns the allocations made by 120
+/// steps taken once it has warmed up.
+fn allocations_once_warm() -> usize {
+	let mut world = PhysicsWorld::new();
+	world.energy_audit = Some(EnergyAudit::new());
+	world.add_plane(CollisionPlane::new(Vector3::y_axis(), 0.0));
+	for level in 0..4_u8 {
+		let position = Vector3::new(Real::from(level) * 0.1, Real::from(level).mul_add(1.1, 0.5), 0.0);
+		ball(&mut world, position, level == 0);
+	}
+	let sensor = world.add_body(RigidBodyBuilder::new(Shape::default()).kind(BodyKind::Static).build());
+	world.add_sensor(sensor, &Shape::Cuboid { half_size: Vector3::new(2.0, 1.0, 2.0) });
+	// A pendulum, so that joint rows are solved too
+	let bob = ball(&mut world, Vector3::new(8.0, 5.0, 0.0), false);
+	let anchors = [Vector3::new(6.0, 5.0, 0.0), Vector3::new(8.0, 5.0, 0.0)];
+	let joint = DistanceJoint::new([None, Some(bob)], &world.bodies.iter().collect::<Vec<_>>(), anchors);
+	world.add_joint(Joint::new(JointKind::Distance(joint)));
+	let mut step = || {
+		world.step(STEP);
+		world.events.drain().for_each(drop);
+	};
+	for _ in 0..240 {
+		step();
+	}
+	let before = ALLOCATIONS.load(Ordering::Relaxed);
+	for _ in 0..120 {
+		step();
+	}
+	ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+#[test]
+pub fn warm_steps_allocate_nothing() {
+	// Work handed to rayon from outside its threads goes through a queue
+	// that allocates now and then, so the world is stepped on one of them
+	#[cfg(feature = "rayon")]
+	let allocations = rayon::scope(|_| allocations_once_warm());
+	#[cfg(not(feature = "rayon"))]
+	let allocations = allocations_once_warm();
+	assert_eq!(allocations, 0);
+}